@@ -6,7 +6,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::Path;
 
-use crate::ConfigError;
+use crate::{ConfigError, PerformanceProfile, SystemConfig};
 
 /// Display configuration for a device
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -139,6 +139,40 @@ fn default_true() -> bool {
     true
 }
 
+/// System settings recommended for a device
+///
+/// Used in place of the generic defaults when no config file exists yet.
+/// Unset values keep the generic default.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecommendedDefaults {
+    /// Display brightness (1-255)
+    pub brightness: Option<u8>,
+    /// Volume percentage
+    pub volume: Option<u8>,
+    /// CPU performance profile
+    pub performance: Option<PerformanceProfile>,
+    /// Auto-suspend timeout in minutes (0 = disabled)
+    pub suspend_timeout: Option<u32>,
+}
+
+impl RecommendedDefaults {
+    /// Apply the set values to a system config
+    pub fn apply(&self, system: &mut SystemConfig) {
+        if let Some(brightness) = self.brightness {
+            system.brightness = brightness;
+        }
+        if let Some(volume) = self.volume {
+            system.volume = volume;
+        }
+        if let Some(performance) = self.performance {
+            system.performance = performance;
+        }
+        if let Some(suspend_timeout) = self.suspend_timeout {
+            system.suspend_timeout = suspend_timeout;
+        }
+    }
+}
+
 /// Complete device profile configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeviceProfileConfig {
@@ -161,6 +195,9 @@ pub struct DeviceProfileConfig {
     /// Device-specific quirks
     #[serde(default)]
     pub quirks: Vec<String>,
+    /// System settings recommended for first boot
+    #[serde(default)]
+    pub defaults: RecommendedDefaults,
 }
 
 impl Default for DeviceProfileConfig {
@@ -212,6 +249,10 @@ impl DeviceProfileConfig {
                 sleep_support: true,
             },
             quirks: vec![],
+            defaults: RecommendedDefaults {
+                performance: Some(PerformanceProfile::Balanced),
+                ..Default::default()
+            },
         }
     }
 
@@ -264,6 +305,13 @@ impl DeviceProfileConfig {
                 sleep_support: true,
             },
             quirks: vec!["no_analog".to_string()],
+            // Small battery, so favor runtime
+            defaults: RecommendedDefaults {
+                brightness: Some(128),
+                performance: Some(PerformanceProfile::Powersave),
+                suspend_timeout: Some(3),
+                ..Default::default()
+            },
         }
     }
 
@@ -307,23 +355,31 @@ impl DeviceProfileConfig {
                 ],
                 sleep_support: true,
             },
-            quirks: vec!["square_display".to_string()],
+            quirks: vec!["square_display".to_string(), "leds".to_string()],
+            defaults: RecommendedDefaults {
+                performance: Some(PerformanceProfile::Balanced),
+                ..Default::default()
+            },
         }
     }
 }
 
-/// Load device profiles from configuration directory
-pub fn load_device_profiles(
-    config_dir: &Path,
-) -> Result<HashMap<String, DeviceProfileConfig>, ConfigError> {
-    let profiles_dir = config_dir.join("devices");
+/// Profiles built into RexOS, by id
+pub(crate) fn builtin_profiles() -> HashMap<String, DeviceProfileConfig> {
     let mut profiles = HashMap::new();
-
-    // Add built-in profiles
     profiles.insert("rg353m".to_string(), DeviceProfileConfig::rg353m());
     profiles.insert("rg353v".to_string(), DeviceProfileConfig::rg353v());
     profiles.insert("rg35xx".to_string(), DeviceProfileConfig::rg35xx());
     profiles.insert("rgb30".to_string(), DeviceProfileConfig::rgb30());
+    profiles
+}
+
+/// Load device profiles from configuration directory
+pub fn load_device_profiles(
+    config_dir: &Path,
+) -> Result<HashMap<String, DeviceProfileConfig>, ConfigError> {
+    let profiles_dir = config_dir.join("devices");
+    let mut profiles = builtin_profiles();
 
     // Load custom profiles from directory
     if profiles_dir.exists() {
@@ -354,6 +410,21 @@ mod tests {
         assert_eq!(profile.input.analog_sticks, 2);
     }
 
+    #[test]
+    fn test_recommended_defaults() {
+        let mut system = SystemConfig::default();
+        DeviceProfileConfig::rg35xx().defaults.apply(&mut system);
+        assert_eq!(system.performance, PerformanceProfile::Powersave);
+        assert_eq!(system.brightness, 128);
+        assert_eq!(system.volume, SystemConfig::default().volume);
+
+        // Profiles written before the field existed still load
+        let mut table = toml::Value::try_from(DeviceProfileConfig::rg353m()).unwrap();
+        table.as_table_mut().unwrap().remove("defaults");
+        let profile: DeviceProfileConfig = table.try_into().unwrap();
+        assert_eq!(profile.defaults, RecommendedDefaults::default());
+    }
+
     #[test]
     fn test_profile_serialize() {
         let profile = DeviceProfileConfig::rg353m();
@@ -31,6 +31,11 @@ pub struct CoreConfig {
     /// Core-specific options
     #[serde(default)]
     pub options: HashMap<String, String>,
+
+    /// RetroArch settings for this core's games, written as a per-system
+    /// override (e.g. "rewind_enable" = "true")
+    #[serde(default)]
+    pub overrides: HashMap<String, String>,
 }
 
 /// Configuration for a game system
@@ -113,6 +118,18 @@ pub struct EmulatorConfig {
     #[serde(default)]
     pub show_fps: bool,
 
+    /// Capture emulator stderr to a log file per launch
+    #[serde(default)]
+    pub capture_logs: bool,
+
+    /// Directory for captured emulator logs
+    #[serde(default = "default_emulator_log_dir")]
+    pub log_dir: PathBuf,
+
+    /// Sample temperature, clocks and battery during games and log a summary
+    #[serde(default)]
+    pub session_metrics: bool,
+
     /// Enable shaders
     #[serde(default = "default_true")]
     pub shaders_enabled: bool,
@@ -120,6 +137,124 @@ pub struct EmulatorConfig {
     /// Default shader preset
     #[serde(default)]
     pub default_shader: Option<String>,
+
+    /// Directory containing shader presets
+    #[serde(default = "default_shaders_dir")]
+    pub shaders_dir: PathBuf,
+
+    /// Per-system shader presets ("none" disables), keyed by system short name
+    #[serde(default)]
+    pub system_shaders: HashMap<String, String>,
+
+    /// Per-game shader presets ("none" disables), keyed by "system/file name"
+    /// (e.g. "gba/Metroid Fusion.gba")
+    #[serde(default)]
+    pub game_shaders: HashMap<String, String>,
+
+    /// Per-system video overrides, keyed by system short name
+    #[serde(default)]
+    pub video: HashMap<String, VideoConfig>,
+
+    /// Per-system input profile overrides, keyed by system short name
+    #[serde(default)]
+    pub input: HashMap<String, InputProfileConfig>,
+
+    /// Per-system resource limits, keyed by system short name
+    /// (`"default"` applies to systems without an entry)
+    #[serde(default)]
+    pub limits: HashMap<String, ResourceLimitsConfig>,
+
+    /// Per-system CPU/GPU frequency caps, keyed by system short name
+    #[serde(default)]
+    pub clocks: HashMap<String, ClockConfig>,
+
+    /// Per-game CPU/GPU frequency caps, keyed by "system/file name"
+    #[serde(default)]
+    pub game_clocks: HashMap<String, ClockConfig>,
+
+    /// Scripts run around every game
+    #[serde(default)]
+    pub hooks: HookConfig,
+
+    /// Scripts run around games of a system, after the global ones,
+    /// keyed by system short name
+    #[serde(default)]
+    pub system_hooks: HashMap<String, HookConfig>,
+}
+
+/// Video overrides for a system
+///
+/// Unset fields fall back to the built-in per-system defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VideoConfig {
+    /// Aspect ratio (e.g. "4:3", "3:2", "core", "full")
+    #[serde(default)]
+    pub aspect_ratio: Option<String>,
+
+    /// Integer scaling
+    #[serde(default)]
+    pub integer_scale: Option<bool>,
+}
+
+/// Input profile overrides for a system
+///
+/// Merged over the built-in profile for the system.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InputProfileConfig {
+    /// RetroArch analog-to-dpad mode (0 = off, 1 = left stick, 2 = right stick)
+    #[serde(default)]
+    pub analog_dpad_mode: Option<u8>,
+
+    /// Remapped inputs, RetroPad input to RetroPad output (e.g. "x" = "r_y-")
+    #[serde(default)]
+    pub remaps: HashMap<String, String>,
+}
+
+/// Resource limits for an emulator process
+///
+/// All limits are off unless set.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceLimitsConfig {
+    /// Address space limit (RLIMIT_AS) in MiB
+    #[serde(default)]
+    pub address_space_mb: Option<u64>,
+
+    /// cgroup memory limit in MiB
+    #[serde(default)]
+    pub memory_mb: Option<u64>,
+
+    /// cgroup CPU limit in percent of one core (200 = two cores)
+    #[serde(default)]
+    pub cpu_percent: Option<u32>,
+}
+
+/// CPU/GPU frequency caps while a game runs
+///
+/// Requested values are clamped to the device's supported frequencies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClockConfig {
+    /// Maximum CPU frequency in kHz
+    #[serde(default)]
+    pub cpu_max_khz: Option<u64>,
+
+    /// Maximum GPU frequency in Hz
+    #[serde(default)]
+    pub gpu_max_hz: Option<u64>,
+}
+
+/// Scripts run before and after a game
+///
+/// Each script gets the ROM path and system short name as arguments.
+/// A failing pre-launch script stops the game from starting.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HookConfig {
+    /// Script run before the emulator starts
+    #[serde(default)]
+    pub pre_launch: Option<PathBuf>,
+
+    /// Script run after the emulator exits
+    #[serde(default)]
+    pub post_exit: Option<PathBuf>,
 }
 
 /// Configuration for a standalone emulator
@@ -167,6 +302,14 @@ fn default_retroarch_config() -> PathBuf {
     PathBuf::from("/home/ark/.config/retroarch")
 }
 
+fn default_emulator_log_dir() -> PathBuf {
+    PathBuf::from("/var/log/rexos/emulators")
+}
+
+fn default_shaders_dir() -> PathBuf {
+    PathBuf::from("/home/ark/.config/retroarch/shaders")
+}
+
 fn default_true() -> bool {
     true
 }
@@ -339,8 +482,21 @@ impl Default for EmulatorConfig {
             auto_save: true,
             auto_load: false,
             show_fps: false,
+            capture_logs: false,
+            session_metrics: false,
+            log_dir: default_emulator_log_dir(),
             shaders_enabled: true,
             default_shader: None,
+            shaders_dir: default_shaders_dir(),
+            system_shaders: HashMap::new(),
+            game_shaders: HashMap::new(),
+            video: HashMap::new(),
+            input: HashMap::new(),
+            limits: HashMap::new(),
+            clocks: HashMap::new(),
+            game_clocks: HashMap::new(),
+            hooks: HookConfig::default(),
+            system_hooks: HashMap::new(),
         }
     }
 }
@@ -368,6 +524,62 @@ impl EmulatorConfig {
         Some(cores_dir.join(format!("{}_libretro.so", core_name)))
     }
 
+    /// Get video overrides for a system
+    pub fn get_video(&self, system: &str) -> Option<&VideoConfig> {
+        self.video.get(system)
+    }
+
+    /// Get input profile overrides for a system
+    pub fn get_input(&self, system: &str) -> Option<&InputProfileConfig> {
+        self.input.get(system)
+    }
+
+    /// Get resource limits for a system, falling back to `"default"`
+    pub fn get_limits(&self, system: &str) -> Option<&ResourceLimitsConfig> {
+        self.limits
+            .get(system)
+            .or_else(|| self.limits.get("default"))
+    }
+
+    /// Get frequency caps for a game, per-game values over per-system ones
+    pub fn get_clocks(&self, system: &str, file_name: &str) -> Option<ClockConfig> {
+        let game = self.game_clocks.get(&game_key(system, file_name));
+        let system = self.clocks.get(system);
+        if system.is_none() && game.is_none() {
+            return None;
+        }
+
+        let system = system.copied().unwrap_or_default();
+        let game = game.copied().unwrap_or_default();
+        Some(ClockConfig {
+            cpu_max_khz: game.cpu_max_khz.or(system.cpu_max_khz),
+            gpu_max_hz: game.gpu_max_hz.or(system.gpu_max_hz),
+        })
+    }
+
+    /// Get the shader preset value for a game
+    pub fn get_game_shader(&self, system: &str, file_name: &str) -> Option<&str> {
+        self.game_shaders
+            .get(&game_key(system, file_name))
+            .map(String::as_str)
+    }
+
+    /// Set or clear (`None`) the shader preset value for a game
+    pub fn set_game_shader(&mut self, system: &str, file_name: &str, value: Option<String>) {
+        let key = game_key(system, file_name);
+        match value {
+            Some(value) => self.game_shaders.insert(key, value),
+            None => self.game_shaders.remove(&key),
+        };
+    }
+
+    /// Get hooks for a system, global ones first
+    pub fn get_hooks(&self, system: &str) -> Vec<&HookConfig> {
+        std::iter::once(&self.hooks)
+            .chain(self.system_hooks.get(system))
+            .collect()
+    }
+
     /// Find the system for a file extension
     pub fn find_system_for_extension(&self, ext: &str) -> Option<&SystemConfig> {
         let ext_lower = ext.to_lowercase();
@@ -377,10 +589,58 @@ impl EmulatorConfig {
     }
 }
 
+/// Key for per-game settings, so ROMs sharing a file name across systems
+/// keep separate settings
+fn game_key(system: &str, file_name: &str) -> String {
+    format!("{}/{}", system, file_name)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_game_clocks_override_system() {
+        let mut config = EmulatorConfig::default();
+        config.clocks.insert(
+            "psp".to_string(),
+            ClockConfig {
+                cpu_max_khz: Some(1_800_000),
+                gpu_max_hz: Some(800_000_000),
+            },
+        );
+        config.game_clocks.insert(
+            "psp/Puzzle.iso".to_string(),
+            ClockConfig {
+                cpu_max_khz: Some(1_008_000),
+                gpu_max_hz: None,
+            },
+        );
+
+        let clocks = config.get_clocks("psp", "Puzzle.iso").unwrap();
+        assert_eq!(clocks.cpu_max_khz, Some(1_008_000));
+        assert_eq!(clocks.gpu_max_hz, Some(800_000_000));
+        assert_eq!(
+            config.get_clocks("psp", "Other.iso").unwrap().cpu_max_khz,
+            Some(1_800_000)
+        );
+        assert!(config.get_clocks("gba", "Other.gba").is_none());
+        assert!(config.get_clocks("gba", "Puzzle.iso").is_none());
+    }
+
+    #[test]
+    fn test_game_shaders_keyed_by_system() {
+        let mut config = EmulatorConfig::default();
+        config.set_game_shader("gba", "Tetris.gba", Some("none".to_string()));
+
+        assert_eq!(config.game_shaders.get("gba/Tetris.gba").unwrap(), "none");
+        assert_eq!(config.get_game_shader("gba", "Tetris.gba"), Some("none"));
+        assert_eq!(config.get_game_shader("gb", "Tetris.gba"), None);
+
+        config.set_game_shader("gba", "Tetris.gba", None);
+        assert!(config.game_shaders.is_empty());
+    }
+
     #[test]
     fn test_default_systems() {
         let config = EmulatorConfig::default();
@@ -396,6 +656,53 @@ mod tests {
         assert!(path.unwrap().to_string_lossy().contains("mgba"));
     }
 
+    #[test]
+    fn test_video_overrides_from_toml() {
+        let config: EmulatorConfig = toml::from_str(
+            r#"
+            [video.gba]
+            aspect_ratio = "core"
+
+            [video.snes]
+            integer_scale = false
+            "#,
+        )
+        .unwrap();
+
+        let gba = config.get_video("gba").unwrap();
+        assert_eq!(gba.aspect_ratio.as_deref(), Some("core"));
+        assert_eq!(gba.integer_scale, None);
+        assert_eq!(config.get_video("snes").unwrap().integer_scale, Some(false));
+        assert!(config.get_video("nes").is_none());
+    }
+
+    #[test]
+    fn test_hooks_from_toml() {
+        let config: EmulatorConfig = toml::from_str(
+            r#"
+            [hooks]
+            pre_launch = "/home/ark/hooks/perf-mode.sh"
+
+            [system_hooks.dos]
+            pre_launch = "/home/ark/hooks/mount-dos.sh"
+            post_exit = "/home/ark/hooks/umount-dos.sh"
+            "#,
+        )
+        .unwrap();
+
+        let dos = config.get_hooks("dos");
+        assert_eq!(dos.len(), 2);
+        assert_eq!(
+            dos[0].pre_launch,
+            Some(PathBuf::from("/home/ark/hooks/perf-mode.sh"))
+        );
+        assert_eq!(
+            dos[1].post_exit,
+            Some(PathBuf::from("/home/ark/hooks/umount-dos.sh"))
+        );
+        assert_eq!(config.get_hooks("gba").len(), 1);
+    }
+
     #[test]
     fn test_find_system_for_extension() {
         let config = EmulatorConfig::default();
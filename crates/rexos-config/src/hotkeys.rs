@@ -4,6 +4,7 @@
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
 
 /// Actions that can be triggered by hotkeys
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -45,6 +46,31 @@ pub enum HotkeyAction {
     Turbo,
 }
 
+impl HotkeyAction {
+    /// Name as written in the config file
+    pub fn name(&self) -> &'static str {
+        match self {
+            HotkeyAction::Exit => "exit",
+            HotkeyAction::SaveState => "save_state",
+            HotkeyAction::LoadState => "load_state",
+            HotkeyAction::FastForward => "fast_forward",
+            HotkeyAction::Rewind => "rewind",
+            HotkeyAction::Screenshot => "screenshot",
+            HotkeyAction::Pause => "pause",
+            HotkeyAction::Menu => "menu",
+            HotkeyAction::NextSlot => "next_slot",
+            HotkeyAction::PrevSlot => "prev_slot",
+            HotkeyAction::VolumeUp => "volume_up",
+            HotkeyAction::VolumeDown => "volume_down",
+            HotkeyAction::BrightnessUp => "brightness_up",
+            HotkeyAction::BrightnessDown => "brightness_down",
+            HotkeyAction::ShowFps => "show_fps",
+            HotkeyAction::Reset => "reset",
+            HotkeyAction::Turbo => "turbo",
+        }
+    }
+}
+
 /// A hotkey definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Hotkey {
@@ -66,6 +92,62 @@ impl Hotkey {
     pub fn to_string_pretty(&self) -> String {
         format!("{} + {}", self.modifier, self.button)
     }
+
+    /// Buttons held for this hotkey, modifier first
+    ///
+    /// The button may itself be a combo such as `R1+L1`.
+    pub fn buttons(&self) -> Vec<&str> {
+        std::iter::once(self.modifier.trim())
+            .chain(self.button.split('+').map(str::trim))
+            .filter(|button| !button.is_empty())
+            .collect()
+    }
+}
+
+/// How two hotkeys conflict
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// Both actions are bound to the same combo
+    Duplicate,
+    /// The first action's combo is held on the way to the second's, so
+    /// it fires before the second can be pressed
+    Prefix,
+}
+
+/// Two hotkeys that can't both be triggered reliably
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HotkeyConflict {
+    /// Action bound to the shorter (or same) combo
+    pub first: HotkeyAction,
+    /// Combo of `first`
+    pub first_combo: String,
+    /// Action it conflicts with
+    pub second: HotkeyAction,
+    /// Combo of `second`
+    pub second_combo: String,
+    pub kind: ConflictKind,
+}
+
+impl fmt::Display for HotkeyConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ConflictKind::Duplicate => write!(
+                f,
+                "{} and {} are both bound to {}",
+                self.first.name(),
+                self.second.name(),
+                self.first_combo
+            ),
+            ConflictKind::Prefix => write!(
+                f,
+                "{} ({}) fires before {} ({})",
+                self.first.name(),
+                self.first_combo,
+                self.second.name(),
+                self.second_combo
+            ),
+        }
+    }
 }
 
 /// Hotkey configuration
@@ -82,6 +164,11 @@ pub struct HotkeyConfig {
     /// Enable hotkeys globally
     #[serde(default = "default_true")]
     pub enabled: bool,
+
+    /// Joypad button ids for devices that differ from the built-in layout
+    /// (e.g. "Start" = "9", "Up" = "h0up")
+    #[serde(default)]
+    pub button_ids: HashMap<String, String>,
 }
 
 fn default_modifier() -> String {
@@ -115,6 +202,7 @@ impl Default for HotkeyConfig {
             modifier: default_modifier(),
             hotkeys: default_hotkeys(),
             enabled: true,
+            button_ids: HashMap::new(),
         }
     }
 }
@@ -138,6 +226,59 @@ impl HotkeyConfig {
         self.hotkeys.remove(action);
     }
 
+    /// Check that every hotkey can be triggered on its own
+    ///
+    /// Flags actions bound to the same combo, and combos that are the start
+    /// of a longer one (`Select` + `R1` vs `Select` + `R1+L1`).
+    pub fn validate(&self) -> Result<(), Vec<HotkeyConflict>> {
+        let mut hotkeys = self.all_hotkeys();
+        hotkeys.sort_by_key(|(action, _)| action.name());
+
+        let mut conflicts = Vec::new();
+        for (i, (action, hotkey)) in hotkeys.iter().enumerate() {
+            let buttons = hotkey.buttons();
+            for (other, other_hotkey) in &hotkeys[i + 1..] {
+                let other_buttons = other_hotkey.buttons();
+
+                let (first, second, kind) = if buttons == other_buttons {
+                    (
+                        (action, hotkey),
+                        (other, other_hotkey),
+                        ConflictKind::Duplicate,
+                    )
+                } else if other_buttons.starts_with(&buttons) {
+                    (
+                        (action, hotkey),
+                        (other, other_hotkey),
+                        ConflictKind::Prefix,
+                    )
+                } else if buttons.starts_with(&other_buttons) {
+                    (
+                        (other, other_hotkey),
+                        (action, hotkey),
+                        ConflictKind::Prefix,
+                    )
+                } else {
+                    continue;
+                };
+
+                conflicts.push(HotkeyConflict {
+                    first: first.0.clone(),
+                    first_combo: first.1.to_string_pretty(),
+                    second: second.0.clone(),
+                    second_combo: second.1.to_string_pretty(),
+                    kind,
+                });
+            }
+        }
+
+        if conflicts.is_empty() {
+            Ok(())
+        } else {
+            Err(conflicts)
+        }
+    }
+
     /// Get all configured hotkeys
     pub fn all_hotkeys(&self) -> Vec<(HotkeyAction, Hotkey)> {
         self.hotkeys
@@ -170,6 +311,34 @@ mod tests {
         assert_eq!(exit.unwrap().button, "Start");
     }
 
+    #[test]
+    fn test_validate_hotkeys() {
+        assert_eq!(HotkeyConfig::default().validate(), Ok(()));
+
+        let mut config = HotkeyConfig::default();
+        config.set_hotkey(HotkeyAction::Pause, "R1".to_string());
+        config.set_hotkey(HotkeyAction::Rewind, "R1+L1".to_string());
+
+        let conflicts = config.validate().unwrap_err();
+        assert_eq!(conflicts.len(), 3);
+        assert_eq!(
+            conflicts[0],
+            HotkeyConflict {
+                first: HotkeyAction::Pause,
+                first_combo: "Select + R1".to_string(),
+                second: HotkeyAction::Rewind,
+                second_combo: "Select + R1+L1".to_string(),
+                kind: ConflictKind::Prefix,
+            }
+        );
+        assert_eq!(
+            conflicts[1].to_string(),
+            "pause and save_state are both bound to Select + R1"
+        );
+        assert_eq!(conflicts[2].first, HotkeyAction::SaveState);
+        assert_eq!(conflicts[2].second, HotkeyAction::Rewind);
+    }
+
     #[test]
     fn test_hotkey_pretty_string() {
         let hotkey = Hotkey::new("Select", "Start");
@@ -6,14 +6,30 @@
 mod device_profiles;
 mod emulator_config;
 mod hotkeys;
+mod library_config;
+mod migrate;
+mod reset;
+mod store;
 mod system_config;
 
-pub use device_profiles::{DeviceProfileConfig, load_device_profiles};
-pub use emulator_config::{CoreConfig, EmulatorConfig, SystemConfig as EmulatorSystemConfig};
-pub use hotkeys::{Hotkey, HotkeyAction, HotkeyConfig};
-pub use system_config::{NetworkConfig, PerformanceProfile, SystemConfig};
+pub use device_profiles::{DeviceProfileConfig, RecommendedDefaults, load_device_profiles};
+pub use emulator_config::{
+    ClockConfig, CoreConfig, EmulatorConfig, HookConfig, InputProfileConfig, ResourceLimitsConfig,
+    SystemConfig as EmulatorSystemConfig, VideoConfig,
+};
+pub use hotkeys::{ConflictKind, Hotkey, HotkeyAction, HotkeyConfig, HotkeyConflict};
+pub use library_config::{LibraryConfig, NameCleaningConfig};
+pub use migrate::{ConfigChange, MIGRATIONS, Migration, migrate_toml, migrate_toml_with};
+pub use reset::{FactoryReset, ResetAction, USER_STATE_ENTRIES, factory_reset};
+pub use store::{ConfigStore, DEFAULT_SAVE_DELAY, SettingChange};
+pub use system_config::{
+    DisplayConfig, InputConfig, InputRepeatConfig, NetworkConfig, PerformanceProfile, ROTATIONS,
+    RecoveryConfig, StorageConfig, SystemConfig, UPDATE_CHANNELS,
+};
 
 use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 
@@ -53,17 +69,34 @@ pub struct RexOSConfig {
 
     #[serde(default)]
     pub emulators: EmulatorConfig,
+
+    #[serde(default)]
+    pub library: LibraryConfig,
 }
 
 impl RexOSConfig {
-    /// Load configuration from a file
+    /// Load and validate configuration from a file
     pub fn load(path: &Path) -> Result<Self, ConfigError> {
         let contents = std::fs::read_to_string(path)?;
         let config: Self = toml::from_str(&contents)?;
+        config.validate().map_err(|e| match e {
+            ConfigError::Invalid(msg) => {
+                ConfigError::Invalid(format!("{} in {}", msg, path.display()))
+            }
+            e => e,
+        })?;
         Ok(config)
     }
 
+    /// Check values that deserialize but are out of range
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.system.validate()
+    }
+
     /// Load configuration from default locations
+    ///
+    /// Defaults are only used when no file exists; an invalid file is an
+    /// error rather than silently replaced.
     pub fn load_default() -> Result<Self, ConfigError> {
         // Try user config first, then system config
         let user_config = Path::new(USER_CONFIG_DIR).join("config.toml");
@@ -81,7 +114,48 @@ impl RexOSConfig {
         Ok(Self::default())
     }
 
+    /// Default configuration tuned for a device
+    ///
+    /// Applies the recommended defaults of the device profile with this id,
+    /// from the built-in profiles or those in the system config directory.
+    /// Unknown devices get the generic defaults.
+    pub fn defaults_for_device(profile_id: &str) -> Self {
+        let profiles = load_device_profiles(Path::new(CONFIG_DIR)).unwrap_or_else(|e| {
+            tracing::warn!("Failed to load device profiles: {}", e);
+            device_profiles::builtin_profiles()
+        });
+
+        let mut config = Self::default();
+        match profiles.get(profile_id) {
+            Some(profile) => profile.defaults.apply(&mut config.system),
+            None => tracing::debug!("No device profile {}, using generic defaults", profile_id),
+        }
+        config
+    }
+
+    /// Load the system config with the user config layered over it
+    ///
+    /// Distro defaults live in `/etc/rexos/config.toml` and users only set
+    /// the keys they care about in `/roms/.rexos/config.toml`. Either file
+    /// may be missing.
+    pub fn load_layered() -> Result<Self, ConfigError> {
+        Self::load_layered_from(
+            &Path::new(CONFIG_DIR).join("config.toml"),
+            &Path::new(USER_CONFIG_DIR).join("config.toml"),
+        )
+    }
+
+    /// Load `base` with `overlay` layered over it
+    pub fn load_layered_from(base: &Path, overlay: &Path) -> Result<Self, ConfigError> {
+        let config: Self = layered_toml(base, overlay)?.try_into()?;
+        config.validate()?;
+        Ok(config)
+    }
+
     /// Save configuration to a file
+    ///
+    /// The file is replaced atomically, so losing power mid-save leaves
+    /// either the old or the new config, never a truncated one.
     pub fn save(&self, path: &Path) -> Result<(), ConfigError> {
         let contents = toml::to_string_pretty(self)?;
 
@@ -90,7 +164,7 @@ impl RexOSConfig {
             std::fs::create_dir_all(parent)?;
         }
 
-        std::fs::write(path, contents)?;
+        write_atomic(path, contents.as_bytes())?;
         tracing::info!("Configuration saved to {}", path.display());
         Ok(())
     }
@@ -102,6 +176,56 @@ impl RexOSConfig {
     }
 }
 
+/// Merge config files into one TOML value, later files winning
+///
+/// Keys this version doesn't know are kept, so the value can be written
+/// back without dropping settings from newer releases. Missing files are
+/// skipped.
+pub fn layered_toml(base: &Path, overlay: &Path) -> Result<toml::Value, ConfigError> {
+    let mut merged = toml::Value::Table(toml::Table::new());
+
+    for path in [base, overlay] {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+        let layer: toml::Table = contents
+            .parse()
+            .map_err(|e| ConfigError::ParseError(format!("{}: {}", path.display(), e)))?;
+        merge_toml(&mut merged, toml::Value::Table(layer));
+    }
+
+    Ok(merged)
+}
+
+/// Replace a file's contents atomically
+///
+/// Writes and syncs a temporary file in the same directory, then renames
+/// it over `path` and syncs the directory so the rename itself survives a
+/// power cut.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = dir.join(format!(".{}.tmp-{}", name, std::process::id()));
+
+    let result = (|| {
+        let mut file = File::create(&tmp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    })();
+    if result.is_err() {
+        fs::remove_file(&tmp).ok();
+    }
+    result?;
+
+    File::open(dir)?.sync_all()
+}
+
 /// Helper function to merge TOML values
 pub fn merge_toml(base: &mut toml::Value, overlay: toml::Value) {
     match (base, overlay) {
@@ -172,6 +296,72 @@ volume = 70
         assert_eq!(config.system.volume, 70);
     }
 
+    #[test]
+    fn test_load_rejects_invalid() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        write!(temp_file, "[system]\nvolume = 150\n").unwrap();
+
+        match RexOSConfig::load(temp_file.path()) {
+            Err(ConfigError::Invalid(msg)) => {
+                assert!(msg.contains("system.volume"), "{}", msg);
+                assert!(msg.contains(&temp_file.path().display().to_string()));
+            }
+            other => panic!("expected invalid config, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_defaults_for_device() {
+        let config = RexOSConfig::defaults_for_device("rg35xx");
+        assert_eq!(config.system.performance, PerformanceProfile::Powersave);
+        config.validate().unwrap();
+
+        let config = RexOSConfig::defaults_for_device("unknown");
+        assert_eq!(config.system.brightness, SystemConfig::default().brightness);
+    }
+
+    #[test]
+    fn test_load_layered() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("etc.toml");
+        let overlay = dir.path().join("user.toml");
+        std::fs::write(
+            &base,
+            "[system]\nbrightness = 120\nvolume = 40\nfuture_option = true\n",
+        )
+        .unwrap();
+        std::fs::write(&overlay, "[system]\nvolume = 90\n").unwrap();
+
+        let config = RexOSConfig::load_layered_from(&base, &overlay).unwrap();
+        assert_eq!(config.system.brightness, 120);
+        assert_eq!(config.system.volume, 90);
+
+        // Unknown keys survive the merge
+        let merged = layered_toml(&base, &overlay).unwrap();
+        assert_eq!(merged["system"]["future_option"].as_bool(), Some(true));
+
+        // Either layer may be missing
+        let missing = dir.path().join("missing.toml");
+        let config = RexOSConfig::load_layered_from(&missing, &overlay).unwrap();
+        assert_eq!(config.system.volume, 90);
+        assert_eq!(config.system.brightness, SystemConfig::default().brightness);
+    }
+
+    #[test]
+    fn test_save_replaces_atomically() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[system]\nvolume = 10\n").unwrap();
+
+        let mut config = RexOSConfig::default();
+        config.system.volume = 55;
+        config.save(&path).unwrap();
+
+        assert_eq!(RexOSConfig::load(&path).unwrap().system.volume, 55);
+        // Only the config is left behind
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
     #[test]
     fn test_save_config() {
         let temp_file = NamedTempFile::new().unwrap();
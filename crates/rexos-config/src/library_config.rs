@@ -0,0 +1,65 @@
+//! Game library configuration
+
+use serde::{Deserialize, Serialize};
+
+/// Library configuration
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LibraryConfig {
+    /// Display name cleaning for scanned ROMs
+    #[serde(default)]
+    pub names: NameCleaningConfig,
+
+    /// Preferred region codes (e.g. ["USA", "EUR"]), listed first
+    #[serde(default)]
+    pub preferred_regions: Vec<String>,
+
+    /// Hide games only released outside the preferred regions
+    #[serde(default)]
+    pub hide_other_regions: bool,
+}
+
+/// Rules for turning ROM file names into display names
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NameCleaningConfig {
+    /// Strip region and language tags like "(USA)" or "(En,Fr,De)"
+    #[serde(default = "default_true")]
+    pub strip_regions: bool,
+
+    /// Strip revision and release tags like "(Rev 1)" or "(Beta)"
+    #[serde(default = "default_true")]
+    pub strip_versions: bool,
+
+    /// Strip TOSEC release dates and the publisher right after them, like
+    /// "(1991)(Sega)"
+    #[serde(default = "default_true")]
+    pub strip_dates: bool,
+
+    /// Strip dump flags in square brackets like "[!]" or "[b1]"
+    #[serde(default = "default_true")]
+    pub strip_dump_flags: bool,
+
+    /// Replace underscores with spaces
+    #[serde(default = "default_true")]
+    pub collapse_underscores: bool,
+
+    /// Move a trailing article to the front ("Legend of Zelda, The")
+    #[serde(default)]
+    pub move_articles: bool,
+}
+
+impl Default for NameCleaningConfig {
+    fn default() -> Self {
+        Self {
+            strip_regions: true,
+            strip_versions: true,
+            strip_dates: true,
+            strip_dump_flags: true,
+            collapse_underscores: true,
+            move_articles: false,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
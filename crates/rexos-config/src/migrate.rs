@@ -0,0 +1,262 @@
+//! Configuration migration between RexOS versions
+//!
+//! Works on the raw TOML so moved keys can be carried over before the
+//! config is deserialized. Renames are applied in version order, then the
+//! result is layered over the current defaults so new keys appear and user
+//! values win.
+
+use crate::{ConfigError, RexOSConfig, merge_toml};
+use std::fmt;
+
+/// A config change introduced in a RexOS version
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    /// Version that made the change
+    pub version: &'static str,
+    /// Moved keys as (old dotted path, new dotted path)
+    pub renames: &'static [(&'static str, &'static str)],
+}
+
+/// Known migrations, oldest first
+///
+/// Add a step here whenever a config key is renamed or moved.
+pub const MIGRATIONS: &[Migration] = &[];
+
+/// A difference found while migrating
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigChange {
+    /// A user value was moved to a new key
+    Renamed { from: String, to: String },
+    /// A key missing from the old config was filled with its default
+    Added(String),
+}
+
+impl fmt::Display for ConfigChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigChange::Renamed { from, to } => write!(f, "renamed {} -> {}", from, to),
+            ConfigChange::Added(key) => write!(f, "added {}", key),
+        }
+    }
+}
+
+impl RexOSConfig {
+    /// Migrate a config file written by `from_version` to `to_version`
+    pub fn migrate_from(
+        contents: &str,
+        from_version: &str,
+        to_version: &str,
+    ) -> Result<(Self, Vec<ConfigChange>), ConfigError> {
+        let old: toml::Value = toml::from_str(contents)?;
+        let (value, changes) = migrate_toml(old, from_version, to_version)?;
+        let config = value.try_into()?;
+        Ok((config, changes))
+    }
+}
+
+/// Migrate raw config TOML using [`MIGRATIONS`]
+pub fn migrate_toml(
+    old: toml::Value,
+    from_version: &str,
+    to_version: &str,
+) -> Result<(toml::Value, Vec<ConfigChange>), ConfigError> {
+    migrate_toml_with(old, from_version, to_version, MIGRATIONS)
+}
+
+/// Migrate raw config TOML with an explicit migration list
+///
+/// Steps with `from_version < version <= to_version` are applied in order.
+pub fn migrate_toml_with(
+    mut old: toml::Value,
+    from_version: &str,
+    to_version: &str,
+    migrations: &[Migration],
+) -> Result<(toml::Value, Vec<ConfigChange>), ConfigError> {
+    let from = parse_version(from_version)?;
+    let to = parse_version(to_version)?;
+
+    let mut steps = migrations
+        .iter()
+        .map(|m| Ok((parse_version(m.version)?, m)))
+        .collect::<Result<Vec<_>, ConfigError>>()?;
+    steps.sort_by_key(|(version, _)| *version);
+
+    let mut changes = Vec::new();
+    for (version, migration) in steps {
+        if version <= from || version > to {
+            continue;
+        }
+
+        for (old_key, new_key) in migration.renames {
+            let Some(value) = take_key(&mut old, old_key) else {
+                continue;
+            };
+            // Keep a value the user already set under the new name
+            if get_key(&old, new_key).is_none() {
+                set_key(&mut old, new_key, value);
+                tracing::info!(
+                    "Config {}: renamed {} -> {}",
+                    migration.version,
+                    old_key,
+                    new_key
+                );
+                changes.push(ConfigChange::Renamed {
+                    from: old_key.to_string(),
+                    to: new_key.to_string(),
+                });
+            }
+        }
+    }
+
+    let mut merged = toml::Value::try_from(RexOSConfig::default())?;
+    for key in missing_keys(&merged, &old, "") {
+        changes.push(ConfigChange::Added(key));
+    }
+    merge_toml(&mut merged, old);
+
+    Ok((merged, changes))
+}
+
+/// Parse a "major.minor.patch" version
+fn parse_version(version: &str) -> Result<(u64, u64, u64), ConfigError> {
+    let invalid = || ConfigError::Invalid(format!("Invalid version: {}", version));
+
+    let mut parts = version.trim().trim_start_matches('v').splitn(3, '.');
+    let mut next = || -> Result<u64, ConfigError> {
+        match parts.next() {
+            // Ignore pre-release suffixes like "1.2.0-beta"
+            Some(part) => part
+                .split(['-', '+'])
+                .next()
+                .and_then(|n| n.parse().ok())
+                .ok_or_else(invalid),
+            None => Ok(0),
+        }
+    };
+
+    Ok((next()?, next()?, next()?))
+}
+
+/// Look up a dotted key
+fn get_key<'a>(value: &'a toml::Value, key: &str) -> Option<&'a toml::Value> {
+    key.split('.').try_fold(value, |v, part| v.get(part))
+}
+
+/// Remove a dotted key, returning its value
+fn take_key(value: &mut toml::Value, key: &str) -> Option<toml::Value> {
+    let (parent, last) = match key.rsplit_once('.') {
+        Some((parent, last)) => (
+            parent
+                .split('.')
+                .try_fold(value, |v, part| v.get_mut(part))?,
+            last,
+        ),
+        None => (value, key),
+    };
+    parent.as_table_mut()?.remove(last)
+}
+
+/// Set a dotted key, creating intermediate tables
+fn set_key(value: &mut toml::Value, key: &str, new: toml::Value) {
+    let mut current = value;
+    let mut parts = key.split('.').peekable();
+
+    while let Some(part) = parts.next() {
+        let Some(table) = current.as_table_mut() else {
+            return;
+        };
+        if parts.peek().is_none() {
+            table.insert(part.to_string(), new);
+            return;
+        }
+        current = table
+            .entry(part)
+            .or_insert_with(|| toml::Value::Table(toml::map::Map::new()));
+    }
+}
+
+/// Leaf keys present in `defaults` but not in `user`
+fn missing_keys(defaults: &toml::Value, user: &toml::Value, prefix: &str) -> Vec<String> {
+    let Some(table) = defaults.as_table() else {
+        return Vec::new();
+    };
+
+    let mut missing = Vec::new();
+    for (key, value) in table {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+
+        match user.get(key) {
+            None => missing.push(path),
+            Some(user_value) if value.is_table() => {
+                missing.extend(missing_keys(value, user_value, &path));
+            }
+            Some(_) => {}
+        }
+    }
+    missing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RENAME_BRIGHTNESS: &[Migration] = &[Migration {
+        version: "0.2.0",
+        renames: &[("display.backlight", "system.brightness")],
+    }];
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("0.1.0").unwrap(), (0, 1, 0));
+        assert_eq!(parse_version("v1.2").unwrap(), (1, 2, 0));
+        assert_eq!(parse_version("1.3.0-beta.1").unwrap(), (1, 3, 0));
+        assert!(parse_version("stable").is_err());
+    }
+
+    #[test]
+    fn test_rename_preserves_user_value() {
+        let old: toml::Value =
+            toml::from_str("[display]\nbacklight = 42\n\n[system]\nvolume = 15\n").unwrap();
+
+        let (value, changes) = migrate_toml_with(old, "0.1.0", "0.2.0", RENAME_BRIGHTNESS).unwrap();
+        let config: RexOSConfig = value.try_into().unwrap();
+
+        assert_eq!(config.system.brightness, 42);
+        assert_eq!(config.system.volume, 15);
+        assert!(changes.contains(&ConfigChange::Renamed {
+            from: "display.backlight".to_string(),
+            to: "system.brightness".to_string(),
+        }));
+        // New keys are reported and filled from defaults
+        assert!(changes.contains(&ConfigChange::Added("system.timezone".to_string())));
+        assert_eq!(config.system.timezone, "UTC");
+    }
+
+    #[test]
+    fn test_steps_outside_range_skipped() {
+        let old: toml::Value = toml::from_str("[display]\nbacklight = 42\n").unwrap();
+
+        let (value, changes) = migrate_toml_with(old, "0.2.0", "0.3.0", RENAME_BRIGHTNESS).unwrap();
+        assert!(get_key(&value, "display.backlight").is_some());
+        assert!(
+            !changes
+                .iter()
+                .any(|c| matches!(c, ConfigChange::Renamed { .. }))
+        );
+    }
+
+    #[test]
+    fn test_migrate_from_current_defaults() {
+        let contents = toml::to_string(&RexOSConfig::default()).unwrap();
+        let (config, changes) = RexOSConfig::migrate_from(&contents, "0.1.0", "0.1.0").unwrap();
+        assert!(changes.is_empty());
+        assert_eq!(
+            config.system.brightness,
+            RexOSConfig::default().system.brightness
+        );
+    }
+}
@@ -0,0 +1,225 @@
+//! Factory reset
+//!
+//! Restores the default configuration and clears RexOS state kept on the
+//! ROMs partition (game database, caches, user config). Only the entries in
+//! [`USER_STATE_ENTRIES`] are removed, so ROMs, saves, states and
+//! screenshots are never touched.
+
+use crate::{CONFIG_DIR, ConfigError, RexOSConfig, USER_CONFIG_DIR, write_atomic};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Entries in the user config directory that a reset removes
+pub const USER_STATE_ENTRIES: &[&str] = &[
+    "config.toml",
+    "games.db",
+    "games.db-journal",
+    "games.db-wal",
+    "games.db-shm",
+    "cache",
+];
+
+/// A single reset step
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResetAction {
+    /// Write the default configuration to this path
+    RestoreDefaults(PathBuf),
+    /// Remove this file or directory
+    Remove(PathBuf),
+}
+
+impl fmt::Display for ResetAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResetAction::RestoreDefaults(path) => write!(f, "restore {}", path.display()),
+            ResetAction::Remove(path) => write!(f, "remove {}", path.display()),
+        }
+    }
+}
+
+/// Factory reset of configuration and caches
+#[derive(Debug, Clone)]
+pub struct FactoryReset {
+    config_dir: PathBuf,
+    user_dir: PathBuf,
+    dry_run: bool,
+}
+
+impl FactoryReset {
+    /// Reset the standard configuration locations
+    pub fn new() -> Self {
+        Self {
+            config_dir: PathBuf::from(CONFIG_DIR),
+            user_dir: PathBuf::from(USER_CONFIG_DIR),
+            dry_run: false,
+        }
+    }
+
+    /// Use a different system config directory
+    pub fn with_config_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config_dir = dir.into();
+        self
+    }
+
+    /// Use a different user config directory
+    pub fn with_user_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.user_dir = dir.into();
+        self
+    }
+
+    /// Only report what would change
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// List the steps needed to reach factory state
+    ///
+    /// Empty when the system is already reset.
+    pub fn plan(&self) -> Result<Vec<ResetAction>, ConfigError> {
+        let mut actions = Vec::new();
+
+        let config_path = self.config_dir.join("config.toml");
+        // Compared as values, since map entries serialize in no fixed order
+        let defaults = toml::Value::try_from(RexOSConfig::default())?;
+        let current = fs::read_to_string(&config_path)
+            .ok()
+            .and_then(|contents| toml::from_str::<toml::Value>(&contents).ok());
+        if current.as_ref() != Some(&defaults) {
+            actions.push(ResetAction::RestoreDefaults(config_path));
+        }
+
+        for name in USER_STATE_ENTRIES {
+            let path = self.user_dir.join(name);
+            // symlink_metadata so dangling links are still cleared
+            if fs::symlink_metadata(&path).is_ok() {
+                actions.push(ResetAction::Remove(path));
+            }
+        }
+
+        Ok(actions)
+    }
+
+    /// Perform the reset, returning the steps taken (or planned in dry-run)
+    pub fn run(&self) -> Result<Vec<ResetAction>, ConfigError> {
+        let actions = self.plan()?;
+
+        if actions.is_empty() {
+            tracing::info!("Factory reset: nothing to do");
+            return Ok(actions);
+        }
+
+        for action in &actions {
+            if self.dry_run {
+                tracing::info!("Factory reset (dry run): would {}", action);
+                continue;
+            }
+
+            match action {
+                ResetAction::RestoreDefaults(path) => {
+                    if let Some(parent) = path.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    write_atomic(path, default_contents()?.as_bytes())?;
+                }
+                ResetAction::Remove(path) => remove_entry(path)?,
+            }
+            tracing::info!("Factory reset: {}", action);
+        }
+
+        Ok(actions)
+    }
+}
+
+impl Default for FactoryReset {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Restore defaults and clear caches at the standard locations
+pub fn factory_reset(dry_run: bool) -> Result<Vec<ResetAction>, ConfigError> {
+    FactoryReset::new().with_dry_run(dry_run).run()
+}
+
+/// Serialized default configuration
+fn default_contents() -> Result<String, ConfigError> {
+    Ok(toml::to_string_pretty(&RexOSConfig::default())?)
+}
+
+/// Remove a file, link or directory without following links
+fn remove_entry(path: &Path) -> Result<(), ConfigError> {
+    let metadata = fs::symlink_metadata(path)?;
+    if metadata.is_dir() {
+        fs::remove_dir_all(path)?;
+    } else {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn fake_system() -> (TempDir, FactoryReset) {
+        let root = TempDir::new().unwrap();
+        let etc = root.path().join("etc/rexos");
+        let roms = root.path().join("roms");
+        let user = roms.join(".rexos");
+
+        fs::create_dir_all(&etc).unwrap();
+        fs::write(etc.join("config.toml"), "[system]\nbrightness = 3\n").unwrap();
+
+        fs::create_dir_all(user.join("cache/art")).unwrap();
+        fs::create_dir_all(user.join("saves")).unwrap();
+        fs::write(user.join("config.toml"), "[system]\nvolume = 1\n").unwrap();
+        fs::write(user.join("games.db"), "db").unwrap();
+        fs::write(user.join("cache/art/mario.png"), "png").unwrap();
+        fs::write(user.join("saves/mario.srm"), "save").unwrap();
+
+        fs::create_dir_all(roms.join("nes")).unwrap();
+        fs::write(roms.join("nes/mario.nes"), "rom").unwrap();
+
+        let reset = FactoryReset::new().with_config_dir(etc).with_user_dir(user);
+        (root, reset)
+    }
+
+    #[test]
+    fn test_dry_run_changes_nothing() {
+        let (root, reset) = fake_system();
+
+        let actions = reset.clone().with_dry_run(true).run().unwrap();
+        assert_eq!(actions.len(), 4);
+        assert!(root.path().join("roms/.rexos/games.db").exists());
+        assert_eq!(
+            fs::read_to_string(root.path().join("etc/rexos/config.toml")).unwrap(),
+            "[system]\nbrightness = 3\n"
+        );
+    }
+
+    #[test]
+    fn test_reset_preserves_user_data() {
+        let (root, reset) = fake_system();
+        let user = root.path().join("roms/.rexos");
+
+        reset.run().unwrap();
+
+        let config = RexOSConfig::load(&root.path().join("etc/rexos/config.toml")).unwrap();
+        assert_eq!(
+            config.system.brightness,
+            RexOSConfig::default().system.brightness
+        );
+        assert!(!user.join("config.toml").exists());
+        assert!(!user.join("games.db").exists());
+        assert!(!user.join("cache").exists());
+
+        assert!(user.join("saves/mario.srm").exists());
+        assert!(root.path().join("roms/nes/mario.nes").exists());
+
+        // Running again is a no-op
+        assert!(reset.run().unwrap().is_empty());
+    }
+}
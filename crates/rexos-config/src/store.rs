@@ -0,0 +1,212 @@
+//! Live settings with deferred saving
+//!
+//! Dragging a slider changes a setting several times a second. Rewriting
+//! the whole config on each step wears the SD card for nothing, so the
+//! store keeps changes in memory and saves once they stop coming.
+
+use crate::system_config::check_range;
+use crate::{ConfigError, PerformanceProfile, RexOSConfig};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
+
+/// How long settings must stay unchanged before they are saved
+pub const DEFAULT_SAVE_DELAY: Duration = Duration::from_millis(500);
+
+/// A setting changed through the store
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingChange {
+    /// Display brightness (1-255)
+    Brightness(u8),
+    /// Volume percentage
+    Volume(u8),
+    /// CPU performance profile
+    Performance(PerformanceProfile),
+    /// Any other setting, changed through [`ConfigStore::update`]
+    Other,
+}
+
+/// Configuration shared by settings screens
+///
+/// Changes apply at once and are announced to subscribers, but the file
+/// is only written after no change has been made for the save delay.
+/// Call [`ConfigStore::poll`] regularly (e.g. on each UI tick) to write
+/// pending changes, and [`ConfigStore::flush`] before exiting.
+pub struct ConfigStore {
+    config: RexOSConfig,
+    path: PathBuf,
+    save_delay: Duration,
+    /// Time of the latest change not yet saved
+    dirty_since: Option<Instant>,
+    subscribers: Vec<Sender<SettingChange>>,
+}
+
+impl ConfigStore {
+    /// Wrap a loaded config, saving changes to `path`
+    pub fn new(config: RexOSConfig, path: impl Into<PathBuf>) -> Self {
+        Self {
+            config,
+            path: path.into(),
+            save_delay: DEFAULT_SAVE_DELAY,
+            dirty_since: None,
+            subscribers: Vec::new(),
+        }
+    }
+
+    /// Wrap a loaded config, saving changes to the user config
+    pub fn new_default(config: RexOSConfig) -> Self {
+        Self::new(
+            config,
+            Path::new(crate::USER_CONFIG_DIR).join("config.toml"),
+        )
+    }
+
+    /// Set how long settings must stay unchanged before they are saved
+    pub fn with_save_delay(mut self, delay: Duration) -> Self {
+        self.save_delay = delay;
+        self
+    }
+
+    /// Current configuration
+    pub fn config(&self) -> &RexOSConfig {
+        &self.config
+    }
+
+    /// File changes are saved to
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether there are changes not yet saved
+    pub fn is_dirty(&self) -> bool {
+        self.dirty_since.is_some()
+    }
+
+    /// Receive a [`SettingChange`] for each change made from now on
+    pub fn subscribe(&mut self) -> Receiver<SettingChange> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers.push(tx);
+        rx
+    }
+
+    /// Set the display brightness (1-255)
+    pub fn set_brightness(&mut self, brightness: u8) -> Result<(), ConfigError> {
+        check_range("system.brightness", brightness, 1..=255)?;
+        if self.config.system.brightness != brightness {
+            self.config.system.brightness = brightness;
+            self.changed(SettingChange::Brightness(brightness));
+        }
+        Ok(())
+    }
+
+    /// Set the volume percentage
+    pub fn set_volume(&mut self, volume: u8) -> Result<(), ConfigError> {
+        check_range("system.volume", volume, 0..=100)?;
+        if self.config.system.volume != volume {
+            self.config.system.volume = volume;
+            self.changed(SettingChange::Volume(volume));
+        }
+        Ok(())
+    }
+
+    /// Set the CPU performance profile
+    pub fn set_performance(&mut self, profile: PerformanceProfile) {
+        if self.config.system.performance != profile {
+            self.config.system.performance = profile;
+            self.changed(SettingChange::Performance(profile));
+        }
+    }
+
+    /// Change any other setting
+    ///
+    /// The change is rejected, and the config left as it was, if the
+    /// result doesn't validate.
+    pub fn update(&mut self, f: impl FnOnce(&mut RexOSConfig)) -> Result<(), ConfigError> {
+        let mut config = self.config.clone();
+        f(&mut config);
+        config.validate()?;
+        self.config = config;
+        self.changed(SettingChange::Other);
+        Ok(())
+    }
+
+    /// Save pending changes once the save delay has passed since the last
+    ///
+    /// Returns whether the config was written.
+    pub fn poll(&mut self) -> Result<bool, ConfigError> {
+        match self.dirty_since {
+            Some(since) if since.elapsed() >= self.save_delay => {
+                self.flush()?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Save pending changes now
+    pub fn flush(&mut self) -> Result<(), ConfigError> {
+        if self.dirty_since.is_some() {
+            self.config.save(&self.path)?;
+            self.dirty_since = None;
+        }
+        Ok(())
+    }
+
+    fn changed(&mut self, change: SettingChange) {
+        self.dirty_since = Some(Instant::now());
+        // Drop subscribers whose receiver is gone
+        self.subscribers.retain(|tx| tx.send(change).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_coalesces_saves() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let mut store = ConfigStore::new(RexOSConfig::default(), &path)
+            .with_save_delay(Duration::from_millis(50));
+        let changes = store.subscribe();
+
+        for volume in [10, 20, 30] {
+            store.set_volume(volume).unwrap();
+        }
+        store.set_performance(PerformanceProfile::Powersave);
+        assert!(store.set_brightness(0).is_err());
+        assert!(store.set_volume(101).is_err());
+
+        // Nothing is written until changes stop
+        assert!(!store.poll().unwrap());
+        assert!(!path.exists());
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(store.poll().unwrap());
+        assert!(!store.is_dirty());
+        let saved = RexOSConfig::load(&path).unwrap();
+        assert_eq!(saved.system.volume, 30);
+        assert_eq!(saved.system.performance, PerformanceProfile::Powersave);
+
+        let received: Vec<_> = changes.try_iter().collect();
+        assert_eq!(
+            received,
+            vec![
+                SettingChange::Volume(10),
+                SettingChange::Volume(20),
+                SettingChange::Volume(30),
+                SettingChange::Performance(PerformanceProfile::Powersave),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_store_rejects_invalid_update() {
+        let mut store = ConfigStore::new(RexOSConfig::default(), "/nonexistent/config.toml");
+        let result = store.update(|config| config.system.suspend_timeout = 5000);
+        assert!(result.is_err());
+        assert!(!store.is_dirty());
+        assert_ne!(store.config().system.suspend_timeout, 5000);
+    }
+}
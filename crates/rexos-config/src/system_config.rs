@@ -1,6 +1,16 @@
 //! System-wide configuration
 
+use crate::ConfigError;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::ops::RangeInclusive;
+
+/// Update channels the updater knows
+pub const UPDATE_CHANNELS: &[&str] = &["stable", "beta", "nightly"];
+
+/// Screen rotations the display supports, in degrees clockwise
+pub const ROTATIONS: &[u16] = &[0, 90, 180, 270];
 
 /// Performance profile for power management
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -55,6 +65,121 @@ impl Default for NetworkConfig {
     }
 }
 
+/// Boot-time recovery combo
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveryConfig {
+    /// Buttons that must all be held to enter recovery
+    #[serde(default = "default_recovery_combo")]
+    pub combo: Vec<String>,
+
+    /// How long to watch for the combo after input comes up (milliseconds)
+    #[serde(default = "default_recovery_window")]
+    pub window_ms: u64,
+
+    /// Command launched instead of the frontend
+    #[serde(default = "default_recovery_command")]
+    pub command: String,
+}
+
+fn default_recovery_combo() -> Vec<String> {
+    vec!["start".to_string(), "select".to_string()]
+}
+
+fn default_recovery_window() -> u64 {
+    1000
+}
+
+fn default_recovery_command() -> String {
+    "/usr/bin/rexos-launcher --recovery".to_string()
+}
+
+impl Default for RecoveryConfig {
+    fn default() -> Self {
+        Self {
+            combo: default_recovery_combo(),
+            window_ms: default_recovery_window(),
+            command: default_recovery_command(),
+        }
+    }
+}
+
+/// Held-direction repeat for menu navigation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputRepeatConfig {
+    /// Delay before a held direction starts repeating (milliseconds)
+    #[serde(default = "default_repeat_delay")]
+    pub initial_delay_ms: u64,
+
+    /// Interval between the first repeats (milliseconds)
+    #[serde(default = "default_repeat_interval")]
+    pub interval_ms: u64,
+
+    /// Fastest repeat interval (milliseconds)
+    #[serde(default = "default_repeat_min_interval")]
+    pub min_interval_ms: u64,
+
+    /// Interval multiplier per repeat (1.0 = no acceleration)
+    #[serde(default = "default_repeat_acceleration")]
+    pub acceleration: f32,
+}
+
+fn default_repeat_delay() -> u64 {
+    400
+}
+
+fn default_repeat_interval() -> u64 {
+    150
+}
+
+fn default_repeat_min_interval() -> u64 {
+    40
+}
+
+fn default_repeat_acceleration() -> f32 {
+    0.85
+}
+
+impl Default for InputRepeatConfig {
+    fn default() -> Self {
+        Self {
+            initial_delay_ms: default_repeat_delay(),
+            interval_ms: default_repeat_interval(),
+            min_interval_ms: default_repeat_min_interval(),
+            acceleration: default_repeat_acceleration(),
+        }
+    }
+}
+
+/// Gamepad settings
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InputConfig {
+    /// Button each physical button acts as, by name (e.g. "a" = "b" and
+    /// "b" = "a" to swap the face buttons). Unlisted buttons keep their
+    /// own meaning.
+    #[serde(default)]
+    pub remap: BTreeMap<String, String>,
+}
+
+/// Screen settings applied at boot
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DisplayConfig {
+    /// Rotation in degrees clockwise (0, 90, 180 or 270)
+    #[serde(default)]
+    pub rotation: u16,
+}
+
+/// ROMs partition handling
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StorageConfig {
+    /// Run fsck and retry once when the ROMs partition fails to mount
+    #[serde(default)]
+    pub auto_repair: bool,
+
+    /// Only log the fsck that would run
+    #[serde(default)]
+    pub repair_dry_run: bool,
+}
+
 /// System-wide configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemConfig {
@@ -109,6 +234,26 @@ pub struct SystemConfig {
     /// Update channel (stable, beta, nightly)
     #[serde(default = "default_update_channel")]
     pub update_channel: String,
+
+    /// Recovery combo settings
+    #[serde(default)]
+    pub recovery: RecoveryConfig,
+
+    /// Menu navigation repeat settings
+    #[serde(default)]
+    pub input_repeat: InputRepeatConfig,
+
+    /// Gamepad button remapping
+    #[serde(default)]
+    pub input: InputConfig,
+
+    /// ROMs partition handling
+    #[serde(default)]
+    pub storage: StorageConfig,
+
+    /// Screen settings
+    #[serde(default)]
+    pub display: DisplayConfig,
 }
 
 fn default_brightness() -> u8 {
@@ -163,8 +308,77 @@ impl Default for SystemConfig {
             network: NetworkConfig::default(),
             auto_update_check: false,
             update_channel: default_update_channel(),
+            recovery: RecoveryConfig::default(),
+            input_repeat: InputRepeatConfig::default(),
+            input: InputConfig::default(),
+            storage: StorageConfig::default(),
+            display: DisplayConfig::default(),
+        }
+    }
+}
+
+impl SystemConfig {
+    /// Check values deserialization can't, naming the field and its
+    /// valid range on failure
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        check_range("system.brightness", self.brightness, 1..=255)?;
+        check_range("system.volume", self.volume, 0..=100)?;
+        check_range("system.suspend_timeout", self.suspend_timeout, 0..=1440)?;
+        check_range(
+            "system.low_battery_threshold",
+            self.low_battery_threshold,
+            0..=100,
+        )?;
+
+        if !UPDATE_CHANNELS.contains(&self.update_channel.as_str()) {
+            return Err(ConfigError::Invalid(format!(
+                "system.update_channel is \"{}\", expected one of: {}",
+                self.update_channel,
+                UPDATE_CHANNELS.join(", ")
+            )));
+        }
+
+        if !ROTATIONS.contains(&self.display.rotation) {
+            return Err(ConfigError::Invalid(format!(
+                "system.display.rotation is {}, expected one of: 0, 90, 180, 270",
+                self.display.rotation
+            )));
+        }
+
+        let repeat = &self.input_repeat;
+        let valid = repeat.acceleration > 0.0 && repeat.acceleration <= 1.0;
+        if !valid {
+            return Err(ConfigError::Invalid(format!(
+                "system.input_repeat.acceleration is {}, expected more than 0 and at most 1",
+                repeat.acceleration
+            )));
         }
+        check_range(
+            "system.input_repeat.min_interval_ms",
+            repeat.min_interval_ms,
+            1..=repeat.interval_ms.max(1),
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Fail unless `value` is within `range`
+pub(crate) fn check_range<T: PartialOrd + Display>(
+    field: &str,
+    value: T,
+    range: RangeInclusive<T>,
+) -> Result<(), ConfigError> {
+    if range.contains(&value) {
+        return Ok(());
     }
+    Err(ConfigError::Invalid(format!(
+        "{} is {}, expected {} to {}",
+        field,
+        value,
+        range.start(),
+        range.end()
+    )))
 }
 
 #[cfg(test)]
@@ -186,4 +400,53 @@ mod tests {
         let toml_str = toml::to_string(&config).unwrap();
         assert!(toml_str.contains("balanced")); // default is balanced
     }
+
+    #[test]
+    fn test_validate() {
+        assert!(SystemConfig::default().validate().is_ok());
+
+        let config: SystemConfig = toml::from_str("volume = 150\n").unwrap();
+        let err = config.validate().unwrap_err().to_string();
+        assert!(
+            err.contains("system.volume is 150, expected 0 to 100"),
+            "{}",
+            err
+        );
+
+        let config: SystemConfig = toml::from_str("update_channel = \"weekly\"\n").unwrap();
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("stable, beta, nightly"), "{}", err);
+
+        let config: SystemConfig = toml::from_str("suspend_timeout = 100000\n").unwrap();
+        assert!(config.validate().is_err());
+
+        let config: SystemConfig = toml::from_str("[display]\nrotation = 270\n").unwrap();
+        assert!(config.validate().is_ok());
+        let config: SystemConfig = toml::from_str("[display]\nrotation = 45\n").unwrap();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_recovery_defaults() {
+        let config: SystemConfig = toml::from_str("[recovery]\nwindow_ms = 500\n").unwrap();
+        assert_eq!(config.recovery.combo, ["start", "select"]);
+        assert_eq!(config.recovery.window_ms, 500);
+        assert!(config.recovery.command.ends_with("--recovery"));
+    }
+
+    #[test]
+    fn test_input_remap_round_trip() {
+        let config: SystemConfig = toml::from_str(
+            "[input.remap]
+a = \"b\"
+b = \"a\"
+",
+        )
+        .unwrap();
+        assert_eq!(config.input.remap["a"], "b");
+
+        let saved = toml::to_string(&config).unwrap();
+        let loaded: SystemConfig = toml::from_str(&saved).unwrap();
+        assert_eq!(loaded.input, config.input);
+    }
 }
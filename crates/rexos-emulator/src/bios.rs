@@ -0,0 +1,115 @@
+//! BIOS files needed by emulator cores
+//!
+//! Cores for disc-based systems won't boot without a BIOS dump, and fail
+//! inside the emulator with little to go on. Checking for the files
+//! before launch gives the user a list of what to copy in instead.
+
+use crate::GameSystem;
+use md5::{Digest, Md5};
+use std::fs;
+use std::path::Path;
+
+/// A BIOS file a system needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BiosRequirement {
+    /// Path relative to the BIOS directory
+    pub file: &'static str,
+    /// MD5 hashes of known good dumps (lowercase hex)
+    pub md5: &'static [&'static str],
+}
+
+const PSX_BIOS: &[BiosRequirement] = &[BiosRequirement {
+    file: "scph1001.bin",
+    md5: &["924e392ed05558ffdb115408c263dccf"],
+}];
+
+const SEGACD_BIOS: &[BiosRequirement] = &[BiosRequirement {
+    file: "bios_CD_U.bin",
+    md5: &["2efd74e3232ff260e371b99f84024f7f"],
+}];
+
+const SATURN_BIOS: &[BiosRequirement] = &[BiosRequirement {
+    file: "mpr-17933.bin",
+    md5: &["3240872c70984b6cbfda1586cab68dbe"],
+}];
+
+const DREAMCAST_BIOS: &[BiosRequirement] = &[
+    BiosRequirement {
+        file: "dc/dc_boot.bin",
+        md5: &["e10c53c2f8b90bab96ead2d368858623"],
+    },
+    BiosRequirement {
+        file: "dc/dc_flash.bin",
+        md5: &["0a93f7940c455905bea6e392dfde92a4"],
+    },
+];
+
+/// BIOS files a system's default core needs to boot
+pub fn required_bios(system: &GameSystem) -> &'static [BiosRequirement] {
+    match system {
+        GameSystem::Psx => PSX_BIOS,
+        GameSystem::SegaCd => SEGACD_BIOS,
+        GameSystem::Saturn => SATURN_BIOS,
+        GameSystem::Dreamcast => DREAMCAST_BIOS,
+        _ => &[],
+    }
+}
+
+/// Required files missing from `bios_dir` or not matching a known dump
+pub(crate) fn missing_bios(requirements: &[BiosRequirement], bios_dir: &Path) -> Vec<String> {
+    requirements
+        .iter()
+        .filter(|requirement| {
+            let path = bios_dir.join(requirement.file);
+            let Ok(data) = fs::read(&path) else {
+                return true;
+            };
+
+            let hash = format!("{:x}", Md5::digest(&data));
+            let known = requirement.md5.is_empty() || requirement.md5.contains(&hash.as_str());
+            if !known {
+                tracing::warn!("Unknown BIOS dump {} (md5 {})", path.display(), hash);
+            }
+            !known
+        })
+        .map(|requirement| requirement.file.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_bios() {
+        let dir = tempfile::tempdir().unwrap();
+        let requirements = [
+            BiosRequirement {
+                file: "good.bin",
+                md5: &["88264747405203a0502c8d242fdad7df"],
+            },
+            BiosRequirement {
+                file: "bad.bin",
+                md5: &["88264747405203a0502c8d242fdad7df"],
+            },
+            BiosRequirement {
+                file: "absent.bin",
+                md5: &[],
+            },
+        ];
+        fs::write(dir.path().join("good.bin"), b"bios").unwrap();
+        fs::write(dir.path().join("bad.bin"), b"BIOS").unwrap();
+
+        assert_eq!(
+            missing_bios(&requirements, dir.path()),
+            vec!["bad.bin".to_string(), "absent.bin".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_required_bios() {
+        assert_eq!(required_bios(&GameSystem::Psx)[0].file, "scph1001.bin");
+        assert_eq!(required_bios(&GameSystem::Dreamcast).len(), 2);
+        assert!(required_bios(&GameSystem::GameBoyAdvance).is_empty());
+    }
+}
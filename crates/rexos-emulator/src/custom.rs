@@ -0,0 +1,226 @@
+//! Per-game custom launch commands
+//!
+//! Some games (DOS titles needing mount commands, ports with their own
+//! scripts) don't fit a core or standalone emulator. A game can carry a
+//! command template that is run instead, e.g.
+//!
+//! ```text
+//! /usr/bin/dosbox -c "mount c {rom_dir}" -c "c:\GAME.EXE"
+//! ```
+//!
+//! Placeholders, substituted per argument:
+//!
+//! - `{rom}`: full ROM path
+//! - `{rom_dir}`: directory containing the ROM
+//! - `{rom_name}`: ROM file name without extension
+//!
+//! The command is split into arguments here and run directly, never
+//! through a shell, so a substituted path is always a single argument.
+//! Shell syntax is rejected rather than silently passed through.
+
+use crate::EmulatorError;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+
+/// Characters that only mean something to a shell
+const SHELL_CHARS: &[char] = &[';', '|', '&', '$', '`', '<', '>', '\n', '\r'];
+
+/// Placeholders accepted in a template
+const PLACEHOLDERS: &[&str] = &["{rom}", "{rom_dir}", "{rom_name}"];
+
+/// A parsed custom launch command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomCommand {
+    program: String,
+    args: Vec<String>,
+}
+
+impl CustomCommand {
+    /// Parse a command template
+    ///
+    /// Arguments are split on whitespace; single or double quotes group
+    /// an argument containing spaces. The program must be an absolute
+    /// path, optionally starting with `{rom_dir}`.
+    pub fn parse(template: &str) -> Result<Self, EmulatorError> {
+        if let Some(c) = template.chars().find(|c| SHELL_CHARS.contains(c)) {
+            return Err(EmulatorError::ConfigError(format!(
+                "Shell syntax ({:?}) isn't supported in launch commands",
+                c
+            )));
+        }
+
+        let mut words = split_words(template)?;
+        if words.is_empty() {
+            return Err(EmulatorError::ConfigError("Empty launch command".into()));
+        }
+
+        for word in &words {
+            check_placeholders(word, PLACEHOLDERS)?;
+        }
+
+        let program = words.remove(0);
+        if !program.starts_with('/') && !program.starts_with("{rom_dir}/") {
+            return Err(EmulatorError::ConfigError(format!(
+                "Launch command must use an absolute path: {}",
+                program
+            )));
+        }
+
+        Ok(Self {
+            program,
+            args: words,
+        })
+    }
+
+    /// Program to run for a ROM
+    pub fn program(&self, rom: &Path) -> PathBuf {
+        PathBuf::from(substitute(&self.program, rom))
+    }
+
+    /// Arguments for a ROM, with placeholders substituted
+    pub fn args(&self, rom: &Path) -> Vec<OsString> {
+        self.args.iter().map(|arg| substitute(arg, rom)).collect()
+    }
+}
+
+/// Split a template into words, honouring quotes
+pub(crate) fn split_words(template: &str) -> Result<Vec<String>, EmulatorError> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+
+    for c in template.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if quote.is_some() {
+        return Err(EmulatorError::ConfigError(
+            "Unterminated quote in launch command".into(),
+        ));
+    }
+    if in_word {
+        words.push(current);
+    }
+    Ok(words)
+}
+
+/// Reject `{...}` that isn't one of `placeholders`
+pub(crate) fn check_placeholders(word: &str, placeholders: &[&str]) -> Result<(), EmulatorError> {
+    let mut rest = word;
+    while let Some(start) = rest.find('{') {
+        let tail = &rest[start..];
+        match placeholders.iter().find(|p| tail.starts_with(**p)) {
+            Some(p) => rest = &tail[p.len()..],
+            None => {
+                return Err(EmulatorError::ConfigError(format!(
+                    "Unknown placeholder in launch command: {}",
+                    word
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Substitute the ROM placeholders, keeping non-UTF-8 paths intact
+fn substitute(word: &str, rom: &Path) -> OsString {
+    expand(word, &rom_values(rom))
+}
+
+/// Values of the ROM placeholders
+pub(crate) fn rom_values(rom: &Path) -> Vec<(&'static str, &OsStr)> {
+    vec![
+        ("{rom}", rom.as_os_str()),
+        (
+            "{rom_dir}",
+            rom.parent().unwrap_or(Path::new("/")).as_os_str(),
+        ),
+        ("{rom_name}", rom.file_stem().unwrap_or_default()),
+    ]
+}
+
+/// Substitute placeholders with their values, leaving unknown ones as is
+pub(crate) fn expand(word: &str, values: &[(&str, &OsStr)]) -> OsString {
+    let mut out = OsString::new();
+    let mut rest = word;
+
+    while let Some(start) = rest.find('{') {
+        out.push(&rest[..start]);
+        let tail = &rest[start..];
+        let Some((placeholder, value)) = values.iter().find(|(p, _)| tail.starts_with(*p)) else {
+            out.push("{");
+            rest = &tail[1..];
+            continue;
+        };
+
+        out.push(value);
+        rest = &tail[placeholder.len()..];
+    }
+
+    out.push(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_substitute() {
+        let cmd =
+            CustomCommand::parse(r#"/usr/bin/dosbox -c "mount c {rom_dir}" -conf {rom_name}.conf"#)
+                .unwrap();
+        let rom = Path::new("/roms/pc/My Game/GAME.BAT");
+
+        assert_eq!(cmd.program(rom), PathBuf::from("/usr/bin/dosbox"));
+        assert_eq!(
+            cmd.args(rom),
+            vec![
+                OsString::from("-c"),
+                OsString::from("mount c /roms/pc/My Game"),
+                OsString::from("-conf"),
+                OsString::from("GAME.conf"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rom_path_stays_one_argument() {
+        let cmd = CustomCommand::parse("{rom_dir}/run.sh {rom}").unwrap();
+        let rom = Path::new("/roms/ports/a b; rm -rf x/game.sh");
+
+        assert_eq!(
+            cmd.program(rom),
+            PathBuf::from("/roms/ports/a b; rm -rf x/run.sh")
+        );
+        assert_eq!(cmd.args(rom), vec![rom.as_os_str().to_os_string()]);
+    }
+
+    #[test]
+    fn test_parse_rejects_unsafe() {
+        assert!(CustomCommand::parse("").is_err());
+        assert!(CustomCommand::parse("dosbox {rom}").is_err());
+        assert!(CustomCommand::parse("/usr/bin/dosbox {rom}; reboot").is_err());
+        assert!(CustomCommand::parse("/usr/bin/dosbox $(cat x)").is_err());
+        assert!(CustomCommand::parse("/usr/bin/dosbox {path}").is_err());
+        assert!(CustomCommand::parse("/usr/bin/dosbox \"{rom}").is_err());
+    }
+}
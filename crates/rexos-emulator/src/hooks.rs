@@ -0,0 +1,144 @@
+//! User hook scripts run around a game
+//!
+//! Lets users switch a performance mode, mount a DOS image or sync saves
+//! without patching the launcher. These are unrelated to the update
+//! package's post-install scripts. Each script gets the ROM path and
+//! system short name as arguments and as `REXOS_ROM` / `REXOS_SYSTEM`,
+//! plus `REXOS_HOOK` naming the stage. Output goes to the session log when
+//! logs are captured.
+
+use crate::{EmulatorError, GameSystem};
+use rexos_config::HookConfig;
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Hook scripts resolved for one launch
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LaunchHooks {
+    /// ROM being launched
+    pub rom: PathBuf,
+    /// System short name, empty if unknown
+    pub system: String,
+    /// Scripts run before the emulator starts, in order
+    pub pre_launch: Vec<PathBuf>,
+    /// Scripts run after the emulator exits, in order
+    pub post_exit: Vec<PathBuf>,
+}
+
+impl LaunchHooks {
+    /// Resolve hooks for a launch: global ones, then the system's own
+    pub fn resolve(
+        rom: &Path,
+        system: Option<&GameSystem>,
+        global: &HookConfig,
+        per_system: &HashMap<String, HookConfig>,
+    ) -> Self {
+        let system = system.map(|s| s.short_name()).unwrap_or_default();
+        let configs: Vec<&HookConfig> = std::iter::once(global)
+            .chain(per_system.get(system))
+            .collect();
+
+        Self {
+            rom: rom.to_path_buf(),
+            system: system.to_string(),
+            pre_launch: configs
+                .iter()
+                .filter_map(|c| c.pre_launch.clone())
+                .collect(),
+            post_exit: configs.iter().filter_map(|c| c.post_exit.clone()).collect(),
+        }
+    }
+
+    /// Run pre-launch scripts, stopping at the first failure
+    pub fn run_pre_launch(&self, log: Option<&Path>) -> Result<(), EmulatorError> {
+        for script in &self.pre_launch {
+            self.run(script, "pre-launch", log)?;
+        }
+        Ok(())
+    }
+
+    /// Run post-exit scripts; failures are only logged
+    pub fn run_post_exit(&self, log: Option<&Path>) {
+        for script in &self.post_exit {
+            if let Err(e) = self.run(script, "post-exit", log) {
+                tracing::warn!("{}", e);
+            }
+        }
+    }
+
+    /// Run one script and wait for it
+    fn run(&self, script: &Path, stage: &str, log: Option<&Path>) -> Result<(), EmulatorError> {
+        let mut cmd = Command::new(script);
+        cmd.arg(&self.rom)
+            .arg(&self.system)
+            .env("REXOS_ROM", &self.rom)
+            .env("REXOS_SYSTEM", &self.system)
+            .env("REXOS_HOOK", stage)
+            .stdin(Stdio::null());
+
+        if let Some(path) = log {
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+            cmd.stdout(file.try_clone()?).stderr(file);
+        }
+
+        tracing::debug!("Running {} hook {}", stage, script.display());
+        let status = cmd.status().map_err(|e| {
+            EmulatorError::LaunchFailed(format!(
+                "Failed to run {} hook {}: {}",
+                stage,
+                script.display(),
+                e
+            ))
+        })?;
+
+        if !status.success() {
+            return Err(EmulatorError::LaunchFailed(format!(
+                "{} hook {} failed ({})",
+                stage,
+                script.display(),
+                status
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_global_then_system() {
+        let global = HookConfig {
+            pre_launch: Some(PathBuf::from("/hooks/perf.sh")),
+            post_exit: None,
+        };
+        let mut per_system = HashMap::new();
+        per_system.insert(
+            "dos".to_string(),
+            HookConfig {
+                pre_launch: Some(PathBuf::from("/hooks/mount.sh")),
+                post_exit: Some(PathBuf::from("/hooks/umount.sh")),
+            },
+        );
+
+        let rom = Path::new("/roms/dos/game.bat");
+        let hooks = LaunchHooks::resolve(rom, Some(&GameSystem::Dos), &global, &per_system);
+        assert_eq!(hooks.system, "dos");
+        assert_eq!(
+            hooks.pre_launch,
+            vec![
+                PathBuf::from("/hooks/perf.sh"),
+                PathBuf::from("/hooks/mount.sh")
+            ]
+        );
+        assert_eq!(hooks.post_exit, vec![PathBuf::from("/hooks/umount.sh")]);
+
+        let hooks = LaunchHooks::resolve(rom, None, &global, &per_system);
+        assert_eq!(hooks.system, "");
+        assert_eq!(hooks.pre_launch.len(), 1);
+        assert!(hooks.post_exit.is_empty());
+    }
+}
@@ -0,0 +1,164 @@
+//! In-game hotkeys for RetroArch
+//!
+//! Translates the launcher's [`HotkeyConfig`] into RetroArch's input
+//! hotkey entries, so the same combos (e.g. Select + Start to quit) work
+//! in every core. Every action RetroArch knows is written, unbound ones as
+//! "nul", so bindings left in retroarch.cfg can't disagree with the config.
+
+use rexos_config::{HotkeyAction, HotkeyConfig};
+
+/// RetroArch setting for the hotkey modifier
+const ENABLE_HOTKEY: &str = "input_enable_hotkey_btn";
+
+/// Actions with a RetroArch equivalent, and its setting
+const RETROARCH_ACTIONS: &[(HotkeyAction, &str)] = &[
+    (HotkeyAction::Exit, "input_exit_emulator_btn"),
+    (HotkeyAction::SaveState, "input_save_state_btn"),
+    (HotkeyAction::LoadState, "input_load_state_btn"),
+    (HotkeyAction::FastForward, "input_hold_fast_forward_btn"),
+    (HotkeyAction::Rewind, "input_rewind_btn"),
+    (HotkeyAction::Screenshot, "input_screenshot_btn"),
+    (HotkeyAction::Pause, "input_pause_toggle_btn"),
+    (HotkeyAction::Menu, "input_menu_toggle_btn"),
+    (HotkeyAction::NextSlot, "input_state_slot_increase_btn"),
+    (HotkeyAction::PrevSlot, "input_state_slot_decrease_btn"),
+    (HotkeyAction::VolumeUp, "input_volume_up_btn"),
+    (HotkeyAction::VolumeDown, "input_volume_down_btn"),
+    (HotkeyAction::ShowFps, "input_fps_toggle_btn"),
+    (HotkeyAction::Reset, "input_reset_btn"),
+];
+
+/// Joypad id for a button on the built-in layout
+///
+/// Matches the ids in the shipped retroarch.cfg; the D-pad is a hat.
+fn default_button_id(button: &str) -> Option<&'static str> {
+    Some(match button.to_lowercase().as_str() {
+        "a" => "0",
+        "b" => "1",
+        "y" => "2",
+        "x" => "3",
+        "l1" | "l" => "4",
+        "r1" | "r" => "5",
+        "select" => "6",
+        "start" => "7",
+        "l3" => "8",
+        "r3" => "9",
+        "l2" => "10",
+        "r2" => "11",
+        "up" => "h0up",
+        "down" => "h0down",
+        "left" => "h0left",
+        "right" => "h0right",
+        _ => return None,
+    })
+}
+
+/// Joypad id for a button name, preferring configured ids
+fn button_id(config: &HotkeyConfig, button: &str) -> Option<String> {
+    config
+        .button_ids
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(button))
+        .map(|(_, id)| id.clone())
+        .or_else(|| default_button_id(button).map(str::to_string))
+}
+
+/// RetroArch config entries for the configured hotkeys
+pub fn retroarch_hotkeys(config: &HotkeyConfig) -> Vec<(&'static str, String)> {
+    let modifier = if config.enabled {
+        button_id(config, &config.modifier)
+    } else {
+        None
+    };
+    let Some(modifier) = modifier else {
+        if config.enabled {
+            tracing::warn!("Unknown hotkey modifier button: {}", config.modifier);
+        }
+        let mut options = vec![(ENABLE_HOTKEY, "nul".to_string())];
+        options.extend(
+            RETROARCH_ACTIONS
+                .iter()
+                .map(|(_, key)| (*key, "nul".to_string())),
+        );
+        return options;
+    };
+
+    let mut options = vec![(ENABLE_HOTKEY, modifier)];
+    for (action, key) in RETROARCH_ACTIONS {
+        let id = config.hotkeys.get(action).and_then(|button| {
+            let id = button_id(config, button);
+            if id.is_none() {
+                tracing::warn!("Unknown button {} for hotkey {:?}", button, action);
+            }
+            id
+        });
+        options.push((*key, id.unwrap_or_else(|| "nul".to_string())));
+    }
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value<'a>(options: &'a [(&str, String)], key: &str) -> &'a str {
+        options
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v.as_str())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_quit_combo() {
+        let mut config = HotkeyConfig::default();
+        config.hotkeys.clear();
+        config.set_hotkey(HotkeyAction::Exit, "Start".to_string());
+
+        let options = retroarch_hotkeys(&config);
+        assert_eq!(value(&options, "input_enable_hotkey_btn"), "6");
+        assert_eq!(value(&options, "input_exit_emulator_btn"), "7");
+        assert_eq!(value(&options, "input_save_state_btn"), "nul");
+    }
+
+    #[test]
+    fn test_defaults_match_shipped_config() {
+        let options = retroarch_hotkeys(&HotkeyConfig::default());
+        assert_eq!(value(&options, "input_save_state_btn"), "5");
+        assert_eq!(value(&options, "input_load_state_btn"), "4");
+        assert_eq!(value(&options, "input_menu_toggle_btn"), "3");
+        assert_eq!(value(&options, "input_hold_fast_forward_btn"), "11");
+        assert_eq!(value(&options, "input_screenshot_btn"), "10");
+        assert_eq!(value(&options, "input_state_slot_increase_btn"), "h0right");
+    }
+
+    #[test]
+    fn test_button_id_overrides() {
+        let mut config = HotkeyConfig {
+            modifier: "Guide".to_string(),
+            ..HotkeyConfig::default()
+        };
+        config
+            .button_ids
+            .insert("guide".to_string(), "8".to_string());
+        config
+            .button_ids
+            .insert("Start".to_string(), "9".to_string());
+
+        let options = retroarch_hotkeys(&config);
+        assert_eq!(value(&options, "input_enable_hotkey_btn"), "8");
+        assert_eq!(value(&options, "input_exit_emulator_btn"), "9");
+    }
+
+    #[test]
+    fn test_disabled_unbinds_everything() {
+        let config = HotkeyConfig {
+            enabled: false,
+            ..HotkeyConfig::default()
+        };
+
+        let options = retroarch_hotkeys(&config);
+        assert_eq!(options.len(), RETROARCH_ACTIONS.len() + 1);
+        assert!(options.iter().all(|(_, v)| v == "nul"));
+    }
+}
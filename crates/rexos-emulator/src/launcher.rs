@@ -1,8 +1,30 @@
 //! Main emulator launcher
 
-use crate::{EmulatorError, GameSystem};
-use std::path::PathBuf;
-use std::process::{Child, Command, Stdio};
+use crate::bios::missing_bios;
+use crate::hooks::LaunchHooks;
+use crate::hotkeys::retroarch_hotkeys;
+use crate::remap::{InputProfile, core_remap_name};
+use crate::{
+    CustomCommand, DisplayGeometry, EmulatorError, GameSystem, ResourceLimits, RetroArchLauncher,
+    ShaderChoice, ShaderSettings, VideoSettings, required_bios,
+};
+use rexos_config::{
+    CoreConfig, HookConfig, HotkeyConfig, InputProfileConfig, ResourceLimitsConfig, VideoConfig,
+};
+use rexos_hal::DeviceProfile;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Lines of stderr kept in [`LaunchResult::stderr_tail`]
+const STDERR_TAIL_LINES: usize = 20;
+
+/// Launches by this process, keeping append config file names unique
+static APPEND_CONFIG_COUNTER: AtomicU64 = AtomicU64::new(0);
 
 /// Launch configuration
 #[derive(Debug, Clone)]
@@ -30,6 +52,18 @@ pub struct LaunchConfig {
 
     /// Additional arguments
     pub extra_args: Vec<String>,
+
+    /// Video settings (system defaults if None)
+    pub video: Option<VideoSettings>,
+
+    /// Per-game shader choice (system default if None)
+    pub shader: Option<ShaderChoice>,
+
+    /// Custom command run instead of RetroArch (see [`CustomCommand`])
+    pub command: Option<String>,
+
+    /// Panel geometry (the launcher's device if None)
+    pub display: Option<DisplayGeometry>,
 }
 
 impl Default for LaunchConfig {
@@ -43,6 +77,10 @@ impl Default for LaunchConfig {
             load_state: None,
             verbose: false,
             extra_args: Vec::new(),
+            video: None,
+            shader: None,
+            command: None,
+            display: None,
         }
     }
 }
@@ -50,21 +88,33 @@ impl Default for LaunchConfig {
 impl LaunchConfig {
     /// Create config for a ROM path
     pub fn for_rom(rom_path: impl Into<PathBuf>) -> Self {
-        let path = rom_path.into();
-
-        // Auto-detect system from extension
-        let system = path
-            .extension()
-            .and_then(|e| e.to_str())
-            .and_then(GameSystem::from_extension);
+        Self::default().with_rom(rom_path)
+    }
 
+    /// Create config with a device's display geometry, before setting
+    /// the ROM with [`LaunchConfig::with_rom`]
+    pub fn from_device_profile(profile: &DeviceProfile) -> Self {
         Self {
-            rom_path: path,
-            system,
+            display: Some(DisplayGeometry::from_profile(profile)),
             ..Default::default()
         }
     }
 
+    /// Set the ROM path, detecting its system
+    pub fn with_rom(mut self, rom_path: impl Into<PathBuf>) -> Self {
+        self.rom_path = rom_path.into();
+
+        // Auto-detect system from extension, or directory for disc images
+        self.system = GameSystem::from_path(&self.rom_path);
+        self
+    }
+
+    /// Set the panel geometry
+    pub fn with_display(mut self, display: DisplayGeometry) -> Self {
+        self.display = Some(display);
+        self
+    }
+
     /// Set the game system
     pub fn with_system(mut self, system: GameSystem) -> Self {
         self.system = Some(system);
@@ -88,6 +138,24 @@ impl LaunchConfig {
         self.load_state = Some(slot);
         self
     }
+
+    /// Override video settings
+    pub fn with_video(mut self, video: VideoSettings) -> Self {
+        self.video = Some(video);
+        self
+    }
+
+    /// Override the shader preset for this game
+    pub fn with_shader(mut self, shader: ShaderChoice) -> Self {
+        self.shader = Some(shader);
+        self
+    }
+
+    /// Run a custom command template instead of RetroArch
+    pub fn with_command(mut self, command: impl Into<String>) -> Self {
+        self.command = Some(command.into());
+        self
+    }
 }
 
 /// Launch result
@@ -101,6 +169,152 @@ pub struct LaunchResult {
 
     /// Core/emulator used
     pub emulator: String,
+
+    /// Per-launch stderr log, when capturing logs
+    pub log_path: Option<PathBuf>,
+
+    /// Per-launch RetroArch append config, removed by [`LaunchResult::wait`]
+    pub append_config: Option<PathBuf>,
+
+    /// Exit status, set by [`LaunchResult::wait`]
+    pub status: Option<ExitStatus>,
+
+    /// Last lines of the stderr log, set by [`LaunchResult::wait`]
+    pub stderr_tail: Vec<String>,
+
+    /// Time from launch to exit, set by [`LaunchResult::wait`]
+    pub duration: Option<Duration>,
+
+    /// When the process was spawned
+    started: Instant,
+
+    /// Post-exit hooks, run once by [`LaunchResult::wait`]
+    hooks: LaunchHooks,
+}
+
+impl LaunchResult {
+    /// Wait for the emulator to exit and collect its status and log tail
+    pub fn wait(&mut self) -> Result<ExitStatus, EmulatorError> {
+        let status = self.child.wait()?;
+        self.status = Some(status);
+        self.duration = Some(self.started.elapsed());
+
+        if let Some(path) = &self.log_path {
+            self.stderr_tail = read_tail(path, STDERR_TAIL_LINES);
+        }
+
+        if let Some(path) = self.append_config.take() {
+            fs::remove_file(path).ok();
+        }
+
+        std::mem::take(&mut self.hooks).run_post_exit(self.log_path.as_deref());
+        Ok(status)
+    }
+
+    /// Exit code, `None` if still running or killed by a signal
+    pub fn exit_code(&self) -> Option<i32> {
+        self.status.and_then(|status| status.code())
+    }
+
+    /// Check if the emulator exited abnormally
+    pub fn failed(&self) -> bool {
+        self.status.is_some_and(|status| !status.success())
+    }
+
+    /// Short reason for an abnormal exit (e.g. "segfault", "exit code 1")
+    pub fn exit_reason(&self) -> Option<String> {
+        exit_reason(self.status?)
+    }
+
+    /// How the session ended, once [`LaunchResult::wait`] has returned
+    pub fn exit_info(&self) -> Option<ExitInfo> {
+        let status = self.status?;
+
+        Some(ExitInfo {
+            emulator: self.emulator.clone(),
+            exit_code: status.code(),
+            signal: status.signal(),
+            duration: self.duration.unwrap_or_default(),
+            reason: exit_reason(status),
+            log_path: self.log_path.clone(),
+            stderr_tail: self.stderr_tail.clone(),
+        })
+    }
+}
+
+/// How a game session ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExitInfo {
+    /// Core/emulator used
+    pub emulator: String,
+
+    /// Exit code, `None` if killed by a signal
+    pub exit_code: Option<i32>,
+
+    /// Signal that killed the process
+    pub signal: Option<i32>,
+
+    /// Wall-clock time from launch to exit
+    pub duration: Duration,
+
+    /// Short reason for an abnormal exit (e.g. "segfault", "exit code 1")
+    pub reason: Option<String>,
+
+    /// Per-launch stderr log, when capturing logs
+    pub log_path: Option<PathBuf>,
+
+    /// Last lines of the stderr log
+    pub stderr_tail: Vec<String>,
+}
+
+impl ExitInfo {
+    /// Check if the emulator crashed, rather than quitting or being asked
+    /// to stop
+    pub fn crashed(&self) -> bool {
+        self.signal
+            .is_some_and(|signal| ![libc::SIGTERM, libc::SIGINT, libc::SIGHUP].contains(&signal))
+    }
+}
+
+/// Short reason for an exit status, `None` on success
+fn exit_reason(status: ExitStatus) -> Option<String> {
+    if status.success() {
+        return None;
+    }
+
+    Some(match (status.code(), status.signal()) {
+        (Some(code), _) => format!("exit code {}", code),
+        (None, Some(signal)) => signal_name(signal),
+        (None, None) => "unknown".to_string(),
+    })
+}
+
+/// Readable name for a signal that ended a process
+fn signal_name(signal: i32) -> String {
+    match signal {
+        libc::SIGSEGV => "segfault".to_string(),
+        libc::SIGABRT => "aborted".to_string(),
+        libc::SIGBUS => "bus error".to_string(),
+        libc::SIGILL => "illegal instruction".to_string(),
+        libc::SIGFPE => "arithmetic error".to_string(),
+        libc::SIGKILL => "killed".to_string(),
+        libc::SIGTERM => "terminated".to_string(),
+        _ => format!("signal {}", signal),
+    }
+}
+
+/// Last `lines` lines of a file
+fn read_tail(path: &Path, lines: usize) -> Vec<String> {
+    let Ok(bytes) = fs::read(path) else {
+        return Vec::new();
+    };
+    let contents = String::from_utf8_lossy(&bytes);
+    let all: Vec<&str> = contents.lines().collect();
+
+    all[all.len().saturating_sub(lines)..]
+        .iter()
+        .map(|line| line.to_string())
+        .collect()
 }
 
 /// Main emulator launcher
@@ -119,6 +333,45 @@ pub struct EmulatorLauncher {
 
     /// Default RetroArch config
     config_path: PathBuf,
+
+    /// Per-system video overrides from config
+    video_overrides: HashMap<String, VideoConfig>,
+
+    /// Device profile, for display-specific defaults
+    device: Option<DeviceProfile>,
+
+    /// Shader preset settings
+    shaders: ShaderSettings,
+
+    /// Directory for generated per-launch config
+    runtime_dir: PathBuf,
+
+    /// Per-system input profile overrides from config
+    input_profiles: HashMap<String, InputProfileConfig>,
+
+    /// Per-system resource limits from config
+    resource_limits: HashMap<String, ResourceLimitsConfig>,
+
+    /// cgroup v2 mount point for emulator cgroups
+    cgroup_root: PathBuf,
+
+    /// Directory for per-launch stderr logs (inherit stderr if None)
+    log_dir: Option<PathBuf>,
+
+    /// In-game hotkeys (retroarch.cfg bindings if None)
+    hotkeys: Option<HotkeyConfig>,
+
+    /// Hook scripts run around every game
+    hooks: HookConfig,
+
+    /// Per-system hook scripts from config
+    system_hooks: HashMap<String, HookConfig>,
+
+    /// Core options and overrides from config, keyed by core name
+    core_configs: HashMap<String, CoreConfig>,
+
+    /// Directory RetroArch loads BIOS files from
+    bios_dir: PathBuf,
 }
 
 impl Default for EmulatorLauncher {
@@ -129,6 +382,19 @@ impl Default for EmulatorLauncher {
             cores64_dir: PathBuf::from("/usr/lib/libretro"),
             cores32_dir: PathBuf::from("/usr/lib/libretro32"),
             config_path: PathBuf::from("/home/ark/.config/retroarch/retroarch.cfg"),
+            video_overrides: HashMap::new(),
+            device: None,
+            shaders: ShaderSettings::default(),
+            runtime_dir: std::env::temp_dir(),
+            input_profiles: HashMap::new(),
+            resource_limits: HashMap::new(),
+            cgroup_root: PathBuf::from("/sys/fs/cgroup"),
+            log_dir: None,
+            hotkeys: None,
+            hooks: HookConfig::default(),
+            system_hooks: HashMap::new(),
+            core_configs: HashMap::new(),
+            bios_dir: PathBuf::from("/roms/bios"),
         }
     }
 }
@@ -152,9 +418,118 @@ impl EmulatorLauncher {
             cores64_dir: cores64.into(),
             cores32_dir: cores32.into(),
             config_path: PathBuf::from("/home/ark/.config/retroarch/retroarch.cfg"),
+            video_overrides: HashMap::new(),
+            device: None,
+            shaders: ShaderSettings::default(),
+            runtime_dir: std::env::temp_dir(),
+            input_profiles: HashMap::new(),
+            resource_limits: HashMap::new(),
+            cgroup_root: PathBuf::from("/sys/fs/cgroup"),
+            log_dir: None,
+            hotkeys: None,
+            hooks: HookConfig::default(),
+            system_hooks: HashMap::new(),
+            core_configs: HashMap::new(),
+            bios_dir: PathBuf::from("/roms/bios"),
         }
     }
 
+    /// Set per-system video overrides
+    pub fn with_video_overrides(mut self, overrides: HashMap<String, VideoConfig>) -> Self {
+        self.video_overrides = overrides;
+        self
+    }
+
+    /// Set core options and overrides, keyed by core name
+    pub fn with_core_configs(mut self, cores: HashMap<String, CoreConfig>) -> Self {
+        self.core_configs = cores;
+        self
+    }
+
+    /// Set the directory BIOS files are checked in
+    pub fn with_bios_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.bios_dir = dir.into();
+        self
+    }
+
+    /// Set per-system input profile overrides
+    pub fn with_input_profiles(mut self, profiles: HashMap<String, InputProfileConfig>) -> Self {
+        self.input_profiles = profiles;
+        self
+    }
+
+    /// Set per-system resource limits
+    pub fn with_resource_limits(mut self, limits: HashMap<String, ResourceLimitsConfig>) -> Self {
+        self.resource_limits = limits;
+        self
+    }
+
+    /// Set the cgroup v2 mount point
+    pub fn with_cgroup_root(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cgroup_root = dir.into();
+        self
+    }
+
+    /// Capture emulator stderr to a per-launch log file in `dir`
+    pub fn with_log_capture(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.log_dir = Some(dir.into());
+        self
+    }
+
+    /// Bind in-game hotkeys from the launcher's hotkey config
+    pub fn with_hotkeys(mut self, hotkeys: HotkeyConfig) -> Self {
+        self.hotkeys = Some(hotkeys);
+        self
+    }
+
+    /// Run hook scripts around games, global ones before per-system ones
+    pub fn with_hooks(
+        mut self,
+        hooks: HookConfig,
+        system_hooks: HashMap<String, HookConfig>,
+    ) -> Self {
+        self.hooks = hooks;
+        self.system_hooks = system_hooks;
+        self
+    }
+
+    /// Set shader preset settings
+    pub fn with_shaders(mut self, shaders: ShaderSettings) -> Self {
+        self.shaders = shaders;
+        self
+    }
+
+    /// Get shader preset settings
+    pub fn shaders(&self) -> &ShaderSettings {
+        &self.shaders
+    }
+
+    /// Set the device profile
+    pub fn with_device(mut self, profile: DeviceProfile) -> Self {
+        self.device = Some(profile);
+        self
+    }
+
+    /// Set the directory for generated per-launch config
+    pub fn with_runtime_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.runtime_dir = dir.into();
+        self
+    }
+
+    /// Resolve video settings for a launch
+    pub fn video_settings(&self, config: &LaunchConfig, system: &GameSystem) -> VideoSettings {
+        config.video.unwrap_or_else(|| {
+            VideoSettings::resolve(system, self.display(config).as_ref(), &self.video_overrides)
+        })
+    }
+
+    /// Panel geometry for a launch
+    pub fn display(&self, config: &LaunchConfig) -> Option<DisplayGeometry> {
+        config
+            .display
+            .or_else(|| self.device.as_ref().map(DisplayGeometry::from_profile))
+    }
+
     /// Launch a game
     pub fn launch(&self, config: LaunchConfig) -> Result<LaunchResult, EmulatorError> {
         // Verify ROM exists
@@ -162,14 +537,26 @@ impl EmulatorLauncher {
             return Err(EmulatorError::RomNotFound(config.rom_path));
         }
 
-        // Determine system
+        // A per-game command replaces the core entirely
+        if let Some(template) = &config.command {
+            return self.launch_custom(
+                &CustomCommand::parse(template)?,
+                &config.rom_path,
+                config.system.as_ref(),
+                self.display(&config),
+            );
+        }
+
+        // Determine system (cloned, config is still borrowed below)
         let system = config
             .system
+            .clone()
             .ok_or_else(|| EmulatorError::ConfigError("Could not determine game system".into()))?;
 
         // Determine core
         let core_name = config
             .core
+            .clone()
             .unwrap_or_else(|| system.default_core().to_string());
 
         // Get paths based on 32/64 bit
@@ -191,6 +578,12 @@ impl EmulatorLauncher {
             }
         }
 
+        // The core would only fail to boot without its BIOS
+        let missing = self.check_bios(&system, &self.bios_dir);
+        if !missing.is_empty() {
+            return Err(EmulatorError::MissingBios(missing));
+        }
+
         // Build command
         let mut cmd = Command::new(retroarch_path);
 
@@ -213,6 +606,45 @@ impl EmulatorLauncher {
             cmd.arg("-v");
         }
 
+        // Per-launch overrides appended on top of the main config
+        let mut options = Vec::new();
+        if let Some(display) = self.display(&config) {
+            options.extend(display.retroarch_options());
+            cmd.envs(display.env());
+        }
+        options.extend(self.video_settings(&config, &system).retroarch_options());
+        options.extend(
+            self.shaders
+                .resolve(&system, config.shader.as_ref())
+                .retroarch_options(),
+        );
+        if let Some(hotkeys) = &self.hotkeys {
+            options.extend(retroarch_hotkeys(hotkeys));
+        }
+
+        // Core-wide remap for systems with an input profile
+        let retroarch_dir = cfg.parent().unwrap_or(Path::new("."));
+        if let Some(profile) = InputProfile::resolve(&system, &self.input_profiles) {
+            let remap_name = core_remap_name(&core_name, &retroarch_dir.join("cores"));
+            match profile.write_remap(&retroarch_dir.join("config/remaps"), &remap_name) {
+                Ok(path) => tracing::debug!("Wrote input remap {}", path.display()),
+                Err(e) => tracing::warn!("Failed to write input remap: {}", e),
+            }
+        }
+
+        // Core options and per-system overrides from config
+        if let Some(core_config) = self.core_configs.get(&core_name) {
+            let mut retroarch = RetroArchLauncher::new(retroarch_path, cores_dir);
+            retroarch.config_dir = retroarch_dir.to_path_buf();
+            if let Err(e) = retroarch.apply_core_options(&system, core_config) {
+                tracing::warn!("Failed to write core options: {}", e);
+            }
+        }
+
+        let append_path = append_config_path(&self.runtime_dir);
+        write_append_config(&append_path, &options)?;
+        cmd.arg("--appendconfig").arg(&append_path);
+
         // Extra arguments
         for arg in &config.extra_args {
             cmd.arg(arg);
@@ -221,10 +653,25 @@ impl EmulatorLauncher {
         // ROM path (must be last)
         cmd.arg(&config.rom_path);
 
-        // Set up stdio
+        // Set up stdio; output goes to the console unless capturing logs
         cmd.stdin(Stdio::null());
-        cmd.stdout(Stdio::piped());
-        cmd.stderr(Stdio::piped());
+        let log_path = match &self.log_dir {
+            Some(dir) => {
+                let path = launch_log_path(dir, &core_name);
+                fs::create_dir_all(dir)?;
+                cmd.stderr(create_log(&path)?);
+                Some(path)
+            }
+            None => None,
+        };
+
+        if let Some(limits) = ResourceLimits::resolve(&system, &self.resource_limits) {
+            tracing::debug!("Applying resource limits: {:?}", limits);
+            limits.apply(&mut cmd, &self.cgroup_root, "rexos-emulator");
+        }
+
+        let hooks = self.launch_hooks(&config.rom_path, Some(&system));
+        hooks.run_pre_launch(log_path.as_deref())?;
 
         // Launch
         tracing::info!(
@@ -243,9 +690,98 @@ impl EmulatorLauncher {
             child,
             pid,
             emulator: core_name,
+            log_path,
+            append_config: Some(append_path),
+            status: None,
+            stderr_tail: Vec::new(),
+            duration: None,
+            started: Instant::now(),
+            hooks,
+        })
+    }
+
+    /// Launch a game and wait for it to exit
+    pub fn launch_and_wait(&self, config: LaunchConfig) -> Result<ExitInfo, EmulatorError> {
+        let mut result = self.launch(config)?;
+        result.wait()?;
+        result
+            .exit_info()
+            .ok_or_else(|| EmulatorError::LaunchFailed("No exit status".into()))
+    }
+
+    /// Launch a game with its custom command
+    fn launch_custom(
+        &self,
+        command: &CustomCommand,
+        rom_path: &Path,
+        system: Option<&GameSystem>,
+        display: Option<DisplayGeometry>,
+    ) -> Result<LaunchResult, EmulatorError> {
+        let program = command.program(rom_path);
+        let name = program
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "custom".to_string());
+
+        let mut cmd = Command::new(&program);
+        cmd.args(command.args(rom_path));
+        if let Some(display) = display {
+            cmd.envs(display.env());
+        }
+        if let Some(dir) = rom_path.parent() {
+            cmd.current_dir(dir);
+        }
+
+        cmd.stdin(Stdio::null());
+        let log_path = match &self.log_dir {
+            Some(dir) => {
+                let path = launch_log_path(dir, &name);
+                fs::create_dir_all(dir)?;
+                cmd.stderr(create_log(&path)?);
+                Some(path)
+            }
+            None => None,
+        };
+
+        let hooks = self.launch_hooks(rom_path, system);
+        hooks.run_pre_launch(log_path.as_deref())?;
+
+        tracing::info!(
+            "Launching {} with custom command {}",
+            rom_path.display(),
+            program.display()
+        );
+
+        let child = cmd
+            .spawn()
+            .map_err(|e| EmulatorError::LaunchFailed(format!("Failed to spawn process: {}", e)))?;
+        let pid = child.id();
+
+        Ok(LaunchResult {
+            child,
+            pid,
+            emulator: name,
+            log_path,
+            append_config: None,
+            status: None,
+            stderr_tail: Vec::new(),
+            duration: None,
+            started: Instant::now(),
+            hooks,
         })
     }
 
+    /// Hook scripts for a launch
+    fn launch_hooks(&self, rom_path: &Path, system: Option<&GameSystem>) -> LaunchHooks {
+        LaunchHooks::resolve(rom_path, system, &self.hooks, &self.system_hooks)
+    }
+
+    /// Required BIOS files for a system missing from `bios_dir`, or not
+    /// matching a known good dump
+    pub fn check_bios(&self, system: &GameSystem, bios_dir: &Path) -> Vec<String> {
+        missing_bios(required_bios(system), bios_dir)
+    }
+
     /// Check if a core is available
     pub fn has_core(&self, core_name: &str, use_32bit: bool) -> bool {
         let cores_dir = if use_32bit {
@@ -299,6 +835,43 @@ impl EmulatorLauncher {
     }
 }
 
+/// Append config file for one launch, unique across processes and launches
+fn append_config_path(dir: &Path) -> PathBuf {
+    let launch = APPEND_CONFIG_COUNTER.fetch_add(1, Ordering::Relaxed);
+    dir.join(format!(
+        "rexos-append-{}-{}.cfg",
+        std::process::id(),
+        launch
+    ))
+}
+
+/// Log file for one launch, named by core and start time
+fn launch_log_path(dir: &Path, core_name: &str) -> PathBuf {
+    let started = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    dir.join(format!("{}-{}.log", core_name, started))
+}
+
+/// Create an empty log opened for appending, so hook output written
+/// before the emulator starts is kept
+fn create_log(path: &Path) -> Result<File, EmulatorError> {
+    File::create(path)?;
+    Ok(OpenOptions::new().append(true).open(path)?)
+}
+
+/// Write RetroArch config entries to a file for `--appendconfig`
+fn write_append_config(path: &Path, options: &[(&str, String)]) -> Result<(), EmulatorError> {
+    let content: String = options
+        .iter()
+        .map(|(key, value)| format!("{} = \"{}\"\n", key, value))
+        .collect();
+
+    fs::write(path, content)?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -382,6 +955,26 @@ mod tests {
         assert_eq!(config.system, Some(GameSystem::Nes));
     }
 
+    #[test]
+    fn test_launch_config_from_device_profile() {
+        let rgb30 = rexos_hal::mock::MockProfile::Rgb30.to_device_profile();
+        let config = LaunchConfig::from_device_profile(&rgb30).with_rom("/roms/snes/game.sfc");
+        assert_eq!(config.system, Some(GameSystem::Snes));
+
+        let display = config.display.unwrap();
+        assert_eq!((display.width, display.height), (720, 720));
+
+        // Square panels get integer scaling without a device on the launcher
+        let video = EmulatorLauncher::new().video_settings(&config, &GameSystem::Snes);
+        assert!(video.integer_scale);
+    }
+
+    #[test]
+    fn test_system_detection_disc_image() {
+        let config = LaunchConfig::for_rom("/roms/psx/game.cue");
+        assert_eq!(config.system, Some(GameSystem::Psx));
+    }
+
     #[test]
     fn test_system_detection_unknown() {
         let config = LaunchConfig::for_rom("/roms/unknown/game.xyz");
@@ -408,6 +1001,135 @@ mod tests {
         assert!(cores.is_empty());
     }
 
+    #[test]
+    fn test_launch_custom_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let rom = dir.path().join("game.bat");
+        fs::write(&rom, "").unwrap();
+
+        // No system or core needed; RetroArch paths are never touched
+        let launcher = EmulatorLauncher::with_paths(
+            "/nonexistent/retroarch",
+            "/nonexistent/retroarch32",
+            "/nonexistent/cores64",
+            "/nonexistent/cores32",
+        )
+        .with_log_capture(dir.path().join("logs"));
+
+        // Shell syntax is refused before anything runs
+        let config = LaunchConfig::for_rom(&rom).with_command("/bin/sh -c \"echo $0 >&2\" {rom}");
+        assert!(launcher.launch(config).is_err());
+
+        let config = LaunchConfig::for_rom(&rom).with_command("/bin/ls {rom_name}.bat");
+        let mut result = launcher.launch(config).unwrap();
+        assert_eq!(result.emulator, "ls");
+        assert!(result.wait().unwrap().success());
+    }
+
+    #[test]
+    fn test_launch_and_wait() {
+        let dir = tempfile::tempdir().unwrap();
+        let rom = dir.path().join("game.bat");
+        fs::write(&rom, "").unwrap();
+
+        let config = LaunchConfig::for_rom(&rom).with_command("/bin/sleep 0.2");
+        let exit = EmulatorLauncher::new().launch_and_wait(config).unwrap();
+        assert_eq!(exit.emulator, "sleep");
+        assert_eq!(exit.exit_code, Some(0));
+        assert_eq!(exit.reason, None);
+        assert!(!exit.crashed());
+        assert!(exit.duration >= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn test_launch_without_bios() {
+        let dir = tempfile::tempdir().unwrap();
+        let cores = dir.path().join("cores");
+        fs::create_dir_all(&cores).unwrap();
+        fs::write(cores.join("pcsx_rearmed_libretro.so"), "").unwrap();
+        let rom = dir.path().join("psx/game.cue");
+        fs::create_dir_all(rom.parent().unwrap()).unwrap();
+        fs::write(&rom, "").unwrap();
+
+        let launcher = EmulatorLauncher::with_paths(
+            "/nonexistent/retroarch",
+            "/nonexistent/retroarch32",
+            &cores,
+            &cores,
+        )
+        .with_bios_dir(dir.path().join("bios"));
+
+        match launcher.launch(LaunchConfig::for_rom(&rom)) {
+            Err(EmulatorError::MissingBios(files)) => assert_eq!(files, vec!["scph1001.bin"]),
+            other => panic!("expected missing BIOS, got {:?}", other.map(|r| r.pid)),
+        }
+    }
+
+    #[test]
+    fn test_failing_pre_launch_hook_stops_launch() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let rom = dir.path().join("game.bat");
+        fs::write(&rom, "").unwrap();
+
+        let script = |name: &str, body: &str| {
+            let path = dir.path().join(name);
+            fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+            path
+        };
+        let failing = script("mount.sh", "echo \"cannot mount $1\"\nexit 3");
+        let passing = script("perf.sh", "echo \"perf mode for $REXOS_HOOK\"");
+        let post = script("sync.sh", "touch \"$REXOS_ROM.synced\"");
+
+        let logs = dir.path().join("logs");
+        let launcher = EmulatorLauncher::with_paths(
+            "/nonexistent/retroarch",
+            "/nonexistent/retroarch32",
+            "/nonexistent/cores64",
+            "/nonexistent/cores32",
+        )
+        .with_log_capture(&logs)
+        .with_hooks(
+            HookConfig {
+                pre_launch: Some(failing),
+                post_exit: Some(post),
+            },
+            HashMap::new(),
+        );
+
+        // The command would create this file if the emulator were spawned
+        let spawned = dir.path().join("spawned");
+        let config = LaunchConfig::for_rom(&rom).with_command("/bin/touch {rom_dir}/spawned");
+        assert!(matches!(
+            launcher.launch(config),
+            Err(EmulatorError::LaunchFailed(_))
+        ));
+        assert!(!spawned.exists());
+        assert!(!dir.path().join("game.bat.synced").exists());
+
+        let log = fs::read_dir(&logs).unwrap().next().unwrap().unwrap().path();
+        let output = fs::read_to_string(log).unwrap();
+        assert!(output.contains(&format!("cannot mount {}", rom.display())));
+
+        let launcher = launcher.with_hooks(
+            HookConfig {
+                pre_launch: Some(passing),
+                post_exit: Some(dir.path().join("sync.sh")),
+            },
+            HashMap::new(),
+        );
+        let config = LaunchConfig::for_rom(&rom).with_command("/bin/touch {rom_dir}/spawned");
+        let mut result = launcher.launch(config).unwrap();
+        assert!(result.wait().unwrap().success());
+        assert!(spawned.exists());
+        assert!(dir.path().join("game.bat.synced").exists());
+
+        let output = fs::read_to_string(result.log_path.unwrap()).unwrap();
+        assert!(output.contains("perf mode for pre-launch"));
+    }
+
     #[test]
     fn test_launch_rom_not_found() {
         let launcher = EmulatorLauncher::new();
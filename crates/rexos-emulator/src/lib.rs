@@ -3,15 +3,33 @@
 //! Handles launching RetroArch cores and standalone emulators,
 //! based on ArkOS emulator management patterns.
 
+mod bios;
+mod custom;
+mod hooks;
+mod hotkeys;
 mod launcher;
+mod limits;
+mod metrics;
+mod remap;
 mod retroarch;
+mod shader;
 mod standalone;
+mod video;
 
-pub use launcher::{EmulatorLauncher, LaunchConfig, LaunchResult};
+pub use bios::{BiosRequirement, required_bios};
+pub use custom::CustomCommand;
+pub use hooks::LaunchHooks;
+pub use hotkeys::retroarch_hotkeys;
+pub use launcher::{EmulatorLauncher, ExitInfo, LaunchConfig, LaunchResult};
+pub use limits::ResourceLimits;
+pub use metrics::{MetricsSample, MetricsSummary, SAMPLE_INTERVAL, SessionMetrics};
+pub use remap::{InputProfile, RetroPad, core_remap_name};
 pub use retroarch::{CoreInfo, RetroArchLauncher};
-pub use standalone::{EmulatorInfo, StandaloneLauncher};
+pub use shader::{SHADER_NONE, ShaderChoice, ShaderSettings, list_presets};
+pub use standalone::{EmulatorInfo, SavePathStrategy, SaveRedirect, StandaloneLauncher};
+pub use video::{AspectRatio, DisplayGeometry, VideoSettings};
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -28,6 +46,9 @@ pub enum EmulatorError {
     #[error("Configuration error: {0}")]
     ConfigError(String),
 
+    #[error("Missing BIOS files: {}", .0.join(", "))]
+    MissingBios(Vec<String>),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
@@ -77,6 +98,9 @@ pub enum GameSystem {
     Custom(String),
 }
 
+/// Extensions used by more than one system (`m3u` lists multi-disc games)
+const AMBIGUOUS_EXTENSIONS: &[&str] = &["bin", "iso", "cue", "chd", "m3u"];
+
 impl GameSystem {
     /// Get system from file extension
     pub fn from_extension(ext: &str) -> Option<Self> {
@@ -103,6 +127,58 @@ impl GameSystem {
         }
     }
 
+    /// Get system from a ROM path
+    ///
+    /// Uses the extension where it's conclusive. Disc images, playlists and
+    /// `.bin` files are shared between systems, so for those the nearest directory
+    /// named after a system decides (`/roms/psx/game.cue` is a PlayStation
+    /// game), with `.bin` falling back to Genesis.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        if !AMBIGUOUS_EXTENSIONS.contains(&ext.as_str()) {
+            return Self::from_extension(&ext);
+        }
+
+        path.ancestors()
+            .skip(1)
+            .filter_map(|dir| dir.file_name()?.to_str())
+            .find_map(Self::from_short_name)
+            .or_else(|| Self::from_extension(&ext))
+    }
+
+    /// Get system from its short name (see [`GameSystem::short_name`])
+    pub fn from_short_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "nes" => Some(GameSystem::Nes),
+            "snes" => Some(GameSystem::Snes),
+            "n64" => Some(GameSystem::N64),
+            "gb" => Some(GameSystem::GameBoy),
+            "gbc" => Some(GameSystem::GameBoyColor),
+            "gba" => Some(GameSystem::GameBoyAdvance),
+            "nds" => Some(GameSystem::Nds),
+            "sms" => Some(GameSystem::MasterSystem),
+            "genesis" => Some(GameSystem::Genesis),
+            "segacd" => Some(GameSystem::SegaCd),
+            "saturn" => Some(GameSystem::Saturn),
+            "dreamcast" => Some(GameSystem::Dreamcast),
+            "gg" => Some(GameSystem::GameGear),
+            "psx" => Some(GameSystem::Psx),
+            "psp" => Some(GameSystem::Psp),
+            "mame" => Some(GameSystem::Mame),
+            "fbneo" => Some(GameSystem::FinalBurnNeo),
+            "amiga" => Some(GameSystem::Amiga),
+            "dos" => Some(GameSystem::Dos),
+            "atari2600" => Some(GameSystem::Atari2600),
+            "atari7800" => Some(GameSystem::Atari7800),
+            "lynx" => Some(GameSystem::Lynx),
+            "neogeo" => Some(GameSystem::NeoGeo),
+            "ngp" => Some(GameSystem::NeoGeoPocket),
+            "pce" => Some(GameSystem::PcEngine),
+            "wonderswan" => Some(GameSystem::WonderSwan),
+            _ => None,
+        }
+    }
+
     /// Get system short name (for directory paths)
     pub fn short_name(&self) -> &str {
         match self {
@@ -215,6 +291,46 @@ mod tests {
         assert_eq!(GameSystem::from_extension("unknown"), None);
     }
 
+    #[test]
+    fn test_system_from_path() {
+        let detect = |path: &str| GameSystem::from_path(Path::new(path));
+
+        assert_eq!(detect("/roms/psx/game.cue"), Some(GameSystem::Psx));
+        assert_eq!(detect("/roms/saturn/game.CHD"), Some(GameSystem::Saturn));
+        assert_eq!(
+            detect("/roms/dreamcast/Game (Disc 1)/game.iso"),
+            Some(GameSystem::Dreamcast)
+        );
+        assert_eq!(detect("/roms/segacd/game.bin"), Some(GameSystem::SegaCd));
+        assert_eq!(detect("/roms/psx/Game.m3u"), Some(GameSystem::Psx));
+
+        // Conclusive extensions win over the directory
+        assert_eq!(
+            detect("/roms/psx/game.gba"),
+            Some(GameSystem::GameBoyAdvance)
+        );
+
+        // Directory not conclusive
+        assert_eq!(detect("/roms/misc/game.bin"), Some(GameSystem::Genesis));
+        assert_eq!(detect("/roms/misc/game.cue"), None);
+        assert_eq!(detect("/roms/psx/game"), None);
+    }
+
+    #[test]
+    fn test_short_name_round_trip() {
+        for system in [
+            GameSystem::Psx,
+            GameSystem::GameGear,
+            GameSystem::NeoGeoPocket,
+        ] {
+            assert_eq!(
+                GameSystem::from_short_name(system.short_name()),
+                Some(system)
+            );
+        }
+        assert_eq!(GameSystem::from_short_name("pico8"), None);
+    }
+
     #[test]
     fn test_system_names() {
         assert_eq!(GameSystem::GameBoyAdvance.short_name(), "gba");
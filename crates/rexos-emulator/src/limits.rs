@@ -0,0 +1,199 @@
+//! Resource limits for emulator processes
+//!
+//! Opt-in per system. An address space rlimit is set in the child just
+//! before exec, and a cgroup (v2) can also cap memory and CPU, so a runaway
+//! emulator is OOM-killed on its own instead of taking the launcher or init
+//! with it.
+
+use crate::{EmulatorError, GameSystem};
+use rexos_config::ResourceLimitsConfig;
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::os::unix::io::AsRawFd;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const MIB: u64 = 1024 * 1024;
+
+/// cgroup v2 CPU period in microseconds
+const CPU_PERIOD_US: u64 = 100_000;
+
+/// Limits applied to a launched emulator
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// Address space limit in bytes
+    pub address_space: Option<u64>,
+    /// cgroup memory limit in bytes
+    pub memory: Option<u64>,
+    /// cgroup CPU limit in percent of one core
+    pub cpu_percent: Option<u32>,
+}
+
+impl ResourceLimits {
+    /// Convert from config values
+    pub fn from_config(config: &ResourceLimitsConfig) -> Self {
+        Self {
+            address_space: config.address_space_mb.map(|mb| mb * MIB),
+            memory: config.memory_mb.map(|mb| mb * MIB),
+            cpu_percent: config.cpu_percent,
+        }
+    }
+
+    /// Resolve limits for a system: its own entry, then `"default"`
+    pub fn resolve(
+        system: &GameSystem,
+        overrides: &HashMap<String, ResourceLimitsConfig>,
+    ) -> Option<Self> {
+        overrides
+            .get(system.short_name())
+            .or_else(|| overrides.get("default"))
+            .map(Self::from_config)
+            .filter(|limits| !limits.is_empty())
+    }
+
+    /// Check if no limit is set
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Check if a cgroup is needed
+    pub fn uses_cgroup(&self) -> bool {
+        self.memory.is_some() || self.cpu_percent.is_some()
+    }
+
+    /// Create (or reuse) a cgroup under `root` and write its limits
+    ///
+    /// Returns the cgroup directory.
+    pub fn setup_cgroup(&self, root: &Path, name: &str) -> Result<PathBuf, EmulatorError> {
+        let dir = root.join(name);
+        fs::create_dir_all(&dir)?;
+
+        let memory = match self.memory {
+            Some(bytes) => bytes.to_string(),
+            None => "max".to_string(),
+        };
+        fs::write(dir.join("memory.max"), memory)?;
+
+        let cpu = match self.cpu_percent {
+            Some(percent) => (u64::from(percent) * CPU_PERIOD_US / 100).to_string(),
+            None => "max".to_string(),
+        };
+        fs::write(dir.join("cpu.max"), format!("{} {}", cpu, CPU_PERIOD_US))?;
+
+        Ok(dir)
+    }
+
+    /// Apply the limits to a command before it is spawned
+    ///
+    /// A cgroup that can't be set up is logged and skipped; the rlimit is
+    /// still applied.
+    pub fn apply(&self, cmd: &mut Command, cgroup_root: &Path, name: &str) {
+        let procs = if self.uses_cgroup() {
+            let procs = self.setup_cgroup(cgroup_root, name).and_then(|dir| {
+                Ok(OpenOptions::new()
+                    .write(true)
+                    .open(dir.join("cgroup.procs"))?)
+            });
+            match procs {
+                Ok(file) => Some(file),
+                Err(e) => {
+                    tracing::warn!("Emulator cgroup unavailable: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let address_space = self.address_space;
+
+        // SAFETY: the hook runs in the forked child and only makes
+        // async-signal-safe calls (write, setrlimit) without allocating
+        unsafe {
+            cmd.pre_exec(move || {
+                if let Some(procs) = &procs {
+                    // Writing "0" moves the calling process, i.e. the child.
+                    // Failure leaves it in the launcher's cgroup, which is
+                    // no worse than running unlimited.
+                    libc::write(procs.as_raw_fd(), b"0".as_ptr().cast(), 1);
+                }
+
+                if let Some(bytes) = address_space {
+                    let limit = libc::rlimit {
+                        rlim_cur: bytes as libc::rlim_t,
+                        rlim_max: bytes as libc::rlim_t,
+                    };
+                    if libc::setrlimit(libc::RLIMIT_AS, &limit) != 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+
+                Ok(())
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_opt_in() {
+        let mut overrides = HashMap::new();
+        assert!(ResourceLimits::resolve(&GameSystem::Psp, &overrides).is_none());
+
+        overrides.insert(
+            "default".to_string(),
+            ResourceLimitsConfig {
+                address_space_mb: Some(768),
+                ..Default::default()
+            },
+        );
+        overrides.insert("nes".to_string(), ResourceLimitsConfig::default());
+
+        let limits = ResourceLimits::resolve(&GameSystem::Psp, &overrides).unwrap();
+        assert_eq!(limits.address_space, Some(768 * MIB));
+        assert!(!limits.uses_cgroup());
+
+        // An empty per-system entry turns limits off for that system
+        assert!(ResourceLimits::resolve(&GameSystem::Nes, &overrides).is_none());
+    }
+
+    #[test]
+    fn test_rlimit_applied_to_child() {
+        let limits = ResourceLimits {
+            address_space: Some(512 * MIB),
+            ..Default::default()
+        };
+
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg("ulimit -v");
+        limits.apply(&mut cmd, Path::new("/nonexistent"), "rexos-test");
+
+        let output = cmd.output().unwrap();
+        assert!(output.status.success());
+        // ulimit -v reports KiB
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "524288");
+    }
+
+    #[test]
+    fn test_cgroup_limits_written() {
+        let root = tempfile::tempdir().unwrap();
+        let limits = ResourceLimits {
+            memory: Some(256 * MIB),
+            cpu_percent: Some(150),
+            ..Default::default()
+        };
+
+        let dir = limits.setup_cgroup(root.path(), "emulator").unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join("memory.max")).unwrap(),
+            "268435456"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("cpu.max")).unwrap(),
+            "150000 100000"
+        );
+    }
+}
@@ -0,0 +1,224 @@
+//! Session metrics
+//!
+//! Optional, local-only sampling of temperature, CPU clock, governor and
+//! battery while a game runs, summarized when it exits (e.g. "avg 58°C,
+//! battery -12%/hr"). Samples are taken on a background thread every few
+//! seconds from sysfs, so the game isn't affected.
+
+use rexos_hal::PowerManager;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Default time between samples
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// One reading during a session
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetricsSample {
+    /// Time since the session started
+    pub elapsed: Duration,
+    /// CPU/SoC temperature in degrees Celsius
+    pub cpu_temp: Option<f32>,
+    /// CPU frequency in MHz
+    pub cpu_mhz: Option<u64>,
+    /// Battery charge in percent (None while charging)
+    pub battery: Option<u8>,
+    /// CPU governor name
+    pub governor: Option<String>,
+}
+
+/// Summary of a session's samples
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetricsSummary {
+    /// Session length
+    pub duration: Duration,
+    /// Number of samples taken
+    pub samples: usize,
+    /// Average temperature in degrees Celsius
+    pub avg_temp: Option<f32>,
+    /// Highest temperature in degrees Celsius
+    pub max_temp: Option<f32>,
+    /// Average CPU frequency in MHz
+    pub avg_cpu_mhz: Option<u64>,
+    /// Battery change in percent per hour (negative while draining)
+    pub battery_per_hour: Option<f32>,
+    /// Governor in use at the end of the session
+    pub governor: Option<String>,
+}
+
+impl MetricsSummary {
+    /// Summarize samples from a session of the given length
+    pub fn from_samples(samples: &[MetricsSample], duration: Duration) -> Self {
+        let temps: Vec<f32> = samples.iter().filter_map(|s| s.cpu_temp).collect();
+        let clocks: Vec<u64> = samples.iter().filter_map(|s| s.cpu_mhz).collect();
+
+        // Drain needs two readings a while apart to mean anything
+        let battery: Vec<(Duration, u8)> = samples
+            .iter()
+            .filter_map(|s| s.battery.map(|b| (s.elapsed, b)))
+            .collect();
+        let battery_per_hour = match (battery.first(), battery.last()) {
+            (Some(&(t0, b0)), Some(&(t1, b1))) if t1 > t0 + Duration::from_secs(60) => {
+                let hours = (t1 - t0).as_secs_f32() / 3600.0;
+                Some((b1 as f32 - b0 as f32) / hours)
+            }
+            _ => None,
+        };
+
+        Self {
+            duration,
+            samples: samples.len(),
+            avg_temp: (!temps.is_empty()).then(|| temps.iter().sum::<f32>() / temps.len() as f32),
+            max_temp: temps.iter().copied().reduce(f32::max),
+            avg_cpu_mhz: (!clocks.is_empty())
+                .then(|| clocks.iter().sum::<u64>() / clocks.len() as u64),
+            battery_per_hour,
+            governor: samples.iter().rev().find_map(|s| s.governor.clone()),
+        }
+    }
+}
+
+impl fmt::Display for MetricsSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.duration.as_secs();
+        let mut parts = vec![format!("{}m{:02}s", secs / 60, secs % 60)];
+
+        if let (Some(avg), Some(max)) = (self.avg_temp, self.max_temp) {
+            parts.push(format!("avg {:.0}°C (max {:.0}°C)", avg, max));
+        }
+        if let Some(rate) = self.battery_per_hour {
+            parts.push(format!("battery {:+.0}%/hr", rate));
+        }
+        match (&self.governor, self.avg_cpu_mhz) {
+            (Some(governor), Some(mhz)) => parts.push(format!("{} @ {} MHz avg", governor, mhz)),
+            (Some(governor), None) => parts.push(governor.clone()),
+            (None, Some(mhz)) => parts.push(format!("{} MHz avg", mhz)),
+            (None, None) => {}
+        }
+
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+/// Samples system metrics until finished
+pub struct SessionMetrics {
+    started: Instant,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<Vec<MetricsSample>>>,
+}
+
+impl SessionMetrics {
+    /// Start sampling every [`SAMPLE_INTERVAL`]
+    pub fn start() -> Self {
+        Self::with_interval(SAMPLE_INTERVAL)
+    }
+
+    /// Start sampling at a custom interval
+    pub fn with_interval(interval: Duration) -> Self {
+        let started = Instant::now();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread_stop = Arc::clone(&stop);
+        let handle = thread::Builder::new()
+            .name("rexos-metrics".to_string())
+            .spawn(move || {
+                let power = PowerManager::default();
+                let mut samples = Vec::new();
+
+                while !thread_stop.load(Ordering::Relaxed) {
+                    samples.push(sample(&power, started.elapsed()));
+                    // Unparked early by finish()
+                    thread::park_timeout(interval);
+                }
+                samples
+            })
+            .map_err(|e| tracing::warn!("Failed to start session metrics: {}", e))
+            .ok();
+
+        Self {
+            started,
+            stop,
+            handle,
+        }
+    }
+
+    /// Stop sampling and summarize the session
+    pub fn finish(mut self) -> MetricsSummary {
+        let duration = self.started.elapsed();
+        let samples = self.stop_thread();
+        MetricsSummary::from_samples(&samples, duration)
+    }
+
+    fn stop_thread(&mut self) -> Vec<MetricsSample> {
+        self.stop.store(true, Ordering::Relaxed);
+        let Some(handle) = self.handle.take() else {
+            return Vec::new();
+        };
+        handle.thread().unpark();
+        handle.join().unwrap_or_default()
+    }
+}
+
+impl Drop for SessionMetrics {
+    fn drop(&mut self) {
+        self.stop_thread();
+    }
+}
+
+/// Take one reading
+fn sample(power: &PowerManager, elapsed: Duration) -> MetricsSample {
+    let battery = if power.is_charger_connected() {
+        None
+    } else {
+        power.get_battery_info().ok().map(|info| info.percentage)
+    };
+
+    MetricsSample {
+        elapsed,
+        cpu_temp: power.get_cpu_temperature(),
+        cpu_mhz: power.get_cpu_frequency().map(|hz| hz / 1_000_000),
+        battery,
+        governor: power.get_governor().map(|g| g.as_str().to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(mins: u64, temp: f32, battery: u8) -> MetricsSample {
+        MetricsSample {
+            elapsed: Duration::from_secs(mins * 60),
+            cpu_temp: Some(temp),
+            cpu_mhz: Some(1416),
+            battery: Some(battery),
+            governor: Some("performance".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_summary() {
+        let samples = [at(0, 55.0, 80), at(15, 58.0, 77), at(30, 61.0, 74)];
+        let summary = MetricsSummary::from_samples(&samples, Duration::from_secs(30 * 60));
+
+        assert_eq!(summary.samples, 3);
+        assert_eq!(summary.avg_temp, Some(58.0));
+        assert_eq!(summary.max_temp, Some(61.0));
+        assert_eq!(summary.battery_per_hour, Some(-12.0));
+        assert_eq!(
+            summary.to_string(),
+            "30m00s, avg 58°C (max 61°C), battery -12%/hr, performance @ 1416 MHz avg"
+        );
+    }
+
+    #[test]
+    fn test_short_session_has_no_drain() {
+        let samples = [at(0, 50.0, 80)];
+        let summary = MetricsSummary::from_samples(&samples, Duration::from_secs(20));
+        assert_eq!(summary.battery_per_hour, None);
+        assert_eq!(MetricsSummary::default().to_string(), "0m00s");
+    }
+}
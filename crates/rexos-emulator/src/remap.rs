@@ -0,0 +1,308 @@
+//! Per-system input profiles
+//!
+//! Built-in control layouts for systems that need them (e.g. N64
+//! C-buttons on the right stick), merged with config overrides and written
+//! as a RetroArch core remap file (`.rmp`) before launch.
+
+use crate::{EmulatorError, GameSystem};
+use rexos_config::InputProfileConfig;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// RetroPad inputs, in RetroArch id order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum RetroPad {
+    B,
+    Y,
+    Select,
+    Start,
+    Up,
+    Down,
+    Left,
+    Right,
+    A,
+    X,
+    L,
+    R,
+    L2,
+    R2,
+    L3,
+    R3,
+    LeftXPlus,
+    LeftXMinus,
+    LeftYPlus,
+    LeftYMinus,
+    RightXPlus,
+    RightXMinus,
+    RightYPlus,
+    RightYMinus,
+}
+
+impl RetroPad {
+    /// All inputs in id order
+    pub fn all() -> &'static [RetroPad] {
+        &[
+            RetroPad::B,
+            RetroPad::Y,
+            RetroPad::Select,
+            RetroPad::Start,
+            RetroPad::Up,
+            RetroPad::Down,
+            RetroPad::Left,
+            RetroPad::Right,
+            RetroPad::A,
+            RetroPad::X,
+            RetroPad::L,
+            RetroPad::R,
+            RetroPad::L2,
+            RetroPad::R2,
+            RetroPad::L3,
+            RetroPad::R3,
+            RetroPad::LeftXPlus,
+            RetroPad::LeftXMinus,
+            RetroPad::LeftYPlus,
+            RetroPad::LeftYMinus,
+            RetroPad::RightXPlus,
+            RetroPad::RightXMinus,
+            RetroPad::RightYPlus,
+            RetroPad::RightYMinus,
+        ]
+    }
+
+    /// RetroArch input id
+    pub fn id(&self) -> u32 {
+        *self as u32
+    }
+
+    /// Name as used in config and remap keys (e.g. "l2", "r_y-")
+    pub fn name(&self) -> &'static str {
+        match self {
+            RetroPad::B => "b",
+            RetroPad::Y => "y",
+            RetroPad::Select => "select",
+            RetroPad::Start => "start",
+            RetroPad::Up => "up",
+            RetroPad::Down => "down",
+            RetroPad::Left => "left",
+            RetroPad::Right => "right",
+            RetroPad::A => "a",
+            RetroPad::X => "x",
+            RetroPad::L => "l",
+            RetroPad::R => "r",
+            RetroPad::L2 => "l2",
+            RetroPad::R2 => "r2",
+            RetroPad::L3 => "l3",
+            RetroPad::R3 => "r3",
+            RetroPad::LeftXPlus => "l_x+",
+            RetroPad::LeftXMinus => "l_x-",
+            RetroPad::LeftYPlus => "l_y+",
+            RetroPad::LeftYMinus => "l_y-",
+            RetroPad::RightXPlus => "r_x+",
+            RetroPad::RightXMinus => "r_x-",
+            RetroPad::RightYPlus => "r_y+",
+            RetroPad::RightYMinus => "r_y-",
+        }
+    }
+
+    /// Parse a config name
+    pub fn parse(name: &str) -> Option<RetroPad> {
+        let name = name.trim().to_lowercase();
+        RetroPad::all().iter().copied().find(|p| p.name() == name)
+    }
+
+    /// Check if this is an analog stick direction
+    pub fn is_analog(&self) -> bool {
+        *self >= RetroPad::LeftXPlus
+    }
+
+    /// Remap file key for player 1
+    fn remap_key(&self) -> String {
+        if self.is_analog() {
+            format!("input_player1_stk_{}", self.name())
+        } else {
+            format!("input_player1_btn_{}", self.name())
+        }
+    }
+}
+
+/// Input profile for a system
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputProfile {
+    /// RetroArch analog-to-dpad mode
+    pub analog_dpad_mode: Option<u8>,
+    /// Physical input to the RetroPad input the core receives
+    pub remaps: HashMap<RetroPad, RetroPad>,
+}
+
+impl InputProfile {
+    /// Built-in profile for a system, if it needs one
+    pub fn for_system(system: &GameSystem) -> Option<Self> {
+        match system {
+            // Left stick stays analog and L doubles as Z (L2 is awkward on
+            // most handhelds). The right stick entries pin the C-buttons to
+            // it even if an older remap moved them.
+            GameSystem::N64 => Some(Self {
+                analog_dpad_mode: Some(0),
+                remaps: HashMap::from([
+                    (RetroPad::L, RetroPad::L2),
+                    (RetroPad::RightXPlus, RetroPad::RightXPlus),
+                    (RetroPad::RightXMinus, RetroPad::RightXMinus),
+                    (RetroPad::RightYPlus, RetroPad::RightYPlus),
+                    (RetroPad::RightYMinus, RetroPad::RightYMinus),
+                ]),
+            }),
+            // The PSP nub maps to the left stick
+            GameSystem::Psp => Some(Self {
+                analog_dpad_mode: Some(0),
+                remaps: HashMap::new(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Apply user overrides from config
+    pub fn with_override(mut self, input: &InputProfileConfig) -> Self {
+        if let Some(mode) = input.analog_dpad_mode {
+            self.analog_dpad_mode = Some(mode);
+        }
+
+        for (from, to) in &input.remaps {
+            match (RetroPad::parse(from), RetroPad::parse(to)) {
+                (Some(from), Some(to)) => {
+                    self.remaps.insert(from, to);
+                }
+                _ => tracing::warn!("Unknown input remap in config: {} = {}", from, to),
+            }
+        }
+        self
+    }
+
+    /// Resolve the profile for a system: built-in, then config
+    pub fn resolve(
+        system: &GameSystem,
+        overrides: &HashMap<String, InputProfileConfig>,
+    ) -> Option<Self> {
+        let builtin = Self::for_system(system);
+
+        match overrides.get(system.short_name()) {
+            Some(input) => Some(builtin.unwrap_or_default().with_override(input)),
+            None => builtin,
+        }
+    }
+
+    /// Remap file contents
+    pub fn rmp_content(&self) -> String {
+        let mut lines = Vec::new();
+
+        if let Some(mode) = self.analog_dpad_mode {
+            lines.push(format!("input_player1_analog_dpad_mode = \"{}\"", mode));
+        }
+
+        let mut remaps: Vec<_> = self.remaps.iter().collect();
+        remaps.sort();
+        for (from, to) in remaps {
+            lines.push(format!("{} = \"{}\"", from.remap_key(), to.id()));
+        }
+
+        lines.into_iter().map(|line| line + "\n").collect()
+    }
+
+    /// Write the core-wide remap file under a RetroArch remaps directory
+    pub fn write_remap(
+        &self,
+        remaps_dir: &Path,
+        core_name: &str,
+    ) -> Result<PathBuf, EmulatorError> {
+        let dir = remaps_dir.join(core_name);
+        fs::create_dir_all(&dir)?;
+
+        let path = dir.join(format!("{}.rmp", core_name));
+        fs::write(&path, self.rmp_content())?;
+        Ok(path)
+    }
+}
+
+/// RetroArch's remap directory name for a core
+///
+/// RetroArch uses the core's library name, which differs from the file
+/// name; read it from the core info file when it isn't a known core.
+pub fn core_remap_name(core: &str, info_dir: &Path) -> String {
+    let known = match core {
+        "mupen64plus_next" => Some("Mupen64Plus-Next"),
+        "parallel_n64" => Some("ParaLLEl N64"),
+        "ppsspp" => Some("PPSSPP"),
+        "pcsx_rearmed" => Some("PCSX-ReARMed"),
+        "flycast" => Some("Flycast"),
+        _ => None,
+    };
+    if let Some(name) = known {
+        return name.to_string();
+    }
+
+    fs::read_to_string(info_dir.join(format!("{}_libretro.info", core)))
+        .ok()
+        .and_then(|contents| {
+            contents.lines().find_map(|line| {
+                let (key, value) = line.split_once('=')?;
+                (key.trim() == "corename").then(|| value.trim().trim_matches('"').to_string())
+            })
+        })
+        .unwrap_or_else(|| core.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_n64_remap_content() {
+        let profile = InputProfile::for_system(&GameSystem::N64).unwrap();
+        assert_eq!(
+            profile.rmp_content(),
+            "input_player1_analog_dpad_mode = \"0\"\n\
+             input_player1_btn_l = \"12\"\n\
+             input_player1_stk_r_x+ = \"20\"\n\
+             input_player1_stk_r_x- = \"21\"\n\
+             input_player1_stk_r_y+ = \"22\"\n\
+             input_player1_stk_r_y- = \"23\"\n"
+        );
+    }
+
+    #[test]
+    fn test_config_override() {
+        let mut overrides = HashMap::new();
+        overrides.insert(
+            "n64".to_string(),
+            InputProfileConfig {
+                analog_dpad_mode: None,
+                remaps: HashMap::from([("x".to_string(), "r_y-".to_string())]),
+            },
+        );
+
+        let profile = InputProfile::resolve(&GameSystem::N64, &overrides).unwrap();
+        assert_eq!(profile.analog_dpad_mode, Some(0));
+        assert_eq!(profile.remaps[&RetroPad::X], RetroPad::RightYMinus);
+        assert_eq!(profile.remaps[&RetroPad::L], RetroPad::L2);
+
+        assert!(InputProfile::resolve(&GameSystem::Snes, &overrides).is_none());
+    }
+
+    #[test]
+    fn test_write_remap() {
+        let dir = tempfile::tempdir().unwrap();
+        let profile = InputProfile::for_system(&GameSystem::Psp).unwrap();
+
+        let name = core_remap_name("ppsspp", dir.path());
+        let path = profile.write_remap(dir.path(), &name).unwrap();
+        assert_eq!(path, dir.path().join("PPSSPP/PPSSPP.rmp"));
+
+        fs::write(
+            dir.path().join("custom_libretro.info"),
+            "display_name = \"Custom\"\ncorename = \"Custom Core\"\n",
+        )
+        .unwrap();
+        assert_eq!(core_remap_name("custom", dir.path()), "Custom Core");
+        assert_eq!(core_remap_name("missing", dir.path()), "missing");
+    }
+}
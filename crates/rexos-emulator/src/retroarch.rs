@@ -1,10 +1,14 @@
 //! RetroArch-specific functionality
 
-use crate::EmulatorError;
+use crate::{EmulatorError, GameSystem, core_remap_name};
+use rexos_config::CoreConfig;
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+/// Core options file shared by all cores
+pub const CORE_OPTIONS_FILE: &str = "retroarch-core-options.cfg";
+
 /// Information about a RetroArch core
 #[derive(Debug, Clone)]
 pub struct CoreInfo {
@@ -162,6 +166,50 @@ impl RetroArchLauncher {
             .join(format!("{}.cfg", game_name))
     }
 
+    /// Get path to a core's override for games in a system's directory
+    pub fn system_override_path(&self, core_name: &str, system: &GameSystem) -> PathBuf {
+        let core = core_remap_name(core_name, &self.config_dir.join("cores"));
+        self.config_dir
+            .join("config")
+            .join(core)
+            .join(format!("{}.cfg", system.short_name()))
+    }
+
+    /// Merge a core's options and overrides into RetroArch's config
+    ///
+    /// Options go into the shared core options file. Overrides go into a
+    /// content directory override, so they apply to the core's games in
+    /// the system's ROM directory, and skip keys `retroarch.cfg` already
+    /// sets the same way. Only keys whose value differs are written; every
+    /// other line, including the user's own edits, is kept.
+    pub fn apply_core_options(
+        &self,
+        system: &GameSystem,
+        opts: &CoreConfig,
+    ) -> Result<(), EmulatorError> {
+        let options = opts
+            .options
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()));
+        merge_config(&self.config_dir.join(CORE_OPTIONS_FILE), options)?;
+
+        let overrides: Vec<_> = opts
+            .overrides
+            .iter()
+            .filter(|(key, value)| self.read_config(key).as_ref() != Some(*value))
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
+        if !overrides.is_empty() {
+            let core_name = opts
+                .library
+                .trim_end_matches(".so")
+                .trim_end_matches("_libretro");
+            merge_config(&self.system_override_path(core_name, system), overrides)?;
+        }
+
+        Ok(())
+    }
+
     /// Read a RetroArch config value
     pub fn read_config(&self, key: &str) -> Option<String> {
         let config_path = self.config_dir.join("retroarch.cfg");
@@ -244,10 +292,111 @@ impl RetroArchLauncher {
     }
 }
 
+/// Set `key = "value"` entries in a RetroArch config file
+///
+/// Existing keys are updated in place and new ones appended in key order.
+/// The file is only written if something changed.
+fn merge_config<'a>(
+    path: &Path,
+    entries: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> Result<(), EmulatorError> {
+    let contents = fs::read_to_string(path).unwrap_or_default();
+    let mut lines: Vec<String> = contents.lines().map(String::from).collect();
+
+    let mut entries: Vec<_> = entries.into_iter().collect();
+    entries.sort();
+
+    let mut changed = false;
+    for (key, value) in entries {
+        let entry = format!("{} = \"{}\"", key, value);
+        let existing = lines
+            .iter()
+            .position(|line| line.split_once('=').is_some_and(|(k, _)| k.trim() == key));
+
+        match existing {
+            Some(i) => {
+                let current = lines[i]
+                    .split_once('=')
+                    .map(|(_, v)| v.trim().trim_matches('"'));
+                if current != Some(value) {
+                    lines[i] = entry;
+                    changed = true;
+                }
+            }
+            None => {
+                lines.push(entry);
+                changed = true;
+            }
+        }
+    }
+
+    if changed {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut contents = lines.join("\n");
+        contents.push('\n');
+        fs::write(path, contents)?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_apply_core_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut retroarch = RetroArchLauncher::new("/usr/bin/retroarch", "/usr/lib/libretro");
+        retroarch.config_dir = dir.path().to_path_buf();
+
+        fs::write(
+            dir.path().join("retroarch.cfg"),
+            "rewind_enable = \"false\"\nvideo_smooth = \"false\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join(CORE_OPTIONS_FILE),
+            "# user edit\nsnes9x_overclock = \"10 MHz\"\nmgba_skip_bios = \"OFF\"\n",
+        )
+        .unwrap();
+
+        let opts = CoreConfig {
+            library: "mgba_libretro.so".to_string(),
+            name: "mGBA".to_string(),
+            extensions: vec![],
+            needs_bios: false,
+            bios_files: vec![],
+            options: HashMap::from([
+                ("mgba_skip_bios".to_string(), "ON".to_string()),
+                ("mgba_solar_sensor_level".to_string(), "0".to_string()),
+            ]),
+            overrides: HashMap::from([
+                ("rewind_enable".to_string(), "true".to_string()),
+                ("video_smooth".to_string(), "false".to_string()),
+            ]),
+        };
+        retroarch
+            .apply_core_options(&GameSystem::GameBoyAdvance, &opts)
+            .unwrap();
+
+        // Changed option updated in place, user lines kept
+        assert_eq!(
+            fs::read_to_string(dir.path().join(CORE_OPTIONS_FILE)).unwrap(),
+            "# user edit\nsnes9x_overclock = \"10 MHz\"\nmgba_skip_bios = \"ON\"\n\
+             mgba_solar_sensor_level = \"0\"\n"
+        );
+
+        // Only the override that differs from retroarch.cfg
+        let override_path = retroarch.system_override_path("mgba", &GameSystem::GameBoyAdvance);
+        assert_eq!(override_path, dir.path().join("config/mgba/gba.cfg"));
+        assert_eq!(
+            fs::read_to_string(&override_path).unwrap(),
+            "rewind_enable = \"true\"\n"
+        );
+    }
+
     #[test]
     fn test_core_info_default() {
         let info = CoreInfo {
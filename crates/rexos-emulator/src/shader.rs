@@ -0,0 +1,243 @@
+//! Shader preset selection
+//!
+//! Resolves the shader preset for a launch from the per-game choice, the
+//! per-system config, and built-in per-system defaults, and applies it via
+//! RetroArch's `video_shader` / `video_shader_enable` options.
+
+use crate::GameSystem;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Supported preset extensions
+pub const PRESET_EXTENSIONS: &[&str] = &["glslp", "slangp"];
+
+/// Config value meaning "no shader"
+pub const SHADER_NONE: &str = "none";
+
+/// Built-in defaults, relative to the shaders directory
+const SYSTEM_SHADER_DEFAULTS: &[(&str, &str)] = &[
+    ("nes", "crt/crt-pi.glslp"),
+    ("snes", "crt/crt-pi.glslp"),
+    ("genesis", "crt/crt-pi.glslp"),
+    ("sms", "crt/crt-pi.glslp"),
+    ("pce", "crt/crt-pi.glslp"),
+    ("psx", "crt/crt-pi.glslp"),
+];
+
+/// Shader choice for a launch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShaderChoice {
+    /// Explicitly disable shaders
+    Disabled,
+    /// Use a preset file
+    Preset(PathBuf),
+}
+
+impl ShaderChoice {
+    /// Parse a config value, resolving relative paths against `shaders_dir`
+    pub fn parse(value: &str, shaders_dir: &Path) -> Self {
+        let value = value.trim();
+        if value.is_empty() || value.eq_ignore_ascii_case(SHADER_NONE) {
+            return ShaderChoice::Disabled;
+        }
+
+        let path = Path::new(value);
+        if path.is_absolute() {
+            ShaderChoice::Preset(path.to_path_buf())
+        } else {
+            ShaderChoice::Preset(shaders_dir.join(path))
+        }
+    }
+
+    /// RetroArch config entries for this choice
+    ///
+    /// A preset that does not exist is not applied; shaders are disabled
+    /// instead so RetroArch doesn't fail to load it.
+    pub fn retroarch_options(&self) -> Vec<(&'static str, String)> {
+        match self {
+            ShaderChoice::Preset(path) if is_preset(path) && path.is_file() => vec![
+                ("video_shader_enable", "true".to_string()),
+                ("video_shader", path.to_string_lossy().to_string()),
+            ],
+            ShaderChoice::Preset(path) => {
+                tracing::warn!("Shader preset not found: {}", path.display());
+                vec![("video_shader_enable", "false".to_string())]
+            }
+            ShaderChoice::Disabled => vec![("video_shader_enable", "false".to_string())],
+        }
+    }
+}
+
+/// Shader settings from config
+#[derive(Debug, Clone)]
+pub struct ShaderSettings {
+    /// Whether shaders are enabled at all
+    pub enabled: bool,
+    /// Directory containing presets
+    pub shaders_dir: PathBuf,
+    /// Global default preset (used when a system has no default)
+    pub default_shader: Option<String>,
+    /// Per-system overrides, keyed by system short name
+    pub system_shaders: HashMap<String, String>,
+}
+
+impl Default for ShaderSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            shaders_dir: PathBuf::from("/home/ark/.config/retroarch/shaders"),
+            default_shader: None,
+            system_shaders: HashMap::new(),
+        }
+    }
+}
+
+impl ShaderSettings {
+    /// Build from the emulator config
+    pub fn from_config(config: &rexos_config::EmulatorConfig) -> Self {
+        Self {
+            enabled: config.shaders_enabled,
+            shaders_dir: config.shaders_dir.clone(),
+            default_shader: config.default_shader.clone(),
+            system_shaders: config.system_shaders.clone(),
+        }
+    }
+
+    /// Resolve the shader for a system, given an optional per-game choice
+    pub fn resolve(&self, system: &GameSystem, game: Option<&ShaderChoice>) -> ShaderChoice {
+        if !self.enabled {
+            return ShaderChoice::Disabled;
+        }
+
+        if let Some(choice) = game {
+            return choice.clone();
+        }
+
+        if let Some(value) = self.system_shaders.get(system.short_name()) {
+            return ShaderChoice::parse(value, &self.shaders_dir);
+        }
+
+        let builtin = SYSTEM_SHADER_DEFAULTS
+            .iter()
+            .find(|(name, _)| *name == system.short_name())
+            .map(|(_, preset)| *preset);
+
+        match builtin.or(self.default_shader.as_deref()) {
+            Some(value) => ShaderChoice::parse(value, &self.shaders_dir),
+            None => ShaderChoice::Disabled,
+        }
+    }
+
+    /// List available presets under the shaders directory
+    pub fn list_presets(&self) -> Vec<PathBuf> {
+        list_presets(&self.shaders_dir)
+    }
+}
+
+/// Check if a path has a preset extension
+pub fn is_preset(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| PRESET_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+/// Recursively list `.glslp`/`.slangp` presets in a directory
+pub fn list_presets(dir: &Path) -> Vec<PathBuf> {
+    let mut presets = Vec::new();
+    collect_presets(dir, &mut presets);
+    presets.sort();
+    presets
+}
+
+fn collect_presets(dir: &Path, presets: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_presets(&path, presets);
+        } else if is_preset(&path) {
+            presets.push(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_system_defaults() {
+        let settings = ShaderSettings::default();
+
+        let nes = settings.resolve(&GameSystem::Nes, None);
+        assert_eq!(
+            nes,
+            ShaderChoice::Preset(settings.shaders_dir.join("crt/crt-pi.glslp"))
+        );
+
+        let gba = settings.resolve(&GameSystem::GameBoyAdvance, None);
+        assert_eq!(gba, ShaderChoice::Disabled);
+    }
+
+    #[test]
+    fn test_overrides() {
+        let mut settings = ShaderSettings::default();
+        settings
+            .system_shaders
+            .insert("nes".to_string(), "none".to_string());
+        settings
+            .system_shaders
+            .insert("gba".to_string(), "/opt/lcd.slangp".to_string());
+
+        assert_eq!(
+            settings.resolve(&GameSystem::Nes, None),
+            ShaderChoice::Disabled
+        );
+        assert_eq!(
+            settings.resolve(&GameSystem::GameBoyAdvance, None),
+            ShaderChoice::Preset(PathBuf::from("/opt/lcd.slangp"))
+        );
+
+        // Per-game choice wins
+        let game = ShaderChoice::Preset(PathBuf::from("/opt/crt.glslp"));
+        assert_eq!(settings.resolve(&GameSystem::Nes, Some(&game)), game);
+
+        // Disabled globally
+        settings.enabled = false;
+        assert_eq!(
+            settings.resolve(&GameSystem::Nes, Some(&game)),
+            ShaderChoice::Disabled
+        );
+    }
+
+    #[test]
+    fn test_missing_preset_is_not_applied() {
+        let choice = ShaderChoice::Preset(PathBuf::from("/nonexistent/crt.glslp"));
+        assert_eq!(
+            choice.retroarch_options(),
+            vec![("video_shader_enable", "false".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_list_presets() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("crt")).unwrap();
+        fs::write(dir.path().join("crt/crt-pi.glslp"), "").unwrap();
+        fs::write(dir.path().join("lcd.slangp"), "").unwrap();
+        fs::write(dir.path().join("crt/crt-pi.glsl"), "").unwrap();
+
+        let presets = list_presets(dir.path());
+        assert_eq!(presets.len(), 2);
+
+        let choice = ShaderChoice::Preset(presets[0].clone());
+        let options = choice.retroarch_options();
+        assert_eq!(options[0], ("video_shader_enable", "true".to_string()));
+        assert_eq!(options[1].0, "video_shader");
+    }
+}
@@ -1,9 +1,52 @@
 //! Standalone emulator support
+//!
+//! Saves are redirected to the per-system saves directory where an
+//! emulator allows it, so they're backed up with RetroArch's.
+//!
+//! Emulators that need their arguments laid out differently can take an
+//! argument template instead of default arguments and a trailing ROM path,
+//! e.g. `--fullscreen --config "{config_dir}/ppsspp.ini" {rom}`. The
+//! template is split into arguments (honouring quotes) before placeholders
+//! are substituted, so a path is always one argument:
+//!
+//! - `{rom}`, `{rom_dir}`, `{rom_name}`: as for custom launch commands
+//! - `{system}`: the emulator's (first) system
+//! - `{config_dir}`: the emulator's config directory
 
 use crate::EmulatorError;
+use crate::custom::{check_placeholders, expand, rom_values, split_words};
+use std::ffi::{OsStr, OsString};
+use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::{Child, Command, Stdio};
 
+/// Placeholders accepted in an argument template
+const TEMPLATE_PLACEHOLDERS: &[&str] = &[
+    "{rom}",
+    "{rom_dir}",
+    "{rom_name}",
+    "{system}",
+    "{config_dir}",
+];
+
+/// How an emulator is pointed at the saves directory
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum SavePathStrategy {
+    /// Saves stay where the emulator puts them
+    #[default]
+    None,
+    /// Command line flag taking the directory; a trailing `=` joins them
+    /// (e.g. "--savepath=")
+    Flag(String),
+    /// Environment variable set to the directory
+    Env(String),
+    /// Directory the emulator writes saves to, replaced by a symlink
+    Symlink(PathBuf),
+}
+
+/// Arguments and environment variables that redirect an emulator's saves
+pub type SaveRedirect = (Vec<String>, Vec<(String, String)>);
+
 /// Information about a standalone emulator
 #[derive(Debug, Clone)]
 pub struct EmulatorInfo {
@@ -24,6 +67,13 @@ pub struct EmulatorInfo {
 
     /// Config directory
     pub config_dir: Option<PathBuf>,
+
+    /// How saves are redirected
+    pub save_strategy: SavePathStrategy,
+
+    /// Argument template split into words, replacing the default
+    /// arguments and trailing ROM path
+    pub args_template: Option<Vec<String>>,
 }
 
 impl EmulatorInfo {
@@ -37,6 +87,8 @@ impl EmulatorInfo {
             systems: Vec::new(),
             default_args: Vec::new(),
             config_dir: None,
+            save_strategy: SavePathStrategy::None,
+            args_template: None,
         }
     }
 
@@ -58,16 +110,143 @@ impl EmulatorInfo {
         self
     }
 
+    /// Lay out arguments with a template (see the module docs)
+    ///
+    /// Fails if the template doesn't place the ROM with `{rom}`, or uses
+    /// an unknown placeholder.
+    pub fn with_args_template(mut self, template: &str) -> Result<Self, EmulatorError> {
+        let words = split_words(template)?;
+        for word in &words {
+            check_placeholders(word, TEMPLATE_PLACEHOLDERS)?;
+        }
+
+        if !words.iter().any(|word| word.contains("{rom}")) {
+            return Err(EmulatorError::ConfigError(format!(
+                "Argument template for {} doesn't include {{rom}}",
+                self.name
+            )));
+        }
+
+        self.args_template = Some(words);
+        Ok(self)
+    }
+
+    /// Arguments from the template for a ROM, `None` without a template
+    pub fn template_args(&self, rom: &Path) -> Result<Option<Vec<OsString>>, EmulatorError> {
+        let Some(template) = &self.args_template else {
+            return Ok(None);
+        };
+
+        let config_dir = self.config_dir.as_deref();
+        if config_dir.is_none() && template.iter().any(|word| word.contains("{config_dir}")) {
+            return Err(EmulatorError::ConfigError(format!(
+                "Argument template for {} uses {{config_dir}}, but none is set",
+                self.name
+            )));
+        }
+
+        let mut values = rom_values(rom);
+        values.push((
+            "{system}",
+            OsStr::new(self.systems.first().map_or("", String::as_str)),
+        ));
+        values.push((
+            "{config_dir}",
+            config_dir.unwrap_or(Path::new("")).as_os_str(),
+        ));
+
+        Ok(Some(
+            template.iter().map(|word| expand(word, &values)).collect(),
+        ))
+    }
+
     /// Set config directory
     pub fn with_config_dir(mut self, dir: impl Into<PathBuf>) -> Self {
         self.config_dir = Some(dir.into());
         self
     }
+
+    /// Set how saves are redirected
+    pub fn with_save_strategy(mut self, strategy: SavePathStrategy) -> Self {
+        self.save_strategy = strategy;
+        self
+    }
+
+    /// Point the emulator at `saves_dir` before launch
+    ///
+    /// Creates the directory, and for [`SavePathStrategy::Symlink`] moves
+    /// existing saves into it before linking. Returns arguments and
+    /// environment to add to the command.
+    pub fn redirect_saves(&self, saves_dir: &Path) -> Result<SaveRedirect, EmulatorError> {
+        if self.save_strategy == SavePathStrategy::None {
+            return Ok((Vec::new(), Vec::new()));
+        }
+        fs::create_dir_all(saves_dir)?;
+        let dir = saves_dir.to_string_lossy().to_string();
+
+        match &self.save_strategy {
+            SavePathStrategy::None => Ok((Vec::new(), Vec::new())),
+            SavePathStrategy::Flag(flag) if flag.ends_with('=') => {
+                Ok((vec![format!("{}{}", flag, dir)], Vec::new()))
+            }
+            SavePathStrategy::Flag(flag) => Ok((vec![flag.clone(), dir], Vec::new())),
+            SavePathStrategy::Env(var) => Ok((Vec::new(), vec![(var.clone(), dir)])),
+            SavePathStrategy::Symlink(link) => {
+                link_saves(link, saves_dir)?;
+                Ok((Vec::new(), Vec::new()))
+            }
+        }
+    }
+}
+
+/// Replace an emulator's save directory with a symlink to `saves_dir`
+///
+/// Existing saves are moved over first; a save already present in
+/// `saves_dir` is never overwritten, and the original directory is left
+/// alone if anything can't be moved.
+fn link_saves(link: &Path, saves_dir: &Path) -> Result<(), EmulatorError> {
+    match fs::symlink_metadata(link) {
+        Ok(meta) if meta.file_type().is_symlink() => {
+            if fs::read_link(link)? == saves_dir {
+                return Ok(());
+            }
+            fs::remove_file(link)?;
+        }
+        Ok(meta) if meta.is_dir() => {
+            for entry in fs::read_dir(link)? {
+                let entry = entry?;
+                let target = saves_dir.join(entry.file_name());
+                if target.exists() || fs::rename(entry.path(), &target).is_err() {
+                    tracing::warn!(
+                        "Not redirecting saves: couldn't move {}",
+                        entry.path().display()
+                    );
+                    return Ok(());
+                }
+            }
+            fs::remove_dir(link)?;
+        }
+        Ok(_) => {
+            tracing::warn!("Not redirecting saves: {} is a file", link.display());
+            return Ok(());
+        }
+        Err(_) => {
+            if let Some(parent) = link.parent() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+    }
+
+    std::os::unix::fs::symlink(saves_dir, link)?;
+    tracing::info!("Linked {} to {}", link.display(), saves_dir.display());
+    Ok(())
 }
 
 /// Launcher for standalone emulators
 pub struct StandaloneLauncher {
     emulators: Vec<EmulatorInfo>,
+    /// Saves root, with a directory per system
+    saves_dir: Option<PathBuf>,
 }
 
 impl Default for StandaloneLauncher {
@@ -81,6 +260,7 @@ impl StandaloneLauncher {
     pub fn new() -> Self {
         let mut launcher = Self {
             emulators: Vec::new(),
+            saves_dir: None,
         };
 
         // Register default standalone emulators
@@ -97,7 +277,10 @@ impl StandaloneLauncher {
                     .with_display_name("PPSSPP")
                     .with_system("psp")
                     .with_args(vec!["--fullscreen".to_string()])
-                    .with_config_dir("/home/ark/.config/ppsspp"),
+                    .with_config_dir("/home/ark/.config/ppsspp")
+                    .with_save_strategy(SavePathStrategy::Symlink(PathBuf::from(
+                        "/home/ark/.config/ppsspp/PSP/SAVEDATA",
+                    ))),
             );
         }
 
@@ -107,7 +290,10 @@ impl StandaloneLauncher {
                 EmulatorInfo::new("drastic", "/opt/drastic/drastic")
                     .with_display_name("DraStic")
                     .with_system("nds")
-                    .with_config_dir("/opt/drastic"),
+                    .with_config_dir("/opt/drastic")
+                    .with_save_strategy(SavePathStrategy::Symlink(PathBuf::from(
+                        "/opt/drastic/backup",
+                    ))),
             );
         }
 
@@ -127,7 +313,8 @@ impl StandaloneLauncher {
                 EmulatorInfo::new("scummvm", "/usr/bin/scummvm")
                     .with_display_name("ScummVM")
                     .with_system("scummvm")
-                    .with_args(vec!["--fullscreen".to_string()]),
+                    .with_args(vec!["--fullscreen".to_string()])
+                    .with_save_strategy(SavePathStrategy::Flag("--savepath=".to_string())),
             );
         }
 
@@ -160,6 +347,12 @@ impl StandaloneLauncher {
         }
     }
 
+    /// Redirect saves to `<dir>/<system>`
+    pub fn with_saves_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.saves_dir = Some(dir.into());
+        self
+    }
+
     /// Register an emulator
     pub fn register(&mut self, info: EmulatorInfo) {
         tracing::debug!("Registered standalone emulator: {}", info.name);
@@ -211,10 +404,22 @@ impl StandaloneLauncher {
         }
 
         let mut cmd = Command::new(&info.path);
+        let template_args = info.template_args(rom_path)?;
 
-        // Add default args
-        for arg in &info.default_args {
-            cmd.arg(arg);
+        // Add default args, which a template replaces
+        if template_args.is_none() {
+            cmd.args(&info.default_args);
+        }
+
+        // Redirect saves to the emulator's (first) system
+        // Avoid if-let chains for MSRV 1.85 compatibility
+        #[allow(clippy::collapsible_if)]
+        if let Some(saves_dir) = &self.saves_dir {
+            if let Some(system) = info.systems.first() {
+                let (args, envs) = info.redirect_saves(&saves_dir.join(system))?;
+                cmd.args(args);
+                cmd.envs(envs);
+            }
         }
 
         // Add extra args
@@ -222,8 +427,11 @@ impl StandaloneLauncher {
             cmd.arg(arg);
         }
 
-        // Add ROM path
-        cmd.arg(rom_path);
+        // Add ROM path, or the template which places it
+        match template_args {
+            Some(args) => cmd.args(args),
+            None => cmd.arg(rom_path),
+        };
 
         // Configure stdio
         cmd.stdin(Stdio::null());
@@ -258,6 +466,85 @@ mod tests {
         assert!(info.systems.contains(&"test_system".to_string()));
     }
 
+    #[test]
+    fn test_args_template() {
+        let info = EmulatorInfo::new("ppsspp", "/usr/bin/PPSSPPSDL")
+            .with_system("psp")
+            .with_config_dir("/home/ark/.config/ppsspp")
+            .with_args_template(
+                r#"--fullscreen --config "{config_dir}/ppsspp.ini" --{system} {rom}"#,
+            )
+            .unwrap();
+        let rom = Path::new("/roms/psp/My Game.iso");
+
+        assert_eq!(
+            info.template_args(rom).unwrap().unwrap(),
+            vec![
+                OsString::from("--fullscreen"),
+                OsString::from("--config"),
+                OsString::from("/home/ark/.config/ppsspp/ppsspp.ini"),
+                OsString::from("--psp"),
+                OsString::from("/roms/psp/My Game.iso"),
+            ]
+        );
+
+        // No template: default args and ROM path
+        let plain = EmulatorInfo::new("dosbox", "/usr/bin/dosbox");
+        assert_eq!(plain.template_args(rom).unwrap(), None);
+    }
+
+    #[test]
+    fn test_args_template_rejects_invalid() {
+        let info = || EmulatorInfo::new("flycast", "/usr/bin/flycast");
+        assert!(info().with_args_template("--fullscreen {rom_dir}").is_err());
+        assert!(info().with_args_template("--bios {bios} {rom}").is_err());
+        assert!(info().with_args_template("\"{rom}").is_err());
+
+        // {config_dir} needs a config directory by launch time
+        let info = info().with_args_template("-c {config_dir} {rom}").unwrap();
+        assert!(info.template_args(Path::new("/roms/dc/game.chd")).is_err());
+    }
+
+    #[test]
+    fn test_redirect_saves_flag() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let saves = dir.join("saves/scummvm");
+
+        let info = EmulatorInfo::new("scummvm", "/usr/bin/scummvm")
+            .with_save_strategy(SavePathStrategy::Flag("--savepath=".to_string()));
+        let (args, envs) = info.redirect_saves(&saves).unwrap();
+        assert_eq!(args, vec![format!("--savepath={}", saves.display())]);
+        assert!(envs.is_empty());
+        assert!(saves.is_dir());
+
+        let info = info.with_save_strategy(SavePathStrategy::Env("SAVE_DIR".to_string()));
+        let (args, envs) = info.redirect_saves(&saves).unwrap();
+        assert!(args.is_empty());
+        assert_eq!(envs[0].0, "SAVE_DIR");
+    }
+
+    #[test]
+    fn test_redirect_saves_symlink_moves_existing() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let backup = dir.join("drastic/backup");
+        let saves = dir.join("saves/nds");
+        fs::create_dir_all(&backup).unwrap();
+        fs::write(backup.join("mario.dsv"), b"save").unwrap();
+
+        let info = EmulatorInfo::new("drastic", "/opt/drastic/drastic")
+            .with_save_strategy(SavePathStrategy::Symlink(backup.clone()));
+        info.redirect_saves(&saves).unwrap();
+
+        assert_eq!(fs::read_link(&backup).unwrap(), saves);
+        assert_eq!(fs::read(saves.join("mario.dsv")).unwrap(), b"save");
+
+        // Already linked: nothing to do
+        info.redirect_saves(&saves).unwrap();
+        assert_eq!(fs::read(backup.join("mario.dsv")).unwrap(), b"save");
+    }
+
     #[test]
     fn test_standalone_launcher() {
         let launcher = StandaloneLauncher::new();
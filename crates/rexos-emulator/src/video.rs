@@ -0,0 +1,329 @@
+//! Per-system video defaults
+//!
+//! Aspect ratio and integer scaling defaults for each system, applied to
+//! RetroArch through an appended config unless overridden in config.
+//!
+//! Emulators also get the panel's real geometry, since most assume a
+//! 640x480 screen and the RGB30 (720x720) and RG503 (960x544) aren't.
+
+use crate::GameSystem;
+use rexos_config::VideoConfig;
+use rexos_hal::DeviceProfile;
+use std::collections::HashMap;
+
+/// Display aspect ratio
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AspectRatio {
+    /// Let the core decide
+    Core,
+    /// 4:3 (most home consoles)
+    Ratio4x3,
+    /// 3:2 (Game Boy Advance)
+    Ratio3x2,
+    /// 10:9 (Game Boy, Game Gear)
+    Ratio10x9,
+    /// 1:1 (square displays)
+    Ratio1x1,
+    /// Stretch to fill the screen
+    Full,
+}
+
+impl AspectRatio {
+    /// Parse from a config string
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "core" | "auto" => Some(AspectRatio::Core),
+            "4:3" => Some(AspectRatio::Ratio4x3),
+            "3:2" => Some(AspectRatio::Ratio3x2),
+            "10:9" => Some(AspectRatio::Ratio10x9),
+            "1:1" | "square" => Some(AspectRatio::Ratio1x1),
+            "full" | "stretch" => Some(AspectRatio::Full),
+            _ => None,
+        }
+    }
+
+    /// Get config string
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AspectRatio::Core => "core",
+            AspectRatio::Ratio4x3 => "4:3",
+            AspectRatio::Ratio3x2 => "3:2",
+            AspectRatio::Ratio10x9 => "10:9",
+            AspectRatio::Ratio1x1 => "1:1",
+            AspectRatio::Full => "full",
+        }
+    }
+
+    /// Ratio as width / height, if fixed
+    pub fn value(&self) -> Option<f32> {
+        match self {
+            AspectRatio::Ratio4x3 => Some(4.0 / 3.0),
+            AspectRatio::Ratio3x2 => Some(3.0 / 2.0),
+            AspectRatio::Ratio10x9 => Some(10.0 / 9.0),
+            AspectRatio::Ratio1x1 => Some(1.0),
+            AspectRatio::Core | AspectRatio::Full => None,
+        }
+    }
+
+    /// RetroArch `aspect_ratio_index` value
+    pub fn retroarch_index(&self) -> u32 {
+        // Indices into RetroArch's aspect ratio table
+        match self {
+            AspectRatio::Ratio4x3 => 0,
+            AspectRatio::Ratio3x2 | AspectRatio::Ratio10x9 => 20, // "Config", uses video_aspect_ratio
+            AspectRatio::Ratio1x1 => 5,
+            AspectRatio::Core => 22,
+            AspectRatio::Full => 24,
+        }
+    }
+}
+
+/// Panel geometry emulators are told to use
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayGeometry {
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+    /// Refresh rate in Hz
+    pub refresh_rate: u32,
+    /// Square panel, letterboxing every system
+    pub square: bool,
+}
+
+impl DisplayGeometry {
+    /// Geometry of a device's display
+    pub fn from_profile(profile: &DeviceProfile) -> Self {
+        let display = &profile.display;
+        Self {
+            width: display.width,
+            height: display.height,
+            refresh_rate: display.refresh_rate,
+            square: profile.quirks.iter().any(|q| q == "square_display")
+                || display.width == display.height,
+        }
+    }
+
+    /// RetroArch config entries for fullscreen at the panel's resolution
+    pub fn retroarch_options(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("video_fullscreen", "true".to_string()),
+            ("video_fullscreen_x", self.width.to_string()),
+            ("video_fullscreen_y", self.height.to_string()),
+            (
+                "video_refresh_rate",
+                format!("{:.6}", self.refresh_rate as f32),
+            ),
+        ]
+    }
+
+    /// Environment for SDL emulators and ports
+    ///
+    /// `DISPLAY_WIDTH`/`DISPLAY_HEIGHT` follow the PortMaster convention.
+    pub fn env(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("DISPLAY_WIDTH", self.width.to_string()),
+            ("DISPLAY_HEIGHT", self.height.to_string()),
+            ("SDL_VIDEO_MINIMIZE_ON_FOCUS_LOSS", "0".to_string()),
+        ]
+    }
+}
+
+/// Video settings applied at launch
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VideoSettings {
+    /// Aspect ratio
+    pub aspect_ratio: AspectRatio,
+    /// Integer scaling
+    pub integer_scale: bool,
+}
+
+impl Default for VideoSettings {
+    fn default() -> Self {
+        Self {
+            aspect_ratio: AspectRatio::Core,
+            integer_scale: false,
+        }
+    }
+}
+
+/// Built-in defaults, keyed by system short name
+const SYSTEM_VIDEO_DEFAULTS: &[(&str, AspectRatio, bool)] = &[
+    ("nes", AspectRatio::Ratio4x3, false),
+    ("snes", AspectRatio::Ratio4x3, false),
+    ("n64", AspectRatio::Ratio4x3, false),
+    ("gb", AspectRatio::Ratio10x9, true),
+    ("gbc", AspectRatio::Ratio10x9, true),
+    ("gba", AspectRatio::Ratio3x2, true),
+    ("nds", AspectRatio::Core, false),
+    ("sms", AspectRatio::Ratio4x3, false),
+    ("genesis", AspectRatio::Ratio4x3, false),
+    ("segacd", AspectRatio::Ratio4x3, false),
+    ("saturn", AspectRatio::Ratio4x3, false),
+    ("dreamcast", AspectRatio::Ratio4x3, false),
+    ("gg", AspectRatio::Ratio10x9, true),
+    ("psx", AspectRatio::Ratio4x3, false),
+    ("psp", AspectRatio::Core, false),
+    ("mame", AspectRatio::Core, false),
+    ("fbneo", AspectRatio::Core, false),
+    ("pce", AspectRatio::Ratio4x3, false),
+    ("neogeo", AspectRatio::Ratio4x3, false),
+    ("ngp", AspectRatio::Core, true),
+    ("lynx", AspectRatio::Core, true),
+    ("wonderswan", AspectRatio::Core, true),
+];
+
+impl VideoSettings {
+    /// Built-in defaults for a system
+    pub fn for_system(system: &GameSystem) -> Self {
+        SYSTEM_VIDEO_DEFAULTS
+            .iter()
+            .find(|(name, _, _)| *name == system.short_name())
+            .map(|(_, aspect_ratio, integer_scale)| Self {
+                aspect_ratio: *aspect_ratio,
+                integer_scale: *integer_scale,
+            })
+            .unwrap_or_default()
+    }
+
+    /// Adjust for the device display
+    pub fn for_device(self, profile: &DeviceProfile) -> Self {
+        self.for_display(&DisplayGeometry::from_profile(profile))
+    }
+
+    /// Adjust for a display
+    ///
+    /// Square displays (RGB30) letterbox every system, so integer scaling
+    /// gives sharp pixels without wasting more space than the letterbox
+    /// already does.
+    pub fn for_display(mut self, display: &DisplayGeometry) -> Self {
+        if display.square {
+            self.integer_scale = true;
+        }
+        self
+    }
+
+    /// Apply user overrides from config
+    pub fn with_override(mut self, video: &VideoConfig) -> Self {
+        // Avoid if-let chains for MSRV 1.85 compatibility
+        #[allow(clippy::collapsible_if)]
+        if let Some(ref ratio) = video.aspect_ratio {
+            if let Some(parsed) = AspectRatio::parse(ratio) {
+                self.aspect_ratio = parsed;
+            } else {
+                tracing::warn!("Unknown aspect ratio in config: {}", ratio);
+            }
+        }
+        if let Some(integer_scale) = video.integer_scale {
+            self.integer_scale = integer_scale;
+        }
+        self
+    }
+
+    /// Resolve settings for a system: defaults, then display, then config
+    pub fn resolve(
+        system: &GameSystem,
+        display: Option<&DisplayGeometry>,
+        overrides: &HashMap<String, VideoConfig>,
+    ) -> Self {
+        let mut settings = Self::for_system(system);
+
+        if let Some(display) = display {
+            settings = settings.for_display(display);
+        }
+
+        if let Some(video) = overrides.get(system.short_name()) {
+            settings = settings.with_override(video);
+        }
+
+        settings
+    }
+
+    /// RetroArch config entries for these settings
+    pub fn retroarch_options(&self) -> Vec<(&'static str, String)> {
+        let mut options = vec![
+            (
+                "aspect_ratio_index",
+                self.aspect_ratio.retroarch_index().to_string(),
+            ),
+            ("video_scale_integer", self.integer_scale.to_string()),
+        ];
+
+        if let Some(value) = self.aspect_ratio.value() {
+            options.push(("video_aspect_ratio", format!("{:.6}", value)));
+        }
+
+        options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rexos_hal::mock::MockProfile;
+
+    #[test]
+    fn test_system_defaults() {
+        let gba = VideoSettings::for_system(&GameSystem::GameBoyAdvance);
+        assert_eq!(gba.aspect_ratio, AspectRatio::Ratio3x2);
+        assert!(gba.integer_scale);
+
+        let snes = VideoSettings::for_system(&GameSystem::Snes);
+        assert_eq!(snes.aspect_ratio, AspectRatio::Ratio4x3);
+        assert!(!snes.integer_scale);
+
+        let custom = VideoSettings::for_system(&GameSystem::Custom("pico8".into()));
+        assert_eq!(custom, VideoSettings::default());
+    }
+
+    #[test]
+    fn test_square_display_uses_integer_scale() {
+        let rgb30 = MockProfile::Rgb30.to_device_profile();
+        let snes = VideoSettings::for_system(&GameSystem::Snes).for_device(&rgb30);
+        assert!(snes.integer_scale);
+        assert_eq!(snes.aspect_ratio, AspectRatio::Ratio4x3);
+    }
+
+    #[test]
+    fn test_config_override_wins() {
+        let mut overrides = HashMap::new();
+        overrides.insert(
+            "gba".to_string(),
+            VideoConfig {
+                aspect_ratio: Some("full".into()),
+                integer_scale: Some(false),
+            },
+        );
+
+        let rgb30 = DisplayGeometry::from_profile(&MockProfile::Rgb30.to_device_profile());
+        let gba = VideoSettings::resolve(&GameSystem::GameBoyAdvance, Some(&rgb30), &overrides);
+        assert_eq!(gba.aspect_ratio, AspectRatio::Full);
+        assert!(!gba.integer_scale);
+    }
+
+    #[test]
+    fn test_display_geometry() {
+        let rg503 = DisplayGeometry::from_profile(&MockProfile::Rg503.to_device_profile());
+        assert_eq!((rg503.width, rg503.height), (960, 544));
+        assert!(!rg503.square);
+
+        let options = rg503.retroarch_options();
+        assert!(options.contains(&("video_fullscreen_x", "960".to_string())));
+        assert!(options.contains(&("video_fullscreen_y", "544".to_string())));
+        assert!(rg503.env().contains(&("DISPLAY_WIDTH", "960".to_string())));
+
+        let rgb30 = DisplayGeometry::from_profile(&MockProfile::Rgb30.to_device_profile());
+        assert!(rgb30.square);
+    }
+
+    #[test]
+    fn test_retroarch_options() {
+        let options = VideoSettings::for_system(&GameSystem::GameBoyAdvance).retroarch_options();
+        assert!(options.contains(&("aspect_ratio_index", "20".to_string())));
+        assert!(options.contains(&("video_scale_integer", "true".to_string())));
+        assert!(options.contains(&("video_aspect_ratio", "1.500000".to_string())));
+
+        // RetroArch's "1:1" entry, not "Square pixel"
+        assert_eq!(AspectRatio::parse("square").unwrap().retroarch_index(), 5);
+    }
+}
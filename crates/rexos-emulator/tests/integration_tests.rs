@@ -134,6 +134,110 @@ fn test_launch_validates_rom_path() {
     assert!(result.is_err());
 }
 
+#[test]
+fn test_launch_non_utf8_rom_path() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::PermissionsExt;
+
+    let env = EmulatorTestEnv::new();
+    env.create_core("mgba");
+
+    // Latin-1 filename, as written by some exFAT tools
+    let rom_path = env.roms_dir.join(OsStr::from_bytes(b"Caf\xe9.gba"));
+    fs::write(&rom_path, b"FAKE_ROM_DATA").unwrap();
+
+    // Fake RetroArch that succeeds only if the ROM argument names a real file
+    let retroarch = env.temp_dir.path().join("retroarch");
+    fs::write(
+        &retroarch,
+        "#!/bin/sh\nfor arg; do rom=\"$arg\"; done\ntest -f \"$rom\"\n",
+    )
+    .unwrap();
+    fs::set_permissions(&retroarch, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let launcher =
+        EmulatorLauncher::with_paths(&retroarch, &retroarch, &env.cores_dir, &env.cores_dir);
+
+    let config = LaunchConfig::for_rom(&rom_path);
+    assert_eq!(config.system, Some(GameSystem::GameBoyAdvance));
+
+    let mut result = launcher.launch(config).unwrap();
+    assert!(result.child.wait().unwrap().success());
+}
+
+#[test]
+fn test_launch_captures_crash_log() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let env = EmulatorTestEnv::new();
+    env.create_core("mgba");
+    let rom_path = env.create_rom("crash.gba");
+
+    // Fake RetroArch that logs and then segfaults
+    let retroarch = env.temp_dir.path().join("retroarch");
+    fs::write(
+        &retroarch,
+        "#!/bin/sh\necho \"[INFO] Loading core\" >&2\necho \"[ERROR] Bad opcode\" >&2\nkill -SEGV $$\n",
+    )
+    .unwrap();
+    fs::set_permissions(&retroarch, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let log_dir = env.temp_dir.path().join("logs");
+    let launcher =
+        EmulatorLauncher::with_paths(&retroarch, &retroarch, &env.cores_dir, &env.cores_dir)
+            .with_runtime_dir(env.temp_dir.path())
+            .with_log_capture(&log_dir);
+
+    let mut result = launcher.launch(LaunchConfig::for_rom(&rom_path)).unwrap();
+    let log_path = result.log_path.clone().unwrap();
+    assert!(log_path.starts_with(&log_dir));
+
+    assert!(!result.wait().unwrap().success());
+    assert!(result.failed());
+    assert_eq!(result.exit_code(), None);
+    assert_eq!(result.exit_reason().as_deref(), Some("segfault"));
+
+    let exit = result.exit_info().unwrap();
+    assert_eq!(exit.signal, Some(libc::SIGSEGV));
+    assert!(exit.crashed());
+    assert_eq!(
+        result.stderr_tail,
+        vec!["[INFO] Loading core", "[ERROR] Bad opcode"]
+    );
+    assert!(fs::read_to_string(log_path).unwrap().contains("Bad opcode"));
+}
+
+#[test]
+fn test_launch_uses_per_launch_append_config() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let env = EmulatorTestEnv::new();
+    env.create_core("mgba");
+    let rom_path = env.create_rom("game.gba");
+
+    let retroarch = env.temp_dir.path().join("retroarch");
+    fs::write(&retroarch, "#!/bin/sh\nexit 0\n").unwrap();
+    fs::set_permissions(&retroarch, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let launcher =
+        EmulatorLauncher::with_paths(&retroarch, &retroarch, &env.cores_dir, &env.cores_dir)
+            .with_runtime_dir(env.temp_dir.path());
+
+    let mut first = launcher.launch(LaunchConfig::for_rom(&rom_path)).unwrap();
+    let mut second = launcher.launch(LaunchConfig::for_rom(&rom_path)).unwrap();
+    let first_config = first.append_config.clone().unwrap();
+    let second_config = second.append_config.clone().unwrap();
+    assert_ne!(first_config, second_config);
+    assert!(first_config.starts_with(env.temp_dir.path()));
+    assert!(first_config.exists() && second_config.exists());
+
+    first.wait().unwrap();
+    second.wait().unwrap();
+    assert!(!first_config.exists());
+    assert!(!second_config.exists());
+}
+
 #[test]
 fn test_game_system_properties() {
     // Test display names
@@ -4,26 +4,113 @@
 
 use crate::DeviceError;
 use std::fs;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
+/// Jack switches exposed by older Android-style and extcon drivers
+const SWITCH_PATHS: &[&str] = &[
+    "/sys/class/switch/h2w/state",
+    "/sys/devices/platform/sound/jack",
+];
+
+/// extcon devices, which may also be USB or charger cables
+const EXTCON_DIR: &str = "/sys/class/extcon";
+
+/// Input devices, where ASoC jacks report `SW_HEADPHONE_INSERT`
+const INPUT_CLASS_DIR: &str = "/sys/class/input";
+
+/// Switch code for a plugged-in headphone jack
+const SW_HEADPHONE_INSERT: usize = 0x02;
+
+/// Size of the switch state bitmap (SW_MAX + 1 bits, rounded up)
+const SW_STATE_BYTES: usize = 8;
+
+/// `_IOR('E', 0x1b, len)`: read the current switch state bitmap
+const EVIOCGSW: libc::c_ulong =
+    (2 << 30) | ((SW_STATE_BYTES as libc::c_ulong) << 16) | (0x45 << 8) | 0x1b;
+
+/// How the UI volume percentage maps to the mixer level
+///
+/// Loudness is perceived logarithmically, so a linear mapping leaves the
+/// bottom half of the slider nearly silent.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum VolumeCurve {
+    /// Mixer level equals the UI percentage
+    Linear,
+    /// Low percentages get a larger share of the mixer range
+    #[default]
+    Logarithmic,
+    /// `(ui, mixer)` percentage points, interpolated linearly between them
+    Custom(Vec<(u8, u8)>),
+}
+
+impl VolumeCurve {
+    /// Mixer level (0-100) for a UI volume (0-100)
+    pub fn mixer_level(&self, volume: u8) -> u8 {
+        let volume = volume.min(100);
+        match self {
+            VolumeCurve::Linear => volume,
+            VolumeCurve::Logarithmic => {
+                let x = f32::from(volume) / 100.0;
+                ((1.0 + 9.0 * x).log10() * 100.0).round() as u8
+            }
+            VolumeCurve::Custom(points) => interpolate(points, volume),
+        }
+    }
+}
+
+/// Piecewise-linear lookup, clamped to the first and last points
+fn interpolate(points: &[(u8, u8)], volume: u8) -> u8 {
+    let mut points = points.to_vec();
+    points.sort_by_key(|(ui, _)| *ui);
+
+    let (Some(&first), Some(&last)) = (points.first(), points.last()) else {
+        return volume;
+    };
+    if volume <= first.0 {
+        return first.1.min(100);
+    }
+    if volume >= last.0 {
+        return last.1.min(100);
+    }
+
+    let level = points
+        .windows(2)
+        .find(|pair| volume <= pair[1].0)
+        .map(|pair| {
+            let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+            let t = f32::from(volume - x0) / f32::from(x1 - x0);
+            f32::from(y0) + t * (f32::from(y1) - f32::from(y0))
+        })
+        .unwrap_or(f32::from(last.1));
+    level.round().clamp(0.0, 100.0) as u8
+}
+
 /// Audio configuration
 #[derive(Debug, Clone)]
 pub struct AudioConfig {
+    /// Volume as shown in the UI, before the curve
     pub volume: u8,
+    pub volume_curve: VolumeCurve,
     pub sample_rate: u32,
     pub muted: bool,
     pub alsa_card: String,
     pub mixer_control: String,
+    /// Mixer enum that selects speaker or headphone output
+    pub path_control: String,
 }
 
 impl Default for AudioConfig {
     fn default() -> Self {
         Self {
             volume: 70,
+            volume_curve: VolumeCurve::default(),
             sample_rate: 48000,
             muted: false,
             alsa_card: "default".to_string(),
             mixer_control: "Playback".to_string(),
+            path_control: "Playback Path".to_string(),
         }
     }
 }
@@ -44,10 +131,32 @@ pub enum AudioProfile {
     Hdmi,
 }
 
+impl AudioProfile {
+    /// Built-in output for a headphone state
+    pub fn for_headphones(state: HeadphoneState) -> Self {
+        match state {
+            HeadphoneState::Connected => AudioProfile::Headphones,
+            HeadphoneState::Disconnected | HeadphoneState::Unknown => AudioProfile::Speaker,
+        }
+    }
+
+    /// Value of the codec's playback path control, `None` for outputs on
+    /// another card
+    fn playback_path(&self) -> Option<&'static str> {
+        match self {
+            AudioProfile::Speaker => Some("SPK"),
+            AudioProfile::Headphones => Some("HP"),
+            AudioProfile::Hdmi => None,
+        }
+    }
+}
+
 /// Audio manager
 pub struct AudioManager {
     config: AudioConfig,
     previous_volume: u8,
+    /// Headphone state at the last [`AudioManager::poll_headphone`]
+    headphones: HeadphoneState,
 }
 
 impl AudioManager {
@@ -56,19 +165,24 @@ impl AudioManager {
         let mut manager = Self {
             config,
             previous_volume: 70,
+            headphones: HeadphoneState::Unknown,
         };
 
         // Apply initial volume
         let volume = manager.config.volume;
         manager.set_volume(volume)?;
 
+        // Route to whatever is plugged in at startup
+        manager.poll_headphone();
+
         Ok(manager)
     }
 
-    /// Set volume (0-100)
+    /// Set volume (0-100), mapped to the mixer through the volume curve
     pub fn set_volume(&mut self, volume: u8) -> Result<(), DeviceError> {
         let volume = volume.min(100);
         self.config.volume = volume;
+        let level = self.config.volume_curve.mixer_level(volume);
 
         // Use amixer to set volume
         let result = Command::new("amixer")
@@ -77,18 +191,18 @@ impl AudioManager {
                 &self.config.alsa_card,
                 "sset",
                 &self.config.mixer_control,
-                &format!("{}%", volume),
+                &format!("{}%", level),
             ])
             .output();
 
         match result {
             Ok(output) => {
                 if output.status.success() {
-                    tracing::debug!("Volume set to {}%", volume);
+                    tracing::debug!("Volume set to {}% (mixer {}%)", volume, level);
                 } else {
                     // Fallback: try with 'Master' control
                     let _ = Command::new("amixer")
-                        .args(["sset", "Master", &format!("{}%", volume)])
+                        .args(["sset", "Master", &format!("{}%", level)])
                         .output();
                 }
             }
@@ -100,7 +214,7 @@ impl AudioManager {
         Ok(())
     }
 
-    /// Get current volume (0-100)
+    /// Get current volume (0-100) as set, not the mixer level
     pub fn get_volume(&self) -> u8 {
         self.config.volume
     }
@@ -174,26 +288,76 @@ impl AudioManager {
     }
 
     /// Get headphone connection state
+    ///
+    /// Checks jack switches in sysfs first, then input devices that
+    /// report `SW_HEADPHONE_INSERT`.
     pub fn headphone_state(&self) -> HeadphoneState {
-        // Check common headphone detection paths
-        let paths = [
-            "/sys/class/switch/h2w/state",
-            "/sys/devices/platform/sound/jack",
-            "/sys/class/extcon/extcon0/state",
-        ];
-
-        for path in &paths {
-            if let Ok(contents) = fs::read_to_string(path) {
-                let state = contents.trim();
-                if state == "1" || state.contains("HEADPHONE") {
-                    return HeadphoneState::Connected;
-                } else if state == "0" || state.is_empty() {
-                    return HeadphoneState::Disconnected;
-                }
-            }
+        let extcon = fs::read_dir(EXTCON_DIR)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|entry| entry.path().join("state"))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        let sysfs = SWITCH_PATHS
+            .iter()
+            .map(PathBuf::from)
+            .chain(extcon)
+            .filter_map(|path| fs::read_to_string(path).ok())
+            .map(|contents| parse_jack_state(&contents))
+            .find(|state| *state != HeadphoneState::Unknown);
+        if let Some(state) = sysfs {
+            return state;
+        }
+
+        headphone_switch_devices()
+            .iter()
+            .find_map(|device| read_headphone_switch(device))
+            .unwrap_or(HeadphoneState::Unknown)
+    }
+
+    /// Check the jack and follow it with the audio output
+    ///
+    /// Returns the new state when headphones were plugged in or pulled out
+    /// since the last poll, after switching output to them or back to the
+    /// speaker. Call this periodically.
+    pub fn poll_headphone(&mut self) -> Option<HeadphoneState> {
+        let state = headphone_change(self.headphones, self.headphone_state())?;
+        self.headphones = state;
+
+        tracing::info!("Headphones {:?}", state);
+        if let Err(e) = self.set_output(AudioProfile::for_headphones(state)) {
+            tracing::warn!("Failed to switch audio output: {}", e);
+        }
+        Some(state)
+    }
+
+    /// Switch the codec between speaker and headphone output
+    pub fn set_output(&mut self, profile: AudioProfile) -> Result<(), DeviceError> {
+        let Some(path) = profile.playback_path() else {
+            return Ok(());
+        };
+
+        let output = Command::new("amixer")
+            .args([
+                "-c",
+                &self.config.alsa_card,
+                "cset",
+                &format!("name={}", self.config.path_control),
+                path,
+            ])
+            .output()?;
+        if !output.status.success() {
+            return Err(DeviceError::InitializationFailed(format!(
+                "Failed to set {} to {}",
+                self.config.path_control, path
+            )));
         }
 
-        HeadphoneState::Unknown
+        tracing::debug!("Audio output set to {:?}", profile);
+        Ok(())
     }
 
     /// Check if headphones are connected
@@ -268,8 +432,96 @@ impl Default for AudioManager {
         Self::new(AudioConfig::default()).unwrap_or_else(|_| Self {
             config: AudioConfig::default(),
             previous_volume: 70,
+            headphones: HeadphoneState::Unknown,
+        })
+    }
+}
+
+/// The new state if the jack changed, ignoring unreadable states
+pub(crate) fn headphone_change(
+    previous: HeadphoneState,
+    current: HeadphoneState,
+) -> Option<HeadphoneState> {
+    (current != HeadphoneState::Unknown && current != previous).then_some(current)
+}
+
+/// Parse a switch (`1`/`0`) or extcon (`HEADPHONE=1`) state file
+///
+/// extcon devices that don't list a headphone cable are `Unknown`, so a
+/// USB or charger extcon isn't mistaken for the jack.
+fn parse_jack_state(contents: &str) -> HeadphoneState {
+    let contents = contents.trim();
+    let connected = |value: &str| {
+        if value.trim() == "0" {
+            HeadphoneState::Disconnected
+        } else {
+            HeadphoneState::Connected
+        }
+    };
+
+    if !contents.contains('=') {
+        return match contents {
+            "" | "0" => HeadphoneState::Disconnected,
+            "1" => HeadphoneState::Connected,
+            _ => HeadphoneState::Unknown,
+        };
+    }
+
+    contents
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .find(|(cable, _)| cable.trim().eq_ignore_ascii_case("headphone"))
+        .map(|(_, value)| connected(value))
+        .unwrap_or(HeadphoneState::Unknown)
+}
+
+/// Event devices whose switch capabilities include the headphone jack
+fn headphone_switch_devices() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(INPUT_CLASS_DIR) else {
+        return Vec::new();
+    };
+
+    let mut devices: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("event"))
+        .filter(|entry| {
+            fs::read_to_string(entry.path().join("device/capabilities/sw"))
+                .is_ok_and(|caps| capability_bit_set(&caps, SW_HEADPHONE_INSERT))
         })
+        .map(|entry| Path::new("/dev/input").join(entry.file_name()))
+        .collect();
+    devices.sort();
+    devices
+}
+
+/// Read the headphone switch of an event device with `EVIOCGSW`
+fn read_headphone_switch(device: &Path) -> Option<HeadphoneState> {
+    let file = fs::File::open(device).ok()?;
+    let mut bits = [0u8; SW_STATE_BYTES];
+    // SAFETY: EVIOCGSW writes at most SW_STATE_BYTES into the buffer
+    let ret = unsafe { libc::ioctl(file.as_raw_fd(), EVIOCGSW as _, bits.as_mut_ptr()) };
+    if ret < 0 {
+        return None;
     }
+
+    let inserted = bits[SW_HEADPHONE_INSERT / 8] & (1 << (SW_HEADPHONE_INSERT % 8)) != 0;
+    Some(if inserted {
+        HeadphoneState::Connected
+    } else {
+        HeadphoneState::Disconnected
+    })
+}
+
+/// Check a bit in a sysfs capability bitmap
+///
+/// The bitmap is hex words of `unsigned long`, most significant first.
+fn capability_bit_set(caps: &str, bit: usize) -> bool {
+    let bits_per_word = usize::BITS as usize;
+    caps.split_whitespace()
+        .rev()
+        .nth(bit / bits_per_word)
+        .and_then(|word| usize::from_str_radix(word, 16).ok())
+        .is_some_and(|word| word & (1 << (bit % bits_per_word)) != 0)
 }
 
 #[cfg(test)]
@@ -284,11 +536,81 @@ mod tests {
         assert!(!config.muted);
     }
 
+    #[test]
+    fn test_parse_jack_state() {
+        assert_eq!(parse_jack_state("1\n"), HeadphoneState::Connected);
+        assert_eq!(parse_jack_state("0\n"), HeadphoneState::Disconnected);
+        assert_eq!(
+            parse_jack_state("HEADPHONE=1\nMICROPHONE=0\n"),
+            HeadphoneState::Connected
+        );
+        assert_eq!(
+            parse_jack_state("HEADPHONE=0\nMICROPHONE=0\n"),
+            HeadphoneState::Disconnected
+        );
+        // A USB extcon says nothing about the jack
+        assert_eq!(
+            parse_jack_state("USB=1\nUSB-HOST=0\n"),
+            HeadphoneState::Unknown
+        );
+    }
+
+    #[test]
+    fn test_capability_bit_set() {
+        assert!(capability_bit_set("4\n", SW_HEADPHONE_INSERT));
+        assert!(capability_bit_set("14", SW_HEADPHONE_INSERT));
+        assert!(!capability_bit_set("10", SW_HEADPHONE_INSERT));
+        assert!(!capability_bit_set("0", SW_HEADPHONE_INSERT));
+        assert!(!capability_bit_set("", SW_HEADPHONE_INSERT));
+    }
+
+    #[test]
+    fn test_headphone_change() {
+        use HeadphoneState::*;
+        assert_eq!(headphone_change(Unknown, Connected), Some(Connected));
+        assert_eq!(
+            headphone_change(Connected, Disconnected),
+            Some(Disconnected)
+        );
+        assert_eq!(headphone_change(Connected, Connected), None);
+        // A failed read doesn't flip the output
+        assert_eq!(headphone_change(Connected, Unknown), None);
+        assert_eq!(
+            AudioProfile::for_headphones(Connected),
+            AudioProfile::Headphones
+        );
+        assert_eq!(AudioProfile::for_headphones(Unknown), AudioProfile::Speaker);
+    }
+
+    #[test]
+    fn test_volume_curve() {
+        assert_eq!(VolumeCurve::Linear.mixer_level(30), 30);
+
+        let log = VolumeCurve::Logarithmic;
+        assert_eq!(log.mixer_level(0), 0);
+        assert_eq!(log.mixer_level(100), 100);
+        assert_eq!(log.mixer_level(200), 100);
+        // The bottom half of the slider covers most of the mixer range
+        assert!(log.mixer_level(50) > 70);
+        assert!((0..100).all(|v| log.mixer_level(v) <= log.mixer_level(v + 1)));
+
+        let custom = VolumeCurve::Custom(vec![(100, 90), (0, 10), (50, 70)]);
+        assert_eq!(custom.mixer_level(0), 10);
+        assert_eq!(custom.mixer_level(25), 40);
+        assert_eq!(custom.mixer_level(75), 80);
+        assert_eq!(custom.mixer_level(100), 90);
+        assert_eq!(VolumeCurve::Custom(Vec::new()).mixer_level(42), 42);
+    }
+
     #[test]
     fn test_volume_clamping() {
         let mut manager = AudioManager::default();
         manager.config.volume = 100;
         let _ = manager.volume_up(20);
         assert_eq!(manager.config.volume, 100);
+
+        // The UI value round-trips, not the mixer level
+        let _ = manager.set_volume(40);
+        assert_eq!(manager.get_volume(), 40);
     }
 }
@@ -20,6 +20,12 @@ pub enum DeviceError {
     #[error("Hardware initialization failed: {0}")]
     InitializationFailed(String),
 
+    #[error("Invalid frequency: {0}")]
+    InvalidFrequency(String),
+
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
@@ -389,7 +395,7 @@ impl Device {
             .collect(),
             analog_sticks: 2,
             battery_capacity: 4100,
-            quirks: vec!["square_display".into()],
+            quirks: vec!["square_display".into(), "leds".into()],
         }
     }
 
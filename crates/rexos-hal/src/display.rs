@@ -5,6 +5,11 @@
 use crate::DeviceError;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+/// Time between brightness steps while fading (about one frame)
+pub const BRIGHTNESS_STEP_INTERVAL: Duration = Duration::from_millis(16);
 
 /// Display configuration
 #[derive(Debug, Clone)]
@@ -50,6 +55,22 @@ impl Rotation {
         }
     }
 
+    /// Rotation for an angle in degrees clockwise
+    pub fn from_degrees(degrees: u32) -> Option<Self> {
+        match degrees {
+            0 => Some(Rotation::Normal),
+            90 => Some(Rotation::Rotate90),
+            180 => Some(Rotation::Rotate180),
+            270 => Some(Rotation::Rotate270),
+            _ => None,
+        }
+    }
+
+    /// Whether width and height trade places
+    pub fn swaps_axes(&self) -> bool {
+        matches!(self, Rotation::Rotate90 | Rotation::Rotate270)
+    }
+
     /// Get fbcon rotate value
     pub fn fbcon_value(&self) -> u8 {
         match self {
@@ -155,6 +176,22 @@ impl Display {
         Ok(())
     }
 
+    /// Fade to a brightness (0-255) over `duration`
+    ///
+    /// Blocks until the fade is done. [`set_brightness`](Self::set_brightness)
+    /// stays the instant path.
+    pub fn set_brightness_smooth(
+        &mut self,
+        target: u8,
+        duration: Duration,
+    ) -> Result<(), DeviceError> {
+        for level in brightness_steps(self.config.brightness, target, duration) {
+            self.set_brightness(level)?;
+            thread::sleep(BRIGHTNESS_STEP_INTERVAL);
+        }
+        self.set_brightness(target)
+    }
+
     /// Get current brightness (0-255)
     pub fn get_brightness(&self) -> u8 {
         self.config.brightness
@@ -197,10 +234,22 @@ impl Display {
         self.config.rotation
     }
 
+    /// Resolution as drawn, after rotation
+    pub fn rotated_resolution(&self) -> (u32, u32) {
+        rotated_resolution(self.resolution(), self.config.rotation)
+    }
+
     /// Set display rotation (requires framebuffer support)
+    ///
+    /// On square panels (e.g. RGB30) a quarter turn keeps the resolution;
+    /// only the picture turns.
     pub fn set_rotation(&mut self, rotation: Rotation) -> Result<(), DeviceError> {
         self.config.rotation = rotation;
 
+        if rotation.swaps_axes() && self.config.width == self.config.height {
+            tracing::debug!("Square display, resolution unchanged by rotation");
+        }
+
         // Try to set via fbcon (framebuffer console)
         let fbcon_rotate = Path::new("/sys/class/graphics/fb0/rotate");
         if fbcon_rotate.exists() {
@@ -296,6 +345,29 @@ impl Display {
     }
 }
 
+/// Intermediate levels for a fade, excluding the target itself
+///
+/// One step per [`BRIGHTNESS_STEP_INTERVAL`], but never more steps than
+/// there are levels between the two values.
+pub(crate) fn brightness_steps(from: u8, to: u8, duration: Duration) -> Vec<u8> {
+    let distance = (to as i32 - from as i32).unsigned_abs();
+    let steps = (duration.as_millis() / BRIGHTNESS_STEP_INTERVAL.as_millis()) as u32;
+    let steps = steps.min(distance);
+
+    (1..steps)
+        .map(|i| (from as i32 + (to as i32 - from as i32) * i as i32 / steps as i32) as u8)
+        .collect()
+}
+
+/// Swap width and height for quarter turns
+fn rotated_resolution((width, height): (u32, u32), rotation: Rotation) -> (u32, u32) {
+    if rotation.swaps_axes() {
+        (height, width)
+    } else {
+        (width, height)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -316,6 +388,51 @@ mod tests {
         assert_eq!(Rotation::Rotate270.degrees(), 270);
     }
 
+    #[test]
+    fn test_rotation_from_degrees() {
+        for rotation in [
+            Rotation::Normal,
+            Rotation::Rotate90,
+            Rotation::Rotate180,
+            Rotation::Rotate270,
+        ] {
+            assert_eq!(Rotation::from_degrees(rotation.degrees()), Some(rotation));
+        }
+        assert_eq!(Rotation::from_degrees(45), None);
+    }
+
+    #[test]
+    fn test_brightness_steps() {
+        let steps = brightness_steps(0, 255, Duration::from_millis(160));
+        assert_eq!(steps.len(), 9);
+        assert!(steps.windows(2).all(|w| w[0] < w[1]));
+        assert!(steps.iter().all(|&s| s > 0 && s < 255));
+
+        // Fading down, and never more steps than levels
+        let steps = brightness_steps(100, 97, Duration::from_secs(1));
+        assert_eq!(steps, vec![99, 98]);
+
+        assert!(brightness_steps(50, 50, Duration::from_secs(1)).is_empty());
+        assert!(brightness_steps(0, 255, Duration::ZERO).is_empty());
+    }
+
+    #[test]
+    fn test_rotated_resolution() {
+        assert_eq!(
+            rotated_resolution((640, 480), Rotation::Rotate90),
+            (480, 640)
+        );
+        assert_eq!(
+            rotated_resolution((640, 480), Rotation::Rotate180),
+            (640, 480)
+        );
+        // Square panels keep their resolution
+        assert_eq!(
+            rotated_resolution((720, 720), Rotation::Rotate270),
+            (720, 720)
+        );
+    }
+
     #[test]
     fn test_rotation_fbcon() {
         assert_eq!(Rotation::Normal.fbcon_value(), 0);
@@ -4,10 +4,39 @@
 //! Supports both GPIO buttons and USB/Bluetooth controllers.
 
 use crate::DeviceError;
-use std::collections::HashMap;
+use crate::rumble::{self, RumbleDevice};
+use rexos_config::InputConfig;
+use std::collections::{HashMap, HashSet};
 use std::fs::{self, File};
-use std::io::{Read, Seek, SeekFrom};
+use std::io::Read;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Size of one evdev event as read from a device
+const EVENT_SIZE: usize = std::mem::size_of::<InputEvent>();
+
+/// Events read from a device per `read` call
+const EVENTS_PER_READ: usize = 64;
+
+/// Size of the key state bitmap (KEY_MAX + 1 bits)
+const KEY_STATE_BYTES: usize = 0x300 / 8;
+
+/// `_IOR('E', 0x18, len)`: read the current key state bitmap
+const EVIOCGKEY: libc::c_ulong =
+    (2 << 30) | ((KEY_STATE_BYTES as libc::c_ulong) << 16) | (0x45 << 8) | 0x18;
+
+/// Buttons a remap must leave reachable to move around and back out of
+/// menus
+const NAVIGATION_BUTTONS: &[Button] = &[
+    Button::Up,
+    Button::Down,
+    Button::Left,
+    Button::Right,
+    Button::A,
+    Button::B,
+];
 
 /// Gamepad buttons
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -77,6 +106,20 @@ impl Button {
             Button::Home => "home",
         }
     }
+
+    /// Check if holding the button repeats it (directions and page buttons)
+    pub fn repeats(&self) -> bool {
+        matches!(
+            self,
+            Button::Up | Button::Down | Button::Left | Button::Right | Button::L1 | Button::R1
+        )
+    }
+
+    /// Parse a button name (as returned by `name()`)
+    pub fn from_name(name: &str) -> Option<Button> {
+        let name = name.trim().to_lowercase();
+        Button::all().iter().copied().find(|b| b.name() == name)
+    }
 }
 
 /// Analog stick state
@@ -96,6 +139,44 @@ impl AnalogStick {
     pub fn normalized(&self) -> (f32, f32) {
         (self.x as f32 / 32767.0, self.y as f32 / 32767.0)
     }
+
+    /// D-pad direction the stick is pushed past `deadzone`
+    ///
+    /// Only the dominant axis counts, so a slightly diagonal push still
+    /// moves in one direction.
+    pub fn direction(&self, deadzone: i16) -> Option<Button> {
+        let (x, y) = (i32::from(self.x), i32::from(self.y));
+        let deadzone = i32::from(deadzone);
+        if x.abs() < deadzone && y.abs() < deadzone {
+            None
+        } else if x.abs() > y.abs() {
+            Some(if x < 0 { Button::Left } else { Button::Right })
+        } else {
+            Some(if y < 0 { Button::Up } else { Button::Down })
+        }
+    }
+}
+
+/// Left stick standing in for the D-pad
+#[derive(Debug, Clone, Copy)]
+struct StickDpad {
+    enabled: bool,
+    /// Whether the device has a stick at all
+    available: bool,
+    held: Option<Button>,
+    /// `held` before the last poll, for edge detection
+    previous: Option<Button>,
+}
+
+impl Default for StickDpad {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            available: true,
+            held: None,
+            previous: None,
+        }
+    }
 }
 
 /// Input event types (from linux/input-event-codes.h)
@@ -126,6 +207,8 @@ pub struct InputDevice {
     pub name: String,
     pub is_gamepad: bool,
     pub has_analog: bool,
+    /// Supports force-feedback rumble
+    pub has_rumble: bool,
 }
 
 /// State of all inputs
@@ -138,13 +221,210 @@ pub struct InputState {
     pub r2_analog: i16,
 }
 
+/// Held-button repeat timing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RepeatSettings {
+    /// Delay before the first repeat
+    pub initial_delay: Duration,
+    /// Interval between the first repeats
+    pub interval: Duration,
+    /// Shortest interval reached while held
+    pub min_interval: Duration,
+    /// Factor applied to the interval after each repeat (1.0 = constant rate)
+    pub acceleration: f32,
+}
+
+impl Default for RepeatSettings {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(400),
+            interval: Duration::from_millis(150),
+            min_interval: Duration::from_millis(40),
+            acceleration: 0.85,
+        }
+    }
+}
+
+/// Press/repeat state machine for a held button
+///
+/// A new press fires at once. Held repeatable buttons fire again after the
+/// initial delay, then at an interval that shrinks by `acceleration` each
+/// repeat down to `min_interval`. Releasing resets the timing.
+#[derive(Debug, Clone)]
+pub struct KeyRepeat {
+    settings: RepeatSettings,
+    /// Held button and when it next fires
+    held: Option<(Button, Instant)>,
+    interval: Duration,
+}
+
+impl KeyRepeat {
+    /// Create with the given timing
+    pub fn new(settings: RepeatSettings) -> Self {
+        Self {
+            settings,
+            held: None,
+            interval: settings.interval,
+        }
+    }
+
+    /// Current timing
+    pub fn settings(&self) -> &RepeatSettings {
+        &self.settings
+    }
+
+    /// Feed the currently held button, returning it when a press should fire
+    pub fn update(&mut self, held: Option<Button>, now: Instant) -> Option<Button> {
+        let Some(button) = held else {
+            self.held = None;
+            return None;
+        };
+
+        match self.held {
+            Some((current, next_fire)) if current == button => {
+                if !button.repeats() || now < next_fire {
+                    return None;
+                }
+                self.held = Some((button, now + self.interval));
+                self.interval = self.next_interval();
+                Some(button)
+            }
+            _ => {
+                self.held = Some((button, now + self.settings.initial_delay));
+                self.interval = self.settings.interval;
+                Some(button)
+            }
+        }
+    }
+
+    /// Interval after one more repeat
+    fn next_interval(&self) -> Duration {
+        let acceleration = if self.settings.acceleration > 0.0 {
+            self.settings.acceleration
+        } else {
+            1.0
+        };
+        // Rounded to whole microseconds, Duration::mul_f32 drifts past
+        // exact intervals (200ms * 0.5 comes out just over 100ms)
+        let micros = (self.interval.as_micros() as f64 * f64::from(acceleration)).round();
+        Duration::from_micros(micros as u64).max(self.settings.min_interval)
+    }
+}
+
+impl Default for KeyRepeat {
+    fn default() -> Self {
+        Self::new(RepeatSettings::default())
+    }
+}
+
+/// Minimum time between two firings of the same combo
+///
+/// Worn contacts can drop a button for a frame while it is held, which
+/// would otherwise read as releasing and pressing the combo again.
+pub const COMBO_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// A registered button combination
+#[derive(Debug, Clone)]
+struct Combo {
+    id: String,
+    buttons: Vec<Button>,
+    held: bool,
+    last_fired: Option<Instant>,
+}
+
+/// Recognizes button combinations as single events
+///
+/// A combo fires once when its last button goes down and not again until
+/// it is released, so holding Select+Start doesn't repeat the action.
+/// When a combo and a longer one containing it go down together, only
+/// the longer one fires.
+#[derive(Debug, Clone, Default)]
+pub struct ComboRecognizer {
+    combos: Vec<Combo>,
+    /// Combos that fired on the last update
+    fired: Vec<String>,
+}
+
+impl ComboRecognizer {
+    /// Fire `id` when all of `buttons` are held, replacing any combo with
+    /// the same id
+    pub fn register(&mut self, id: impl Into<String>, buttons: &[Button]) {
+        let id = id.into();
+        self.combos.retain(|combo| combo.id != id);
+        if buttons.is_empty() {
+            return;
+        }
+        self.combos.push(Combo {
+            id,
+            buttons: buttons.to_vec(),
+            held: false,
+            last_fired: None,
+        });
+    }
+
+    /// Remove all combos
+    pub fn clear(&mut self) {
+        self.combos.clear();
+        self.fired.clear();
+    }
+
+    /// Feed the current button state, returning the combos that fired
+    pub fn update(&mut self, buttons: &HashMap<Button, bool>, now: Instant) -> &[String] {
+        let is_held = |button: &Button| buttons.get(button).copied().unwrap_or(false);
+
+        let mut rising = Vec::new();
+        for (index, combo) in self.combos.iter_mut().enumerate() {
+            let held = combo.buttons.iter().all(is_held);
+            let debounced = combo
+                .last_fired
+                .is_none_or(|t| now.duration_since(t) >= COMBO_DEBOUNCE);
+            if held && !combo.held && debounced {
+                rising.push(index);
+            }
+            combo.held = held;
+        }
+
+        // Select+R1+L1 going down shouldn't also fire Select+R1
+        let combos = &self.combos;
+        let shadowed = |index: &usize| {
+            rising.iter().any(|other| {
+                let (inner, outer) = (&combos[*index].buttons, &combos[*other].buttons);
+                outer.len() > inner.len() && inner.iter().all(|b| outer.contains(b))
+            })
+        };
+        let fired: Vec<usize> = rising.iter().copied().filter(|i| !shadowed(i)).collect();
+
+        self.fired.clear();
+        for index in fired {
+            self.combos[index].last_fired = Some(now);
+            self.fired.push(self.combos[index].id.clone());
+        }
+        &self.fired
+    }
+
+    /// Combos that fired on the last update
+    pub fn fired(&self) -> &[String] {
+        &self.fired
+    }
+}
+
 /// Manages input devices
 pub struct InputManager {
     devices: Vec<InputDevice>,
     device_files: Vec<File>,
+    rumble_devices: Vec<RumbleDevice>,
+    /// Keys and axes each device last reported away from rest, by path
+    held: HashMap<PathBuf, HashSet<(u16, u16)>>,
     state: InputState,
+    /// Button states before the last poll, for edge detection
+    previous: HashMap<Button, bool>,
     deadzone: i16,
     button_map: HashMap<u16, Button>,
+    repeat: KeyRepeat,
+    combos: ComboRecognizer,
+    stick_dpad: StickDpad,
+    /// Physical button -> the button it acts as
+    remap: HashMap<Button, Button>,
 }
 
 impl InputManager {
@@ -153,9 +433,16 @@ impl InputManager {
         let mut manager = Self {
             devices: Vec::new(),
             device_files: Vec::new(),
+            rumble_devices: Vec::new(),
+            held: HashMap::new(),
             state: InputState::default(),
+            previous: HashMap::new(),
             deadzone: 4096,
             button_map: Self::default_button_map(),
+            repeat: KeyRepeat::default(),
+            combos: ComboRecognizer::default(),
+            stick_dpad: StickDpad::default(),
+            remap: HashMap::new(),
         };
 
         // Initialize button states
@@ -181,6 +468,8 @@ impl InputManager {
     pub fn scan_devices(&mut self) -> Result<(), DeviceError> {
         self.devices.clear();
         self.device_files.clear();
+        self.rumble_devices.clear();
+        self.held.clear();
 
         let input_dir = Path::new("/dev/input");
         if !input_dir.exists() {
@@ -200,8 +489,23 @@ impl InputManager {
             // Probe device and check if it's a gamepad
             if let Ok(device) = self.probe_device(&path) {
                 if device.is_gamepad {
-                    if let Ok(file) = File::open(&path) {
+                    // Non-blocking, so poll can drain a device without stalling
+                    let opened = fs::OpenOptions::new()
+                        .read(true)
+                        .custom_flags(libc::O_NONBLOCK)
+                        .open(&path);
+                    if let Ok(file) = opened {
                         tracing::info!("Found gamepad: {} at {}", device.name, path.display());
+
+                        if device.has_rumble {
+                            match RumbleDevice::open(&path) {
+                                Ok(rumble) => self.rumble_devices.push(rumble),
+                                Err(e) => {
+                                    tracing::debug!("Rumble not usable on {}: {}", device.name, e)
+                                }
+                            }
+                        }
+
                         self.device_files.push(file);
                         self.devices.push(device);
                     }
@@ -242,11 +546,14 @@ impl InputManager {
                 || name.to_lowercase().contains("rg351")
                 || name.to_lowercase().contains("rg353"));
 
+        let has_rumble = rumble::has_force_feedback(&sysfs_name);
+
         Ok(InputDevice {
             path: path.to_path_buf(),
             name,
             is_gamepad,
             has_analog,
+            has_rumble,
         })
     }
 
@@ -279,43 +586,107 @@ impl InputManager {
     }
 
     /// Poll for input events (non-blocking)
+    ///
+    /// Only devices that `poll(2)` reports readable are read, and each is
+    /// drained until it would block. Events from several devices are
+    /// returned in timestamp order. Devices that were unplugged are
+    /// dropped; call [`InputManager::scan_devices`] to pick up new ones.
     pub fn poll(&mut self) -> Result<Vec<InputEvent>, DeviceError> {
+        self.begin_poll();
         let mut events = Vec::new();
 
-        for file in &mut self.device_files {
-            let mut buffer = [0u8; std::mem::size_of::<InputEvent>()];
-
-            // Use poll or select in production; here we just try reading
-            loop {
-                match file.read(&mut buffer) {
-                    Ok(size) if size == buffer.len() => {
-                        // SAFETY: InputEvent is repr(C) and the buffer is correctly sized
-                        let event: InputEvent =
-                            unsafe { std::ptr::read(buffer.as_ptr() as *const InputEvent) };
-                        events.push(event);
-                    }
-                    _ => break,
+        let mut fds: Vec<libc::pollfd> = self
+            .device_files
+            .iter()
+            .map(|file| libc::pollfd {
+                fd: file.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            })
+            .collect();
+
+        if !fds.is_empty() {
+            // SAFETY: fds points to fds.len() initialized pollfd structs
+            let ready = unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, 0) };
+            if ready < 0 {
+                let error = std::io::Error::last_os_error();
+                if error.kind() != std::io::ErrorKind::Interrupted {
+                    return Err(DeviceError::Io(error));
                 }
             }
+        }
+
+        let mut gone = Vec::new();
+        for (index, fd) in fds.iter().enumerate() {
+            let result = if fd.revents & libc::POLLIN != 0 {
+                let start = events.len();
+                let result = read_events(&mut self.device_files[index], &mut events);
+                let held = self.held.entry(self.devices[index].path.clone());
+                track_held(held.or_default(), &events[start..]);
+                result
+            } else if fd.revents & (libc::POLLERR | libc::POLLHUP | libc::POLLNVAL) != 0 {
+                Err(std::io::ErrorKind::BrokenPipe.into())
+            } else {
+                Ok(())
+            };
+            if let Err(e) = result {
+                tracing::debug!("Input device read failed: {}", e);
+                gone.push(index);
+            }
+        }
+        let mut released = Vec::new();
+        for index in gone.into_iter().rev() {
+            self.device_files.remove(index);
+            let device = self.devices.remove(index);
+            self.rumble_devices
+                .retain(|rumble| rumble.path() != device.path);
 
-            // Reset file position for next poll
-            let _ = file.seek(SeekFrom::End(0));
+            // Return what it held to rest, so its buttons don't stay down
+            let held = self.held.remove(&device.path).unwrap_or_default();
+            released.extend(held.into_iter().map(|(event_type, code)| InputEvent {
+                event_type,
+                code,
+                value: 0,
+                ..Default::default()
+            }));
+            tracing::info!("Gamepad removed: {}", device.name);
         }
 
+        // Interleave devices by when the kernel saw each event
+        events.sort_by_key(|event| (event.tv_sec, event.tv_usec));
+
         // Process events after collecting them (avoids borrow issue)
-        for event in &events {
+        for event in events.iter().chain(&released) {
             self.process_event(event);
         }
+        self.end_poll(Instant::now());
 
         Ok(events)
     }
 
+    /// Remember what was held before a poll's events are applied
+    fn begin_poll(&mut self) {
+        self.previous = self.state.buttons.clone();
+        self.stick_dpad.previous = self.stick_dpad.held;
+    }
+
+    /// Derive state that depends on all of a poll's events
+    fn end_poll(&mut self, now: Instant) {
+        self.stick_dpad.held = if self.stick_dpad.enabled && self.stick_dpad.available {
+            self.state.left_stick.direction(self.deadzone)
+        } else {
+            None
+        };
+        self.combos.update(&self.state.buttons, now);
+    }
+
     /// Process a raw input event
     fn process_event(&mut self, event: &InputEvent) {
         match event.event_type {
             // Key/Button event
             0x01 => {
                 if let Some(&button) = self.button_map.get(&event.code) {
+                    let button = self.logical(button);
                     self.state.buttons.insert(button, event.value != 0);
                 }
             }
@@ -334,13 +705,16 @@ impl InputManager {
                     // D-pad as axes (HAT)
                     0x10 => {
                         // ABS_HAT0X
-                        self.state.buttons.insert(Button::Left, event.value < 0);
-                        self.state.buttons.insert(Button::Right, event.value > 0);
+                        let (left, right) =
+                            (self.logical(Button::Left), self.logical(Button::Right));
+                        self.state.buttons.insert(left, event.value < 0);
+                        self.state.buttons.insert(right, event.value > 0);
                     }
                     0x11 => {
                         // ABS_HAT0Y
-                        self.state.buttons.insert(Button::Up, event.value < 0);
-                        self.state.buttons.insert(Button::Down, event.value > 0);
+                        let (up, down) = (self.logical(Button::Up), self.logical(Button::Down));
+                        self.state.buttons.insert(up, event.value < 0);
+                        self.state.buttons.insert(down, event.value > 0);
                     }
                     _ => {}
                 }
@@ -349,14 +723,95 @@ impl InputManager {
         }
     }
 
+    /// Read which buttons are currently held
+    ///
+    /// Unlike `poll`, this sees buttons that were already pressed before the
+    /// device was opened (e.g. held during boot) and never blocks.
+    pub fn sync_key_state(&mut self) -> Result<(), DeviceError> {
+        let mut held = HashMap::new();
+
+        for file in &self.device_files {
+            let mut bits = [0u8; KEY_STATE_BYTES];
+            // SAFETY: EVIOCGKEY writes at most KEY_STATE_BYTES into the buffer
+            let ret = unsafe { libc::ioctl(file.as_raw_fd(), EVIOCGKEY as _, bits.as_mut_ptr()) };
+            if ret < 0 {
+                return Err(DeviceError::Io(std::io::Error::last_os_error()));
+            }
+
+            for (&code, &button) in &self.button_map {
+                if key_bit_set(&bits, code) {
+                    held.insert(self.logical(button), true);
+                }
+            }
+        }
+
+        for &button in self.button_map.values() {
+            let button = self.logical(button);
+            self.state
+                .buttons
+                .insert(button, held.get(&button).copied().unwrap_or(false));
+        }
+
+        // Buttons already held aren't new presses
+        self.previous = self.state.buttons.clone();
+        self.stick_dpad.previous = self.stick_dpad.held;
+
+        Ok(())
+    }
+
     /// Get current input state
     pub fn state(&self) -> &InputState {
         &self.state
     }
 
     /// Check if a button is pressed
+    ///
+    /// Includes the direction synthesized from the left stick when
+    /// [`InputManager::set_analog_as_dpad`] is on.
     pub fn is_pressed(&self, button: Button) -> bool {
-        *self.state.buttons.get(&button).unwrap_or(&false)
+        *self.state.buttons.get(&button).unwrap_or(&false) || self.stick_dpad.held == Some(button)
+    }
+
+    /// Check if a button went down during the last poll
+    pub fn just_pressed(&self, button: Button) -> bool {
+        self.is_pressed(button) && !self.was_pressed(button)
+    }
+
+    /// Check if a button went up during the last poll
+    pub fn just_released(&self, button: Button) -> bool {
+        !self.is_pressed(button) && self.was_pressed(button)
+    }
+
+    /// Check if a button was held before the last poll
+    fn was_pressed(&self, button: Button) -> bool {
+        *self.previous.get(&button).unwrap_or(&false) || self.stick_dpad.previous == Some(button)
+    }
+
+    /// Button press to act on now, with held-button repeat
+    ///
+    /// Call after [`InputManager::poll`]. Action buttons fire only on the
+    /// poll they go down in, so a press spanning several ticks acts once;
+    /// held directions and page buttons repeat. When several buttons
+    /// qualify the first in [`Button::all`] order wins.
+    pub fn next_press(&mut self, now: Instant) -> Option<Button> {
+        let pressed = Button::all()
+            .iter()
+            .copied()
+            .find(|button| !button.repeats() && self.just_pressed(*button));
+        if pressed.is_some() {
+            return pressed;
+        }
+
+        let held = Button::all()
+            .iter()
+            .copied()
+            .find(|button| button.repeats() && self.is_pressed(*button));
+        self.repeat.update(held, now)
+    }
+
+    /// Set held-button repeat timing
+    pub fn set_repeat(&mut self, settings: RepeatSettings) {
+        self.repeat = KeyRepeat::new(settings);
     }
 
     /// Check if a button combination is pressed
@@ -364,6 +819,26 @@ impl InputManager {
         buttons.iter().all(|b| self.is_pressed(*b))
     }
 
+    /// Report `id` from [`InputManager::combos_fired`] when `buttons` go
+    /// down together
+    ///
+    /// Registering an id again replaces its buttons.
+    pub fn register_combo(&mut self, id: impl Into<String>, buttons: &[Button]) {
+        self.combos.register(id, buttons);
+    }
+
+    /// Remove all registered combos
+    pub fn clear_combos(&mut self) {
+        self.combos.clear();
+    }
+
+    /// Ids of the combos that went down during the last poll
+    ///
+    /// Each press of a combo is reported once, however long it is held.
+    pub fn combos_fired(&self) -> &[String] {
+        self.combos.fired()
+    }
+
     /// Get left analog stick state
     pub fn left_stick(&self) -> AnalogStick {
         self.state.left_stick
@@ -384,6 +859,30 @@ impl InputManager {
         self.deadzone = deadzone;
     }
 
+    /// Let the left stick press Up/Down/Left/Right
+    ///
+    /// A push past the deadzone reads as the matching D-pad button, so it
+    /// shows up in [`InputManager::is_pressed`] and repeats through
+    /// [`InputManager::next_press`] like a held D-pad. Does nothing on
+    /// devices without a stick (see [`InputManager::set_analog_sticks`]).
+    pub fn set_analog_as_dpad(&mut self, enabled: bool) {
+        self.stick_dpad.enabled = enabled;
+        if !enabled {
+            self.stick_dpad.held = None;
+        }
+    }
+
+    /// Set how many analog sticks the device has
+    ///
+    /// Take this from [`crate::DeviceProfile::analog_sticks`]. With none,
+    /// stick-as-D-pad stays off whatever the axes report.
+    pub fn set_analog_sticks(&mut self, count: u8) {
+        self.stick_dpad.available = count > 0;
+        if count == 0 {
+            self.stick_dpad.held = None;
+        }
+    }
+
     /// Get list of detected devices
     pub fn devices(&self) -> &[InputDevice] {
         &self.devices
@@ -393,6 +892,146 @@ impl InputManager {
     pub fn set_button_map(&mut self, map: HashMap<u16, Button>) {
         self.button_map = map;
     }
+
+    /// Create an input manager with the user's button remapping applied
+    pub fn from_config(config: &InputConfig) -> Result<Self, DeviceError> {
+        let mut manager = Self::new()?;
+        manager.set_remap(config)?;
+        Ok(manager)
+    }
+
+    /// Make physical buttons act as others, as set in `config`
+    ///
+    /// Fails, leaving the current remap in place, on an unknown button name
+    /// or a remap that leaves a D-pad direction, A or B with no physical
+    /// button.
+    pub fn set_remap(&mut self, config: &InputConfig) -> Result<(), DeviceError> {
+        self.remap = parse_remap(config)?;
+
+        // A button held across the change would otherwise stay down under
+        // its old name
+        for pressed in self.state.buttons.values_mut() {
+            *pressed = false;
+        }
+        self.previous = self.state.buttons.clone();
+        Ok(())
+    }
+
+    /// Current remapping, for saving back to the config
+    pub fn remap_config(&self) -> InputConfig {
+        InputConfig {
+            remap: self
+                .remap
+                .iter()
+                .map(|(physical, logical)| {
+                    (physical.name().to_string(), logical.name().to_string())
+                })
+                .collect(),
+        }
+    }
+
+    /// Button a physical button acts as
+    fn logical(&self, physical: Button) -> Button {
+        self.remap.get(&physical).copied().unwrap_or(physical)
+    }
+
+    /// Check if any device supports rumble
+    pub fn supports_rumble(&self) -> bool {
+        !self.rumble_devices.is_empty()
+    }
+
+    /// Rumble all capable devices
+    ///
+    /// `strength` ranges from 0.0 to 1.0. Does nothing if no device
+    /// supports force-feedback.
+    pub fn rumble(&mut self, strength: f32, duration: Duration) -> Result<(), DeviceError> {
+        for device in &mut self.rumble_devices {
+            device.rumble(strength, duration)?;
+        }
+        Ok(())
+    }
+
+    /// Stop rumble on all devices
+    pub fn stop_rumble(&mut self) -> Result<(), DeviceError> {
+        for device in &mut self.rumble_devices {
+            device.stop()?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse and check a remap table from the config
+fn parse_remap(config: &InputConfig) -> Result<HashMap<Button, Button>, DeviceError> {
+    let button = |name: &str| {
+        Button::from_name(name)
+            .ok_or_else(|| DeviceError::InvalidConfig(format!("unknown button \"{}\"", name)))
+    };
+
+    let mut remap = HashMap::new();
+    for (physical, logical) in &config.remap {
+        let (physical, logical) = (button(physical)?, button(logical)?);
+        if physical != logical {
+            remap.insert(physical, logical);
+        }
+    }
+
+    let logical = |physical: &Button| remap.get(physical).copied().unwrap_or(*physical);
+    for required in NAVIGATION_BUTTONS {
+        if !Button::all().iter().any(|b| logical(b) == *required) {
+            return Err(DeviceError::InvalidConfig(format!(
+                "remap leaves no button acting as {}",
+                required.name()
+            )));
+        }
+    }
+
+    Ok(remap)
+}
+
+/// Read everything queued on a non-blocking device
+fn read_events(file: &mut File, events: &mut Vec<InputEvent>) -> std::io::Result<()> {
+    let mut buffer = [0u8; EVENT_SIZE * EVENTS_PER_READ];
+    loop {
+        match file.read(&mut buffer) {
+            // End of file: the device is gone
+            Ok(0) => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+            Ok(size) => events.extend(parse_events(&buffer[..size])),
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => return Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Track the keys and axes a device holds away from rest
+fn track_held(held: &mut HashSet<(u16, u16)>, events: &[InputEvent]) {
+    for event in events {
+        // Key and absolute axis events
+        if event.event_type != 0x01 && event.event_type != 0x03 {
+            continue;
+        }
+        if event.value != 0 {
+            held.insert((event.event_type, event.code));
+        } else {
+            held.remove(&(event.event_type, event.code));
+        }
+    }
+}
+
+/// Decode whole events from bytes read off a device
+fn parse_events(bytes: &[u8]) -> impl Iterator<Item = InputEvent> + '_ {
+    bytes.chunks_exact(EVENT_SIZE).map(|chunk| {
+        // SAFETY: InputEvent is repr(C) plain data and chunk holds EVENT_SIZE
+        // bytes; the byte buffer may not be aligned for it
+        unsafe { std::ptr::read_unaligned(chunk.as_ptr() as *const InputEvent) }
+    })
+}
+
+/// Check a key in an `EVIOCGKEY` bitmap
+fn key_bit_set(bits: &[u8], code: u16) -> bool {
+    let code = code as usize;
+    bits.get(code / 8)
+        .is_some_and(|byte| byte & (1 << (code % 8)) != 0)
 }
 
 impl Default for InputManager {
@@ -400,9 +1039,16 @@ impl Default for InputManager {
         Self::new().unwrap_or_else(|_| Self {
             devices: Vec::new(),
             device_files: Vec::new(),
+            rumble_devices: Vec::new(),
+            held: HashMap::new(),
             state: InputState::default(),
+            previous: HashMap::new(),
             deadzone: 4096,
             button_map: Self::default_button_map(),
+            repeat: KeyRepeat::default(),
+            combos: ComboRecognizer::default(),
+            stick_dpad: StickDpad::default(),
+            remap: HashMap::new(),
         })
     }
 }
@@ -411,12 +1057,439 @@ impl Default for InputManager {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_key_repeat_accelerates_and_resets() {
+        let mut repeat = KeyRepeat::new(RepeatSettings {
+            initial_delay: Duration::from_millis(400),
+            interval: Duration::from_millis(200),
+            min_interval: Duration::from_millis(60),
+            acceleration: 0.5,
+        });
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let down = Some(Button::Down);
+
+        // Fires on press, then waits for the initial delay
+        assert_eq!(repeat.update(down, at(0)), down);
+        assert_eq!(repeat.update(down, at(100)), None);
+        assert_eq!(repeat.update(down, at(399)), None);
+        assert_eq!(repeat.update(down, at(400)), down);
+
+        // Intervals shrink 200 -> 100 -> 60 (floor)
+        assert_eq!(repeat.update(down, at(550)), None);
+        assert_eq!(repeat.update(down, at(600)), down);
+        assert_eq!(repeat.update(down, at(700)), down);
+        assert_eq!(repeat.update(down, at(750)), None);
+        assert_eq!(repeat.update(down, at(760)), down);
+        assert_eq!(repeat.update(down, at(820)), down);
+
+        // Release resets to the initial delay
+        assert_eq!(repeat.update(None, at(830)), None);
+        assert_eq!(repeat.update(down, at(900)), down);
+        assert_eq!(repeat.update(down, at(1000)), None);
+        assert_eq!(repeat.update(down, at(1300)), down);
+
+        // Switching buttons counts as a new press
+        assert_eq!(repeat.update(Some(Button::Up), at(1310)), Some(Button::Up));
+    }
+
+    fn manager() -> InputManager {
+        InputManager {
+            devices: Vec::new(),
+            device_files: Vec::new(),
+            rumble_devices: Vec::new(),
+            held: HashMap::new(),
+            state: InputState::default(),
+            previous: HashMap::new(),
+            deadzone: 4096,
+            button_map: InputManager::default_button_map(),
+            repeat: KeyRepeat::default(),
+            combos: ComboRecognizer::default(),
+            stick_dpad: StickDpad::default(),
+            remap: HashMap::new(),
+        }
+    }
+
+    /// Stand-in for one `poll` that read `events`
+    fn poll_events(manager: &mut InputManager, events: &[(u16, u16, i32)]) {
+        manager.begin_poll();
+        for &(event_type, code, value) in events {
+            manager.process_event(&InputEvent {
+                tv_sec: 0,
+                tv_usec: 0,
+                event_type,
+                code,
+                value,
+            });
+        }
+        manager.end_poll(Instant::now());
+    }
+
+    #[test]
+    fn test_edges_across_repeated_polls() {
+        let mut input = manager();
+        // BTN_SOUTH
+        let a = 304;
+
+        poll_events(&mut input, &[(0x01, a, 1)]);
+        assert!(input.just_pressed(Button::A));
+        assert!(!input.just_released(Button::A));
+
+        // Still held on the following ticks: no new press
+        poll_events(&mut input, &[]);
+        assert!(input.is_pressed(Button::A));
+        assert!(!input.just_pressed(Button::A));
+        poll_events(&mut input, &[(0x01, a, 1)]);
+        assert!(!input.just_pressed(Button::A));
+
+        poll_events(&mut input, &[(0x01, a, 0)]);
+        assert!(input.just_released(Button::A));
+        assert!(!input.just_pressed(Button::A));
+        poll_events(&mut input, &[]);
+        assert!(!input.just_released(Button::A));
+
+        // A second press is a new edge
+        poll_events(&mut input, &[(0x01, a, 1)]);
+        assert!(input.just_pressed(Button::A));
+    }
+
+    #[test]
+    fn test_next_press_fires_actions_once() {
+        let mut input = manager();
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        poll_events(&mut input, &[(0x01, 304, 1)]);
+        assert_eq!(input.next_press(at(0)), Some(Button::A));
+        for tick in 1..20 {
+            poll_events(&mut input, &[]);
+            assert_eq!(input.next_press(at(tick * 50)), None);
+        }
+
+        // Held D-pad (hat) still repeats
+        poll_events(&mut input, &[(0x03, 0x11, 1)]);
+        assert_eq!(input.next_press(at(1000)), Some(Button::Down));
+        poll_events(&mut input, &[]);
+        assert_eq!(input.next_press(at(1050)), None);
+        poll_events(&mut input, &[]);
+        assert_eq!(input.next_press(at(1400)), Some(Button::Down));
+    }
+
+    #[test]
+    fn test_analog_as_dpad() {
+        let mut input = manager();
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        // Off by default
+        poll_events(&mut input, &[(0x03, 0x00, -20000)]);
+        assert!(!input.is_pressed(Button::Left));
+
+        input.set_analog_as_dpad(true);
+        poll_events(&mut input, &[]);
+        assert!(input.just_pressed(Button::Left));
+        assert_eq!(input.next_press(at(0)), Some(Button::Left));
+
+        // Held stick repeats at the D-pad rate
+        poll_events(&mut input, &[]);
+        assert_eq!(input.next_press(at(50)), None);
+        poll_events(&mut input, &[]);
+        assert_eq!(input.next_press(at(400)), Some(Button::Left));
+
+        // Back inside the deadzone releases it
+        poll_events(&mut input, &[(0x03, 0x00, 1000)]);
+        assert!(input.just_released(Button::Left));
+
+        // Dominant axis wins on a diagonal
+        poll_events(&mut input, &[(0x03, 0x00, 8000), (0x03, 0x01, 20000)]);
+        assert!(input.is_pressed(Button::Down));
+        assert!(!input.is_pressed(Button::Right));
+    }
+
+    #[test]
+    fn test_analog_as_dpad_without_stick() {
+        let mut input = manager();
+        input.set_analog_sticks(0);
+        input.set_analog_as_dpad(true);
+
+        poll_events(&mut input, &[(0x03, 0x01, -20000)]);
+        assert!(!input.is_pressed(Button::Up));
+
+        // Real D-pad is unaffected
+        poll_events(&mut input, &[(0x03, 0x11, -1)]);
+        assert!(input.is_pressed(Button::Up));
+    }
+
+    fn remap(pairs: &[(&str, &str)]) -> InputConfig {
+        InputConfig {
+            remap: pairs
+                .iter()
+                .map(|(physical, logical)| (physical.to_string(), logical.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_remap_swaps_buttons() {
+        let mut input = manager();
+        let config = remap(&[("a", "b"), ("B", "A")]);
+        input.set_remap(&config).unwrap();
+
+        // BTN_SOUTH is physical A
+        poll_events(&mut input, &[(0x01, 304, 1)]);
+        assert!(input.is_pressed(Button::B));
+        assert!(!input.is_pressed(Button::A));
+
+        // Saved config reads back the same
+        let saved = input.remap_config();
+        assert_eq!(saved.remap["a"], "b");
+        assert_eq!(saved.remap["b"], "a");
+        assert_eq!(parse_remap(&saved).unwrap(), input.remap);
+    }
+
+    #[test]
+    fn test_remap_applies_to_hat() {
+        let mut input = manager();
+        input
+            .set_remap(&remap(&[("up", "down"), ("down", "up")]))
+            .unwrap();
+
+        poll_events(&mut input, &[(0x03, 0x11, -1)]);
+        assert!(input.is_pressed(Button::Down));
+        assert!(!input.is_pressed(Button::Up));
+    }
+
+    #[test]
+    fn test_remap_rejects_invalid() {
+        let mut input = manager();
+        assert!(input.set_remap(&remap(&[("a", "jump")])).is_err());
+
+        // Nothing left acting as B
+        let err = input.set_remap(&remap(&[("b", "x")])).unwrap_err();
+        assert!(err.to_string().contains("b"));
+
+        // Failed remaps leave the old one in place
+        assert!(input.remap.is_empty());
+    }
+
+    #[test]
+    fn test_stick_direction() {
+        assert_eq!(AnalogStick { x: 0, y: 0 }.direction(4096), None);
+        assert_eq!(
+            AnalogStick { x: 0, y: -32768 }.direction(4096),
+            Some(Button::Up)
+        );
+        assert_eq!(
+            AnalogStick { x: 32767, y: 100 }.direction(4096),
+            Some(Button::Right)
+        );
+    }
+
+    #[test]
+    fn test_poll_reads_ready_devices() {
+        use std::io::Write;
+        use std::os::unix::io::FromRawFd;
+
+        let mut input = manager();
+        let mut fds = [0; 2];
+        // SAFETY: fds has room for the two descriptors pipe2 writes
+        assert_eq!(
+            unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_NONBLOCK) },
+            0
+        );
+        // SAFETY: pipe2 just returned these descriptors and nothing else owns them
+        let (reader, mut writer) =
+            unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) };
+        input.device_files.push(reader);
+        input.devices.push(InputDevice {
+            path: PathBuf::from("/dev/input/event0"),
+            name: "pipe".to_string(),
+            is_gamepad: true,
+            has_analog: false,
+            has_rumble: false,
+        });
+
+        // Nothing queued: returns at once
+        assert!(input.poll().unwrap().is_empty());
+
+        let event = |tv_usec, value| InputEvent {
+            tv_sec: 1,
+            tv_usec,
+            event_type: 0x01,
+            code: 304,
+            value,
+        };
+        for event in [event(20, 0), event(10, 1)] {
+            // SAFETY: InputEvent is repr(C) plain data
+            let bytes = unsafe {
+                std::slice::from_raw_parts(&event as *const InputEvent as *const u8, EVENT_SIZE)
+            };
+            writer.write_all(bytes).unwrap();
+        }
+
+        let events = input.poll().unwrap();
+        assert_eq!(
+            events.iter().map(|e| e.tv_usec).collect::<Vec<_>>(),
+            [10, 20]
+        );
+        assert!(!input.is_pressed(Button::A));
+        assert!(input.poll().unwrap().is_empty());
+
+        // Unplugged devices are dropped
+        drop(writer);
+        assert!(input.poll().unwrap().is_empty());
+        assert!(input.devices().is_empty());
+    }
+
+    #[test]
+    fn test_unplug_releases_held_buttons() {
+        use std::io::Write;
+        use std::os::unix::io::FromRawFd;
+
+        let mut input = manager();
+        let mut fds = [0; 2];
+        // SAFETY: fds has room for the two descriptors pipe2 writes
+        assert_eq!(
+            unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_NONBLOCK) },
+            0
+        );
+        // SAFETY: pipe2 just returned these descriptors and nothing else owns them
+        let (reader, mut writer) =
+            unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) };
+        input.device_files.push(reader);
+        input.devices.push(InputDevice {
+            path: PathBuf::from("/dev/input/event0"),
+            name: "pipe".to_string(),
+            is_gamepad: true,
+            has_analog: false,
+            has_rumble: false,
+        });
+
+        // A and d-pad left held when the device goes away
+        for (event_type, code, value) in [(0x01, 304, 1), (0x03, 0x10, -1)] {
+            let event = InputEvent {
+                event_type,
+                code,
+                value,
+                ..Default::default()
+            };
+            // SAFETY: InputEvent is repr(C) plain data
+            let bytes = unsafe {
+                std::slice::from_raw_parts(&event as *const InputEvent as *const u8, EVENT_SIZE)
+            };
+            writer.write_all(bytes).unwrap();
+        }
+        input.poll().unwrap();
+        assert!(input.is_pressed(Button::A));
+        assert!(input.is_pressed(Button::Left));
+
+        drop(writer);
+        input.poll().unwrap();
+        assert!(input.devices().is_empty());
+        assert!(!input.is_pressed(Button::A));
+        assert!(!input.is_pressed(Button::Left));
+        assert!(input.just_released(Button::A));
+    }
+
+    #[test]
+    fn test_combo_fires_once_per_press() {
+        let mut input = manager();
+        input.register_combo("exit", &[Button::Select, Button::Start]);
+        // BTN_SELECT, BTN_START
+        let (select, start) = (314, 315);
+
+        poll_events(&mut input, &[(0x01, select, 1)]);
+        assert!(input.combos_fired().is_empty());
+        poll_events(&mut input, &[(0x01, start, 1)]);
+        assert_eq!(input.combos_fired(), ["exit"]);
+
+        // Held: no repeats
+        for _ in 0..5 {
+            poll_events(&mut input, &[]);
+            assert!(input.combos_fired().is_empty());
+        }
+
+        poll_events(&mut input, &[(0x01, start, 0)]);
+        assert!(input.combos_fired().is_empty());
+    }
+
+    #[test]
+    fn test_combo_debounce_and_shadowing() {
+        let mut combos = ComboRecognizer::default();
+        combos.register("save", &[Button::Select, Button::R1]);
+        combos.register("both", &[Button::Select, Button::R1, Button::L1]);
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let held = |buttons: &[Button]| -> HashMap<Button, bool> {
+            buttons.iter().map(|b| (*b, true)).collect()
+        };
+
+        assert_eq!(
+            combos.update(&held(&[Button::Select, Button::R1]), at(0)),
+            ["save"]
+        );
+        // A one-frame dropout doesn't fire again
+        assert!(combos.update(&held(&[Button::Select]), at(16)).is_empty());
+        assert!(
+            combos
+                .update(&held(&[Button::Select, Button::R1]), at(33))
+                .is_empty()
+        );
+        assert!(combos.update(&held(&[]), at(100)).is_empty());
+        assert_eq!(
+            combos.update(&held(&[Button::Select, Button::R1]), at(400)),
+            ["save"]
+        );
+        assert!(combos.update(&held(&[]), at(500)).is_empty());
+
+        // Pressed together, only the longer combo fires
+        let all = held(&[Button::Select, Button::R1, Button::L1]);
+        assert_eq!(combos.update(&all, at(1000)), ["both"]);
+
+        combos.register("save", &[]);
+        assert!(combos.update(&held(&[]), at(2000)).is_empty());
+        assert!(
+            combos
+                .update(&held(&[Button::Select, Button::R1]), at(3000))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_key_repeat_only_for_navigation() {
+        let mut repeat = KeyRepeat::default();
+        let start = Instant::now();
+
+        assert_eq!(repeat.update(Some(Button::A), start), Some(Button::A));
+        assert_eq!(
+            repeat.update(Some(Button::A), start + Duration::from_secs(5)),
+            None
+        );
+    }
+
     #[test]
     fn test_button_names() {
         assert_eq!(Button::A.name(), "a");
         assert_eq!(Button::Start.name(), "start");
     }
 
+    #[test]
+    fn test_button_from_name() {
+        assert_eq!(Button::from_name("Start"), Some(Button::Start));
+        assert_eq!(Button::from_name(" select "), Some(Button::Select));
+        assert_eq!(Button::from_name("turbo"), None);
+    }
+
+    #[test]
+    fn test_key_bit_set() {
+        let mut bits = [0u8; KEY_STATE_BYTES];
+        // BTN_START (0x13b)
+        bits[0x13b / 8] |= 1 << (0x13b % 8);
+        assert!(key_bit_set(&bits, 0x13b));
+        assert!(!key_bit_set(&bits, 0x13a));
+        assert!(!key_bit_set(&bits, 0xffff));
+    }
+
     #[test]
     fn test_analog_stick_neutral() {
         let stick = AnalogStick { x: 100, y: -50 };
@@ -0,0 +1,288 @@
+//! LED control
+//!
+//! Drives indicator LEDs through `/sys/class/leds`. RGB LEDs use the
+//! kernel's multicolor class (`multi_index` / `multi_intensity`).
+
+use crate::DeviceError;
+use crate::device::DeviceProfile;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// LED class directory
+pub const LED_CLASS_PATH: &str = "/sys/class/leds";
+
+/// Device quirk marking controllable LEDs
+pub const LED_QUIRK: &str = "leds";
+
+/// RGB color
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LedColor {
+    pub red: u32,
+    pub green: u32,
+    pub blue: u32,
+}
+
+impl LedColor {
+    pub const OFF: LedColor = LedColor::new(0, 0, 0);
+    pub const RED: LedColor = LedColor::new(255, 0, 0);
+    pub const GREEN: LedColor = LedColor::new(0, 255, 0);
+    pub const AMBER: LedColor = LedColor::new(255, 96, 0);
+
+    /// Create a color
+    pub const fn new(red: u32, green: u32, blue: u32) -> Self {
+        Self { red, green, blue }
+    }
+
+    /// Indicator color for the battery state
+    ///
+    /// Low battery takes priority over charging; otherwise the LED is off.
+    pub fn for_battery(is_charging: bool, is_low: bool) -> Self {
+        if is_low {
+            LedColor::RED
+        } else if is_charging {
+            LedColor::AMBER
+        } else {
+            LedColor::OFF
+        }
+    }
+
+    /// Get the channel value for a `multi_index` name
+    fn channel(&self, name: &str) -> u32 {
+        match name {
+            "red" => self.red,
+            "green" => self.green,
+            "blue" => self.blue,
+            _ => 0,
+        }
+    }
+}
+
+/// A sysfs LED
+#[derive(Debug, Clone)]
+pub struct Led {
+    /// LED name (e.g. "rgb:status")
+    pub name: String,
+    /// sysfs directory
+    pub path: PathBuf,
+    /// Maximum brightness value
+    pub max_brightness: u32,
+    /// Channel order for multicolor LEDs, empty for single-color LEDs
+    pub channels: Vec<String>,
+}
+
+impl Led {
+    /// List LEDs for a device
+    ///
+    /// Returns an empty list unless the profile has the `leds` quirk.
+    pub fn list(profile: &DeviceProfile) -> Result<Vec<Led>, DeviceError> {
+        if !profile.quirks.iter().any(|q| q == LED_QUIRK) {
+            return Ok(Vec::new());
+        }
+        Self::list_in(Path::new(LED_CLASS_PATH))
+    }
+
+    /// List LEDs in a sysfs LED class directory
+    pub fn list_in(dir: &Path) -> Result<Vec<Led>, DeviceError> {
+        let mut leds = Vec::new();
+
+        if !dir.exists() {
+            return Ok(leds);
+        }
+
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+
+            // Skip anything without a brightness control
+            if !path.join("brightness").exists() {
+                continue;
+            }
+
+            let name = entry.file_name().to_string_lossy().to_string();
+
+            let max_brightness = fs::read_to_string(path.join("max_brightness"))
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .unwrap_or(255);
+
+            let channels = fs::read_to_string(path.join("multi_index"))
+                .map(|s| parse_multi_index(&s))
+                .unwrap_or_default();
+
+            leds.push(Led {
+                name,
+                path,
+                max_brightness,
+                channels,
+            });
+        }
+
+        leds.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(leds)
+    }
+
+    /// Check if this is a multicolor (RGB) LED
+    pub fn is_rgb(&self) -> bool {
+        !self.channels.is_empty()
+    }
+
+    /// Read current brightness
+    pub fn brightness(&self) -> Result<u32, DeviceError> {
+        let contents = fs::read_to_string(self.path.join("brightness"))?;
+        Ok(contents.trim().parse().unwrap_or(0))
+    }
+
+    /// Set brightness (clamped to `max_brightness`)
+    pub fn set_brightness(&self, value: u32) -> Result<(), DeviceError> {
+        let value = value.min(self.max_brightness);
+        fs::write(self.path.join("brightness"), value.to_string())?;
+        Ok(())
+    }
+
+    /// Read the current color of an RGB LED
+    pub fn color(&self) -> Result<LedColor, DeviceError> {
+        let contents = fs::read_to_string(self.path.join("multi_intensity"))?;
+        let values = parse_multi_intensity(&contents);
+
+        let mut color = LedColor::default();
+        for (channel, value) in self.channels.iter().zip(values) {
+            match channel.as_str() {
+                "red" => color.red = value,
+                "green" => color.green = value,
+                "blue" => color.blue = value,
+                _ => {}
+            }
+        }
+        Ok(color)
+    }
+
+    /// Set the color of an RGB LED
+    ///
+    /// Writes `multi_intensity` in the LED's channel order and turns it on at
+    /// full brightness, or off for [`LedColor::OFF`].
+    pub fn set_color(&self, color: LedColor) -> Result<(), DeviceError> {
+        if !self.is_rgb() {
+            return Err(DeviceError::InitializationFailed(format!(
+                "LED {} is not an RGB LED",
+                self.name
+            )));
+        }
+
+        fs::write(
+            self.path.join("multi_intensity"),
+            format_multi_intensity(&self.channels, color),
+        )?;
+
+        if color == LedColor::OFF {
+            self.set_brightness(0)
+        } else {
+            self.set_brightness(self.max_brightness)
+        }
+    }
+}
+
+/// Parse a `multi_index` file (e.g. "red green blue")
+pub fn parse_multi_index(contents: &str) -> Vec<String> {
+    contents.split_whitespace().map(String::from).collect()
+}
+
+/// Parse a `multi_intensity` file (e.g. "255 0 128")
+pub fn parse_multi_intensity(contents: &str) -> Vec<u32> {
+    contents
+        .split_whitespace()
+        .map(|s| s.parse().unwrap_or(0))
+        .collect()
+}
+
+/// Format a color as a `multi_intensity` value in the given channel order
+pub fn format_multi_intensity(channels: &[String], color: LedColor) -> String {
+    channels
+        .iter()
+        .map(|channel| color.channel(channel).to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockProfile;
+
+    fn fake_sysfs() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rexos-leds-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let rgb = dir.join("rgb:status");
+        fs::create_dir_all(&rgb).unwrap();
+        fs::write(rgb.join("brightness"), "0\n").unwrap();
+        fs::write(rgb.join("max_brightness"), "255\n").unwrap();
+        fs::write(rgb.join("multi_index"), "green red blue\n").unwrap();
+        fs::write(rgb.join("multi_intensity"), "0 0 0\n").unwrap();
+
+        let power = dir.join("power");
+        fs::create_dir_all(&power).unwrap();
+        fs::write(power.join("brightness"), "1\n").unwrap();
+        fs::write(power.join("max_brightness"), "1\n").unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn test_parse_multi_intensity() {
+        assert_eq!(
+            parse_multi_index("red green blue\n"),
+            ["red", "green", "blue"]
+        );
+        assert_eq!(parse_multi_intensity("255 0 128\n"), vec![255, 0, 128]);
+
+        let channels = parse_multi_index("green red blue");
+        assert_eq!(
+            format_multi_intensity(&channels, LedColor::new(1, 2, 3)),
+            "2 1 3"
+        );
+    }
+
+    #[test]
+    fn test_synthetic_sysfs() {
+        let dir = fake_sysfs();
+
+        let leds = Led::list_in(&dir).unwrap();
+        assert_eq!(leds.len(), 2);
+        assert_eq!(leds[0].name, "power");
+        assert!(!leds[0].is_rgb());
+        assert!(leds[1].is_rgb());
+
+        // Single-color LED clamps to its maximum and rejects colors
+        leds[0].set_brightness(10).unwrap();
+        assert_eq!(leds[0].brightness().unwrap(), 1);
+        assert!(leds[0].set_color(LedColor::RED).is_err());
+
+        // RGB LED writes in channel order and turns on
+        let rgb = &leds[1];
+        rgb.set_color(LedColor::AMBER).unwrap();
+        assert_eq!(
+            fs::read_to_string(rgb.path.join("multi_intensity")).unwrap(),
+            "96 255 0"
+        );
+        assert_eq!(rgb.color().unwrap(), LedColor::AMBER);
+        assert_eq!(rgb.brightness().unwrap(), 255);
+
+        rgb.set_color(LedColor::OFF).unwrap();
+        assert_eq!(rgb.brightness().unwrap(), 0);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_list_gated_by_quirk() {
+        let profile = MockProfile::Rg353m.to_device_profile();
+        assert!(Led::list(&profile).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_battery_color() {
+        assert_eq!(LedColor::for_battery(false, false), LedColor::OFF);
+        assert_eq!(LedColor::for_battery(true, false), LedColor::AMBER);
+        assert_eq!(LedColor::for_battery(true, true), LedColor::RED);
+    }
+}
@@ -30,15 +30,24 @@ pub mod audio;
 pub mod device;
 pub mod display;
 pub mod input;
+pub mod led;
+pub mod logs;
 pub mod mock;
 pub mod power;
+pub mod rumble;
 
-pub use audio::{AudioConfig, AudioManager, AudioProfile, HeadphoneState};
+pub use audio::{AudioConfig, AudioManager, AudioProfile, HeadphoneState, VolumeCurve};
 pub use device::{Device, DeviceError, DeviceProfile, DisplaySpec, SystemInfo};
 pub use display::{BacklightInfo, Display, DisplayConfig, Rotation};
-pub use input::{AnalogStick, Button, InputDevice, InputEvent, InputManager, InputState};
+pub use input::{
+    AnalogStick, Button, ComboRecognizer, InputDevice, InputEvent, InputManager, InputState,
+    KeyRepeat, RepeatSettings,
+};
+pub use led::{Led, LedColor};
+pub use logs::{LogBuffer, LogLevel, LogLine, LogSource, LogTail};
 pub use power::{
-    BatteryHealth, BatteryInfo, BatteryStatus, CpuGovernor, PowerConfig, PowerManager,
+    BatteryHealth, BatteryInfo, BatteryStatus, CpuGovernor, CpuProfile, FreqGuard, FreqLimits,
+    PowerConfig, PowerEvent, PowerManager, ThermalStatus,
 };
 
 /// HAL Result type
@@ -0,0 +1,476 @@
+//! System log tailing
+//!
+//! Tails the kernel ring buffer (`/dev/kmsg`) and the RexOS log file into a
+//! bounded ring of recent lines, so logs can be read on-device without SSH.
+
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+/// Kernel ring buffer device
+pub const KMSG_PATH: &str = "/dev/kmsg";
+
+/// Default RexOS log file
+pub const REXOS_LOG_PATH: &str = "/var/log/rexos.log";
+
+/// Default number of lines retained
+pub const DEFAULT_CAPACITY: usize = 500;
+
+/// How much of an existing log file to read when first opened
+const INITIAL_TAIL_BYTES: u64 = 64 * 1024;
+
+/// Maximum size of a single kmsg record
+const KMSG_RECORD_MAX: usize = 8192;
+
+/// Syslog severity level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Emergency,
+    Alert,
+    Critical,
+    Error,
+    Warning,
+    Notice,
+    Info,
+    Debug,
+}
+
+impl LogLevel {
+    /// Parse from a syslog priority value (facility bits are ignored)
+    pub fn from_priority(priority: u32) -> Self {
+        match priority & 7 {
+            0 => LogLevel::Emergency,
+            1 => LogLevel::Alert,
+            2 => LogLevel::Critical,
+            3 => LogLevel::Error,
+            4 => LogLevel::Warning,
+            5 => LogLevel::Notice,
+            6 => LogLevel::Info,
+            _ => LogLevel::Debug,
+        }
+    }
+
+    /// Short display label
+    pub fn label(&self) -> &'static str {
+        match self {
+            LogLevel::Emergency => "EMERG",
+            LogLevel::Alert => "ALERT",
+            LogLevel::Critical => "CRIT",
+            LogLevel::Error => "ERROR",
+            LogLevel::Warning => "WARN",
+            LogLevel::Notice => "NOTICE",
+            LogLevel::Info => "INFO",
+            LogLevel::Debug => "DEBUG",
+        }
+    }
+}
+
+/// Where a log line came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogSource {
+    Kernel,
+    File,
+}
+
+/// A single log line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogLine {
+    /// Source of the line
+    pub source: LogSource,
+    /// Severity, if known
+    pub level: Option<LogLevel>,
+    /// Kernel sequence number (kmsg only)
+    pub sequence: Option<u64>,
+    /// Microseconds since boot (kmsg only)
+    pub timestamp_us: Option<u64>,
+    /// Message text
+    pub message: String,
+}
+
+impl LogLine {
+    /// Create a plain line read from a log file
+    pub fn from_file(message: impl Into<String>) -> Self {
+        Self {
+            source: LogSource::File,
+            level: None,
+            sequence: None,
+            timestamp_us: None,
+            message: message.into(),
+        }
+    }
+
+    /// Parse a `/dev/kmsg` record
+    ///
+    /// Records look like `pri,seq,ts_usec,flags[,...];message`, optionally
+    /// followed by continuation lines starting with a space (`KEY=value`),
+    /// which are dropped. Non-printable bytes in the message are escaped by
+    /// the kernel as `\xNN`; those escapes are decoded.
+    pub fn parse_kmsg(record: &str) -> Option<Self> {
+        let first = record.lines().next()?;
+        let (header, message) = first.split_once(';')?;
+
+        let mut fields = header.split(',');
+        let priority: u32 = fields.next()?.trim().parse().ok()?;
+        let sequence: u64 = fields.next()?.trim().parse().ok()?;
+        let timestamp_us: u64 = fields.next()?.trim().parse().ok()?;
+
+        Some(Self {
+            source: LogSource::Kernel,
+            level: Some(LogLevel::from_priority(priority)),
+            sequence: Some(sequence),
+            timestamp_us: Some(timestamp_us),
+            message: unescape_kmsg(message),
+        })
+    }
+
+    /// Format for display, dmesg style for kernel lines
+    pub fn display(&self) -> String {
+        match (self.timestamp_us, self.level) {
+            (Some(ts), Some(level)) => format!(
+                "[{:>5}.{:06}] {:<6} {}",
+                ts / 1_000_000,
+                ts % 1_000_000,
+                level.label(),
+                self.message
+            ),
+            _ => self.message.clone(),
+        }
+    }
+}
+
+/// Decode `\xNN` escapes used by the kernel in kmsg messages
+fn unescape_kmsg(message: &str) -> String {
+    let bytes = message.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && i + 3 < bytes.len() && bytes[i + 1] == b'x' {
+            let hex = std::str::from_utf8(&bytes[i + 2..i + 4]).unwrap_or("");
+            if let Ok(b) = u8::from_str_radix(hex, 16) {
+                out.push(b);
+                i += 4;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Fixed-capacity ring of recent log lines
+#[derive(Debug, Clone)]
+pub struct LogBuffer {
+    lines: VecDeque<LogLine>,
+    capacity: usize,
+}
+
+impl LogBuffer {
+    /// Create a buffer retaining at most `capacity` lines
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            lines: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Append a line, evicting the oldest if full
+    pub fn push(&mut self, line: LogLine) {
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+    }
+
+    /// Number of retained lines
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Check if empty
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Maximum number of retained lines
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Iterate from oldest to newest
+    pub fn iter(&self) -> impl Iterator<Item = &LogLine> {
+        self.lines.iter()
+    }
+
+    /// Get the last `n` lines, oldest first
+    pub fn last(&self, n: usize) -> impl Iterator<Item = &LogLine> {
+        self.lines.iter().skip(self.lines.len().saturating_sub(n))
+    }
+
+    /// Remove all lines
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+}
+
+/// Tails the kernel log and/or a log file into a [`LogBuffer`]
+pub struct LogTail {
+    buffer: LogBuffer,
+    kmsg: Option<File>,
+    file: Option<FileTail>,
+}
+
+/// Read position within a tailed log file
+struct FileTail {
+    path: PathBuf,
+    offset: u64,
+    partial: Vec<u8>,
+}
+
+impl LogTail {
+    /// Create a tail with no sources
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            buffer: LogBuffer::new(capacity),
+            kmsg: None,
+            file: None,
+        }
+    }
+
+    /// Tail the kernel ring buffer
+    ///
+    /// If `/dev/kmsg` cannot be opened (e.g. `dmesg_restrict`), the kernel
+    /// source is skipped; see [`LogTail::has_kernel`].
+    pub fn with_kernel(mut self) -> Self {
+        match open_kmsg(Path::new(KMSG_PATH)) {
+            Ok(file) => self.kmsg = Some(file),
+            Err(e) => tracing::warn!("Kernel log not available: {}", e),
+        }
+        self
+    }
+
+    /// Tail a log file, starting near its current end
+    pub fn with_file(mut self, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let offset = fs::metadata(&path)
+            .map(|m| m.len().saturating_sub(INITIAL_TAIL_BYTES))
+            .unwrap_or(0);
+
+        self.file = Some(FileTail {
+            path,
+            offset,
+            partial: Vec::new(),
+        });
+
+        // Drop the first line when starting mid-file, it is likely truncated
+        if offset > 0 {
+            self.skip_partial_line();
+        }
+        self
+    }
+
+    /// Read any new lines from all sources, returning how many were added
+    pub fn poll(&mut self) -> io::Result<usize> {
+        let mut added = 0;
+
+        if let Some(kmsg) = self.kmsg.as_mut() {
+            added += read_kmsg(kmsg, &mut self.buffer)?;
+        }
+
+        if let Some(tail) = self.file.as_mut() {
+            added += tail.read_new(&mut self.buffer)?;
+        }
+
+        Ok(added)
+    }
+
+    /// Check if the kernel log is being tailed
+    pub fn has_kernel(&self) -> bool {
+        self.kmsg.is_some()
+    }
+
+    /// Retained lines
+    pub fn buffer(&self) -> &LogBuffer {
+        &self.buffer
+    }
+
+    /// Clear retained lines (sources keep their position)
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+
+    fn skip_partial_line(&mut self) {
+        // Avoid if-let chains for MSRV 1.85 compatibility
+        #[allow(clippy::collapsible_if)]
+        if let Some(tail) = self.file.as_mut() {
+            if let Ok(mut f) = File::open(&tail.path) {
+                if f.seek(SeekFrom::Start(tail.offset)).is_ok() {
+                    let mut skipped = Vec::new();
+                    let mut reader = BufReader::new(&mut f);
+                    if let Ok(n) = reader.read_until(b'\n', &mut skipped) {
+                        tail.offset += n as u64;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl FileTail {
+    /// Read lines appended since the last call
+    fn read_new(&mut self, buffer: &mut LogBuffer) -> io::Result<usize> {
+        let len = match fs::metadata(&self.path) {
+            Ok(m) => m.len(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e),
+        };
+
+        // File was truncated or rotated, start over
+        if len < self.offset {
+            self.offset = 0;
+            self.partial.clear();
+        }
+        if len == self.offset {
+            return Ok(0);
+        }
+
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(self.offset))?;
+
+        let read = file
+            .take(len - self.offset)
+            .read_to_end(&mut self.partial)?;
+        self.offset += read as u64;
+
+        let mut added = 0;
+        while let Some(pos) = self.partial.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.partial.drain(..=pos).collect();
+            buffer.push(LogLine::from_file(
+                String::from_utf8_lossy(&line).trim_end(),
+            ));
+            added += 1;
+        }
+
+        Ok(added)
+    }
+}
+
+/// Open `/dev/kmsg` for non-blocking reads
+fn open_kmsg(path: &Path) -> io::Result<File> {
+    OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NONBLOCK)
+        .open(path)
+}
+
+/// Drain all currently available kmsg records
+///
+/// Each `read` on `/dev/kmsg` returns exactly one record. `EPIPE` means
+/// records were overwritten before we read them; the next read continues
+/// from the oldest available record.
+fn read_kmsg(kmsg: &mut File, buffer: &mut LogBuffer) -> io::Result<usize> {
+    let mut record = vec![0u8; KMSG_RECORD_MAX];
+    let mut added = 0;
+
+    loop {
+        match kmsg.read(&mut record) {
+            Ok(0) => break,
+            Ok(n) => {
+                let text = String::from_utf8_lossy(&record[..n]);
+                if let Some(line) = LogLine::parse_kmsg(&text) {
+                    buffer.push(line);
+                    added += 1;
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+            Err(e) if e.raw_os_error() == Some(libc::EPIPE) => {
+                tracing::debug!("kmsg records overwritten before read");
+                continue;
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_parse_kmsg_record() {
+        let line =
+            LogLine::parse_kmsg("6,1234,5678901,-;mmc1: new high speed SDXC card\n").unwrap();
+        assert_eq!(line.source, LogSource::Kernel);
+        assert_eq!(line.level, Some(LogLevel::Info));
+        assert_eq!(line.sequence, Some(1234));
+        assert_eq!(line.timestamp_us, Some(5678901));
+        assert_eq!(line.message, "mmc1: new high speed SDXC card");
+        assert_eq!(
+            line.display(),
+            "[    5.678901] INFO   mmc1: new high speed SDXC card"
+        );
+    }
+
+    #[test]
+    fn test_parse_kmsg_continuation_and_escapes() {
+        let record = "3,42,100,c,more;usb 1-1: bad\\x20desc\n SUBSYSTEM=usb\n DEVICE=c189:1\n";
+        let line = LogLine::parse_kmsg(record).unwrap();
+        assert_eq!(line.level, Some(LogLevel::Error));
+        assert_eq!(line.message, "usb 1-1: bad desc");
+
+        // Facility bits are ignored
+        assert_eq!(LogLevel::from_priority(30), LogLevel::Info);
+
+        assert!(LogLine::parse_kmsg("garbage").is_none());
+        assert!(LogLine::parse_kmsg("x,1,2,-;msg").is_none());
+    }
+
+    #[test]
+    fn test_buffer_is_bounded() {
+        let mut buffer = LogBuffer::new(3);
+        for i in 0..5 {
+            buffer.push(LogLine::from_file(format!("line {}", i)));
+        }
+
+        assert_eq!(buffer.len(), 3);
+        let messages: Vec<_> = buffer.iter().map(|l| l.message.as_str()).collect();
+        assert_eq!(messages, vec!["line 2", "line 3", "line 4"]);
+
+        let last: Vec<_> = buffer.last(2).map(|l| l.message.as_str()).collect();
+        assert_eq!(last, vec!["line 3", "line 4"]);
+    }
+
+    #[test]
+    fn test_file_tail_follows_appends() {
+        let path = std::env::temp_dir().join(format!("rexos-logtail-{}.log", std::process::id()));
+        fs::write(&path, "first\n").unwrap();
+
+        let mut tail = LogTail::new(10).with_file(&path);
+        assert_eq!(tail.poll().unwrap(), 1);
+
+        let mut f = OpenOptions::new().append(true).open(&path).unwrap();
+        write!(f, "second\nthi").unwrap();
+        assert_eq!(tail.poll().unwrap(), 1);
+
+        writeln!(f, "rd").unwrap();
+        assert_eq!(tail.poll().unwrap(), 1);
+
+        let messages: Vec<_> = tail.buffer().iter().map(|l| l.message.clone()).collect();
+        assert_eq!(messages, vec!["first", "second", "third"]);
+
+        // Truncation restarts from the beginning
+        fs::write(&path, "new\n").unwrap();
+        assert_eq!(tail.poll().unwrap(), 1);
+
+        let _ = fs::remove_file(&path);
+    }
+}
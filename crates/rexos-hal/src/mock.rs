@@ -16,14 +16,15 @@
 //! let custom = MockDevice::from_profile_file(Path::new("profiles/custom.toml"));
 //! ```
 
-use crate::power::CpuGovernor;
+use crate::audio::headphone_change;
+use crate::power::{BatteryWatch, CpuGovernor, PowerEvent, ThermalStatus, thermal_event};
 use crate::{
-    AudioConfig, BatteryHealth, BatteryStatus, Button, DeviceError, DeviceProfile, DisplaySpec,
-    HeadphoneState, InputEvent, InputState, Rotation,
+    AudioConfig, AudioProfile, BatteryHealth, BatteryStatus, Button, DeviceError, DeviceProfile,
+    DisplaySpec, HeadphoneState, InputEvent, InputState, Rotation,
 };
 use std::collections::HashMap;
 use std::path::Path;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 
 /// Pre-defined mock device profiles
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -172,7 +173,7 @@ impl MockProfile {
                 buttons: standard_buttons(),
                 analog_sticks: 2,
                 battery_capacity: 4100,
-                quirks: vec!["mock".into(), "square_display".into()],
+                quirks: vec!["mock".into(), "square_display".into(), "leds".into()],
             },
             MockProfile::Rg503 => DeviceProfile {
                 id: "rg503".into(),
@@ -330,6 +331,8 @@ pub struct MockState {
     pub battery: MockBatteryInfo,
     /// CPU governor
     pub governor: CpuGovernor,
+    /// SoC temperature in degrees Celsius
+    pub temperature: f32,
     /// Button states
     pub buttons: HashMap<Button, bool>,
     /// Left stick position
@@ -356,6 +359,7 @@ impl MockState {
             headphones: HeadphoneState::Disconnected,
             battery: MockBatteryInfo::default(),
             governor: CpuGovernor::Ondemand,
+            temperature: 45.0,
             buttons,
             left_stick: (0, 0),
             right_stick: (0, 0),
@@ -458,6 +462,19 @@ impl MockDisplay {
         Ok(())
     }
 
+    pub fn set_brightness_smooth(
+        &mut self,
+        target: u8,
+        duration: std::time::Duration,
+    ) -> Result<(), DeviceError> {
+        let from = self.get_brightness();
+        for level in crate::display::brightness_steps(from, target, duration) {
+            self.set_brightness(level)?;
+            std::thread::sleep(crate::display::BRIGHTNESS_STEP_INTERVAL);
+        }
+        self.set_brightness(target)
+    }
+
     pub fn get_brightness(&self) -> u8 {
         self.state
             .read()
@@ -465,6 +482,13 @@ impl MockDisplay {
             .unwrap_or(self.config.brightness)
     }
 
+    pub fn rotation(&self) -> Rotation {
+        self.state
+            .read()
+            .map(|s| s.rotation)
+            .unwrap_or(self.config.rotation)
+    }
+
     pub fn set_rotation(&mut self, rotation: Rotation) -> Result<(), DeviceError> {
         self.config.rotation = rotation;
         if let Ok(mut state) = self.state.write() {
@@ -499,6 +523,8 @@ impl MockDisplay {
 pub struct MockAudio {
     config: AudioConfig,
     state: Arc<RwLock<MockState>>,
+    headphones: HeadphoneState,
+    output: AudioProfile,
 }
 
 impl MockAudio {
@@ -507,6 +533,8 @@ impl MockAudio {
         Self {
             config: AudioConfig::default(),
             state,
+            headphones: HeadphoneState::Unknown,
+            output: AudioProfile::Speaker,
         }
     }
 
@@ -541,6 +569,27 @@ impl MockAudio {
             .map(|s| s.headphones)
             .unwrap_or(HeadphoneState::Disconnected)
     }
+
+    /// Simulate plugging in or pulling out headphones
+    pub fn set_headphones(&self, headphones: HeadphoneState) {
+        if let Ok(mut state) = self.state.write() {
+            state.headphones = headphones;
+        }
+    }
+
+    /// Follow the jack like [`crate::AudioManager::poll_headphone`]
+    pub fn poll_headphone(&mut self) -> Option<HeadphoneState> {
+        let state = headphone_change(self.headphones, self.headphone_state())?;
+        self.headphones = state;
+        self.output = AudioProfile::for_headphones(state);
+        tracing::debug!("[MOCK] Audio output set to {:?}", self.output);
+        Some(state)
+    }
+
+    /// Output selected by the last headphone change
+    pub fn output(&self) -> AudioProfile {
+        self.output
+    }
 }
 
 /// Mock input manager for testing
@@ -625,12 +674,17 @@ pub struct MockPowerConfig {
     pub low_battery_threshold: u8,
     pub critical_battery_threshold: u8,
     pub auto_sleep_timeout: u32,
+    pub thermal_threshold: f32,
+    /// Passive trip point reported by [`MockPower::thermal_status`]
+    pub trip_point: f32,
 }
 
 /// Mock power manager for testing
 pub struct MockPower {
     config: MockPowerConfig,
     state: Arc<RwLock<MockState>>,
+    watch: Mutex<Option<BatteryWatch>>,
+    throttled: Mutex<bool>,
 }
 
 impl MockPower {
@@ -641,8 +695,13 @@ impl MockPower {
                 low_battery_threshold: 15,
                 critical_battery_threshold: 5,
                 auto_sleep_timeout: 300,
+                thermal_threshold: 80.0,
+                // RK3566 soc-thermal passive trip
+                trip_point: 85.0,
             },
             state,
+            watch: Mutex::new(None),
+            throttled: Mutex::new(false),
         }
     }
 
@@ -678,6 +737,45 @@ impl MockPower {
         }
     }
 
+    /// Report threshold crossings and charger changes since the last poll
+    pub fn poll_battery(&self) -> Vec<PowerEvent> {
+        let info = self.battery_info();
+        let Ok(mut watch) = self.watch.lock() else {
+            return Vec::new();
+        };
+
+        let (next, events) = BatteryWatch::update(
+            *watch,
+            info.capacity,
+            info.status == BatteryStatus::Charging,
+            self.config.low_battery_threshold,
+            self.config.critical_battery_threshold,
+        );
+        *watch = Some(next);
+        events
+    }
+
+    /// Simulate the SoC heating up or cooling down
+    pub fn set_temperature(&self, temperature: f32) {
+        if let Ok(mut state) = self.state.write() {
+            state.temperature = temperature;
+        }
+    }
+
+    pub fn thermal_status(&self) -> ThermalStatus {
+        ThermalStatus {
+            temperature: self.state.read().map(|s| s.temperature).unwrap_or(0.0),
+            trip_point: Some(self.config.trip_point),
+        }
+    }
+
+    /// Report when the temperature reaches the thermal threshold
+    pub fn poll_thermal(&self) -> Option<PowerEvent> {
+        let status = self.thermal_status();
+        let mut throttled = self.throttled.lock().ok()?;
+        thermal_event(&mut throttled, status, self.config.thermal_threshold)
+    }
+
     pub fn set_governor(&mut self, governor: CpuGovernor) -> Result<(), DeviceError> {
         let _ = self.config.auto_sleep_timeout; // Silence unused warning
         if let Ok(mut state) = self.state.write() {
@@ -768,11 +866,38 @@ mod tests {
         display.set_brightness(100).unwrap();
         assert_eq!(display.get_brightness(), 100);
 
+        display
+            .set_brightness_smooth(200, std::time::Duration::from_millis(50))
+            .unwrap();
+        assert_eq!(display.get_brightness(), 200);
+        display
+            .set_brightness_smooth(20, std::time::Duration::ZERO)
+            .unwrap();
+        assert_eq!(display.get_brightness(), 20);
+
         display.set_rotation(Rotation::Rotate90).unwrap();
+        assert_eq!(display.rotation(), Rotation::Rotate90);
         display.power_off().unwrap();
         display.power_on().unwrap();
     }
 
+    #[test]
+    fn test_mock_headphones() {
+        let device = MockDevice::new(MockProfile::Rg353m);
+        let mut audio = MockAudio::new(device.profile(), device.state());
+
+        assert_eq!(audio.poll_headphone(), Some(HeadphoneState::Disconnected));
+        assert_eq!(audio.poll_headphone(), None);
+
+        audio.set_headphones(HeadphoneState::Connected);
+        assert_eq!(audio.poll_headphone(), Some(HeadphoneState::Connected));
+        assert_eq!(audio.output(), AudioProfile::Headphones);
+
+        audio.set_headphones(HeadphoneState::Disconnected);
+        assert_eq!(audio.poll_headphone(), Some(HeadphoneState::Disconnected));
+        assert_eq!(audio.output(), AudioProfile::Speaker);
+    }
+
     #[test]
     fn test_mock_input() {
         let device = MockDevice::new(MockProfile::Rg353m);
@@ -806,6 +931,21 @@ mod tests {
 
         power.set_charging(true);
         assert_eq!(power.battery_info().status, BatteryStatus::Charging);
+
+        assert!(power.poll_battery().is_empty());
+        power.set_charging(false);
+        power.set_battery_capacity(15);
+        assert_eq!(
+            power.poll_battery(),
+            vec![PowerEvent::Unplugged, PowerEvent::LowBattery(15)]
+        );
+
+        assert!(!power.thermal_status().near_trip_point());
+        assert_eq!(power.poll_thermal(), None);
+        power.set_temperature(82.0);
+        assert!(power.thermal_status().near_trip_point());
+        assert_eq!(power.poll_thermal(), Some(PowerEvent::ThermalThrottle(82)));
+        assert_eq!(power.poll_thermal(), None);
     }
 
     #[test]
@@ -1,25 +1,92 @@
 //! Power management
 //!
-//! Handles battery monitoring, charging detection, and CPU governor control via sysfs.
-//! Based on ArkOS power management patterns including low battery warning.
+//! Handles battery monitoring, charging detection, CPU governor control and
+//! CPU/GPU frequency caps via sysfs. Based on ArkOS power management
+//! patterns including low battery warning.
 
 use crate::DeviceError;
+use rexos_config::PerformanceProfile;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// cpufreq policy directories
+const CPUFREQ_DIR: &str = "/sys/devices/system/cpu/cpufreq";
+
+/// devfreq devices (GPU, memory controller)
+const DEVFREQ_DIR: &str = "/sys/class/devfreq";
+
+/// Thermal zones
+const THERMAL_DIR: &str = "/sys/class/thermal";
+
+/// Weight of a new capacity reading in the smoothed percentage
+///
+/// Fuel gauges report capacity that jumps a few percent between reads
+/// as load changes; a low weight keeps the displayed value steady.
+pub const BATTERY_SMOOTHING: f32 = 0.2;
+
+/// Percent above a threshold the battery must recover to clear its warning
+///
+/// Without a margin, a reading wobbling between 19% and 20% would raise a
+/// low battery warning on every other poll.
+pub const BATTERY_HYSTERESIS: u8 = 3;
+
+/// Degrees below the thermal threshold needed to clear a throttle warning
+pub const THERMAL_HYSTERESIS: f32 = 5.0;
+
+/// Degrees below a trip point that count as near it
+const TRIP_POINT_MARGIN: f32 = 5.0;
 
 /// Battery information
 #[derive(Debug, Clone)]
 pub struct BatteryInfo {
+    /// Capacity as reported by the fuel gauge
     pub percentage: u8,
+    /// Capacity averaged over recent readings, for display
+    pub smoothed_percentage: u8,
     pub voltage: f32,
     pub current: f32,
+    /// Current in amps, `None` if the battery doesn't report it
+    pub current_now: Option<f32>,
+    /// Remaining charge in amp hours, if reported
+    pub charge_now: Option<f32>,
+    /// Charge when full in amp hours, if reported
+    pub charge_full: Option<f32>,
     pub is_charging: bool,
     pub status: BatteryStatus,
     pub health: BatteryHealth,
     pub temperature: f32,
 }
 
+impl BatteryInfo {
+    /// Time until empty while discharging, or until full while charging
+    ///
+    /// `None` when the battery doesn't report its current or capacity,
+    /// or isn't charging or discharging.
+    pub fn time_remaining(&self) -> Option<Duration> {
+        let current = self.current_now?.abs();
+        if current <= f32::EPSILON {
+            return None;
+        }
+
+        let charge_full = self.charge_full?;
+        // Some gauges only report the percentage
+        let charge_now = self
+            .charge_now
+            .unwrap_or(charge_full * self.percentage as f32 / 100.0);
+
+        let amp_hours = match self.status {
+            BatteryStatus::Discharging => charge_now,
+            BatteryStatus::Charging => (charge_full - charge_now).max(0.0),
+            _ => return None,
+        };
+
+        Some(Duration::from_secs_f32(amp_hours / current * 3600.0))
+    }
+}
+
 /// Battery charging status
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BatteryStatus {
@@ -30,6 +97,103 @@ pub enum BatteryStatus {
     Unknown,
 }
 
+/// Battery state change reported by [`PowerManager::poll_battery`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerEvent {
+    /// Dropped to the low battery threshold, with the current percentage
+    LowBattery(u8),
+    /// Dropped to the critical battery threshold, with the current percentage
+    CriticalBattery(u8),
+    /// Charger plugged in
+    Charging,
+    /// Charger unplugged
+    Unplugged,
+    /// Temperature reached the thermal threshold, in whole degrees Celsius
+    ThermalThrottle(i32),
+}
+
+/// Temperature of the hottest thermal zone
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThermalStatus {
+    /// Temperature in degrees Celsius
+    pub temperature: f32,
+    /// Lowest passive trip point of the zone, where the kernel starts
+    /// throttling, if it has one
+    pub trip_point: Option<f32>,
+}
+
+impl ThermalStatus {
+    /// Within a few degrees of the trip point
+    pub fn near_trip_point(&self) -> bool {
+        self.trip_point
+            .is_some_and(|trip| self.temperature >= trip - TRIP_POINT_MARGIN)
+    }
+}
+
+/// Warning level of a discharging battery, ordered by severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum BatteryLevel {
+    Normal,
+    Low,
+    Critical,
+}
+
+/// Battery state at the last poll, for finding transitions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct BatteryWatch {
+    level: BatteryLevel,
+    charging: bool,
+}
+
+impl BatteryWatch {
+    /// Events that lead from `previous` to a new reading
+    ///
+    /// The first reading reports a battery that is already low but not the
+    /// charger state, which hasn't changed as far as the caller knows.
+    pub(crate) fn update(
+        previous: Option<Self>,
+        percentage: u8,
+        charging: bool,
+        low_threshold: u8,
+        critical_threshold: u8,
+    ) -> (Self, Vec<PowerEvent>) {
+        let previous = previous.unwrap_or(Self {
+            level: BatteryLevel::Normal,
+            charging,
+        });
+        let mut events = Vec::new();
+
+        if charging != previous.charging {
+            events.push(if charging {
+                PowerEvent::Charging
+            } else {
+                PowerEvent::Unplugged
+            });
+        }
+
+        // Unplugging a low battery warns again
+        let level = if charging {
+            BatteryLevel::Normal
+        } else {
+            battery_level(
+                previous.level,
+                percentage,
+                low_threshold,
+                critical_threshold,
+            )
+        };
+
+        if level > previous.level {
+            events.push(match level {
+                BatteryLevel::Critical => PowerEvent::CriticalBattery(percentage),
+                _ => PowerEvent::LowBattery(percentage),
+            });
+        }
+
+        (Self { level, charging }, events)
+    }
+}
+
 /// Battery health status
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BatteryHealth {
@@ -73,6 +237,39 @@ impl CpuGovernor {
             _ => None,
         }
     }
+
+    /// Closest governor on kernels built without this one
+    fn fallback(&self) -> Option<Self> {
+        match self {
+            CpuGovernor::Schedutil | CpuGovernor::Conservative => Some(CpuGovernor::Ondemand),
+            _ => None,
+        }
+    }
+}
+
+/// CPU governor and frequency range applied for a performance profile
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuProfile {
+    pub governor: CpuGovernor,
+    /// Lowest frequency in kHz, `None` for the hardware minimum
+    pub min_khz: Option<u64>,
+    /// Highest frequency in kHz, `None` for the hardware maximum
+    pub max_khz: Option<u64>,
+}
+
+impl From<PerformanceProfile> for CpuProfile {
+    fn from(profile: PerformanceProfile) -> Self {
+        let governor = match profile {
+            PerformanceProfile::Powersave => CpuGovernor::Powersave,
+            PerformanceProfile::Balanced => CpuGovernor::Schedutil,
+            PerformanceProfile::Performance => CpuGovernor::Performance,
+        };
+        Self {
+            governor,
+            min_khz: None,
+            max_khz: None,
+        }
+    }
 }
 
 /// Power manager configuration
@@ -83,6 +280,8 @@ pub struct PowerConfig {
     pub low_battery_threshold: u8,
     pub critical_battery_threshold: u8,
     pub suspend_timeout: u32,
+    /// Temperature in degrees Celsius that reports [`PowerEvent::ThermalThrottle`]
+    pub thermal_threshold: f32,
 }
 
 impl Default for PowerConfig {
@@ -93,6 +292,41 @@ impl Default for PowerConfig {
             low_battery_threshold: 20,
             critical_battery_threshold: 5,
             suspend_timeout: 300,
+            thermal_threshold: 80.0,
+        }
+    }
+}
+
+/// Maximum CPU/GPU frequencies while a game runs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FreqLimits {
+    /// Maximum CPU frequency in kHz
+    pub cpu_max_khz: Option<u64>,
+    /// Maximum GPU frequency in Hz
+    pub gpu_max_hz: Option<u64>,
+}
+
+impl FreqLimits {
+    /// Check if no cap is set
+    pub fn is_empty(&self) -> bool {
+        self.cpu_max_khz.is_none() && self.gpu_max_hz.is_none()
+    }
+}
+
+/// Restores the previous frequency caps when dropped
+#[must_use = "frequency caps are reverted when the guard is dropped"]
+#[derive(Debug, Default)]
+pub struct FreqGuard {
+    /// Files and their previous contents, in the order they were changed
+    restore: Vec<(PathBuf, String)>,
+}
+
+impl Drop for FreqGuard {
+    fn drop(&mut self) {
+        for (path, value) in self.restore.drain(..).rev() {
+            if let Err(e) = fs::write(&path, &value) {
+                tracing::warn!("Failed to restore {}: {}", path.display(), e);
+            }
         }
     }
 }
@@ -102,6 +336,12 @@ pub struct PowerManager {
     config: PowerConfig,
     battery_path: PathBuf,
     charger_path: PathBuf,
+    /// Smoothed capacity and the status it was measured under
+    smoothed: Mutex<Option<(f32, BatteryStatus)>>,
+    /// Battery state at the last [`PowerManager::poll_battery`]
+    watch: Mutex<Option<BatteryWatch>>,
+    /// Whether the last [`PowerManager::poll_thermal`] was over the threshold
+    throttled: Mutex<bool>,
 }
 
 impl PowerManager {
@@ -116,6 +356,9 @@ impl PowerManager {
             battery_path: config.battery_path.clone(),
             charger_path: config.charger_path.clone(),
             config,
+            smoothed: Mutex::new(None),
+            watch: Mutex::new(None),
+            throttled: Mutex::new(false),
         };
 
         // Auto-detect battery and charger paths
@@ -172,6 +415,17 @@ impl PowerManager {
             .read_sysfs_int(&self.battery_path.join("current_now"))
             .unwrap_or(0);
         let current = current_ua as f32 / 1_000_000.0;
+        let current_now = self
+            .read_sysfs_int(&self.battery_path.join("current_now"))
+            .map(|ua| ua as f32 / 1_000_000.0);
+
+        // Charge in microamp hours, convert to amp hours
+        let charge = |name: &str| {
+            self.read_sysfs_int(&self.battery_path.join(name))
+                .map(|uah| uah as f32 / 1_000_000.0)
+        };
+        let charge_now = charge("charge_now");
+        let charge_full = charge("charge_full").or_else(|| charge("charge_full_design"));
 
         // Read status
         let status_str = fs::read_to_string(self.battery_path.join("status"))
@@ -204,10 +458,16 @@ impl PowerManager {
         // Determine charging state
         let is_charging = status == BatteryStatus::Charging || self.is_charger_connected();
 
+        let smoothed_percentage = self.smooth_percentage(percentage, status);
+
         Ok(BatteryInfo {
             percentage,
+            smoothed_percentage,
             voltage,
             current,
+            current_now,
+            charge_now,
+            charge_full,
             is_charging,
             status,
             health,
@@ -215,6 +475,27 @@ impl PowerManager {
         })
     }
 
+    /// Time until the battery is empty, or full while charging
+    pub fn time_remaining(&self) -> Option<Duration> {
+        self.get_battery_info().ok()?.time_remaining()
+    }
+
+    /// Fold a capacity reading into the moving average
+    ///
+    /// Plugging or unplugging the charger starts over, so the average
+    /// doesn't lag behind a change of direction.
+    fn smooth_percentage(&self, percentage: u8, status: BatteryStatus) -> u8 {
+        let Ok(mut smoothed) = self.smoothed.lock() else {
+            return percentage;
+        };
+        let previous = smoothed
+            .filter(|(_, previous_status)| *previous_status == status)
+            .map(|(value, _)| value);
+        let value = moving_average(previous, percentage as f32, BATTERY_SMOOTHING);
+        *smoothed = Some((value, status));
+        value.round().clamp(0.0, 100.0) as u8
+    }
+
     /// Read integer from sysfs file
     fn read_sysfs_int(&self, path: &Path) -> Option<i64> {
         fs::read_to_string(path)
@@ -254,6 +535,39 @@ impl PowerManager {
         false
     }
 
+    /// Read the battery and report threshold crossings and charger changes
+    ///
+    /// Call this periodically; each event is reported once per crossing.
+    /// Recovering needs [`BATTERY_HYSTERESIS`] percent above a threshold
+    /// before it can warn again.
+    pub fn poll_battery(&self) -> Vec<PowerEvent> {
+        match self.get_battery_info() {
+            Ok(info) => self.battery_events(&info),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Like [`PowerManager::poll_battery`] for a reading the caller already has
+    pub fn battery_events(&self, info: &BatteryInfo) -> Vec<PowerEvent> {
+        let Ok(mut watch) = self.watch.lock() else {
+            return Vec::new();
+        };
+
+        let (next, events) = BatteryWatch::update(
+            *watch,
+            info.percentage,
+            info.is_charging,
+            self.config.low_battery_threshold,
+            self.config.critical_battery_threshold,
+        );
+        *watch = Some(next);
+
+        for event in &events {
+            tracing::info!("Battery event: {:?}", event);
+        }
+        events
+    }
+
     /// Get current CPU governor
     pub fn get_governor(&self) -> Option<CpuGovernor> {
         let path = "/sys/devices/system/cpu/cpu0/cpufreq/scaling_governor";
@@ -262,28 +576,44 @@ impl PowerManager {
             .and_then(|s| CpuGovernor::parse(&s))
     }
 
+    /// Governor shared by every CPU policy
+    ///
+    /// `None` if cpufreq isn't available or the policies disagree, which
+    /// usually means something outside RexOS changed one of them.
+    pub fn active_governor(&self) -> Option<CpuGovernor> {
+        active_governor_in(Path::new(CPUFREQ_DIR))
+    }
+
     /// Set CPU governor for all CPUs
     pub fn set_governor(&self, governor: CpuGovernor) -> Result<(), DeviceError> {
-        let cpu_dir = Path::new("/sys/devices/system/cpu");
-
-        for entry in fs::read_dir(cpu_dir)? {
-            let entry = entry?;
-            let name = entry.file_name().to_string_lossy().to_string();
-
-            if name.starts_with("cpu") && name.chars().nth(3).is_some_and(|c| c.is_ascii_digit()) {
-                let governor_path = entry.path().join("cpufreq/scaling_governor");
-                if governor_path.exists() {
-                    fs::write(&governor_path, governor.as_str()).map_err(|e| {
-                        DeviceError::InitializationFailed(format!("Failed to set governor: {}", e))
-                    })?;
-                }
-            }
+        for policy in sysfs_dirs(Path::new(CPUFREQ_DIR), |name| name.starts_with("policy")) {
+            set_policy_governor(&policy, governor)?;
         }
 
         tracing::info!("CPU governor set to {}", governor.as_str());
         Ok(())
     }
 
+    /// Apply the governor for a configured performance profile
+    pub fn apply_profile(&self, profile: PerformanceProfile) -> Result<(), DeviceError> {
+        self.apply_cpu_profile(CpuProfile::from(profile))
+    }
+
+    /// Apply a governor and frequency range to every CPU policy
+    ///
+    /// Clusters on big.LITTLE SoCs have their own policy, so writing only
+    /// cpu0 would leave the other cluster on its old settings.
+    pub fn apply_cpu_profile(&self, profile: CpuProfile) -> Result<(), DeviceError> {
+        apply_cpu_profile_in(Path::new(CPUFREQ_DIR), profile)?;
+        tracing::info!(
+            "CPU profile applied: {} ({:?}-{:?} kHz)",
+            profile.governor.as_str(),
+            profile.min_khz,
+            profile.max_khz
+        );
+        Ok(())
+    }
+
     /// Get available governors
     pub fn available_governors(&self) -> Vec<CpuGovernor> {
         let path = "/sys/devices/system/cpu/cpu0/cpufreq/scaling_available_governors";
@@ -305,6 +635,58 @@ impl PowerManager {
             .map(|khz| khz * 1000) // Convert to Hz
     }
 
+    /// Get SoC/CPU temperature in degrees Celsius
+    ///
+    /// Uses the thermal zone named for the CPU or SoC, else the first one.
+    pub fn get_cpu_temperature(&self) -> Option<f32> {
+        let zones = sysfs_dirs(Path::new(THERMAL_DIR), |name| {
+            name.starts_with("thermal_zone")
+        });
+        let zone = zones
+            .iter()
+            .find(|zone| {
+                fs::read_to_string(zone.join("type"))
+                    .is_ok_and(|t| t.contains("cpu") || t.contains("soc"))
+            })
+            .or(zones.first())?;
+
+        // Millidegrees Celsius
+        self.read_sysfs_int(&zone.join("temp"))
+            .map(|millis| millis as f32 / 1000.0)
+    }
+
+    /// Temperature of the hottest thermal zone and its trip point
+    pub fn thermal_status(&self) -> Option<ThermalStatus> {
+        thermal_status_in(Path::new(THERMAL_DIR))
+    }
+
+    /// Report when the temperature reaches the configured threshold
+    ///
+    /// Call this periodically. The event is reported once, and again only
+    /// after cooling [`THERMAL_HYSTERESIS`] degrees below the threshold.
+    pub fn poll_thermal(&self) -> Option<PowerEvent> {
+        let status = self.thermal_status()?;
+        let mut throttled = self.throttled.lock().ok()?;
+        let event = thermal_event(&mut throttled, status, self.config.thermal_threshold);
+        if let Some(event) = event {
+            tracing::warn!(
+                "Thermal event: {:?} (trip point {:?})",
+                event,
+                status.trip_point
+            );
+        }
+        event
+    }
+
+    /// Cap CPU and GPU frequencies until the returned guard is dropped
+    ///
+    /// Requests are clamped to the highest supported frequency at or below
+    /// them (or the lowest one). Caps are refused if the device doesn't
+    /// publish its supported frequencies.
+    pub fn set_freq_limits(&self, limits: FreqLimits) -> Result<FreqGuard, DeviceError> {
+        set_freq_limits_in(Path::new(CPUFREQ_DIR), Path::new(DEVFREQ_DIR), limits)
+    }
+
     /// Suspend the system
     pub fn suspend(&self) -> Result<(), DeviceError> {
         tracing::info!("Suspending system...");
@@ -355,10 +737,289 @@ impl Default for PowerManager {
             config: PowerConfig::default(),
             battery_path: PathBuf::from("/sys/class/power_supply/battery"),
             charger_path: PathBuf::from("/sys/class/power_supply/usb"),
+            smoothed: Mutex::new(None),
+            watch: Mutex::new(None),
+            throttled: Mutex::new(false),
+        })
+    }
+}
+
+/// Apply frequency caps under the given cpufreq and devfreq directories
+fn set_freq_limits_in(
+    cpufreq_dir: &Path,
+    devfreq_dir: &Path,
+    limits: FreqLimits,
+) -> Result<FreqGuard, DeviceError> {
+    // Caps already written are reverted if a later one fails
+    let mut guard = FreqGuard::default();
+
+    if let Some(khz) = limits.cpu_max_khz {
+        for policy in sysfs_dirs(cpufreq_dir, |name| name.starts_with("policy")) {
+            cap_frequency(
+                &policy,
+                "scaling_max_freq",
+                "scaling_available_frequencies",
+                khz,
+                &mut guard,
+            )?;
+        }
+    }
+
+    if let Some(hz) = limits.gpu_max_hz {
+        for device in sysfs_dirs(devfreq_dir, |name| {
+            name.contains("gpu") || name.contains("mali")
+        }) {
+            cap_frequency(&device, "max_freq", "available_frequencies", hz, &mut guard)?;
+        }
+    }
+
+    Ok(guard)
+}
+
+/// Apply a CPU profile to the policies under a cpufreq directory
+fn apply_cpu_profile_in(cpufreq_dir: &Path, profile: CpuProfile) -> Result<(), DeviceError> {
+    for policy in sysfs_dirs(cpufreq_dir, |name| name.starts_with("policy")) {
+        set_policy_governor(&policy, profile.governor)?;
+
+        // Drop the minimum first so a lower maximum is never below it
+        let hardware_min = read_khz(&policy.join("cpuinfo_min_freq"));
+        if let Some(khz) = hardware_min {
+            write_policy(&policy, "scaling_min_freq", khz)?;
+        }
+        if let Some(khz) = policy_freq(&policy, profile.max_khz, "cpuinfo_max_freq") {
+            write_policy(&policy, "scaling_max_freq", khz)?;
+        }
+        if let Some(khz) = policy_freq(&policy, profile.min_khz, "cpuinfo_min_freq") {
+            write_policy(&policy, "scaling_min_freq", khz)?;
+        }
+    }
+    Ok(())
+}
+
+/// Requested frequency clamped to the policy's table, or the hardware limit
+fn policy_freq(policy: &Path, requested: Option<u64>, hardware_file: &str) -> Option<u64> {
+    let Some(requested) = requested else {
+        return read_khz(&policy.join(hardware_file));
+    };
+
+    let available: Vec<u64> = fs::read_to_string(policy.join("scaling_available_frequencies"))
+        .map(|s| {
+            s.split_whitespace()
+                .filter_map(|f| f.parse().ok())
+                .collect()
         })
+        .unwrap_or_default();
+    if available.is_empty() {
+        Some(requested)
+    } else {
+        clamp_to_opp(requested, &available)
     }
 }
 
+/// Set a policy's governor, falling back when the kernel lacks it
+fn set_policy_governor(policy: &Path, governor: CpuGovernor) -> Result<(), DeviceError> {
+    let available = fs::read_to_string(policy.join("scaling_available_governors"))
+        .map(|s| {
+            s.split_whitespace()
+                .filter_map(CpuGovernor::parse)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    let governor = match governor.fallback() {
+        Some(fallback) if !available.is_empty() && !available.contains(&governor) => {
+            tracing::warn!(
+                "{} has no {} governor, using {}",
+                policy.display(),
+                governor.as_str(),
+                fallback.as_str()
+            );
+            fallback
+        }
+        _ => governor,
+    };
+
+    fs::write(policy.join("scaling_governor"), governor.as_str())
+        .map_err(|e| DeviceError::InitializationFailed(format!("Failed to set governor: {}", e)))
+}
+
+fn write_policy(policy: &Path, file: &str, khz: u64) -> Result<(), DeviceError> {
+    fs::write(policy.join(file), khz.to_string()).map_err(|e| {
+        DeviceError::InvalidFrequency(format!("{} for {}: {}", khz, policy.display(), e))
+    })
+}
+
+fn read_khz(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Governor shared by the policies under a cpufreq directory
+fn active_governor_in(cpufreq_dir: &Path) -> Option<CpuGovernor> {
+    let governors: Vec<(PathBuf, Option<CpuGovernor>)> =
+        sysfs_dirs(cpufreq_dir, |name| name.starts_with("policy"))
+            .into_iter()
+            .map(|policy| {
+                let governor = fs::read_to_string(policy.join("scaling_governor"))
+                    .ok()
+                    .and_then(|s| CpuGovernor::parse(&s));
+                (policy, governor)
+            })
+            .collect();
+
+    let (_, first) = governors.first()?;
+    if governors.iter().any(|(_, governor)| governor != first) {
+        tracing::warn!("CPU policies have different governors: {:?}", governors);
+        return None;
+    }
+    *first
+}
+
+/// Subdirectories of a sysfs class directory with matching names
+fn sysfs_dirs(dir: &Path, matches: impl Fn(&str) -> bool) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| matches(&entry.file_name().to_string_lossy()))
+                .map(|entry| entry.path())
+                .collect()
+        })
+        .unwrap_or_default();
+    dirs.sort();
+    dirs
+}
+
+/// Write a clamped maximum frequency, recording the old one in `guard`
+fn cap_frequency(
+    dir: &Path,
+    max_file: &str,
+    available_file: &str,
+    requested: u64,
+    guard: &mut FreqGuard,
+) -> Result<(), DeviceError> {
+    let available: Vec<u64> = fs::read_to_string(dir.join(available_file))
+        .map(|s| {
+            s.split_whitespace()
+                .filter_map(|f| f.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let Some(freq) = clamp_to_opp(requested, &available) else {
+        return Err(DeviceError::InvalidFrequency(format!(
+            "{} for {} (supported: {:?})",
+            requested,
+            dir.display(),
+            available
+        )));
+    };
+    if freq != requested {
+        tracing::debug!("Clamped {} to {} for {}", requested, freq, dir.display());
+    }
+
+    let path = dir.join(max_file);
+    let previous = fs::read_to_string(&path)?;
+    fs::write(&path, freq.to_string())?;
+    guard.restore.push((path, previous.trim().to_string()));
+
+    tracing::info!("Capped {} at {}", dir.display(), freq);
+    Ok(())
+}
+
+/// Hottest thermal zone under a thermal class directory
+fn thermal_status_in(thermal_dir: &Path) -> Option<ThermalStatus> {
+    sysfs_dirs(thermal_dir, |name| name.starts_with("thermal_zone"))
+        .iter()
+        .filter_map(|zone| {
+            let temperature = read_millidegrees(&zone.join("temp"))?;
+            Some(ThermalStatus {
+                temperature,
+                trip_point: passive_trip_point(zone),
+            })
+        })
+        .max_by(|a, b| a.temperature.total_cmp(&b.temperature))
+}
+
+/// Lowest passive trip point of a thermal zone
+fn passive_trip_point(zone: &Path) -> Option<f32> {
+    (0..)
+        .map_while(|i| {
+            let kind = fs::read_to_string(zone.join(format!("trip_point_{}_type", i))).ok()?;
+            Some((kind, zone.join(format!("trip_point_{}_temp", i))))
+        })
+        .filter(|(kind, _)| kind.trim() == "passive")
+        .filter_map(|(_, temp)| read_millidegrees(&temp))
+        .min_by(|a, b| a.total_cmp(b))
+}
+
+fn read_millidegrees(path: &Path) -> Option<f32> {
+    let millis: i64 = fs::read_to_string(path).ok()?.trim().parse().ok()?;
+    Some(millis as f32 / 1000.0)
+}
+
+/// Update the throttle state, returning an event when it starts
+pub(crate) fn thermal_event(
+    throttled: &mut bool,
+    status: ThermalStatus,
+    threshold: f32,
+) -> Option<PowerEvent> {
+    let was_throttled = *throttled;
+    *throttled = if was_throttled {
+        status.temperature > threshold - THERMAL_HYSTERESIS
+    } else {
+        status.temperature >= threshold
+    };
+
+    (*throttled && !was_throttled)
+        .then(|| PowerEvent::ThermalThrottle(status.temperature.round() as i32))
+}
+
+/// Warning level for a discharging battery
+///
+/// A level is only left once the battery is [`BATTERY_HYSTERESIS`] above
+/// its threshold.
+fn battery_level(previous: BatteryLevel, percentage: u8, low: u8, critical: u8) -> BatteryLevel {
+    let threshold = |threshold: u8, level: BatteryLevel| {
+        if previous >= level {
+            threshold.saturating_add(BATTERY_HYSTERESIS)
+        } else {
+            threshold
+        }
+    };
+
+    if percentage <= threshold(critical, BatteryLevel::Critical) {
+        BatteryLevel::Critical
+    } else if percentage <= threshold(low, BatteryLevel::Low) {
+        BatteryLevel::Low
+    } else {
+        BatteryLevel::Normal
+    }
+}
+
+/// Exponential moving average, starting at the first sample
+fn moving_average(previous: Option<f32>, sample: f32, weight: f32) -> f32 {
+    match previous {
+        Some(previous) => previous + weight * (sample - previous),
+        None => sample,
+    }
+}
+
+/// Highest supported frequency at or below `requested`, else the lowest
+///
+/// `None` for a zero request or an empty table, so nothing unsupported is
+/// ever written.
+fn clamp_to_opp(requested: u64, available: &[u64]) -> Option<u64> {
+    if requested == 0 {
+        return None;
+    }
+    available
+        .iter()
+        .copied()
+        .filter(|&f| f <= requested)
+        .max()
+        .or_else(|| available.iter().copied().min())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -370,6 +1031,265 @@ mod tests {
         assert_eq!(config.critical_battery_threshold, 5);
     }
 
+    fn battery(status: BatteryStatus, current_now: Option<f32>) -> BatteryInfo {
+        BatteryInfo {
+            percentage: 50,
+            smoothed_percentage: 50,
+            voltage: 3.8,
+            current: current_now.unwrap_or(0.0),
+            current_now,
+            charge_now: Some(1.5),
+            charge_full: Some(3.0),
+            is_charging: status == BatteryStatus::Charging,
+            status,
+            health: BatteryHealth::Good,
+            temperature: 25.0,
+        }
+    }
+
+    #[test]
+    fn test_time_remaining() {
+        // 1.5Ah left at 0.75A lasts two hours
+        let info = battery(BatteryStatus::Discharging, Some(-0.75));
+        assert_eq!(info.time_remaining(), Some(Duration::from_secs(7200)));
+
+        // 1.5Ah to go at 1.5A takes an hour
+        let info = battery(BatteryStatus::Charging, Some(1.5));
+        assert_eq!(info.time_remaining(), Some(Duration::from_secs(3600)));
+
+        assert_eq!(
+            battery(BatteryStatus::Discharging, None).time_remaining(),
+            None
+        );
+        assert_eq!(
+            battery(BatteryStatus::Discharging, Some(0.0)).time_remaining(),
+            None
+        );
+        assert_eq!(
+            battery(BatteryStatus::Full, Some(0.5)).time_remaining(),
+            None
+        );
+
+        // Charge derived from the percentage when charge_now is missing
+        let mut info = battery(BatteryStatus::Discharging, Some(1.5));
+        info.charge_now = None;
+        assert_eq!(info.time_remaining(), Some(Duration::from_secs(3600)));
+        info.charge_full = None;
+        assert_eq!(info.time_remaining(), None);
+    }
+
+    #[test]
+    fn test_moving_average() {
+        assert_eq!(moving_average(None, 80.0, BATTERY_SMOOTHING), 80.0);
+
+        // A one-off dip barely moves the average
+        let mut value = 80.0;
+        for sample in [80.0, 74.0, 80.0, 80.0] {
+            value = moving_average(Some(value), sample, BATTERY_SMOOTHING);
+        }
+        assert!((value - 79.0).abs() < 1.0, "{}", value);
+    }
+
+    #[test]
+    fn test_battery_events() {
+        let mut watch = None;
+        let mut poll = |percentage, charging| {
+            let (next, events) = BatteryWatch::update(watch, percentage, charging, 20, 5);
+            watch = Some(next);
+            events
+        };
+
+        assert!(poll(50, false).is_empty());
+        assert_eq!(poll(20, false), vec![PowerEvent::LowBattery(20)]);
+
+        // Wobbling around the threshold doesn't warn again
+        assert!(poll(21, false).is_empty());
+        assert!(poll(19, false).is_empty());
+        assert!(poll(24, false).is_empty());
+        assert_eq!(poll(20, false), vec![PowerEvent::LowBattery(20)]);
+
+        assert_eq!(poll(5, false), vec![PowerEvent::CriticalBattery(5)]);
+        assert!(poll(7, false).is_empty());
+        assert_eq!(poll(7, true), vec![PowerEvent::Charging]);
+        assert_eq!(
+            poll(7, false),
+            vec![PowerEvent::Unplugged, PowerEvent::LowBattery(7)]
+        );
+
+        // Already critical on the first poll
+        let (_, events) = BatteryWatch::update(None, 3, false, 20, 5);
+        assert_eq!(events, vec![PowerEvent::CriticalBattery(3)]);
+    }
+
+    #[test]
+    fn test_clamp_to_opp() {
+        let opp = [408000, 816000, 1200000, 1800000];
+        assert_eq!(clamp_to_opp(1200000, &opp), Some(1200000));
+        assert_eq!(clamp_to_opp(1000000, &opp), Some(816000));
+        assert_eq!(clamp_to_opp(100000, &opp), Some(408000));
+        assert_eq!(clamp_to_opp(9000000, &opp), Some(1800000));
+        assert_eq!(clamp_to_opp(0, &opp), None);
+        assert_eq!(clamp_to_opp(1200000, &[]), None);
+    }
+
+    #[test]
+    fn test_freq_limits_reverted() {
+        let root = std::env::temp_dir().join(format!("rexos-freq-{}", std::process::id()));
+        let policy = root.join("cpufreq/policy0");
+        let gpu = root.join("devfreq/fde60000.gpu");
+        fs::create_dir_all(&policy).unwrap();
+        fs::create_dir_all(&gpu).unwrap();
+        fs::write(
+            policy.join("scaling_available_frequencies"),
+            "408000 816000 1800000 \n",
+        )
+        .unwrap();
+        fs::write(policy.join("scaling_max_freq"), "1800000\n").unwrap();
+        fs::write(
+            gpu.join("available_frequencies"),
+            "200000000 400000000 800000000\n",
+        )
+        .unwrap();
+        fs::write(gpu.join("max_freq"), "800000000\n").unwrap();
+
+        let limits = FreqLimits {
+            cpu_max_khz: Some(1000000),
+            gpu_max_hz: Some(400000000),
+        };
+        let guard =
+            set_freq_limits_in(&root.join("cpufreq"), &root.join("devfreq"), limits).unwrap();
+        assert_eq!(
+            fs::read_to_string(policy.join("scaling_max_freq")).unwrap(),
+            "816000"
+        );
+        assert_eq!(
+            fs::read_to_string(gpu.join("max_freq")).unwrap(),
+            "400000000"
+        );
+
+        drop(guard);
+        assert_eq!(
+            fs::read_to_string(policy.join("scaling_max_freq")).unwrap(),
+            "1800000"
+        );
+        assert_eq!(
+            fs::read_to_string(gpu.join("max_freq")).unwrap(),
+            "800000000"
+        );
+
+        // No frequency table: refuse rather than write a guess
+        fs::remove_file(gpu.join("available_frequencies")).unwrap();
+        let limits = FreqLimits {
+            cpu_max_khz: Some(1000000),
+            gpu_max_hz: Some(400000000),
+        };
+        assert!(set_freq_limits_in(&root.join("cpufreq"), &root.join("devfreq"), limits).is_err());
+        assert_eq!(
+            fs::read_to_string(policy.join("scaling_max_freq")).unwrap(),
+            "1800000"
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_apply_cpu_profile() {
+        let root = std::env::temp_dir().join(format!("rexos-profile-{}", std::process::id()));
+        for (policy, max) in [("policy0", "1416000"), ("policy4", "1800000")] {
+            let dir = root.join(policy);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(
+                dir.join("scaling_available_governors"),
+                "ondemand performance powersave\n",
+            )
+            .unwrap();
+            fs::write(
+                dir.join("scaling_available_frequencies"),
+                format!("408000 816000 {}\n", max),
+            )
+            .unwrap();
+            fs::write(dir.join("cpuinfo_min_freq"), "408000\n").unwrap();
+            fs::write(dir.join("cpuinfo_max_freq"), format!("{}\n", max)).unwrap();
+            fs::write(dir.join("scaling_governor"), "performance\n").unwrap();
+        }
+        let policy4 = root.join("policy4");
+
+        // Every cluster gets the profile, schedutil falls back to ondemand
+        apply_cpu_profile_in(&root, CpuProfile::from(PerformanceProfile::Balanced)).unwrap();
+        assert_eq!(active_governor_in(&root), Some(CpuGovernor::Ondemand));
+        assert_eq!(
+            fs::read_to_string(policy4.join("scaling_max_freq")).unwrap(),
+            "1800000"
+        );
+
+        let capped = CpuProfile {
+            governor: CpuGovernor::Performance,
+            min_khz: Some(816000),
+            max_khz: Some(1000000),
+        };
+        apply_cpu_profile_in(&root, capped).unwrap();
+        assert_eq!(
+            fs::read_to_string(policy4.join("scaling_max_freq")).unwrap(),
+            "816000"
+        );
+        assert_eq!(
+            fs::read_to_string(policy4.join("scaling_min_freq")).unwrap(),
+            "816000"
+        );
+
+        // Diverging policies have no common governor
+        fs::write(policy4.join("scaling_governor"), "powersave\n").unwrap();
+        assert_eq!(active_governor_in(&root), None);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_thermal_status() {
+        let root = std::env::temp_dir().join(format!("rexos-thermal-{}", std::process::id()));
+        let cpu = root.join("thermal_zone0");
+        let gpu = root.join("thermal_zone1");
+        fs::create_dir_all(&cpu).unwrap();
+        fs::create_dir_all(&gpu).unwrap();
+        fs::write(cpu.join("temp"), "72500\n").unwrap();
+        fs::write(cpu.join("trip_point_0_type"), "passive\n").unwrap();
+        fs::write(cpu.join("trip_point_0_temp"), "75000\n").unwrap();
+        fs::write(cpu.join("trip_point_1_type"), "critical\n").unwrap();
+        fs::write(cpu.join("trip_point_1_temp"), "115000\n").unwrap();
+        fs::write(gpu.join("temp"), "61000\n").unwrap();
+
+        let status = thermal_status_in(&root).unwrap();
+        assert_eq!(status.temperature, 72.5);
+        assert_eq!(status.trip_point, Some(75.0));
+        assert!(status.near_trip_point());
+
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(thermal_status_in(&root), None);
+    }
+
+    #[test]
+    fn test_thermal_event() {
+        let status = |temperature| ThermalStatus {
+            temperature,
+            trip_point: None,
+        };
+        let mut throttled = false;
+
+        assert_eq!(thermal_event(&mut throttled, status(70.0), 80.0), None);
+        assert_eq!(
+            thermal_event(&mut throttled, status(80.4), 80.0),
+            Some(PowerEvent::ThermalThrottle(80))
+        );
+        // Reported once, and not again while hovering near the threshold
+        assert_eq!(thermal_event(&mut throttled, status(82.0), 80.0), None);
+        assert_eq!(thermal_event(&mut throttled, status(78.0), 80.0), None);
+        assert_eq!(thermal_event(&mut throttled, status(80.0), 80.0), None);
+
+        assert_eq!(thermal_event(&mut throttled, status(74.0), 80.0), None);
+        assert!(!throttled);
+        assert!(thermal_event(&mut throttled, status(81.0), 80.0).is_some());
+    }
+
     #[test]
     fn test_cpu_governor_str() {
         assert_eq!(CpuGovernor::Performance.as_str(), "performance");
@@ -0,0 +1,304 @@
+//! Force-feedback (rumble) via evdev
+//!
+//! Uploads an `FF_RUMBLE` effect with `EVIOCSFF` and plays it by writing an
+//! `EV_FF` event. Layouts mirror `struct ff_effect` from `linux/input.h`.
+
+use crate::DeviceError;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Force-feedback event type
+pub const EV_FF: u16 = 0x15;
+
+/// Rumble effect type
+pub const FF_RUMBLE: u16 = 0x50;
+
+/// `_IOW('E', 0x80, struct ff_effect)`
+pub const EVIOCSFF: libc::c_ulong = ioc_write(b'E', 0x80, std::mem::size_of::<FfEffect>());
+
+/// `_IOW('E', 0x81, int)`
+pub const EVIOCRMFF: libc::c_ulong = ioc_write(b'E', 0x81, std::mem::size_of::<libc::c_int>());
+
+/// Encode a write ioctl request number (asm-generic layout)
+const fn ioc_write(ty: u8, nr: u8, size: usize) -> libc::c_ulong {
+    const IOC_WRITE: libc::c_ulong = 1;
+    (IOC_WRITE << 30)
+        | ((size as libc::c_ulong) << 16)
+        | ((ty as libc::c_ulong) << 8)
+        | nr as libc::c_ulong
+}
+
+/// `struct ff_trigger`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FfTrigger {
+    pub button: u16,
+    pub interval: u16,
+}
+
+/// `struct ff_replay`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FfReplay {
+    /// Duration in milliseconds
+    pub length: u16,
+    /// Delay before start in milliseconds
+    pub delay: u16,
+}
+
+/// `struct ff_envelope`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FfEnvelope {
+    pub attack_length: u16,
+    pub attack_level: u16,
+    pub fade_length: u16,
+    pub fade_level: u16,
+}
+
+/// `struct ff_rumble_effect`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FfRumbleEffect {
+    pub strong_magnitude: u16,
+    pub weak_magnitude: u16,
+}
+
+/// `struct ff_periodic_effect`
+///
+/// Only present so the effect union has the kernel's size and alignment.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FfPeriodicEffect {
+    pub waveform: u16,
+    pub period: u16,
+    pub magnitude: i16,
+    pub offset: i16,
+    pub phase: u16,
+    pub envelope: FfEnvelope,
+    pub custom_len: u32,
+    pub custom_data: *mut i16,
+}
+
+/// Effect parameters union
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union FfEffectData {
+    pub rumble: FfRumbleEffect,
+    pub periodic: FfPeriodicEffect,
+}
+
+/// `struct ff_effect`
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct FfEffect {
+    pub effect_type: u16,
+    /// Effect id, -1 to allocate a new one
+    pub id: i16,
+    pub direction: u16,
+    pub trigger: FfTrigger,
+    pub replay: FfReplay,
+    pub u: FfEffectData,
+}
+
+impl FfEffect {
+    /// Build a rumble effect
+    ///
+    /// `strength` is clamped to 0.0-1.0 and drives both motors; duration is
+    /// capped at the kernel's u16 millisecond limit.
+    pub fn rumble(id: i16, strength: f32, duration: Duration) -> Self {
+        let magnitude = (strength.clamp(0.0, 1.0) * u16::MAX as f32) as u16;
+        let length = duration.as_millis().min(u16::MAX as u128) as u16;
+
+        // Zero the whole union so the unused bytes are deterministic
+        let mut u = FfEffectData {
+            periodic: FfPeriodicEffect {
+                waveform: 0,
+                period: 0,
+                magnitude: 0,
+                offset: 0,
+                phase: 0,
+                envelope: FfEnvelope::default(),
+                custom_len: 0,
+                custom_data: std::ptr::null_mut(),
+            },
+        };
+        u.rumble = FfRumbleEffect {
+            strong_magnitude: magnitude,
+            weak_magnitude: magnitude,
+        };
+
+        Self {
+            effect_type: FF_RUMBLE,
+            id,
+            direction: 0,
+            trigger: FfTrigger::default(),
+            replay: FfReplay { length, delay: 0 },
+            u,
+        }
+    }
+
+    /// Get the rumble parameters
+    pub fn rumble_params(&self) -> FfRumbleEffect {
+        // SAFETY: every variant is plain data; reading as rumble is always valid
+        unsafe { self.u.rumble }
+    }
+}
+
+/// A rumble-capable input device
+pub struct RumbleDevice {
+    file: File,
+    path: PathBuf,
+    effect_id: i16,
+}
+
+impl RumbleDevice {
+    /// Open an event device for force-feedback (needs write access)
+    pub fn open(path: &Path) -> Result<Self, DeviceError> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        Ok(Self {
+            file,
+            path: path.to_path_buf(),
+            effect_id: -1,
+        })
+    }
+
+    /// Event device path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Upload and play a rumble effect
+    pub fn rumble(&mut self, strength: f32, duration: Duration) -> Result<(), DeviceError> {
+        let mut effect = FfEffect::rumble(self.effect_id, strength, duration);
+
+        // SAFETY: EVIOCSFF takes a pointer to a struct ff_effect, which FfEffect mirrors
+        let ret = unsafe {
+            libc::ioctl(
+                self.file.as_raw_fd(),
+                EVIOCSFF as _,
+                &mut effect as *mut FfEffect,
+            )
+        };
+        if ret < 0 {
+            return Err(DeviceError::Io(std::io::Error::last_os_error()));
+        }
+
+        // The kernel assigns an id on first upload; reuse it afterwards
+        self.effect_id = effect.id;
+
+        self.write_event(effect.id as u16, 1)
+    }
+
+    /// Stop the current effect
+    pub fn stop(&mut self) -> Result<(), DeviceError> {
+        if self.effect_id < 0 {
+            return Ok(());
+        }
+
+        self.write_event(self.effect_id as u16, 0)
+    }
+
+    /// Write an `EV_FF` event, using libc's `input_event` so `timeval`
+    /// matches the target's layout
+    fn write_event(&mut self, code: u16, value: i32) -> Result<(), DeviceError> {
+        let event = libc::input_event {
+            time: libc::timeval {
+                tv_sec: 0,
+                tv_usec: 0,
+            },
+            type_: EV_FF,
+            code,
+            value,
+        };
+        // SAFETY: input_event is repr(C) plain data
+        let bytes = unsafe {
+            std::slice::from_raw_parts(
+                &event as *const libc::input_event as *const u8,
+                std::mem::size_of::<libc::input_event>(),
+            )
+        };
+        self.file.write_all(bytes)?;
+        Ok(())
+    }
+}
+
+impl Drop for RumbleDevice {
+    fn drop(&mut self) {
+        if self.effect_id >= 0 {
+            // SAFETY: EVIOCRMFF takes the effect id by value
+            unsafe {
+                libc::ioctl(
+                    self.file.as_raw_fd(),
+                    EVIOCRMFF as _,
+                    self.effect_id as libc::c_int,
+                );
+            }
+        }
+    }
+}
+
+/// Check if an event device advertises force-feedback in sysfs
+pub fn has_force_feedback(event_name: &str) -> bool {
+    let path = format!("/sys/class/input/{}/device/capabilities/ff", event_name);
+    std::fs::read_to_string(path)
+        .map(|s| s.split_whitespace().any(|word| word != "0"))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::{align_of, offset_of, size_of};
+
+    #[test]
+    fn test_ff_effect_layout() {
+        assert_eq!(offset_of!(FfEffect, effect_type), 0);
+        assert_eq!(offset_of!(FfEffect, id), 2);
+        assert_eq!(offset_of!(FfEffect, direction), 4);
+        assert_eq!(offset_of!(FfEffect, trigger), 6);
+        assert_eq!(offset_of!(FfEffect, replay), 10);
+        assert_eq!(offset_of!(FfEffect, u), 16);
+        assert_eq!(align_of::<FfEffect>(), align_of::<usize>());
+
+        #[cfg(target_pointer_width = "64")]
+        {
+            assert_eq!(size_of::<FfEffect>(), 48);
+            assert_eq!(EVIOCSFF, 0x4030_4580);
+        }
+        #[cfg(target_pointer_width = "32")]
+        {
+            assert_eq!(size_of::<FfEffect>(), 44);
+            assert_eq!(EVIOCSFF, 0x402c_4580);
+        }
+        assert_eq!(EVIOCRMFF, 0x4004_4581);
+    }
+
+    #[test]
+    fn test_input_event_layout() {
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(size_of::<libc::input_event>(), 24);
+        #[cfg(target_pointer_width = "32")]
+        assert_eq!(size_of::<libc::input_event>(), 16);
+    }
+
+    #[test]
+    fn test_rumble_effect() {
+        let effect = FfEffect::rumble(-1, 0.5, Duration::from_millis(200));
+        assert_eq!(effect.effect_type, FF_RUMBLE);
+        assert_eq!(effect.id, -1);
+        assert_eq!(effect.replay.length, 200);
+
+        let params = effect.rumble_params();
+        assert_eq!(params.strong_magnitude, 32767);
+        assert_eq!(params.weak_magnitude, 32767);
+
+        // Clamped strength and duration
+        let effect = FfEffect::rumble(3, 2.0, Duration::from_secs(120));
+        assert_eq!(effect.rumble_params().strong_magnitude, u16::MAX);
+        assert_eq!(effect.replay.length, u16::MAX);
+    }
+}
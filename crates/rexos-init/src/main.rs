@@ -8,8 +8,15 @@
 //! 2. Initialize hardware (display, input, audio)
 //! 3. Start system services
 //! 4. Launch frontend (EmulationStation or custom launcher)
+//!
+//! Holding the recovery combo (Start+Select by default) during early boot
+//! launches the recovery menu instead of the frontend.
+//!
+//! After an update, the first boot checks that the frontend stays up and
+//! rolls the update back if it keeps crashing.
 
 use anyhow::{Context, Result};
+use rexos_storage::MountManager;
 use std::fs;
 use std::path::Path;
 use std::process::{Child, Command, Stdio};
@@ -72,6 +79,9 @@ fn main() -> Result<()> {
     }
     log_stage_complete(BootStage::Filesystems, stage_start);
 
+    // Watch for the recovery combo while hardware and services come up
+    let recovery_check = std::thread::spawn(recovery::combo_held);
+
     // Stage 2: Initialize hardware
     let stage_start = Instant::now();
     if let Err(e) = initialize_hardware() {
@@ -91,7 +101,21 @@ fn main() -> Result<()> {
 
     // Stage 4: Launch frontend
     let stage_start = Instant::now();
-    let frontend_child = match launch_frontend() {
+    let recovery_requested = recovery_check.join().unwrap_or(false);
+
+    // Only a normal boot can verify a freshly installed update
+    let pending_update = if recovery_requested {
+        None
+    } else {
+        update_check::PendingUpdate::detect()
+    };
+
+    let launched = if recovery_requested {
+        recovery::launch()
+    } else {
+        launch_frontend()
+    };
+    let frontend_child = match launched {
         Ok(child) => child,
         Err(e) => {
             error!("CRITICAL: Frontend launch failed: {}", e);
@@ -101,13 +125,24 @@ fn main() -> Result<()> {
     };
     log_stage_complete(BootStage::Frontend, stage_start);
 
+    // An update that can't even start the frontend is rolled back at once
+    // Avoid if-let chains for MSRV 1.85 compatibility
+    #[allow(clippy::collapsible_if)]
+    if frontend_child.is_none() {
+        if let Some(update) = pending_update {
+            update.roll_back();
+            shutdown::reboot();
+            return Ok(());
+        }
+    }
+
     info!("Boot complete in {:?}", boot_start.elapsed());
 
     // Write boot time to file for monitoring
     let _ = write_boot_time(boot_start.elapsed());
 
     // Enter main loop (handle signals, reap zombies, watchdog frontend)
-    main_loop(frontend_child)
+    main_loop(frontend_child, pending_update)
 }
 
 /// Setup logging to console and file
@@ -266,16 +301,45 @@ fn mount_roms_partition() -> Result<()> {
         "/dev/sda1",      // USB drive
     ];
 
+    // A dirty exFAT card after power loss can be repaired and retried
+    let storage = rexos_config::RexOSConfig::load_default()
+        .map(|config| config.system.storage)
+        .unwrap_or_default();
+    let mut mounts = MountManager::default();
+    // Options follow the detected filesystem (exFAT cards get noatime and
+    // the ROM owner's uid/gid)
+    let options = [];
+
     for device in &candidates {
         if Path::new(device).exists() {
             // Try to mount with auto-detect filesystem
-            let result = Command::new("mount")
-                .args(["-o", "rw,noatime", device, roms_mount])
-                .output();
-
-            if result.map(|o| o.status.success()).unwrap_or(false) {
-                info!("Mounted ROMs partition from {}", device);
-                return Ok(());
+            let result = if storage.auto_repair {
+                mounts.mount_with_repair(
+                    device,
+                    Path::new(roms_mount),
+                    None,
+                    &options,
+                    storage.repair_dry_run,
+                )
+            } else {
+                mounts
+                    .mount(device, Path::new(roms_mount), None, &options)
+                    .map(|()| None)
+            };
+
+            match result {
+                Ok(None) => {
+                    info!("Mounted ROMs partition from {}", device);
+                    return Ok(());
+                }
+                Ok(Some(outcome)) => {
+                    info!(
+                        "Mounted ROMs partition from {} after repair ({:?})",
+                        device, outcome
+                    );
+                    return Ok(());
+                }
+                Err(e) => debug!("Could not mount {}: {}", device, e),
             }
         }
     }
@@ -296,6 +360,9 @@ fn initialize_hardware() -> Result<()> {
         device.profile().chipset
     );
 
+    // First boot: start from settings tuned for this device
+    write_device_defaults(&device.profile().id);
+
     // Initialize display
     init_display(&device)?;
 
@@ -311,19 +378,50 @@ fn initialize_hardware() -> Result<()> {
     Ok(())
 }
 
+/// Write the device's recommended settings if no config exists yet
+fn write_device_defaults(profile_id: &str) {
+    let user_config = Path::new(rexos_config::USER_CONFIG_DIR).join("config.toml");
+    let system_config = Path::new(rexos_config::CONFIG_DIR).join("config.toml");
+    if user_config.exists() || system_config.exists() {
+        return;
+    }
+
+    let config = rexos_config::RexOSConfig::defaults_for_device(profile_id);
+    match config.save(&user_config) {
+        Ok(()) => info!("Wrote default configuration for {}", profile_id),
+        Err(e) => warn!("Failed to write default configuration: {}", e),
+    }
+}
+
 /// Initialize display
-fn init_display(_device: &rexos_hal::Device) -> Result<()> {
+fn init_display(device: &rexos_hal::Device) -> Result<()> {
     // Set initial brightness
     let config = rexos_config::RexOSConfig::load_default()?;
     let brightness = config.system.brightness;
 
     // Create display manager and set brightness
-    let display_config = rexos_hal::DisplayConfig::default();
+    let panel = &device.profile().display;
+    let display_config = rexos_hal::DisplayConfig {
+        width: panel.width,
+        height: panel.height,
+        ..rexos_hal::DisplayConfig::default()
+    };
     match rexos_hal::Display::new(display_config) {
         Ok(mut display) => {
             if let Err(e) = display.set_brightness(brightness) {
                 warn!("Failed to set display brightness: {}", e);
             }
+
+            // Reapply the saved rotation (vertical-screen setups)
+            let degrees = config.system.display.rotation;
+            match rexos_hal::Rotation::from_degrees(u32::from(degrees)) {
+                Some(rotation) => {
+                    if let Err(e) = display.set_rotation(rotation) {
+                        warn!("Failed to set display rotation: {}", e);
+                    }
+                }
+                None => warn!("Ignoring invalid display rotation {}", degrees),
+            }
         }
         Err(e) => {
             warn!("Failed to initialize display: {}", e);
@@ -377,18 +475,39 @@ fn init_input(_device: &rexos_hal::Device) -> Result<()> {
         warn!("No input devices found in /dev/input/");
     }
 
+    let config = rexos_config::RexOSConfig::load_default()?;
+    if let Err(conflicts) = config.hotkeys.validate() {
+        for conflict in conflicts {
+            warn!("Hotkey conflict: {}", conflict);
+        }
+    }
+
     Ok(())
 }
 
 /// Initialize audio
 fn init_audio(_device: &rexos_hal::Device) -> Result<()> {
-    // Set initial volume
-    let config = rexos_config::RexOSConfig::load_default()?;
+    const HEADPHONE_POLL_INTERVAL: Duration = Duration::from_millis(500);
 
-    // Use amixer to set volume
-    let _ = Command::new("amixer")
-        .args(["sset", "Master", &format!("{}%", config.system.volume)])
-        .output();
+    // Creating the manager sets the initial volume through the volume curve
+    let config = rexos_config::RexOSConfig::load_default()?;
+    let audio_config = rexos_hal::AudioConfig {
+        volume: config.system.volume,
+        ..rexos_hal::AudioConfig::default()
+    };
+    match rexos_hal::AudioManager::new(audio_config) {
+        Ok(mut audio) => {
+            // Follow the headphone jack for the whole session, so unplugging
+            // mid-game switches back to the speaker without a restart
+            std::thread::spawn(move || {
+                loop {
+                    audio.poll_headphone();
+                    std::thread::sleep(HEADPHONE_POLL_INTERVAL);
+                }
+            });
+        }
+        Err(e) => warn!("Audio manager unavailable: {}", e),
+    }
 
     debug!("Audio initialized");
     Ok(())
@@ -398,20 +517,11 @@ fn init_audio(_device: &rexos_hal::Device) -> Result<()> {
 fn init_power(_device: &rexos_hal::Device) -> Result<()> {
     let config = rexos_config::RexOSConfig::load_default()?;
 
-    // Set CPU governor based on performance profile
-    let governor = match config.system.performance {
-        rexos_config::PerformanceProfile::Powersave => "powersave",
-        rexos_config::PerformanceProfile::Balanced => "schedutil",
-        rexos_config::PerformanceProfile::Performance => "performance",
-    };
-
-    // Set governor for all CPUs
-    let governor_path = "/sys/devices/system/cpu/cpu0/cpufreq/scaling_governor";
-    if Path::new(governor_path).exists() {
-        let _ = fs::write(governor_path, governor);
-        debug!("CPU governor set to {}", governor);
-    } else {
-        debug!("CPU governor path not found, skipping");
+    // Set CPU governor on every cluster based on performance profile
+    let applied = rexos_hal::PowerManager::new()
+        .and_then(|power| power.apply_profile(config.system.performance));
+    if let Err(e) = applied {
+        debug!("CPU governor not set: {}", e);
     }
 
     Ok(())
@@ -490,16 +600,24 @@ fn write_boot_time(duration: std::time::Duration) -> Result<()> {
 }
 
 /// Main loop - handle signals, reap zombies, and watchdog frontend
-fn main_loop(mut frontend_child: Option<Child>) -> Result<()> {
+fn main_loop(
+    mut frontend_child: Option<Child>,
+    mut pending_update: Option<update_check::PendingUpdate>,
+) -> Result<()> {
     use std::thread;
 
     const WATCHDOG_INTERVAL: Duration = Duration::from_secs(5);
     const MAX_FRONTEND_RESTARTS: u32 = 3;
     const RESTART_COOLDOWN: Duration = Duration::from_secs(30);
+    // Time for the frontend to show its warning before powering off
+    const CRITICAL_BATTERY_GRACE: Duration = Duration::from_secs(10);
 
     let mut restart_count = 0u32;
     let mut last_restart = Instant::now();
 
+    let power = rexos_hal::PowerManager::new().ok();
+    let mut critical_since: Option<Instant> = None;
+
     info!("Entering main loop (watchdog active)");
 
     loop {
@@ -516,6 +634,23 @@ fn main_loop(mut frontend_child: Option<Child>) -> Result<()> {
             return Ok(());
         }
 
+        // Shut down cleanly before the battery cuts out
+        for event in power.iter().flat_map(|p| p.poll_battery()) {
+            match event {
+                rexos_hal::PowerEvent::CriticalBattery(percentage) => {
+                    warn!("Battery critical ({}%), shutting down soon", percentage);
+                    critical_since = Some(Instant::now());
+                }
+                rexos_hal::PowerEvent::Charging => critical_since = None,
+                _ => {}
+            }
+        }
+        if critical_since.is_some_and(|t| t.elapsed() >= CRITICAL_BATTERY_GRACE) {
+            info!("Shutting down on critical battery");
+            shutdown::critical_shutdown();
+            return Ok(());
+        }
+
         // Watchdog: Check if frontend is still running
         if let Some(ref mut child) = frontend_child {
             match child.try_wait() {
@@ -530,6 +665,18 @@ fn main_loop(mut frontend_child: Option<Child>) -> Result<()> {
                         }
                     } else {
                         error!("Frontend crashed with status: {:?}", status);
+
+                        // A crash-looping update goes back to the previous files
+                        if pending_update
+                            .as_mut()
+                            .is_some_and(|update| update.frontend_crashed())
+                        {
+                            if let Some(update) = pending_update.take() {
+                                update.roll_back();
+                            }
+                            shutdown::reboot();
+                            return Ok(());
+                        }
                     }
 
                     // Attempt restart with rate limiting
@@ -551,6 +698,9 @@ fn main_loop(mut frontend_child: Option<Child>) -> Result<()> {
                             Ok(new_child) => {
                                 frontend_child = new_child;
                                 restart_count += 1;
+                                if let Some(update) = pending_update.as_mut() {
+                                    update.frontend_started();
+                                }
                                 last_restart = Instant::now();
                                 info!("Frontend restarted successfully");
                             }
@@ -570,7 +720,10 @@ fn main_loop(mut frontend_child: Option<Child>) -> Result<()> {
                     }
                 }
                 Ok(None) => {
-                    // Still running - good
+                    // Still running - good; long enough to trust a new update
+                    if let Some(update) = pending_update.take_if(|u| u.is_stable()) {
+                        update.commit();
+                    }
                 }
                 Err(e) => {
                     warn!("Failed to check frontend status: {}", e);
@@ -682,23 +835,259 @@ mod services {
     }
 }
 
+mod recovery {
+    //! Boot-time recovery combo
+    //!
+    //! Samples the held buttons through the input HAL during the configured
+    //! window. Any failure (no config, no input devices yet, bad combo)
+    //! means a normal boot.
+
+    use anyhow::{Context, Result};
+    use rexos_config::RecoveryConfig;
+    use rexos_hal::{Button, InputManager};
+    use std::process::{Child, Command, Stdio};
+    use std::time::{Duration, Instant};
+    use tracing::{debug, info, warn};
+
+    /// Interval between key state samples
+    const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+    /// Load recovery settings, falling back to defaults
+    fn config() -> RecoveryConfig {
+        rexos_config::RexOSConfig::load_default()
+            .map(|c| c.system.recovery)
+            .unwrap_or_default()
+    }
+
+    /// Check whether the recovery combo is held during the boot window
+    pub fn combo_held() -> bool {
+        let config = config();
+
+        let combo: Option<Vec<Button>> = config
+            .combo
+            .iter()
+            .map(|name| Button::from_name(name))
+            .collect();
+        let combo = match combo {
+            Some(combo) if !combo.is_empty() => combo,
+            _ => {
+                warn!("Invalid recovery combo {:?}, skipping check", config.combo);
+                return false;
+            }
+        };
+
+        let window = Duration::from_millis(config.window_ms);
+        let start = Instant::now();
+
+        // Input devices may still be appearing, so keep rescanning
+        let mut input: Option<InputManager> = None;
+        while start.elapsed() < window {
+            match input {
+                Some(ref mut manager) if !manager.devices().is_empty() => {
+                    match manager.sync_key_state() {
+                        Ok(()) => {
+                            if manager.is_combo_pressed(&combo) {
+                                info!("Recovery combo held, entering recovery");
+                                return true;
+                            }
+                        }
+                        Err(e) => debug!("Failed to read key state: {}", e),
+                    }
+                }
+                Some(ref mut manager) => {
+                    let _ = manager.scan_devices();
+                }
+                None => input = InputManager::new().ok(),
+            }
+
+            std::thread::sleep(POLL_INTERVAL);
+        }
+
+        if input.is_none_or(|m| m.devices().is_empty()) {
+            debug!("No input devices during recovery window");
+        }
+        false
+    }
+
+    /// Launch the recovery menu in place of the frontend
+    pub fn launch() -> Result<Option<Child>> {
+        let config = config();
+        let mut parts = config.command.split_whitespace();
+        let Some(program) = parts.next() else {
+            warn!("Recovery command is empty, launching frontend");
+            return super::launch_frontend();
+        };
+
+        let child = Command::new(program)
+            .args(parts)
+            .stdin(Stdio::null())
+            .spawn()
+            .with_context(|| format!("Failed to launch recovery {}", program))?;
+
+        info!("Recovery launched: {} (PID {})", program, child.id());
+        Ok(Some(child))
+    }
+}
+
+mod update_check {
+    //! Post-update health check
+    //!
+    //! The updater leaves a pending marker next to its backup after an
+    //! install. On the next boot the frontend has to stay up for
+    //! [`STABLE_PERIOD`] to commit the update; crashing [`MAX_CRASHES`]
+    //! times first rolls it back from the backup. An update staged to
+    //! install later is applied on a clean shutdown or reboot, given enough
+    //! battery to carry the install.
+
+    use rexos_hal::PowerManager;
+    use rexos_update::{BACKUP_DIR, STAGING_DIR, UpdateConfig, UpdateInstaller, check_battery};
+    use std::path::PathBuf;
+    use std::time::{Duration, Instant};
+    use tracing::{error, info, warn};
+
+    /// How long the frontend must stay up to commit an update
+    pub const STABLE_PERIOD: Duration = Duration::from_secs(60);
+
+    /// Frontend crashes before rolling back, below the watchdog's limit
+    pub const MAX_CRASHES: u32 = 2;
+
+    /// Installer over the updater's persistent directories
+    fn installer() -> UpdateInstaller {
+        UpdateInstaller::new(PathBuf::from(STAGING_DIR)).with_backup_dir(PathBuf::from(BACKUP_DIR))
+    }
+
+    /// Apply an update staged to install on shutdown, so it's verified on
+    /// the next boot
+    ///
+    /// On a low battery the update stays staged for a later shutdown.
+    pub fn apply_staged() {
+        let installer = installer();
+        let Some(staged) = installer.staged() else {
+            return;
+        };
+
+        let min_battery = UpdateConfig::default().min_battery;
+        let battery =
+            PowerManager::new().map_or(Ok(()), |power| check_battery(&power, min_battery));
+        if let Err(e) = battery {
+            warn!("Not applying staged update to {}: {}", staged.version, e);
+            return;
+        }
+        info!("Applying staged update to {}", staged.version);
+
+        let result = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .map_err(rexos_update::UpdateError::from)
+            .and_then(|runtime| runtime.block_on(installer.apply_staged()));
+        if let Err(e) = result {
+            error!("Failed to apply staged update to {}: {}", staged.version, e);
+        }
+    }
+
+    /// An installed update awaiting verification
+    pub struct PendingUpdate {
+        installer: UpdateInstaller,
+        version: String,
+        started: Instant,
+        crashes: u32,
+    }
+
+    impl PendingUpdate {
+        /// Check for an update installed since the last good boot
+        pub fn detect() -> Option<Self> {
+            let installer = installer();
+            let version = installer.pending_verification()?;
+            info!("Verifying update to {}", version);
+
+            Some(Self {
+                installer,
+                version,
+                started: Instant::now(),
+                crashes: 0,
+            })
+        }
+
+        /// Restart the stability timer after a frontend relaunch
+        pub fn frontend_started(&mut self) {
+            self.started = Instant::now();
+        }
+
+        /// Record a frontend crash, returning true when it's time to roll back
+        pub fn frontend_crashed(&mut self) -> bool {
+            self.crashes += 1;
+            warn!(
+                "Frontend crashed after update to {} ({}/{})",
+                self.version, self.crashes, MAX_CRASHES
+            );
+            self.crashes >= MAX_CRASHES
+        }
+
+        /// Check if the frontend has been up long enough
+        pub fn is_stable(&self) -> bool {
+            self.started.elapsed() >= STABLE_PERIOD
+        }
+
+        /// Accept the update
+        pub fn commit(self) {
+            if let Err(e) = self.installer.commit() {
+                warn!("Failed to commit update to {}: {}", self.version, e);
+            }
+        }
+
+        /// Restore the files from before the update
+        ///
+        /// The pending marker is cleared even if this fails, so a broken
+        /// rollback can't turn into a reboot loop.
+        pub fn roll_back(self) {
+            error!(
+                "Update to {} failed verification, rolling back",
+                self.version
+            );
+
+            let result = tokio::runtime::Builder::new_current_thread()
+                .build()
+                .map_err(rexos_update::UpdateError::from)
+                .and_then(|runtime| runtime.block_on(self.installer.rollback()));
+            if let Err(e) = result {
+                error!("Rollback failed: {}", e);
+                let _ = self.installer.clear_pending();
+            }
+        }
+    }
+}
+
 mod shutdown {
     //! Shutdown and reboot handling
     //!
     //! Provides clean shutdown and reboot procedures that properly
     //! stop services, sync filesystems, and unmount partitions.
 
-    use super::services;
+    use super::{services, update_check};
     use std::process::Command;
     use tracing::info;
 
     /// Perform clean shutdown
     pub fn shutdown() {
+        power_off(true);
+    }
+
+    /// Shut down on a critical battery, leaving any staged update for a
+    /// later shutdown
+    pub fn critical_shutdown() {
+        power_off(false);
+    }
+
+    fn power_off(apply_update: bool) {
         info!("Initiating shutdown...");
 
         // Stop services (in reverse order)
         services::stop_all_nonessential();
 
+        // Apply an update deferred until the user was done playing
+        if apply_update {
+            update_check::apply_staged();
+        }
+
         // Sync filesystems
         info!("Syncing filesystems...");
         let _ = Command::new("sync").output();
@@ -719,6 +1108,7 @@ mod shutdown {
         info!("Initiating reboot...");
 
         services::stop_all_nonessential();
+        update_check::apply_staged();
 
         info!("Syncing filesystems...");
         let _ = Command::new("sync").output();
@@ -8,6 +8,11 @@
 //! The launcher supports both keyboard and gamepad input:
 //! - Keyboard: For development and SSH access
 //! - Gamepad: Via HAL InputManager for actual device usage
+//!
+//! # Recovery
+//!
+//! `--recovery` opens the recovery menu instead of the system list. Init
+//! launches it this way when the recovery button combo is held at boot.
 
 use anyhow::Result;
 use crossterm::{
@@ -18,31 +23,46 @@ use crossterm::{
 use ratatui::{
     Frame, Terminal,
     backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap, block::Title},
 };
 use std::io;
 use std::path::PathBuf;
+use std::sync::mpsc::Receiver;
+use std::thread::JoinHandle;
 use std::time::{Duration, Instant};
 use tracing::{debug, error, info, warn};
 
-use rexos_config::RexOSConfig;
-use rexos_emulator::{EmulatorLauncher, LaunchConfig};
-use rexos_hal::input::{Button, InputManager};
-use rexos_library::{Game, GameDatabase, RomScanner};
-use rexos_network::{NetworkConfig, NetworkManager};
+use rexos_config::{ConfigStore, RexOSConfig};
+use rexos_emulator::{
+    EmulatorLauncher, LaunchConfig, SHADER_NONE, SessionMetrics, ShaderChoice, ShaderSettings,
+};
+use rexos_hal::input::{Button, InputManager, RepeatSettings};
+use rexos_hal::led::{Led, LedColor};
+use rexos_hal::logs::{self, LogTail};
+use rexos_hal::{
+    BatteryInfo, Display, DisplayConfig, FreqLimits, PowerEvent, PowerManager, Rotation,
+    VolumeCurve,
+};
+use rexos_library::{
+    DatabaseWorker, DbRequest, DbResponse, Game, GameDatabase, NameCleaner, RomScanner, ScanIndex,
+    region_matches,
+};
+use rexos_network::{
+    ConnectionState, NetworkConfig, NetworkEvent, NetworkManager, SshConfig, TimeSync,
+};
 
 /// Application state
 struct App {
-    /// Game database
-    db: GameDatabase,
+    /// Game database, queried on a background thread
+    db: DatabaseWorker,
 
     /// Emulator launcher
     launcher: EmulatorLauncher,
 
-    /// Configuration
-    config: RexOSConfig,
+    /// Configuration, saved shortly after settings stop changing
+    config: ConfigStore,
 
     /// Gamepad input manager (optional - may not be available on dev machines)
     input: Option<InputManager>,
@@ -50,6 +70,9 @@ struct App {
     /// Network manager (optional - may not be available)
     network: Option<NetworkManager>,
 
+    /// WiFi and Bluetooth connection changes
+    network_events: Option<Receiver<NetworkEvent>>,
+
     /// Current view
     view: View,
 
@@ -82,6 +105,66 @@ struct App {
 
     /// Whether we're currently editing a setting
     editing_setting: bool,
+
+    /// Kernel and system log tail
+    logs: LogTail,
+
+    /// Log view scroll offset (lines up from the newest)
+    log_scroll: usize,
+
+    /// Shader picker list state
+    shader_state: ListState,
+
+    /// Shader presets available in the picker
+    shader_presets: Vec<PathBuf>,
+
+    /// Power manager for battery state (optional)
+    power: Option<PowerManager>,
+
+    /// RGB indicator LEDs (empty on devices without them)
+    leds: Vec<Led>,
+
+    /// Color currently shown on the indicator LEDs
+    led_color: Option<LedColor>,
+
+    /// Last time the battery LED state was checked
+    last_led_check: Option<Instant>,
+
+    /// Battery state shown in the header
+    battery: Option<BatteryInfo>,
+
+    /// Last time the battery was read for the header
+    last_battery_check: Option<Instant>,
+
+    /// Battery warning shown over the current view until a key is pressed
+    battery_warning: Option<String>,
+
+    /// Whether the secondary card's ROMs directory was there at the last check
+    roms2_mounted: bool,
+
+    /// Last time the secondary card was checked for
+    last_card_check: Option<Instant>,
+
+    /// Background clock sync, returns whether it succeeded
+    time_sync: Option<JoinHandle<bool>>,
+
+    /// Whether the clock has been synchronized this session
+    time_synced: bool,
+
+    /// Last time network connectivity was checked for clock sync
+    last_net_check: Option<Instant>,
+
+    /// Started from the boot recovery combo
+    recovery_mode: bool,
+
+    /// Recovery menu list state
+    recovery_state: ListState,
+
+    /// Factory reset was previewed and awaits confirmation
+    reset_pending: bool,
+
+    /// ROMs partition is mounted read-only
+    roms_read_only: bool,
 }
 
 /// A setting that can be edited
@@ -112,8 +195,58 @@ enum View {
     Games,
     GameInfo,
     Settings,
+    Logs,
+    Shaders,
+    Recovery,
+}
+
+/// Recovery menu entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RecoveryAction {
+    /// Exit so init starts the normal frontend
+    ContinueBoot,
+    ViewLogs,
+    /// Restore default config and clear caches, keeping ROMs and saves
+    FactoryReset,
+    Reboot,
+}
+
+impl RecoveryAction {
+    const ALL: [RecoveryAction; 4] = [
+        RecoveryAction::ContinueBoot,
+        RecoveryAction::ViewLogs,
+        RecoveryAction::FactoryReset,
+        RecoveryAction::Reboot,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            RecoveryAction::ContinueBoot => "Continue boot",
+            RecoveryAction::ViewLogs => "View system log",
+            RecoveryAction::FactoryReset => "Factory reset",
+            RecoveryAction::Reboot => "Reboot",
+        }
+    }
 }
 
+/// Fixed entries at the top of the shader picker
+const SHADER_PICKER_FIXED: [&str; 2] = ["System default", "None"];
+
+/// How often the battery LED indicator is refreshed
+const LED_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often the battery and temperature are checked
+const BATTERY_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often to check whether the secondary card came or went
+const CARD_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How often to check for a network connection until the clock is synced
+const NET_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Status shown when library changes can't be saved
+const READ_ONLY_STATUS: &str = "ROMs card is read-only";
+
 impl App {
     /// Get ROM directory from environment or default
     fn get_roms_dir() -> PathBuf {
@@ -122,29 +255,81 @@ impl App {
             .unwrap_or_else(|_| PathBuf::from("/roms"))
     }
 
+    /// Get the secondary card's ROMs directory, if one is mounted
+    fn get_roms2_dir() -> Option<PathBuf> {
+        let dir = std::env::var("REXOS_ROMS2_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from("/roms2"));
+        dir.is_dir().then_some(dir)
+    }
+
     /// Create new application
     fn new() -> Result<Self> {
         let roms_dir = Self::get_roms_dir();
 
-        // Open game database
+        // Open game database, falling back to a copy if the card is read-only
         let db_path = roms_dir.join(".rexos/games.db");
-
-        // Create directory if needed
-        if let Some(parent) = db_path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-
-        let db = GameDatabase::open(&db_path)?;
+        let fallback_path = std::env::temp_dir().join("rexos/games.db");
+        let (db, roms_read_only) = GameDatabase::open_writable(&db_path, &fallback_path)?;
 
         // Load configuration
         let config = RexOSConfig::load_default()?;
+        if let Err(conflicts) = config.hotkeys.validate() {
+            for conflict in conflicts {
+                warn!("Hotkey conflict: {}", conflict);
+            }
+        }
 
-        // Create launcher
-        let launcher = EmulatorLauncher::new();
+        // Create launcher with per-system video overrides and device display
+        let mut launcher = EmulatorLauncher::new()
+            .with_video_overrides(config.emulators.video.clone())
+            .with_input_profiles(config.emulators.input.clone())
+            .with_resource_limits(config.emulators.limits.clone())
+            .with_core_configs(config.emulators.cores.clone())
+            .with_bios_dir(roms_dir.join("bios"))
+            .with_hotkeys(config.hotkeys.clone())
+            .with_hooks(
+                config.emulators.hooks.clone(),
+                config.emulators.system_hooks.clone(),
+            )
+            .with_shaders(ShaderSettings::from_config(&config.emulators));
+        if config.emulators.capture_logs {
+            launcher = launcher.with_log_capture(&config.emulators.log_dir);
+        }
+        let mut leds = Vec::new();
+        let mut analog_sticks = None;
+        if let Ok(device) = rexos_hal::Device::detect() {
+            launcher = launcher.with_device(device.profile().clone());
+            analog_sticks = Some(device.profile().analog_sticks);
+
+            // Only RGB LEDs are used as indicators
+            leds = Led::list(device.profile())
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|led| led.is_rgb())
+                .collect();
+        }
+
+        let power = PowerManager::new().ok();
 
         // Initialize gamepad input (optional - may fail on dev machines)
         let input = match InputManager::new() {
-            Ok(mgr) => {
+            Ok(mut mgr) => {
+                let repeat = &config.system.input_repeat;
+                mgr.set_repeat(RepeatSettings {
+                    initial_delay: Duration::from_millis(repeat.initial_delay_ms),
+                    interval: Duration::from_millis(repeat.interval_ms),
+                    min_interval: Duration::from_millis(repeat.min_interval_ms),
+                    acceleration: repeat.acceleration,
+                });
+                // Stick navigates menus too, where the device has one
+                if let Some(count) = analog_sticks {
+                    mgr.set_analog_sticks(count);
+                }
+                mgr.set_analog_as_dpad(true);
+                if let Err(e) = mgr.set_remap(&config.system.input) {
+                    warn!("Ignoring button remap: {}", e);
+                }
                 info!(
                     "Gamepad input initialized with {} devices",
                     mgr.devices().len()
@@ -159,8 +344,9 @@ impl App {
 
         // Initialize network manager (optional)
         let network = match NetworkManager::new(NetworkConfig::default()) {
-            Ok(mgr) => {
+            Ok(mut mgr) => {
                 info!("Network manager initialized");
+                mgr.start_bluetooth_reconnect();
                 Some(mgr)
             }
             Err(e) => {
@@ -168,19 +354,35 @@ impl App {
                 None
             }
         };
+        let network_events = network
+            .as_ref()
+            .and_then(|mgr| match mgr.connection_events() {
+                Ok(events) => Some(events),
+                Err(e) => {
+                    warn!("Network status updates not available: {}", e);
+                    None
+                }
+            });
 
-        // Get systems
+        // Get systems, then hand the database to its own thread
         let systems = db.get_systems()?;
+        let db = DatabaseWorker::spawn(db);
 
         // Build settings items from current config
         let settings_items = Self::build_settings_items(&config);
 
+        // Tail kernel and RexOS logs (kernel log may be restricted)
+        let logs = LogTail::new(logs::DEFAULT_CAPACITY)
+            .with_kernel()
+            .with_file(logs::REXOS_LOG_PATH);
+
         let mut app = Self {
             db,
             launcher,
-            config,
+            config: ConfigStore::new_default(config),
             input,
             network,
+            network_events,
             view: View::Systems,
             systems_state: ListState::default(),
             games_state: ListState::default(),
@@ -192,8 +394,32 @@ impl App {
             should_quit: false,
             settings_items,
             editing_setting: false,
+            logs,
+            log_scroll: 0,
+            shader_state: ListState::default(),
+            shader_presets: Vec::new(),
+            power,
+            leds,
+            led_color: None,
+            last_led_check: None,
+            battery: None,
+            last_battery_check: None,
+            battery_warning: None,
+            roms2_mounted: Self::get_roms2_dir().is_some(),
+            last_card_check: None,
+            time_sync: None,
+            time_synced: false,
+            last_net_check: None,
+            recovery_mode: false,
+            recovery_state: ListState::default(),
+            reset_pending: false,
+            roms_read_only,
         };
 
+        if app.roms_read_only {
+            app.status = READ_ONLY_STATUS.to_string();
+        }
+
         // Select first system if available
         if !app.systems.is_empty() {
             app.systems_state.select(Some(0));
@@ -255,6 +481,16 @@ impl App {
                     },
                 },
             },
+            SettingItem {
+                name: "Screen Rotation",
+                kind: SettingKind::Select {
+                    options: vec!["0°", "90°", "180°", "270°"],
+                    current: rexos_config::ROTATIONS
+                        .iter()
+                        .position(|&r| r == config.system.display.rotation)
+                        .unwrap_or(0),
+                },
+            },
         ]
     }
 
@@ -264,30 +500,43 @@ impl App {
             return Ok(());
         }
 
+        let mut provision_ssh = false;
         let item = &self.settings_items[index];
         match (&item.kind, item.name) {
             (SettingKind::Percentage { value, .. }, "Brightness") => {
-                self.config.system.brightness = (*value as f32 / 100.0 * 255.0) as u8;
+                // The panel is off at 0, so the lowest step stays lit
+                let brightness = ((*value as f32 / 100.0 * 255.0) as u8).max(1);
+                self.config.set_brightness(brightness)?;
                 // Apply immediately via HAL if available
-                debug!("Setting brightness to {}", self.config.system.brightness);
+                debug!("Setting brightness to {}", brightness);
             }
             (SettingKind::Percentage { value, .. }, "Volume") => {
-                self.config.system.volume = *value;
-                // Apply via amixer
+                self.config.set_volume(*value)?;
+                // Apply via amixer, scaled like AudioManager::set_volume
+                let level = VolumeCurve::default().mixer_level(*value);
                 let _ = std::process::Command::new("amixer")
-                    .args(["sset", "Master", &format!("{}%", value)])
+                    .args(["sset", "Master", &format!("{}%", level)])
                     .output();
                 debug!("Setting volume to {}%", value);
             }
             (SettingKind::Select { current, .. }, "Performance Mode") => {
-                self.config.system.performance = match current {
+                let profile = match current {
                     0 => rexos_config::PerformanceProfile::Powersave,
                     1 => rexos_config::PerformanceProfile::Balanced,
                     _ => rexos_config::PerformanceProfile::Performance,
                 };
+                self.config.set_performance(profile);
+                // Avoid if-let chains for MSRV 1.85 compatibility
+                #[allow(clippy::collapsible_if)]
+                if let Some(power) = self.power.as_ref() {
+                    if let Err(e) = power.apply_profile(profile) {
+                        warn!("Failed to apply performance profile: {}", e);
+                    }
+                }
             }
             (SettingKind::Toggle { value }, "WiFi") => {
-                self.config.system.network.wifi_enabled = *value;
+                self.config
+                    .update(|config| config.system.network.wifi_enabled = *value)?;
                 // Toggle WiFi via network manager
                 if let Some(ref mut net) = self.network {
                     if *value {
@@ -298,32 +547,70 @@ impl App {
                 }
             }
             (SettingKind::Toggle { value }, "SSH") => {
-                self.config.system.network.ssh_enabled = *value;
+                self.config
+                    .update(|config| config.system.network.ssh_enabled = *value)?;
                 // Toggle SSH service
                 let cmd = if *value { "start" } else { "stop" };
                 let _ = std::process::Command::new("systemctl")
                     .args([cmd, "sshd"])
                     .output();
+                provision_ssh = *value;
             }
             (SettingKind::Select { options, current }, "Auto-suspend") => {
-                self.config.system.suspend_timeout = match current {
+                let timeout = match current {
                     0 => 0,
                     1 => 5,
                     2 => 10,
                     3 => 15,
                     _ => 30,
                 };
+                self.config
+                    .update(|config| config.system.suspend_timeout = timeout)?;
                 let _ = options; // silence unused warning
             }
+            (SettingKind::Select { current, .. }, "Screen Rotation") => {
+                let degrees = rexos_config::ROTATIONS.get(*current).copied().unwrap_or(0);
+                self.config
+                    .update(|config| config.system.display.rotation = degrees)?;
+                self.apply_rotation(degrees);
+            }
             _ => {}
         }
 
-        // Save config to file
-        self.config.save_default()?;
+        // Saved by the main loop once the setting stops changing
         self.status = format!("{} updated", item.name);
+        if provision_ssh {
+            self.provision_ssh();
+        }
         Ok(())
     }
 
+    /// Install SSH keys left on the ROMs card and show the host key
+    fn provision_ssh(&mut self) {
+        let ssh = SshConfig::new();
+        let keys_file = Self::get_roms_dir().join(".rexos/authorized_keys");
+
+        let mut parts = Vec::new();
+        if keys_file.exists() {
+            match ssh.import_authorized_keys(&keys_file) {
+                Ok(added) => parts.push(format!("{} keys added", added)),
+                Err(e) => {
+                    warn!("Failed to import SSH keys: {}", e);
+                    parts.push(format!("key import failed: {}", e));
+                }
+            }
+        }
+        if let Ok(fingerprint) = ssh.host_key_fingerprint() {
+            parts.push(fingerprint);
+        }
+
+        self.status = if parts.is_empty() {
+            "SSH enabled".to_string()
+        } else {
+            format!("SSH enabled, {}", parts.join(", "))
+        };
+    }
+
     /// Poll gamepad input and convert to key codes
     fn poll_gamepad(&mut self) -> Option<KeyCode> {
         let input = self.input.as_mut()?;
@@ -333,54 +620,41 @@ impl App {
             return None;
         }
 
-        // Map gamepad buttons to key codes
-        if input.is_pressed(Button::Up) {
-            return Some(KeyCode::Up);
-        }
-        if input.is_pressed(Button::Down) {
-            return Some(KeyCode::Down);
-        }
-        if input.is_pressed(Button::Left) {
-            return Some(KeyCode::Left);
-        }
-        if input.is_pressed(Button::Right) {
-            return Some(KeyCode::Right);
+        // Map gamepad buttons to key codes; actions fire once per press,
+        // held directions repeat
+        match input.next_press(Instant::now())? {
+            Button::Up => Some(KeyCode::Up),
+            Button::Down => Some(KeyCode::Down),
+            Button::Left => Some(KeyCode::Left),
+            Button::Right => Some(KeyCode::Right),
+            Button::A => Some(KeyCode::Enter),
+            Button::B => Some(KeyCode::Esc),
+            Button::X => Some(KeyCode::Char('x')),
+            Button::Y => Some(KeyCode::Char('f')), // Favorite
+            Button::Start => Some(KeyCode::Tab),
+            Button::Select => Some(KeyCode::Char('r')), // Rescan
+            Button::L1 => Some(KeyCode::PageUp),
+            Button::R1 => Some(KeyCode::PageDown),
+            Button::L2 => Some(KeyCode::Char('l')), // Logs
+            _ => None,
         }
-        if input.is_pressed(Button::A) {
-            return Some(KeyCode::Enter);
-        }
-        if input.is_pressed(Button::B) {
-            return Some(KeyCode::Esc);
-        }
-        if input.is_pressed(Button::X) {
-            return Some(KeyCode::Char('x'));
-        }
-        if input.is_pressed(Button::Y) {
-            return Some(KeyCode::Char('f')); // Favorite
-        }
-        if input.is_pressed(Button::Start) {
-            return Some(KeyCode::Tab);
-        }
-        if input.is_pressed(Button::Select) {
-            return Some(KeyCode::Char('r')); // Rescan
-        }
-        if input.is_pressed(Button::L1) {
-            return Some(KeyCode::PageUp);
-        }
-        if input.is_pressed(Button::R1) {
-            return Some(KeyCode::PageDown);
-        }
-
-        None
     }
 
     /// Handle input
     fn handle_input(&mut self, key: KeyCode) -> Result<()> {
+        // Any key dismisses the battery warning
+        if self.battery_warning.take().is_some() {
+            return Ok(());
+        }
+
         match self.view {
             View::Systems => self.handle_systems_input(key)?,
             View::Games => self.handle_games_input(key)?,
             View::GameInfo => self.handle_game_info_input(key)?,
             View::Settings => self.handle_settings_input(key)?,
+            View::Logs => self.handle_logs_input(key),
+            View::Shaders => self.handle_shaders_input(key)?,
+            View::Recovery => self.handle_recovery_input(key),
         }
         Ok(())
     }
@@ -401,6 +675,8 @@ impl App {
             if self.settings_state.selected().is_none() && !self.settings_items.is_empty() {
                 self.settings_state.select(Some(0));
             }
+        } else if input::is_logs(key) {
+            self.open_logs();
         } else if input::is_quit(key) {
             self.should_quit = true;
         }
@@ -431,12 +707,170 @@ impl App {
     fn handle_game_info_input(&mut self, key: KeyCode) -> Result<()> {
         if input::is_select(key) {
             self.launch_selected_game()?;
+        } else if input::is_info(key) {
+            self.open_shader_picker();
         } else if input::is_back(key) {
             self.view = View::Games;
         }
         Ok(())
     }
 
+    /// Handle shader picker input
+    fn handle_shaders_input(&mut self, key: KeyCode) -> Result<()> {
+        let len = SHADER_PICKER_FIXED.len() + self.shader_presets.len();
+
+        if input::is_nav_up(key) {
+            let i = self.shader_state.selected().unwrap_or(0);
+            self.shader_state
+                .select(Some(if i == 0 { len - 1 } else { i - 1 }));
+        } else if input::is_nav_down(key) {
+            let i = self.shader_state.selected().unwrap_or(0);
+            self.shader_state.select(Some((i + 1) % len));
+        } else if input::is_select(key) {
+            self.apply_shader_choice()?;
+            self.view = View::GameInfo;
+        } else if input::is_back(key) {
+            self.view = View::GameInfo;
+        }
+        Ok(())
+    }
+
+    /// Switch to the recovery menu
+    fn enter_recovery(&mut self) {
+        self.recovery_mode = true;
+        self.recovery_state.select(Some(0));
+        self.view = View::Recovery;
+        self.status = "Recovery mode".to_string();
+    }
+
+    /// Handle recovery menu input
+    fn handle_recovery_input(&mut self, key: KeyCode) {
+        let len = RecoveryAction::ALL.len();
+
+        // Any other input cancels a pending factory reset
+        if !input::is_select(key) && self.reset_pending {
+            self.reset_pending = false;
+            self.status = "Factory reset cancelled".to_string();
+        }
+
+        if input::is_nav_up(key) {
+            let i = self.recovery_state.selected().unwrap_or(0);
+            self.recovery_state
+                .select(Some(if i == 0 { len - 1 } else { i - 1 }));
+        } else if input::is_nav_down(key) {
+            let i = self.recovery_state.selected().unwrap_or(0);
+            self.recovery_state.select(Some((i + 1) % len));
+        } else if input::is_select(key) {
+            let i = self.recovery_state.selected().unwrap_or(0);
+            self.run_recovery_action(RecoveryAction::ALL[i]);
+        }
+    }
+
+    /// Run a recovery menu entry
+    fn run_recovery_action(&mut self, action: RecoveryAction) {
+        match action {
+            RecoveryAction::ContinueBoot => {
+                info!("Leaving recovery, continuing boot");
+                self.should_quit = true;
+            }
+            RecoveryAction::ViewLogs => self.open_logs(),
+            RecoveryAction::FactoryReset => self.factory_reset(),
+            RecoveryAction::Reboot => {
+                use nix::sys::signal::{Signal, kill};
+                use nix::unistd::Pid;
+
+                // rexos-init reboots on SIGUSR2
+                info!("Reboot requested from recovery");
+                match kill(Pid::from_raw(1), Signal::SIGUSR2) {
+                    Ok(()) => self.status = "Rebooting...".to_string(),
+                    Err(e) => {
+                        error!("Failed to request reboot: {}", e);
+                        self.status = format!("Reboot failed: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Preview a factory reset, then run it on the second press
+    fn factory_reset(&mut self) {
+        let confirmed = std::mem::take(&mut self.reset_pending);
+        match rexos_config::factory_reset(!confirmed) {
+            Ok(actions) if actions.is_empty() => {
+                self.status = "Already at factory settings".to_string();
+            }
+            Ok(actions) if !confirmed => {
+                self.reset_pending = true;
+                self.status = format!("Reset {} items? [Enter] again", actions.len());
+            }
+            Ok(actions) => {
+                info!("Factory reset cleared {} items", actions.len());
+                self.status = "Factory reset done, restarting".to_string();
+                // Init restarts the frontend with fresh config and database
+                self.should_quit = true;
+            }
+            Err(e) => {
+                error!("Factory reset failed: {}", e);
+                self.status = format!("Factory reset failed: {}", e);
+            }
+        }
+    }
+
+    /// Open the shader picker for the selected game
+    fn open_shader_picker(&mut self) {
+        let Some(game) = self.selected_game() else {
+            return;
+        };
+        let (system, file_name) = (game.system.clone(), game_file_name(game));
+
+        self.shader_presets = self.launcher.shaders().list_presets();
+
+        // Preselect the game's current choice
+        let current = self
+            .config
+            .config()
+            .emulators
+            .get_game_shader(&system, &file_name)
+            .map(|value| ShaderChoice::parse(value, &self.launcher.shaders().shaders_dir));
+        let index = match current {
+            None => 0,
+            Some(ShaderChoice::Disabled) => 1,
+            Some(ShaderChoice::Preset(path)) => self
+                .shader_presets
+                .iter()
+                .position(|p| *p == path)
+                .map_or(0, |i| i + SHADER_PICKER_FIXED.len()),
+        };
+
+        self.shader_state.select(Some(index));
+        self.view = View::Shaders;
+        self.status = format!("{} shader presets", self.shader_presets.len());
+    }
+
+    /// Save the picked shader for the selected game
+    fn apply_shader_choice(&mut self) -> Result<()> {
+        let Some(game) = self.selected_game() else {
+            return Ok(());
+        };
+        let (system, file_name) = (game.system.clone(), game_file_name(game));
+
+        let selected = self.shader_state.selected().unwrap_or(0);
+        let preset = selected
+            .checked_sub(SHADER_PICKER_FIXED.len())
+            .and_then(|i| self.shader_presets.get(i));
+        let value = match (selected, preset) {
+            (0, _) => None,
+            (1, _) => Some(SHADER_NONE.to_string()),
+            (_, Some(path)) => Some(path.to_string_lossy().to_string()),
+            (_, None) => return Ok(()),
+        };
+        self.config
+            .update(|config| config.emulators.set_game_shader(&system, &file_name, value))?;
+        self.config.flush()?;
+        self.status = "Shader updated".to_string();
+        Ok(())
+    }
+
     /// Handle settings view input
     fn handle_settings_input(&mut self, key: KeyCode) -> Result<()> {
         if self.editing_setting {
@@ -470,24 +904,24 @@ impl App {
                 KeyCode::Down | KeyCode::Char('s') => {
                     self.select_next_setting();
                 }
-                KeyCode::Enter | KeyCode::Char('a') | KeyCode::Left | KeyCode::Right => {
+                KeyCode::Enter | KeyCode::Char('a') | KeyCode::Left | KeyCode::Right
+                    if self.settings_state.selected().is_some() =>
+                {
                     // Enter editing mode for the selected setting
-                    if self.settings_state.selected().is_some() {
-                        self.editing_setting = true;
-                        self.status = "[←→] Adjust  [Enter] Confirm".to_string();
-
-                        // For toggles, immediately toggle on Enter
-                        #[allow(clippy::collapsible_if)]
-                        if let Some(i) = self.settings_state.selected() {
-                            if i < self.settings_items.len() {
-                                if let SettingKind::Toggle { .. } = self.settings_items[i].kind {
-                                    self.adjust_setting(i, true); // Toggle
-                                    self.apply_setting(i)?;
-                                    self.editing_setting = false;
-                                } else if key == KeyCode::Left || key == KeyCode::Right {
-                                    self.adjust_setting(i, key == KeyCode::Right);
-                                    self.apply_setting(i)?;
-                                }
+                    self.editing_setting = true;
+                    self.status = "[←→] Adjust  [Enter] Confirm".to_string();
+
+                    // For toggles, immediately toggle on Enter
+                    #[allow(clippy::collapsible_if)]
+                    if let Some(i) = self.settings_state.selected() {
+                        if i < self.settings_items.len() {
+                            if let SettingKind::Toggle { .. } = self.settings_items[i].kind {
+                                self.adjust_setting(i, true); // Toggle
+                                self.apply_setting(i)?;
+                                self.editing_setting = false;
+                            } else if key == KeyCode::Left || key == KeyCode::Right {
+                                self.adjust_setting(i, key == KeyCode::Right);
+                                self.apply_setting(i)?;
                             }
                         }
                     }
@@ -502,6 +936,260 @@ impl App {
         Ok(())
     }
 
+    /// Handle log view input
+    fn handle_logs_input(&mut self, key: KeyCode) {
+        let max_scroll = self.logs.buffer().len().saturating_sub(1);
+
+        match key {
+            KeyCode::PageUp => {
+                self.log_scroll = (self.log_scroll + LOG_PAGE_LINES).min(max_scroll);
+            }
+            KeyCode::PageDown => {
+                self.log_scroll = self.log_scroll.saturating_sub(LOG_PAGE_LINES);
+            }
+            _ if input::is_nav_up(key) => {
+                self.log_scroll = (self.log_scroll + 1).min(max_scroll);
+            }
+            _ if input::is_nav_down(key) => {
+                self.log_scroll = self.log_scroll.saturating_sub(1);
+            }
+            _ if input::is_back(key) || input::is_logs(key) => {
+                self.view = if self.recovery_mode {
+                    View::Recovery
+                } else {
+                    View::Systems
+                };
+            }
+            _ => {}
+        }
+    }
+
+    /// Open the log view scrolled to the newest lines
+    fn open_logs(&mut self) {
+        self.refresh_logs();
+        self.log_scroll = 0;
+        self.view = View::Logs;
+        self.status = format!("{} log lines", self.logs.buffer().len());
+    }
+
+    /// Show charging / low battery on the indicator LEDs
+    fn update_status_led(&mut self) {
+        if self.leds.is_empty()
+            || self
+                .last_led_check
+                .is_some_and(|t| t.elapsed() < LED_CHECK_INTERVAL)
+        {
+            return;
+        }
+        self.last_led_check = Some(Instant::now());
+
+        let Some(power) = self.power.as_ref() else {
+            return;
+        };
+
+        let color = LedColor::for_battery(power.is_charger_connected(), power.is_battery_low());
+        if self.led_color == Some(color) {
+            return;
+        }
+
+        for led in &self.leds {
+            if let Err(e) = led.set_color(color) {
+                warn!("Failed to set LED {}: {}", led.name, e);
+            }
+        }
+        self.led_color = Some(color);
+    }
+
+    /// Rescan when the secondary card is inserted or removed
+    ///
+    /// Only changed ROMs are processed, so this is quick enough to run
+    /// without asking.
+    fn update_cards(&mut self) {
+        if self
+            .last_card_check
+            .is_some_and(|t| t.elapsed() < CARD_CHECK_INTERVAL)
+        {
+            return;
+        }
+        self.last_card_check = Some(Instant::now());
+
+        let mounted = Self::get_roms2_dir().is_some();
+        if mounted == self.roms2_mounted {
+            return;
+        }
+        self.roms2_mounted = mounted;
+        info!(
+            "Secondary card {}, rescanning",
+            if mounted { "inserted" } else { "removed" }
+        );
+        if let Err(e) = self.rescan_roms() {
+            error!("Failed to start rescan: {}", e);
+        }
+    }
+
+    /// Read the battery for the header indicator and handle power events
+    ///
+    /// Reading on a fixed interval also feeds the power manager's
+    /// moving average, so the percentage settles instead of jittering.
+    fn update_power(&mut self) {
+        if self
+            .last_battery_check
+            .is_some_and(|t| t.elapsed() < BATTERY_CHECK_INTERVAL)
+        {
+            return;
+        }
+        self.last_battery_check = Some(Instant::now());
+
+        let Some(power) = self.power.as_ref() else {
+            return;
+        };
+        self.battery = power.get_battery_info().ok();
+
+        let mut events = match self.battery.as_ref() {
+            Some(battery) => power.battery_events(battery),
+            None => Vec::new(),
+        };
+        events.extend(power.poll_thermal());
+
+        for event in events {
+            match event {
+                PowerEvent::LowBattery(percentage) => {
+                    self.battery_warning = Some(format!(
+                        "Battery low ({}%)\nConnect the charger",
+                        percentage
+                    ));
+                }
+                // rexos-init shuts down on critical battery
+                PowerEvent::CriticalBattery(percentage) => {
+                    self.battery_warning = Some(format!(
+                        "Battery critical ({}%)\nShutting down to save your data",
+                        percentage
+                    ));
+                }
+                PowerEvent::Charging => {
+                    self.battery_warning = None;
+                    self.status = "Charging".to_string();
+                }
+                PowerEvent::Unplugged => self.status = "Charger unplugged".to_string(),
+                PowerEvent::ThermalThrottle(temperature) => {
+                    self.status = format!("Device is hot ({}°C), games may slow down", temperature);
+                }
+            }
+        }
+    }
+
+    /// Rotate the screen now; init reapplies the saved rotation at boot
+    fn apply_rotation(&self, degrees: u16) {
+        let Some(rotation) = Rotation::from_degrees(u32::from(degrees)) else {
+            return;
+        };
+        let display_config = DisplayConfig {
+            brightness: self.config.config().system.brightness,
+            ..DisplayConfig::default()
+        };
+        match Display::new(display_config) {
+            Ok(mut display) => {
+                if let Err(e) = display.set_rotation(rotation) {
+                    warn!("Failed to set display rotation: {}", e);
+                }
+            }
+            Err(e) => warn!("Display not available: {}", e),
+        }
+    }
+
+    /// Show WiFi and Bluetooth connection changes in the status bar
+    fn poll_network_events(&mut self) {
+        let Some(events) = &self.network_events else {
+            return;
+        };
+        let events: Vec<_> = events.try_iter().collect();
+
+        for event in events {
+            let status = match event {
+                NetworkEvent::WifiStateChanged { ssid, state } => {
+                    let ssid = ssid.unwrap_or_else(|| "WiFi".to_string());
+                    match state {
+                        ConnectionState::Connecting => format!("Connecting to {}...", ssid),
+                        ConnectionState::Connected => format!("Connected to {}", ssid),
+                        ConnectionState::Retrying { attempt, attempts } => {
+                            format!("Retrying {} ({}/{})", ssid, attempt, attempts)
+                        }
+                        ConnectionState::Failed => format!("Failed to connect to {}", ssid),
+                        ConnectionState::Disconnected => format!("Disconnected from {}", ssid),
+                        ConnectionState::Scanning => continue,
+                    }
+                }
+                NetworkEvent::IpAcquired(ip) => {
+                    // Sync the clock now rather than at the next check
+                    self.last_net_check = None;
+                    format!("IP address: {}", ip)
+                }
+                NetworkEvent::BluetoothConnected(address) => {
+                    format!("Bluetooth connected: {}", address)
+                }
+                NetworkEvent::BluetoothDisconnected(address) => {
+                    format!("Bluetooth disconnected: {}", address)
+                }
+                _ => continue,
+            };
+            info!("{}", status);
+            self.status = status;
+        }
+    }
+
+    /// Synchronize the clock once a network connection comes up
+    ///
+    /// Runs in the background so a slow NTP server can't stall the UI.
+    fn check_time_sync(&mut self) {
+        if self.time_synced {
+            return;
+        }
+
+        if let Some(handle) = self.time_sync.take() {
+            if handle.is_finished() {
+                self.time_synced = handle.join().unwrap_or(false);
+            } else {
+                self.time_sync = Some(handle);
+            }
+            return;
+        }
+
+        if self
+            .last_net_check
+            .is_some_and(|t| t.elapsed() < NET_CHECK_INTERVAL)
+        {
+            return;
+        }
+        self.last_net_check = Some(Instant::now());
+
+        if !self.network.as_ref().is_some_and(|n| n.is_connected()) {
+            return;
+        }
+
+        let sync = TimeSync::new().with_timezone(self.config.config().system.timezone.clone());
+        self.time_sync = Some(std::thread::spawn(move || match sync.sync_now() {
+            Ok(_) => true,
+            Err(e) => {
+                warn!("Clock sync failed: {}", e);
+                false
+            }
+        }));
+    }
+
+    /// Pull new lines from the log sources
+    fn refresh_logs(&mut self) {
+        match self.logs.poll() {
+            Ok(added) => {
+                // Keep the view anchored while scrolled back
+                if self.log_scroll > 0 {
+                    let max_scroll = self.logs.buffer().len().saturating_sub(1);
+                    self.log_scroll = (self.log_scroll + added).min(max_scroll);
+                }
+            }
+            Err(e) => debug!("Failed to read logs: {}", e),
+        }
+    }
+
     /// Select previous setting
     fn select_prev_setting(&mut self) {
         if self.settings_items.is_empty() {
@@ -615,16 +1303,23 @@ impl App {
         #[allow(clippy::collapsible_if)]
         if let Some(i) = self.systems_state.selected() {
             if i < self.systems.len() {
-                let system = &self.systems[i].0;
+                let system = self.systems[i].0.clone();
                 self.selected_system = Some(system.clone());
-                self.games = self.db.get_games_by_system(system)?;
+                self.games.clear();
+                self.games_state.select(None);
                 self.view = View::Games;
-
-                if !self.games.is_empty() {
-                    self.games_state.select(Some(0));
+                self.status = "Loading...".to_string();
+
+                // Filled in by handle_db_response
+                let library = &self.config.config().library;
+                if library.hide_other_regions && !library.preferred_regions.is_empty() {
+                    self.db.send(DbRequest::GamesInRegions {
+                        system,
+                        regions: library.preferred_regions.clone(),
+                    })?;
+                } else {
+                    self.db.send(DbRequest::GamesBySystem(system))?;
                 }
-
-                self.status = format!("{} games", self.games.len());
             }
         }
         Ok(())
@@ -677,13 +1372,22 @@ impl App {
 
     /// Toggle favorite for selected game
     fn toggle_favorite(&mut self) -> Result<()> {
+        // Changes to the relocated database would be lost on reboot
+        if self.roms_read_only {
+            self.status = READ_ONLY_STATUS.to_string();
+            return Ok(());
+        }
+
         // Avoid if-let chains for MSRV 1.85 compatibility
         #[allow(clippy::collapsible_if)]
         if let Some(i) = self.games_state.selected() {
             if i < self.games.len() {
                 let game = &mut self.games[i];
                 game.favorite = !game.favorite;
-                self.db.set_favorite(game.id, game.favorite)?;
+                self.db.send(DbRequest::SetFavorite {
+                    id: game.id,
+                    favorite: game.favorite,
+                })?;
 
                 self.status = if game.favorite {
                     "Added to favorites".to_string()
@@ -704,26 +1408,108 @@ impl App {
                 let game = &self.games[i];
                 self.status = format!("Launching {}...", game.name);
 
-                // Build launch config
-                let config = LaunchConfig::for_rom(&game.path);
+                // Build launch config, applying any per-game shader
+                let mut config = LaunchConfig::for_rom(game.rom_path());
+                if let Some(value) = self
+                    .config
+                    .config()
+                    .emulators
+                    .get_game_shader(&game.system, &game_file_name(game))
+                {
+                    config = config.with_shader(ShaderChoice::parse(
+                        value,
+                        &self.launcher.shaders().shaders_dir,
+                    ));
+                }
+
+                // A per-game command replaces the emulator entirely
+                if let Some(command) = &game.launch_override {
+                    config = config.with_command(command.clone());
+                }
+
+                // Per-game or per-system clock caps, reverted when the game exits
+                let clocks = self
+                    .config
+                    .config()
+                    .emulators
+                    .get_clocks(&game.system, &game_file_name(game));
+                let _freq_guard = match (clocks, self.power.as_ref()) {
+                    (Some(clocks), Some(power)) => power
+                        .set_freq_limits(FreqLimits {
+                            cpu_max_khz: clocks.cpu_max_khz,
+                            gpu_max_hz: clocks.gpu_max_hz,
+                        })
+                        .map_err(|e| warn!("Failed to apply clock limits: {}", e))
+                        .ok(),
+                    _ => None,
+                };
 
                 // Launch game
                 match self.launcher.launch(config) {
-                    Ok(result) => {
+                    Ok(mut result) => {
                         info!("Launched game with PID {}", result.pid);
 
-                        // Wait for emulator to exit
-                        let mut child = result.child;
-                        let _ = child.wait();
+                        if let Some(input) = self.input.as_mut() {
+                            let _ = input.rumble(0.4, Duration::from_millis(120));
+                        }
 
-                        // Update play stats
-                        self.db.update_play_stats(game.id, 0)?;
+                        // Wait for emulator to exit, sampling metrics if enabled
+                        let metrics = self
+                            .config
+                            .config()
+                            .emulators
+                            .session_metrics
+                            .then(SessionMetrics::start);
+                        if let Err(e) = result.wait() {
+                            error!("Failed to wait for emulator: {}", e);
+                        }
+                        if let Some(metrics) = metrics {
+                            info!("Session metrics for {}: {}", game.name, metrics.finish());
+                        }
+                        let Some(exit) = result.exit_info() else {
+                            self.status = "Error: lost track of the emulator".to_string();
+                            return Ok(());
+                        };
+                        if exit.crashed() {
+                            warn!(
+                                "{} crashed running {} after {}s",
+                                exit.emulator,
+                                game.name,
+                                exit.duration.as_secs()
+                            );
+                        }
 
-                        self.status = "Ready".to_string();
+                        // Update play stats
+                        self.db.send(DbRequest::UpdatePlayStats {
+                            id: game.id,
+                            play_time: exit.duration.as_secs() as i64,
+                        })?;
+
+                        self.status = match &exit.reason {
+                            Some(reason) => {
+                                for line in &exit.stderr_tail {
+                                    warn!("{}: {}", exit.emulator, line);
+                                }
+                                match &exit.log_path {
+                                    Some(path) => format!(
+                                        "RetroArch exited ({}), see {}",
+                                        reason,
+                                        path.display()
+                                    ),
+                                    None => format!("RetroArch exited ({})", reason),
+                                }
+                            }
+                            None => "Ready".to_string(),
+                        };
                     }
                     Err(e) => {
                         error!("Failed to launch game: {}", e);
                         self.status = format!("Error: {}", e);
+
+                        // Buzz so the failure is noticed without looking at the status line
+                        if let Some(input) = self.input.as_mut() {
+                            let _ = input.rumble(0.8, Duration::from_millis(300));
+                        }
                     }
                 }
             }
@@ -735,26 +1521,118 @@ impl App {
     fn rescan_roms(&mut self) -> Result<()> {
         self.status = "Scanning ROMs...".to_string();
 
-        let scanner = RomScanner::new();
-        let roms_dir = Self::get_roms_dir();
+        // The scan runs once the stored stamps arrive
+        self.db.send(DbRequest::ScanIndex)?;
+        Ok(())
+    }
 
-        if let Ok(results) = scanner.scan_all(&roms_dir) {
-            let mut total_games = 0;
+    /// Scan for new, changed and removed ROMs and store the changes
+    fn scan_roms(&mut self, index: ScanIndex) -> Result<()> {
+        let mut scanner = RomScanner::new()
+            .with_name_cleaner(NameCleaner::new(self.config.config().library.names.clone()))
+            .with_index(index);
+        if let Some(roms2) = Self::get_roms2_dir() {
+            scanner = scanner.with_secondary_roms(roms2);
+        }
+        let roms_dir = Self::get_roms_dir();
 
-            for (_system, games) in results {
-                for game in games {
-                    self.db.add_game(&game)?;
-                    total_games += 1;
-                }
+        let mut games: Vec<Game> = Vec::new();
+        let scanned = scanner.scan_all_with_progress(&roms_dir, |progress, found| {
+            info!(
+                "Scanned {} ({}/{}): {} games",
+                progress.system, progress.completed, progress.total, progress.games
+            );
+            games.extend(found);
+        });
+
+        if let Ok(result) = scanned {
+            for e in &result.errors {
+                warn!("Scan error: {}", e);
             }
+            info!(
+                "Scanned {} games in {} ms: {} new, {} updated, {} removed",
+                result.games_found,
+                result.duration_ms,
+                result.games_added,
+                result.games_updated,
+                result.games_removed
+            );
+            self.status = format!(
+                "Found {} games ({} new, {} removed)",
+                result.games_found, result.games_added, result.games_removed
+            );
+
+            // Store, then refresh the systems list once stored
+            self.db.send(DbRequest::ApplyScan {
+                games,
+                removed: result.removed,
+            })?;
+            self.db.send(DbRequest::Systems)?;
+        }
 
-            self.status = format!("Found {} games", total_games);
+        Ok(())
+    }
 
-            // Refresh systems list
-            self.systems = self.db.get_systems()?;
+    /// Apply finished database requests
+    fn poll_db(&mut self) {
+        while let Some(response) = self.db.try_recv() {
+            self.handle_db_response(response);
         }
+    }
 
-        Ok(())
+    /// Apply one database response
+    fn handle_db_response(&mut self, response: DbResponse) {
+        match response {
+            DbResponse::Systems(Ok(systems)) => {
+                self.systems = systems;
+                if self.systems_state.selected().is_none() && !self.systems.is_empty() {
+                    self.systems_state.select(Some(0));
+                }
+            }
+            DbResponse::Games { system, games } => {
+                // Ignore results for a system the user already left
+                if self.view != View::Games || self.selected_system.as_ref() != Some(&system) {
+                    return;
+                }
+                match games {
+                    Ok(mut games) => {
+                        // Preferred regions first, keeping name order within each group
+                        let preferred = &self.config.config().library.preferred_regions;
+                        if !preferred.is_empty() {
+                            games.sort_by_key(|game| {
+                                !region_matches(game.region.as_deref(), preferred)
+                            });
+                        }
+                        self.games = games;
+                        if !self.games.is_empty() {
+                            self.games_state.select(Some(0));
+                        }
+                        self.status = format!("{} games", self.games.len());
+                    }
+                    Err(e) => self.status = format!("Error: {}", e),
+                }
+            }
+            DbResponse::GamesAdded(Ok(count)) => {
+                debug!("Stored {} new or changed games", count);
+            }
+            DbResponse::ScanIndex(index) => {
+                let index = index.unwrap_or_else(|e| {
+                    warn!("Stored ROM stamps unavailable, scanning everything: {}", e);
+                    ScanIndex::new()
+                });
+                if let Err(e) = self.scan_roms(index) {
+                    error!("ROM scan failed: {}", e);
+                    self.status = format!("Error: {}", e);
+                }
+            }
+            DbResponse::Done(Ok(())) => {}
+            DbResponse::Systems(Err(e))
+            | DbResponse::GamesAdded(Err(e))
+            | DbResponse::Done(Err(e)) => {
+                error!("Database error: {}", e);
+                self.status = format!("Error: {}", e);
+            }
+        }
     }
 
     /// Get selected game
@@ -763,6 +1641,14 @@ impl App {
     }
 }
 
+/// ROM file name, identifying the game in per-game settings
+fn game_file_name(game: &Game) -> String {
+    game.rom_path()
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| game.path.clone())
+}
+
 /// Draw the UI
 fn draw_ui(frame: &mut Frame, app: &mut App) {
     let chunks = Layout::default()
@@ -783,10 +1669,37 @@ fn draw_ui(frame: &mut Frame, app: &mut App) {
         View::Games => draw_games_view(frame, chunks[1], app),
         View::GameInfo => draw_game_info_view(frame, chunks[1], app),
         View::Settings => draw_settings_view(frame, chunks[1], app),
+        View::Logs => draw_logs_view(frame, chunks[1], app),
+        View::Shaders => draw_shaders_view(frame, chunks[1], app),
+        View::Recovery => draw_recovery_view(frame, chunks[1], app),
     }
 
     // Draw footer
     draw_footer(frame, chunks[2], app);
+
+    if let Some(warning) = app.battery_warning.as_deref() {
+        draw_battery_warning(frame, chunks[1], warning);
+    }
+}
+
+/// Draw the battery warning centered over `area`
+fn draw_battery_warning(frame: &mut Frame, area: Rect, warning: &str) {
+    let width = 40.min(area.width);
+    let height = 5.min(area.height);
+    let popup = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+
+    let text = Paragraph::new(warning)
+        .alignment(Alignment::Center)
+        .style(ui::header_style())
+        .block(Block::default().borders(Borders::ALL).title("Battery"));
+
+    frame.render_widget(Clear, popup);
+    frame.render_widget(text, popup);
 }
 
 /// Draw header
@@ -799,15 +1712,39 @@ fn draw_header(frame: &mut Frame, area: Rect, app: &App) {
         ),
         View::GameInfo => "RexOS - Game Info",
         View::Settings => "RexOS - Settings",
+        View::Logs => "RexOS - System Log",
+        View::Shaders => "RexOS - Shader",
+        View::Recovery => "RexOS - Recovery",
     };
 
-    let header = Paragraph::new(title)
-        .style(ui::header_style())
-        .block(Block::default().borders(Borders::ALL));
+    let mut block = Block::default().borders(Borders::ALL);
+    if let Some(battery) = app.battery.as_ref() {
+        block = block.title(Title::from(battery_label(battery)).alignment(Alignment::Right));
+    }
+
+    let header = Paragraph::new(title).style(ui::header_style()).block(block);
 
     frame.render_widget(header, area);
 }
 
+/// Battery percentage with time to empty or full, e.g. "72% 3h05m"
+fn battery_label(battery: &BatteryInfo) -> String {
+    let charging = if battery.is_charging { "+" } else { "" };
+    match battery.time_remaining() {
+        Some(remaining) => {
+            let minutes = remaining.as_secs() / 60;
+            format!(
+                " {}{}% {}h{:02}m ",
+                charging,
+                battery.smoothed_percentage,
+                minutes / 60,
+                minutes % 60
+            )
+        }
+        None => format!(" {}{}% ", charging, battery.smoothed_percentage),
+    }
+}
+
 /// Draw systems view
 fn draw_systems_view(frame: &mut Frame, area: Rect, app: &mut App) {
     let items: Vec<ListItem> = app
@@ -865,7 +1802,7 @@ fn draw_game_info_view(frame: &mut Frame, area: Rect, app: &App) {
             ]),
             Line::from(vec![
                 Span::styled("Path: ", ui::label_style()),
-                Span::raw(&game.path),
+                Span::raw(game.rom_path().display().to_string()),
             ]),
         ];
 
@@ -950,12 +1887,80 @@ fn draw_settings_view(frame: &mut Frame, area: Rect, app: &mut App) {
     frame.render_stateful_widget(list, area, &mut app.settings_state);
 }
 
+/// Draw log view - newest lines at the bottom, scrollable
+fn draw_logs_view(frame: &mut Frame, area: Rect, app: &App) {
+    let buffer = app.logs.buffer();
+    let height = area.height.saturating_sub(2) as usize;
+    let end = buffer.len().saturating_sub(app.log_scroll);
+    let start = end.saturating_sub(height);
+
+    let lines: Vec<Line> = buffer
+        .iter()
+        .skip(start)
+        .take(end - start)
+        .map(|line| Line::styled(line.display(), ui::log_style(line.level)))
+        .collect();
+
+    let title = if app.log_scroll > 0 {
+        format!("System Log (-{})", app.log_scroll)
+    } else {
+        "System Log".to_string()
+    };
+
+    let paragraph = Paragraph::new(Text::from(lines))
+        .block(Block::default().borders(Borders::ALL).title(title));
+
+    frame.render_widget(paragraph, area);
+}
+
+/// Draw shader picker
+fn draw_shaders_view(frame: &mut Frame, area: Rect, app: &mut App) {
+    let shaders_dir = app.launcher.shaders().shaders_dir.clone();
+
+    let items: Vec<ListItem> = SHADER_PICKER_FIXED
+        .iter()
+        .map(|name| ListItem::new(*name))
+        .chain(app.shader_presets.iter().map(|path| {
+            let name = path.strip_prefix(&shaders_dir).unwrap_or(path);
+            ListItem::new(name.display().to_string())
+        }))
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Shader Preset"),
+        )
+        .highlight_style(ui::highlight_style())
+        .highlight_symbol(ui::SELECTION_SYMBOL);
+
+    frame.render_stateful_widget(list, area, &mut app.shader_state);
+}
+
+/// Draw recovery menu
+fn draw_recovery_view(frame: &mut Frame, area: Rect, app: &mut App) {
+    let items: Vec<ListItem> = RecoveryAction::ALL
+        .iter()
+        .map(|action| ListItem::new(action.label()))
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title("Recovery"))
+        .highlight_style(ui::highlight_style())
+        .highlight_symbol(ui::SELECTION_SYMBOL);
+
+    frame.render_stateful_widget(list, area, &mut app.recovery_state);
+}
+
 /// Draw footer
 fn draw_footer(frame: &mut Frame, area: Rect, app: &App) {
     let help_text = match app.view {
-        View::Systems => "[↑↓] Navigate  [Enter] Select  [R] Rescan  [Tab] Settings  [Q] Quit",
+        View::Systems => {
+            "[↑↓] Navigate  [Enter] Select  [R] Rescan  [Tab] Settings  [L] Logs  [Q] Quit"
+        }
         View::Games => "[↑↓] Navigate  [Enter] Launch  [F] Favorite  [X] Info  [B] Back",
-        View::GameInfo => "[Enter] Launch  [B] Back",
+        View::GameInfo => "[Enter] Launch  [X] Shader  [B] Back",
         View::Settings => {
             if app.editing_setting {
                 "[←→] Adjust  [Enter] Confirm  [B] Cancel"
@@ -963,6 +1968,9 @@ fn draw_footer(frame: &mut Frame, area: Rect, app: &App) {
                 "[↑↓] Navigate  [Enter/←→] Edit  [Tab/B] Back"
             }
         }
+        View::Logs => "[↑↓] Scroll  [L1/R1] Page  [B] Back",
+        View::Shaders => "[↑↓] Navigate  [Enter] Select  [B] Back",
+        View::Recovery => "[↑↓] Navigate  [Enter] Select",
     };
 
     let chunks = Layout::default()
@@ -982,6 +1990,9 @@ fn draw_footer(frame: &mut Frame, area: Rect, app: &App) {
     frame.render_widget(status, chunks[1]);
 }
 
+/// Lines scrolled per page in the log view
+const LOG_PAGE_LINES: usize = 10;
+
 fn main() -> Result<()> {
     // Setup logging
     tracing_subscriber::fmt().with_env_filter("info").init();
@@ -997,12 +2008,13 @@ fn main() -> Result<()> {
 
     // Create app
     let mut app = App::new()?;
+    if std::env::args().any(|arg| arg == "--recovery") {
+        app.enter_recovery();
+    }
 
     // Main loop
     let tick_rate = Duration::from_millis(50); // Faster for responsive gamepad input
     let mut last_tick = Instant::now();
-    let mut last_gamepad_input = Instant::now();
-    let gamepad_repeat_delay = Duration::from_millis(150); // Debounce gamepad
 
     loop {
         terminal.draw(|f| draw_ui(f, &mut app))?;
@@ -1021,17 +2033,28 @@ fn main() -> Result<()> {
             }
         }
 
-        // Also check gamepad input (with debounce)
-        #[allow(clippy::collapsible_if)]
-        if last_gamepad_input.elapsed() >= gamepad_repeat_delay {
-            if let Some(key) = app.poll_gamepad() {
-                app.handle_input(key)?;
-                last_gamepad_input = Instant::now();
-            }
+        // Also check gamepad input (held directions repeat)
+        if let Some(key) = app.poll_gamepad() {
+            app.handle_input(key)?;
         }
 
+        app.poll_db();
+
         if last_tick.elapsed() >= tick_rate {
             last_tick = Instant::now();
+
+            if app.view == View::Logs {
+                app.refresh_logs();
+            }
+
+            app.update_status_led();
+            app.update_power();
+            app.update_cards();
+            app.poll_network_events();
+            app.check_time_sync();
+            if let Err(e) = app.config.poll() {
+                error!("Failed to save configuration: {}", e);
+            }
         }
 
         if app.should_quit {
@@ -1039,6 +2062,10 @@ fn main() -> Result<()> {
         }
     }
 
+    if let Err(e) = app.config.flush() {
+        error!("Failed to save configuration: {}", e);
+    }
+
     // Restore terminal
     disable_raw_mode()?;
     execute!(
@@ -1058,6 +2085,7 @@ mod ui {
     //! This module contains reusable UI components for the TUI launcher.
 
     use ratatui::style::{Color, Modifier, Style};
+    use rexos_hal::logs::LogLevel;
 
     /// Default highlight style for selected items
     pub fn highlight_style() -> Style {
@@ -1089,6 +2117,18 @@ mod ui {
             .add_modifier(Modifier::BOLD)
     }
 
+    /// Style for a log line by severity
+    pub fn log_style(level: Option<LogLevel>) -> Style {
+        match level {
+            Some(LogLevel::Emergency | LogLevel::Alert | LogLevel::Critical | LogLevel::Error) => {
+                Style::default().fg(Color::Red)
+            }
+            Some(LogLevel::Warning) => Style::default().fg(Color::Yellow),
+            Some(LogLevel::Debug) => Style::default().fg(Color::DarkGray),
+            _ => Style::default(),
+        }
+    }
+
     /// Prefix for favorite games
     pub const FAVORITE_PREFIX: &str = "★ ";
 
@@ -1158,6 +2198,11 @@ mod input {
     pub fn is_tab(key: KeyCode) -> bool {
         matches!(key, KeyCode::Tab)
     }
+
+    /// Check if a key opens the log view
+    pub fn is_logs(key: KeyCode) -> bool {
+        matches!(key, KeyCode::Char('l'))
+    }
 }
 
 #[allow(dead_code)] // State utilities module - provides alternative/extended state types
@@ -1,8 +1,10 @@
 //! Game database using SQLite
 
-use crate::{GameMetadata, LibraryError};
+use crate::{FileStamp, GameMetadata, LibraryError, ScanIndex, region_matches};
+use rexos_storage::MountManager;
 use rusqlite::{Connection, OptionalExtension, params};
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 /// A game in the library
 #[derive(Debug, Clone)]
@@ -20,9 +22,29 @@ pub struct Game {
     pub rating: Option<f32>,
     pub favorite: bool,
     pub hidden: bool,
+    /// Region codes from the file name, comma separated (e.g. "USA,EUR")
+    pub region: Option<String>,
+    /// ROM file size in bytes when scanned
+    pub size: i64,
+    /// Command template run instead of the emulator (see
+    /// `rexos_emulator::CustomCommand` for placeholders)
+    pub launch_override: Option<String>,
 }
 
 impl Game {
+    /// Filesystem path of the ROM, restoring any non-UTF-8 bytes
+    pub fn rom_path(&self) -> PathBuf {
+        crate::decode_path(&self.path)
+    }
+
+    /// File name of the ROM without extension, before name cleaning
+    pub fn raw_name(&self) -> String {
+        self.rom_path()
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
     /// Apply metadata from a GameMetadata struct (e.g., from gamelist.xml)
     ///
     /// This merges metadata into the game, preferring existing values
@@ -69,6 +91,22 @@ pub struct GameStats {
     pub play_time_seconds: i64,
 }
 
+/// Aggregate numbers for the library statistics screen
+///
+/// Hidden games are left out, as they are from the game lists.
+#[derive(Debug, Clone, Default)]
+pub struct LibraryStats {
+    pub total_games: i64,
+    /// Game count per system, sorted by system
+    pub systems: Vec<(String, i64)>,
+    pub total_play_time_seconds: i64,
+    pub favorites: i64,
+    /// Game with the most play time, and that time in seconds
+    pub most_played: Option<(Game, i64)>,
+    /// Sum of scanned ROM sizes in bytes
+    pub total_size: i64,
+}
+
 /// Game database manager
 pub struct GameDatabase {
     conn: Connection,
@@ -85,6 +123,52 @@ impl GameDatabase {
         Ok(db)
     }
 
+    /// Open a database, moving it off read-only storage
+    ///
+    /// When `path` is on a read-only mount (e.g. an exFAT card after an
+    /// unclean eject), any existing database is copied to `fallback` and
+    /// opened there so the library still loads. Returns the database and
+    /// whether it was relocated; changes to a relocated copy aren't saved
+    /// back to the card.
+    pub fn open_writable(path: &Path, fallback: &Path) -> Result<(Self, bool), LibraryError> {
+        let read_only = MountManager::default().is_read_only(path);
+        Self::open_at(path, fallback, read_only)
+    }
+
+    fn open_at(
+        path: &Path,
+        fallback: &Path,
+        read_only: bool,
+    ) -> Result<(Self, bool), LibraryError> {
+        if !read_only {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            return Ok((Self::open(path)?, false));
+        }
+
+        tracing::warn!(
+            "{} is on read-only storage, using {}",
+            path.display(),
+            fallback.display()
+        );
+        let relocate = || -> std::io::Result<()> {
+            if let Some(parent) = fallback.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            if path.exists() {
+                fs::copy(path, fallback)?;
+            }
+            Ok(())
+        };
+        relocate().map_err(|e| {
+            tracing::error!("Failed to relocate database: {}", e);
+            LibraryError::ReadOnly(path.to_path_buf())
+        })?;
+
+        Ok((Self::open(fallback)?, true))
+    }
+
     /// Create an in-memory database (for testing)
     pub fn in_memory() -> Result<Self, LibraryError> {
         let conn = Connection::open_in_memory()?;
@@ -113,6 +197,7 @@ impl GameDatabase {
                 rating REAL,
                 favorite INTEGER DEFAULT 0,
                 hidden INTEGER DEFAULT 0,
+                region TEXT,
                 created_at TEXT DEFAULT CURRENT_TIMESTAMP,
                 updated_at TEXT DEFAULT CURRENT_TIMESTAMP
             );
@@ -148,16 +233,53 @@ impl GameDatabase {
         "#,
         )?;
 
+        // Columns added after the first release
+        self.add_column_if_missing("games", "region", "TEXT")?;
+        self.add_column_if_missing("games", "size", "INTEGER DEFAULT 0")?;
+        self.add_column_if_missing("games", "launch_override", "TEXT")?;
+        self.add_column_if_missing("games", "mtime", "INTEGER")?;
+
+        Ok(())
+    }
+
+    /// Add a column to a table created by an older version
+    fn add_column_if_missing(
+        &self,
+        table: &str,
+        column: &str,
+        definition: &str,
+    ) -> Result<(), LibraryError> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("SELECT name FROM pragma_table_info('{}')", table))?;
+        let exists = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?
+            .iter()
+            .any(|name| name == column);
+
+        if !exists {
+            self.conn.execute_batch(&format!(
+                "ALTER TABLE {} ADD COLUMN {} {}",
+                table, column, definition
+            ))?;
+        }
         Ok(())
     }
 
     /// Add a game to the database
+    ///
+    /// Replacing an existing game keeps its launch override unless the new
+    /// entry sets one, so a rescan doesn't drop it.
     pub fn add_game(&self, game: &Game) -> Result<i64, LibraryError> {
         self.conn.execute(
             r#"INSERT OR REPLACE INTO games
                (path, system, name, description, release_date, developer,
-                publisher, genre, players, rating, favorite, hidden, updated_at)
-               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, CURRENT_TIMESTAMP)"#,
+                publisher, genre, players, rating, favorite, hidden, region, size,
+                launch_override, updated_at)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14,
+                       COALESCE(?15, (SELECT launch_override FROM games WHERE path = ?1)),
+                       CURRENT_TIMESTAMP)"#,
             params![
                 game.path,
                 game.system,
@@ -171,6 +293,9 @@ impl GameDatabase {
                 game.rating,
                 game.favorite,
                 game.hidden,
+                game.region,
+                game.size,
+                game.launch_override,
             ],
         )?;
 
@@ -229,6 +354,22 @@ impl GameDatabase {
         Ok(games)
     }
 
+    /// Get games by system, keeping only the preferred regions
+    ///
+    /// Games without a known region and world releases are always kept.
+    /// An empty list keeps everything.
+    pub fn get_games_by_system_in_regions(
+        &self,
+        system: &str,
+        regions: &[String],
+    ) -> Result<Vec<Game>, LibraryError> {
+        let mut games = self.get_games_by_system(system)?;
+        if !regions.is_empty() {
+            games.retain(|game| region_matches(game.region.as_deref(), regions));
+        }
+        Ok(games)
+    }
+
     /// Get favorite games
     pub fn get_favorites(&self) -> Result<Vec<Game>, LibraryError> {
         let mut stmt = self
@@ -291,6 +432,117 @@ impl GameDatabase {
         Ok(())
     }
 
+    /// Set several games as favorite in one transaction
+    ///
+    /// Unknown IDs are skipped. Returns the number of games updated.
+    pub fn set_favorite_bulk(&self, ids: &[i64], favorite: bool) -> Result<usize, LibraryError> {
+        self.set_flag_bulk("favorite", ids, favorite)
+    }
+
+    /// Set several games as hidden in one transaction
+    ///
+    /// Unknown IDs are skipped. Returns the number of games updated.
+    pub fn set_hidden_bulk(&self, ids: &[i64], hidden: bool) -> Result<usize, LibraryError> {
+        self.set_flag_bulk("hidden", ids, hidden)
+    }
+
+    /// Hide every game of a system, returning how many were hidden
+    pub fn hide_system(&self, system: &str) -> Result<usize, LibraryError> {
+        self.set_system_hidden(system, true)
+    }
+
+    /// Unhide every game of a system, returning how many were shown
+    pub fn unhide_system(&self, system: &str) -> Result<usize, LibraryError> {
+        self.set_system_hidden(system, false)
+    }
+
+    /// Update a boolean column for several games
+    fn set_flag_bulk(&self, column: &str, ids: &[i64], value: bool) -> Result<usize, LibraryError> {
+        let tx = self.conn.unchecked_transaction()?;
+        let mut updated = 0;
+        {
+            let mut stmt = tx.prepare(&format!(
+                "UPDATE games SET {} = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+                column
+            ))?;
+            for id in ids {
+                updated += stmt.execute(params![value, id])?;
+            }
+        }
+        tx.commit()?;
+        Ok(updated)
+    }
+
+    /// Set the hidden flag on a whole system
+    fn set_system_hidden(&self, system: &str, hidden: bool) -> Result<usize, LibraryError> {
+        let updated = self.conn.execute(
+            "UPDATE games SET hidden = ?1, updated_at = CURRENT_TIMESTAMP
+             WHERE system = ?2 AND hidden != ?1",
+            params![hidden, system],
+        )?;
+        Ok(updated)
+    }
+
+    /// Set or clear a game's custom launch command
+    pub fn set_launch_override(&self, id: i64, command: Option<&str>) -> Result<(), LibraryError> {
+        self.conn.execute(
+            "UPDATE games SET launch_override = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+            params![command, id],
+        )?;
+        Ok(())
+    }
+
+    /// Stamps of stored ROMs, for an incremental scan
+    ///
+    /// Games stored before stamps were recorded are left out, so the next
+    /// scan processes them once.
+    pub fn scan_index(&self) -> Result<ScanIndex, LibraryError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT path, system, size, mtime FROM games WHERE mtime IS NOT NULL")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, i64>(3)?,
+            ))
+        })?;
+
+        let mut index = ScanIndex::new();
+        for row in rows {
+            let (path, system, size, mtime) = row?;
+            let stamp = FileStamp {
+                size: size as u64,
+                mtime,
+            };
+            index.insert(path, system, stamp);
+        }
+        Ok(index)
+    }
+
+    /// Store a scan's changes in one transaction
+    ///
+    /// `games` are added with their file's current stamp and `removed`
+    /// paths are deleted. Returns the number of games stored.
+    pub fn apply_scan(&self, games: &[Game], removed: &[String]) -> Result<usize, LibraryError> {
+        let tx = self.conn.unchecked_transaction()?;
+        for game in games {
+            self.add_game(game)?;
+            if let Some(stamp) = FileStamp::of(&game.rom_path()) {
+                tx.execute(
+                    "UPDATE games SET size = ?1, mtime = ?2 WHERE path = ?3",
+                    params![stamp.size as i64, stamp.mtime, game.path],
+                )?;
+            }
+        }
+        for path in removed {
+            tx.execute("DELETE FROM games WHERE path = ?1", params![path])?;
+        }
+        tx.commit()?;
+        Ok(games.len())
+    }
+
     /// Delete a game
     pub fn delete_game(&self, id: i64) -> Result<(), LibraryError> {
         self.conn
@@ -360,6 +612,45 @@ impl GameDatabase {
         Ok(systems)
     }
 
+    /// Gather totals for the library statistics screen
+    pub fn library_stats(&self) -> Result<LibraryStats, LibraryError> {
+        let (total_games, favorites, total_size) = self.conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(favorite), 0), COALESCE(SUM(size), 0)
+             FROM games WHERE hidden = 0",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+
+        let total_play_time_seconds = self.conn.query_row(
+            "SELECT COALESCE(SUM(s.play_time_seconds), 0)
+             FROM game_stats s JOIN games g ON g.id = s.game_id
+             WHERE g.hidden = 0",
+            [],
+            |row| row.get(0),
+        )?;
+
+        let most_played = self
+            .conn
+            .query_row(
+                "SELECT g.*, s.play_time_seconds AS most_played_seconds
+                 FROM games g JOIN game_stats s ON g.id = s.game_id
+                 WHERE g.hidden = 0 AND s.play_time_seconds > 0
+                 ORDER BY s.play_time_seconds DESC LIMIT 1",
+                [],
+                |row| Ok((Self::row_to_game(row)?, row.get("most_played_seconds")?)),
+            )
+            .optional()?;
+
+        Ok(LibraryStats {
+            total_games,
+            systems: self.get_systems()?,
+            total_play_time_seconds,
+            favorites,
+            most_played,
+            total_size,
+        })
+    }
+
     /// Convert a row to a Game
     fn row_to_game(row: &rusqlite::Row) -> rusqlite::Result<Game> {
         Ok(Game {
@@ -376,6 +667,9 @@ impl GameDatabase {
             rating: row.get("rating")?,
             favorite: row.get("favorite")?,
             hidden: row.get("hidden")?,
+            region: row.get("region")?,
+            size: row.get("size")?,
+            launch_override: row.get("launch_override")?,
         })
     }
 }
@@ -390,6 +684,42 @@ mod tests {
         assert_eq!(db.game_count().unwrap(), 0);
     }
 
+    #[test]
+    fn test_read_only_database_relocated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("roms/.rexos/games.db");
+        let fallback = dir.path().join("tmp/games.db");
+
+        let (db, relocated) = GameDatabase::open_at(&path, &fallback, false).unwrap();
+        assert!(!relocated);
+        db.add_game(&Game {
+            id: 0,
+            path: "/roms/gba/test.gba".to_string(),
+            system: "gba".to_string(),
+            name: "Test Game".to_string(),
+            description: None,
+            release_date: None,
+            developer: None,
+            publisher: None,
+            genre: None,
+            players: None,
+            rating: None,
+            favorite: false,
+            hidden: false,
+            region: None,
+            size: 0,
+            launch_override: None,
+        })
+        .unwrap();
+        drop(db);
+
+        // The existing library is carried over to the fallback
+        let (db, relocated) = GameDatabase::open_at(&path, &fallback, true).unwrap();
+        assert!(relocated);
+        assert_eq!(db.game_count().unwrap(), 1);
+        assert!(fallback.exists());
+    }
+
     #[test]
     fn test_add_and_get_game() {
         let db = GameDatabase::in_memory().unwrap();
@@ -408,6 +738,9 @@ mod tests {
             rating: None,
             favorite: false,
             hidden: false,
+            region: None,
+            size: 0,
+            launch_override: None,
         };
 
         let id = db.add_game(&game).unwrap();
@@ -435,6 +768,9 @@ mod tests {
             rating: None,
             favorite: false,
             hidden: false,
+            region: None,
+            size: 0,
+            launch_override: None,
         };
 
         db.add_game(&game).unwrap();
@@ -443,4 +779,267 @@ mod tests {
         assert_eq!(results.len(), 1);
         assert!(results[0].name.contains("Mario"));
     }
+
+    #[test]
+    fn test_region_filter() {
+        let db = GameDatabase::in_memory().unwrap();
+
+        for (path, region) in [
+            ("/roms/snes/a (USA).sfc", Some("USA")),
+            ("/roms/snes/a (Japan).sfc", Some("JPN")),
+            ("/roms/snes/b (USA, Europe).sfc", Some("USA,EUR")),
+            ("/roms/snes/c (World).sfc", Some("WLD")),
+            ("/roms/snes/d.sfc", None),
+        ] {
+            let game = Game {
+                id: 0,
+                path: path.to_string(),
+                system: "snes".to_string(),
+                name: path.to_string(),
+                description: None,
+                release_date: None,
+                developer: None,
+                publisher: None,
+                genre: None,
+                players: None,
+                rating: None,
+                favorite: false,
+                hidden: false,
+                region: region.map(str::to_string),
+                size: 0,
+                launch_override: None,
+            };
+            db.add_game(&game).unwrap();
+        }
+
+        let eur = db
+            .get_games_by_system_in_regions("snes", &["EUR".to_string()])
+            .unwrap();
+        assert_eq!(eur.len(), 3);
+        assert!(eur.iter().all(|g| g.region.as_deref() != Some("USA")));
+
+        let all = db.get_games_by_system_in_regions("snes", &[]).unwrap();
+        assert_eq!(all.len(), 5);
+    }
+
+    #[test]
+    fn test_bulk_flags() {
+        let db = GameDatabase::in_memory().unwrap();
+
+        let mut ids = Vec::new();
+        for path in ["/roms/nes/a.nes", "/roms/nes/b.nes", "/roms/nes/c.nes"] {
+            let game = Game {
+                id: 0,
+                path: path.to_string(),
+                system: "nes".to_string(),
+                name: path.to_string(),
+                description: None,
+                release_date: None,
+                developer: None,
+                publisher: None,
+                genre: None,
+                players: None,
+                rating: None,
+                favorite: false,
+                hidden: false,
+                region: None,
+                size: 0,
+                launch_override: None,
+            };
+            ids.push(db.add_game(&game).unwrap());
+        }
+
+        // Unknown IDs are skipped
+        assert_eq!(
+            db.set_favorite_bulk(&[ids[0], ids[2], 999], true).unwrap(),
+            2
+        );
+        assert_eq!(db.get_favorites().unwrap().len(), 2);
+
+        assert_eq!(db.set_hidden_bulk(&ids[..2], true).unwrap(), 2);
+        assert_eq!(db.game_count().unwrap(), 1);
+        assert_eq!(db.set_hidden_bulk(&ids[..2], false).unwrap(), 2);
+        assert_eq!(db.game_count().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_hide_system() {
+        let db = GameDatabase::in_memory().unwrap();
+
+        for (path, system) in [
+            ("/roms/bios/scph1001.bin", "bios"),
+            ("/roms/bios/gba_bios.bin", "bios"),
+            ("/roms/gba/game.gba", "gba"),
+        ] {
+            let game = Game {
+                id: 0,
+                path: path.to_string(),
+                system: system.to_string(),
+                name: path.to_string(),
+                description: None,
+                release_date: None,
+                developer: None,
+                publisher: None,
+                genre: None,
+                players: None,
+                rating: None,
+                favorite: false,
+                hidden: false,
+                region: None,
+                size: 0,
+                launch_override: None,
+            };
+            db.add_game(&game).unwrap();
+        }
+
+        assert_eq!(db.hide_system("bios").unwrap(), 2);
+        assert!(db.get_games_by_system("bios").unwrap().is_empty());
+        assert_eq!(db.game_count().unwrap(), 1);
+        assert_eq!(db.game_count_by_system("bios").unwrap(), 0);
+
+        // Already hidden games aren't counted again
+        assert_eq!(db.hide_system("bios").unwrap(), 0);
+
+        assert_eq!(db.unhide_system("bios").unwrap(), 2);
+        assert_eq!(db.get_games_by_system("bios").unwrap().len(), 2);
+        assert_eq!(db.game_count().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_launch_override() {
+        let db = GameDatabase::in_memory().unwrap();
+        let mut game = Game {
+            id: 0,
+            path: "/roms/pc/doom/DOOM.BAT".to_string(),
+            system: "pc".to_string(),
+            name: "Doom".to_string(),
+            description: None,
+            release_date: None,
+            developer: None,
+            publisher: None,
+            genre: None,
+            players: None,
+            rating: None,
+            favorite: false,
+            hidden: false,
+            region: None,
+            size: 0,
+            launch_override: None,
+        };
+        let id = db.add_game(&game).unwrap();
+        assert_eq!(db.get_game(id).unwrap().unwrap().launch_override, None);
+
+        let command = "/usr/bin/dosbox -c \"mount c {rom_dir}\"";
+        db.set_launch_override(id, Some(command)).unwrap();
+
+        // A rescan of the same file keeps the override
+        let id = db.add_game(&game).unwrap();
+        assert_eq!(
+            db.get_game(id).unwrap().unwrap().launch_override.as_deref(),
+            Some(command)
+        );
+
+        game.launch_override = Some("/roms/pc/doom/run.sh".to_string());
+        let id = db.add_game(&game).unwrap();
+        assert_eq!(
+            db.get_game(id).unwrap().unwrap().launch_override,
+            game.launch_override
+        );
+
+        db.set_launch_override(id, None).unwrap();
+        assert_eq!(db.get_game(id).unwrap().unwrap().launch_override, None);
+    }
+
+    #[test]
+    fn test_library_stats() {
+        let db = GameDatabase::in_memory().unwrap();
+
+        let mut ids = Vec::new();
+        for (path, system, size, favorite, hidden) in [
+            ("/roms/snes/a.sfc", "snes", 1_000, true, false),
+            ("/roms/snes/b.sfc", "snes", 2_000, false, false),
+            ("/roms/gba/c.gba", "gba", 4_000, true, false),
+            ("/roms/gba/hidden.gba", "gba", 8_000, true, true),
+        ] {
+            let game = Game {
+                id: 0,
+                path: path.to_string(),
+                system: system.to_string(),
+                name: path.to_string(),
+                description: None,
+                release_date: None,
+                developer: None,
+                publisher: None,
+                genre: None,
+                players: None,
+                rating: None,
+                favorite,
+                hidden,
+                region: None,
+                size,
+                launch_override: None,
+            };
+            ids.push(db.add_game(&game).unwrap());
+        }
+
+        db.update_play_stats(ids[0], 600).unwrap();
+        db.update_play_stats(ids[2], 3_000).unwrap();
+        db.update_play_stats(ids[2], 600).unwrap();
+        db.update_play_stats(ids[3], 10_000).unwrap();
+
+        let stats = db.library_stats().unwrap();
+        assert_eq!(stats.total_games, 3);
+        assert_eq!(
+            stats.systems,
+            vec![("gba".to_string(), 1), ("snes".to_string(), 2)]
+        );
+        assert_eq!(stats.favorites, 2);
+        assert_eq!(stats.total_size, 7_000);
+        assert_eq!(stats.total_play_time_seconds, 4_200);
+
+        let (game, seconds) = stats.most_played.unwrap();
+        assert_eq!(game.path, "/roms/gba/c.gba");
+        assert_eq!(game.size, 4_000);
+        assert_eq!(seconds, 3_600);
+    }
+
+    #[test]
+    fn test_library_stats_empty() {
+        let stats = GameDatabase::in_memory().unwrap().library_stats().unwrap();
+        assert_eq!(stats.total_games, 0);
+        assert_eq!(stats.total_size, 0);
+        assert!(stats.most_played.is_none());
+    }
+
+    #[test]
+    fn test_region_column_added_to_old_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("games.db");
+
+        // Schema from before the region column
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE games (
+                id INTEGER PRIMARY KEY, path TEXT NOT NULL UNIQUE, system TEXT NOT NULL,
+                name TEXT NOT NULL, description TEXT, release_date TEXT, developer TEXT,
+                publisher TEXT, genre TEXT, players INTEGER, rating REAL,
+                favorite INTEGER DEFAULT 0, hidden INTEGER DEFAULT 0,
+                created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+                updated_at TEXT DEFAULT CURRENT_TIMESTAMP
+            );
+            INSERT INTO games (path, system, name) VALUES ('/roms/nes/old.nes', 'nes', 'Old');",
+        )
+        .unwrap();
+        drop(conn);
+
+        let db = GameDatabase::open(&path).unwrap();
+        let games = db.get_games_by_system("nes").unwrap();
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].region, None);
+        assert_eq!(games[0].size, 0);
+
+        // Opening again leaves the schema alone
+        drop(db);
+        GameDatabase::open(&path).unwrap();
+    }
 }
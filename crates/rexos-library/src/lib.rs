@@ -5,11 +5,21 @@
 
 mod database;
 mod metadata;
+mod names;
+mod path;
+mod playlist;
 mod scanner;
+mod worker;
 
-pub use database::{Game, GameDatabase, GameStats};
-pub use metadata::{GameMetadata, MetadataSource, parse_gamelist_xml};
-pub use scanner::{RomScanner, ScanResult};
+pub use database::{Game, GameDatabase, GameStats, LibraryStats};
+pub use metadata::{
+    GameMetadata, GamelistProvider, MetadataProvider, MetadataResolver, MetadataSource,
+    parse_gamelist_xml,
+};
+pub use names::{NameCleaner, REGION_WORLD, parse_regions, region_matches};
+pub use path::{decode_path, encode_path, is_encoded};
+pub use scanner::{FileStamp, RomScanner, ScanIndex, ScanProgress, ScanResult};
+pub use worker::{DatabaseWorker, DbRequest, DbResponse};
 
 use std::path::PathBuf;
 use thiserror::Error;
@@ -28,6 +38,9 @@ pub enum LibraryError {
     #[error("Path not found: {0}")]
     PathNotFound(PathBuf),
 
+    #[error("Storage is read-only: {0}")]
+    ReadOnly(PathBuf),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -1,6 +1,14 @@
 //! Game metadata handling
+//!
+//! Metadata can come from several places (local gamelist.xml, DAT files,
+//! online scrapers). A [`MetadataResolver`] asks each [`MetadataProvider`]
+//! in priority order and fills every field from the first one that has it.
 
+use crate::Game;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
 
 /// Game metadata
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -23,6 +31,8 @@ pub struct GameMetadata {
 pub enum MetadataSource {
     /// Local gamelist.xml
     Local,
+    /// DAT file match
+    Dat,
     /// ScreenScraper API
     ScreenScraper,
     /// TheGamesDB API
@@ -83,6 +93,101 @@ impl GameMetadata {
     }
 }
 
+/// Something that can look up metadata for a game
+pub trait MetadataProvider: Send + Sync {
+    /// Where the metadata comes from
+    fn source(&self) -> MetadataSource;
+
+    /// Metadata for a game, if this provider knows it
+    fn lookup(&self, game: &Game) -> Option<GameMetadata>;
+}
+
+/// Ordered chain of metadata providers
+#[derive(Default)]
+pub struct MetadataResolver {
+    providers: Vec<Box<dyn MetadataProvider>>,
+}
+
+impl MetadataResolver {
+    /// Create an empty resolver
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a provider after the existing ones (lower priority)
+    pub fn with_provider(mut self, provider: impl MetadataProvider + 'static) -> Self {
+        self.providers.push(Box::new(provider));
+        self
+    }
+
+    /// Sources in priority order
+    pub fn sources(&self) -> Vec<MetadataSource> {
+        self.providers.iter().map(|p| p.source()).collect()
+    }
+
+    /// Resolve metadata field by field
+    ///
+    /// Each field comes from the first provider that has it, so a local
+    /// description can sit alongside box art from a scraper.
+    pub fn resolve(&self, game: &Game) -> GameMetadata {
+        let mut metadata = GameMetadata::new();
+        for provider in &self.providers {
+            if let Some(found) = provider.lookup(game) {
+                metadata.merge(&found);
+            }
+        }
+        metadata
+    }
+}
+
+/// Metadata from a system's gamelist.xml
+#[derive(Debug, Clone, Default)]
+pub struct GamelistProvider {
+    /// Metadata by ROM file name
+    entries: HashMap<String, GameMetadata>,
+}
+
+impl GamelistProvider {
+    /// Build from gamelist.xml contents
+    pub fn parse(xml: &str) -> Self {
+        let entries = parse_gamelist_xml(xml)
+            .into_iter()
+            .filter_map(|(path, metadata)| {
+                let name = Path::new(&path).file_name()?.to_string_lossy().to_string();
+                Some((name, metadata))
+            })
+            .collect();
+        Self { entries }
+    }
+
+    /// Load `gamelist.xml` from a system directory (empty if missing)
+    pub fn load(dir: &Path) -> Self {
+        let path = dir.join("gamelist.xml");
+        let Ok(xml) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        let provider = Self::parse(&xml);
+        tracing::info!(
+            "Loaded metadata for {} games from {}",
+            provider.entries.len(),
+            path.display()
+        );
+        provider
+    }
+}
+
+impl MetadataProvider for GamelistProvider {
+    fn source(&self) -> MetadataSource {
+        MetadataSource::Local
+    }
+
+    fn lookup(&self, game: &Game) -> Option<GameMetadata> {
+        let name = game.rom_path().file_name()?.to_string_lossy().to_string();
+        self.entries.get(&name).cloned()
+    }
+}
+
 /// Parse gamelist.xml format (EmulationStation compatible)
 ///
 /// This function parses the standard gamelist.xml format used by EmulationStation,
@@ -180,6 +285,77 @@ mod tests {
         assert_eq!(meta1.developer, Some("Dev".to_string())); // Merged
     }
 
+    struct Fixed(MetadataSource, GameMetadata);
+
+    impl MetadataProvider for Fixed {
+        fn source(&self) -> MetadataSource {
+            self.0
+        }
+
+        fn lookup(&self, _game: &Game) -> Option<GameMetadata> {
+            Some(self.1.clone())
+        }
+    }
+
+    #[test]
+    fn test_resolver_merges_by_field() {
+        let gamelist = GamelistProvider::parse(
+            "<gameList>\n<game>\n<path>./Zelda.gba</path>\n\
+             <desc>Local description</desc>\n</game>\n</gameList>\n",
+        );
+        let scraper = Fixed(
+            MetadataSource::ScreenScraper,
+            GameMetadata {
+                description: Some("Scraped description".to_string()),
+                box_art_url: Some("https://example.com/zelda.png".to_string()),
+                ..GameMetadata::default()
+            },
+        );
+
+        let resolver = MetadataResolver::new()
+            .with_provider(gamelist)
+            .with_provider(scraper);
+        assert_eq!(
+            resolver.sources(),
+            vec![MetadataSource::Local, MetadataSource::ScreenScraper]
+        );
+
+        let game = Game {
+            id: 0,
+            path: "/roms/gba/Zelda.gba".to_string(),
+            system: "gba".to_string(),
+            name: "Zelda".to_string(),
+            description: None,
+            release_date: None,
+            developer: None,
+            publisher: None,
+            genre: None,
+            players: None,
+            rating: None,
+            favorite: false,
+            hidden: false,
+            region: None,
+            size: 0,
+            launch_override: None,
+        };
+        let metadata = resolver.resolve(&game);
+        assert_eq!(metadata.description.as_deref(), Some("Local description"));
+        assert_eq!(
+            metadata.box_art_url.as_deref(),
+            Some("https://example.com/zelda.png")
+        );
+
+        // Unknown to the gamelist, so the scraper fills everything
+        let other = Game {
+            path: "/roms/gba/Other.gba".to_string(),
+            ..game
+        };
+        assert_eq!(
+            resolver.resolve(&other).description.as_deref(),
+            Some("Scraped description")
+        );
+    }
+
     #[test]
     fn test_extract_xml_value() {
         assert_eq!(
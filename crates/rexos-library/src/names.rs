@@ -0,0 +1,433 @@
+//! Display names from ROM file names
+//!
+//! ROM sets name files like `Legend_of_Zelda,_The_(USA)_[!]` (No-Intro,
+//! GoodTools) or `Sonic the Hedgehog (1991)(Sega)(US)` (TOSEC). The
+//! cleaner strips the tags it recognizes and leaves any other
+//! parenthetical alone, so titles that really contain parentheses survive. The raw name stays
+//! available from the ROM path ([`crate::Game::raw_name`]).
+
+use rexos_config::NameCleaningConfig;
+
+/// Region tags (names and GoodTools codes) and the region codes they stand for
+const REGIONS: &[(&str, &[&str])] = &[
+    ("USA", &["USA"]),
+    ("Europe", &["EUR"]),
+    ("Japan", &["JPN"]),
+    ("World", &["WLD"]),
+    ("Asia", &["ASI"]),
+    ("Australia", &["AUS"]),
+    ("Brazil", &["BRA"]),
+    ("Canada", &["USA"]),
+    ("China", &["CHN"]),
+    ("France", &["EUR"]),
+    ("Germany", &["EUR"]),
+    ("Hong Kong", &["ASI"]),
+    ("Italy", &["EUR"]),
+    ("Korea", &["KOR"]),
+    ("Netherlands", &["EUR"]),
+    ("Spain", &["EUR"]),
+    ("Sweden", &["EUR"]),
+    ("Taiwan", &["TWN"]),
+    ("UK", &["EUR"]),
+    ("Russia", &["RUS"]),
+    ("Scandinavia", &["EUR"]),
+    ("Latin America", &["LAT"]),
+    ("U", &["USA"]),
+    ("E", &["EUR"]),
+    ("J", &["JPN"]),
+    ("W", &["WLD"]),
+    ("UE", &["USA", "EUR"]),
+    ("JU", &["JPN", "USA"]),
+    ("JE", &["JPN", "EUR"]),
+    ("JUE", &["JPN", "USA", "EUR"]),
+    ("F", &["EUR"]),
+    ("G", &["EUR"]),
+    ("I", &["EUR"]),
+    ("S", &["EUR"]),
+    ("K", &["KOR"]),
+    ("A", &["AUS"]),
+    ("B", &["BRA"]),
+];
+
+/// TOSEC country codes and the region codes they stand for
+const TOSEC_COUNTRIES: &[(&str, &str)] = &[
+    ("US", "USA"),
+    ("EU", "EUR"),
+    ("JP", "JPN"),
+    ("AS", "ASI"),
+    ("AU", "AUS"),
+    ("BR", "BRA"),
+    ("CA", "USA"),
+    ("CN", "CHN"),
+    ("DE", "EUR"),
+    ("ES", "EUR"),
+    ("FR", "EUR"),
+    ("GB", "EUR"),
+    ("HK", "ASI"),
+    ("IT", "EUR"),
+    ("KR", "KOR"),
+    ("NL", "EUR"),
+    ("RU", "RUS"),
+    ("SE", "EUR"),
+    ("TW", "TWN"),
+];
+
+/// Region code for games released everywhere
+pub const REGION_WORLD: &str = "WLD";
+
+/// No-Intro language codes
+const LANGUAGES: &[&str] = &[
+    "En", "Fr", "De", "Es", "It", "Ja", "Nl", "Pt", "Sv", "No", "Da", "Fi", "Zh", "Ko", "Ru", "Pl",
+];
+
+/// Release status tags
+const RELEASE_TAGS: &[&str] = &[
+    "Beta",
+    "Proto",
+    "Prototype",
+    "Demo",
+    "Sample",
+    "Unl",
+    "Alt",
+    "Promo",
+    "Kiosk",
+];
+
+/// Articles moved by `move_articles`
+const ARTICLES: &[&str] = &["The", "A", "An"];
+
+/// Kind of tag found in parentheses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TagKind {
+    Region,
+    Version,
+    Date,
+}
+
+/// Cleans ROM file names into display names
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NameCleaner {
+    rules: NameCleaningConfig,
+}
+
+impl NameCleaner {
+    /// Create a cleaner with the given rules
+    pub fn new(rules: NameCleaningConfig) -> Self {
+        Self { rules }
+    }
+
+    /// Rules in use
+    pub fn rules(&self) -> &NameCleaningConfig {
+        &self.rules
+    }
+
+    /// Clean a file name (without extension)
+    pub fn clean(&self, name: &str) -> String {
+        let name = if self.rules.collapse_underscores {
+            name.replace('_', " ")
+        } else {
+            name.to_string()
+        };
+
+        let mut clean = String::with_capacity(name.len());
+        let mut rest = name.as_str();
+        let mut after_date = false;
+
+        while let Some(start) = rest.find(['(', '[']) {
+            let close = if rest[start..].starts_with('(') {
+                ')'
+            } else {
+                ']'
+            };
+            let Some(len) = rest[start..].find(close) else {
+                break;
+            };
+
+            let group = &rest[start..=start + len];
+            // TOSEC puts the publisher straight after the date, so only a
+            // group touching the date is taken for one
+            let publisher = after_date && start == 0 && group.starts_with('(');
+            clean.push_str(&rest[..start]);
+            if !(self.strips(group) || (publisher && self.rules.strip_dates)) {
+                clean.push_str(group);
+            }
+            after_date = group.starts_with('(') && is_date(&group[1..group.len() - 1]);
+            rest = &rest[start + len + 1..];
+        }
+        clean.push_str(rest);
+
+        let clean = clean.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        if self.rules.move_articles {
+            move_article(&clean)
+        } else {
+            clean
+        }
+    }
+
+    /// Check if a "(...)" or "[...]" group should be removed
+    fn strips(&self, group: &str) -> bool {
+        if group.starts_with('[') {
+            return self.rules.strip_dump_flags;
+        }
+
+        let inner = &group[1..group.len() - 1];
+        inner.split(',').all(|part| match tag_kind(part.trim()) {
+            Some(TagKind::Region) => self.rules.strip_regions,
+            Some(TagKind::Version) => self.rules.strip_versions,
+            Some(TagKind::Date) => self.rules.strip_dates,
+            None => false,
+        })
+    }
+}
+
+/// Region codes for a region tag, or for TOSEC countries joined by '-'
+/// ("US-EU")
+fn region_codes(part: &str) -> Option<Vec<&'static str>> {
+    if let Some((_, codes)) = REGIONS.iter().find(|(tag, _)| *tag == part) {
+        return Some(codes.to_vec());
+    }
+    part.split('-')
+        .map(|country| {
+            TOSEC_COUNTRIES
+                .iter()
+                .find(|(code, _)| *code == country)
+                .map(|(_, region)| *region)
+        })
+        .collect()
+}
+
+/// Check for a language tag: "En" (No-Intro) or "en-de" (TOSEC)
+fn is_language(part: &str) -> bool {
+    LANGUAGES.contains(&part)
+        || part.split('-').all(|language| {
+            language.len() == 2 && LANGUAGES.iter().any(|l| l.to_lowercase() == language)
+        })
+}
+
+/// Check for a TOSEC date: "1991", "199x" or "1991-05-12"
+fn is_date(part: &str) -> bool {
+    let digits = |field: &str| field.chars().all(|c| c.is_ascii_digit() || c == 'x');
+
+    let fields: Vec<_> = part.split('-').collect();
+    let year = fields[0];
+    year.len() == 4
+        && (year.starts_with("19") || year.starts_with("20"))
+        && digits(year)
+        && fields.len() <= 3
+        && fields[1..].iter().all(|f| f.len() == 2 && digits(f))
+}
+
+/// Classify one comma-separated part of a parenthetical
+fn tag_kind(part: &str) -> Option<TagKind> {
+    if region_codes(part).is_some() || is_language(part) {
+        return Some(TagKind::Region);
+    }
+    if is_date(part) {
+        return Some(TagKind::Date);
+    }
+
+    // TOSEC writes these in lower case, sometimes qualified ("demo-playable")
+    let word = part.split([' ', '-']).next().unwrap_or(part);
+    let is_version = RELEASE_TAGS
+        .iter()
+        .any(|tag| tag.eq_ignore_ascii_case(word))
+        || (part.starts_with("Rev ") && part.len() > 4)
+        || (part.starts_with('v') && part[1..].starts_with(|c: char| c.is_ascii_digit()));
+
+    is_version.then_some(TagKind::Version)
+}
+
+/// Region codes from a file name's region tags, e.g. `["USA", "EUR"]` for
+/// "(USA, Europe)"
+pub fn parse_regions(name: &str) -> Vec<&'static str> {
+    let mut regions = Vec::new();
+    let mut rest = name;
+
+    while let Some(start) = rest.find('(') {
+        let Some(len) = rest[start..].find(')') else {
+            break;
+        };
+        let inner = &rest[start + 1..start + len];
+        rest = &rest[start + len + 1..];
+
+        let parts: Vec<_> = inner
+            .split(',')
+            .map(|part| region_codes(part.trim()))
+            .collect();
+        if parts.iter().all(Option::is_some) {
+            for code in parts.into_iter().flatten().flatten() {
+                if !regions.contains(&code) {
+                    regions.push(code);
+                }
+            }
+        }
+    }
+    regions
+}
+
+/// Check if a game's stored region matches any preferred region
+///
+/// Games without a known region and world releases always match.
+pub fn region_matches(region: Option<&str>, preferred: &[String]) -> bool {
+    let Some(region) = region else {
+        return true;
+    };
+    region
+        .split(',')
+        .any(|code| code == REGION_WORLD || preferred.iter().any(|p| p.eq_ignore_ascii_case(code)))
+}
+
+/// Turn "Legend of Zelda, The - ..." into "The Legend of Zelda - ..."
+fn move_article(name: &str) -> String {
+    for article in ARTICLES {
+        let pattern = format!(", {}", article);
+        // Avoid if-let chains for MSRV 1.85 compatibility
+        #[allow(clippy::collapsible_if)]
+        if let Some(pos) = name.find(&pattern) {
+            let after = &name[pos + pattern.len()..];
+            if after.is_empty() || after.starts_with(' ') {
+                return format!("{} {}{}", article, &name[..pos], after);
+            }
+        }
+    }
+    name.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_rules() {
+        let cleaner = NameCleaner::default();
+
+        assert_eq!(
+            cleaner.clean("Super Mario World (USA)"),
+            "Super Mario World"
+        );
+        assert_eq!(cleaner.clean("Zelda (Europe) [!]"), "Zelda");
+        assert_eq!(cleaner.clean("Pokemon Red (U) (Rev 1)"), "Pokemon Red");
+        assert_eq!(
+            cleaner.clean("Sonic_the_Hedgehog_(World)_(Rev_A)_[b1]"),
+            "Sonic the Hedgehog"
+        );
+        assert_eq!(
+            cleaner.clean("Castlevania - Aria of Sorrow (USA, Europe) (En,Fr,De)"),
+            "Castlevania - Aria of Sorrow"
+        );
+        assert_eq!(
+            cleaner.clean("Street Fighter II' (J) (v1.1) [h1C]"),
+            "Street Fighter II'"
+        );
+        assert_eq!(cleaner.clean("Mega Man X (Beta 2)"), "Mega Man X");
+    }
+
+    #[test]
+    fn test_unknown_parentheticals_kept() {
+        let cleaner = NameCleaner::default();
+
+        assert_eq!(cleaner.clean("Tetris (Tengen) (USA)"), "Tetris (Tengen)");
+        assert_eq!(
+            cleaner.clean("Final Fantasy VII (Disc 1) (Europe)"),
+            "Final Fantasy VII (Disc 1)"
+        );
+        assert_eq!(
+            cleaner.clean("Pokemon - Yellow Version (USA, Europe) (CGB+SGB Enhanced)"),
+            "Pokemon - Yellow Version (CGB+SGB Enhanced)"
+        );
+        // Unbalanced brackets are left as they are
+        assert_eq!(cleaner.clean("Broken (USA"), "Broken (USA");
+    }
+
+    #[test]
+    fn test_tosec_names() {
+        let cleaner = NameCleaner::default();
+
+        assert_eq!(
+            cleaner.clean("Sonic the Hedgehog (1991)(Sega)(US)[!]"),
+            "Sonic the Hedgehog"
+        );
+        assert_eq!(
+            cleaner.clean("Gods (1991)(Renegade)(US-EU)(en-de)[cr Skid Row]"),
+            "Gods"
+        );
+        assert_eq!(
+            cleaner.clean("Xenon (demo-playable)(198x)(Melbourne House)"),
+            "Xenon"
+        );
+        assert_eq!(
+            cleaner.clean("Elite (1984-09)(Acornsoft)(Disk 1 of 2)"),
+            "Elite (Disk 1 of 2)"
+        );
+        // Only a group right after the date is its publisher
+        assert_eq!(cleaner.clean("Tetris (1989) (Tengen)"), "Tetris (Tengen)");
+        assert_eq!(cleaner.clean("Road Rash (Sega) (1991)"), "Road Rash (Sega)");
+        assert_eq!(cleaner.clean("Game (12345)"), "Game (12345)");
+
+        let keep_dates = NameCleaner::new(NameCleaningConfig {
+            strip_dates: false,
+            ..Default::default()
+        });
+        assert_eq!(
+            keep_dates.clean("Sonic the Hedgehog (1991)(Sega)(US)"),
+            "Sonic the Hedgehog (1991)(Sega)"
+        );
+
+        assert_eq!(
+            parse_regions("Gods (1991)(Renegade)(US-EU)(en-de)"),
+            vec!["USA", "EUR"]
+        );
+        assert_eq!(parse_regions("Sonic (1991)(Sega)(JP)"), vec!["JPN"]);
+    }
+
+    #[test]
+    fn test_parse_regions() {
+        assert_eq!(parse_regions("Super Mario World (USA)"), vec!["USA"]);
+        assert_eq!(
+            parse_regions("Castlevania (USA, Europe) (En,Fr,De)"),
+            vec!["USA", "EUR"]
+        );
+        assert_eq!(
+            parse_regions("Street Fighter II (JUE) [!]"),
+            vec!["JPN", "USA", "EUR"]
+        );
+        assert_eq!(parse_regions("Tetris (World) (Rev 1)"), vec!["WLD"]);
+        assert!(parse_regions("Tetris (Tengen)").is_empty());
+        assert!(parse_regions("Homebrew").is_empty());
+    }
+
+    #[test]
+    fn test_region_matches() {
+        let preferred = vec!["USA".to_string()];
+        assert!(region_matches(Some("USA,EUR"), &preferred));
+        assert!(region_matches(Some("WLD"), &preferred));
+        assert!(region_matches(None, &preferred));
+        assert!(!region_matches(Some("JPN"), &preferred));
+        assert!(region_matches(Some("JPN"), &["jpn".to_string()]));
+    }
+
+    #[test]
+    fn test_toggles() {
+        let cleaner = NameCleaner::new(NameCleaningConfig {
+            strip_regions: false,
+            strip_dump_flags: false,
+            collapse_underscores: false,
+            move_articles: true,
+            ..Default::default()
+        });
+
+        assert_eq!(
+            cleaner.clean("Super Mario World (USA) (Rev 1) [!]"),
+            "Super Mario World (USA) [!]"
+        );
+        assert_eq!(cleaner.clean("Wario_Land_(Japan)"), "Wario_Land_(Japan)");
+        assert_eq!(
+            cleaner.clean("Legend of Zelda, The - A Link to the Past (USA)"),
+            "The Legend of Zelda - A Link to the Past (USA)"
+        );
+        assert_eq!(cleaner.clean("Addams Family, The"), "The Addams Family");
+        // Only a whole trailing article moves
+        assert_eq!(cleaner.clean("Cars, Another Story"), "Cars, Another Story");
+    }
+}
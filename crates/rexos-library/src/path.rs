@@ -0,0 +1,100 @@
+//! Lossless storage of ROM paths
+//!
+//! `Game.path` is a `String`, but filenames on exFAT/FAT cards written by
+//! other systems are not always valid UTF-8. Valid UTF-8 paths are stored
+//! unchanged; anything else is stored percent-encoded behind a marker prefix
+//! so the exact bytes can be restored at launch.
+
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+/// Marker prefix for percent-encoded paths
+const ENCODED_PREFIX: &str = "rexos-raw:";
+
+/// Encode a filesystem path for storage in the database
+pub fn encode_path(path: &Path) -> String {
+    match path.to_str() {
+        // Paths that happen to start with the marker are encoded too, so
+        // decoding is always unambiguous.
+        Some(s) if !s.starts_with(ENCODED_PREFIX) => s.to_string(),
+        _ => {
+            let bytes = path.as_os_str().as_bytes();
+            let mut encoded = String::with_capacity(ENCODED_PREFIX.len() + bytes.len());
+            encoded.push_str(ENCODED_PREFIX);
+
+            for &b in bytes {
+                if b.is_ascii() && b != b'%' && !b.is_ascii_control() {
+                    encoded.push(b as char);
+                } else {
+                    encoded.push_str(&format!("%{:02X}", b));
+                }
+            }
+
+            encoded
+        }
+    }
+}
+
+/// Decode a stored path back into the original filesystem path
+pub fn decode_path(stored: &str) -> PathBuf {
+    let Some(encoded) = stored.strip_prefix(ENCODED_PREFIX) else {
+        return PathBuf::from(stored);
+    };
+
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+            if let Ok(b) = u8::from_str_radix(hex, 16) {
+                decoded.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    PathBuf::from(OsStr::from_bytes(&decoded))
+}
+
+/// Check if a stored path needed encoding
+pub fn is_encoded(stored: &str) -> bool {
+    stored.starts_with(ENCODED_PREFIX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utf8_paths_unchanged() {
+        let path = Path::new("/roms/snes/Pokémon 100% (Japan).sfc");
+        let stored = encode_path(path);
+        assert_eq!(stored, "/roms/snes/Pokémon 100% (Japan).sfc");
+        assert!(!is_encoded(&stored));
+        assert_eq!(decode_path(&stored), path);
+    }
+
+    #[test]
+    fn test_non_utf8_round_trip() {
+        // Latin-1 "Café" as written by some exFAT tools
+        let path = Path::new(OsStr::from_bytes(b"/roms/gba/Caf\xe9 100%.gba"));
+        let stored = encode_path(path);
+        assert_eq!(stored, "rexos-raw:/roms/gba/Caf%E9 100%25.gba");
+        assert!(is_encoded(&stored));
+        assert_eq!(decode_path(&stored), path);
+    }
+
+    #[test]
+    fn test_marker_prefix_is_escaped() {
+        let path = Path::new("rexos-raw:%41");
+        let stored = encode_path(path);
+        assert_ne!(stored, "rexos-raw:%41");
+        assert_eq!(decode_path(&stored), path);
+    }
+}
@@ -0,0 +1,193 @@
+//! Multi-disc playlists
+//!
+//! Multi-disc games are launched through an `.m3u` playlist listing each
+//! disc, which lets the core swap discs without going back to the menu.
+//! The scanner lists the playlist as the game and hides the discs behind
+//! it, along with the track files their cue sheets name.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Disc image extensions that can be grouped into a playlist
+const DISC_EXTENSIONS: &[&str] = &["cue", "chd", "iso", "pbp"];
+
+/// Group the multi-disc games among a directory's files
+///
+/// Files named by an existing playlist or cue sheet are returned so they
+/// aren't listed on their own. Discs of the same title (`Game (Disc 1).cue`,
+/// `Game (Disc 2).cue`, ...) without a playlist get one written beside them,
+/// which is added to `files`. If it can't be written (e.g. a read-only
+/// card) the discs stay separate.
+pub(crate) fn group_discs(dir: &Path, files: &mut Vec<PathBuf>) -> HashSet<PathBuf> {
+    let mut claimed = HashSet::new();
+
+    for file in files.iter() {
+        match extension(file).as_deref() {
+            Some("m3u") => claimed.extend(read_playlist(file)),
+            Some("cue") => claimed.extend(cue_tracks(file)),
+            _ => {}
+        }
+    }
+
+    // Unclaimed discs by title, in disc order
+    let mut titles: BTreeMap<String, BTreeMap<u32, PathBuf>> = BTreeMap::new();
+    for file in files.iter() {
+        if claimed.contains(file) {
+            continue;
+        }
+        if !extension(file).is_some_and(|ext| DISC_EXTENSIONS.contains(&ext.as_str())) {
+            continue;
+        }
+        let Some(stem) = file.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        if let Some((title, disc)) = disc_title(stem) {
+            titles.entry(title).or_default().insert(disc, file.clone());
+        }
+    }
+
+    for (title, discs) in titles {
+        if discs.len() < 2 {
+            continue;
+        }
+
+        let playlist = dir.join(format!("{}.m3u", title));
+        if playlist.exists() {
+            continue;
+        }
+
+        let contents: String = discs
+            .values()
+            .filter_map(|disc| disc.file_name()?.to_str())
+            .map(|name| format!("{}\n", name))
+            .collect();
+        match fs::write(&playlist, contents) {
+            Ok(()) => {
+                tracing::info!("Wrote playlist {}", playlist.display());
+                claimed.extend(discs.into_values());
+                files.push(playlist);
+            }
+            Err(e) => tracing::warn!("Failed to write playlist {}: {}", playlist.display(), e),
+        }
+    }
+
+    claimed
+}
+
+/// Lowercase extension of a file
+fn extension(path: &Path) -> Option<String> {
+    Some(path.extension()?.to_str()?.to_lowercase())
+}
+
+/// Files listed in an `.m3u` playlist, relative to its directory
+fn read_playlist(path: &Path) -> Vec<PathBuf> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| dir.join(line))
+        .collect()
+}
+
+/// Track files named by a cue sheet's `FILE` lines
+fn cue_tracks(path: &Path) -> Vec<PathBuf> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let rest = line.trim().strip_prefix("FILE ")?.trim();
+            let name = match rest.strip_prefix('"') {
+                Some(quoted) => quoted.split('"').next()?,
+                None => rest.split_whitespace().next()?,
+            };
+            Some(dir.join(name))
+        })
+        .collect()
+}
+
+/// Title and disc number from a name like `Game (USA) (Disc 2)`
+fn disc_title(stem: &str) -> Option<(String, u32)> {
+    // ASCII lowercase keeps byte offsets valid for `stem`
+    let lower = stem.to_ascii_lowercase();
+    let (start, tag) = ["(disc", "(disk", "(cd"]
+        .iter()
+        .find_map(|tag| lower.find(tag).map(|start| (start, tag.len())))?;
+
+    let number: String = lower[start + tag..]
+        .trim_start()
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    let disc = number.parse().ok()?;
+    let end = start + lower[start..].find(')')? + 1;
+
+    let title = format!("{} {}", stem[..start].trim(), stem[end..].trim());
+    Some((title.trim().to_string(), disc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disc_title() {
+        assert_eq!(
+            disc_title("Final Fantasy VII (USA) (Disc 2)"),
+            Some(("Final Fantasy VII (USA)".to_string(), 2))
+        );
+        assert_eq!(
+            disc_title("Riven (Disk 3 of 5) (Europe)"),
+            Some(("Riven (Europe)".to_string(), 3))
+        );
+        assert_eq!(
+            disc_title("Policenauts (CD2)"),
+            Some(("Policenauts".to_string(), 2))
+        );
+        assert_eq!(disc_title("Crash Bandicoot (USA)"), None);
+    }
+
+    #[test]
+    fn test_group_discs() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, contents: &str| {
+            let path = dir.path().join(name);
+            fs::write(&path, contents).unwrap();
+            path
+        };
+
+        let mut files = vec![
+            write("Game (Disc 2).cue", "FILE \"Game (Disc 2).bin\" BINARY\n"),
+            write("Game (Disc 2).bin", ""),
+            write("Game (Disc 1).cue", "FILE \"Game (Disc 1).bin\" BINARY\n"),
+            write("Game (Disc 1).bin", ""),
+            write(
+                "Other.m3u",
+                "# discs\nOther (Disc 1).chd\nOther (Disc 2).chd\n",
+            ),
+            write("Other (Disc 1).chd", ""),
+            write("Other (Disc 2).chd", ""),
+            write("Single.cue", "FILE Single.bin BINARY\n"),
+            write("Single.bin", ""),
+        ];
+        let claimed = group_discs(dir.path(), &mut files);
+
+        // A playlist was written for the discs without one
+        let playlist = dir.path().join("Game.m3u");
+        assert_eq!(files.last(), Some(&playlist));
+        assert_eq!(
+            fs::read_to_string(&playlist).unwrap(),
+            "Game (Disc 1).cue\nGame (Disc 2).cue\n"
+        );
+
+        let listed: Vec<_> = files
+            .iter()
+            .filter(|file| !claimed.contains(*file))
+            .filter_map(|file| file.file_name()?.to_str())
+            .collect();
+        assert_eq!(listed, vec!["Other.m3u", "Single.cue", "Game.m3u"]);
+    }
+}
@@ -1,10 +1,24 @@
 //! ROM scanning functionality
 
-use crate::metadata::parse_gamelist_xml;
-use crate::{Game, GameMetadata, LibraryError};
+use crate::names::parse_regions;
+use crate::playlist::group_discs;
+use crate::{
+    Game, GamelistProvider, LibraryError, MetadataProvider, NameCleaner, decode_path, encode_path,
+    is_encoded,
+};
+use rexos_storage::Paths;
 use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Instant, UNIX_EPOCH};
+
+/// Modification times this many seconds apart count as the same. FAT and
+/// exFAT store them with 2-second granularity, so another machine writing
+/// the card can round a file's time differently.
+const MTIME_TOLERANCE_SECS: i64 = 2;
 
 /// Result of a ROM scan
 #[derive(Debug, Default)]
@@ -12,10 +26,116 @@ pub struct ScanResult {
     pub games_found: usize,
     pub games_added: usize,
     pub games_updated: usize,
+    pub games_removed: usize,
+    /// Stored paths of ROMs that are gone, to delete from the library
+    pub removed: Vec<String>,
     pub errors: Vec<String>,
     pub duration_ms: u64,
 }
 
+/// Size and modification time of a ROM file
+///
+/// A ROM whose stamp still matches the one stored with it is skipped by
+/// an incremental scan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    pub size: u64,
+    /// Seconds since the Unix epoch, negative before it
+    pub mtime: i64,
+}
+
+impl FileStamp {
+    /// Stamp of the file at `path`
+    pub fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let mtime = match metadata.modified().ok()?.duration_since(UNIX_EPOCH) {
+            Ok(since) => since.as_secs() as i64,
+            // Written by a device whose clock was never set
+            Err(e) => -(e.duration().as_secs() as i64),
+        };
+        Some(Self {
+            size: metadata.len(),
+            mtime,
+        })
+    }
+
+    /// Whether a file stamped `other` is unchanged since `self`
+    ///
+    /// Only the two stamps are compared, never the current time, so a
+    /// wrong system clock doesn't cause rescans.
+    pub fn matches(&self, other: &FileStamp) -> bool {
+        self.size == other.size && (self.mtime - other.mtime).abs() <= MTIME_TOLERANCE_SECS
+    }
+}
+
+/// ROMs already in the library, by stored path
+///
+/// Load it with [`crate::GameDatabase::scan_index`] and hand it to
+/// [`RomScanner::with_index`] to only process new, changed and removed
+/// files.
+#[derive(Debug, Clone, Default)]
+pub struct ScanIndex {
+    /// Stored path -> (system, stamp)
+    files: HashMap<String, (String, FileStamp)>,
+}
+
+impl ScanIndex {
+    /// Create an empty index
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a stored ROM
+    pub fn insert(&mut self, path: impl Into<String>, system: impl Into<String>, stamp: FileStamp) {
+        self.files.insert(path.into(), (system.into(), stamp));
+    }
+
+    /// Stamp a ROM was stored with
+    pub fn get(&self, path: &str) -> Option<FileStamp> {
+        self.files.get(path).map(|(_, stamp)| *stamp)
+    }
+
+    /// Number of ROMs in the index
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Check if the index is empty
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Stored paths of one system's ROMs
+    fn paths<'a>(&'a self, system: &'a str) -> impl Iterator<Item = &'a String> {
+        self.files
+            .iter()
+            .filter(move |(_, (s, _))| s == system)
+            .map(|(path, _)| path)
+    }
+}
+
+/// ROMs found for one system
+#[derive(Debug, Default)]
+struct SystemScan {
+    /// New or changed ROMs
+    games: Vec<Game>,
+    /// Stored paths of ROMs skipped as unchanged
+    unchanged: Vec<String>,
+}
+
+/// Progress after a system finishes scanning
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanProgress {
+    /// System that finished
+    pub system: String,
+    /// Games found for it
+    pub games: usize,
+    /// Systems finished so far, including this one
+    pub completed: usize,
+    /// Systems being scanned
+    pub total: usize,
+}
+
 /// ROM scanner configuration
 #[derive(Debug, Clone)]
 pub struct ScanConfig {
@@ -30,6 +150,12 @@ pub struct ScanConfig {
 
     /// Skip hidden files/directories
     pub skip_hidden: bool,
+
+    /// Display name cleaning rules
+    pub names: NameCleaner,
+
+    /// Systems scanned at once (0 = one per CPU)
+    pub threads: usize,
 }
 
 impl Default for ScanConfig {
@@ -39,7 +165,7 @@ impl Default for ScanConfig {
         for ext in &[
             "nes", "fds", "smc", "sfc", "n64", "z64", "v64", "gb", "gbc", "gba", "nds", "sms",
             "gg", "md", "gen", "bin", "32x", "pce", "sgx", "iso", "cso", "chd", "pbp", "cue",
-            "a26", "a78", "lnx", "ngp", "ngc", "ws", "wsc", "zip", "7z",
+            "a26", "a78", "lnx", "ngp", "ngc", "ws", "wsc", "zip", "7z", "m3u",
         ] {
             extensions.insert(ext.to_string());
         }
@@ -56,6 +182,8 @@ impl Default for ScanConfig {
             skip_dirs,
             recursive: true,
             skip_hidden: true,
+            names: NameCleaner::default(),
+            threads: 0,
         }
     }
 }
@@ -63,6 +191,10 @@ impl Default for ScanConfig {
 /// ROM scanner
 pub struct RomScanner {
     config: ScanConfig,
+    /// Secondary card's roms directory, merged into the primary's systems
+    roms2: Option<PathBuf>,
+    /// ROMs from the last scan, skipped while unchanged
+    index: ScanIndex,
 }
 
 impl Default for RomScanner {
@@ -76,12 +208,44 @@ impl RomScanner {
     pub fn new() -> Self {
         Self {
             config: ScanConfig::default(),
+            roms2: None,
+            index: ScanIndex::default(),
         }
     }
 
     /// Create with custom config
     pub fn with_config(config: ScanConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            roms2: None,
+            index: ScanIndex::default(),
+        }
+    }
+
+    /// Also scan a second card's roms directory
+    ///
+    /// Its system directories are merged with the primary's, so games from
+    /// `/roms2/snes` are listed under `snes`. A ROM on both cards is taken
+    /// from the primary.
+    pub fn with_secondary_roms(mut self, roms2: impl Into<PathBuf>) -> Self {
+        self.roms2 = Some(roms2.into());
+        self
+    }
+
+    /// Use different display name cleaning rules
+    pub fn with_name_cleaner(mut self, names: NameCleaner) -> Self {
+        self.config.names = names;
+        self
+    }
+
+    /// Skip ROMs stored with a stamp that still matches the file
+    ///
+    /// Scans then only return new and changed ROMs, and
+    /// [`RomScanner::scan_all_with_progress`] reports indexed ROMs that are
+    /// gone in [`ScanResult::removed`].
+    pub fn with_index(mut self, index: ScanIndex) -> Self {
+        self.index = index;
+        self
     }
 
     /// Scan a directory for ROMs
@@ -89,49 +253,58 @@ impl RomScanner {
     /// This method scans the given directory for ROM files and also loads
     /// metadata from any gamelist.xml files found (EmulationStation compatible).
     pub fn scan(&self, path: &Path, system: &str) -> Result<Vec<Game>, LibraryError> {
-        let mut games = Vec::new();
+        Ok(self.scan_root(path, system)?.games)
+    }
+
+    /// Scan several directories for one system's ROMs
+    ///
+    /// A ROM at the same path within an earlier directory wins over later
+    /// copies, which are skipped with a warning.
+    pub fn scan_dirs(&self, dirs: &[PathBuf], system: &str) -> Result<Vec<Game>, LibraryError> {
+        Ok(self.scan_system(dirs, system)?.games)
+    }
+
+    /// Scan one directory, keeping track of unchanged ROMs
+    fn scan_root(&self, path: &Path, system: &str) -> Result<SystemScan, LibraryError> {
+        let mut found = SystemScan::default();
 
         // First, load any existing gamelist.xml metadata
-        let metadata_map = self.load_gamelist_metadata(path);
+        let gamelist = GamelistProvider::load(path);
 
         // Then scan for ROMs
-        self.scan_dir(path, system, &mut games, &metadata_map)?;
-        Ok(games)
-    }
-
-    /// Load metadata from gamelist.xml if it exists in the directory
-    fn load_gamelist_metadata(&self, path: &Path) -> HashMap<String, GameMetadata> {
-        let gamelist_path = path.join("gamelist.xml");
-        let mut metadata_map = HashMap::new();
-
-        // Avoid if-let chains for MSRV 1.85 compatibility
-        #[allow(clippy::collapsible_if)]
-        if gamelist_path.exists() {
-            if let Ok(xml_content) = fs::read_to_string(&gamelist_path) {
-                tracing::debug!("Loading metadata from {}", gamelist_path.display());
-
-                let entries = parse_gamelist_xml(&xml_content);
-                for (rom_path, metadata) in entries {
-                    // Normalize the path - gamelist.xml typically uses relative paths like "./game.gba"
-                    let normalized = rom_path
-                        .trim_start_matches("./")
-                        .trim_start_matches('/')
-                        .to_string();
-
-                    // Store by filename for matching
-                    if let Some(filename) = Path::new(&normalized).file_name() {
-                        metadata_map.insert(filename.to_string_lossy().to_string(), metadata);
-                    }
+        self.scan_dir(path, system, &mut found, &gamelist)?;
+        Ok(found)
+    }
+
+    /// Scan a system's directories, keeping track of unchanged ROMs
+    fn scan_system(&self, dirs: &[PathBuf], system: &str) -> Result<SystemScan, LibraryError> {
+        let mut merged = SystemScan::default();
+        let mut seen = HashSet::new();
+
+        for dir in dirs {
+            let found = self.scan_root(dir, system)?;
+            let mut first_copy = |stored: &str| {
+                let path = decode_path(stored);
+                let relative = path.strip_prefix(dir).unwrap_or(&path).to_path_buf();
+                let first = seen.insert(relative);
+                if !first {
+                    tracing::warn!("Duplicate ROM {} ignored, using primary copy", stored);
                 }
+                first
+            };
 
-                tracing::info!(
-                    "Loaded metadata for {} games from gamelist.xml",
-                    metadata_map.len()
-                );
+            for game in found.games {
+                if first_copy(&game.path) {
+                    merged.games.push(game);
+                }
+            }
+            for stored in found.unchanged {
+                if first_copy(&stored) {
+                    merged.unchanged.push(stored);
+                }
             }
         }
-
-        metadata_map
+        Ok(merged)
     }
 
     /// Recursively scan a directory
@@ -139,13 +312,14 @@ impl RomScanner {
         &self,
         path: &Path,
         system: &str,
-        games: &mut Vec<Game>,
-        metadata_map: &HashMap<String, GameMetadata>,
+        found: &mut SystemScan,
+        gamelist: &GamelistProvider,
     ) -> Result<(), LibraryError> {
         if !path.exists() || !path.is_dir() {
             return Ok(());
         }
 
+        let mut files = Vec::new();
         for entry in fs::read_dir(path)? {
             let entry = entry?;
             let entry_path = entry.path();
@@ -164,20 +338,30 @@ impl RomScanner {
 
                 // Recurse into subdirectories
                 if self.config.recursive {
-                    self.scan_dir(&entry_path, system, games, metadata_map)?;
+                    self.scan_dir(&entry_path, system, found, gamelist)?;
                 }
             } else if entry_path.is_file() {
-                // Check extension - avoid if-let chains for MSRV 1.85 compatibility
-                #[allow(clippy::collapsible_if)]
-                if let Some(ext) = entry_path.extension().and_then(|e| e.to_str()) {
-                    if self.config.extensions.contains(&ext.to_lowercase()) {
-                        if let Some(mut game) = self.create_game(&entry_path, system) {
-                            // Apply metadata from gamelist.xml if available
-                            if let Some(metadata) = metadata_map.get(&name) {
-                                game.apply_metadata(metadata);
-                            }
-                            games.push(game);
+                files.push(entry_path);
+            }
+        }
+
+        // Multi-disc games are listed once, by their playlist
+        let discs = group_discs(path, &mut files);
+
+        for file in files.iter().filter(|file| !discs.contains(*file)) {
+            // Check extension - avoid if-let chains for MSRV 1.85 compatibility
+            #[allow(clippy::collapsible_if)]
+            if let Some(ext) = file.extension().and_then(|e| e.to_str()) {
+                if self.config.extensions.contains(&ext.to_lowercase()) {
+                    let stored = encode_path(file);
+                    if self.is_unchanged(&stored, file) {
+                        found.unchanged.push(stored);
+                    } else if let Some(mut game) = self.create_game(file, system) {
+                        // Apply metadata from gamelist.xml if available
+                        if let Some(metadata) = gamelist.lookup(&game) {
+                            game.apply_metadata(&metadata);
                         }
+                        found.games.push(game);
                     }
                 }
             }
@@ -186,16 +370,33 @@ impl RomScanner {
         Ok(())
     }
 
+    /// Check if `file` is in the index and hasn't changed since
+    fn is_unchanged(&self, stored: &str, file: &Path) -> bool {
+        self.index
+            .get(stored)
+            .is_some_and(|indexed| FileStamp::of(file).is_some_and(|stamp| indexed.matches(&stamp)))
+    }
+
     /// Create a Game from a ROM file
     fn create_game(&self, path: &Path, system: &str) -> Option<Game> {
         let name = path.file_stem()?.to_string_lossy().to_string();
 
         // Clean up name (remove region codes, etc.)
-        let clean_name = Self::clean_game_name(&name);
+        let clean_name = self.config.names.clean(&name);
+        let regions = parse_regions(&name);
+
+        // Non-UTF-8 names are stored encoded so the ROM can still be launched
+        let stored_path = encode_path(path);
+        if is_encoded(&stored_path) {
+            tracing::warn!(
+                "ROM path can't be stored as is, storing encoded: {}",
+                path.display()
+            );
+        }
 
         Some(Game {
             id: 0,
-            path: path.to_string_lossy().to_string(),
+            path: stored_path,
             system: system.to_string(),
             name: clean_name,
             description: None,
@@ -207,80 +408,194 @@ impl RomScanner {
             rating: None,
             favorite: false,
             hidden: false,
+            region: (!regions.is_empty()).then(|| regions.join(",")),
+            size: std::fs::metadata(path).map_or(0, |m| m.len() as i64),
+            launch_override: None,
         })
     }
 
-    /// Clean up a game name (remove region codes, etc.)
-    fn clean_game_name(name: &str) -> String {
-        let mut clean = name.to_string();
+    /// Scan all systems in a roms directory
+    pub fn scan_all(&self, roms_dir: &Path) -> Result<Vec<(String, Vec<Game>)>, LibraryError> {
+        let mut results = Vec::new();
+        let mut first_error = None;
 
-        // Remove common patterns in parentheses/brackets
-        let patterns = [
-            // Regions
-            "(USA)", "(Europe)", "(Japan)", "(World)", "(U)", "(E)", "(J)", "(W)", "(En)", "(Fr)",
-            "(De)", "(Es)", "(It)", // Versions
-            "(Rev 1)", "(Rev 2)", "(Rev A)", "(Rev B)", "(v1.0)", "(v1.1)", "(v1.2)",
-            // Tags
-            "(Unl)", "(Proto)", "(Beta)", "(Demo)", "(Sample)", "[!]", "[a]", "[b]", "[h]", "[o]",
-            "[t]",
-        ];
+        self.scan_systems(roms_dir, |progress, games| match games {
+            Ok(games) if !games.is_empty() => results.push((progress.system, games)),
+            Ok(_) => {}
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        })?;
 
-        for pattern in &patterns {
-            clean = clean.replace(pattern, "");
+        if let Some(e) = first_error {
+            return Err(e);
         }
+        results.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(results)
+    }
+
+    /// Scan all systems, handing each one's games over as it finishes
+    ///
+    /// System directories are scanned concurrently (see
+    /// [`ScanConfig::threads`]), but `on_system` always runs on the calling
+    /// thread, one system at a time, so it can feed a single database
+    /// writer. Failed systems are recorded in the result's `errors`.
+    pub fn scan_all_with_progress(
+        &self,
+        roms_dir: &Path,
+        mut on_system: impl FnMut(&ScanProgress, Vec<Game>),
+    ) -> Result<ScanResult, LibraryError> {
+        self.scan_systems(roms_dir, |progress, games| {
+            if let Ok(games) = games {
+                on_system(&progress, games);
+            }
+        })
+    }
 
-        // Remove anything in square brackets
-        while let Some(start) = clean.find('[') {
-            if let Some(end) = clean.find(']') {
-                if end > start {
-                    clean = format!("{}{}", &clean[..start], &clean[end + 1..]);
-                } else {
-                    break;
+    /// Scan system directories on a worker pool
+    fn scan_systems(
+        &self,
+        roms_dir: &Path,
+        mut on_system: impl FnMut(ScanProgress, Result<Vec<Game>, LibraryError>),
+    ) -> Result<ScanResult, LibraryError> {
+        let started = Instant::now();
+        let systems = self.system_dirs(roms_dir)?;
+        let mut result = ScanResult::default();
+
+        let threads = match self.config.threads {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        }
+        .min(systems.len());
+        let next = AtomicUsize::new(0);
+        let (tx, rx) = mpsc::channel();
+
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                let tx = tx.clone();
+                let (next, systems) = (&next, &systems);
+                scope.spawn(move || {
+                    while let Some((system, path)) =
+                        systems.get(next.fetch_add(1, Ordering::Relaxed))
+                    {
+                        if tx.send((system, self.scan_system(path, system))).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(tx);
+
+            // Counted here rather than on the workers so progress stays in
+            // order however the systems finish
+            for (completed, (system, found)) in rx.into_iter().enumerate() {
+                let progress = ScanProgress {
+                    system: system.clone(),
+                    games: found
+                        .as_ref()
+                        .map_or(0, |f| f.games.len() + f.unchanged.len()),
+                    completed: completed + 1,
+                    total: systems.len(),
+                };
+
+                match &found {
+                    Ok(found) => self.count_changes(system, found, &mut result),
+                    Err(e) => {
+                        // Its indexed ROMs are kept rather than reported removed
+                        tracing::warn!("Failed to scan {}: {}", system, e);
+                        result.errors.push(format!("{}: {}", system, e));
+                    }
                 }
-            } else {
-                break;
+                on_system(progress, found.map(|f| f.games));
             }
+        });
+
+        // Systems whose directory is gone entirely. Skipped when the roms
+        // directory itself is missing, so an unmounted card isn't taken
+        // for an empty one.
+        if roms_dir.exists() {
+            let present: HashSet<&str> = systems.iter().map(|(s, _)| s.as_str()).collect();
+            let mut vanished: Vec<_> = self
+                .index
+                .files
+                .iter()
+                .filter(|(_, (system, _))| !present.contains(system.as_str()))
+                .map(|(path, _)| path.clone())
+                .collect();
+            result.removed.append(&mut vanished);
         }
+        result.removed.sort();
+        result.games_removed = result.removed.len();
 
-        // Clean up whitespace
-        clean = clean
-            .split_whitespace()
-            .collect::<Vec<_>>()
-            .join(" ")
-            .trim()
-            .to_string();
-
-        clean
+        result.duration_ms = started.elapsed().as_millis() as u64;
+        Ok(result)
     }
 
-    /// Scan all systems in a roms directory
-    pub fn scan_all(&self, roms_dir: &Path) -> Result<Vec<(String, Vec<Game>)>, LibraryError> {
-        let mut results = Vec::new();
-
-        if !roms_dir.exists() {
-            return Ok(results);
+    /// Add one system's found, added, updated and removed ROMs to `result`
+    fn count_changes(&self, system: &str, found: &SystemScan, result: &mut ScanResult) {
+        result.games_found += found.games.len() + found.unchanged.len();
+        for game in &found.games {
+            if self.index.get(&game.path).is_some() {
+                result.games_updated += 1;
+            } else {
+                result.games_added += 1;
+            }
         }
 
-        for entry in fs::read_dir(roms_dir)? {
-            let entry = entry?;
-            let path = entry.path();
+        let present: HashSet<&str> = found
+            .games
+            .iter()
+            .map(|game| game.path.as_str())
+            .chain(found.unchanged.iter().map(String::as_str))
+            .collect();
+        result.removed.extend(
+            self.index
+                .paths(system)
+                .filter(|path| !present.contains(path.as_str()))
+                .cloned(),
+        );
+    }
 
-            if path.is_dir() {
-                let system = entry.file_name().to_string_lossy().to_string();
+    /// Systems under the roms directories and the directories holding
+    /// each, sorted by name
+    fn system_dirs(&self, roms_dir: &Path) -> Result<Vec<(String, Vec<PathBuf>)>, LibraryError> {
+        let paths = Paths {
+            roms: roms_dir.to_path_buf(),
+            roms2: self.roms2.clone(),
+            ..Default::default()
+        };
+
+        let mut names = HashSet::new();
+        for root in std::iter::once(roms_dir).chain(self.roms2.as_deref()) {
+            if !root.exists() {
+                continue;
+            }
 
-                // Skip special directories
-                if self.config.skip_dirs.contains(&system.to_lowercase()) {
-                    continue;
-                }
+            for entry in fs::read_dir(root)? {
+                let entry = entry?;
 
-                let games = self.scan(&path, &system)?;
-                if !games.is_empty() {
-                    results.push((system, games));
+                if entry.path().is_dir() {
+                    let system = entry.file_name().to_string_lossy().to_string();
+
+                    // Skip special directories
+                    if self.config.skip_dirs.contains(&system.to_lowercase()) {
+                        continue;
+                    }
+
+                    names.insert(system);
                 }
             }
         }
 
-        Ok(results)
+        let mut systems: Vec<_> = names
+            .into_iter()
+            .map(|system| {
+                let dirs = paths.system_rom_dirs(&system);
+                (system, dirs)
+            })
+            .collect();
+        systems.sort();
+        Ok(systems)
     }
 
     /// Get file info (size, hash, etc.)
@@ -306,16 +621,219 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_clean_game_name() {
+    fn test_scan_non_utf8_filename() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let rom = dir.path().join(OsStr::from_bytes(b"Caf\xe9 (Europe).gba"));
+        fs::write(&rom, b"ROM").unwrap();
+
+        let games = RomScanner::new().scan(dir.path(), "gba").unwrap();
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].name, "Caf\u{FFFD}");
+        assert_eq!(games[0].raw_name(), "Caf\u{FFFD} (Europe)");
+        assert_eq!(games[0].region.as_deref(), Some("EUR"));
+        assert_eq!(games[0].rom_path(), rom);
+
+        // Survives a round trip through the database
+        let db = crate::GameDatabase::in_memory().unwrap();
+        let id = db.add_game(&games[0]).unwrap();
+        let stored = db.get_game(id).unwrap().unwrap();
+        assert_eq!(stored.rom_path(), rom);
+        assert!(stored.rom_path().exists());
+    }
+
+    #[test]
+    fn test_parallel_scan_all() {
+        let dir = tempfile::tempdir().unwrap();
+        for (system, count) in [("gba", 3), ("nes", 2), ("snes", 0), ("bios", 1)] {
+            let system_dir = dir.path().join(system);
+            fs::create_dir_all(&system_dir).unwrap();
+            for i in 0..count {
+                fs::write(system_dir.join(format!("game{}.{}", i, system)), b"ROM").unwrap();
+            }
+        }
+        fs::write(dir.path().join("snes/game.sfc"), b"ROM").unwrap();
+
+        let config = ScanConfig {
+            threads: 2,
+            ..ScanConfig::default()
+        };
+        let scanner = RomScanner::with_config(config);
+
+        let mut seen = Vec::new();
+        let result = scanner
+            .scan_all_with_progress(dir.path(), |progress, games| {
+                assert_eq!(progress.games, games.len());
+                assert_eq!(progress.total, 3);
+                seen.push((progress.completed, progress.system.clone()));
+            })
+            .unwrap();
+
+        assert_eq!(result.games_found, 6);
+        assert!(result.errors.is_empty());
         assert_eq!(
-            RomScanner::clean_game_name("Super Mario World (USA)"),
-            "Super Mario World"
+            seen.iter().map(|(n, _)| *n).collect::<Vec<_>>(),
+            vec![1, 2, 3]
         );
-        assert_eq!(RomScanner::clean_game_name("Zelda (Europe) [!]"), "Zelda");
+
+        let all = scanner.scan_all(dir.path()).unwrap();
+        let systems: Vec<_> = all.iter().map(|(s, g)| (s.as_str(), g.len())).collect();
+        assert_eq!(systems, vec![("gba", 3), ("nes", 2), ("snes", 1)]);
+    }
+
+    #[test]
+    fn test_scan_secondary_roms() {
+        let roms = tempfile::tempdir().unwrap();
+        let roms2 = tempfile::tempdir().unwrap();
+        for (root, file) in [
+            (roms.path(), "snes/Shared.sfc"),
+            (roms.path(), "snes/Primary.sfc"),
+            (roms2.path(), "snes/Shared.sfc"),
+            (roms2.path(), "snes/Secondary.sfc"),
+            (roms2.path(), "gba/Only Here.gba"),
+        ] {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, b"ROM").unwrap();
+        }
+
+        let scanner = RomScanner::new().with_secondary_roms(roms2.path());
+        let all = scanner.scan_all(roms.path()).unwrap();
+        let systems: Vec<_> = all.iter().map(|(s, g)| (s.as_str(), g.len())).collect();
+        assert_eq!(systems, vec![("gba", 1), ("snes", 3)]);
+
+        // The primary card's copy wins
+        let shared = all[1].1.iter().find(|g| g.name == "Shared").unwrap();
+        assert!(Path::new(&shared.path).starts_with(roms.path()));
+    }
+
+    #[test]
+    fn test_scan_multi_disc() {
+        let dir = tempfile::tempdir().unwrap();
+        for disc in 1..=3 {
+            let name = format!("Final Fantasy VII (USA) (Disc {})", disc);
+            fs::write(
+                dir.path().join(format!("{}.cue", name)),
+                format!("FILE \"{}.bin\" BINARY\n", name),
+            )
+            .unwrap();
+            fs::write(dir.path().join(format!("{}.bin", name)), b"DISC").unwrap();
+        }
+
+        let games = RomScanner::new().scan(dir.path(), "psx").unwrap();
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].name, "Final Fantasy VII");
         assert_eq!(
-            RomScanner::clean_game_name("Pokemon Red (U) (Rev 1)"),
-            "Pokemon Red"
+            games[0].rom_path(),
+            dir.path().join("Final Fantasy VII (USA).m3u")
         );
+
+        // The playlist written on the first scan is reused
+        let games = RomScanner::new().scan(dir.path(), "psx").unwrap();
+        assert_eq!(games.len(), 1);
+    }
+
+    #[test]
+    fn test_incremental_scan() {
+        use std::time::{Duration, SystemTime};
+
+        let dir = tempfile::tempdir().unwrap();
+        let gba = dir.path().join("gba");
+        fs::create_dir_all(&gba).unwrap();
+        for name in ["Kept", "Changed", "Deleted"] {
+            fs::write(gba.join(format!("{}.gba", name)), b"ROM").unwrap();
+        }
+        fs::create_dir_all(dir.path().join("nes")).unwrap();
+        fs::write(dir.path().join("nes/Gone.nes"), b"ROM").unwrap();
+
+        let db = crate::GameDatabase::in_memory().unwrap();
+        let mut games = Vec::new();
+        let first = RomScanner::new()
+            .with_index(db.scan_index().unwrap())
+            .scan_all_with_progress(dir.path(), |_, found| games.extend(found))
+            .unwrap();
+        assert_eq!(first.games_added, 4);
+        db.apply_scan(&games, &first.removed).unwrap();
+
+        // Rounding by a FAT card doesn't count as a change
+        let kept = fs::File::options()
+            .write(true)
+            .open(gba.join("Kept.gba"))
+            .unwrap();
+        let mtime = fs::metadata(gba.join("Kept.gba"))
+            .unwrap()
+            .modified()
+            .unwrap();
+        kept.set_modified(mtime + Duration::from_secs(1)).unwrap();
+
+        fs::write(gba.join("Changed.gba"), b"LONGER ROM").unwrap();
+        fs::remove_file(gba.join("Deleted.gba")).unwrap();
+        fs::write(gba.join("New.gba"), b"ROM").unwrap();
+        fs::remove_dir_all(dir.path().join("nes")).unwrap();
+
+        let mut games = Vec::new();
+        let second = RomScanner::new()
+            .with_index(db.scan_index().unwrap())
+            .scan_all_with_progress(dir.path(), |_, found| games.extend(found))
+            .unwrap();
+        let mut names: Vec<_> = games.iter().map(|g| g.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["Changed", "New"]);
+        assert_eq!(second.games_found, 3);
+        assert_eq!(second.games_added, 1);
+        assert_eq!(second.games_updated, 1);
+        assert_eq!(second.games_removed, 2);
+        assert!(second.removed.iter().any(|p| p.ends_with("Deleted.gba")));
+        assert!(second.removed.iter().any(|p| p.ends_with("Gone.nes")));
+
+        db.apply_scan(&games, &second.removed).unwrap();
+        assert_eq!(db.game_count().unwrap(), 3);
+        assert_eq!(db.scan_index().unwrap().len(), 3);
+
+        // Pre-epoch times from a card written with no clock set
+        let kept = fs::File::options()
+            .write(true)
+            .open(gba.join("Kept.gba"))
+            .unwrap();
+        kept.set_modified(SystemTime::UNIX_EPOCH - Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(FileStamp::of(&gba.join("Kept.gba")).unwrap().mtime, -60);
+    }
+
+    #[test]
+    fn test_missing_roms_dir_keeps_index() {
+        let mut index = ScanIndex::new();
+        let stamp = FileStamp { size: 3, mtime: 0 };
+        index.insert("/missing/gba/Game.gba", "gba", stamp);
+
+        let result = RomScanner::new()
+            .with_index(index)
+            .scan_all_with_progress(Path::new("/nonexistent/rexos-roms"), |_, _| {})
+            .unwrap();
+        assert!(result.removed.is_empty());
+    }
+
+    #[test]
+    fn test_file_stamp_tolerance() {
+        let stamp = FileStamp {
+            size: 100,
+            mtime: 1_000,
+        };
+        assert!(stamp.matches(&FileStamp {
+            mtime: 1_002,
+            ..stamp
+        }));
+        assert!(stamp.matches(&FileStamp {
+            mtime: 998,
+            ..stamp
+        }));
+        assert!(!stamp.matches(&FileStamp {
+            mtime: 1_003,
+            ..stamp
+        }));
+        assert!(!stamp.matches(&FileStamp { size: 101, ..stamp }));
     }
 
     #[test]
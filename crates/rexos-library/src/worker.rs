@@ -0,0 +1,221 @@
+//! Background database access
+//!
+//! `rusqlite::Connection` isn't `Sync`, so the database is moved onto its
+//! own thread and driven through a request channel. Responses are polled
+//! without blocking, which keeps slow queries off the UI thread.
+
+use crate::{Game, GameDatabase, LibraryError, ScanIndex};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// A request to the database thread
+#[derive(Debug, Clone)]
+pub enum DbRequest {
+    /// List systems with game counts
+    Systems,
+    /// List games for a system
+    GamesBySystem(String),
+    /// List games for a system in the preferred regions
+    GamesInRegions {
+        system: String,
+        regions: Vec<String>,
+    },
+    /// Mark a game as favorite or not
+    SetFavorite { id: i64, favorite: bool },
+    /// Record a play session
+    UpdatePlayStats { id: i64, play_time: i64 },
+    /// Insert or update scanned games
+    AddGames(Vec<Game>),
+    /// Load stored ROM stamps for an incremental scan
+    ScanIndex,
+    /// Store an incremental scan's new and changed games and delete the
+    /// removed paths
+    ApplyScan {
+        games: Vec<Game>,
+        removed: Vec<String>,
+    },
+}
+
+/// A response from the database thread
+#[derive(Debug)]
+pub enum DbResponse {
+    /// Result of [`DbRequest::Systems`]
+    Systems(Result<Vec<(String, i64)>, LibraryError>),
+    /// Result of [`DbRequest::GamesBySystem`] or [`DbRequest::GamesInRegions`]
+    Games {
+        system: String,
+        games: Result<Vec<Game>, LibraryError>,
+    },
+    /// Number of games stored by [`DbRequest::AddGames`] or
+    /// [`DbRequest::ApplyScan`]
+    GamesAdded(Result<usize, LibraryError>),
+    /// Result of [`DbRequest::ScanIndex`]
+    ScanIndex(Result<ScanIndex, LibraryError>),
+    /// Result of a write without data
+    Done(Result<(), LibraryError>),
+}
+
+/// Database running on a background thread
+pub struct DatabaseWorker {
+    requests: Option<Sender<DbRequest>>,
+    responses: Receiver<DbResponse>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl DatabaseWorker {
+    /// Move an open database onto a new thread
+    pub fn spawn(db: GameDatabase) -> Self {
+        let (request_tx, request_rx) = mpsc::channel::<DbRequest>();
+        let (response_tx, response_rx) = mpsc::channel();
+
+        let handle = thread::Builder::new()
+            .name("rexos-db".to_string())
+            .spawn(move || {
+                for request in request_rx {
+                    if response_tx.send(handle_request(&db, request)).is_err() {
+                        break;
+                    }
+                }
+            })
+            .expect("failed to spawn database thread");
+
+        Self {
+            requests: Some(request_tx),
+            responses: response_rx,
+            handle: Some(handle),
+        }
+    }
+
+    /// Open a database and start its thread
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, LibraryError> {
+        Ok(Self::spawn(GameDatabase::open(path)?))
+    }
+
+    /// Queue a request
+    pub fn send(&self, request: DbRequest) -> Result<(), LibraryError> {
+        self.requests
+            .as_ref()
+            .and_then(|tx| tx.send(request).ok())
+            .ok_or_else(|| LibraryError::Database("Database thread stopped".to_string()))
+    }
+
+    /// Get a finished response without blocking
+    pub fn try_recv(&self) -> Option<DbResponse> {
+        self.responses.try_recv().ok()
+    }
+
+    /// Wait up to `timeout` for a response
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<DbResponse, LibraryError> {
+        self.responses.recv_timeout(timeout).map_err(|e| match e {
+            RecvTimeoutError::Timeout => {
+                LibraryError::Database("Timed out waiting for database".to_string())
+            }
+            RecvTimeoutError::Disconnected => {
+                LibraryError::Database("Database thread stopped".to_string())
+            }
+        })
+    }
+}
+
+impl Drop for DatabaseWorker {
+    fn drop(&mut self) {
+        // Closing the channel ends the thread after pending requests
+        self.requests.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Run one request against the database
+fn handle_request(db: &GameDatabase, request: DbRequest) -> DbResponse {
+    match request {
+        DbRequest::Systems => DbResponse::Systems(db.get_systems()),
+        DbRequest::GamesBySystem(system) => {
+            let games = db.get_games_by_system(&system);
+            DbResponse::Games { system, games }
+        }
+        DbRequest::GamesInRegions { system, regions } => {
+            let games = db.get_games_by_system_in_regions(&system, &regions);
+            DbResponse::Games { system, games }
+        }
+        DbRequest::SetFavorite { id, favorite } => DbResponse::Done(db.set_favorite(id, favorite)),
+        DbRequest::UpdatePlayStats { id, play_time } => {
+            DbResponse::Done(db.update_play_stats(id, play_time))
+        }
+        DbRequest::AddGames(games) => DbResponse::GamesAdded(
+            games
+                .iter()
+                .try_for_each(|game| db.add_game(game).map(|_| ()))
+                .map(|()| games.len()),
+        ),
+        DbRequest::ScanIndex => DbResponse::ScanIndex(db.scan_index()),
+        DbRequest::ApplyScan { games, removed } => {
+            DbResponse::GamesAdded(db.apply_scan(&games, &removed))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(path: &str, system: &str) -> Game {
+        Game {
+            id: 0,
+            path: path.to_string(),
+            system: system.to_string(),
+            name: path.to_string(),
+            description: None,
+            release_date: None,
+            developer: None,
+            publisher: None,
+            genre: None,
+            players: None,
+            rating: None,
+            favorite: false,
+            hidden: false,
+            region: None,
+            size: 0,
+            launch_override: None,
+        }
+    }
+
+    #[test]
+    fn test_requests_answered_in_order() {
+        let worker = DatabaseWorker::spawn(GameDatabase::in_memory().unwrap());
+        let timeout = Duration::from_secs(5);
+
+        worker
+            .send(DbRequest::AddGames(vec![
+                game("/roms/gba/a.gba", "gba"),
+                game("/roms/gba/b.gba", "gba"),
+                game("/roms/nes/c.nes", "nes"),
+            ]))
+            .unwrap();
+        worker
+            .send(DbRequest::GamesBySystem("gba".to_string()))
+            .unwrap();
+        worker.send(DbRequest::Systems).unwrap();
+
+        match worker.recv_timeout(timeout).unwrap() {
+            DbResponse::GamesAdded(count) => assert_eq!(count.unwrap(), 3),
+            other => panic!("unexpected response: {:?}", other),
+        }
+        match worker.recv_timeout(timeout).unwrap() {
+            DbResponse::Games { system, games } => {
+                assert_eq!(system, "gba");
+                assert_eq!(games.unwrap().len(), 2);
+            }
+            other => panic!("unexpected response: {:?}", other),
+        }
+        match worker.recv_timeout(timeout).unwrap() {
+            DbResponse::Systems(systems) => assert_eq!(systems.unwrap().len(), 2),
+            other => panic!("unexpected response: {:?}", other),
+        }
+
+        assert!(worker.try_recv().is_none());
+    }
+}
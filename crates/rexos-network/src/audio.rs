@@ -0,0 +1,180 @@
+//! Bluetooth audio (A2DP) routing
+//!
+//! Connecting headphones only makes them available as a sink; the audio
+//! server keeps playing through the speaker until it's told otherwise.
+//! Routing goes through `pactl`, which talks to both PulseAudio and
+//! PipeWire's pulse server.
+
+use crate::{BluetoothDeviceType, BluetoothManager, NetworkError};
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long to wait for a connected device's sink to appear
+const SINK_WAIT: Duration = Duration::from_secs(5);
+
+/// How often sinks are listed while waiting
+const SINK_POLL: Duration = Duration::from_millis(250);
+
+impl BluetoothManager {
+    /// Play audio through a connected Bluetooth device
+    ///
+    /// Makes the device's sink the default and moves streams that are
+    /// already playing onto it.
+    pub fn set_audio_output(&self, address: &str) -> Result<(), NetworkError> {
+        let deadline = Instant::now() + SINK_WAIT;
+        // The sink shows up shortly after the device connects
+        let sink = loop {
+            if let Some(sink) = bluez_sink(&list_sinks()?, address) {
+                break sink;
+            }
+            if Instant::now() >= deadline {
+                return Err(NetworkError::DeviceNotFound(format!(
+                    "no audio sink for {}",
+                    address
+                )));
+            }
+            thread::sleep(SINK_POLL);
+        };
+
+        route_to_sink(&sink)?;
+        tracing::info!("Audio routed to {}", address);
+        Ok(())
+    }
+
+    /// Play audio through the built-in speaker (or headphone jack)
+    pub fn route_audio_to_speaker(&self) -> Result<(), NetworkError> {
+        let sink = speaker_sink(&list_sinks()?)
+            .ok_or_else(|| NetworkError::DeviceNotFound("no speaker sink".to_string()))?;
+
+        route_to_sink(&sink)?;
+        tracing::info!("Audio routed to speaker");
+        Ok(())
+    }
+
+    /// Route audio to a device that just connected, if it's an audio device
+    pub(crate) fn route_connected_audio(&self, address: &str) {
+        let is_audio = self
+            .get_device_info(address)
+            .is_ok_and(|d| d.device_type == BluetoothDeviceType::Audio);
+        if !is_audio {
+            return;
+        }
+
+        if let Err(e) = self.set_audio_output(address) {
+            tracing::warn!("Failed to route audio to {}: {}", address, e);
+        }
+    }
+}
+
+/// Run pactl, mapping a missing or unreachable server to a clear error
+fn pactl(args: &[&str]) -> Result<String, NetworkError> {
+    let output = Command::new("pactl")
+        .args(args)
+        .output()
+        .map_err(|_| NetworkError::AudioServerUnavailable)?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains("Connection failure") || stderr.contains("Connection refused") {
+        return Err(NetworkError::AudioServerUnavailable);
+    }
+    if !output.status.success() {
+        return Err(NetworkError::CommandFailed(stderr.trim().to_string()));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn list_sinks() -> Result<Vec<String>, NetworkError> {
+    Ok(sink_names(&pactl(&["list", "short", "sinks"])?))
+}
+
+/// Make `sink` the default and move playing streams onto it
+fn route_to_sink(sink: &str) -> Result<(), NetworkError> {
+    pactl(&["set-default-sink", sink])?;
+
+    for input in sink_input_ids(&pactl(&["list", "short", "sink-inputs"])?) {
+        if let Err(e) = pactl(&["move-sink-input", &input, sink]) {
+            tracing::debug!("Failed to move stream {}: {}", input, e);
+        }
+    }
+    Ok(())
+}
+
+/// Sink names from `pactl list short sinks` (`id name driver format state`)
+fn sink_names(output: &str) -> Vec<String> {
+    column(output, 1)
+}
+
+/// Stream ids from `pactl list short sink-inputs` (`id sink client ...`)
+fn sink_input_ids(output: &str) -> Vec<String> {
+    column(output, 0)
+}
+
+fn column(output: &str, index: usize) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.split('\t').nth(index))
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Sink of a Bluetooth device
+///
+/// PulseAudio names it `bluez_sink.AA_BB_...`, PipeWire `bluez_output.AA_BB_...`.
+fn bluez_sink(sinks: &[String], address: &str) -> Option<String> {
+    let address = address.to_uppercase().replace(':', "_");
+    sinks
+        .iter()
+        .find(|s| s.starts_with("bluez_") && s.to_uppercase().contains(&address))
+        .cloned()
+}
+
+/// First sink that isn't a Bluetooth device
+fn speaker_sink(sinks: &[String]) -> Option<String> {
+    sinks.iter().find(|s| !s.starts_with("bluez_")).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SINKS: &str = "0\talsa_output.platform-rk817-sound.stereo-fallback\tPipeWire\ts16le 2ch 48000Hz\tSUSPENDED\n\
+                         57\tbluez_output.AA_BB_CC_DD_EE_FF.1\tPipeWire\ts16le 2ch 48000Hz\tRUNNING\n";
+
+    #[test]
+    fn test_parse_short_lists() {
+        assert_eq!(
+            sink_names(SINKS),
+            vec![
+                "alsa_output.platform-rk817-sound.stereo-fallback".to_string(),
+                "bluez_output.AA_BB_CC_DD_EE_FF.1".to_string(),
+            ]
+        );
+        assert_eq!(
+            sink_input_ids("12\t57\t31\tPipeWire\tfloat32le 2ch 48000Hz\n"),
+            vec!["12".to_string()]
+        );
+        assert!(sink_names("").is_empty());
+    }
+
+    #[test]
+    fn test_bluez_and_speaker_sinks() {
+        let sinks = sink_names(SINKS);
+        assert_eq!(
+            bluez_sink(&sinks, "aa:bb:cc:dd:ee:ff").as_deref(),
+            Some("bluez_output.AA_BB_CC_DD_EE_FF.1")
+        );
+        assert_eq!(bluez_sink(&sinks, "11:22:33:44:55:66"), None);
+        assert_eq!(
+            speaker_sink(&sinks).as_deref(),
+            Some("alsa_output.platform-rk817-sound.stereo-fallback")
+        );
+
+        let pulse = vec!["bluez_sink.AA_BB_CC_DD_EE_FF.a2dp_sink".to_string()];
+        assert!(bluez_sink(&pulse, "AA:BB:CC:DD:EE:FF").is_some());
+        assert_eq!(speaker_sink(&pulse), None);
+    }
+}
@@ -1,7 +1,24 @@
 //! Bluetooth management using bluetoothctl
 
 use crate::NetworkError;
-use std::process::Command;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// sysfs power supplies, where HID drivers expose controller batteries
+const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+
+/// How long pairing may take, including waiting for the user to confirm
+pub const PAIRING_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// PIN sent to legacy devices that ask for one; most controllers use 0000
+const LEGACY_PIN: &str = "0000";
 
 /// Bluetooth device information
 #[derive(Debug, Clone)]
@@ -86,15 +103,70 @@ impl BluetoothDeviceType {
 pub enum PairingState {
     NotPaired,
     Pairing,
+    /// The device shows this passkey; pairing continues once
+    /// [`BluetoothManager::confirm_pairing`] is called
+    AwaitingConfirmation(u32),
     Paired,
     Failed,
 }
 
+/// IO capability the pairing agent registers with BlueZ
+///
+/// This decides how the device and BlueZ authenticate each other.
+/// `NoInputNoOutput` pairs without asking the user, which is what most
+/// controllers expect; the display capabilities let the launcher show a
+/// passkey for devices that require numeric comparison.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AgentCapability {
+    #[default]
+    NoInputNoOutput,
+    DisplayOnly,
+    DisplayYesNo,
+    KeyboardOnly,
+    KeyboardDisplay,
+}
+
+impl AgentCapability {
+    /// Name bluetoothctl's `agent` command takes
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NoInputNoOutput => "NoInputNoOutput",
+            Self::DisplayOnly => "DisplayOnly",
+            Self::DisplayYesNo => "DisplayYesNo",
+            Self::KeyboardOnly => "KeyboardOnly",
+            Self::KeyboardDisplay => "KeyboardDisplay",
+        }
+    }
+
+    /// Whether passkey confirmations are answered without asking the user
+    fn auto_confirms(&self) -> bool {
+        matches!(self, Self::NoInputNoOutput)
+    }
+}
+
+/// A line of interest from an interactive bluetoothctl session
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum AgentEvent {
+    /// Numeric comparison: the passkey must be confirmed with yes/no
+    ConfirmPasskey(u32),
+    /// The passkey to type on the device
+    DisplayPasskey(u32),
+    /// A legacy device wants a PIN code
+    PinRequested,
+    /// A service authorization request
+    Authorize,
+    Paired,
+    Failed(String),
+}
+
 /// Manages Bluetooth connections
 pub struct BluetoothManager {
     /// Bluetooth adapter interface name (e.g., "hci0")
     interface: String,
     available: bool,
+    agent: AgentCapability,
+    /// Addresses whose passkey the user has confirmed
+    confirmed: Arc<Mutex<HashSet<String>>>,
 }
 
 impl BluetoothManager {
@@ -105,9 +177,35 @@ impl BluetoothManager {
         Ok(Self {
             interface,
             available,
+            agent: AgentCapability::default(),
+            confirmed: Arc::new(Mutex::new(HashSet::new())),
         })
     }
 
+    /// Set the IO capability used when pairing
+    pub fn with_agent(mut self, agent: AgentCapability) -> Self {
+        self.agent = agent;
+        self
+    }
+
+    /// IO capability used when pairing
+    pub fn agent(&self) -> AgentCapability {
+        self.agent
+    }
+
+    /// A handle for confirming pairings from another thread
+    ///
+    /// Pairing blocks while it waits for confirmation, so the UI needs a
+    /// handle of its own to call [`BluetoothManager::confirm_pairing`] on.
+    pub fn confirmer(&self) -> Self {
+        Self {
+            interface: self.interface.clone(),
+            available: self.available,
+            agent: self.agent,
+            confirmed: Arc::clone(&self.confirmed),
+        }
+    }
+
     /// Check if Bluetooth is available
     fn check_available() -> bool {
         Command::new("bluetoothctl")
@@ -159,40 +257,32 @@ impl BluetoothManager {
         Ok(())
     }
 
-    /// Get list of discovered devices
+    /// Get list of discovered devices, strongest signal first
     pub fn list_devices(&self) -> Result<Vec<BluetoothDevice>, NetworkError> {
         if !self.available {
             return Err(NetworkError::BluetoothNotAvailable);
         }
 
         let output = self.bluetoothctl(&["devices"])?;
-        let mut devices = Vec::new();
+        let listed = parse_device_list(&output);
+        let addresses: Vec<&str> = listed.iter().map(|(a, _)| a.as_str()).collect();
+        let mut infos = self.device_infos(&addresses);
 
-        for line in output.lines() {
-            // Format: "Device XX:XX:XX:XX:XX:XX Name"
-            if line.starts_with("Device ") {
-                let parts: Vec<&str> = line.splitn(3, ' ').collect();
-                if parts.len() >= 3 {
-                    let address = parts[1].to_string();
-                    let name = parts[2].to_string();
-
-                    // Get device info
-                    if let Ok(info) = self.get_device_info(&address) {
-                        devices.push(info);
-                    } else {
-                        devices.push(BluetoothDevice {
-                            address,
-                            name,
-                            device_type: BluetoothDeviceType::Unknown,
-                            paired: false,
-                            connected: false,
-                            trusted: false,
-                            rssi: None,
-                        });
-                    }
-                }
-            }
-        }
+        let mut devices: Vec<BluetoothDevice> = listed
+            .into_iter()
+            .map(|(address, name)| {
+                infos.remove(&address).unwrap_or(BluetoothDevice {
+                    address,
+                    name,
+                    device_type: BluetoothDeviceType::Unknown,
+                    paired: false,
+                    connected: false,
+                    trusted: false,
+                    rssi: None,
+                })
+            })
+            .collect();
+        sort_by_rssi(&mut devices);
 
         Ok(devices)
     }
@@ -204,91 +294,237 @@ impl BluetoothManager {
         }
 
         let output = self.bluetoothctl(&["paired-devices"])?;
-        let mut devices = Vec::new();
+        let listed = parse_device_list(&output);
+        let addresses: Vec<&str> = listed.iter().map(|(a, _)| a.as_str()).collect();
+        let mut infos = self.device_infos(&addresses);
 
-        for line in output.lines() {
-            if line.starts_with("Device ") {
-                let parts: Vec<&str> = line.splitn(3, ' ').collect();
-                if parts.len() >= 3 {
-                    let address = parts[1].to_string();
-
-                    if let Ok(info) = self.get_device_info(&address) {
-                        devices.push(info);
-                    }
-                }
-            }
-        }
-
-        Ok(devices)
+        Ok(listed
+            .into_iter()
+            .filter_map(|(address, _)| infos.remove(&address))
+            .collect())
     }
 
     /// Get detailed device info
-    fn get_device_info(&self, address: &str) -> Result<BluetoothDevice, NetworkError> {
+    pub(crate) fn get_device_info(&self, address: &str) -> Result<BluetoothDevice, NetworkError> {
         let output = self.bluetoothctl(&["info", address])?;
+        Ok(parse_device_info(address, &output))
+    }
 
-        let mut device = BluetoothDevice {
-            address: address.to_string(),
-            name: address.to_string(),
-            device_type: BluetoothDeviceType::Unknown,
-            paired: false,
-            connected: false,
-            trusted: false,
-            rssi: None,
-        };
-
-        for line in output.lines() {
-            let line = line.trim();
-
-            if line.starts_with("Name:") {
-                device.name = line.trim_start_matches("Name:").trim().to_string();
-            } else if line.starts_with("Paired:") {
-                device.paired = line.contains("yes");
-            } else if line.starts_with("Connected:") {
-                device.connected = line.contains("yes");
-            } else if line.starts_with("Trusted:") {
-                device.trusted = line.contains("yes");
-            } else if line.starts_with("Class:") {
-                // Avoid if-let chains for MSRV 1.85 compatibility
-                #[allow(clippy::collapsible_if)]
-                if let Some(class_str) = line.split_whitespace().next_back() {
-                    if let Ok(class) = u32::from_str_radix(class_str.trim_start_matches("0x"), 16) {
-                        device.device_type = BluetoothDeviceType::from_class(class);
-                    }
+    /// Look up several devices in one bluetoothctl session
+    ///
+    /// Running `bluetoothctl info` per device costs a process and a D-Bus
+    /// connection each, which adds up with many devices nearby. Devices
+    /// missing from the batched output are looked up one by one.
+    fn device_infos(&self, addresses: &[&str]) -> HashMap<String, BluetoothDevice> {
+        let mut infos =
+            match self.bluetoothctl_batch(addresses.iter().map(|a| format!("info {}", a))) {
+                Ok(output) => parse_info_batch(&output),
+                Err(e) => {
+                    tracing::debug!("Batched device info failed: {}", e);
+                    HashMap::new()
                 }
-            } else if line.starts_with("RSSI:") {
-                #[allow(clippy::collapsible_if)]
-                if let Some(rssi_str) = line.split_whitespace().next_back() {
-                    device.rssi = rssi_str.parse().ok();
+            };
+
+        for address in addresses {
+            // Avoid if-let chains for MSRV 1.85 compatibility
+            #[allow(clippy::collapsible_if)]
+            if !infos.contains_key(*address) {
+                if let Ok(info) = self.get_device_info(address) {
+                    infos.insert(address.to_string(), info);
                 }
             }
         }
+        infos
+    }
 
-        Ok(device)
+    /// Run several commands in one interactive bluetoothctl session
+    fn bluetoothctl_batch(
+        &self,
+        commands: impl Iterator<Item = String>,
+    ) -> Result<String, NetworkError> {
+        let mut child = Command::new("bluetoothctl")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        // Avoid if-let chains for MSRV 1.85 compatibility
+        #[allow(clippy::collapsible_if)]
+        if let Some(mut stdin) = child.stdin.take() {
+            for command in commands {
+                send_line(&mut stdin, &command)?;
+            }
+            send_line(&mut stdin, "quit")?;
+        }
+
+        let output = child.wait_with_output()?;
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
     }
 
     /// Pair with a device
     pub fn pair(&self, address: &str) -> Result<(), NetworkError> {
+        self.pair_with_progress(address, &mut |_| {})
+    }
+
+    /// Pair with a device, reporting each state
+    ///
+    /// Pairing runs in an interactive bluetoothctl session with our own
+    /// agent registered, so BlueZ's passkey prompts get answered instead of
+    /// hanging. With a display capability, a numeric comparison reports
+    /// [`PairingState::AwaitingConfirmation`] and waits for
+    /// [`BluetoothManager::confirm_pairing`] until [`PAIRING_TIMEOUT`].
+    pub fn pair_with_progress(
+        &self,
+        address: &str,
+        progress: &mut dyn FnMut(PairingState),
+    ) -> Result<(), NetworkError> {
         if !self.available {
             return Err(NetworkError::BluetoothNotAvailable);
         }
 
-        tracing::info!("Pairing with device: {}", address);
+        tracing::info!("Pairing with device: {} ({})", address, self.agent.as_str());
+        progress(PairingState::Pairing);
+        self.take_confirmation(address);
 
         // Trust the device first (for auto-reconnect)
         self.bluetoothctl(&["trust", address])?;
 
-        // Attempt pairing
-        let output = self.bluetoothctl(&["pair", address])?;
+        let result = self.run_pairing(address, progress);
+        self.take_confirmation(address);
+        match &result {
+            Ok(()) => {
+                tracing::info!("Paired with {}", address);
+                progress(PairingState::Paired);
+            }
+            Err(e) => {
+                tracing::warn!("Pairing with {} failed: {}", address, e);
+                progress(PairingState::Failed);
+            }
+        }
+        result
+    }
 
-        if output.contains("Pairing successful") || output.contains("already paired") {
-            tracing::info!("Paired with {}", address);
-            Ok(())
-        } else {
-            Err(NetworkError::PairingFailed(output))
+    /// Confirm the passkey shown for a device that is pairing
+    pub fn confirm_pairing(&self, address: &str) {
+        if let Ok(mut confirmed) = self.confirmed.lock() {
+            confirmed.insert(address.to_uppercase());
+        }
+    }
+
+    /// Clear a confirmation, returning whether there was one
+    fn take_confirmation(&self, address: &str) -> bool {
+        self.confirmed
+            .lock()
+            .is_ok_and(|mut confirmed| confirmed.remove(&address.to_uppercase()))
+    }
+
+    /// Drive `bluetoothctl` interactively until pairing ends
+    fn run_pairing(
+        &self,
+        address: &str,
+        progress: &mut dyn FnMut(PairingState),
+    ) -> Result<(), NetworkError> {
+        let mut child = Command::new("bluetoothctl")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| NetworkError::CommandFailed("bluetoothctl stdin".to_string()))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| NetworkError::CommandFailed("bluetoothctl stdout".to_string()))?;
+
+        // Lines are read on their own thread so the deadline and
+        // confirmations can be checked while bluetoothctl is quiet
+        let (line_tx, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if line_tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let agent = self.agent.as_str();
+        let result = send_line(&mut stdin, &format!("agent {}", agent))
+            .and_then(|_| send_line(&mut stdin, "default-agent"))
+            .and_then(|_| send_line(&mut stdin, &format!("pair {}", address)))
+            .and_then(|_| self.answer_agent(address, &mut stdin, &lines, progress));
+
+        let _ = send_line(&mut stdin, "quit");
+        drop(stdin);
+        let _ = child.kill();
+        let _ = child.wait();
+        result
+    }
+
+    /// Answer agent prompts until pairing succeeds, fails or times out
+    fn answer_agent(
+        &self,
+        address: &str,
+        stdin: &mut ChildStdin,
+        lines: &mpsc::Receiver<String>,
+        progress: &mut dyn FnMut(PairingState),
+    ) -> Result<(), NetworkError> {
+        let deadline = Instant::now() + PAIRING_TIMEOUT;
+        let mut awaiting = false;
+
+        loop {
+            if awaiting && self.take_confirmation(address) {
+                tracing::info!("Passkey confirmed for {}", address);
+                send_line(stdin, "yes")?;
+                awaiting = false;
+                progress(PairingState::Pairing);
+            }
+
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            if timeout.is_zero() {
+                return Err(NetworkError::Timeout);
+            }
+
+            let line = match lines.recv_timeout(timeout.min(Duration::from_millis(100))) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(NetworkError::PairingFailed(
+                        "bluetoothctl exited".to_string(),
+                    ));
+                }
+            };
+
+            match parse_agent_line(&line) {
+                Some(AgentEvent::ConfirmPasskey(passkey)) => {
+                    if self.agent.auto_confirms() {
+                        send_line(stdin, "yes")?;
+                    } else {
+                        tracing::info!("Confirm passkey {:06} for {}", passkey, address);
+                        awaiting = true;
+                        progress(PairingState::AwaitingConfirmation(passkey));
+                    }
+                }
+                Some(AgentEvent::DisplayPasskey(passkey)) => {
+                    tracing::info!("Enter passkey {:06} on {}", passkey, address);
+                    progress(PairingState::AwaitingConfirmation(passkey));
+                }
+                Some(AgentEvent::PinRequested) => send_line(stdin, LEGACY_PIN)?,
+                Some(AgentEvent::Authorize) => send_line(stdin, "yes")?,
+                Some(AgentEvent::Paired) => return Ok(()),
+                Some(AgentEvent::Failed(reason)) => {
+                    return Err(NetworkError::PairingFailed(reason));
+                }
+                None => {}
+            }
         }
     }
 
     /// Connect to a paired device
+    ///
+    /// Audio devices also become the audio output.
     pub fn connect(&self, address: &str) -> Result<(), NetworkError> {
         if !self.available {
             return Err(NetworkError::BluetoothNotAvailable);
@@ -300,6 +536,7 @@ impl BluetoothManager {
 
         if output.contains("Connection successful") || output.contains("Connected: yes") {
             tracing::info!("Connected to {}", address);
+            self.route_connected_audio(address);
             Ok(())
         } else {
             Err(NetworkError::ConnectionFailed(output))
@@ -357,6 +594,41 @@ impl BluetoothManager {
             .collect())
     }
 
+    /// Battery level of a connected controller in percent
+    ///
+    /// Reads the bluez `Battery1` interface over D-Bus, falling back to the
+    /// HID driver's sysfs power supply. `None` when the device isn't a
+    /// connected controller or doesn't report its battery.
+    pub fn device_battery(&self, address: &str) -> Option<u8> {
+        let device = self.get_device_info(address).ok()?;
+        if !device.connected || device.device_type != BluetoothDeviceType::Controller {
+            return None;
+        }
+
+        self.bluez_battery(address)
+            .or_else(|| sysfs_battery(Path::new(POWER_SUPPLY_DIR), address))
+    }
+
+    /// Query `org.bluez.Battery1.Percentage` for a device
+    fn bluez_battery(&self, address: &str) -> Option<u8> {
+        let connection = zbus::blocking::Connection::system().ok()?;
+        let proxy = zbus::blocking::Proxy::new(
+            &connection,
+            "org.bluez",
+            device_object_path(&self.interface, address),
+            "org.bluez.Battery1",
+        )
+        .ok()?;
+
+        match proxy.get_property::<u8>("Percentage") {
+            Ok(percentage) => Some(percentage.min(100)),
+            Err(e) => {
+                tracing::debug!("No bluez battery for {}: {}", address, e);
+                None
+            }
+        }
+    }
+
     /// Run bluetoothctl command
     fn bluetoothctl(&self, args: &[&str]) -> Result<String, NetworkError> {
         let output = Command::new("bluetoothctl").args(args).output()?;
@@ -415,6 +687,214 @@ impl BluetoothManager {
     }
 }
 
+/// Addresses and names from `bluetoothctl devices` output
+///
+/// Lines read `Device XX:XX:XX:XX:XX:XX Name`.
+fn parse_device_list(output: &str) -> Vec<(String, String)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.strip_prefix("Device ")?.splitn(2, ' ');
+            let address = parts.next()?.to_string();
+            let name = parts.next().unwrap_or(&address).trim().to_string();
+            Some((address, name))
+        })
+        .collect()
+}
+
+/// Parse `bluetoothctl info` output for one device
+fn parse_device_info(address: &str, output: &str) -> BluetoothDevice {
+    let mut device = BluetoothDevice {
+        address: address.to_string(),
+        name: address.to_string(),
+        device_type: BluetoothDeviceType::Unknown,
+        paired: false,
+        connected: false,
+        trusted: false,
+        rssi: None,
+    };
+
+    for line in output.lines() {
+        let line = line.trim();
+
+        if line.starts_with("Name:") {
+            device.name = line.trim_start_matches("Name:").trim().to_string();
+        } else if line.starts_with("Paired:") {
+            device.paired = line.contains("yes");
+        } else if line.starts_with("Connected:") {
+            device.connected = line.contains("yes");
+        } else if line.starts_with("Trusted:") {
+            device.trusted = line.contains("yes");
+        } else if line.starts_with("Class:") {
+            // Avoid if-let chains for MSRV 1.85 compatibility
+            #[allow(clippy::collapsible_if)]
+            if let Some(class) = parse_class(line.trim_start_matches("Class:")) {
+                device.device_type = BluetoothDeviceType::from_class(class);
+            }
+        } else if line.starts_with("RSSI:") {
+            device.rssi = parse_rssi(line.trim_start_matches("RSSI:"));
+        }
+    }
+
+    device
+}
+
+/// Split the output of batched `info` commands per device
+///
+/// Each device's block starts with `Device XX:XX:XX:XX:XX:XX (public)`.
+/// Interactive mode may put a `[bluetooth]# ` prompt before it.
+fn parse_info_batch(output: &str) -> HashMap<String, BluetoothDevice> {
+    let mut blocks: Vec<(String, String)> = Vec::new();
+
+    for line in output.lines() {
+        let line = strip_ansi(line);
+        let line = match line.find("]# ") {
+            Some(end) if line.starts_with('[') => line[end + 3..].to_string(),
+            _ => line,
+        };
+
+        let header = line
+            .strip_prefix("Device ")
+            .and_then(|rest| rest.split_whitespace().next())
+            .filter(|address| is_address(address));
+        match (header, blocks.last_mut()) {
+            (Some(address), _) => blocks.push((address.to_string(), String::new())),
+            (None, Some((_, block))) => {
+                block.push_str(&line);
+                block.push('\n');
+            }
+            (None, None) => {}
+        }
+    }
+
+    blocks
+        .into_iter()
+        .map(|(address, block)| {
+            let device = parse_device_info(&address, &block);
+            (address, device)
+        })
+        .collect()
+}
+
+/// Whether a string is a `XX:XX:XX:XX:XX:XX` address
+fn is_address(s: &str) -> bool {
+    s.len() == 17
+        && s.split(':').count() == 6
+        && s.split(':')
+            .all(|b| b.len() == 2 && b.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Parse a device class such as `0x002508`
+pub(crate) fn parse_class(value: &str) -> Option<u32> {
+    let value = value.split_whitespace().next()?;
+    u32::from_str_radix(value.trim_start_matches("0x"), 16).ok()
+}
+
+/// Parse an RSSI value, either `-62` or `0xffffffc2 (-62)`
+pub(crate) fn parse_rssi(value: &str) -> Option<i32> {
+    let value = value.trim();
+    let value = match (value.find('('), value.find(')')) {
+        (Some(start), Some(end)) if start < end => &value[start + 1..end],
+        _ => value.split_whitespace().next()?,
+    };
+    value.parse().ok()
+}
+
+/// Sort devices by signal strength, strongest first and unknown last
+pub(crate) fn sort_by_rssi(devices: &mut [BluetoothDevice]) {
+    devices.sort_by_key(|d| std::cmp::Reverse(d.rssi.unwrap_or(i32::MIN)));
+}
+
+/// Write a command to an interactive bluetoothctl session
+fn send_line(stdin: &mut ChildStdin, line: &str) -> Result<(), NetworkError> {
+    writeln!(stdin, "{}", line)?;
+    stdin.flush()?;
+    Ok(())
+}
+
+/// Remove the ANSI colour codes bluetoothctl adds to its output
+pub(crate) fn strip_ansi(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // Skip to the end of the escape sequence
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else if c != '\u{1}' && c != '\u{2}' && c != '\r' {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Recognize agent prompts and pairing results in bluetoothctl output
+fn parse_agent_line(line: &str) -> Option<AgentEvent> {
+    let line = strip_ansi(line);
+    let line = line.trim();
+
+    let passkey = |after: &str| -> Option<u32> {
+        let start = line.find(after)? + after.len();
+        line[start..]
+            .split(|c: char| !c.is_ascii_digit())
+            .find(|s| !s.is_empty())?
+            .parse()
+            .ok()
+    };
+
+    if line.contains("Confirm passkey") {
+        passkey("Confirm passkey").map(AgentEvent::ConfirmPasskey)
+    } else if line.contains("[agent] Passkey:") {
+        passkey("Passkey:").map(AgentEvent::DisplayPasskey)
+    } else if line.contains("Enter PIN code") {
+        Some(AgentEvent::PinRequested)
+    } else if line.contains("Authorize service") {
+        Some(AgentEvent::Authorize)
+    } else if line.contains("Pairing successful") || line.contains("AlreadyExists") {
+        Some(AgentEvent::Paired)
+    } else if line.contains("Failed to pair") || line.contains("not available") {
+        Some(AgentEvent::Failed(line.to_string()))
+    } else {
+        None
+    }
+}
+
+/// bluez D-Bus object path for a device on an adapter
+fn device_object_path(interface: &str, address: &str) -> String {
+    format!(
+        "/org/bluez/{}/dev_{}",
+        interface,
+        address.to_uppercase().replace(':', "_")
+    )
+}
+
+/// Check if a power supply name belongs to a device
+///
+/// HID drivers embed the address, e.g. `hid-aa:bb:cc:dd:ee:ff-battery` or
+/// `ps-controller-battery-aa:bb:cc:dd:ee:ff`.
+fn supply_matches(name: &str, address: &str) -> bool {
+    name.to_lowercase().contains(&address.to_lowercase())
+}
+
+/// Read a device's battery capacity from sysfs power supplies
+fn sysfs_battery(power_supply_dir: &Path, address: &str) -> Option<u8> {
+    fs::read_dir(power_supply_dir)
+        .ok()?
+        .flatten()
+        .filter(|entry| supply_matches(&entry.file_name().to_string_lossy(), address))
+        .find_map(|entry| {
+            fs::read_to_string(entry.path().join("capacity"))
+                .ok()?
+                .trim()
+                .parse::<u8>()
+                .ok()
+        })
+        .map(|capacity| capacity.min(100))
+}
+
 /// Bluetooth adapter information
 #[derive(Debug, Clone)]
 pub struct AdapterInfo {
@@ -450,9 +930,127 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_device_object_path() {
+        assert_eq!(
+            device_object_path("hci0", "aa:bb:cc:dd:ee:ff"),
+            "/org/bluez/hci0/dev_AA_BB_CC_DD_EE_FF"
+        );
+    }
+
+    #[test]
+    fn test_supply_matches() {
+        let address = "AA:BB:CC:DD:EE:FF";
+        assert!(supply_matches("hid-aa:bb:cc:dd:ee:ff-battery", address));
+        assert!(supply_matches(
+            "ps-controller-battery-aa:bb:cc:dd:ee:ff",
+            address
+        ));
+        assert!(!supply_matches("battery", address));
+        assert!(!supply_matches("hid-11:22:33:44:55:66-battery", address));
+    }
+
     #[test]
     fn test_device_type_icon() {
         assert_eq!(BluetoothDeviceType::Controller.icon(), "input-gaming");
         assert_eq!(BluetoothDeviceType::Audio.icon(), "audio-headphones");
     }
+
+    #[test]
+    fn test_parse_info_batch() {
+        let output = "[bluetooth]# Device AA:BB:CC:DD:EE:FF (public)\n\
+                      \tName: 8BitDo Pro 2\n\
+                      \tClass: 0x00002508 (9480)\n\
+                      \tPaired: yes\n\
+                      \tTrusted: yes\n\
+                      \tConnected: no\n\
+                      \tRSSI: 0xffffffc2 (-62)\n\
+                      [bluetooth]# Device 11:22:33:44:55:66 (random)\n\
+                      \tName: Headphones\n\
+                      \tClass: 0x00240404\n\
+                      \tRSSI: -40\n";
+        let infos = parse_info_batch(output);
+
+        let pad = &infos["AA:BB:CC:DD:EE:FF"];
+        assert_eq!(pad.name, "8BitDo Pro 2");
+        assert_eq!(pad.device_type, BluetoothDeviceType::Controller);
+        assert!(pad.paired && pad.trusted && !pad.connected);
+        assert_eq!(pad.rssi, Some(-62));
+
+        let headphones = &infos["11:22:33:44:55:66"];
+        assert_eq!(headphones.device_type, BluetoothDeviceType::Audio);
+        assert!(!headphones.paired);
+
+        let mut devices = vec![pad.clone(), headphones.clone()];
+        devices.push(parse_device_info("22:22:22:22:22:22", ""));
+        sort_by_rssi(&mut devices);
+        let order: Vec<_> = devices.iter().map(|d| d.rssi).collect();
+        assert_eq!(order, vec![Some(-40), Some(-62), None]);
+    }
+
+    #[test]
+    fn test_parse_device_list() {
+        assert_eq!(
+            parse_device_list(
+                "Device AA:BB:CC:DD:EE:FF Pro Controller\nDevice 11:22:33:44:55:66\n"
+            ),
+            vec![
+                (
+                    "AA:BB:CC:DD:EE:FF".to_string(),
+                    "Pro Controller".to_string()
+                ),
+                (
+                    "11:22:33:44:55:66".to_string(),
+                    "11:22:33:44:55:66".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_agent_line() {
+        assert_eq!(
+            parse_agent_line("\u{1b}[0;93m[agent]\u{1b}[0m Confirm passkey 012345 (yes/no): "),
+            Some(AgentEvent::ConfirmPasskey(12345))
+        );
+        assert_eq!(
+            parse_agent_line("[agent] Passkey: 987654"),
+            Some(AgentEvent::DisplayPasskey(987654))
+        );
+        assert_eq!(
+            parse_agent_line("[agent] Enter PIN code: "),
+            Some(AgentEvent::PinRequested)
+        );
+        assert_eq!(
+            parse_agent_line(
+                "[agent] Authorize service 00001124-0000-1000-8000-00805f9b34fb (yes/no): "
+            ),
+            Some(AgentEvent::Authorize)
+        );
+        assert_eq!(
+            parse_agent_line("Pairing successful"),
+            Some(AgentEvent::Paired)
+        );
+        assert!(matches!(
+            parse_agent_line("Failed to pair: org.bluez.Error.AuthenticationFailed"),
+            Some(AgentEvent::Failed(_))
+        ));
+        assert_eq!(
+            parse_agent_line("[CHG] Device AA:BB:CC:DD:EE:FF Connected: yes"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_confirm_pairing_shared_with_confirmer() {
+        let manager = BluetoothManager::new("hci0".to_string())
+            .unwrap()
+            .with_agent(AgentCapability::DisplayYesNo);
+        let confirmer = manager.confirmer();
+        assert_eq!(confirmer.agent(), AgentCapability::DisplayYesNo);
+
+        confirmer.confirm_pairing("aa:bb:cc:dd:ee:ff");
+        assert!(manager.take_confirmation("AA:BB:CC:DD:EE:FF"));
+        assert!(!manager.take_confirmation("AA:BB:CC:DD:EE:FF"));
+    }
 }
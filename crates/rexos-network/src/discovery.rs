@@ -0,0 +1,261 @@
+//! Live Bluetooth discovery
+//!
+//! [`BluetoothManager::list_devices`] only sees what a scan has found so
+//! far. A discovery keeps one bluetoothctl session scanning and reads its
+//! `[NEW]` and `[CHG]` lines, so devices show up in the UI as they are
+//! found instead of after the scan ends.
+
+use crate::bluetooth::{parse_class, parse_rssi, sort_by_rssi, strip_ansi};
+use crate::{BluetoothDevice, BluetoothDeviceType, BluetoothManager, NetworkError};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A change reported by a scanning bluetoothctl session
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum DiscoveryLine {
+    New { address: String, name: String },
+    Name { address: String, name: String },
+    Rssi { address: String, rssi: i32 },
+    Class { address: String, class: u32 },
+}
+
+impl BluetoothManager {
+    /// Scan for `duration`, yielding devices as they are found
+    ///
+    /// The scan stops when the discovery ends or is dropped.
+    pub fn discover(&self, duration: Duration) -> Result<DeviceDiscovery, NetworkError> {
+        if !self.is_available() {
+            return Err(NetworkError::BluetoothNotAvailable);
+        }
+
+        let mut child = Command::new("bluetoothctl")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| NetworkError::CommandFailed("bluetoothctl stdin".to_string()))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| NetworkError::CommandFailed("bluetoothctl stdout".to_string()))?;
+
+        let (line_tx, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if line_tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut discovery = DeviceDiscovery {
+            child,
+            stdin,
+            lines,
+            deadline: Instant::now() + duration,
+            devices: HashMap::new(),
+        };
+        discovery.send("scan on")?;
+        tracing::debug!("Bluetooth discovery started");
+        Ok(discovery)
+    }
+}
+
+/// Devices found by a running scan
+///
+/// Iterating yields a device when it is first seen and again whenever its
+/// name, type or signal strength changes, so callers should update their
+/// list by address. Iteration ends when the scan duration has passed.
+pub struct DeviceDiscovery {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+    deadline: Instant,
+    devices: HashMap<String, BluetoothDevice>,
+}
+
+impl DeviceDiscovery {
+    /// Devices found so far, strongest signal first
+    pub fn devices(&self) -> Vec<BluetoothDevice> {
+        let mut devices: Vec<_> = self.devices.values().cloned().collect();
+        sort_by_rssi(&mut devices);
+        devices
+    }
+
+    /// Scan until the duration has passed and return every device found
+    pub fn finish(mut self) -> Vec<BluetoothDevice> {
+        for _ in self.by_ref() {}
+        self.devices()
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), NetworkError> {
+        writeln!(self.stdin, "{}", command)?;
+        self.stdin.flush()?;
+        Ok(())
+    }
+
+    /// Apply a change, returning the updated device
+    fn apply(&mut self, change: DiscoveryLine) -> Option<BluetoothDevice> {
+        let device = match change {
+            DiscoveryLine::New { address, name } => self
+                .devices
+                .entry(address.clone())
+                .or_insert_with(|| BluetoothDevice {
+                    address,
+                    name,
+                    device_type: BluetoothDeviceType::Unknown,
+                    paired: false,
+                    connected: false,
+                    trusted: false,
+                    rssi: None,
+                }),
+            DiscoveryLine::Name { address, name } => {
+                let device = self.devices.get_mut(&address)?;
+                device.name = name;
+                device
+            }
+            DiscoveryLine::Rssi { address, rssi } => {
+                let device = self.devices.get_mut(&address)?;
+                device.rssi = Some(rssi);
+                device
+            }
+            DiscoveryLine::Class { address, class } => {
+                let device = self.devices.get_mut(&address)?;
+                device.device_type = BluetoothDeviceType::from_class(class);
+                device
+            }
+        };
+        Some(device.clone())
+    }
+}
+
+impl Iterator for DeviceDiscovery {
+    type Item = BluetoothDevice;
+
+    fn next(&mut self) -> Option<BluetoothDevice> {
+        loop {
+            let timeout = self.deadline.saturating_duration_since(Instant::now());
+            if timeout.is_zero() {
+                return None;
+            }
+
+            let line = match self.lines.recv_timeout(timeout) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => return None,
+            };
+
+            // Avoid if-let chains for MSRV 1.85 compatibility
+            #[allow(clippy::collapsible_if)]
+            if let Some(change) = parse_discovery_line(&line) {
+                if let Some(device) = self.apply(change) {
+                    return Some(device);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for DeviceDiscovery {
+    fn drop(&mut self) {
+        let _ = self.send("scan off");
+        let _ = self.send("quit");
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        tracing::debug!("Bluetooth discovery stopped");
+    }
+}
+
+/// Parse `[NEW] Device ...` and `[CHG] Device ...` lines
+fn parse_discovery_line(line: &str) -> Option<DiscoveryLine> {
+    const NEW: &str = "[NEW] Device ";
+    const CHG: &str = "[CHG] Device ";
+
+    // Interactive mode may put a prompt before the marker
+    let line = strip_ansi(line);
+    let (new, rest) = if let Some(start) = line.find(NEW) {
+        (true, &line[start + NEW.len()..])
+    } else if let Some(start) = line.find(CHG) {
+        (false, &line[start + CHG.len()..])
+    } else {
+        return None;
+    };
+
+    let (address, detail) = rest.split_once(' ').unwrap_or((rest, ""));
+    let address = address.to_string();
+    let detail = detail.trim();
+
+    if new {
+        let name = if detail.is_empty() {
+            address.clone()
+        } else {
+            detail.to_string()
+        };
+        return Some(DiscoveryLine::New { address, name });
+    }
+
+    if let Some(value) = detail.strip_prefix("RSSI:") {
+        parse_rssi(value).map(|rssi| DiscoveryLine::Rssi { address, rssi })
+    } else if let Some(value) = detail.strip_prefix("Class:") {
+        parse_class(value).map(|class| DiscoveryLine::Class { address, class })
+    } else {
+        detail
+            .strip_prefix("Name:")
+            .or_else(|| detail.strip_prefix("Alias:"))
+            .map(|value| DiscoveryLine::Name {
+                address,
+                name: value.trim().to_string(),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_discovery_line() {
+        assert_eq!(
+            parse_discovery_line(
+                "[\u{1b}[0;92mNEW\u{1b}[0m] Device AA:BB:CC:DD:EE:FF 8BitDo Pro 2"
+            ),
+            Some(DiscoveryLine::New {
+                address: "AA:BB:CC:DD:EE:FF".to_string(),
+                name: "8BitDo Pro 2".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_discovery_line("[CHG] Device AA:BB:CC:DD:EE:FF RSSI: 0xffffffc2 (-62)"),
+            Some(DiscoveryLine::Rssi {
+                address: "AA:BB:CC:DD:EE:FF".to_string(),
+                rssi: -62,
+            })
+        );
+        assert_eq!(
+            parse_discovery_line("[bluetooth]# [CHG] Device AA:BB:CC:DD:EE:FF RSSI: -70"),
+            Some(DiscoveryLine::Rssi {
+                address: "AA:BB:CC:DD:EE:FF".to_string(),
+                rssi: -70,
+            })
+        );
+        assert_eq!(
+            parse_discovery_line("[CHG] Device AA:BB:CC:DD:EE:FF Class: 0x00002508"),
+            Some(DiscoveryLine::Class {
+                address: "AA:BB:CC:DD:EE:FF".to_string(),
+                class: 0x2508,
+            })
+        );
+        assert_eq!(
+            parse_discovery_line("[CHG] Device AA:BB:CC:DD:EE:FF ManufacturerData Key: 0x0006"),
+            None
+        );
+        assert_eq!(parse_discovery_line("Discovery started"), None);
+    }
+}
@@ -4,15 +4,31 @@ use crate::NetworkError;
 use std::fs;
 use std::process::Command;
 
+/// 5GHz channels usable everywhere (UNII-1, no radar detection needed)
+const CHANNELS_5GHZ_LOW: [u8; 4] = [36, 40, 44, 48];
+
+/// 5GHz UNII-3 channels, allowed without radar detection in some regions
+const CHANNELS_5GHZ_HIGH: [u8; 5] = [149, 153, 157, 161, 165];
+
+/// Regions where the UNII-3 channels are open to access points
+const UNII3_REGIONS: [&str; 6] = ["US", "CA", "AU", "NZ", "CN", "IN"];
+
+/// Regions limited to 2.4GHz channels 1-11
+const ELEVEN_CHANNEL_REGIONS: [&str; 2] = ["US", "CA"];
+
 /// Hotspot configuration
 #[derive(Debug, Clone)]
 pub struct HotspotConfig {
     /// Network name (SSID)
     pub ssid: String,
-    /// Password (minimum 8 characters)
+    /// WPA2 passphrase (8-63 characters)
     pub password: String,
-    /// Channel (1-11 for 2.4GHz)
+    /// Channel (1-13 for 2.4GHz, 36-48 or 149-165 for 5GHz)
     pub channel: u8,
+    /// Two-letter regulatory country code (e.g. "US", "DE")
+    ///
+    /// Without one, only channels allowed everywhere are accepted.
+    pub country_code: Option<String>,
     /// Hide SSID
     pub hidden: bool,
     /// IP address for AP
@@ -29,6 +45,7 @@ impl Default for HotspotConfig {
             ssid: "RexOS".to_string(),
             password: "rexos123".to_string(),
             channel: 6,
+            country_code: None,
             hidden: false,
             ip_address: "192.168.4.1".to_string(),
             dhcp_start: "192.168.4.2".to_string(),
@@ -37,6 +54,82 @@ impl Default for HotspotConfig {
     }
 }
 
+impl HotspotConfig {
+    /// Check the config can be used by hostapd
+    pub fn validate(&self) -> Result<(), NetworkError> {
+        let invalid = |msg: String| Err(NetworkError::ConnectionFailed(msg));
+
+        if self.ssid.is_empty() || self.ssid.len() > 32 {
+            return invalid(format!(
+                "Hotspot SSID must be 1-32 bytes, got {}",
+                self.ssid.len()
+            ));
+        }
+
+        // hostapd reads a 64 character value as a raw hex key
+        let chars = self.password.chars().count();
+        if !(8..=63).contains(&chars) {
+            return invalid(format!(
+                "Hotspot passphrase must be 8-63 characters, got {}",
+                chars
+            ));
+        }
+        if !self.password.chars().all(|c| (' '..='~').contains(&c)) {
+            return invalid("Hotspot passphrase must be printable ASCII".to_string());
+        }
+
+        // Avoid if-let chains for MSRV 1.85 compatibility
+        #[allow(clippy::collapsible_if)]
+        if let Some(country) = &self.country_code {
+            if country.len() != 2 || !country.chars().all(|c| c.is_ascii_alphabetic()) {
+                return invalid(format!("Invalid country code: {}", country));
+            }
+        }
+
+        let channels = Self::channels_for(self.country_code.as_deref());
+        if !channels.contains(&self.channel) {
+            return invalid(format!(
+                "Channel {} isn't allowed{}; use one of {:?}",
+                self.channel,
+                self.country_code
+                    .as_deref()
+                    .map(|c| format!(" in {}", c.to_uppercase()))
+                    .unwrap_or_default(),
+                channels
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Channels an access point may use in a region
+    ///
+    /// Radar (DFS) channels are left out since hostapd would have to
+    /// listen for radar before it could start. Without a country only
+    /// channels allowed everywhere are returned.
+    pub fn channels_for(country_code: Option<&str>) -> Vec<u8> {
+        let country = country_code.map(str::to_uppercase);
+        let country = country.as_deref();
+
+        let last_2ghz = match country {
+            Some(c) if !ELEVEN_CHANNEL_REGIONS.contains(&c) => 13,
+            _ => 11,
+        };
+
+        let mut channels: Vec<u8> = (1..=last_2ghz).collect();
+        channels.extend(CHANNELS_5GHZ_LOW);
+        if country.is_some_and(|c| UNII3_REGIONS.contains(&c)) {
+            channels.extend(CHANNELS_5GHZ_HIGH);
+        }
+        channels
+    }
+
+    /// Whether the channel is in the 5GHz band
+    pub fn is_5ghz(&self) -> bool {
+        self.channel > 14
+    }
+}
+
 /// Manages WiFi hotspot mode
 pub struct HotspotManager {
     interface: String,
@@ -59,13 +152,24 @@ impl HotspotManager {
         self.config = config;
     }
 
+    /// Current hotspot settings
+    pub fn config(&self) -> &HotspotConfig {
+        &self.config
+    }
+
     /// Start the hotspot
     pub fn start(&mut self) -> Result<(), NetworkError> {
         if self.running {
             return Ok(());
         }
 
-        tracing::info!("Starting WiFi hotspot: {}", self.config.ssid);
+        self.config.validate()?;
+
+        tracing::info!(
+            "Starting WiFi hotspot: {} (channel {})",
+            self.config.ssid,
+            self.config.channel
+        );
 
         // Check if hostapd and dnsmasq are available
         if !Self::is_hostapd_available() {
@@ -188,11 +292,17 @@ impl HotspotManager {
 
     /// Write hostapd configuration
     fn write_hostapd_config(&self) -> Result<(), NetworkError> {
-        let config = format!(
+        fs::write("/tmp/hostapd.conf", self.hostapd_config())?;
+        Ok(())
+    }
+
+    /// hostapd configuration for the current settings
+    fn hostapd_config(&self) -> String {
+        let mut config = format!(
             r#"interface={}
 driver=nl80211
 ssid={}
-hw_mode=g
+hw_mode={}
 channel={}
 wmm_enabled=0
 macaddr_acl=0
@@ -206,13 +316,20 @@ rsn_pairwise=CCMP
 "#,
             self.interface,
             self.config.ssid,
+            if self.config.is_5ghz() { "a" } else { "g" },
             self.config.channel,
             if self.config.hidden { 1 } else { 0 },
             self.config.password,
         );
 
-        fs::write("/tmp/hostapd.conf", config)?;
-        Ok(())
+        if let Some(country) = &self.config.country_code {
+            config.push_str(&format!(
+                "country_code={}\nieee80211d=1\n",
+                country.to_uppercase()
+            ));
+        }
+
+        config
     }
 
     /// Write dnsmasq configuration
@@ -340,6 +457,75 @@ mod tests {
         assert!(!config.hidden);
     }
 
+    #[test]
+    fn test_hotspot_config_validate() {
+        assert!(HotspotConfig::default().validate().is_ok());
+
+        let with = |f: fn(&mut HotspotConfig)| {
+            let mut config = HotspotConfig::default();
+            f(&mut config);
+            config.validate()
+        };
+        assert!(with(|c| c.password = "short".to_string()).is_err());
+        assert!(with(|c| c.password = "x".repeat(64)).is_err());
+        assert!(with(|c| c.password = "x".repeat(63)).is_ok());
+        assert!(with(|c| c.password = "pässwörd".to_string()).is_err());
+        assert!(with(|c| c.ssid = String::new()).is_err());
+        assert!(with(|c| c.ssid = "x".repeat(33)).is_err());
+
+        // Channels depend on the region
+        assert!(with(|c| c.channel = 13).is_err());
+        assert!(
+            with(|c| {
+                c.channel = 13;
+                c.country_code = Some("de".to_string());
+            })
+            .is_ok()
+        );
+        assert!(
+            with(|c| {
+                c.channel = 13;
+                c.country_code = Some("US".to_string());
+            })
+            .is_err()
+        );
+        assert!(with(|c| c.channel = 36).is_ok());
+        assert!(with(|c| c.channel = 149).is_err());
+        assert!(
+            with(|c| {
+                c.channel = 149;
+                c.country_code = Some("US".to_string());
+            })
+            .is_ok()
+        );
+        // Radar channels are never offered
+        assert!(
+            with(|c| {
+                c.channel = 52;
+                c.country_code = Some("US".to_string());
+            })
+            .is_err()
+        );
+        assert!(with(|c| c.country_code = Some("USA".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_hostapd_config_band() {
+        let mut manager = HotspotManager::new("wlan0".to_string());
+        let config = manager.hostapd_config();
+        assert!(config.contains("hw_mode=g\nchannel=6\n"));
+        assert!(!config.contains("country_code"));
+
+        manager.configure(HotspotConfig {
+            channel: 36,
+            country_code: Some("de".to_string()),
+            ..HotspotConfig::default()
+        });
+        let config = manager.hostapd_config();
+        assert!(config.contains("hw_mode=a\nchannel=36\n"));
+        assert!(config.contains("country_code=DE\n"));
+    }
+
     #[test]
     fn test_hotspot_manager_creation() {
         let manager = HotspotManager::new("wlan0".to_string());
@@ -11,16 +11,41 @@
 //! - Saved network management
 //! - Bluetooth device discovery and pairing
 //! - Bluetooth audio (A2DP) for wireless controllers
+//! - Automatic reconnection of trusted controllers
+//! - Clock synchronization (SNTP) and timezone setup
+//! - SSH authorized key provisioning
+//! - Background scan/connect/pair with progress events for the UI
 
+mod audio;
 mod bluetooth;
+mod discovery;
 mod hotspot;
+mod monitor;
+mod reconnect;
+mod ssh;
+mod time;
 mod wifi;
+mod worker;
 
-pub use bluetooth::{BluetoothDevice, BluetoothDeviceType, BluetoothManager, PairingState};
+pub use bluetooth::{
+    AgentCapability, BluetoothDevice, BluetoothDeviceType, BluetoothManager, PAIRING_TIMEOUT,
+    PairingState,
+};
+pub use discovery::DeviceDiscovery;
 pub use hotspot::{HotspotConfig, HotspotManager};
-pub use wifi::{ConnectionState, WifiManager, WifiNetwork, WifiSecurity, WifiStatus};
+pub use monitor::DEFAULT_STATUS_INTERVAL;
+pub use reconnect::{BluetoothReconnector, DEFAULT_RECONNECT_FAILURES, DEFAULT_RECONNECT_INTERVAL};
+pub use ssh::{SshConfig, validate_public_key};
+pub use time::{NtpTimestamp, SntpResponse, TimeSync, clock_is_unset};
+pub use wifi::{
+    ConnectionState, DEFAULT_CONNECT_ATTEMPTS, DEFAULT_RETRY_BACKOFF, DEFAULT_SCAN_CACHE,
+    DEFAULT_SCAN_WAIT, SavedNetwork, WifiManager, WifiNetwork, WifiSecurity, WifiStatus,
+};
+pub use worker::{NetworkBackend, NetworkEvent, NetworkRequest, NetworkWorker};
 
 use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -52,6 +77,12 @@ pub enum NetworkError {
     #[error("Command failed: {0}")]
     CommandFailed(String),
 
+    #[error("No audio server running")]
+    AudioServerUnavailable,
+
+    #[error("Invalid SSH key: {0}")]
+    InvalidKey(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
@@ -71,6 +102,15 @@ pub struct NetworkConfig {
     /// Bluetooth interface (hci0, etc.)
     pub bt_interface: String,
 
+    /// IO capability of the Bluetooth pairing agent
+    pub bt_agent: AgentCapability,
+
+    /// How often dropped Bluetooth controllers are retried
+    pub bt_reconnect_interval: Duration,
+
+    /// Failed reconnects before a controller is left alone until wake
+    pub bt_reconnect_failures: u32,
+
     /// Enable WiFi power saving
     pub wifi_power_save: bool,
 
@@ -79,6 +119,21 @@ pub struct NetworkConfig {
 
     /// Scan interval in seconds
     pub scan_interval: u32,
+
+    /// Time given to wpa_supplicant to collect scan results
+    pub scan_wait: Duration,
+
+    /// How long scan results are reused before scanning again
+    pub scan_cache: Duration,
+
+    /// WiFi connection attempts before giving up
+    pub connect_attempts: u32,
+
+    /// Wait before the first connection retry, doubled for each one after
+    pub retry_backoff: Duration,
+
+    /// How often connection status is polled for events
+    pub status_interval: Duration,
 }
 
 impl Default for NetworkConfig {
@@ -88,9 +143,17 @@ impl Default for NetworkConfig {
             wpa_config: PathBuf::from("/etc/wpa_supplicant/wpa_supplicant.conf"),
             wifi_interface: "wlan0".to_string(),
             bt_interface: "hci0".to_string(),
+            bt_agent: AgentCapability::default(),
+            bt_reconnect_interval: reconnect::DEFAULT_RECONNECT_INTERVAL,
+            bt_reconnect_failures: reconnect::DEFAULT_RECONNECT_FAILURES,
             wifi_power_save: false, // Keep responsive for gaming
             auto_reconnect: true,
             scan_interval: 30,
+            scan_wait: wifi::DEFAULT_SCAN_WAIT,
+            scan_cache: wifi::DEFAULT_SCAN_CACHE,
+            connect_attempts: wifi::DEFAULT_CONNECT_ATTEMPTS,
+            retry_backoff: wifi::DEFAULT_RETRY_BACKOFF,
+            status_interval: monitor::DEFAULT_STATUS_INTERVAL,
         }
     }
 }
@@ -100,28 +163,64 @@ pub struct NetworkManager {
     wifi: WifiManager,
     bluetooth: BluetoothManager,
     hotspot: HotspotManager,
+    reconnector: BluetoothReconnector,
+    config: NetworkConfig,
 }
 
 impl NetworkManager {
     /// Create a new network manager
     pub fn new(config: NetworkConfig) -> Result<Self, NetworkError> {
-        let wifi = WifiManager::new(
-            config.wifi_interface.clone(),
-            config.wpa_socket.clone(),
-            config.wpa_config.clone(),
-        )?;
-
-        let bluetooth = BluetoothManager::new(config.bt_interface.clone())?;
+        let wifi = Self::wifi_manager(&config)?;
+        let bluetooth = Self::bluetooth_manager(&config)?;
 
         let hotspot = HotspotManager::new(config.wifi_interface.clone());
 
+        let reconnector = BluetoothReconnector::new(Self::bluetooth_manager(&config)?)
+            .with_interval(config.bt_reconnect_interval)
+            .with_max_failures(config.bt_reconnect_failures);
+
         Ok(Self {
             wifi,
             bluetooth,
             hotspot,
+            reconnector,
+            config,
         })
     }
 
+    fn wifi_manager(config: &NetworkConfig) -> Result<WifiManager, NetworkError> {
+        Ok(WifiManager::new(
+            config.wifi_interface.clone(),
+            config.wpa_socket.clone(),
+            config.wpa_config.clone(),
+        )?
+        .with_scan_wait(config.scan_wait)
+        .with_scan_cache(config.scan_cache)
+        .with_connect_retries(config.connect_attempts, config.retry_backoff))
+    }
+
+    fn bluetooth_manager(config: &NetworkConfig) -> Result<BluetoothManager, NetworkError> {
+        Ok(BluetoothManager::new(config.bt_interface.clone())?.with_agent(config.bt_agent))
+    }
+
+    /// Receive WiFi, IP address and Bluetooth connection changes
+    ///
+    /// Status is polled on a background thread every
+    /// [`NetworkConfig::status_interval`] and only changes are sent, so the
+    /// UI can drain the receiver with `try_recv` on each tick. Connections
+    /// already up are reported first. The thread stops once the receiver
+    /// is dropped and a change comes in.
+    pub fn connection_events(&self) -> Result<Receiver<NetworkEvent>, NetworkError> {
+        let (tx, rx) = mpsc::channel();
+        monitor::spawn(
+            Self::wifi_manager(&self.config)?,
+            Self::bluetooth_manager(&self.config)?,
+            self.config.status_interval,
+            tx,
+        );
+        Ok(rx)
+    }
+
     /// Get WiFi manager
     pub fn wifi(&mut self) -> &mut WifiManager {
         &mut self.wifi
@@ -137,6 +236,18 @@ impl NetworkManager {
         &mut self.hotspot
     }
 
+    /// Keep trusted Bluetooth controllers connected in the background
+    pub fn start_bluetooth_reconnect(&mut self) {
+        if self.bluetooth.is_available() {
+            self.reconnector.start();
+        }
+    }
+
+    /// Retry controllers at once after the system wakes from sleep
+    pub fn bluetooth_wake(&self) {
+        self.reconnector.wake();
+    }
+
     /// Check if WiFi is available
     pub fn wifi_available(&self) -> bool {
         self.wifi.is_available()
@@ -0,0 +1,197 @@
+//! Connection status changes for the UI
+//!
+//! wpa_supplicant and BlueZ don't push state changes to us, so a thread
+//! polls WiFi status and connected Bluetooth devices and sends a
+//! [`NetworkEvent`] only for what changed since the last poll.
+
+use crate::{BluetoothManager, ConnectionState, NetworkEvent, WifiManager, WifiStatus};
+use std::collections::BTreeSet;
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::Duration;
+
+/// How often connection status is polled
+pub const DEFAULT_STATUS_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Connection status at one poll
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ConnectionSnapshot {
+    wifi_state: ConnectionState,
+    ssid: Option<String>,
+    ip_address: Option<String>,
+    bluetooth: BTreeSet<String>,
+}
+
+impl Default for ConnectionSnapshot {
+    fn default() -> Self {
+        Self {
+            wifi_state: ConnectionState::Disconnected,
+            ssid: None,
+            ip_address: None,
+            bluetooth: BTreeSet::new(),
+        }
+    }
+}
+
+impl ConnectionSnapshot {
+    fn new(wifi: Option<WifiStatus>, bluetooth: BTreeSet<String>) -> Self {
+        match wifi {
+            Some(status) => Self {
+                wifi_state: status.state,
+                ssid: status.ssid,
+                ip_address: status.ip_address,
+                bluetooth,
+            },
+            None => Self {
+                bluetooth,
+                ..Self::default()
+            },
+        }
+    }
+
+    /// Events that lead from `self` to `next`
+    fn changes(&self, next: &Self) -> Vec<NetworkEvent> {
+        let mut events = Vec::new();
+
+        if self.wifi_state != next.wifi_state || self.ssid != next.ssid {
+            events.push(NetworkEvent::WifiStateChanged {
+                // Report which network dropped, not the empty current one
+                ssid: next.ssid.clone().or_else(|| self.ssid.clone()),
+                state: next.wifi_state,
+            });
+        }
+
+        if self.ip_address != next.ip_address {
+            events.push(match &next.ip_address {
+                Some(ip) => NetworkEvent::IpAcquired(ip.clone()),
+                None => NetworkEvent::IpLost,
+            });
+        }
+
+        for address in next.bluetooth.difference(&self.bluetooth) {
+            events.push(NetworkEvent::BluetoothConnected(address.clone()));
+        }
+        for address in self.bluetooth.difference(&next.bluetooth) {
+            events.push(NetworkEvent::BluetoothDisconnected(address.clone()));
+        }
+
+        events
+    }
+}
+
+/// Poll connection status until the receiver is gone
+///
+/// The first poll is compared against an all-disconnected state, so the
+/// receiver learns about connections that were up before it subscribed.
+pub(crate) fn spawn(
+    wifi: WifiManager,
+    bluetooth: BluetoothManager,
+    interval: Duration,
+    events: Sender<NetworkEvent>,
+) {
+    thread::Builder::new()
+        .name("rexos-net-status".to_string())
+        .spawn(move || {
+            let mut last = ConnectionSnapshot::default();
+            loop {
+                let next = poll(&wifi, &bluetooth);
+                for event in last.changes(&next) {
+                    if events.send(event).is_err() {
+                        return;
+                    }
+                }
+                last = next;
+                thread::sleep(interval);
+            }
+        })
+        .expect("failed to spawn network status thread");
+}
+
+fn poll(wifi: &WifiManager, bluetooth: &BluetoothManager) -> ConnectionSnapshot {
+    let wifi_status = if wifi.is_available() {
+        wifi.status().ok()
+    } else {
+        None
+    };
+
+    let connected = if bluetooth.is_available() {
+        bluetooth
+            .list_paired_devices()
+            .map(|devices| {
+                devices
+                    .into_iter()
+                    .filter(|d| d.connected)
+                    .map(|d| d.address)
+                    .collect()
+            })
+            .unwrap_or_default()
+    } else {
+        BTreeSet::new()
+    };
+
+    ConnectionSnapshot::new(wifi_status, connected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(
+        state: ConnectionState,
+        ip: Option<&str>,
+        bluetooth: &[&str],
+    ) -> ConnectionSnapshot {
+        ConnectionSnapshot {
+            wifi_state: state,
+            ssid: (state != ConnectionState::Disconnected).then(|| "Home".to_string()),
+            ip_address: ip.map(str::to_string),
+            bluetooth: bluetooth.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_no_change_no_events() {
+        let connected = snapshot(ConnectionState::Connected, Some("192.168.1.20"), &["AA"]);
+        assert!(connected.changes(&connected.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_connect_then_drop() {
+        let idle = ConnectionSnapshot::default();
+        let associating = snapshot(ConnectionState::Connecting, None, &[]);
+        let connected = snapshot(ConnectionState::Connected, Some("192.168.1.20"), &["AA"]);
+
+        let events = idle.changes(&associating);
+        assert!(matches!(
+            events.as_slice(),
+            [NetworkEvent::WifiStateChanged {
+                state: ConnectionState::Connecting,
+                ..
+            }]
+        ));
+
+        let events = associating.changes(&connected);
+        assert_eq!(events.len(), 3);
+        assert!(matches!(
+            events[0],
+            NetworkEvent::WifiStateChanged {
+                state: ConnectionState::Connected,
+                ..
+            }
+        ));
+        assert!(matches!(&events[1], NetworkEvent::IpAcquired(ip) if ip == "192.168.1.20"));
+        assert!(matches!(&events[2], NetworkEvent::BluetoothConnected(a) if a == "AA"));
+
+        // A drop names the network that was lost
+        let events = connected.changes(&idle);
+        assert!(matches!(
+            &events[0],
+            NetworkEvent::WifiStateChanged {
+                ssid: Some(ssid),
+                state: ConnectionState::Disconnected,
+            } if ssid == "Home"
+        ));
+        assert!(matches!(events[1], NetworkEvent::IpLost));
+        assert!(matches!(&events[2], NetworkEvent::BluetoothDisconnected(a) if a == "AA"));
+    }
+}
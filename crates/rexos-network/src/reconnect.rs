@@ -0,0 +1,254 @@
+//! Bluetooth controller auto-reconnect
+//!
+//! Controllers don't always come back on their own after the handheld
+//! sleeps, or after they drop out of range. The reconnector retries
+//! trusted controllers on its own thread, giving up on a device after a
+//! few failures so a controller that's switched off isn't polled forever.
+//! A wake event clears the failures and retries at once.
+
+use crate::{BluetoothDevice, BluetoothDeviceType, BluetoothManager, NetworkError};
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration;
+
+/// How often dropped controllers are retried
+pub const DEFAULT_RECONNECT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Failed attempts before a controller is left alone until the next wake
+pub const DEFAULT_RECONNECT_FAILURES: u32 = 5;
+
+impl BluetoothManager {
+    /// Connect trusted, paired controllers that aren't connected
+    ///
+    /// Returns the addresses that connected. A controller that fails is
+    /// logged and skipped so the rest still get a chance.
+    pub fn reconnect_trusted(&self) -> Result<Vec<String>, NetworkError> {
+        let mut connected = Vec::new();
+        for address in self.reconnect_candidates()? {
+            match self.connect(&address) {
+                Ok(()) => connected.push(address),
+                Err(e) => tracing::debug!("Reconnecting {} failed: {}", address, e),
+            }
+        }
+        Ok(connected)
+    }
+
+    /// Trusted, paired controllers that aren't connected
+    fn reconnect_candidates(&self) -> Result<Vec<String>, NetworkError> {
+        let paired = self.list_paired_devices()?;
+        let connected = self.get_connected_controllers()?;
+        Ok(reconnect_candidates(&paired, &connected))
+    }
+}
+
+/// Controllers in `paired` worth reconnecting, skipping `connected` ones
+fn reconnect_candidates(paired: &[BluetoothDevice], connected: &[BluetoothDevice]) -> Vec<String> {
+    let connected: HashSet<&str> = connected.iter().map(|d| d.address.as_str()).collect();
+    paired
+        .iter()
+        .filter(|d| d.paired && d.trusted && d.device_type == BluetoothDeviceType::Controller)
+        .filter(|d| !connected.contains(d.address.as_str()))
+        .map(|d| d.address.clone())
+        .collect()
+}
+
+/// Failed reconnect attempts per device
+#[derive(Debug)]
+struct FailureTracker {
+    failures: HashMap<String, u32>,
+    max_failures: u32,
+}
+
+impl FailureTracker {
+    fn new(max_failures: u32) -> Self {
+        Self {
+            failures: HashMap::new(),
+            max_failures,
+        }
+    }
+
+    /// Whether the device is still worth trying
+    fn should_try(&self, address: &str) -> bool {
+        self.failures.get(address).copied().unwrap_or(0) < self.max_failures
+    }
+
+    fn record(&mut self, address: &str, connected: bool) {
+        if connected {
+            self.failures.remove(address);
+        } else {
+            let failures = self.failures.entry(address.to_string()).or_insert(0);
+            *failures += 1;
+            if *failures == self.max_failures {
+                tracing::info!(
+                    "Giving up reconnecting {} until the next wake after {} failures",
+                    address,
+                    failures
+                );
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.failures.clear();
+    }
+}
+
+enum ReconnectCommand {
+    /// The system woke up: forget failures and retry now
+    Wake,
+}
+
+/// Retries trusted controllers on a background thread
+///
+/// The thread stops when the reconnector is dropped.
+pub struct BluetoothReconnector {
+    bluetooth: Option<BluetoothManager>,
+    interval: Duration,
+    max_failures: u32,
+    commands: Option<Sender<ReconnectCommand>>,
+}
+
+impl BluetoothReconnector {
+    pub fn new(bluetooth: BluetoothManager) -> Self {
+        Self {
+            bluetooth: Some(bluetooth),
+            interval: DEFAULT_RECONNECT_INTERVAL,
+            max_failures: DEFAULT_RECONNECT_FAILURES,
+            commands: None,
+        }
+    }
+
+    /// Set how often dropped controllers are retried
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Set how many failed attempts are made before waiting for a wake
+    pub fn with_max_failures(mut self, max_failures: u32) -> Self {
+        self.max_failures = max_failures;
+        self
+    }
+
+    /// Start retrying in the background
+    pub fn start(&mut self) {
+        let Some(bluetooth) = self.bluetooth.take() else {
+            return;
+        };
+        let interval = self.interval;
+        let mut tracker = FailureTracker::new(self.max_failures);
+        let (tx, rx) = mpsc::channel();
+
+        thread::Builder::new()
+            .name("rexos-bt-reconnect".to_string())
+            .spawn(move || {
+                tracing::info!("Bluetooth reconnect started");
+                loop {
+                    reconnect_once(&bluetooth, &mut tracker);
+
+                    match rx.recv_timeout(interval) {
+                        Ok(ReconnectCommand::Wake) => tracker.reset(),
+                        Err(RecvTimeoutError::Timeout) => {}
+                        // Reconnector dropped
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                }
+            })
+            .expect("failed to spawn bluetooth reconnect thread");
+
+        self.commands = Some(tx);
+    }
+
+    /// Whether the background thread has been started
+    pub fn is_running(&self) -> bool {
+        self.commands.is_some()
+    }
+
+    /// Tell the reconnector the system woke from sleep
+    ///
+    /// Devices that were given up on are retried straight away.
+    pub fn wake(&self) {
+        if let Some(commands) = &self.commands {
+            let _ = commands.send(ReconnectCommand::Wake);
+        }
+    }
+}
+
+/// Try each controller that hasn't failed too often
+fn reconnect_once(bluetooth: &BluetoothManager, tracker: &mut FailureTracker) {
+    if !bluetooth.is_available() || !bluetooth.is_powered() {
+        return;
+    }
+
+    let candidates = match bluetooth.reconnect_candidates() {
+        Ok(candidates) => candidates,
+        Err(e) => {
+            tracing::debug!("Listing Bluetooth devices failed: {}", e);
+            return;
+        }
+    };
+
+    for address in &candidates {
+        if !tracker.should_try(address) {
+            continue;
+        }
+        let connected = bluetooth.connect(address).is_ok();
+        if connected {
+            tracing::info!("Reconnected controller {}", address);
+        }
+        tracker.record(address, connected);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(address: &str, device_type: BluetoothDeviceType, trusted: bool) -> BluetoothDevice {
+        BluetoothDevice {
+            address: address.to_string(),
+            name: address.to_string(),
+            device_type,
+            paired: true,
+            connected: false,
+            trusted,
+            rssi: None,
+        }
+    }
+
+    #[test]
+    fn test_reconnect_candidates() {
+        let paired = vec![
+            device("AA:AA:AA:AA:AA:01", BluetoothDeviceType::Controller, true),
+            device("AA:AA:AA:AA:AA:02", BluetoothDeviceType::Controller, true),
+            device("AA:AA:AA:AA:AA:03", BluetoothDeviceType::Controller, false),
+            device("AA:AA:AA:AA:AA:04", BluetoothDeviceType::Audio, true),
+        ];
+        let connected = vec![paired[1].clone()];
+
+        assert_eq!(
+            reconnect_candidates(&paired, &connected),
+            vec!["AA:AA:AA:AA:AA:01".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_failure_tracker_gives_up_until_reset() {
+        let mut tracker = FailureTracker::new(2);
+        let address = "AA:AA:AA:AA:AA:01";
+
+        tracker.record(address, false);
+        assert!(tracker.should_try(address));
+        tracker.record(address, false);
+        assert!(!tracker.should_try(address));
+
+        tracker.reset();
+        assert!(tracker.should_try(address));
+
+        tracker.record(address, false);
+        tracker.record(address, true);
+        tracker.record(address, false);
+        assert!(tracker.should_try(address));
+    }
+}
@@ -0,0 +1,364 @@
+//! SSH key provisioning
+//!
+//! Installs authorized public keys and toggles password logins in
+//! `sshd_config`, so SSH can be set up on-device without editing files
+//! from a PC. Keys are checked before they're written.
+
+use crate::NetworkError;
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Key types sshd accepts in `authorized_keys`
+const KEY_TYPES: &[&str] = &[
+    "ssh-ed25519",
+    "ssh-rsa",
+    "ecdsa-sha2-nistp256",
+    "ecdsa-sha2-nistp384",
+    "ecdsa-sha2-nistp521",
+    "sk-ssh-ed25519@openssh.com",
+    "sk-ecdsa-sha2-nistp256@openssh.com",
+];
+
+/// SSH server key and login settings
+#[derive(Debug, Clone)]
+pub struct SshConfig {
+    authorized_keys: PathBuf,
+    sshd_config: PathBuf,
+    host_key: PathBuf,
+}
+
+impl Default for SshConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SshConfig {
+    /// Settings at the standard OpenSSH locations for root
+    pub fn new() -> Self {
+        Self {
+            authorized_keys: PathBuf::from("/root/.ssh/authorized_keys"),
+            sshd_config: PathBuf::from("/etc/ssh/sshd_config"),
+            host_key: PathBuf::from("/etc/ssh/ssh_host_ed25519_key.pub"),
+        }
+    }
+
+    /// Use a different authorized_keys file
+    pub fn with_authorized_keys(mut self, path: impl Into<PathBuf>) -> Self {
+        self.authorized_keys = path.into();
+        self
+    }
+
+    /// Use a different sshd_config file
+    pub fn with_sshd_config(mut self, path: impl Into<PathBuf>) -> Self {
+        self.sshd_config = path.into();
+        self
+    }
+
+    /// Use a different public host key
+    pub fn with_host_key(mut self, path: impl Into<PathBuf>) -> Self {
+        self.host_key = path.into();
+        self
+    }
+
+    /// Installed authorized keys
+    pub fn authorized_keys(&self) -> Result<Vec<String>, NetworkError> {
+        match fs::read_to_string(&self.authorized_keys) {
+            Ok(contents) => Ok(contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(String::from)
+                .collect()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Authorize a public key
+    ///
+    /// Returns `false` if the key was already installed. The `.ssh`
+    /// directory and file are created with the modes sshd requires.
+    pub fn add_authorized_key(&self, key: &str) -> Result<bool, NetworkError> {
+        let key = key.trim();
+        validate_public_key(key)?;
+
+        // Compare type and key data only; the comment may differ
+        fn fields(line: &str) -> Vec<&str> {
+            line.split_whitespace().take(2).collect()
+        }
+        if self
+            .authorized_keys()?
+            .iter()
+            .any(|existing| fields(existing) == fields(key))
+        {
+            return Ok(false);
+        }
+
+        if let Some(dir) = self.authorized_keys.parent() {
+            fs::create_dir_all(dir)?;
+            fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+        }
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .mode(0o600)
+            .open(&self.authorized_keys)?;
+        fs::set_permissions(&self.authorized_keys, fs::Permissions::from_mode(0o600))?;
+        writeln!(file, "{}", key)?;
+
+        tracing::info!("Authorized SSH key: {}", key_summary(key));
+        Ok(true)
+    }
+
+    /// Authorize every valid key in a file (e.g. one left on the ROMs card)
+    ///
+    /// Invalid lines are skipped. Returns the number of keys added.
+    pub fn import_authorized_keys(&self, path: &Path) -> Result<usize, NetworkError> {
+        let mut added = 0;
+        for line in fs::read_to_string(path)?.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match self.add_authorized_key(line) {
+                Ok(true) => added += 1,
+                Ok(false) => {}
+                Err(NetworkError::InvalidKey(e)) => tracing::warn!("Skipping SSH key: {}", e),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(added)
+    }
+
+    /// Allow or refuse password logins
+    ///
+    /// Takes effect when sshd is restarted.
+    pub fn enable_password_auth(&self, enabled: bool) -> Result<(), NetworkError> {
+        let contents = fs::read_to_string(&self.sshd_config).unwrap_or_default();
+        fs::write(
+            &self.sshd_config,
+            set_sshd_option(&contents, "PasswordAuthentication", yes_no(enabled)),
+        )?;
+        tracing::info!("SSH password login {}", yes_no(enabled));
+        Ok(())
+    }
+
+    /// Fingerprint of the device's host key (e.g. "SHA256:...")
+    pub fn host_key_fingerprint(&self) -> Result<String, NetworkError> {
+        let output = Command::new("ssh-keygen")
+            .arg("-lf")
+            .arg(&self.host_key)
+            .output()?;
+
+        if !output.status.success() {
+            return Err(NetworkError::CommandFailed(
+                String::from_utf8_lossy(&output.stderr).to_string(),
+            ));
+        }
+
+        // Format: "256 SHA256:xxxx comment (ED25519)"
+        String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .nth(1)
+            .map(String::from)
+            .ok_or_else(|| NetworkError::CommandFailed("Unexpected ssh-keygen output".into()))
+    }
+}
+
+/// Check that a line is an OpenSSH public key sshd will accept
+///
+/// The key data must be valid base64 and name the same type as the line.
+pub fn validate_public_key(key: &str) -> Result<(), NetworkError> {
+    let invalid = |reason: &str| NetworkError::InvalidKey(reason.to_string());
+
+    // A line break would smuggle a second line into authorized_keys
+    if key.contains(['\n', '\r']) {
+        return Err(invalid("key contains a line break"));
+    }
+
+    let mut parts = key.split_whitespace();
+    let key_type = parts.next().ok_or_else(|| invalid("empty key"))?;
+    if !KEY_TYPES.contains(&key_type) {
+        return Err(invalid(&format!("unsupported key type {}", key_type)));
+    }
+
+    let data = parts.next().ok_or_else(|| invalid("missing key data"))?;
+    let blob = decode_base64(data).ok_or_else(|| invalid("key data is not base64"))?;
+
+    // The blob starts with its own type as a length-prefixed string
+    let embedded = blob
+        .get(..4)
+        .map(|len| u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize)
+        .and_then(|len| blob.get(4..4 + len));
+    if embedded != Some(key_type.as_bytes()) {
+        return Err(invalid("key data doesn't match its type"));
+    }
+    Ok(())
+}
+
+/// Key type and comment, for logs
+fn key_summary(key: &str) -> String {
+    let mut parts = key.split_whitespace();
+    let key_type = parts.next().unwrap_or_default();
+    let comment: Vec<&str> = parts.skip(1).collect();
+    format!("{} {}", key_type, comment.join(" "))
+        .trim()
+        .to_string()
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value { "yes" } else { "no" }
+}
+
+/// Set an sshd option globally, replacing existing (or commented-out) lines
+///
+/// Everything from the first `Match` line on is scoped to that block, so
+/// those lines are left alone and a new setting goes before them.
+fn set_sshd_option(contents: &str, option: &str, value: &str) -> String {
+    let setting = format!("{} {}", option, value);
+    let mut replaced = false;
+    let mut in_match = false;
+    let mut lines = Vec::new();
+
+    for line in contents.lines() {
+        if !in_match
+            && line
+                .split_whitespace()
+                .next()
+                .is_some_and(|k| k.eq_ignore_ascii_case("Match"))
+        {
+            in_match = true;
+            if !replaced {
+                lines.push(setting.clone());
+                replaced = true;
+            }
+        }
+
+        let keyword = line
+            .trim_start()
+            .trim_start_matches('#')
+            .split_whitespace()
+            .next();
+        if !in_match && keyword.is_some_and(|k| k.eq_ignore_ascii_case(option)) {
+            // Keep only the first occurrence; sshd uses the first value
+            if !replaced {
+                lines.push(setting.clone());
+                replaced = true;
+            }
+            continue;
+        }
+        lines.push(line.to_string());
+    }
+
+    if !replaced {
+        lines.push(setting);
+    }
+    lines.into_iter().map(|line| line + "\n").collect()
+}
+
+/// Decode standard padded base64
+fn decode_base64(data: &str) -> Option<Vec<u8>> {
+    let bytes = data.as_bytes();
+    let chunks = bytes.chunks_exact(4);
+    if bytes.is_empty() || !chunks.remainder().is_empty() {
+        return None;
+    }
+    let count = chunks.len();
+
+    let value = |c: u8| -> Option<u32> {
+        match c {
+            b'A'..=b'Z' => Some((c - b'A') as u32),
+            b'a'..=b'z' => Some((c - b'a' + 26) as u32),
+            b'0'..=b'9' => Some((c - b'0' + 52) as u32),
+            b'+' => Some(62),
+            b'/' => Some(63),
+            _ => None,
+        }
+    };
+
+    let mut out = Vec::with_capacity(bytes.len() / 4 * 3);
+    for (i, chunk) in chunks.enumerate() {
+        let last = i == count - 1;
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding > 0 && !last) {
+            return None;
+        }
+
+        let mut n = 0u32;
+        for &c in &chunk[..4 - padding] {
+            n = (n << 6) | value(c)?;
+        }
+        n <<= 6 * padding as u32;
+
+        let decoded = [(n >> 16) as u8, (n >> 8) as u8, n as u8];
+        out.extend_from_slice(&decoded[..3 - padding]);
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ED25519: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl user@pc";
+
+    #[test]
+    fn test_validate_public_key() {
+        assert!(validate_public_key(ED25519).is_ok());
+
+        // Type on the line doesn't match the key data
+        let mismatched = ED25519.replace("ssh-ed25519", "ssh-rsa");
+        assert!(validate_public_key(&mismatched).is_err());
+
+        assert!(validate_public_key("ssh-ed25519 not-base64!").is_err());
+        assert!(validate_public_key("ssh-dss AAAAB3NzaC1kc3M=").is_err());
+        assert!(validate_public_key("").is_err());
+    }
+
+    #[test]
+    fn test_set_sshd_option() {
+        let contents = "Port 22\n#PasswordAuthentication yes\nUsePAM yes\n";
+        assert_eq!(
+            set_sshd_option(contents, "PasswordAuthentication", "no"),
+            "Port 22\nPasswordAuthentication no\nUsePAM yes\n"
+        );
+        assert_eq!(
+            set_sshd_option("Port 22\n", "PasswordAuthentication", "yes"),
+            "Port 22\nPasswordAuthentication yes\n"
+        );
+
+        // Match blocks keep their own setting; the global one goes before them
+        let contents = "Port 22\nMatch User guest\n    PasswordAuthentication yes\n";
+        assert_eq!(
+            set_sshd_option(contents, "PasswordAuthentication", "no"),
+            "Port 22\nPasswordAuthentication no\nMatch User guest\n    PasswordAuthentication yes\n"
+        );
+    }
+
+    #[test]
+    fn test_add_authorized_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".ssh/authorized_keys");
+        let ssh = SshConfig::new().with_authorized_keys(&path);
+
+        assert!(ssh.add_authorized_key(ED25519).unwrap());
+        // Same key with another comment is not added twice
+        let recommented = ED25519.replace("user@pc", "laptop");
+        assert!(!ssh.add_authorized_key(&recommented).unwrap());
+        assert!(ssh.add_authorized_key("ssh-rsa AAAA").is_err());
+        // A second line can't ride along with a valid key
+        let smuggled = format!("{}\nssh-ed25519 AAAA attacker", ED25519);
+        assert!(ssh.add_authorized_key(&smuggled).is_err());
+        assert!(ssh.add_authorized_key(&format!("{}\rx", ED25519)).is_err());
+
+        assert_eq!(ssh.authorized_keys().unwrap(), vec![ED25519.to_string()]);
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
@@ -0,0 +1,463 @@
+//! Clock synchronization
+//!
+//! Most supported handhelds have no battery-backed RTC, so the clock starts
+//! at (or near) the epoch on every boot. `TimeSync` sets the clock with a
+//! minimal SNTP client (RFC 4330) once a network is available, falling back
+//! to `chronyc`/`ntpd` if present, and applies the configured timezone.
+
+use crate::NetworkError;
+use std::fs;
+use std::net::{ToSocketAddrs, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Default NTP servers
+pub const DEFAULT_NTP_SERVERS: &[&str] =
+    &["pool.ntp.org", "time.google.com", "time.cloudflare.com"];
+
+/// Seconds between the NTP epoch (1900) and the Unix epoch (1970)
+const NTP_UNIX_OFFSET: u64 = 2_208_988_800;
+
+/// Clocks earlier than this (2024-01-01) are treated as never set
+const MIN_VALID_UNIX_TIME: u64 = 1_704_067_200;
+
+/// SNTP packet size
+const PACKET_SIZE: usize = 48;
+
+/// NTP port
+const NTP_PORT: u16 = 123;
+
+/// An NTP timestamp (seconds and fraction since 1900)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct NtpTimestamp {
+    pub seconds: u32,
+    pub fraction: u32,
+}
+
+impl NtpTimestamp {
+    /// Convert from system time
+    pub fn from_system_time(time: SystemTime) -> Self {
+        let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let fraction = ((since_epoch.subsec_nanos() as u64) << 32) / 1_000_000_000;
+        Self {
+            // Truncation is intended: NTP seconds wrap every 136 years
+            seconds: (since_epoch.as_secs() + NTP_UNIX_OFFSET) as u32,
+            fraction: fraction as u32,
+        }
+    }
+
+    /// Convert to nanoseconds since the Unix epoch
+    ///
+    /// Timestamps with the high bit clear are taken to be in NTP era 1
+    /// (after 2036), per RFC 4330 section 3.
+    pub fn to_unix_nanos(self) -> i128 {
+        let mut seconds = self.seconds as i128;
+        if self.seconds & 0x8000_0000 == 0 {
+            seconds += 1 << 32;
+        }
+        let nanos = ((self.fraction as u64 * 1_000_000_000) >> 32) as i128;
+        (seconds - NTP_UNIX_OFFSET as i128) * 1_000_000_000 + nanos
+    }
+
+    fn read(buf: &[u8]) -> Self {
+        Self {
+            seconds: u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]),
+            fraction: u32::from_be_bytes([buf[4], buf[5], buf[6], buf[7]]),
+        }
+    }
+
+    fn write(&self, buf: &mut [u8]) {
+        buf[..4].copy_from_slice(&self.seconds.to_be_bytes());
+        buf[4..8].copy_from_slice(&self.fraction.to_be_bytes());
+    }
+}
+
+/// Build an SNTP client request
+pub fn build_request(transmit: NtpTimestamp) -> [u8; PACKET_SIZE] {
+    let mut packet = [0u8; PACKET_SIZE];
+    // LI = 0, VN = 4, Mode = 3 (client)
+    packet[0] = (4 << 3) | 3;
+    transmit.write(&mut packet[40..48]);
+    packet
+}
+
+/// Server timestamps from an SNTP response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SntpResponse {
+    /// Stratum of the server
+    pub stratum: u8,
+    /// Client transmit time echoed back by the server
+    pub originate: NtpTimestamp,
+    /// Time the request arrived at the server
+    pub receive: NtpTimestamp,
+    /// Time the response left the server
+    pub transmit: NtpTimestamp,
+}
+
+impl SntpResponse {
+    /// Parse and validate a server response
+    pub fn parse(buf: &[u8]) -> Result<Self, NetworkError> {
+        if buf.len() < PACKET_SIZE {
+            return Err(NetworkError::CommandFailed(format!(
+                "Short SNTP response ({} bytes)",
+                buf.len()
+            )));
+        }
+
+        let leap = buf[0] >> 6;
+        let mode = buf[0] & 0x7;
+        let stratum = buf[1];
+
+        if mode != 4 && mode != 5 {
+            return Err(NetworkError::CommandFailed(format!(
+                "Unexpected SNTP mode {}",
+                mode
+            )));
+        }
+        // Stratum 0 is a kiss-o'-death packet, leap 3 an unsynchronized server
+        if stratum == 0 || leap == 3 {
+            return Err(NetworkError::CommandFailed(
+                "SNTP server is not synchronized".into(),
+            ));
+        }
+
+        let response = Self {
+            stratum,
+            originate: NtpTimestamp::read(&buf[24..32]),
+            receive: NtpTimestamp::read(&buf[32..40]),
+            transmit: NtpTimestamp::read(&buf[40..48]),
+        };
+
+        if response.transmit == NtpTimestamp::default() {
+            return Err(NetworkError::CommandFailed(
+                "SNTP response has no transmit time".into(),
+            ));
+        }
+
+        Ok(response)
+    }
+
+    /// Clock offset in nanoseconds given the client send/receive times
+    ///
+    /// `((T2 - T1) + (T3 - T4)) / 2`, which stays correct even when the local
+    /// clock is decades off.
+    pub fn offset_nanos(&self, sent: NtpTimestamp, received: NtpTimestamp) -> i128 {
+        let t1 = sent.to_unix_nanos();
+        let t2 = self.receive.to_unix_nanos();
+        let t3 = self.transmit.to_unix_nanos();
+        let t4 = received.to_unix_nanos();
+        ((t2 - t1) + (t3 - t4)) / 2
+    }
+}
+
+/// Check if the system clock looks like it was never set
+pub fn clock_is_unset() -> bool {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() < MIN_VALID_UNIX_TIME)
+        .unwrap_or(true)
+}
+
+/// Clock and timezone synchronization
+#[derive(Debug, Clone)]
+pub struct TimeSync {
+    servers: Vec<String>,
+    timezone: Option<String>,
+    timeout: Duration,
+    zoneinfo_dir: PathBuf,
+    localtime_path: PathBuf,
+}
+
+impl Default for TimeSync {
+    fn default() -> Self {
+        Self {
+            servers: DEFAULT_NTP_SERVERS.iter().map(|s| s.to_string()).collect(),
+            timezone: None,
+            timeout: Duration::from_secs(3),
+            zoneinfo_dir: PathBuf::from("/usr/share/zoneinfo"),
+            localtime_path: PathBuf::from("/etc/localtime"),
+        }
+    }
+}
+
+impl TimeSync {
+    /// Create with default servers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set NTP servers
+    pub fn with_servers(mut self, servers: Vec<String>) -> Self {
+        self.servers = servers;
+        self
+    }
+
+    /// Set the timezone to apply (e.g. "Europe/Lisbon")
+    pub fn with_timezone(mut self, timezone: impl Into<String>) -> Self {
+        self.timezone = Some(timezone.into());
+        self
+    }
+
+    /// Set the per-server timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the zoneinfo database and `/etc/localtime` paths
+    pub fn with_zoneinfo(mut self, zoneinfo_dir: PathBuf, localtime_path: PathBuf) -> Self {
+        self.zoneinfo_dir = zoneinfo_dir;
+        self.localtime_path = localtime_path;
+        self
+    }
+
+    /// Apply the timezone and synchronize the clock
+    ///
+    /// Returns the adjusted system time.
+    pub fn sync_now(&self) -> Result<SystemTime, NetworkError> {
+        // Avoid if-let chains for MSRV 1.85 compatibility
+        #[allow(clippy::collapsible_if)]
+        if let Some(ref timezone) = self.timezone {
+            if let Err(e) = self.apply_timezone(timezone) {
+                tracing::warn!("Failed to set timezone {}: {}", timezone, e);
+            }
+        }
+
+        if clock_is_unset() {
+            tracing::info!("System clock is unset, synchronizing");
+        }
+
+        let mut last_error = NetworkError::Timeout;
+        for server in &self.servers {
+            match self.query(server) {
+                Ok(offset) => {
+                    let now = SystemTime::now();
+                    let adjusted = apply_offset(now, offset);
+                    set_system_time(adjusted)?;
+                    tracing::info!(
+                        "Clock synchronized with {} (offset {} ms)",
+                        server,
+                        offset / 1_000_000
+                    );
+                    return Ok(SystemTime::now());
+                }
+                Err(e) => {
+                    tracing::debug!("SNTP query to {} failed: {}", server, e);
+                    last_error = e;
+                }
+            }
+        }
+
+        // Fall back to a system NTP client if one is installed
+        if self.sync_with_daemon() {
+            return Ok(SystemTime::now());
+        }
+
+        Err(last_error)
+    }
+
+    /// Query a server and return the clock offset in nanoseconds
+    pub fn query(&self, server: &str) -> Result<i128, NetworkError> {
+        let addr = (server, NTP_PORT)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| NetworkError::NetworkNotFound(server.to_string()))?;
+
+        let bind = if addr.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(bind)?;
+        socket.set_read_timeout(Some(self.timeout))?;
+        socket.connect(addr)?;
+
+        let sent = NtpTimestamp::from_system_time(SystemTime::now());
+        socket.send(&build_request(sent))?;
+
+        let mut buf = [0u8; PACKET_SIZE];
+        let len = socket.recv(&mut buf).map_err(|e| match e.kind() {
+            std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut => NetworkError::Timeout,
+            _ => NetworkError::Io(e),
+        })?;
+        let received = NtpTimestamp::from_system_time(SystemTime::now());
+
+        let response = SntpResponse::parse(&buf[..len])?;
+        if response.originate != sent {
+            return Err(NetworkError::CommandFailed(
+                "SNTP response does not match request".into(),
+            ));
+        }
+
+        Ok(response.offset_nanos(sent, received))
+    }
+
+    /// Point `/etc/localtime` at the configured zone
+    pub fn apply_timezone(&self, timezone: &str) -> Result<(), NetworkError> {
+        // Reject anything that could escape the zoneinfo directory
+        if timezone.is_empty()
+            || timezone.starts_with('/')
+            || timezone.split('/').any(|part| part == "..")
+        {
+            return Err(NetworkError::CommandFailed(format!(
+                "Invalid timezone: {}",
+                timezone
+            )));
+        }
+
+        let zone = self.zoneinfo_dir.join(timezone);
+        if !zone.is_file() {
+            return Err(NetworkError::CommandFailed(format!(
+                "Unknown timezone: {}",
+                timezone
+            )));
+        }
+
+        if fs::read_link(&self.localtime_path).is_ok_and(|target| target == zone) {
+            return Ok(());
+        }
+
+        let _ = fs::remove_file(&self.localtime_path);
+        std::os::unix::fs::symlink(&zone, &self.localtime_path)?;
+
+        if let Some(parent) = self.localtime_path.parent() {
+            let _ = fs::write(parent.join("timezone"), format!("{}\n", timezone));
+        }
+
+        tracing::info!("Timezone set to {}", timezone);
+        Ok(())
+    }
+
+    /// Try `chronyc` then busybox `ntpd`
+    fn sync_with_daemon(&self) -> bool {
+        let chrony = Command::new("chronyc").args(["makestep"]).output();
+        if chrony.is_ok_and(|o| o.status.success()) {
+            return true;
+        }
+
+        for server in &self.servers {
+            let ntpd = Command::new("ntpd")
+                .args(["-q", "-n", "-p", server])
+                .output();
+            if ntpd.is_ok_and(|o| o.status.success()) {
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+/// Shift a time by a signed offset in nanoseconds
+fn apply_offset(time: SystemTime, offset_nanos: i128) -> SystemTime {
+    let magnitude = Duration::from_nanos(offset_nanos.unsigned_abs().min(u64::MAX as u128) as u64);
+    if offset_nanos >= 0 {
+        time + magnitude
+    } else {
+        time.checked_sub(magnitude).unwrap_or(UNIX_EPOCH)
+    }
+}
+
+/// Set the system clock (requires CAP_SYS_TIME)
+fn set_system_time(time: SystemTime) -> Result<(), NetworkError> {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    // Zeroed first: some 32-bit targets have padding fields in timespec
+    // SAFETY: timespec is plain data, all-zero is a valid value
+    let mut ts: libc::timespec = unsafe { std::mem::zeroed() };
+    ts.tv_sec = since_epoch.as_secs() as libc::time_t;
+    ts.tv_nsec = since_epoch.subsec_nanos() as _;
+
+    // SAFETY: clock_settime only reads the timespec we pass
+    let ret = unsafe { libc::clock_settime(libc::CLOCK_REALTIME, &ts) };
+    if ret != 0 {
+        return Err(NetworkError::Io(std::io::Error::last_os_error()));
+    }
+
+    // Persist to the RTC when the device has one
+    if Path::new("/dev/rtc0").exists() {
+        let _ = Command::new("hwclock")
+            .args(["--systohc", "--utc"])
+            .output();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timestamp_round_trip() {
+        let time = UNIX_EPOCH + Duration::from_millis(1_750_000_000_250);
+        let ts = NtpTimestamp::from_system_time(time);
+        assert_eq!(ts.seconds as u64, 1_750_000_000 + NTP_UNIX_OFFSET);
+
+        let nanos = ts.to_unix_nanos();
+        assert!((nanos - 1_750_000_000_250_000_000).abs() < 1_000);
+    }
+
+    #[test]
+    fn test_parse_response_and_offset() {
+        // Client clock stuck at the epoch (no RTC), server in 2025
+        let sent = NtpTimestamp::from_system_time(UNIX_EPOCH + Duration::from_secs(10));
+        let received = NtpTimestamp::from_system_time(UNIX_EPOCH + Duration::from_secs(12));
+        let server =
+            NtpTimestamp::from_system_time(UNIX_EPOCH + Duration::from_secs(1_750_000_001));
+
+        let mut packet = [0u8; PACKET_SIZE];
+        packet[0] = (4 << 3) | 4;
+        packet[1] = 2;
+        sent.write(&mut packet[24..32]);
+        server.write(&mut packet[32..40]);
+        server.write(&mut packet[40..48]);
+
+        let response = SntpResponse::parse(&packet).unwrap();
+        assert_eq!(response.stratum, 2);
+        assert_eq!(response.originate, sent);
+
+        let offset = response.offset_nanos(sent, received);
+        let adjusted = apply_offset(UNIX_EPOCH + Duration::from_secs(12), offset);
+        let secs = adjusted.duration_since(UNIX_EPOCH).unwrap().as_secs();
+        assert_eq!(secs, 1_750_000_002);
+    }
+
+    #[test]
+    fn test_reject_unsynchronized() {
+        let mut packet = build_request(NtpTimestamp::default());
+        assert!(SntpResponse::parse(&packet).is_err()); // client mode
+
+        packet[0] = (4 << 3) | 4;
+        packet[1] = 0; // kiss-o'-death
+        assert!(SntpResponse::parse(&packet).is_err());
+        assert!(SntpResponse::parse(&packet[..10]).is_err());
+    }
+
+    #[test]
+    fn test_apply_timezone() {
+        let dir = std::env::temp_dir().join(format!("rexos-tz-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let zoneinfo = dir.join("zoneinfo");
+        fs::create_dir_all(zoneinfo.join("Europe")).unwrap();
+        fs::write(zoneinfo.join("Europe/Lisbon"), "TZif").unwrap();
+
+        let localtime = dir.join("localtime");
+        let sync = TimeSync::new().with_zoneinfo(zoneinfo.clone(), localtime.clone());
+
+        sync.apply_timezone("Europe/Lisbon").unwrap();
+        assert_eq!(
+            fs::read_link(&localtime).unwrap(),
+            zoneinfo.join("Europe/Lisbon")
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("timezone")).unwrap(),
+            "Europe/Lisbon\n"
+        );
+
+        assert!(sync.apply_timezone("Mars/Olympus").is_err());
+        assert!(sync.apply_timezone("../../etc/passwd").is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -3,6 +3,23 @@
 use crate::NetworkError;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long wpa_supplicant is given to collect scan results
+pub const DEFAULT_SCAN_WAIT: Duration = Duration::from_secs(2);
+
+/// How long scan results are reused before scanning again
+pub const DEFAULT_SCAN_CACHE: Duration = Duration::from_secs(10);
+
+/// Connection attempts before giving up
+pub const DEFAULT_CONNECT_ATTEMPTS: u32 = 3;
+
+/// Wait before the first retry, doubled for each one after
+pub const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_secs(2);
+
+/// Longest wait between attempts
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
 
 /// WiFi network information
 #[derive(Debug, Clone)]
@@ -23,43 +40,121 @@ pub struct WifiNetwork {
     pub connected: bool,
 }
 
+/// A network saved in wpa_supplicant
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavedNetwork {
+    /// wpa_supplicant network id
+    pub id: String,
+    /// Network SSID
+    pub ssid: String,
+    /// Auto-connect priority (higher is preferred)
+    pub priority: i32,
+    /// Whether this is the network in use
+    pub current: bool,
+}
+
 /// WiFi security types
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum WifiSecurity {
     Open,
+    /// Opportunistic Wireless Encryption ("Enhanced Open", no password)
+    OWE,
     WEP,
     WPA,
     WPA2,
+    /// WPA3-Personal (SAE)
     WPA3,
+    /// WPA2/WPA3 transition mode, accepting PSK or SAE
+    WPA3Transition,
     WPA2Enterprise,
 }
 
 impl WifiSecurity {
     /// Parse from wpa_supplicant flags
+    ///
+    /// Flags come in bracketed groups such as `[WPA2-PSK+SAE-CCMP][ESS]`:
+    /// protocol, then `+`-joined key management, then ciphers. The key
+    /// management of every group is considered, so a network offering
+    /// both PSK and SAE is a transition network rather than WPA3-only.
     pub fn from_flags(flags: &str) -> Self {
-        if flags.contains("WPA3") {
-            WifiSecurity::WPA3
-        } else if flags.contains("WPA2-EAP") {
+        let (mut psk, mut sae, mut eap, mut owe, mut wep) = (false, false, false, false, false);
+        let mut rsn = false;
+
+        for group in flags.split(['[', ']']).filter(|group| !group.is_empty()) {
+            let mut tokens = group.split(['-', '+']);
+            match tokens.next() {
+                Some("WPA2" | "RSN") => rsn = true,
+                Some("WPA") => {}
+                Some("WEP") => {
+                    wep = true;
+                    continue;
+                }
+                // ESS, WPS, OWE-TRANS (an open BSS pointing at its OWE twin), ...
+                _ => continue,
+            }
+
+            for token in tokens {
+                match token {
+                    "PSK" => psk = true,
+                    "SAE" => sae = true,
+                    "EAP" => eap = true,
+                    "OWE" => owe = true,
+                    _ => {}
+                }
+            }
+        }
+
+        if eap {
             WifiSecurity::WPA2Enterprise
-        } else if flags.contains("WPA2") {
+        } else if sae && psk {
+            WifiSecurity::WPA3Transition
+        } else if sae {
+            WifiSecurity::WPA3
+        } else if psk && rsn {
             WifiSecurity::WPA2
-        } else if flags.contains("WPA") {
+        } else if psk {
             WifiSecurity::WPA
-        } else if flags.contains("WEP") {
+        } else if owe {
+            WifiSecurity::OWE
+        } else if wep {
             WifiSecurity::WEP
         } else {
             WifiSecurity::Open
         }
     }
 
+    /// wpa_supplicant `key_mgmt` value for connecting
+    pub fn key_mgmt(&self) -> &'static str {
+        match self {
+            WifiSecurity::Open | WifiSecurity::WEP => "NONE",
+            WifiSecurity::OWE => "OWE",
+            WifiSecurity::WPA | WifiSecurity::WPA2 => "WPA-PSK",
+            WifiSecurity::WPA3 => "SAE",
+            WifiSecurity::WPA3Transition => "WPA-PSK SAE",
+            WifiSecurity::WPA2Enterprise => "WPA-EAP",
+        }
+    }
+
+    /// wpa_supplicant `ieee80211w` (management frame protection) value,
+    /// which SAE and OWE require
+    fn ieee80211w(&self) -> Option<&'static str> {
+        match self {
+            WifiSecurity::WPA3 | WifiSecurity::OWE => Some("2"),
+            WifiSecurity::WPA3Transition => Some("1"),
+            _ => None,
+        }
+    }
+
     /// Get display name
     pub fn as_str(&self) -> &'static str {
         match self {
             WifiSecurity::Open => "Open",
+            WifiSecurity::OWE => "Enhanced Open",
             WifiSecurity::WEP => "WEP",
             WifiSecurity::WPA => "WPA",
             WifiSecurity::WPA2 => "WPA2",
             WifiSecurity::WPA3 => "WPA3",
+            WifiSecurity::WPA3Transition => "WPA2/WPA3",
             WifiSecurity::WPA2Enterprise => "WPA2-Enterprise",
         }
     }
@@ -73,6 +168,11 @@ pub enum ConnectionState {
     Connecting,
     Connected,
     Failed,
+    /// An attempt failed and attempt `attempt` of `attempts` is starting
+    Retrying {
+        attempt: u32,
+        attempts: u32,
+    },
 }
 
 /// WiFi status information
@@ -87,6 +187,9 @@ pub struct WifiStatus {
 }
 
 /// Manages WiFi connections
+///
+/// [`scan`](Self::scan) blocks while wpa_supplicant scans; use
+/// [`NetworkWorker`](crate::NetworkWorker) to scan without freezing a UI.
 pub struct WifiManager {
     interface: String,
     /// Path to wpa_supplicant control socket
@@ -94,6 +197,16 @@ pub struct WifiManager {
     /// Path to wpa_supplicant configuration file
     wpa_config: PathBuf,
     available: bool,
+    /// How long to wait for scan results
+    scan_wait: Duration,
+    /// How long scan results stay fresh
+    scan_cache_ttl: Duration,
+    /// Connection attempts before giving up
+    connect_attempts: u32,
+    /// Wait before the first retry
+    retry_backoff: Duration,
+    /// Last scan results and when they were taken
+    scan_cache: Mutex<Option<(Instant, Vec<WifiNetwork>)>>,
 }
 
 impl WifiManager {
@@ -110,9 +223,34 @@ impl WifiManager {
             wpa_socket,
             wpa_config,
             available,
+            scan_wait: DEFAULT_SCAN_WAIT,
+            scan_cache_ttl: DEFAULT_SCAN_CACHE,
+            connect_attempts: DEFAULT_CONNECT_ATTEMPTS,
+            retry_backoff: DEFAULT_RETRY_BACKOFF,
+            scan_cache: Mutex::new(None),
         })
     }
 
+    /// Set how many times connecting is tried, and the wait before the
+    /// first retry (doubled for each one after)
+    pub fn with_connect_retries(mut self, attempts: u32, backoff: Duration) -> Self {
+        self.connect_attempts = attempts.max(1);
+        self.retry_backoff = backoff;
+        self
+    }
+
+    /// Set how long wpa_supplicant is given to collect scan results
+    pub fn with_scan_wait(mut self, wait: Duration) -> Self {
+        self.scan_wait = wait;
+        self
+    }
+
+    /// Set how long scan results are reused (zero disables the cache)
+    pub fn with_scan_cache(mut self, ttl: Duration) -> Self {
+        self.scan_cache_ttl = ttl;
+        self
+    }
+
     /// Check if WiFi interface is available
     fn check_available(interface: &str) -> bool {
         let path = format!("/sys/class/net/{}", interface);
@@ -139,25 +277,58 @@ impl WifiManager {
     }
 
     /// Scan for available networks
+    ///
+    /// Results from a scan within the cache time are returned at once;
+    /// otherwise this blocks for the scan wait.
     pub fn scan(&self) -> Result<Vec<WifiNetwork>, NetworkError> {
         if !self.available {
             return Err(NetworkError::WifiNotAvailable);
         }
 
+        if let Some(networks) = self.cached_scan() {
+            tracing::debug!("Using cached scan results");
+            return Ok(networks);
+        }
+        self.scan_fresh()
+    }
+
+    /// Scan for available networks, ignoring cached results
+    pub fn scan_fresh(&self) -> Result<Vec<WifiNetwork>, NetworkError> {
+        if !self.available {
+            return Err(NetworkError::WifiNotAvailable);
+        }
+
         // Trigger scan
         self.wpa_cli(&["scan"])?;
 
         // Wait a bit for scan to complete
-        std::thread::sleep(std::time::Duration::from_secs(2));
+        std::thread::sleep(self.scan_wait);
 
         // Get results
         let output = self.wpa_cli(&["scan_results"])?;
         let networks = self.parse_scan_results(&output)?;
 
         tracing::debug!("Found {} networks", networks.len());
+        if let Ok(mut cache) = self.scan_cache.lock() {
+            *cache = Some((Instant::now(), networks.clone()));
+        }
         Ok(networks)
     }
 
+    /// Results of the last scan, if still fresh
+    pub fn cached_scan(&self) -> Option<Vec<WifiNetwork>> {
+        let cache = self.scan_cache.lock().ok()?;
+        let (scanned, networks) = cache.as_ref()?;
+        (scanned.elapsed() < self.scan_cache_ttl).then(|| networks.clone())
+    }
+
+    /// Forget cached scan results, e.g. once their connected flags are stale
+    pub fn clear_scan_cache(&self) {
+        if let Ok(mut cache) = self.scan_cache.lock() {
+            *cache = None;
+        }
+    }
+
     /// Parse scan results
     fn parse_scan_results(&self, output: &str) -> Result<Vec<WifiNetwork>, NetworkError> {
         let mut networks = Vec::new();
@@ -195,7 +366,7 @@ impl WifiManager {
         }
 
         // Sort by signal strength
-        networks.sort_by(|a, b| b.signal.cmp(&a.signal));
+        networks.sort_by_key(|n| std::cmp::Reverse(n.signal));
 
         // Remove duplicates (same SSID, keep strongest signal)
         let mut seen = std::collections::HashSet::new();
@@ -206,6 +377,48 @@ impl WifiManager {
 
     /// Connect to a network
     pub fn connect(&self, ssid: &str, password: Option<&str>) -> Result<(), NetworkError> {
+        self.connect_with_progress(ssid, password, |_| {})
+    }
+
+    /// Connect to a network that doesn't broadcast its SSID
+    ///
+    /// The network is probed for by name, since it never shows up in scans.
+    pub fn connect_hidden(&self, ssid: &str, password: Option<&str>) -> Result<(), NetworkError> {
+        self.connect_hidden_with_progress(ssid, password, |_| {})
+    }
+
+    /// Connect to a network, reporting each polled state
+    ///
+    /// Blocks for up to 30 seconds per attempt while wpa_supplicant
+    /// associates. Failed associations are retried with backoff, reported
+    /// as [`ConnectionState::Retrying`]; a rejected password fails at once
+    /// with [`NetworkError::AuthenticationFailed`].
+    pub fn connect_with_progress(
+        &self,
+        ssid: &str,
+        password: Option<&str>,
+        progress: impl FnMut(ConnectionState),
+    ) -> Result<(), NetworkError> {
+        self.connect_network(ssid, password, false, progress)
+    }
+
+    /// Connect to a hidden network, reporting each polled state
+    pub fn connect_hidden_with_progress(
+        &self,
+        ssid: &str,
+        password: Option<&str>,
+        progress: impl FnMut(ConnectionState),
+    ) -> Result<(), NetworkError> {
+        self.connect_network(ssid, password, true, progress)
+    }
+
+    fn connect_network(
+        &self,
+        ssid: &str,
+        password: Option<&str>,
+        hidden: bool,
+        mut progress: impl FnMut(ConnectionState),
+    ) -> Result<(), NetworkError> {
         if !self.available {
             return Err(NetworkError::WifiNotAvailable);
         }
@@ -221,46 +434,109 @@ impl WifiManager {
             let output = self.wpa_cli(&["add_network"])?;
             let network_id = output.trim();
 
-            // Set SSID
-            self.wpa_cli(&["set_network", network_id, "ssid", &format!("\"{}\"", ssid)])?;
+            // Key management follows what the network advertises
+            let security = self.network_security(ssid).unwrap_or(match password {
+                Some(_) => WifiSecurity::WPA2,
+                None => WifiSecurity::Open,
+            });
+            for args in add_network_commands(network_id, ssid, password, security, hidden) {
+                let args: Vec<&str> = args.iter().map(String::as_str).collect();
+                self.wpa_cli(&args)?;
+            }
+        }
 
-            // Set password if provided
-            if let Some(pass) = password {
-                self.wpa_cli(&["set_network", network_id, "psk", &format!("\"{}\"", pass)])?;
-            } else {
-                self.wpa_cli(&["set_network", network_id, "key_mgmt", "NONE"])?;
+        let attempts = self.connect_attempts;
+        let mut last_error = NetworkError::Timeout;
+        for attempt in 1..=attempts {
+            if attempt > 1 {
+                let delay = retry_delay(self.retry_backoff, attempt - 1);
+                tracing::warn!(
+                    "Connecting to {} failed ({}), retrying in {:?} (attempt {}/{})",
+                    ssid,
+                    last_error,
+                    delay,
+                    attempt,
+                    attempts
+                );
+                progress(ConnectionState::Retrying { attempt, attempts });
+                std::thread::sleep(delay);
+                self.wpa_cli(&["reassociate"])?;
             }
 
-            // Enable and select network
-            self.wpa_cli(&["enable_network", network_id])?;
-            self.wpa_cli(&["select_network", network_id])?;
+            match self.wait_for_connection(&mut progress) {
+                Ok(()) => {
+                    tracing::info!("Connected to {}", ssid);
+                    self.clear_scan_cache();
+                    self.wpa_cli(&["save_config"])?;
+                    return Ok(());
+                }
+                // Retrying a wrong password only locks the network out
+                Err(NetworkError::AuthenticationFailed) => {
+                    tracing::warn!("Authentication to {} failed", ssid);
+                    return Err(NetworkError::AuthenticationFailed);
+                }
+                Err(e) => last_error = e,
+            }
         }
 
-        // Wait for connection
+        Err(last_error)
+    }
+
+    /// Poll until associated, for up to 30 seconds
+    fn wait_for_connection(
+        &self,
+        progress: &mut impl FnMut(ConnectionState),
+    ) -> Result<(), NetworkError> {
+        let mut previous = String::new();
         for _ in 0..30 {
             std::thread::sleep(std::time::Duration::from_secs(1));
 
-            if let Ok(status) = self.status() {
-                match status.state {
-                    ConnectionState::Connected => {
-                        tracing::info!("Connected to {}", ssid);
-                        self.wpa_cli(&["save_config"])?;
-                        return Ok(());
-                    }
-                    ConnectionState::Failed => {
-                        return Err(NetworkError::ConnectionFailed("Connection failed".into()));
-                    }
-                    _ => continue,
+            let Ok(output) = self.wpa_cli(&["status"]) else {
+                continue;
+            };
+            let status = parse_status(&output);
+            progress(status.state);
+
+            let wpa_state = status_value(&output, "wpa_state").unwrap_or_default();
+            if auth_failed(&previous, wpa_state) {
+                return Err(NetworkError::AuthenticationFailed);
+            }
+            previous = wpa_state.to_string();
+
+            match status.state {
+                ConnectionState::Connected => return Ok(()),
+                ConnectionState::Failed => {
+                    return Err(NetworkError::ConnectionFailed("Connection failed".into()));
                 }
+                _ => continue,
             }
         }
 
         Err(NetworkError::Timeout)
     }
 
+    /// Security a network advertised in the last scan
+    fn network_security(&self, ssid: &str) -> Option<WifiSecurity> {
+        if let Some(networks) = self.cached_scan() {
+            return networks
+                .iter()
+                .find(|network| network.ssid == ssid)
+                .map(|network| network.security);
+        }
+
+        // scan_results reads the last results without scanning again
+        let output = self.wpa_cli(&["scan_results"]).ok()?;
+        output.lines().skip(1).find_map(|line| {
+            let parts: Vec<&str> = line.split('\t').collect();
+            (parts.len() >= 5 && parts[4..].join("\t") == ssid)
+                .then(|| WifiSecurity::from_flags(parts[3]))
+        })
+    }
+
     /// Disconnect from current network
     pub fn disconnect(&self) -> Result<(), NetworkError> {
         self.wpa_cli(&["disconnect"])?;
+        self.clear_scan_cache();
         tracing::info!("Disconnected from WiFi");
         Ok(())
     }
@@ -268,39 +544,7 @@ impl WifiManager {
     /// Get current status
     pub fn status(&self) -> Result<WifiStatus, NetworkError> {
         let output = self.wpa_cli(&["status"])?;
-        let mut status = WifiStatus {
-            state: ConnectionState::Disconnected,
-            ssid: None,
-            bssid: None,
-            ip_address: None,
-            signal: None,
-            frequency: None,
-        };
-
-        for line in output.lines() {
-            if let Some((key, value)) = line.split_once('=') {
-                match key {
-                    "wpa_state" => {
-                        status.state = match value {
-                            "COMPLETED" => ConnectionState::Connected,
-                            "SCANNING" => ConnectionState::Scanning,
-                            "ASSOCIATING" | "ASSOCIATED" | "4WAY_HANDSHAKE" | "GROUP_HANDSHAKE" => {
-                                ConnectionState::Connecting
-                            }
-                            "DISCONNECTED" | "INACTIVE" => ConnectionState::Disconnected,
-                            _ => ConnectionState::Disconnected,
-                        };
-                    }
-                    "ssid" => status.ssid = Some(value.to_string()),
-                    "bssid" => status.bssid = Some(value.to_string()),
-                    "ip_address" => status.ip_address = Some(value.to_string()),
-                    "freq" => status.frequency = value.parse().ok(),
-                    _ => {}
-                }
-            }
-        }
-
-        Ok(status)
+        Ok(parse_status(&output))
     }
 
     /// Check if connected
@@ -319,27 +563,64 @@ impl WifiManager {
         self.status().ok().and_then(|s| s.ssid)
     }
 
-    /// Get saved networks
-    pub fn list_saved_networks(&self) -> Result<Vec<String>, NetworkError> {
+    /// Get saved networks, most preferred first
+    pub fn list_saved_networks(&self) -> Result<Vec<SavedNetwork>, NetworkError> {
         let output = self.wpa_cli(&["list_networks"])?;
-        let mut networks = Vec::new();
-
-        // Skip header line
-        for line in output.lines().skip(1) {
-            let parts: Vec<&str> = line.split('\t').collect();
-            if parts.len() >= 2 {
-                networks.push(parts[1].to_string());
-            }
+        let mut networks = parse_network_list(&output);
+
+        for network in &mut networks {
+            network.priority = self
+                .wpa_cli(&["get_network", &network.id, "priority"])
+                .ok()
+                .and_then(|value| value.trim().parse().ok())
+                .unwrap_or(0);
         }
 
+        // Stable, so equal priorities keep wpa_supplicant's order
+        networks.sort_by_key(|network| std::cmp::Reverse(network.priority));
         Ok(networks)
     }
 
+    /// Set the auto-connect priority of a saved network
+    pub fn set_priority(&self, ssid: &str, priority: i32) -> Result<(), NetworkError> {
+        let network_id = self
+            .find_network_id(ssid)?
+            .ok_or_else(|| NetworkError::NetworkNotFound(ssid.to_string()))?;
+
+        self.wpa_cli(&[
+            "set_network",
+            &network_id,
+            "priority",
+            &priority.to_string(),
+        ])?;
+        self.wpa_cli(&["save_config"])?;
+        tracing::info!("Set priority of {} to {}", ssid, priority);
+        Ok(())
+    }
+
+    /// Prefer saved networks in the given order
+    ///
+    /// The first SSID gets the highest priority. Saved networks not listed
+    /// drop to priority 0; unknown SSIDs are ignored.
+    pub fn reorder_priorities(&self, ssids_in_order: &[&str]) -> Result<(), NetworkError> {
+        let output = self.wpa_cli(&["list_networks"])?;
+        let saved = parse_network_list(&output);
+
+        for (id, priority) in priority_plan(&saved, ssids_in_order) {
+            self.wpa_cli(&["set_network", &id, "priority", &priority.to_string()])?;
+        }
+        self.wpa_cli(&["save_config"])?;
+
+        tracing::info!("Reordered WiFi priorities: {}", ssids_in_order.join(", "));
+        Ok(())
+    }
+
     /// Remove a saved network
     pub fn forget_network(&self, ssid: &str) -> Result<(), NetworkError> {
         if let Some(network_id) = self.find_network_id(ssid)? {
             self.wpa_cli(&["remove_network", &network_id])?;
             self.wpa_cli(&["save_config"])?;
+            self.clear_scan_cache();
             tracing::info!("Forgot network: {}", ssid);
         }
         Ok(())
@@ -440,10 +721,211 @@ impl WifiManager {
     }
 }
 
+/// Parse `wpa_cli status` output
+fn parse_status(output: &str) -> WifiStatus {
+    let mut status = WifiStatus {
+        state: ConnectionState::Disconnected,
+        ssid: None,
+        bssid: None,
+        ip_address: None,
+        signal: None,
+        frequency: None,
+    };
+
+    for line in output.lines() {
+        if let Some((key, value)) = line.split_once('=') {
+            match key {
+                "wpa_state" => {
+                    status.state = match value {
+                        "COMPLETED" => ConnectionState::Connected,
+                        "SCANNING" => ConnectionState::Scanning,
+                        "ASSOCIATING" | "ASSOCIATED" | "4WAY_HANDSHAKE" | "GROUP_HANDSHAKE" => {
+                            ConnectionState::Connecting
+                        }
+                        "DISCONNECTED" | "INACTIVE" => ConnectionState::Disconnected,
+                        _ => ConnectionState::Disconnected,
+                    };
+                }
+                "ssid" => status.ssid = Some(value.to_string()),
+                "bssid" => status.bssid = Some(value.to_string()),
+                "ip_address" => status.ip_address = Some(value.to_string()),
+                "freq" => status.frequency = value.parse().ok(),
+                _ => {}
+            }
+        }
+    }
+
+    status
+}
+
+/// Value of a `key=value` line in `wpa_cli status` output
+fn status_value<'a>(output: &'a str, key: &str) -> Option<&'a str> {
+    output
+        .lines()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+}
+
+/// Whether a change of `wpa_state` means the password was rejected
+///
+/// wpa_supplicant drops back out of the 4-way handshake when the PSK is
+/// wrong; association failures never reach it.
+fn auth_failed(previous: &str, current: &str) -> bool {
+    previous == "4WAY_HANDSHAKE" && matches!(current, "DISCONNECTED" | "SCANNING" | "INACTIVE")
+}
+
+/// Wait before retry `retry` (1-based), doubling from `backoff`
+fn retry_delay(backoff: Duration, retry: u32) -> Duration {
+    backoff
+        .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+        .min(MAX_RETRY_DELAY)
+}
+
+/// wpa_cli commands configuring a newly added network and selecting it
+fn add_network_commands(
+    network_id: &str,
+    ssid: &str,
+    password: Option<&str>,
+    security: WifiSecurity,
+    hidden: bool,
+) -> Vec<Vec<String>> {
+    let set = |key: &str, value: &str| {
+        vec![
+            "set_network".to_string(),
+            network_id.to_string(),
+            key.to_string(),
+            value.to_string(),
+        ]
+    };
+
+    let mut commands = vec![
+        set("ssid", &format!("\"{}\"", ssid)),
+        set("key_mgmt", security.key_mgmt()),
+    ];
+    if let Some(pmf) = security.ieee80211w() {
+        commands.push(set("ieee80211w", pmf));
+    }
+    // Probe for the SSID by name, since hidden networks don't beacon it
+    if hidden {
+        commands.push(set("scan_ssid", "1"));
+    }
+    // Also used as the SAE password
+    if let Some(pass) = password {
+        commands.push(set("psk", &format!("\"{}\"", pass)));
+    }
+
+    // Enable and select network
+    commands.push(vec!["enable_network".to_string(), network_id.to_string()]);
+    commands.push(vec!["select_network".to_string(), network_id.to_string()]);
+    commands
+}
+
+/// Parse `wpa_cli list_networks` output (priorities are left at 0)
+fn parse_network_list(output: &str) -> Vec<SavedNetwork> {
+    // Skip header line
+    output
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split('\t').collect();
+            if parts.len() < 2 {
+                return None;
+            }
+            Some(SavedNetwork {
+                id: parts[0].to_string(),
+                ssid: parts[1].to_string(),
+                priority: 0,
+                current: parts
+                    .get(3)
+                    .is_some_and(|flags| flags.contains("[CURRENT]")),
+            })
+        })
+        .collect()
+}
+
+/// Network ids and priorities so `ssids_in_order` are preferred in order
+fn priority_plan(saved: &[SavedNetwork], ssids_in_order: &[&str]) -> Vec<(String, i32)> {
+    saved
+        .iter()
+        .map(|network| {
+            let priority = ssids_in_order
+                .iter()
+                .position(|ssid| *ssid == network.ssid)
+                .map_or(0, |pos| (ssids_in_order.len() - pos) as i32);
+            (network.id.clone(), priority)
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const LIST_NETWORKS: &str = "network id / ssid / bssid / flags\n\
+        0\tCoffeeShop\tany\t\n\
+        1\tHome\tany\t[CURRENT]\n\
+        2\tPhone Hotspot\tany\t[DISABLED]\n";
+
+    #[test]
+    fn test_scan_cache() {
+        let mut wifi = WifiManager::new(
+            "wlan-test".to_string(),
+            PathBuf::from("/nonexistent"),
+            PathBuf::from("/nonexistent"),
+        )
+        .unwrap()
+        .with_scan_cache(Duration::from_secs(60));
+        wifi.available = true;
+        assert!(wifi.cached_scan().is_none());
+
+        let network = WifiNetwork {
+            ssid: "Home".to_string(),
+            bssid: "00:11:22:33:44:55".to_string(),
+            signal: 80,
+            frequency: 2412,
+            security: WifiSecurity::WPA2,
+            saved: true,
+            connected: false,
+        };
+        *wifi.scan_cache.lock().unwrap() = Some((Instant::now(), vec![network]));
+
+        // Served from the cache without running wpa_cli
+        let networks = wifi.scan().unwrap();
+        assert_eq!(networks[0].ssid, "Home");
+
+        wifi.clear_scan_cache();
+        assert!(wifi.cached_scan().is_none());
+
+        let wifi = wifi.with_scan_cache(Duration::ZERO);
+        *wifi.scan_cache.lock().unwrap() = Some((Instant::now(), Vec::new()));
+        assert!(wifi.cached_scan().is_none());
+    }
+
+    #[test]
+    fn test_parse_network_list() {
+        let networks = parse_network_list(LIST_NETWORKS);
+        assert_eq!(networks.len(), 3);
+        assert_eq!(networks[1].id, "1");
+        assert_eq!(networks[1].ssid, "Home");
+        assert!(networks[1].current);
+        assert!(!networks[0].current);
+        assert_eq!(networks[2].ssid, "Phone Hotspot");
+    }
+
+    #[test]
+    fn test_priority_plan() {
+        let saved = parse_network_list(LIST_NETWORKS);
+        let plan = priority_plan(&saved, &["Home", "Phone Hotspot", "Unknown"]);
+
+        assert_eq!(
+            plan,
+            vec![
+                ("0".to_string(), 0),
+                ("1".to_string(), 3),
+                ("2".to_string(), 2),
+            ]
+        );
+    }
+
     #[test]
     fn test_security_from_flags() {
         assert_eq!(
@@ -452,6 +934,100 @@ mod tests {
         );
         assert_eq!(WifiSecurity::from_flags("[WPA-PSK]"), WifiSecurity::WPA);
         assert_eq!(WifiSecurity::from_flags("[ESS]"), WifiSecurity::Open);
+        assert_eq!(
+            WifiSecurity::from_flags("[WPA-PSK-CCMP+TKIP][WPA2-PSK-CCMP+TKIP][ESS]"),
+            WifiSecurity::WPA2
+        );
+        assert_eq!(WifiSecurity::from_flags("[WEP][ESS]"), WifiSecurity::WEP);
+    }
+
+    #[test]
+    fn test_security_from_sae_flags() {
+        assert_eq!(
+            WifiSecurity::from_flags("[WPA2-SAE-CCMP][ESS]"),
+            WifiSecurity::WPA3
+        );
+        assert_eq!(
+            WifiSecurity::from_flags("[RSN-SAE+FT/SAE-CCMP][ESS]"),
+            WifiSecurity::WPA3
+        );
+        // Transition mode takes a PSK, so it isn't WPA3-only
+        let transition = WifiSecurity::from_flags("[WPA2-PSK+SAE-CCMP][ESS]");
+        assert_eq!(transition, WifiSecurity::WPA3Transition);
+        assert_eq!(transition.key_mgmt(), "WPA-PSK SAE");
+    }
+
+    #[test]
+    fn test_security_from_eap_and_owe_flags() {
+        assert_eq!(
+            WifiSecurity::from_flags("[WPA2-EAP-SHA256-CCMP][ESS]"),
+            WifiSecurity::WPA2Enterprise
+        );
+        assert_eq!(
+            WifiSecurity::from_flags("[WPA2-EAP+FT/EAP-CCMP][ESS]"),
+            WifiSecurity::WPA2Enterprise
+        );
+        let owe = WifiSecurity::from_flags("[WPA2-OWE-CCMP][ESS]");
+        assert_eq!(owe, WifiSecurity::OWE);
+        assert_eq!(owe.key_mgmt(), "OWE");
+        // The open half of an OWE transition pair
+        assert_eq!(
+            WifiSecurity::from_flags("[ESS][OWE-TRANS]"),
+            WifiSecurity::Open
+        );
+    }
+
+    #[test]
+    fn test_add_network_commands() {
+        let commands = add_network_commands("3", "Attic", Some("secret"), WifiSecurity::WPA2, true);
+        let lines: Vec<String> = commands.iter().map(|args| args.join(" ")).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "set_network 3 ssid \"Attic\"",
+                "set_network 3 key_mgmt WPA-PSK",
+                "set_network 3 scan_ssid 1",
+                "set_network 3 psk \"secret\"",
+                "enable_network 3",
+                "select_network 3",
+            ]
+        );
+
+        // Broadcast networks aren't probed for
+        let commands = add_network_commands("0", "Cafe", None, WifiSecurity::Open, false);
+        assert!(
+            !commands
+                .iter()
+                .any(|args| args[2..].contains(&"scan_ssid".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_status() {
+        let output = "bssid=00:11:22:33:44:55\nfreq=2412\nssid=Home\n\
+            wpa_state=COMPLETED\nip_address=192.168.1.20\n";
+        let status = parse_status(output);
+        assert_eq!(status.state, ConnectionState::Connected);
+        assert_eq!(status.ssid.as_deref(), Some("Home"));
+        assert_eq!(status.frequency, Some(2412));
+        assert_eq!(status_value(output, "wpa_state"), Some("COMPLETED"));
+        assert_eq!(status_value(output, "id"), None);
+    }
+
+    #[test]
+    fn test_auth_failed() {
+        assert!(auth_failed("4WAY_HANDSHAKE", "DISCONNECTED"));
+        assert!(!auth_failed("4WAY_HANDSHAKE", "GROUP_HANDSHAKE"));
+        assert!(!auth_failed("ASSOCIATING", "DISCONNECTED"));
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let backoff = Duration::from_secs(2);
+        assert_eq!(retry_delay(backoff, 1), Duration::from_secs(2));
+        assert_eq!(retry_delay(backoff, 2), Duration::from_secs(4));
+        assert_eq!(retry_delay(backoff, 3), Duration::from_secs(8));
+        assert_eq!(retry_delay(backoff, 20), MAX_RETRY_DELAY);
     }
 
     #[test]
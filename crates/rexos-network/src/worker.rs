@@ -0,0 +1,378 @@
+//! Background network operations
+//!
+//! Scanning, connecting and pairing shell out to wpa_cli and bluetoothctl
+//! and can block for up to 30 seconds. The worker runs them on their own
+//! thread and reports progress over a channel, so the launcher's tick loop
+//! keeps drawing (e.g. "Connecting...") while they run. The blocking API on
+//! [`WifiManager`] and [`BluetoothManager`] stays available for CLI tools.
+
+use crate::{
+    BluetoothManager, ConnectionState, NetworkConfig, NetworkError, PairingState, WifiManager,
+    WifiNetwork,
+};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+/// Long-running operations the worker can run
+pub trait NetworkBackend: Send + 'static {
+    /// Scan for WiFi networks
+    fn scan(&mut self) -> Result<Vec<WifiNetwork>, NetworkError>;
+
+    /// Connect to a WiFi network, reporting each polled state
+    fn connect(
+        &mut self,
+        ssid: &str,
+        password: Option<&str>,
+        progress: &mut dyn FnMut(ConnectionState),
+    ) -> Result<(), NetworkError>;
+
+    /// Pair with a Bluetooth device, reporting each pairing state
+    fn pair(
+        &mut self,
+        address: &str,
+        progress: &mut dyn FnMut(PairingState),
+    ) -> Result<(), NetworkError>;
+}
+
+/// Backend using the system's wpa_supplicant and BlueZ
+struct SystemBackend {
+    wifi: WifiManager,
+    bluetooth: BluetoothManager,
+}
+
+impl NetworkBackend for SystemBackend {
+    fn scan(&mut self) -> Result<Vec<WifiNetwork>, NetworkError> {
+        self.wifi.scan()
+    }
+
+    fn connect(
+        &mut self,
+        ssid: &str,
+        password: Option<&str>,
+        progress: &mut dyn FnMut(ConnectionState),
+    ) -> Result<(), NetworkError> {
+        self.wifi.connect_with_progress(ssid, password, progress)
+    }
+
+    fn pair(
+        &mut self,
+        address: &str,
+        progress: &mut dyn FnMut(PairingState),
+    ) -> Result<(), NetworkError> {
+        self.bluetooth.pair_with_progress(address, progress)
+    }
+}
+
+/// A request to the network thread
+#[derive(Debug, Clone)]
+pub enum NetworkRequest {
+    /// Scan for WiFi networks
+    Scan,
+    /// Connect to a WiFi network
+    Connect {
+        ssid: String,
+        password: Option<String>,
+    },
+    /// Pair with a Bluetooth device
+    Pair(String),
+}
+
+/// Progress and results from the network threads
+#[derive(Debug)]
+pub enum NetworkEvent {
+    /// A scan started
+    Scanning,
+    /// Result of [`NetworkRequest::Scan`]
+    ScanFinished(Result<Vec<WifiNetwork>, NetworkError>),
+    /// Connection state while associating
+    Connecting {
+        ssid: String,
+        state: ConnectionState,
+    },
+    /// Result of [`NetworkRequest::Connect`]
+    Connected {
+        ssid: String,
+        result: Result<(), NetworkError>,
+    },
+    /// Pairing started
+    Pairing(String),
+    /// Pairing state while pairing, including passkeys to confirm
+    PairingProgress {
+        address: String,
+        state: PairingState,
+    },
+    /// Result of [`NetworkRequest::Pair`]
+    Paired {
+        address: String,
+        result: Result<(), NetworkError>,
+    },
+    /// WiFi connection state changed, from
+    /// [`NetworkManager::connection_events`](crate::NetworkManager::connection_events)
+    ///
+    /// `ssid` names the network that dropped on a disconnect.
+    WifiStateChanged {
+        ssid: Option<String>,
+        state: ConnectionState,
+    },
+    /// DHCP assigned an address
+    IpAcquired(String),
+    /// The address was released
+    IpLost,
+    /// A Bluetooth device connected
+    BluetoothConnected(String),
+    /// A Bluetooth device disconnected
+    BluetoothDisconnected(String),
+}
+
+/// Network operations running on a background thread
+pub struct NetworkWorker {
+    requests: Sender<NetworkRequest>,
+    events: Receiver<NetworkEvent>,
+    /// Confirms passkeys while the worker thread is blocked pairing
+    bluetooth: Option<BluetoothManager>,
+}
+
+impl NetworkWorker {
+    /// Start a worker using the system's WiFi and Bluetooth
+    pub fn new(config: &NetworkConfig) -> Result<Self, NetworkError> {
+        let wifi = WifiManager::new(
+            config.wifi_interface.clone(),
+            config.wpa_socket.clone(),
+            config.wpa_config.clone(),
+        )?
+        .with_scan_wait(config.scan_wait)
+        .with_scan_cache(config.scan_cache)
+        .with_connect_retries(config.connect_attempts, config.retry_backoff);
+        let bluetooth =
+            BluetoothManager::new(config.bt_interface.clone())?.with_agent(config.bt_agent);
+        let confirmer = bluetooth.confirmer();
+
+        let mut worker = Self::spawn(SystemBackend { wifi, bluetooth });
+        worker.bluetooth = Some(confirmer);
+        Ok(worker)
+    }
+
+    /// Move a backend onto a new thread
+    ///
+    /// The thread isn't joined on drop; it exits once the operation in
+    /// progress finishes, so shutting down never waits on a slow connect.
+    pub fn spawn(mut backend: impl NetworkBackend) -> Self {
+        let (request_tx, request_rx) = mpsc::channel::<NetworkRequest>();
+        let (event_tx, event_rx) = mpsc::channel();
+
+        thread::Builder::new()
+            .name("rexos-network".to_string())
+            .spawn(move || {
+                for request in request_rx {
+                    if handle_request(&mut backend, request, &event_tx).is_err() {
+                        break;
+                    }
+                }
+            })
+            .expect("failed to spawn network thread");
+
+        Self {
+            requests: request_tx,
+            events: event_rx,
+            bluetooth: None,
+        }
+    }
+
+    /// Queue a request
+    pub fn send(&self, request: NetworkRequest) -> Result<(), NetworkError> {
+        self.requests
+            .send(request)
+            .map_err(|_| NetworkError::CommandFailed("Network thread stopped".to_string()))
+    }
+
+    /// Start a WiFi scan
+    pub fn scan(&self) -> Result<(), NetworkError> {
+        self.send(NetworkRequest::Scan)
+    }
+
+    /// Start connecting to a WiFi network
+    pub fn connect(&self, ssid: &str, password: Option<&str>) -> Result<(), NetworkError> {
+        self.send(NetworkRequest::Connect {
+            ssid: ssid.to_string(),
+            password: password.map(str::to_string),
+        })
+    }
+
+    /// Start pairing with a Bluetooth device
+    pub fn pair(&self, address: &str) -> Result<(), NetworkError> {
+        self.send(NetworkRequest::Pair(address.to_string()))
+    }
+
+    /// Confirm the passkey reported by [`PairingState::AwaitingConfirmation`]
+    pub fn confirm_pairing(&self, address: &str) -> Result<(), NetworkError> {
+        let bluetooth = self
+            .bluetooth
+            .as_ref()
+            .ok_or(NetworkError::BluetoothNotAvailable)?;
+        bluetooth.confirm_pairing(address);
+        Ok(())
+    }
+
+    /// Get the next event without blocking
+    pub fn try_recv(&self) -> Option<NetworkEvent> {
+        self.events.try_recv().ok()
+    }
+}
+
+/// Run one request, stopping early if the receiver is gone
+fn handle_request(
+    backend: &mut impl NetworkBackend,
+    request: NetworkRequest,
+    events: &Sender<NetworkEvent>,
+) -> Result<(), mpsc::SendError<NetworkEvent>> {
+    match request {
+        NetworkRequest::Scan => {
+            events.send(NetworkEvent::Scanning)?;
+            events.send(NetworkEvent::ScanFinished(backend.scan()))
+        }
+        NetworkRequest::Connect { ssid, password } => {
+            events.send(NetworkEvent::Connecting {
+                ssid: ssid.clone(),
+                state: ConnectionState::Connecting,
+            })?;
+            let result = backend.connect(&ssid, password.as_deref(), &mut |state| {
+                let _ = events.send(NetworkEvent::Connecting {
+                    ssid: ssid.clone(),
+                    state,
+                });
+            });
+            events.send(NetworkEvent::Connected { ssid, result })
+        }
+        NetworkRequest::Pair(address) => {
+            events.send(NetworkEvent::Pairing(address.clone()))?;
+            let result = backend.pair(&address, &mut |state| {
+                let _ = events.send(NetworkEvent::PairingProgress {
+                    address: address.clone(),
+                    state,
+                });
+            });
+            events.send(NetworkEvent::Paired { address, result })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WifiSecurity;
+    use std::time::Duration;
+
+    struct FakeBackend;
+
+    impl NetworkBackend for FakeBackend {
+        fn scan(&mut self) -> Result<Vec<WifiNetwork>, NetworkError> {
+            Ok(vec![WifiNetwork {
+                ssid: "Home".to_string(),
+                bssid: "00:11:22:33:44:55".to_string(),
+                signal: 80,
+                frequency: 2412,
+                security: WifiSecurity::WPA2,
+                saved: true,
+                connected: false,
+            }])
+        }
+
+        fn connect(
+            &mut self,
+            ssid: &str,
+            _password: Option<&str>,
+            progress: &mut dyn FnMut(ConnectionState),
+        ) -> Result<(), NetworkError> {
+            progress(ConnectionState::Connecting);
+            if ssid == "Home" {
+                progress(ConnectionState::Connected);
+                Ok(())
+            } else {
+                Err(NetworkError::AuthenticationFailed)
+            }
+        }
+
+        fn pair(
+            &mut self,
+            address: &str,
+            progress: &mut dyn FnMut(PairingState),
+        ) -> Result<(), NetworkError> {
+            progress(PairingState::AwaitingConfirmation(123456));
+            progress(PairingState::Failed);
+            Err(NetworkError::PairingFailed(address.to_string()))
+        }
+    }
+
+    fn next(worker: &NetworkWorker) -> NetworkEvent {
+        worker.events.recv_timeout(Duration::from_secs(5)).unwrap()
+    }
+
+    #[test]
+    fn test_scan_reports_progress() {
+        let worker = NetworkWorker::spawn(FakeBackend);
+        worker.scan().unwrap();
+
+        assert!(matches!(next(&worker), NetworkEvent::Scanning));
+        match next(&worker) {
+            NetworkEvent::ScanFinished(Ok(networks)) => assert_eq!(networks[0].ssid, "Home"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_connect_reports_states() {
+        let worker = NetworkWorker::spawn(FakeBackend);
+        worker.connect("Home", Some("secret")).unwrap();
+
+        let mut states = Vec::new();
+        loop {
+            match next(&worker) {
+                NetworkEvent::Connecting { state, .. } => states.push(state),
+                NetworkEvent::Connected { ssid, result } => {
+                    assert_eq!(ssid, "Home");
+                    assert!(result.is_ok());
+                    break;
+                }
+                other => panic!("unexpected event: {:?}", other),
+            }
+        }
+        assert_eq!(states.last(), Some(&ConnectionState::Connected));
+
+        worker.connect("Cafe", None).unwrap();
+        loop {
+            if let NetworkEvent::Connected { result, .. } = next(&worker) {
+                assert!(matches!(result, Err(NetworkError::AuthenticationFailed)));
+                break;
+            }
+        }
+    }
+
+    #[test]
+    fn test_pair_failure() {
+        let worker = NetworkWorker::spawn(FakeBackend);
+        worker.pair("AA:BB:CC:DD:EE:FF").unwrap();
+
+        assert!(matches!(next(&worker), NetworkEvent::Pairing(_)));
+        assert!(matches!(
+            next(&worker),
+            NetworkEvent::PairingProgress {
+                state: PairingState::AwaitingConfirmation(123456),
+                ..
+            }
+        ));
+        assert!(matches!(
+            next(&worker),
+            NetworkEvent::PairingProgress {
+                state: PairingState::Failed,
+                ..
+            }
+        ));
+        assert!(matches!(
+            next(&worker),
+            NetworkEvent::Paired {
+                result: Err(NetworkError::PairingFailed(_)),
+                ..
+            }
+        ));
+    }
+}
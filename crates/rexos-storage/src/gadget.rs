@@ -0,0 +1,253 @@
+//! USB mass storage gadget
+//!
+//! Exports the ROMs partition (or a disk image) to a connected PC through
+//! the configfs USB gadget interface. The partition is unmounted locally
+//! while exported so it's never written from both sides, and remounted
+//! when the gadget is disabled.
+
+use crate::{MountManager, MountPoint, StorageError};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// configfs USB gadget directory
+pub const CONFIGFS_GADGET_DIR: &str = "/sys/kernel/config/usb_gadget";
+
+/// USB device controllers available to gadgets
+pub const UDC_DIR: &str = "/sys/class/udc";
+
+/// Linux Foundation vendor id with the multifunction composite product id
+const VENDOR_ID: &str = "0x1d6b";
+const PRODUCT_ID: &str = "0x0104";
+
+/// Mass storage gadget exporting a block device or image
+pub struct UsbGadget {
+    backing: PathBuf,
+    gadget_dir: PathBuf,
+    udc_dir: PathBuf,
+    /// Local mount to restore on disable
+    unmounted: Option<MountPoint>,
+    enabled: bool,
+}
+
+impl UsbGadget {
+    /// Gadget exporting a block device or image file
+    pub fn new(backing: impl Into<PathBuf>) -> Self {
+        Self {
+            backing: backing.into(),
+            gadget_dir: PathBuf::from(CONFIGFS_GADGET_DIR).join("rexos"),
+            udc_dir: PathBuf::from(UDC_DIR),
+            unmounted: None,
+            enabled: false,
+        }
+    }
+
+    /// Use a different gadget directory under configfs
+    pub fn with_gadget_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.gadget_dir = dir.into();
+        self
+    }
+
+    /// Use a different UDC class directory
+    pub fn with_udc_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.udc_dir = dir.into();
+        self
+    }
+
+    /// First USB device controller, if the device has one
+    pub fn udc(&self) -> Option<String> {
+        let mut names: Vec<String> = fs::read_dir(&self.udc_dir)
+            .ok()?
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names.into_iter().next()
+    }
+
+    /// Check if the device can act as a USB gadget
+    pub fn is_supported(&self) -> bool {
+        self.gadget_dir.parent().is_some_and(Path::exists) && self.udc().is_some()
+    }
+
+    /// Check if the gadget is exported
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Unmount the backing device locally and export it over USB
+    pub fn enable(&mut self, mounts: &mut MountManager) -> Result<(), StorageError> {
+        if self.enabled {
+            return Ok(());
+        }
+        let Some(udc) = self.udc() else {
+            return Err(StorageError::Gadget("No USB device controller".to_string()));
+        };
+        if !self.is_supported() {
+            return Err(StorageError::Gadget(
+                "configfs gadgets not available".to_string(),
+            ));
+        }
+
+        mounts.refresh().ok();
+        let device = self.backing.to_string_lossy().to_string();
+        if let Some(mount) = mounts.find_device(&device).cloned() {
+            mounts
+                .safe_unmount(&mount.mount_point)
+                .map_err(|e| StorageError::MountFailed(e.to_string()))?;
+            self.unmounted = Some(mount);
+        }
+
+        if let Err(e) = self.configure(&udc) {
+            tracing::error!("Failed to configure USB gadget: {}", e);
+            self.teardown();
+            self.remount(mounts);
+            return Err(e);
+        }
+
+        self.enabled = true;
+        tracing::info!("Exporting {} over USB ({})", self.backing.display(), udc);
+        Ok(())
+    }
+
+    /// Stop exporting and remount the backing device locally
+    pub fn disable(&mut self, mounts: &mut MountManager) -> Result<(), StorageError> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        // Unbinding detaches the host before the backing file is released
+        fs::write(self.gadget_dir.join("UDC"), "\n")?;
+        self.teardown();
+        self.enabled = false;
+        tracing::info!("Stopped exporting {} over USB", self.backing.display());
+
+        self.remount(mounts);
+        Ok(())
+    }
+
+    /// Create the gadget in configfs and bind it to a controller
+    fn configure(&self, udc: &str) -> Result<(), StorageError> {
+        let dir = &self.gadget_dir;
+        let strings = dir.join("strings/0x409");
+        let config = dir.join("configs/c.1");
+        let function = dir.join("functions/mass_storage.usb0");
+        let lun = function.join("lun.0");
+
+        fs::create_dir_all(&strings)?;
+        fs::create_dir_all(config.join("strings/0x409"))?;
+        fs::create_dir_all(&lun)?;
+
+        fs::write(dir.join("idVendor"), VENDOR_ID)?;
+        fs::write(dir.join("idProduct"), PRODUCT_ID)?;
+        fs::write(dir.join("bcdUSB"), "0x0200")?;
+        fs::write(strings.join("manufacturer"), "RexOS")?;
+        fs::write(strings.join("product"), "RexOS ROMs")?;
+        fs::write(strings.join("serialnumber"), "0123456789")?;
+        fs::write(config.join("strings/0x409/configuration"), "Mass Storage")?;
+        fs::write(config.join("MaxPower"), "250")?;
+
+        fs::write(lun.join("removable"), "1")?;
+        fs::write(lun.join("ro"), "0")?;
+        fs::write(
+            lun.join("file"),
+            self.backing.as_os_str().as_encoded_bytes(),
+        )?;
+
+        let link = config.join("mass_storage.usb0");
+        if fs::symlink_metadata(&link).is_err() {
+            std::os::unix::fs::symlink(&function, &link)?;
+        }
+
+        fs::write(dir.join("UDC"), udc)?;
+        Ok(())
+    }
+
+    /// Remove the gadget from configfs
+    ///
+    /// configfs drops attribute files along with their directory, so only
+    /// directories and the function link are removed, innermost first.
+    fn teardown(&self) {
+        let dir = &self.gadget_dir;
+        let _ = fs::remove_file(dir.join("configs/c.1/mass_storage.usb0"));
+        for sub in [
+            "configs/c.1/strings/0x409",
+            "configs/c.1",
+            "functions/mass_storage.usb0",
+            "strings/0x409",
+            "",
+        ] {
+            if let Err(e) = fs::remove_dir(dir.join(sub)) {
+                tracing::debug!("Failed to remove gadget {}: {}", sub, e);
+            }
+        }
+    }
+
+    /// Restore the local mount taken down by [`enable`](Self::enable)
+    fn remount(&mut self, mounts: &mut MountManager) {
+        let Some(mount) = self.unmounted.take() else {
+            return;
+        };
+
+        let options: Vec<&str> = mount.options.iter().map(String::as_str).collect();
+        if let Err(e) = mounts.mount(
+            &mount.device,
+            &mount.mount_point,
+            Some(&mount.filesystem),
+            &options,
+        ) {
+            tracing::error!("Failed to remount {}: {}", mount.device, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unsupported_without_udc() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut gadget = UsbGadget::new(dir.path().join("roms.img"))
+            .with_gadget_dir(dir.path().join("usb_gadget/rexos"))
+            .with_udc_dir(dir.path().join("udc"));
+
+        assert!(!gadget.is_supported());
+        assert!(gadget.enable(&mut MountManager::new()).is_err());
+        assert!(!gadget.is_enabled());
+    }
+
+    #[test]
+    fn test_enable_writes_gadget() {
+        let dir = tempfile::tempdir().unwrap();
+        let image = dir.path().join("roms.img");
+        fs::write(&image, b"").unwrap();
+        fs::create_dir_all(dir.path().join("usb_gadget")).unwrap();
+        fs::create_dir_all(dir.path().join("udc/ff400000.usb")).unwrap();
+
+        let gadget_dir = dir.path().join("usb_gadget/rexos");
+        let mut gadget = UsbGadget::new(&image)
+            .with_gadget_dir(&gadget_dir)
+            .with_udc_dir(dir.path().join("udc"));
+        let mut mounts = MountManager::new();
+
+        assert!(gadget.is_supported());
+        gadget.enable(&mut mounts).unwrap();
+        assert!(gadget.is_enabled());
+
+        let read = |path: &str| fs::read_to_string(gadget_dir.join(path)).unwrap();
+        assert_eq!(read("UDC"), "ff400000.usb");
+        assert_eq!(
+            read("functions/mass_storage.usb0/lun.0/file"),
+            image.to_string_lossy()
+        );
+        assert!(
+            gadget_dir
+                .join("configs/c.1/mass_storage.usb0/lun.0")
+                .exists()
+        );
+
+        gadget.disable(&mut mounts).unwrap();
+        assert!(!gadget.is_enabled());
+        assert!(fs::symlink_metadata(gadget_dir.join("configs/c.1/mass_storage.usb0")).is_err());
+    }
+}
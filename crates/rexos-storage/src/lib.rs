@@ -10,13 +10,20 @@
 //! - Partition 2: ROMs (exFAT) - Games, BIOS files, saves
 //! - Optional: Secondary SD card for additional storage
 
+mod gadget;
 mod mount;
 mod partition;
+mod usage;
 mod watcher;
 
-pub use mount::{MountError, MountManager, MountPoint};
+pub use gadget::{CONFIGFS_GADGET_DIR, UDC_DIR, UsbGadget};
+pub use mount::{
+    MountError, MountManager, MountPoint, RepairOutcome, default_mount_options, detect_filesystem,
+    open_pids,
+};
 pub use partition::{Partition, PartitionInfo, StorageDevice};
-pub use watcher::{StorageEvent, StorageWatcher};
+pub use usage::StorageUsage;
+pub use watcher::{SYS_BLOCK_DIR, StorageEvent, StorageWatcher};
 
 use std::path::PathBuf;
 use thiserror::Error;
@@ -32,6 +39,12 @@ pub enum StorageError {
     #[error("Partition error: {0}")]
     PartitionError(String),
 
+    #[error("Not mounted: {0}")]
+    NotMounted(String),
+
+    #[error("USB gadget error: {0}")]
+    Gadget(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
@@ -99,6 +112,21 @@ impl Paths {
         self.roms.join(system)
     }
 
+    /// Directories holding a system's ROMs, primary card first
+    ///
+    /// On two-card setups the secondary card's directory for the system is
+    /// included when it exists. Saves stay on the primary card either way.
+    pub fn system_rom_dirs(&self, system: &str) -> Vec<PathBuf> {
+        let mut dirs = vec![self.system_roms(system)];
+        if let Some(roms2) = &self.roms2 {
+            let dir = roms2.join(system);
+            if dir.is_dir() {
+                dirs.push(dir);
+            }
+        }
+        dirs
+    }
+
     /// Get the save path for a specific system
     pub fn system_saves(&self, system: &str) -> PathBuf {
         self.saves.join(system)
@@ -136,6 +164,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_system_rom_dirs() {
+        let roms2 = tempfile::tempdir().unwrap();
+        std::fs::create_dir(roms2.path().join("snes")).unwrap();
+        let paths = Paths {
+            roms2: Some(roms2.path().to_path_buf()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            paths.system_rom_dirs("snes"),
+            vec![PathBuf::from("/roms/snes"), roms2.path().join("snes")]
+        );
+        assert_eq!(
+            paths.system_rom_dirs("gba"),
+            vec![PathBuf::from("/roms/gba")]
+        );
+    }
+
     #[test]
     fn test_storage_error_display() {
         let err = StorageError::MountFailed("test mount error".to_string());
@@ -2,25 +2,44 @@
 
 use crate::StorageError;
 use std::collections::HashMap;
-use std::fs;
+use std::fs::{self, File};
+use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum MountError {
-    #[error("Failed to mount {device} at {mount_point}: {reason}")]
+    #[error("Failed to mount {device} ({filesystem}) at {mount_point}: {reason}")]
     MountFailed {
         device: String,
         mount_point: String,
+        /// Filesystem type tried, or "auto" if it couldn't be detected
+        filesystem: String,
         reason: String,
     },
 
     #[error("Failed to unmount {mount_point}: {reason}")]
     UnmountFailed { mount_point: String, reason: String },
 
-    #[error("Mount point busy: {0}")]
-    Busy(String),
+    #[error("Mount point busy: {mount_point} (open in processes {pids:?})")]
+    Busy {
+        mount_point: String,
+        /// Processes with files open under the mount point (empty if the
+        /// kernel reported it busy but no process was found)
+        pids: Vec<u32>,
+    },
+}
+
+/// Result of repairing a filesystem before retrying its mount
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RepairOutcome {
+    /// fsck fixed the filesystem or found it clean
+    Repaired,
+    /// fsck ran but left errors (or couldn't run)
+    Failed(String),
 }
 
 /// Information about a mount point
@@ -32,18 +51,68 @@ pub struct MountPoint {
     pub options: Vec<String>,
 }
 
+impl MountPoint {
+    /// Check if the filesystem is mounted read-only
+    ///
+    /// exFAT/vfat cards can come back read-only after an unclean eject.
+    pub fn is_read_only(&self) -> bool {
+        self.options.iter().any(|option| option == "ro")
+    }
+}
+
+/// Owner of files on FAT and exFAT cards, which have no permissions of
+/// their own (ArkOS's `ark` user)
+const CARD_UID: u32 = 1000;
+const CARD_GID: u32 = 1000;
+
+/// Mount options used for a filesystem when none are given
+pub fn default_mount_options(filesystem: &str) -> Vec<String> {
+    match filesystem {
+        "exfat" | "vfat" | "fat" | "msdos" => vec![
+            "rw".into(),
+            "noatime".into(),
+            format!("uid={}", CARD_UID),
+            format!("gid={}", CARD_GID),
+            "fmask=0022".into(),
+            "dmask=0022".into(),
+        ],
+        "ext2" | "ext3" | "ext4" => vec!["rw".into(), "noatime".into(), "errors=remount-ro".into()],
+        _ => vec!["rw".into(), "noatime".into()],
+    }
+}
+
 /// Manages mount operations
 pub struct MountManager {
     mounts: HashMap<PathBuf, MountPoint>,
+    /// Mount options replacing the defaults, by filesystem type
+    option_overrides: HashMap<String, Vec<String>>,
 }
 
 impl MountManager {
     pub fn new() -> Self {
         Self {
             mounts: HashMap::new(),
+            option_overrides: HashMap::new(),
         }
     }
 
+    /// Replace the default mount options for a filesystem type
+    pub fn with_mount_options(mut self, filesystem: &str, options: &[&str]) -> Self {
+        self.option_overrides.insert(
+            filesystem.to_string(),
+            options.iter().map(|o| o.to_string()).collect(),
+        );
+        self
+    }
+
+    /// Mount options used for a filesystem when none are given
+    pub fn mount_options(&self, filesystem: &str) -> Vec<String> {
+        self.option_overrides
+            .get(filesystem)
+            .cloned()
+            .unwrap_or_else(|| default_mount_options(filesystem))
+    }
+
     /// Read current mount points from /proc/mounts
     pub fn refresh(&mut self) -> Result<(), StorageError> {
         self.mounts.clear();
@@ -86,7 +155,24 @@ impl MountManager {
         self.mounts.get(path)
     }
 
+    /// Find the mount containing a path (the deepest matching mount point)
+    pub fn find_mount(&self, path: &Path) -> Option<&MountPoint> {
+        self.mounts
+            .values()
+            .filter(|m| path.starts_with(&m.mount_point))
+            .max_by_key(|m| m.mount_point.components().count())
+    }
+
+    /// Check if the filesystem holding a path is mounted read-only
+    pub fn is_read_only(&self, path: &Path) -> bool {
+        self.find_mount(path).is_some_and(MountPoint::is_read_only)
+    }
+
     /// Mount a device at a mount point
+    ///
+    /// Without a filesystem type it is detected from the device, and
+    /// without options the filesystem's defaults (or overrides set with
+    /// [`with_mount_options`](Self::with_mount_options)) are used.
     pub fn mount(
         &mut self,
         device: &str,
@@ -94,21 +180,35 @@ impl MountManager {
         filesystem: Option<&str>,
         options: &[&str],
     ) -> Result<(), MountError> {
+        let filesystem = filesystem
+            .map(String::from)
+            .or_else(|| detect_filesystem(device));
+        let failed = |reason: String| MountError::MountFailed {
+            device: device.to_string(),
+            mount_point: mount_point.display().to_string(),
+            filesystem: filesystem.clone().unwrap_or_else(|| "auto".to_string()),
+            reason,
+        };
+
         // Create mount point if it doesn't exist
         if !mount_point.exists() {
-            fs::create_dir_all(mount_point).map_err(|e| MountError::MountFailed {
-                device: device.to_string(),
-                mount_point: mount_point.display().to_string(),
-                reason: e.to_string(),
-            })?;
+            fs::create_dir_all(mount_point).map_err(|e| failed(e.to_string()))?;
         }
 
         let mut cmd = Command::new("mount");
 
-        if let Some(fs) = filesystem {
+        if let Some(fs) = &filesystem {
             cmd.args(["-t", fs]);
         }
 
+        let options = if options.is_empty() {
+            filesystem
+                .as_deref()
+                .map(|fs| self.mount_options(fs))
+                .unwrap_or_default()
+        } else {
+            options.iter().map(|o| o.to_string()).collect()
+        };
         if !options.is_empty() {
             cmd.args(["-o", &options.join(",")]);
         }
@@ -116,30 +216,134 @@ impl MountManager {
         cmd.arg(device);
         cmd.arg(mount_point);
 
-        let output = cmd.output().map_err(|e| MountError::MountFailed {
-            device: device.to_string(),
-            mount_point: mount_point.display().to_string(),
-            reason: e.to_string(),
-        })?;
+        let output = cmd.output().map_err(|e| failed(e.to_string()))?;
 
         if !output.status.success() {
+            return Err(failed(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+
+        // Refresh to get updated mount info
+        self.refresh().ok();
+
+        tracing::info!(
+            "Mounted {} ({}) at {}",
+            device,
+            filesystem.as_deref().unwrap_or("auto"),
+            mount_point.display()
+        );
+        Ok(())
+    }
+
+    /// Mount a device, repairing it with fsck and retrying once on failure
+    ///
+    /// Repair is only attempted on removable media (SD cards, USB) that
+    /// aren't holding the system. Returns the repair outcome if one was
+    /// attempted.
+    pub fn mount_with_repair(
+        &mut self,
+        device: &str,
+        mount_point: &Path,
+        filesystem: Option<&str>,
+        options: &[&str],
+        dry_run: bool,
+    ) -> Result<Option<RepairOutcome>, MountError> {
+        let first = match self.mount(device, mount_point, filesystem, options) {
+            Ok(()) => return Ok(None),
+            Err(e) => e,
+        };
+
+        let Some((program, args)) = self.repair_command(device, mount_point, filesystem) else {
+            tracing::warn!("Not repairing {}: not removable media or no fsck", device);
+            return Err(first);
+        };
+
+        let command = format!("{} {} {}", program, args.join(" "), device);
+        if dry_run {
+            tracing::info!("Dry run: would run {}", command);
             return Err(MountError::MountFailed {
                 device: device.to_string(),
                 mount_point: mount_point.display().to_string(),
-                reason: String::from_utf8_lossy(&output.stderr).to_string(),
+                filesystem: mount_error_filesystem(&first),
+                reason: format!("{}; dry run, would run {}", first, command),
             });
         }
 
-        // Refresh to get updated mount info
-        self.refresh().ok();
+        tracing::warn!(
+            "Mount of {} failed ({}), running {}",
+            device,
+            first,
+            command
+        );
+        let outcome = match Command::new(program).args(args).arg(device).status() {
+            // 0 = clean, 1 = errors corrected; anything higher is left broken
+            Ok(status) if matches!(status.code(), Some(0 | 1)) => RepairOutcome::Repaired,
+            Ok(status) => RepairOutcome::Failed(format!("{} exited with {}", program, status)),
+            Err(e) => RepairOutcome::Failed(format!("{}: {}", program, e)),
+        };
+        tracing::info!("Repair of {}: {:?}", device, outcome);
 
-        tracing::info!("Mounted {} at {}", device, mount_point.display());
-        Ok(())
+        if let RepairOutcome::Failed(reason) = &outcome {
+            return Err(MountError::MountFailed {
+                device: device.to_string(),
+                mount_point: mount_point.display().to_string(),
+                filesystem: mount_error_filesystem(&first),
+                reason: format!("repair failed: {}", reason),
+            });
+        }
+
+        self.mount(device, mount_point, filesystem, options)?;
+        Ok(Some(outcome))
+    }
+
+    /// fsck program and arguments to repair a device, if it qualifies
+    fn repair_command(
+        &self,
+        device: &str,
+        mount_point: &Path,
+        filesystem: Option<&str>,
+    ) -> Option<(&'static str, &'static [&'static str])> {
+        if !self.may_repair(device, mount_point) {
+            return None;
+        }
+        match filesystem {
+            Some(fs) => fsck_command(fs),
+            None => fsck_command(&detect_filesystem(device)?),
+        }
+    }
+
+    /// Check if a device may be repaired automatically
+    ///
+    /// Only SD card and USB partitions qualify, and never one that holds
+    /// the root or boot filesystem.
+    fn may_repair(&self, device: &str, mount_point: &Path) -> bool {
+        let removable = device.starts_with("/dev/mmcblk") || device.starts_with("/dev/sd");
+        let system_mount = |path: &Path| {
+            ["/", "/boot", "/usr"]
+                .iter()
+                .any(|system| path == Path::new(system))
+        };
+
+        removable
+            && !system_mount(mount_point)
+            && !self
+                .mounts
+                .values()
+                .any(|m| m.device == device && system_mount(&m.mount_point))
     }
 
     /// Unmount a mount point
     pub fn unmount(&mut self, mount_point: &Path) -> Result<(), MountError> {
-        let output = Command::new("umount")
+        self.umount(mount_point, false)
+    }
+
+    fn umount(&mut self, mount_point: &Path, lazy: bool) -> Result<(), MountError> {
+        let mut cmd = Command::new("umount");
+        if lazy {
+            cmd.arg("-l");
+        }
+        let output = cmd
             .arg(mount_point)
             .output()
             .map_err(|e| MountError::UnmountFailed {
@@ -150,7 +354,10 @@ impl MountManager {
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
             if stderr.contains("busy") {
-                return Err(MountError::Busy(mount_point.display().to_string()));
+                return Err(MountError::Busy {
+                    mount_point: mount_point.display().to_string(),
+                    pids: open_pids(mount_point),
+                });
             }
             return Err(MountError::UnmountFailed {
                 mount_point: mount_point.display().to_string(),
@@ -163,6 +370,65 @@ impl MountManager {
         Ok(())
     }
 
+    /// Flush pending writes, then unmount if nothing has files open
+    ///
+    /// A filesystem still in use is reported as [`MountError::Busy`] with
+    /// the processes holding it, so it isn't handed to another writer (or
+    /// pulled out) while open. Once no process holds it, it is detached
+    /// lazily so a stray kernel reference can't block the eject.
+    pub fn safe_unmount(&mut self, mount_point: &Path) -> Result<(), MountError> {
+        // SAFETY: sync() takes no arguments and cannot fail
+        unsafe { libc::sync() };
+
+        let pids = open_pids(mount_point);
+        if !pids.is_empty() {
+            return Err(MountError::Busy {
+                mount_point: mount_point.display().to_string(),
+                pids,
+            });
+        }
+        self.umount(mount_point, true)
+    }
+
+    /// Like [`safe_unmount`](Self::safe_unmount), but asks the processes
+    /// holding the mount to exit
+    ///
+    /// Each is sent SIGTERM, then the mount is retried once they're gone
+    /// or `timeout` passes. This process is never signalled; files it
+    /// holds open still make the unmount fail.
+    pub fn force_unmount(
+        &mut self,
+        mount_point: &Path,
+        timeout: Duration,
+    ) -> Result<(), MountError> {
+        let pids = match self.safe_unmount(mount_point) {
+            Err(MountError::Busy { pids, .. }) if !pids.is_empty() => pids,
+            result => return result,
+        };
+
+        let own_pid = std::process::id();
+        for &pid in pids.iter().filter(|&&pid| pid != own_pid) {
+            tracing::warn!("Stopping process {} holding {}", pid, mount_point.display());
+            if let Err(e) = nix::sys::signal::kill(
+                nix::unistd::Pid::from_raw(pid as i32),
+                nix::sys::signal::Signal::SIGTERM,
+            ) {
+                tracing::debug!("Failed to signal {}: {}", pid, e);
+            }
+        }
+
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline && !open_pids(mount_point).is_empty() {
+            thread::sleep(Duration::from_millis(100));
+        }
+        self.safe_unmount(mount_point)
+    }
+
+    /// Find the mount of a device, if it's mounted
+    pub fn find_device(&self, device: &str) -> Option<&MountPoint> {
+        self.mounts.values().find(|m| m.device == device)
+    }
+
     /// Find mount points for removable storage (SD cards, USB)
     pub fn find_removable(&self) -> Vec<&MountPoint> {
         self.mounts
@@ -177,6 +443,91 @@ impl MountManager {
     }
 }
 
+/// Processes with a file, working directory or root under a mount point
+pub fn open_pids(mount_point: &Path) -> Vec<u32> {
+    open_pids_in(Path::new("/proc"), mount_point)
+}
+
+fn open_pids_in(proc_dir: &Path, mount_point: &Path) -> Vec<u32> {
+    let Ok(entries) = fs::read_dir(proc_dir) else {
+        return Vec::new();
+    };
+
+    let holds =
+        |link: PathBuf| fs::read_link(link).is_ok_and(|target| target.starts_with(mount_point));
+
+    let mut pids: Vec<u32> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+            let dir = entry.path();
+
+            // Processes can exit mid-scan; unreadable entries are skipped
+            let open_file = fs::read_dir(dir.join("fd"))
+                .map(|fds| fds.filter_map(|fd| fd.ok()).any(|fd| holds(fd.path())))
+                .unwrap_or(false);
+            (open_file || holds(dir.join("cwd")) || holds(dir.join("root"))).then_some(pid)
+        })
+        .collect();
+    pids.sort_unstable();
+    pids
+}
+
+/// Filesystem type tried by a failed mount
+fn mount_error_filesystem(error: &MountError) -> String {
+    match error {
+        MountError::MountFailed { filesystem, .. } => filesystem.clone(),
+        _ => "auto".to_string(),
+    }
+}
+
+/// Filesystem type of a device
+///
+/// Asks blkid, falling back to reading the superblock on images without it.
+pub fn detect_filesystem(device: &str) -> Option<String> {
+    let blkid = Command::new("blkid")
+        .args(["-o", "value", "-s", "TYPE", device])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|fstype| !fstype.is_empty());
+
+    blkid.or_else(|| superblock_filesystem(Path::new(device)).map(String::from))
+}
+
+/// Filesystem type from the signatures in a device's first sectors
+fn superblock_filesystem(device: &Path) -> Option<&'static str> {
+    let mut header = vec![0u8; 2048];
+    let mut file = File::open(device).ok()?;
+    let read = file.read(&mut header).ok()?;
+    header.truncate(read);
+
+    if header.get(3..11) == Some(b"EXFAT   ") {
+        return Some("exfat");
+    }
+    // FAT32 and FAT12/16 keep their type string in different places
+    let fat16 = header.get(54..59);
+    if header.get(82..87) == Some(b"FAT32") || fat16 == Some(b"FAT16") || fat16 == Some(b"FAT12") {
+        return Some("vfat");
+    }
+    // ext2/3/4 magic; the ext4 driver mounts all three
+    if header.get(0x438..0x43a) == Some(&[0x53, 0xef]) {
+        return Some("ext4");
+    }
+    None
+}
+
+/// Non-interactive fsck program and arguments for a filesystem
+fn fsck_command(filesystem: &str) -> Option<(&'static str, &'static [&'static str])> {
+    match filesystem {
+        "exfat" => Some(("fsck.exfat", &["-y"])),
+        "vfat" | "fat" | "msdos" => Some(("fsck.vfat", &["-a"])),
+        "ext2" | "ext3" | "ext4" => Some(("e2fsck", &["-p"])),
+        _ => None,
+    }
+}
+
 impl Default for MountManager {
     fn default() -> Self {
         let mut manager = Self::new();
@@ -209,6 +560,120 @@ mod tests {
         assert_eq!(mount.options.len(), 2);
     }
 
+    #[test]
+    fn test_read_only_mount() {
+        let mut manager = MountManager::new();
+        for (device, path, options) in [
+            ("/dev/mmcblk0p2", "/", "rw,noatime"),
+            ("/dev/mmcblk0p3", "/roms", "ro,relatime,fmask=0022"),
+        ] {
+            manager.mounts.insert(
+                PathBuf::from(path),
+                MountPoint {
+                    device: device.to_string(),
+                    mount_point: PathBuf::from(path),
+                    filesystem: "exfat".to_string(),
+                    options: options.split(',').map(String::from).collect(),
+                },
+            );
+        }
+
+        assert!(manager.is_read_only(Path::new("/roms/.rexos/games.db")));
+        assert!(!manager.is_read_only(Path::new("/roms2/games.db")));
+        assert_eq!(
+            manager.find_mount(Path::new("/roms")).unwrap().device,
+            "/dev/mmcblk0p3"
+        );
+    }
+
+    #[test]
+    fn test_superblock_filesystem() {
+        let dir = tempfile::tempdir().unwrap();
+        let image = |name: &str, offset: usize, signature: &[u8]| {
+            let mut data = vec![0u8; 2048];
+            data[offset..offset + signature.len()].copy_from_slice(signature);
+            let path = dir.path().join(name);
+            fs::write(&path, data).unwrap();
+            path
+        };
+
+        let exfat = image("exfat.img", 3, b"EXFAT   ");
+        let fat32 = image("fat32.img", 82, b"FAT32   ");
+        let ext4 = image("ext4.img", 0x438, &[0x53, 0xef]);
+        let blank = image("blank.img", 0, &[]);
+
+        assert_eq!(superblock_filesystem(&exfat), Some("exfat"));
+        assert_eq!(superblock_filesystem(&fat32), Some("vfat"));
+        assert_eq!(superblock_filesystem(&ext4), Some("ext4"));
+        assert_eq!(superblock_filesystem(&blank), None);
+    }
+
+    #[test]
+    fn test_mount_options() {
+        assert!(default_mount_options("exfat").contains(&"noatime".to_string()));
+        assert!(default_mount_options("exfat").contains(&"uid=1000".to_string()));
+        assert!(default_mount_options("ext4").contains(&"errors=remount-ro".to_string()));
+
+        let manager = MountManager::new().with_mount_options("exfat", &["ro"]);
+        assert_eq!(manager.mount_options("exfat"), vec!["ro".to_string()]);
+        assert_eq!(manager.mount_options("ext4"), default_mount_options("ext4"));
+    }
+
+    #[test]
+    fn test_mount_error_names_filesystem() {
+        let err = MountError::MountFailed {
+            device: "/dev/mmcblk1p1".to_string(),
+            mount_point: "/roms2".to_string(),
+            filesystem: "exfat".to_string(),
+            reason: "unknown filesystem type 'exfat'".to_string(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "Failed to mount /dev/mmcblk1p1 (exfat) at /roms2: unknown filesystem type 'exfat'"
+        );
+    }
+
+    #[test]
+    fn test_fsck_command() {
+        assert_eq!(fsck_command("exfat"), Some(("fsck.exfat", &["-y"][..])));
+        assert_eq!(fsck_command("ext4").unwrap().0, "e2fsck");
+        assert_eq!(fsck_command("vfat").unwrap().0, "fsck.vfat");
+        assert!(fsck_command("squashfs").is_none());
+    }
+
+    #[test]
+    fn test_repair_only_removable_media() {
+        let mut manager = MountManager::new();
+        manager.mounts.insert(
+            PathBuf::from("/"),
+            MountPoint {
+                device: "/dev/mmcblk0p2".to_string(),
+                mount_point: PathBuf::from("/"),
+                filesystem: "ext4".to_string(),
+                options: vec!["rw".to_string()],
+            },
+        );
+
+        assert!(manager.may_repair("/dev/mmcblk1p1", Path::new("/roms")));
+        assert!(manager.may_repair("/dev/sda1", Path::new("/roms")));
+        assert!(!manager.may_repair("/dev/mmcblk0p2", Path::new("/roms")));
+        assert!(!manager.may_repair("/dev/mmcblk1p1", Path::new("/")));
+        assert!(!manager.may_repair("/dev/nvme0n1p1", Path::new("/roms")));
+
+        assert_eq!(
+            manager
+                .repair_command("/dev/mmcblk1p1", Path::new("/roms"), Some("exfat"))
+                .unwrap()
+                .0,
+            "fsck.exfat"
+        );
+        assert!(
+            manager
+                .repair_command("/dev/mmcblk0p2", Path::new("/roms"), Some("ext4"))
+                .is_none()
+        );
+    }
+
     #[test]
     fn test_is_mounted_empty() {
         let manager = MountManager::new();
@@ -233,10 +698,12 @@ mod tests {
         let err = MountError::MountFailed {
             device: "/dev/sda1".to_string(),
             mount_point: "/mnt/usb".to_string(),
+            filesystem: "exfat".to_string(),
             reason: "Permission denied".to_string(),
         };
         let msg = format!("{}", err);
         assert!(msg.contains("sda1"));
+        assert!(msg.contains("(exfat)"));
         assert!(msg.contains("Permission denied"));
 
         let err = MountError::UnmountFailed {
@@ -247,9 +714,40 @@ mod tests {
         assert!(msg.contains("unmount"));
         assert!(msg.contains("busy"));
 
-        let err = MountError::Busy("/mnt/usb".to_string());
+        let err = MountError::Busy {
+            mount_point: "/mnt/usb".to_string(),
+            pids: vec![42],
+        };
         let msg = format!("{}", err);
         assert!(msg.contains("busy"));
+        assert!(msg.contains("[42]"));
+    }
+
+    #[test]
+    fn test_open_pids() {
+        use std::os::unix::fs::symlink;
+
+        let proc_dir = tempfile::tempdir().unwrap();
+        let process = |pid: &str, links: &[(&str, &str)]| {
+            let dir = proc_dir.path().join(pid);
+            fs::create_dir_all(dir.join("fd")).unwrap();
+            for (name, target) in links {
+                symlink(target, dir.join(name)).unwrap();
+            }
+        };
+
+        process(
+            "100",
+            &[("fd/0", "/dev/null"), ("fd/3", "/roms2/psx/game.chd")],
+        );
+        process("200", &[("cwd", "/roms2/saves")]);
+        process("300", &[("cwd", "/roms"), ("fd/1", "/roms2-other/log")]);
+        process("self", &[("cwd", "/roms2")]);
+
+        assert_eq!(
+            open_pids_in(proc_dir.path(), Path::new("/roms2")),
+            vec![100, 200]
+        );
     }
 
     #[test]
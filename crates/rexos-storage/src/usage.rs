@@ -0,0 +1,128 @@
+//! Free space reporting
+
+use crate::{MountManager, Paths, StorageDevice, StorageError};
+use std::path::{Path, PathBuf};
+
+/// Space on a mounted filesystem
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StorageUsage {
+    /// Size of the filesystem
+    pub total_bytes: u64,
+    /// Bytes in use
+    pub used_bytes: u64,
+    /// Bytes free for unprivileged users (less than total - used when
+    /// blocks are reserved for root, as on ext4)
+    pub available_bytes: u64,
+}
+
+impl StorageUsage {
+    /// Usage of the filesystem mounted at `path`
+    pub fn at(path: &Path) -> Result<Self, StorageError> {
+        if !path.exists() {
+            return Err(StorageError::NotMounted(path.display().to_string()));
+        }
+        let stat = nix::sys::statvfs::statvfs(path).map_err(std::io::Error::from)?;
+
+        // Types vary by platform (u32 on 32-bit ARM, u64 elsewhere)
+        #[allow(clippy::useless_conversion)]
+        let (block_size, blocks, free, available) = (
+            u64::from(stat.fragment_size()),
+            u64::from(stat.blocks()),
+            u64::from(stat.blocks_free()),
+            u64::from(stat.blocks_available()),
+        );
+        Ok(Self {
+            total_bytes: blocks * block_size,
+            used_bytes: blocks.saturating_sub(free) * block_size,
+            available_bytes: available * block_size,
+        })
+    }
+
+    /// Percentage of the filesystem in use
+    pub fn percent_used(&self) -> f32 {
+        if self.total_bytes == 0 {
+            return 0.0;
+        }
+        self.used_bytes as f32 / self.total_bytes as f32 * 100.0
+    }
+
+    /// Combined usage of two filesystems
+    fn add(self, other: Self) -> Self {
+        Self {
+            total_bytes: self.total_bytes + other.total_bytes,
+            used_bytes: self.used_bytes + other.used_bytes,
+            available_bytes: self.available_bytes + other.available_bytes,
+        }
+    }
+}
+
+impl StorageDevice {
+    /// Combined usage of the device's mounted partitions
+    ///
+    /// Fails with [`StorageError::NotMounted`] if none are mounted, since
+    /// an unmounted card's free space can't be known.
+    pub fn usage(&self) -> Result<StorageUsage, StorageError> {
+        let mut mounts = MountManager::new();
+        mounts.refresh()?;
+
+        let mut usage = None;
+        for partition in &self.partitions {
+            let device = partition.path.to_string_lossy();
+            if let Some(mount) = mounts.find_device(&device) {
+                let partition_usage = StorageUsage::at(&mount.mount_point)?;
+                usage = Some(usage.map_or(partition_usage, |total: StorageUsage| {
+                    total.add(partition_usage)
+                }));
+            }
+        }
+        usage.ok_or_else(|| StorageError::NotMounted(self.path.display().to_string()))
+    }
+}
+
+impl Paths {
+    /// Usage of each ROMs location, `/roms` first
+    ///
+    /// The secondary card is only included while it is mounted; an empty
+    /// `/roms2` directory means no card rather than an error.
+    pub fn roms_usage(&self) -> Result<Vec<(PathBuf, StorageUsage)>, StorageError> {
+        let mut usage = vec![(self.roms.clone(), StorageUsage::at(&self.roms)?)];
+
+        if let Some(roms2) = &self.roms2 {
+            let mut mounts = MountManager::new();
+            mounts.refresh()?;
+            if mounts.is_mounted(roms2) {
+                usage.push((roms2.clone(), StorageUsage::at(roms2)?));
+            }
+        }
+        Ok(usage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_at() {
+        let dir = tempfile::tempdir().unwrap();
+        let usage = StorageUsage::at(dir.path()).unwrap();
+        assert!(usage.total_bytes > 0);
+        assert!(usage.used_bytes <= usage.total_bytes);
+        assert!(usage.available_bytes <= usage.total_bytes);
+        assert!((0.0..=100.0).contains(&usage.percent_used()));
+
+        let missing = StorageUsage::at(&dir.path().join("missing"));
+        assert!(matches!(missing, Err(StorageError::NotMounted(_))));
+    }
+
+    #[test]
+    fn test_percent_used() {
+        let usage = StorageUsage {
+            total_bytes: 200,
+            used_bytes: 50,
+            available_bytes: 150,
+        };
+        assert_eq!(usage.percent_used(), 25.0);
+        assert_eq!(StorageUsage::default().percent_used(), 0.0);
+    }
+}
@@ -1,10 +1,29 @@
 //! Storage event watcher for hotplug detection
+//!
+//! Polls `/sys/block` for SD cards and USB drives coming and going. A
+//! card's partitions and its disk node don't all show up at once, so a
+//! change has to hold for the settle time before an event is sent.
+//!
+//! To pick up a second SD card while running, react to
+//! [`StorageEvent::DeviceAdded`] by mounting its partition at `/roms2`
+//! and rescanning, and to [`StorageEvent::DeviceRemoved`] by dropping the
+//! games under it.
 
 use crate::StorageError;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::{Receiver, Sender, channel};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+/// Where the kernel lists block devices
+pub const SYS_BLOCK_DIR: &str = "/sys/block";
+
+/// How often `/sys/block` is checked
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long a change must hold before it is reported
+const DEFAULT_SETTLE_TIME: Duration = Duration::from_millis(1500);
 
 /// Events that can occur on storage devices
 #[derive(Debug, Clone)]
@@ -27,6 +46,9 @@ pub struct StorageWatcher {
     tx: Sender<StorageEvent>,
     rx: Receiver<StorageEvent>,
     running: bool,
+    sys_block: PathBuf,
+    poll_interval: Duration,
+    settle_time: Duration,
 }
 
 impl StorageWatcher {
@@ -36,61 +58,69 @@ impl StorageWatcher {
             tx,
             rx,
             running: false,
+            sys_block: PathBuf::from(SYS_BLOCK_DIR),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            settle_time: DEFAULT_SETTLE_TIME,
         }
     }
 
+    /// Watch a different block device directory (for testing)
+    pub fn with_sys_block(mut self, path: impl Into<PathBuf>) -> Self {
+        self.sys_block = path.into();
+        self
+    }
+
+    /// Set how often devices are checked
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Set how long a change must hold before it is reported
+    pub fn with_settle_time(mut self, settle_time: Duration) -> Self {
+        self.settle_time = settle_time;
+        self
+    }
+
     /// Start watching for storage events
+    ///
+    /// Devices present at start are taken as known; only later changes
+    /// are reported.
     pub fn start(&mut self) -> Result<(), StorageError> {
         if self.running {
             return Ok(());
         }
 
-        self.running = true;
+        let sys_block = self.sys_block.clone();
+        let mut tracker = DeviceTracker::new(block_devices(&sys_block)?, self.settle_time);
+        let poll_interval = self.poll_interval;
         let tx = self.tx.clone();
 
-        // Spawn thread to watch /dev for changes
-        // In production, this would use udev or inotify
         thread::spawn(move || {
             tracing::info!("Storage watcher started");
 
-            // Simple polling approach - production would use udev
-            let mut known_devices: std::collections::HashSet<PathBuf> =
-                std::collections::HashSet::new();
-
             loop {
-                // Check for mmcblk and sd devices
-                if let Ok(entries) = std::fs::read_dir("/dev") {
-                    let current_devices: std::collections::HashSet<PathBuf> = entries
-                        .filter_map(|e| e.ok())
-                        .filter(|e| {
-                            let name = e.file_name().to_string_lossy().to_string();
-                            (name.starts_with("mmcblk") || name.starts_with("sd"))
-                                && !name.contains('p')
-                        })
-                        .map(|e| e.path())
-                        .collect();
-
-                    // Check for new devices
-                    for device in current_devices.difference(&known_devices) {
-                        let _ = tx.send(StorageEvent::DeviceAdded {
-                            device: device.clone(),
-                        });
-                    }
+                thread::sleep(poll_interval);
 
-                    // Check for removed devices
-                    for device in known_devices.difference(&current_devices) {
-                        let _ = tx.send(StorageEvent::DeviceRemoved {
-                            device: device.clone(),
-                        });
+                let current = match block_devices(&sys_block) {
+                    Ok(current) => current,
+                    Err(e) => {
+                        tracing::warn!("Failed to list block devices: {}", e);
+                        continue;
                     }
+                };
 
-                    known_devices = current_devices;
+                for event in tracker.update(&current, Instant::now()) {
+                    tracing::info!("Storage event: {:?}", event);
+                    if tx.send(event).is_err() {
+                        // Watcher dropped
+                        return;
+                    }
                 }
-
-                thread::sleep(Duration::from_secs(2));
             }
         });
 
+        self.running = true;
         Ok(())
     }
 
@@ -121,6 +151,71 @@ impl Default for StorageWatcher {
     }
 }
 
+/// SD cards and USB drives listed in a `/sys/block` directory, as `/dev` paths
+///
+/// Partitions are listed under their disk, so only whole disks appear.
+fn block_devices(sys_block: &Path) -> Result<HashSet<PathBuf>, StorageError> {
+    let mut devices = HashSet::new();
+    for entry in std::fs::read_dir(sys_block)? {
+        let name = entry?.file_name().to_string_lossy().to_string();
+
+        // eMMC boot and RPMB areas aren't storage
+        let storage = (name.starts_with("mmcblk") || name.starts_with("sd"))
+            && !name.contains("boot")
+            && !name.contains("rpmb");
+        if storage {
+            devices.insert(Path::new("/dev").join(name));
+        }
+    }
+    Ok(devices)
+}
+
+/// Turns device listings into events once changes settle
+struct DeviceTracker {
+    /// Devices reported as present
+    known: HashSet<PathBuf>,
+    /// Devices whose presence differs from `known`, and since when
+    pending: HashMap<PathBuf, Instant>,
+    settle_time: Duration,
+}
+
+impl DeviceTracker {
+    fn new(known: HashSet<PathBuf>, settle_time: Duration) -> Self {
+        Self {
+            known,
+            pending: HashMap::new(),
+            settle_time,
+        }
+    }
+
+    /// Record the devices present at `now`, returning settled changes
+    fn update(&mut self, current: &HashSet<PathBuf>, now: Instant) -> Vec<StorageEvent> {
+        let changed: HashSet<PathBuf> =
+            current.symmetric_difference(&self.known).cloned().collect();
+
+        // A device back to its reported state didn't really change
+        self.pending.retain(|device, _| changed.contains(device));
+
+        let mut events = Vec::new();
+        for device in changed {
+            let since = *self.pending.entry(device.clone()).or_insert(now);
+            if now.duration_since(since) < self.settle_time {
+                continue;
+            }
+
+            self.pending.remove(&device);
+            if current.contains(&device) {
+                self.known.insert(device.clone());
+                events.push(StorageEvent::DeviceAdded { device });
+            } else {
+                self.known.remove(&device);
+                events.push(StorageEvent::DeviceRemoved { device });
+            }
+        }
+        events
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -224,6 +319,63 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_block_devices() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [
+            "mmcblk0",
+            "mmcblk0boot0",
+            "mmcblk0rpmb",
+            "mmcblk1",
+            "sda",
+            "loop0",
+        ] {
+            std::fs::create_dir(dir.path().join(name)).unwrap();
+        }
+
+        let devices = block_devices(dir.path()).unwrap();
+        let expected: HashSet<PathBuf> = ["/dev/mmcblk0", "/dev/mmcblk1", "/dev/sda"]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect();
+        assert_eq!(devices, expected);
+    }
+
+    #[test]
+    fn test_tracker_debounces_changes() {
+        let settle = Duration::from_secs(1);
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let set =
+            |devices: &[&str]| -> HashSet<PathBuf> { devices.iter().map(PathBuf::from).collect() };
+
+        let mut tracker = DeviceTracker::new(set(&["/dev/mmcblk0"]), settle);
+
+        // The card appears, briefly vanishes while probing, then stays
+        let inserted = set(&["/dev/mmcblk0", "/dev/mmcblk1"]);
+        assert!(tracker.update(&inserted, at(0)).is_empty());
+        assert!(tracker.update(&set(&["/dev/mmcblk0"]), at(500)).is_empty());
+        assert!(tracker.update(&inserted, at(1000)).is_empty());
+        assert!(tracker.update(&inserted, at(1500)).is_empty());
+
+        let events = tracker.update(&inserted, at(2000));
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            &events[0],
+            StorageEvent::DeviceAdded { device } if device == Path::new("/dev/mmcblk1")
+        ));
+        assert!(tracker.update(&inserted, at(3000)).is_empty());
+
+        // Removal is reported once it settles too
+        let removed = set(&["/dev/mmcblk0"]);
+        assert!(tracker.update(&removed, at(4000)).is_empty());
+        let events = tracker.update(&removed, at(5000));
+        assert!(matches!(
+            &events[..],
+            [StorageEvent::DeviceRemoved { device }] if device == Path::new("/dev/mmcblk1")
+        ));
+    }
+
     #[test]
     fn test_storage_event_debug() {
         let event = StorageEvent::DeviceAdded {
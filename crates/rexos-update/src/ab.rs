@@ -0,0 +1,414 @@
+//! A/B slot updates
+//!
+//! The system lives on two slot partitions, mounted at configurable paths.
+//! An update is written to the inactive slot, starting from a copy of the
+//! running one, and read back against the package. Only then does the
+//! boot slot marker, which the bootloader reads, switch to it, so a power
+//! cut mid-install leaves the running slot untouched and bootable. Rolling
+//! back switches the marker back.
+
+use crate::installer::package_version;
+use crate::{InstallProgress, InstallResult, UpdateError, UpdateInstaller};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Boot slot marker read by the bootloader, holding `a` or `b`
+pub const SLOT_MARKER: &str = "/boot/rexos-slot";
+
+/// Kernel command line, where the bootloader passes `rexos.slot=`
+const CMDLINE: &str = "/proc/cmdline";
+
+/// A system slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Slot {
+    A,
+    B,
+}
+
+impl Slot {
+    /// The other slot
+    pub fn other(&self) -> Self {
+        match self {
+            Slot::A => Slot::B,
+            Slot::B => Slot::A,
+        }
+    }
+
+    /// Name as written in the marker
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Slot::A => "a",
+            Slot::B => "b",
+        }
+    }
+
+    /// Parse a slot name, ignoring case
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "a" => Some(Slot::A),
+            "b" => Some(Slot::B),
+            _ => None,
+        }
+    }
+}
+
+/// Where the slots are mounted and the boot marker lives
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AbSlots {
+    /// Mount point of slot A
+    pub slot_a: PathBuf,
+    /// Mount point of slot B
+    pub slot_b: PathBuf,
+    /// Boot slot marker
+    pub marker: PathBuf,
+}
+
+impl Default for AbSlots {
+    fn default() -> Self {
+        Self {
+            slot_a: PathBuf::from("/run/rexos/slot-a"),
+            slot_b: PathBuf::from("/run/rexos/slot-b"),
+            marker: PathBuf::from(SLOT_MARKER),
+        }
+    }
+}
+
+impl AbSlots {
+    /// Mount point of a slot
+    pub fn dir(&self, slot: Slot) -> &Path {
+        match slot {
+            Slot::A => &self.slot_a,
+            Slot::B => &self.slot_b,
+        }
+    }
+
+    /// Slot the marker currently selects
+    pub fn marked_slot(&self) -> Option<Slot> {
+        Slot::parse(&fs::read_to_string(&self.marker).ok()?)
+    }
+
+    /// Sidecar next to the marker, e.g. `rexos-slot.previous`
+    fn sidecar(&self, suffix: &str) -> PathBuf {
+        let mut path = self.marker.as_os_str().to_owned();
+        path.push(suffix);
+        PathBuf::from(path)
+    }
+}
+
+/// Installs updates to the inactive slot
+pub struct ABInstaller {
+    slots: AbSlots,
+    active: Slot,
+    staging_dir: PathBuf,
+    installer: UpdateInstaller,
+}
+
+impl ABInstaller {
+    /// Create an installer, detecting the running slot
+    pub fn new(slots: AbSlots, staging_dir: PathBuf) -> Self {
+        let cmdline = fs::read_to_string(CMDLINE).unwrap_or_default();
+        let active = cmdline_slot(&cmdline)
+            .or_else(|| slots.marked_slot())
+            .unwrap_or(Slot::A);
+        Self::with_active_slot(slots, staging_dir, active)
+    }
+
+    /// Create an installer for a known running slot
+    pub fn with_active_slot(slots: AbSlots, staging_dir: PathBuf, active: Slot) -> Self {
+        tracing::debug!("Running from slot {}", active.as_str());
+        let installer = UpdateInstaller::new(staging_dir.clone())
+            .with_root_dir(slots.dir(active.other()).into());
+
+        Self {
+            slots,
+            active,
+            staging_dir,
+            installer,
+        }
+    }
+
+    /// Slot the system is running from
+    pub fn active_slot(&self) -> Slot {
+        self.active
+    }
+
+    /// Slot updates are written to
+    pub fn inactive_slot(&self) -> Slot {
+        self.active.other()
+    }
+
+    /// Install an update package to the inactive slot and boot it next
+    ///
+    /// Post-install scripts expect to run against the live system and are
+    /// skipped.
+    pub async fn install(&self, package_path: &Path) -> Result<InstallResult, UpdateError> {
+        let target = self.inactive_slot();
+        let target_dir = self.slots.dir(target);
+        tracing::info!(
+            "Installing {} to slot {}",
+            package_path.display(),
+            target.as_str()
+        );
+
+        let result = self.write_slot(package_path, target_dir);
+        fs::remove_dir_all(&self.staging_dir).ok();
+        let (updated, added, removed) = result?;
+
+        let version = package_version(package_path);
+        if let Some(parent) = self.slots.marker.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(self.slots.sidecar(".previous"), self.active.as_str())?;
+        fs::write(self.slots.sidecar(".pending"), &version)?;
+        self.set_marker(target)?;
+        tracing::info!("Slot {} will boot next", target.as_str());
+
+        Ok(InstallResult {
+            version,
+            files_updated: updated,
+            files_added: added,
+            files_removed: removed,
+            // The new slot only runs after a reboot
+            needs_reboot: true,
+        })
+    }
+
+    /// Copy the running system to `dir`, apply the package and verify it
+    fn write_slot(&self, package_path: &Path, dir: &Path) -> Result<(u32, u32, u32), UpdateError> {
+        replace_tree(self.slots.dir(self.active), dir)?;
+
+        let files = self.installer.stage(package_path)?;
+        let (updated, added, removed) = self.installer.apply_update(&files)?;
+        self.installer.verify_installed(&files)?;
+
+        if self.staging_dir.join("post-install.sh").exists() {
+            tracing::warn!("Skipping post-install script for slot install");
+        }
+
+        sync();
+        Ok((updated, added, removed))
+    }
+
+    /// Boot the previous slot again
+    pub async fn rollback(&self) -> Result<(), UpdateError> {
+        let previous = fs::read_to_string(self.slots.sidecar(".previous"))
+            .ok()
+            .and_then(|slot| Slot::parse(&slot))
+            .ok_or_else(|| UpdateError::RollbackFailed("No previous slot recorded".into()))?;
+
+        self.set_marker(previous)?;
+        let _ = fs::remove_file(self.slots.sidecar(".previous"));
+        self.clear_pending()?;
+        tracing::info!("Slot {} will boot next", previous.as_str());
+        Ok(())
+    }
+
+    /// Version of a slot install awaiting a good boot, if any
+    pub fn pending_verification(&self) -> Option<String> {
+        fs::read_to_string(self.slots.sidecar(".pending"))
+            .ok()
+            .map(|version| version.trim().to_string())
+    }
+
+    /// Accept the installed slot after a good boot
+    ///
+    /// The previous slot is left alone for a manual rollback until the
+    /// next update overwrites it.
+    pub fn commit(&self) -> Result<(), UpdateError> {
+        if let Some(version) = self.pending_verification() {
+            self.clear_pending()?;
+            tracing::info!("Update to {} verified", version);
+        }
+        Ok(())
+    }
+
+    /// Forget a pending slot install without switching slots
+    pub fn clear_pending(&self) -> Result<(), UpdateError> {
+        match fs::remove_file(self.slots.sidecar(".pending")) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Get install progress
+    pub fn progress(&self) -> Option<InstallProgress> {
+        self.installer.progress()
+    }
+
+    /// Point the boot marker at a slot, atomically
+    fn set_marker(&self, slot: Slot) -> Result<(), UpdateError> {
+        let tmp = self.slots.sidecar(".tmp");
+        fs::write(&tmp, format!("{}\n", slot.as_str()))?;
+        fs::File::open(&tmp)?.sync_all()?;
+        fs::rename(&tmp, &self.slots.marker)?;
+        sync();
+        Ok(())
+    }
+}
+
+/// Slot passed by the bootloader as `rexos.slot=a`
+fn cmdline_slot(cmdline: &str) -> Option<Slot> {
+    cmdline
+        .split_whitespace()
+        .find_map(|arg| arg.strip_prefix("rexos.slot="))
+        .and_then(Slot::parse)
+}
+
+/// Replace the contents of `dest` with a copy of `src`
+///
+/// `dest` itself is kept since it's a mount point.
+fn replace_tree(src: &Path, dest: &Path) -> Result<(), UpdateError> {
+    fs::create_dir_all(dest)?;
+    for entry in fs::read_dir(dest)? {
+        let path = entry?.path();
+        if path.is_dir() && !path.is_symlink() {
+            fs::remove_dir_all(&path)?;
+        } else {
+            fs::remove_file(&path)?;
+        }
+    }
+    copy_tree(src, dest)
+}
+
+/// Copy directories, files and symlinks; other file types are skipped
+pub(crate) fn copy_tree(src: &Path, dest: &Path) -> Result<(), UpdateError> {
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let target = dest.join(entry.file_name());
+
+        if file_type.is_dir() {
+            fs::create_dir_all(&target)?;
+            fs::set_permissions(&target, entry.metadata()?.permissions())?;
+            copy_tree(&entry.path(), &target)?;
+        } else if file_type.is_symlink() {
+            std::os::unix::fs::symlink(fs::read_link(entry.path())?, &target)?;
+        } else if file_type.is_file() {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Flush written slot data before the marker points at it
+fn sync() {
+    // SAFETY: sync() takes no arguments and cannot fail
+    unsafe { libc::sync() };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cmdline_slot() {
+        assert_eq!(
+            cmdline_slot("console=ttyS2 rexos.slot=b quiet"),
+            Some(Slot::B)
+        );
+        assert_eq!(cmdline_slot("console=ttyS2 quiet"), None);
+        assert_eq!(cmdline_slot("rexos.slot=c"), None);
+    }
+
+    fn slots(dir: &Path) -> AbSlots {
+        AbSlots {
+            slot_a: dir.join("slot-a"),
+            slot_b: dir.join("slot-b"),
+            marker: dir.join("boot/rexos-slot"),
+        }
+    }
+
+    fn package(dir: &Path, manifest: serde_json::Value) -> PathBuf {
+        use std::io::Write;
+
+        let manifest = manifest.to_string();
+        let mut tar = tar::Builder::new(Vec::new());
+        for (path, data) in [
+            ("usr/bin/rexos-launcher", b"new launcher".as_slice()),
+            ("manifest.json", manifest.as_bytes()),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o755);
+            header.set_cksum();
+            tar.append_data(&mut header, path, data).unwrap();
+        }
+
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(&tar.into_inner().unwrap()).unwrap();
+        let path = dir.join("rexos-1.2.0.tar.gz");
+        fs::write(&path, gzip.finish().unwrap()).unwrap();
+        path
+    }
+
+    fn running_slot_a(dir: &Path) -> ABInstaller {
+        let slots = slots(dir);
+        fs::create_dir_all(slots.slot_a.join("usr/bin")).unwrap();
+        fs::write(slots.slot_a.join("usr/bin/rexos-launcher"), b"old launcher").unwrap();
+        fs::write(slots.slot_a.join("usr/bin/obsolete"), b"old").unwrap();
+        std::os::unix::fs::symlink("rexos-launcher", slots.slot_a.join("usr/bin/launcher"))
+            .unwrap();
+
+        // Left over from the update before last
+        fs::create_dir_all(&slots.slot_b).unwrap();
+        fs::write(slots.slot_b.join("stale"), b"stale").unwrap();
+
+        ABInstaller::with_active_slot(slots, dir.join("staging"), Slot::A)
+    }
+
+    #[tokio::test]
+    async fn test_install_switches_slot_and_rolls_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let installer = running_slot_a(dir.path());
+        let slots = slots(dir.path());
+        let package = package(
+            dir.path(),
+            serde_json::json!({ "remove": ["usr/bin/obsolete"] }),
+        );
+
+        let result = installer.install(&package).await.unwrap();
+        assert_eq!(result.version, "1.2.0");
+        assert!(result.needs_reboot);
+
+        // New system in B, running system in A untouched
+        let b = &slots.slot_b;
+        assert_eq!(
+            fs::read(b.join("usr/bin/rexos-launcher")).unwrap(),
+            b"new launcher"
+        );
+        assert!(b.join("usr/bin/launcher").is_symlink());
+        assert!(!b.join("usr/bin/obsolete").exists());
+        assert!(!b.join("stale").exists());
+        assert_eq!(
+            fs::read(slots.slot_a.join("usr/bin/rexos-launcher")).unwrap(),
+            b"old launcher"
+        );
+
+        assert_eq!(slots.marked_slot(), Some(Slot::B));
+        assert_eq!(installer.pending_verification().as_deref(), Some("1.2.0"));
+
+        installer.rollback().await.unwrap();
+        assert_eq!(slots.marked_slot(), Some(Slot::A));
+        assert_eq!(installer.pending_verification(), None);
+        assert!(installer.rollback().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failed_verification_keeps_marker() {
+        let dir = tempfile::tempdir().unwrap();
+        let installer = running_slot_a(dir.path());
+        let slots = slots(dir.path());
+        let package = package(
+            dir.path(),
+            serde_json::json!({ "files": { "usr/bin/rexos-launcher": "0000" } }),
+        );
+
+        assert!(matches!(
+            installer.install(&package).await,
+            Err(UpdateError::VerificationFailed(_))
+        ));
+        assert_eq!(slots.marked_slot(), None);
+        assert_eq!(installer.pending_verification(), None);
+        assert!(!dir.path().join("staging").exists());
+    }
+}
@@ -1,8 +1,12 @@
 //! Update availability checking
 
-use crate::{UpdateError, UpdateManifest};
+use crate::manifest::verify_detached;
+use crate::{SignatureVerifier, UpdateError, UpdateManifest};
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
 
 /// Update channel
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -26,6 +30,16 @@ impl UpdateChannel {
             UpdateChannel::Nightly => "nightly",
         }
     }
+
+    /// Parse a channel name, ignoring case
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "stable" => Some(UpdateChannel::Stable),
+            "beta" => Some(UpdateChannel::Beta),
+            "nightly" => Some(UpdateChannel::Nightly),
+            _ => None,
+        }
+    }
 }
 
 /// Information about an available update
@@ -40,6 +54,10 @@ pub struct UpdateInfo {
     /// Download URL
     pub download_url: String,
 
+    /// Alternate download URLs, tried in order after `download_url`
+    #[serde(default)]
+    pub mirrors: Vec<String>,
+
     /// File size in bytes
     pub size: u64,
 
@@ -63,6 +81,63 @@ pub struct UpdateInfo {
 
     /// Full manifest URL
     pub manifest_url: Option<String>,
+
+    /// Installed version a delta package patches, `None` for a full package
+    #[serde(default)]
+    pub delta_base: Option<String>,
+
+    /// Detached Ed25519 signature over the rest of this response (see
+    /// [`crate::signing_payload`])
+    #[serde(default)]
+    pub manifest_signature: Option<String>,
+
+    /// ID of the key that signed the package and this response (see
+    /// [`crate::key_id`])
+    #[serde(default)]
+    pub key_id: Option<String>,
+}
+
+impl UpdateInfo {
+    /// All download URLs, primary first
+    pub fn sources(&self) -> Vec<&str> {
+        std::iter::once(self.download_url.as_str())
+            .chain(self.mirrors.iter().map(String::as_str))
+            .collect()
+    }
+
+    /// Check if the download is a delta package
+    pub fn is_delta(&self) -> bool {
+        self.delta_base.is_some()
+    }
+
+    /// Check if this update is newer than `version` for its channel
+    pub fn is_newer_than(&self, version: &str) -> bool {
+        crate::version::is_newer(version, &self.version, self.channel)
+    }
+}
+
+/// Request timeout for update checks
+pub(crate) const CHECK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default directory for cached check validators, which must survive a
+/// reboot since `check_on_boot` runs on every startup
+pub const CHECK_CACHE_DIR: &str = "/var/lib/rexos/update-cache";
+
+/// Validators from the last check that found nothing to install
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct CheckCache {
+    /// Installed version the check was for
+    version: String,
+    /// Whether a delta package was asked for
+    #[serde(default)]
+    delta: bool,
+    /// Whether older versions were offered
+    #[serde(default)]
+    allow_downgrade: bool,
+    /// `ETag` header of the response
+    etag: Option<String>,
+    /// `Last-Modified` header of the response
+    last_modified: Option<String>,
 }
 
 /// Checks for available updates
@@ -70,13 +145,17 @@ pub struct UpdateChecker {
     server_url: String,
     channel: UpdateChannel,
     client: reqwest::Client,
+    delta: bool,
+    cache_dir: Option<PathBuf>,
+    verifier: Option<SignatureVerifier>,
+    allow_downgrade: bool,
 }
 
 impl UpdateChecker {
     /// Create a new update checker
     pub fn new(server_url: String, channel: UpdateChannel) -> Self {
         let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
+            .timeout(CHECK_TIMEOUT)
             .user_agent(format!("RexOS/{}", env!("CARGO_PKG_VERSION")))
             .build()
             .expect("Failed to create HTTP client");
@@ -85,10 +164,54 @@ impl UpdateChecker {
             server_url,
             channel,
             client,
+            delta: false,
+            cache_dir: None,
+            verifier: None,
+            allow_downgrade: false,
         }
     }
 
+    /// Use a preconfigured HTTP client (e.g. with a proxy)
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Ask the server for a delta package when it has one for the
+    /// installed version
+    pub fn with_delta(mut self, delta: bool) -> Self {
+        self.delta = delta;
+        self
+    }
+
+    /// Remember check responses in `dir`, one file per channel, so a check
+    /// that finds nothing new can be answered with `304 Not Modified`
+    pub fn with_cache_dir(mut self, dir: PathBuf) -> Self {
+        self.cache_dir = Some(dir);
+        self
+    }
+
+    /// Only trust responses whose detached signature `verifier` accepts
+    pub fn with_verifier(mut self, verifier: SignatureVerifier) -> Self {
+        self.verifier = Some(verifier);
+        self
+    }
+
+    /// Offer releases older than the installed version too, e.g. to go
+    /// back to stable from nightly
+    ///
+    /// Otherwise an older release is never offered, so a replayed
+    /// response can't roll the system back.
+    pub fn with_allow_downgrade(mut self, allow: bool) -> Self {
+        self.allow_downgrade = allow;
+        self
+    }
+
     /// Check for available updates
+    ///
+    /// With a cache directory the check is conditional on the validators
+    /// of the last check that found nothing to install, and a
+    /// `304 Not Modified` returns `Ok(None)` without reading the body.
     pub async fn check(&self, current_version: &str) -> Result<Option<UpdateInfo>, UpdateError> {
         let url = format!(
             "{}/api/v1/updates/{}/latest",
@@ -98,17 +221,39 @@ impl UpdateChecker {
 
         tracing::debug!("Checking for updates at {}", url);
 
-        let response = self
-            .client
-            .get(&url)
-            .query(&[
-                ("current_version", current_version),
-                ("arch", std::env::consts::ARCH),
-            ])
-            .send()
-            .await?;
+        let mut request = self.client.get(&url).query(&[
+            ("current_version", current_version),
+            ("arch", std::env::consts::ARCH),
+            ("delta", if self.delta { "true" } else { "false" }),
+        ]);
+
+        // A cached answer only holds for the same question
+        let cached = self.load_cache().filter(|c| {
+            c.version == current_version
+                && c.delta == self.delta
+                && c.allow_downgrade == self.allow_downgrade
+        });
+        if let Some(cache) = &cached {
+            if let Some(etag) = &cache.etag {
+                request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &cache.last_modified {
+                request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response = request.send().await?;
+
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED && cached.is_some() {
+            tracing::debug!(
+                "No change on {} since the last check",
+                self.channel.as_str()
+            );
+            return Ok(None);
+        }
 
         if response.status() == reqwest::StatusCode::NOT_FOUND {
+            self.clear_cache();
             return Ok(None);
         }
 
@@ -119,16 +264,99 @@ impl UpdateChecker {
             )));
         }
 
-        let update: UpdateInfo = response.json().await?;
+        let header = |name: reqwest::header::HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(String::from)
+        };
+        let cache = CheckCache {
+            version: current_version.to_string(),
+            delta: self.delta,
+            allow_downgrade: self.allow_downgrade,
+            etag: header(reqwest::header::ETAG),
+            last_modified: header(reqwest::header::LAST_MODIFIED),
+        };
 
-        // Compare versions
-        if Self::is_newer(&update.version, current_version) {
+        let update = self.parse_update(&response.text().await?)?;
+
+        // Compare versions. Validators are only kept when there's nothing
+        // to install, so a `304` never hides an update that was skipped.
+        if self.offers(&update, current_version) {
+            self.clear_cache();
             Ok(Some(update))
         } else {
+            self.save_cache(&cache);
             Ok(None)
         }
     }
 
+    /// Parse an update, checking its signature when there's a verifier
+    fn parse_update(&self, body: &str) -> Result<UpdateInfo, UpdateError> {
+        let value: serde_json::Value =
+            serde_json::from_str(body).map_err(|e| UpdateError::CheckFailed(e.to_string()))?;
+
+        if let Some(verifier) = &self.verifier {
+            verify_detached(&value, "manifest_signature", verifier)?;
+        }
+
+        serde_json::from_value(value).map_err(|e| UpdateError::CheckFailed(e.to_string()))
+    }
+
+    /// Check if `update` should be offered over the installed version
+    fn offers(&self, update: &UpdateInfo, current_version: &str) -> bool {
+        if update.is_newer_than(current_version) {
+            return true;
+        }
+
+        let older = crate::version::cmp(&update.version, current_version) == Ordering::Less;
+        if older && !self.allow_downgrade {
+            tracing::debug!(
+                "Ignoring {}, older than installed {}",
+                update.version,
+                current_version
+            );
+        }
+        older && self.allow_downgrade
+    }
+
+    /// Cache file for this checker's channel
+    fn cache_path(&self) -> Option<PathBuf> {
+        let dir = self.cache_dir.as_ref()?;
+        Some(dir.join(format!("check-{}.json", self.channel.as_str())))
+    }
+
+    fn load_cache(&self) -> Option<CheckCache> {
+        let content = fs::read_to_string(self.cache_path()?).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Save validators; failures only cost a full check next time
+    fn save_cache(&self, cache: &CheckCache) {
+        let Some(path) = self.cache_path() else {
+            return;
+        };
+        if cache.etag.is_none() && cache.last_modified.is_none() {
+            self.clear_cache();
+            return;
+        }
+
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let json = serde_json::to_string(cache).unwrap_or_default();
+        if let Err(e) = fs::write(&path, json) {
+            tracing::warn!("Failed to save {}: {}", path.display(), e);
+        }
+    }
+
+    fn clear_cache(&self) {
+        if let Some(path) = self.cache_path() {
+            let _ = fs::remove_file(path);
+        }
+    }
+
     /// Check all channels for updates
     pub async fn check_all_channels(
         &self,
@@ -159,9 +387,13 @@ impl UpdateChecker {
             #[allow(clippy::collapsible_if)]
             if let Ok(resp) = response {
                 if resp.status().is_success() {
-                    if let Ok(update) = resp.json::<UpdateInfo>().await {
-                        if Self::is_newer(&update.version, current_version) {
-                            updates.push(update);
+                    if let Ok(body) = resp.text().await {
+                        match self.parse_update(&body) {
+                            Ok(update) if self.offers(&update, current_version) => {
+                                updates.push(update)
+                            }
+                            Ok(_) => {}
+                            Err(e) => tracing::warn!("Ignoring {} update: {}", channel.as_str(), e),
                         }
                     }
                 }
@@ -187,26 +419,26 @@ impl UpdateChecker {
             )));
         }
 
-        let manifest: UpdateManifest = response
-            .json()
-            .await
-            .map_err(|e| UpdateError::InvalidManifest(e.to_string()))?;
+        let body = response.text().await?;
+        let manifest = match &self.verifier {
+            Some(verifier) => UpdateManifest::from_signed_json(&body, verifier)?,
+            None => UpdateManifest::from_json(&body)?,
+        };
+
+        // The manifest must describe the release that was offered
+        if manifest.version != update.version {
+            return Err(UpdateError::VerificationFailed(format!(
+                "Manifest is for {}, expected {}",
+                manifest.version, update.version
+            )));
+        }
 
         Ok(manifest)
     }
 
-    /// Compare version strings (semver-aware)
+    /// Compare version strings by semver precedence (see [`crate::version`])
     pub fn is_newer(new_version: &str, current_version: &str) -> bool {
-        match (
-            semver::Version::parse(new_version.trim_start_matches('v')),
-            semver::Version::parse(current_version.trim_start_matches('v')),
-        ) {
-            (Ok(new), Ok(current)) => new > current,
-            _ => {
-                // Fallback to string comparison
-                new_version.cmp(current_version) == Ordering::Greater
-            }
-        }
+        crate::version::cmp(new_version, current_version) == Ordering::Greater
     }
 
     /// Get release history
@@ -227,14 +459,18 @@ impl UpdateChecker {
             )));
         }
 
-        let releases: Vec<UpdateInfo> = response.json().await?;
-        Ok(releases)
+        let releases: Vec<serde_json::Value> = response.json().await?;
+        releases
+            .iter()
+            .map(|release| self.parse_update(&release.to_string()))
+            .collect()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::{Arc, Mutex};
 
     #[test]
     fn test_version_comparison() {
@@ -270,6 +506,7 @@ mod tests {
             version: "1.2.3".to_string(),
             channel: UpdateChannel::Stable,
             download_url: "https://example.com/update.tar.gz".to_string(),
+            mirrors: vec!["https://mirror.example.com/update.tar.gz".to_string()],
             size: 1024 * 1024 * 50, // 50MB
             sha256: "abc123".to_string(),
             signature: "def456".to_string(),
@@ -278,12 +515,22 @@ mod tests {
             critical: false,
             min_version: None,
             manifest_url: Some("https://example.com/manifest.json".to_string()),
+            delta_base: None,
+            manifest_signature: None,
+            key_id: None,
         };
 
         assert_eq!(info.version, "1.2.3");
         assert_eq!(info.channel, UpdateChannel::Stable);
         assert!(!info.critical);
         assert!(info.release_notes.is_some());
+        assert_eq!(
+            info.sources(),
+            [
+                "https://example.com/update.tar.gz",
+                "https://mirror.example.com/update.tar.gz"
+            ]
+        );
     }
 
     #[test]
@@ -292,6 +539,7 @@ mod tests {
             version: "1.2.4".to_string(),
             channel: UpdateChannel::Stable,
             download_url: "https://example.com/security-update.tar.gz".to_string(),
+            mirrors: Vec::new(),
             size: 1024 * 1024 * 10,
             sha256: "xyz789".to_string(),
             signature: "sig123".to_string(),
@@ -300,9 +548,13 @@ mod tests {
             critical: true,
             min_version: Some("1.2.0".to_string()),
             manifest_url: None,
+            delta_base: Some("1.2.3".to_string()),
+            manifest_signature: None,
+            key_id: None,
         };
 
         assert!(info.critical);
+        assert!(info.is_delta());
         assert_eq!(info.min_version, Some("1.2.0".to_string()));
         assert!(info.manifest_url.is_none());
     }
@@ -320,6 +572,136 @@ mod tests {
         assert!(UpdateChecker::is_newer("100.0.0", "99.99.99"));
     }
 
+    /// A `latest` response for `version`
+    fn latest(version: &str) -> serde_json::Value {
+        serde_json::json!({
+            "version": version,
+            "channel": "stable",
+            "download_url": "http://example.com/update.tar.gz",
+            "size": 0,
+            "sha256": "",
+            "signature": "",
+            "release_notes": null,
+            "release_date": "2024-01-15",
+            "critical": false,
+            "min_version": null,
+            "manifest_url": null
+        })
+    }
+
+    /// Serve `body` for every request with its version as `ETag`,
+    /// honouring `If-None-Match`; logs each request's `If-None-Match`
+    /// header (empty when absent)
+    async fn serve_json(body: serde_json::Value) -> (String, Arc<Mutex<Vec<String>>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = requests.clone();
+        let etag = format!("\"{}\"", body["version"].as_str().unwrap());
+        let body = body.to_string();
+
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let n = stream.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                let if_none_match = request
+                    .lines()
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(':')?;
+                        name.eq_ignore_ascii_case("if-none-match")
+                            .then(|| value.trim().to_string())
+                    })
+                    .unwrap_or_default();
+                log.lock().unwrap().push(if_none_match.clone());
+
+                let response = if if_none_match == etag {
+                    "HTTP/1.1 304 Not Modified\r\nconnection: close\r\n\r\n".to_string()
+                } else {
+                    format!(
+                        "HTTP/1.1 200 OK\r\netag: {}\r\ncontent-type: application/json\r\n\
+                         content-length: {}\r\nconnection: close\r\n\r\n{}",
+                        etag,
+                        body.len(),
+                        body
+                    )
+                };
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+
+        (format!("http://{}", addr), requests)
+    }
+
+    #[tokio::test]
+    async fn test_check_not_modified() {
+        let dir = tempfile::tempdir().unwrap();
+        let (url, requests) = serve_json(latest("1.2.0")).await;
+        let checker =
+            UpdateChecker::new(url, UpdateChannel::Stable).with_cache_dir(dir.path().to_path_buf());
+
+        assert!(checker.check("1.2.0").await.unwrap().is_none());
+        assert!(dir.path().join("check-stable.json").exists());
+        assert!(checker.check("1.2.0").await.unwrap().is_none());
+
+        // A different installed version asks unconditionally and finds
+        // the update, which isn't cached
+        assert!(checker.check("1.1.0").await.unwrap().is_some());
+        assert!(!dir.path().join("check-stable.json").exists());
+        assert!(checker.check("1.1.0").await.unwrap().is_some());
+
+        assert_eq!(*requests.lock().unwrap(), ["", "\"1.2.0\"", "", ""]);
+    }
+
+    #[tokio::test]
+    async fn test_check_verifies_signature() {
+        use crate::signing_payload;
+        use crate::verification::{generate_keypair, sign_data};
+
+        let (private, public) = generate_keypair();
+        let verifier = || SignatureVerifier::from_hex(&public).unwrap();
+
+        let mut signed = latest("1.2.0");
+        let payload = signing_payload(&signed, "manifest_signature");
+        signed["manifest_signature"] = sign_data(&payload, &private).unwrap().into();
+
+        let (url, _) = serve_json(signed.clone()).await;
+        let checker = UpdateChecker::new(url, UpdateChannel::Stable).with_verifier(verifier());
+        assert_eq!(
+            checker.check("1.1.0").await.unwrap().unwrap().version,
+            "1.2.0"
+        );
+
+        // Unsigned, and signed but with the URL swapped
+        let mut swapped = signed;
+        swapped["download_url"] = "http://evil.example.com/update.tar.gz".into();
+        for body in [latest("1.2.0"), swapped] {
+            let (url, _) = serve_json(body).await;
+            let checker = UpdateChecker::new(url, UpdateChannel::Stable).with_verifier(verifier());
+            assert!(matches!(
+                checker.check("1.1.0").await,
+                Err(UpdateError::VerificationFailed(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_downgrade() {
+        let (url, _) = serve_json(latest("1.0.0")).await;
+
+        let checker = UpdateChecker::new(url.clone(), UpdateChannel::Stable);
+        assert!(checker.check("1.1.0").await.unwrap().is_none());
+
+        let checker = UpdateChecker::new(url, UpdateChannel::Stable).with_allow_downgrade(true);
+        assert_eq!(
+            checker.check("1.1.0").await.unwrap().unwrap().version,
+            "1.0.0"
+        );
+        assert!(checker.check("1.0.0").await.unwrap().is_none());
+    }
+
     #[test]
     fn test_update_checker_creation() {
         let checker =
@@ -0,0 +1,111 @@
+//! Update package compression
+//!
+//! Packages are tar archives compressed with gzip, zstd or xz. The format
+//! is detected from the file's magic bytes, so the server can switch
+//! formats without breaking clients. The zstd and xz decoders sit behind
+//! the `zstd` and `xz` cargo features (both on by default).
+
+use crate::UpdateError;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const XZ_MAGIC: &[u8] = &[0xfd, b'7', b'z', b'X', b'Z', 0x00];
+
+/// Compression format of an update package
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+impl Compression {
+    /// Detect the format from the start of a file
+    pub fn detect(header: &[u8]) -> Option<Self> {
+        if header.starts_with(GZIP_MAGIC) {
+            Some(Compression::Gzip)
+        } else if header.starts_with(ZSTD_MAGIC) {
+            Some(Compression::Zstd)
+        } else if header.starts_with(XZ_MAGIC) {
+            Some(Compression::Xz)
+        } else {
+            None
+        }
+    }
+
+    /// Format name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+            Compression::Xz => "xz",
+        }
+    }
+
+    /// Check if this build can decompress the format
+    pub fn is_supported(&self) -> bool {
+        match self {
+            Compression::Gzip => true,
+            Compression::Zstd => cfg!(feature = "zstd"),
+            Compression::Xz => cfg!(feature = "xz"),
+        }
+    }
+
+    /// Wrap a reader in the matching decoder
+    pub fn decoder<'a, R: BufRead + 'a>(
+        &self,
+        reader: R,
+    ) -> Result<Box<dyn Read + 'a>, UpdateError> {
+        match self {
+            Compression::Gzip => Ok(Box::new(flate2::bufread::GzDecoder::new(reader))),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Ok(Box::new(zstd::stream::read::Decoder::with_buffer(reader)?)),
+            #[cfg(feature = "xz")]
+            Compression::Xz => Ok(Box::new(xz2::bufread::XzDecoder::new(reader))),
+            #[allow(unreachable_patterns)]
+            _ => Err(UpdateError::InstallFailed(format!(
+                "{} packages are not supported by this build",
+                self.name()
+            ))),
+        }
+    }
+}
+
+/// Open a package file for reading its decompressed tar stream
+pub fn open_package(path: &Path) -> Result<(Compression, Box<dyn Read>), UpdateError> {
+    let mut reader = BufReader::new(File::open(path)?);
+
+    // Peek without consuming so the decoder sees the whole stream
+    let compression = Compression::detect(reader.fill_buf()?).ok_or_else(|| {
+        UpdateError::InstallFailed(format!("Unknown package compression: {}", path.display()))
+    })?;
+
+    let decoder = compression.decoder(reader)?;
+    Ok((compression, decoder))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_magic() {
+        assert_eq!(
+            Compression::detect(&[0x1f, 0x8b, 0x08, 0x00]),
+            Some(Compression::Gzip)
+        );
+        assert_eq!(
+            Compression::detect(&[0x28, 0xb5, 0x2f, 0xfd, 0x04]),
+            Some(Compression::Zstd)
+        );
+        assert_eq!(
+            Compression::detect(b"\xfd7zXZ\x00\x00\x04"),
+            Some(Compression::Xz)
+        );
+        assert_eq!(Compression::detect(b"ustar"), None);
+        assert_eq!(Compression::detect(&[0x1f]), None);
+    }
+}
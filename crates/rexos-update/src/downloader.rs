@@ -1,10 +1,24 @@
 //! Update download with resume support
-
-use crate::{UpdateError, UpdateInfo};
+//!
+//! A package is written to `<name>.part` next to a small `<name>.progress`
+//! sidecar recording how many bytes were written and the full size the
+//! server reported. A retry, or the next update check after a reboot,
+//! continues from that offset with a `Range` request. A partial without a
+//! sidecar, or one the server no longer matches, is discarded.
+//!
+//! Progress can be polled with [`UpdateDownloader::progress`] or watched
+//! with [`UpdateDownloader::subscribe`], which wakes on every update.
+
+use crate::{HashVerifier, UpdateError, UpdateInfo};
+use reqwest::StatusCode;
+use reqwest::header::{CONTENT_RANGE, RANGE};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::fs::{self, OpenOptions};
 use std::io::Write;
-use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
 
 /// Download progress information
 #[derive(Debug, Clone)]
@@ -13,10 +27,12 @@ pub struct DownloadProgress {
     pub total: u64,
     /// Bytes downloaded so far
     pub downloaded: u64,
-    /// Download speed in bytes per second
+    /// Download speed in bytes per second, over the last few seconds
     pub speed: u64,
     /// Estimated time remaining in seconds
     pub eta: u64,
+    /// Bytes kept from an earlier partial download
+    pub resumed_bytes: u64,
     /// Current state
     pub state: DownloadState,
 }
@@ -36,26 +52,162 @@ impl DownloadProgress {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DownloadState {
     Pending,
-    Downloading,
+    /// Transferring, `resume` when continuing a partial download
+    Downloading {
+        resume: bool,
+    },
     Paused,
     Completed,
     Failed,
     Verifying,
 }
 
+/// Mismatches after which a source is considered bad
+const MAX_SOURCE_MISMATCHES: u32 = 2;
+
+/// How often progress is published while transferring
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Span of the rolling window speed is measured over
+const SPEED_WINDOW: Duration = Duration::from_secs(3);
+
+/// Rolling window of `(time, bytes downloaded)` samples
+///
+/// Averaging over a few seconds keeps the speed, and so the ETA, steady
+/// on bursty networks.
+#[derive(Debug, Default)]
+struct SpeedWindow {
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl SpeedWindow {
+    /// Record a sample, returning the speed in bytes per second
+    fn record(&mut self, now: Instant, downloaded: u64) -> u64 {
+        self.samples.push_back((now, downloaded));
+        while self.samples.len() > 2 && now.duration_since(self.samples[1].0) >= SPEED_WINDOW {
+            self.samples.pop_front();
+        }
+
+        let (start, start_bytes) = self.samples[0];
+        let elapsed = now.duration_since(start).as_secs_f64();
+        if elapsed > 0.0 {
+            (downloaded.saturating_sub(start_bytes) as f64 / elapsed) as u64
+        } else {
+            0
+        }
+    }
+}
+
+/// Contents of a `.progress` sidecar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct PartProgress {
+    /// Bytes written to the `.part` file
+    written: u64,
+    /// Full size reported by the server, if known
+    total: Option<u64>,
+}
+
+impl PartProgress {
+    fn load(path: &Path) -> Option<Self> {
+        serde_json::from_slice(&fs::read(path).ok()?).ok()
+    }
+
+    fn save(&self, path: &Path) -> Result<(), UpdateError> {
+        let json = serde_json::to_vec(self).map_err(|e| {
+            UpdateError::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+        })?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+/// Where to resume a partial download, discarding one that can't be trusted
+///
+/// The offset is the smaller of the recorded and actual sizes, and the file
+/// is truncated to it, so bytes written after the last sidecar update are
+/// fetched again.
+fn resume_point(part: &Path, sidecar: &Path) -> Option<PartProgress> {
+    let len = fs::metadata(part).map(|m| m.len()).ok()?;
+    let Some(saved) = PartProgress::load(sidecar) else {
+        tracing::warn!("Discarding {} without progress record", part.display());
+        discard_partial(part, Some(sidecar));
+        return None;
+    };
+
+    let written = saved.written.min(len);
+    if written == 0
+        || OpenOptions::new()
+            .write(true)
+            .open(part)
+            .and_then(|f| f.set_len(written))
+            .is_err()
+    {
+        discard_partial(part, Some(sidecar));
+        return None;
+    }
+    Some(PartProgress { written, ..saved })
+}
+
+/// Remove a partial download and its sidecar
+fn discard_partial(part: &Path, sidecar: Option<&Path>) {
+    let _ = fs::remove_file(part);
+    if let Some(sidecar) = sidecar {
+        let _ = fs::remove_file(sidecar);
+    }
+}
+
+/// Parse `Content-Range: bytes start-end/total` into start and total
+fn parse_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let (range, total) = value.trim().strip_prefix("bytes ")?.split_once('/')?;
+    let (start, _) = range.split_once('-')?;
+    let total = match total {
+        "*" => None,
+        total => Some(total.parse().ok()?),
+    };
+    Some((start.parse().ok()?, total))
+}
+
+/// Check a ranged response continues the partial file we have
+fn range_matches(response: &reqwest::Response, saved: &PartProgress) -> bool {
+    let Some((start, total)) = response
+        .headers()
+        .get(CONTENT_RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(parse_content_range)
+    else {
+        return false;
+    };
+
+    start == saved.written
+        && match (total, saved.total) {
+            (Some(remote), Some(recorded)) => remote == recorded,
+            _ => true,
+        }
+}
+
+/// Next source to try after a hash mismatch, skipping bad sources
+fn next_source(mismatches: &[u32], current: usize) -> Option<usize> {
+    (1..=mismatches.len())
+        .map(|offset| (current + offset) % mismatches.len())
+        .find(|&i| mismatches[i] < MAX_SOURCE_MISMATCHES)
+}
+
+/// Request timeout for downloads
+pub(crate) const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(300);
+
 /// Downloads updates with resume support
 pub struct UpdateDownloader {
     download_dir: PathBuf,
     max_retries: u32,
     client: reqwest::Client,
-    progress: Arc<Mutex<Option<DownloadProgress>>>,
+    progress: watch::Sender<Option<DownloadProgress>>,
 }
 
 impl UpdateDownloader {
     /// Create a new downloader
     pub fn new(download_dir: PathBuf, max_retries: u32) -> Self {
         let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(300))
+            .timeout(DOWNLOAD_TIMEOUT)
             .user_agent(format!("RexOS/{}", env!("CARGO_PKG_VERSION")))
             .build()
             .expect("Failed to create HTTP client");
@@ -64,123 +216,267 @@ impl UpdateDownloader {
             download_dir,
             max_retries,
             client,
-            progress: Arc::new(Mutex::new(None)),
+            progress: watch::Sender::new(None),
         }
     }
 
+    /// Use a preconfigured HTTP client (e.g. with a proxy)
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
     /// Download an update
     pub async fn download(&self, update: &UpdateInfo) -> Result<PathBuf, UpdateError> {
         // Ensure download directory exists
         fs::create_dir_all(&self.download_dir)?;
 
         // Determine output path
-        let filename = format!("rexos-{}.tar.gz", update.version);
-        let output_path = self.download_dir.join(&filename);
-        let partial_path = self.download_dir.join(format!("{}.partial", filename));
-
-        // Check for existing partial download
-        let resume_from = if partial_path.exists() {
-            fs::metadata(&partial_path)?.len()
+        let filename = if update.is_delta() {
+            format!("rexos-{}.delta.tar.gz", update.version)
         } else {
-            0
+            format!("rexos-{}.tar.gz", update.version)
         };
+        let output_path = self.download_dir.join(&filename);
+        let partial_path = self.download_dir.join(format!("{}.part", filename));
+        let sidecar_path = self.download_dir.join(format!("{}.progress", filename));
+
+        let sources = update.sources();
 
         tracing::info!(
-            "Downloading {} ({} bytes, resuming from {})",
+            "Downloading {} ({} bytes, {} mirrors)",
             update.download_url,
             update.size,
-            resume_from
+            update.mirrors.len()
         );
 
         // Initialize progress
-        {
-            let mut progress = self.progress.lock().unwrap();
-            *progress = Some(DownloadProgress {
-                total: update.size,
-                downloaded: resume_from,
-                speed: 0,
-                eta: 0,
-                state: DownloadState::Downloading,
-            });
-        }
-
-        // Attempt download with retries
+        self.progress.send_replace(Some(DownloadProgress {
+            total: update.size,
+            downloaded: 0,
+            speed: 0,
+            eta: 0,
+            resumed_bytes: 0,
+            state: DownloadState::Downloading { resume: false },
+        }));
+
+        // Network errors retry the same source, resuming the partial file.
+        // A hash mismatch discards the file and moves to the next source; a
+        // source that mismatches twice is serving a bad file and is dropped.
+        let mut mismatches = vec![0u32; sources.len()];
+        let mut current = 0;
         let mut last_error = None;
+        let mut backoff = false;
 
-        for attempt in 0..self.max_retries {
+        for attempt in 0..self.max_retries.max(1) {
             if attempt > 0 {
-                tracing::warn!("Retry attempt {} of {}", attempt + 1, self.max_retries);
-                tokio::time::sleep(std::time::Duration::from_secs(2u64.pow(attempt))).await;
+                tracing::warn!(
+                    "Retry attempt {} of {} from {}",
+                    attempt + 1,
+                    self.max_retries,
+                    sources[current]
+                );
+                if backoff {
+                    tokio::time::sleep(Duration::from_secs(2u64.pow(attempt))).await;
+                }
             }
 
-            match self
-                .download_with_resume(&update.download_url, &partial_path, resume_from)
+            if let Err(e) = self
+                .download_with_resume(sources[current], &partial_path, Some(&sidecar_path))
                 .await
             {
+                last_error = Some(e);
+                backoff = true;
+                continue;
+            }
+
+            self.set_state(DownloadState::Verifying);
+
+            match HashVerifier::verify_file(&partial_path, &update.sha256) {
                 Ok(()) => {
                     // Rename partial to final
                     fs::rename(&partial_path, &output_path)?;
+                    let _ = fs::remove_file(&sidecar_path);
 
                     // Update progress
-                    {
-                        let mut progress = self.progress.lock().unwrap();
-                        if let Some(ref mut p) = *progress {
-                            p.state = DownloadState::Completed;
-                            p.downloaded = update.size;
-                        }
-                    }
+                    self.update_progress(|p| {
+                        p.state = DownloadState::Completed;
+                        p.downloaded = update.size;
+                    });
 
                     return Ok(output_path);
                 }
                 Err(e) => {
-                    last_error = Some(e);
+                    tracing::warn!("Hash mismatch from {}: {}", sources[current], e);
+                    discard_partial(&partial_path, Some(&sidecar_path));
+                    mismatches[current] += 1;
+                    last_error = Some(UpdateError::VerificationFailed(format!(
+                        "{}: {}",
+                        sources[current], e
+                    )));
+                    backoff = false;
+
+                    match next_source(&mismatches, current) {
+                        Some(next) => current = next,
+                        None => break,
+                    }
+                    self.set_state(DownloadState::Downloading { resume: false });
                 }
             }
         }
 
         // Update progress to failed
-        {
-            let mut progress = self.progress.lock().unwrap();
-            if let Some(ref mut p) = *progress {
-                p.state = DownloadState::Failed;
+        self.set_state(DownloadState::Failed);
+
+        Err(last_error.unwrap_or_else(|| UpdateError::DownloadFailed("Unknown error".into())))
+    }
+
+    /// Set the download state
+    fn set_state(&self, state: DownloadState) {
+        self.update_progress(|p| p.state = state);
+    }
+
+    /// Change the current progress, notifying subscribers
+    fn update_progress(&self, change: impl FnOnce(&mut DownloadProgress)) {
+        self.progress.send_modify(|progress| {
+            if let Some(p) = progress {
+                change(p);
+            }
+        });
+    }
+
+    /// Download a single file and verify its SHA256
+    ///
+    /// Used by sync installs to fetch only changed files. The file is written
+    /// to `dest` only once the hash matches.
+    pub async fn fetch_file(
+        &self,
+        url: &str,
+        dest: &Path,
+        expected_sha256: &str,
+    ) -> Result<(), UpdateError> {
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut partial = dest.as_os_str().to_owned();
+        partial.push(".partial");
+        let partial_path = PathBuf::from(partial);
+
+        let mut last_error = None;
+
+        for attempt in 0..self.max_retries.max(1) {
+            if attempt > 0 {
+                tracing::warn!(
+                    "Retry attempt {} of {} for {}",
+                    attempt + 1,
+                    self.max_retries,
+                    url
+                );
+                tokio::time::sleep(Duration::from_secs(2u64.pow(attempt))).await;
+            }
+
+            // Files are small; restart instead of resuming
+            let _ = fs::remove_file(&partial_path);
+
+            let result = match self.download_with_resume(url, &partial_path, None).await {
+                Ok(()) => HashVerifier::verify_file(&partial_path, expected_sha256)
+                    .map_err(|e| UpdateError::VerificationFailed(format!("{}: {}", url, e))),
+                Err(e) => Err(e),
+            };
+
+            match result {
+                Ok(()) => {
+                    fs::rename(&partial_path, dest)?;
+                    return Ok(());
+                }
+                Err(e) => last_error = Some(e),
             }
         }
 
+        let _ = fs::remove_file(&partial_path);
         Err(last_error.unwrap_or_else(|| UpdateError::DownloadFailed("Unknown error".into())))
     }
 
     /// Download with resume support
+    ///
+    /// With a sidecar, continues `path` from the recorded offset. A server
+    /// that ignores the range (200) restarts the file from its response; one
+    /// whose file changed since the partial was written (a different
+    /// total, or 416) gets a fresh request. Without a sidecar `path` is
+    /// always written from the start.
     async fn download_with_resume(
         &self,
         url: &str,
-        path: &PathBuf,
-        resume_from: u64,
+        path: &Path,
+        sidecar: Option<&Path>,
     ) -> Result<(), UpdateError> {
-        let mut request = self.client.get(url);
+        let saved = sidecar.and_then(|sidecar| resume_point(path, sidecar));
 
-        if resume_from > 0 {
-            request = request.header("Range", format!("bytes={}-", resume_from));
-        }
+        let mut response = self.request(url, saved.map(|p| p.written)).await?;
+        let mut resume_from = 0;
 
-        let response = request.send().await?;
+        if let Some(saved) = saved {
+            match response.status() {
+                StatusCode::PARTIAL_CONTENT if range_matches(&response, &saved) => {
+                    tracing::info!("Resuming {} from byte {}", url, saved.written);
+                    resume_from = saved.written;
+                }
+                StatusCode::OK => {
+                    tracing::info!("{} doesn't support ranges, restarting download", url);
+                }
+                status => {
+                    tracing::warn!(
+                        "Partial download of {} no longer matches the server ({}), restarting",
+                        url,
+                        status
+                    );
+                    discard_partial(path, sidecar);
+                    response = self.request(url, None).await?;
+                }
+            }
+        }
 
-        if !response.status().is_success()
-            && response.status() != reqwest::StatusCode::PARTIAL_CONTENT
-        {
+        if !response.status().is_success() {
             return Err(UpdateError::DownloadFailed(format!(
                 "Server returned {}",
                 response.status()
             )));
         }
 
-        // Open file for appending
-        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        // Append to a resumed file, otherwise start it over
+        let mut file = if resume_from > 0 {
+            OpenOptions::new().append(true).open(path)?
+        } else {
+            OpenOptions::new()
+                .create(true)
+                .write(true)
+                .truncate(true)
+                .open(path)?
+        };
+
+        let mut record = PartProgress {
+            written: resume_from,
+            total: response.content_length().map(|len| len + resume_from),
+        };
+        if let Some(sidecar) = sidecar {
+            record.save(sidecar)?;
+        }
+
+        self.update_progress(|p| {
+            p.downloaded = resume_from;
+            p.resumed_bytes = resume_from;
+            p.state = DownloadState::Downloading {
+                resume: resume_from > 0,
+            };
+        });
 
         // Stream the response
         let mut stream = response.bytes_stream();
         let mut downloaded = resume_from;
-        let mut last_update = std::time::Instant::now();
-        let mut bytes_since_update = 0u64;
+        let mut last_update = Instant::now();
+        let mut speed = SpeedWindow::default();
+        speed.record(last_update, downloaded);
 
         use futures_util::StreamExt;
         while let Some(chunk) = stream.next().await {
@@ -188,45 +484,65 @@ impl UpdateDownloader {
             file.write_all(&chunk)?;
 
             downloaded += chunk.len() as u64;
-            bytes_since_update += chunk.len() as u64;
-
-            // Update progress every 100ms
-            let now = std::time::Instant::now();
-            let elapsed = now.duration_since(last_update);
-
-            if elapsed.as_millis() >= 100 {
-                let speed = (bytes_since_update as f64 / elapsed.as_secs_f64()) as u64;
+            record.written = downloaded;
 
-                let mut progress = self.progress.lock().unwrap();
-                if let Some(ref mut p) = *progress {
+            // Update progress periodically
+            let now = Instant::now();
+            if now.duration_since(last_update) >= PROGRESS_INTERVAL {
+                let speed = speed.record(now, downloaded);
+                self.update_progress(|p| {
                     p.downloaded = downloaded;
                     p.speed = speed;
 
-                    if speed > 0 {
-                        p.eta = (p.total - downloaded) / speed;
+                    if let Some(eta) = p.total.saturating_sub(downloaded).checked_div(speed) {
+                        p.eta = eta;
                     }
-                }
+                });
 
                 last_update = now;
-                bytes_since_update = 0;
+
+                if let Some(sidecar) = sidecar {
+                    record.save(sidecar)?;
+                }
             }
         }
 
         file.sync_all()?;
+        if let Some(sidecar) = sidecar {
+            record.save(sidecar)?;
+        }
         Ok(())
     }
 
+    /// Send a GET, asking for the rest of the file from `offset`
+    async fn request(
+        &self,
+        url: &str,
+        offset: Option<u64>,
+    ) -> Result<reqwest::Response, UpdateError> {
+        let mut request = self.client.get(url);
+        if let Some(offset) = offset {
+            request = request.header(RANGE, format!("bytes={}-", offset));
+        }
+        Ok(request.send().await?)
+    }
+
     /// Get current progress
     pub fn progress(&self) -> Option<DownloadProgress> {
-        self.progress.lock().unwrap().clone()
+        self.progress.borrow().clone()
+    }
+
+    /// Watch progress, waking on each change instead of polling
+    ///
+    /// The receiver sees the latest progress; intermediate values a slow
+    /// consumer misses are skipped. `None` until a download starts.
+    pub fn subscribe(&self) -> watch::Receiver<Option<DownloadProgress>> {
+        self.progress.subscribe()
     }
 
     /// Cancel current download
     pub fn cancel(&self) {
-        let mut progress = self.progress.lock().unwrap();
-        if let Some(ref mut p) = *progress {
-            p.state = DownloadState::Failed;
-        }
+        self.set_state(DownloadState::Failed);
     }
 
     /// Clean up partial downloads
@@ -236,7 +552,10 @@ impl UpdateDownloader {
                 let entry = entry?;
                 let path = entry.path();
 
-                if path.extension().is_some_and(|e| e == "partial") {
+                if path
+                    .extension()
+                    .is_some_and(|e| e == "partial" || e == "part" || e == "progress")
+                {
                     fs::remove_file(path)?;
                 }
             }
@@ -246,40 +565,29 @@ impl UpdateDownloader {
 
     /// Get available disk space
     pub fn available_space(&self) -> Result<u64, UpdateError> {
-        // Use statvfs on Unix
-        #[cfg(unix)]
-        {
-            use std::os::unix::ffi::OsStrExt;
-
-            let path = self.download_dir.as_os_str();
-            let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
-
-            let c_path = std::ffi::CString::new(path.as_bytes()).map_err(|e| {
-                UpdateError::Io(std::io::Error::new(std::io::ErrorKind::InvalidInput, e))
-            })?;
-
-            let result = unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) };
-
-            if result == 0 {
-                // Types vary by platform (u32 on Linux, u64 on macOS)
-                #[allow(clippy::useless_conversion)]
-                Ok(u64::from(stat.f_bavail) * u64::from(stat.f_bsize))
-            } else {
-                Err(UpdateError::Io(std::io::Error::last_os_error()))
-            }
-        }
-
-        #[cfg(not(unix))]
-        {
-            // Fallback: assume enough space
-            Ok(u64::MAX)
-        }
+        available_space(&self.download_dir)
     }
 }
 
+/// Bytes available to unprivileged users on the filesystem holding
+/// `path`, which may not exist yet
+pub(crate) fn available_space(path: &Path) -> Result<u64, UpdateError> {
+    // Measure the nearest directory that does exist
+    let existing = path
+        .ancestors()
+        .find(|p| p.exists())
+        .unwrap_or(Path::new("/"));
+    let stat = nix::sys::statvfs::statvfs(existing).map_err(std::io::Error::from)?;
+
+    // Types vary by platform (u32 on 32-bit ARM, u64 elsewhere)
+    #[allow(clippy::useless_conversion)]
+    Ok(u64::from(stat.blocks_available()) * u64::from(stat.fragment_size()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::{Arc, Mutex};
 
     #[test]
     fn test_progress_percent() {
@@ -288,7 +596,8 @@ mod tests {
             downloaded: 50,
             speed: 10,
             eta: 5,
-            state: DownloadState::Downloading,
+            resumed_bytes: 0,
+            state: DownloadState::Downloading { resume: false },
         };
 
         assert_eq!(progress.percent(), 50);
@@ -301,9 +610,329 @@ mod tests {
             downloaded: 0,
             speed: 0,
             eta: 0,
+            resumed_bytes: 0,
             state: DownloadState::Pending,
         };
 
         assert_eq!(progress.percent(), 0);
     }
+
+    #[test]
+    fn test_next_source_skips_bad() {
+        assert_eq!(next_source(&[1, 0, 0], 0), Some(1));
+        assert_eq!(next_source(&[0, 0, 1], 2), Some(0));
+        // A lone source gets one more try before it is considered bad
+        assert_eq!(next_source(&[1], 0), Some(0));
+        assert_eq!(next_source(&[2, 2], 1), None);
+    }
+
+    #[test]
+    fn test_parse_content_range() {
+        assert_eq!(
+            parse_content_range("bytes 100-999/1000"),
+            Some((100, Some(1000)))
+        );
+        assert_eq!(parse_content_range("bytes 5-9/*"), Some((5, None)));
+        assert_eq!(parse_content_range("items 0-1/2"), None);
+        assert_eq!(parse_content_range("bytes x-9/10"), None);
+    }
+
+    /// Serve fixed bodies by path, logging each request's path and range
+    async fn serve(files: Vec<(&'static str, Vec<u8>)>) -> (String, Arc<Mutex<Vec<String>>>) {
+        serve_with(files, true).await
+    }
+
+    /// Like `serve`, optionally ignoring `Range` headers
+    async fn serve_with(
+        files: Vec<(&'static str, Vec<u8>)>,
+        ranges: bool,
+    ) -> (String, Arc<Mutex<Vec<String>>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = requests.clone();
+
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let n = stream.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                let path = request.split_whitespace().nth(1).unwrap_or("").to_string();
+                let range = request.lines().find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("range")
+                        .then(|| value.trim().to_string())
+                });
+                log.lock().unwrap().push(match &range {
+                    Some(range) => format!("{} {}", path, range),
+                    None => path.clone(),
+                });
+
+                let body = files
+                    .iter()
+                    .find(|(p, _)| *p == path)
+                    .map(|(_, b)| b.clone())
+                    .unwrap_or_default();
+                let start = range
+                    .as_deref()
+                    .and_then(|r| r.strip_prefix("bytes="))
+                    .and_then(|r| r.strip_suffix('-'))
+                    .and_then(|r| r.parse::<usize>().ok())
+                    .filter(|_| ranges);
+
+                let (header, body) = match start {
+                    Some(start) if start < body.len() => (
+                        format!(
+                            "HTTP/1.1 206 Partial Content\r\ncontent-range: bytes {}-{}/{}\r\n\
+                             content-length: {}\r\nconnection: close\r\n\r\n",
+                            start,
+                            body.len() - 1,
+                            body.len(),
+                            body.len() - start
+                        ),
+                        body[start..].to_vec(),
+                    ),
+                    Some(_) => (
+                        "HTTP/1.1 416 Range Not Satisfiable\r\ncontent-length: 0\r\n\
+                         connection: close\r\n\r\n"
+                            .to_string(),
+                        Vec::new(),
+                    ),
+                    None => (
+                        format!(
+                            "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                            body.len()
+                        ),
+                        body,
+                    ),
+                };
+                let _ = stream.write_all(header.as_bytes()).await;
+                let _ = stream.write_all(&body).await;
+            }
+        });
+
+        (format!("http://{}", addr), requests)
+    }
+
+    fn update_info(base: &str, mirrors: &[&str], body: &[u8]) -> UpdateInfo {
+        UpdateInfo {
+            version: "1.0.0".to_string(),
+            channel: crate::UpdateChannel::Stable,
+            download_url: format!("{}/primary", base),
+            mirrors: mirrors.iter().map(|m| format!("{}{}", base, m)).collect(),
+            size: body.len() as u64,
+            sha256: HashVerifier::sha256_data(body),
+            signature: String::new(),
+            release_notes: None,
+            release_date: "2024-01-01".to_string(),
+            critical: false,
+            min_version: None,
+            manifest_url: None,
+            delta_base: None,
+            manifest_signature: None,
+            key_id: None,
+        }
+    }
+
+    fn no_proxy_client() -> reqwest::Client {
+        crate::build_client(Duration::from_secs(5), &crate::ProxySettings::default()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_hash_mismatch_moves_to_mirror() {
+        let good = b"good update package".to_vec();
+        let (base, requests) = serve(vec![
+            ("/primary", b"corrupted by a bad node".to_vec()),
+            ("/mirror", good.clone()),
+        ])
+        .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let downloader =
+            UpdateDownloader::new(dir.path().to_path_buf(), 3).with_client(no_proxy_client());
+
+        let path = downloader
+            .download(&update_info(&base, &["/mirror"], &good))
+            .await
+            .unwrap();
+
+        assert_eq!(fs::read(path).unwrap(), good);
+        assert_eq!(*requests.lock().unwrap(), ["/primary", "/mirror"]);
+        assert_eq!(
+            downloader.progress().unwrap().state,
+            DownloadState::Completed
+        );
+    }
+
+    #[test]
+    fn test_speed_window_smooths_bursts() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut window = SpeedWindow::default();
+
+        assert_eq!(window.record(at(0), 0), 0);
+        // A 2MB burst after a second of nothing averages out
+        assert_eq!(window.record(at(1000), 0), 0);
+        assert_eq!(window.record(at(2000), 2_000_000), 1_000_000);
+
+        // Old samples fall out of the window
+        assert_eq!(window.record(at(6000), 2_000_000), 0);
+        assert_eq!(window.record(at(7000), 3_000_000), 200_000);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_sees_completion() {
+        let body = b"update package".to_vec();
+        let (base, _) = serve(vec![("/primary", body.clone())]).await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let downloader =
+            UpdateDownloader::new(dir.path().to_path_buf(), 1).with_client(no_proxy_client());
+        let mut events = downloader.subscribe();
+        assert!(events.borrow_and_update().is_none());
+
+        downloader
+            .download(&update_info(&base, &[], &body))
+            .await
+            .unwrap();
+
+        assert!(events.has_changed().unwrap());
+        let progress = events.borrow_and_update().clone().unwrap();
+        assert_eq!(progress.state, DownloadState::Completed);
+        assert_eq!(progress.downloaded, body.len() as u64);
+    }
+
+    #[tokio::test]
+    async fn test_bad_source_gives_verification_error() {
+        let (base, requests) = serve(vec![("/primary", b"always wrong".to_vec())]).await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let downloader =
+            UpdateDownloader::new(dir.path().to_path_buf(), 5).with_client(no_proxy_client());
+
+        let result = downloader
+            .download(&update_info(&base, &[], b"expected"))
+            .await;
+
+        assert!(matches!(result, Err(UpdateError::VerificationFailed(_))));
+        // Dropped after two mismatches instead of using every retry
+        assert_eq!(requests.lock().unwrap().len(), 2);
+        assert!(!dir.path().join("rexos-1.0.0.tar.gz.part").exists());
+        assert!(!dir.path().join("rexos-1.0.0.tar.gz.progress").exists());
+    }
+
+    /// Leave a partial download, with its sidecar if given
+    fn write_partial(dir: &Path, data: &[u8], progress: Option<PartProgress>) {
+        fs::write(dir.join("rexos-1.0.0.tar.gz.part"), data).unwrap();
+        if let Some(progress) = progress {
+            progress
+                .save(&dir.join("rexos-1.0.0.tar.gz.progress"))
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resume_from_partial() {
+        let body = b"0123456789 rest of the update package".to_vec();
+        let (base, requests) = serve(vec![("/primary", body.clone())]).await;
+
+        let dir = tempfile::tempdir().unwrap();
+        // Bytes past the recorded offset may not have been synced
+        write_partial(
+            dir.path(),
+            b"0123456789 re??",
+            Some(PartProgress {
+                written: 10,
+                total: Some(body.len() as u64),
+            }),
+        );
+
+        let downloader =
+            UpdateDownloader::new(dir.path().to_path_buf(), 3).with_client(no_proxy_client());
+        let path = downloader
+            .download(&update_info(&base, &[], &body))
+            .await
+            .unwrap();
+
+        assert_eq!(fs::read(path).unwrap(), body);
+        assert_eq!(*requests.lock().unwrap(), ["/primary bytes=10-"]);
+        assert_eq!(downloader.progress().unwrap().resumed_bytes, 10);
+        assert!(!dir.path().join("rexos-1.0.0.tar.gz.progress").exists());
+    }
+
+    #[tokio::test]
+    async fn test_resume_falls_back_without_range_support() {
+        let body = b"complete update package".to_vec();
+        let (base, requests) = serve_with(vec![("/primary", body.clone())], false).await;
+
+        let dir = tempfile::tempdir().unwrap();
+        write_partial(
+            dir.path(),
+            b"complete",
+            Some(PartProgress {
+                written: 8,
+                total: Some(body.len() as u64),
+            }),
+        );
+
+        let downloader =
+            UpdateDownloader::new(dir.path().to_path_buf(), 3).with_client(no_proxy_client());
+        let path = downloader
+            .download(&update_info(&base, &[], &body))
+            .await
+            .unwrap();
+
+        // The 200 body is used from the start, not appended
+        assert_eq!(fs::read(path).unwrap(), body);
+        assert_eq!(*requests.lock().unwrap(), ["/primary bytes=8-"]);
+        assert_eq!(downloader.progress().unwrap().resumed_bytes, 0);
+    }
+
+    #[tokio::test]
+    async fn test_stale_partial_is_discarded() {
+        let body = b"new build of the update package".to_vec();
+        let (base, requests) = serve(vec![("/primary", body.clone())]).await;
+
+        let dir = tempfile::tempdir().unwrap();
+        // Written against an older, larger file on the server
+        write_partial(
+            dir.path(),
+            b"old build",
+            Some(PartProgress {
+                written: 9,
+                total: Some(4096),
+            }),
+        );
+
+        let downloader =
+            UpdateDownloader::new(dir.path().to_path_buf(), 3).with_client(no_proxy_client());
+        let path = downloader
+            .download(&update_info(&base, &[], &body))
+            .await
+            .unwrap();
+
+        assert_eq!(fs::read(path).unwrap(), body);
+        assert_eq!(*requests.lock().unwrap(), ["/primary bytes=9-", "/primary"]);
+    }
+
+    #[tokio::test]
+    async fn test_partial_without_sidecar_restarts() {
+        let body = b"update package".to_vec();
+        let (base, requests) = serve(vec![("/primary", body.clone())]).await;
+
+        let dir = tempfile::tempdir().unwrap();
+        write_partial(dir.path(), b"update", None);
+
+        let downloader =
+            UpdateDownloader::new(dir.path().to_path_buf(), 3).with_client(no_proxy_client());
+        let path = downloader
+            .download(&update_info(&base, &[], &body))
+            .await
+            .unwrap();
+
+        assert_eq!(fs::read(path).unwrap(), body);
+        assert_eq!(*requests.lock().unwrap(), ["/primary"]);
+    }
 }
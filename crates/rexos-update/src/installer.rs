@@ -1,14 +1,52 @@
 //! Update installation with rollback support
-
-use crate::UpdateError;
-use flate2::read::GzDecoder;
+//!
+//! Two modes are supported: replacing files from a tarball, and a sync
+//! install that compares the manifest's per-file hashes with what is on
+//! disk and fetches only the files that differ. A tarball may be a delta
+//! package, whose manifest has `"type": "delta"` and whose entries name
+//! bsdiff patches to apply to the installed files.
+
+use crate::ab::copy_tree;
+use crate::compression::open_package;
+use crate::manifest::{FileAction, FileEntry, FileType};
+use crate::{HashVerifier, UpdateDownloader, UpdateError, UpdateManifest};
+use serde::{Deserialize, Serialize};
 use std::fs::{self, File};
-use std::io::{BufReader, Read};
-use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tar::Archive;
 
+/// Backup directory that survives a reboot, so init can roll back an
+/// update that doesn't boot
+pub const BACKUP_DIR: &str = "/var/lib/rexos/update-backup";
+
+/// Marker in the backup directory for an install not yet confirmed by a
+/// good boot; holds the installed version
+pub const PENDING_MARKER: &str = "pending-verification";
+
+/// Staging directory that survives a reboot, so a staged update can be
+/// applied on a later shutdown
+pub const STAGING_DIR: &str = "/var/lib/rexos/update-staging";
+
+/// Install step reported while a staged update waits to be applied
+pub const STAGED_STEP: &str = "Staged, awaiting reboot";
+
+/// Staged update metadata in the staging directory
+const STAGED_METADATA: &str = "staged.json";
+
+/// Default time a post-install script may run before it's killed
+pub const POST_INSTALL_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Post-install script output, kept in staging when the script fails
+pub const POST_INSTALL_LOG: &str = "post-install.log";
+
+/// `PATH` for post-install scripts, which get no other inherited
+/// environment
+const SCRIPT_PATH: &str = "/usr/sbin:/usr/bin:/sbin:/bin";
+
 /// Installation progress
 #[derive(Debug, Clone)]
 pub struct InstallProgress {
@@ -33,6 +71,22 @@ impl InstallProgress {
             ((self.current_step as f64 / self.total_steps as f64) * 100.0) as u8
         }
     }
+
+    /// Check if an update is staged and waiting to be applied
+    pub fn is_awaiting_reboot(&self) -> bool {
+        self.step == STAGED_STEP
+    }
+}
+
+/// An update extracted and verified in staging, applied later
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StagedUpdate {
+    /// Version staged
+    pub version: String,
+    /// When it was staged (seconds since the Unix epoch)
+    pub staged_at: String,
+    /// Staged files, relative to the staging directory
+    pub files: Vec<PathBuf>,
 }
 
 /// Installation result
@@ -50,10 +104,71 @@ pub struct InstallResult {
     pub needs_reboot: bool,
 }
 
+/// What installing a package would change, from a dry run
+#[derive(Debug, Default)]
+pub struct InstallPlan {
+    /// Version in the package
+    pub version: String,
+    /// Installed files whose contents differ
+    pub updated: Vec<PathBuf>,
+    /// Files not yet installed
+    pub added: Vec<PathBuf>,
+    /// Installed files the update removes
+    pub removed: Vec<PathBuf>,
+    /// Number of files already up to date
+    pub unchanged: usize,
+    /// Bytes the updated and added files would write
+    pub bytes_written: u64,
+    /// Package has a post-install script (not run in a dry run)
+    pub has_post_install: bool,
+    /// Package asks for a reboot
+    pub needs_reboot: bool,
+}
+
+impl InstallPlan {
+    /// Counts in the shape of a real install's result
+    pub fn summary(&self) -> InstallResult {
+        InstallResult {
+            version: self.version.clone(),
+            files_updated: self.updated.len() as u32,
+            files_added: self.added.len() as u32,
+            files_removed: self.removed.len() as u32,
+            needs_reboot: self.needs_reboot,
+        }
+    }
+}
+
+/// Files a sync install needs to change
+#[derive(Debug, Default)]
+pub struct SyncPlan {
+    /// Installed files whose hash differs
+    pub changed: Vec<FileEntry>,
+    /// Files not yet installed
+    pub added: Vec<FileEntry>,
+    /// Number of files already up to date
+    pub unchanged: usize,
+    /// Installed files the update removes
+    pub removed: Vec<PathBuf>,
+}
+
+impl SyncPlan {
+    /// Bytes that need to be downloaded
+    pub fn download_size(&self) -> u64 {
+        self.changed.iter().chain(&self.added).map(|f| f.size).sum()
+    }
+
+    /// Check if there is nothing to do
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
 /// Installs updates with rollback support
 pub struct UpdateInstaller {
     staging_dir: PathBuf,
     backup_dir: PathBuf,
+    root_dir: PathBuf,
+    script_timeout: Duration,
     progress: Arc<Mutex<Option<InstallProgress>>>,
 }
 
@@ -68,12 +183,86 @@ impl UpdateInstaller {
         Self {
             staging_dir,
             backup_dir,
+            root_dir: PathBuf::from("/"),
+            script_timeout: POST_INSTALL_TIMEOUT,
             progress: Arc::new(Mutex::new(None)),
         }
     }
 
+    /// Install into a different root directory
+    pub fn with_root_dir(mut self, root_dir: PathBuf) -> Self {
+        self.root_dir = root_dir;
+        self
+    }
+
+    /// Keep backups in a different directory
+    pub fn with_backup_dir(mut self, backup_dir: PathBuf) -> Self {
+        self.backup_dir = backup_dir;
+        self
+    }
+
+    /// Kill post-install scripts that run longer than `timeout`
+    pub fn with_script_timeout(mut self, timeout: Duration) -> Self {
+        self.script_timeout = timeout;
+        self
+    }
+
     /// Install an update package
-    pub async fn install(&self, package_path: &PathBuf) -> Result<InstallResult, UpdateError> {
+    pub async fn install(&self, package_path: &Path) -> Result<InstallResult, UpdateError> {
+        let files = self.stage(package_path)?;
+        self.finish(&package_version(package_path), &files)
+    }
+
+    /// Extract and verify an update package, leaving it in staging to be
+    /// applied later with [`UpdateInstaller::apply_staged`]
+    ///
+    /// The update only survives a reboot if the staging directory does.
+    pub fn stage_package(&self, package_path: &Path) -> Result<StagedUpdate, UpdateError> {
+        let files = self.stage(package_path)?;
+        let staged = StagedUpdate {
+            version: package_version(package_path),
+            staged_at: chrono::Utc::now().to_rfc3339(),
+            files,
+        };
+
+        let json = serde_json::to_string_pretty(&staged)
+            .map_err(|e| UpdateError::InstallFailed(e.to_string()))?;
+        fs::write(self.staging_dir.join(STAGED_METADATA), json)?;
+
+        let total = staged.files.len() as u32;
+        self.set_progress(STAGED_STEP, 3, 6, total, total);
+        tracing::info!("Update to {} staged", staged.version);
+        Ok(staged)
+    }
+
+    /// Update waiting in staging, if any
+    pub fn staged(&self) -> Option<StagedUpdate> {
+        let content = fs::read_to_string(self.staging_dir.join(STAGED_METADATA)).ok()?;
+        match serde_json::from_str(&content) {
+            Ok(staged) => Some(staged),
+            Err(e) => {
+                tracing::warn!("Ignoring invalid {}: {}", STAGED_METADATA, e);
+                None
+            }
+        }
+    }
+
+    /// Apply a staged update, returning `None` if nothing is staged
+    ///
+    /// The staged metadata is removed first, so an update that fails to
+    /// apply isn't retried on every shutdown.
+    pub async fn apply_staged(&self) -> Result<Option<InstallResult>, UpdateError> {
+        let Some(staged) = self.staged() else {
+            return Ok(None);
+        };
+        fs::remove_file(self.staging_dir.join(STAGED_METADATA))?;
+
+        tracing::info!("Applying staged update to {}", staged.version);
+        self.finish(&staged.version, &staged.files).map(Some)
+    }
+
+    /// Extract, verify and patch a package in staging, ready to apply
+    pub(crate) fn stage(&self, package_path: &Path) -> Result<Vec<PathBuf>, UpdateError> {
         // Initialize progress
         self.set_progress("Preparing installation", 1, 6, 0, 0);
 
@@ -84,36 +273,42 @@ impl UpdateInstaller {
         self.set_progress("Extracting update package", 2, 6, 0, 0);
         let files = self.extract_package(package_path)?;
 
-        // Step 2: Verify extracted files
+        // Step 2: Verify extracted files, rebuilding patched ones
         self.set_progress("Verifying files", 3, 6, 0, files.len() as u32);
         self.verify_extracted_files(&files)?;
+        self.apply_patches(&files)
+    }
 
+    /// Back up, apply and record a prepared update
+    fn finish(&self, version: &str, files: &[PathBuf]) -> Result<InstallResult, UpdateError> {
         // Step 3: Create backup of current files
         self.set_progress("Creating backup", 4, 6, 0, files.len() as u32);
-        self.create_backup(&files)?;
+        self.create_backup(files)?;
 
         // Step 4: Apply update
         self.set_progress("Installing files", 5, 6, 0, files.len() as u32);
-        let (updated, added, removed) = self.apply_update(&files)?;
+        let (updated, added, removed) = self.apply_update(files)?;
 
-        // Step 5: Run post-install scripts
+        // Step 5: Run post-install scripts, undoing the update if they fail
         self.set_progress("Running post-install scripts", 6, 6, 0, 0);
-        let needs_reboot = self.run_post_install()?;
+        let needs_reboot = match self.run_post_install() {
+            Ok(needs_reboot) => needs_reboot,
+            Err(e) => {
+                tracing::error!("{}, rolling back", e);
+                if let Err(rollback) = self.restore_backup() {
+                    tracing::error!("Rollback failed: {}", rollback);
+                }
+                return Err(e);
+            }
+        };
 
         // Clean up staging
         fs::remove_dir_all(&self.staging_dir).ok();
 
-        // Parse version from package name
-        let version = package_path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .and_then(|s| s.strip_prefix("rexos-"))
-            .and_then(|s| s.strip_suffix(".tar"))
-            .unwrap_or("unknown")
-            .to_string();
+        self.mark_pending(version)?;
 
         Ok(InstallResult {
-            version,
+            version: version.to_string(),
             files_updated: updated,
             files_added: added,
             files_removed: removed,
@@ -121,18 +316,280 @@ impl UpdateInstaller {
         })
     }
 
+    /// Work out what installing a package would change, without changing it
+    ///
+    /// The package is extracted and verified in a scratch directory beside
+    /// staging, then compared with the root directory. Nothing else is
+    /// written, so an update already staged survives, and post-install
+    /// scripts aren't run.
+    pub fn dry_run(&self, package_path: &Path) -> Result<InstallPlan, UpdateError> {
+        let mut name = self.staging_dir.file_name().unwrap_or_default().to_owned();
+        name.push(".dry-run");
+        let scratch = UpdateInstaller::new(self.staging_dir.with_file_name(name))
+            .with_root_dir(self.root_dir.clone());
+
+        fs::create_dir_all(&scratch.staging_dir)?;
+        let result = scratch.plan_install(package_path);
+        fs::remove_dir_all(&scratch.staging_dir).ok();
+        result
+    }
+
+    /// Extract, verify and compare a package against the root
+    fn plan_install(&self, package_path: &Path) -> Result<InstallPlan, UpdateError> {
+        let files = self.extract_package(package_path)?;
+        self.verify_extracted_files(&files)?;
+        let files = self.apply_patches(&files)?;
+
+        let mut plan = InstallPlan {
+            version: package_version(package_path),
+            ..Default::default()
+        };
+
+        for file in &files {
+            let source = self.staging_dir.join(file);
+            if is_metadata(file) || source.is_dir() {
+                continue;
+            }
+
+            let dest = self.root_dir.join(file);
+            if !dest.exists() {
+                plan.added.push(file.clone());
+            } else if dest.is_file()
+                && self.compute_sha256(&dest)? == self.compute_sha256(&source)?
+            {
+                plan.unchanged += 1;
+                continue;
+            } else {
+                plan.updated.push(file.clone());
+            }
+            plan.bytes_written += fs::metadata(&source)?.len();
+        }
+
+        plan.removed = self
+            .removal_paths()?
+            .into_iter()
+            .filter(|path| self.root_dir.join(path).exists())
+            .collect();
+        plan.has_post_install = self.staging_dir.join("post-install.sh").exists();
+        plan.needs_reboot = self.staging_dir.join(".needs-reboot").exists();
+
+        tracing::info!(
+            "Dry run of {}: {} updated, {} added, {} removed, {} bytes",
+            plan.version,
+            plan.updated.len(),
+            plan.added.len(),
+            plan.removed.len(),
+            plan.bytes_written
+        );
+        Ok(plan)
+    }
+
+    /// Compare the manifest against installed files
+    pub fn plan_sync(&self, manifest: &UpdateManifest) -> Result<SyncPlan, UpdateError> {
+        let mut plan = SyncPlan::default();
+
+        for entry in &manifest.files {
+            if matches!(entry.file_type, FileType::Directory | FileType::Symlink) {
+                continue;
+            }
+
+            let installed = self.root_dir.join(relative_path(&entry.path)?);
+
+            if !installed.exists() {
+                plan.added.push(entry.clone());
+                continue;
+            }
+
+            // Config files keep user changes once installed
+            if entry.action == FileAction::Config || entry.file_type == FileType::Config {
+                plan.unchanged += 1;
+                continue;
+            }
+
+            let hash = HashVerifier::sha256_file(&installed)
+                .map_err(|e| UpdateError::InstallFailed(e.to_string()))?;
+            if hash == entry.sha256.to_lowercase() {
+                plan.unchanged += 1;
+            } else {
+                plan.changed.push(entry.clone());
+            }
+        }
+
+        for path in &manifest.remove {
+            let relative = relative_path(path)?;
+            if self.root_dir.join(&relative).exists() {
+                plan.removed.push(relative);
+            }
+        }
+
+        Ok(plan)
+    }
+
+    /// Install by fetching only files whose hashes differ from disk
+    ///
+    /// Changed and removed paths are backed up first, so [`rollback`]
+    /// restores them and deletes files the sync added.
+    ///
+    /// [`rollback`]: UpdateInstaller::rollback
+    pub async fn sync_install(
+        &self,
+        manifest: &UpdateManifest,
+        downloader: &UpdateDownloader,
+    ) -> Result<InstallResult, UpdateError> {
+        self.set_progress("Comparing installed files", 1, 5, 0, 0);
+        let plan = self.plan_sync(manifest)?;
+        let fetch: Vec<&FileEntry> = plan.changed.iter().chain(&plan.added).collect();
+
+        tracing::info!(
+            "Sync install {}: {} changed, {} added, {} removed, {} unchanged ({} bytes to fetch)",
+            manifest.version,
+            plan.changed.len(),
+            plan.added.len(),
+            plan.removed.len(),
+            plan.unchanged,
+            plan.download_size()
+        );
+
+        // Step 1: Fetch changed files into staging
+        self.set_progress("Downloading changed files", 2, 5, 0, fetch.len() as u32);
+        if self.staging_dir.exists() {
+            fs::remove_dir_all(&self.staging_dir)?;
+        }
+        fs::create_dir_all(&self.staging_dir)?;
+
+        for (i, entry) in fetch.iter().enumerate() {
+            let url = manifest.file_url(entry).ok_or_else(|| {
+                UpdateError::InvalidManifest("Sync install requires files_url".into())
+            })?;
+            let staged = self.staging_dir.join(relative_path(&entry.path)?);
+            downloader.fetch_file(&url, &staged, &entry.sha256).await?;
+            self.set_files_processed(i as u32 + 1);
+        }
+
+        // Step 2: Back up everything that will be overwritten or removed
+        self.set_progress("Creating backup", 3, 5, 0, fetch.len() as u32);
+        let mut touched: Vec<PathBuf> = fetch
+            .iter()
+            .map(|e| relative_path(&e.path))
+            .collect::<Result<_, _>>()?;
+        touched.extend(plan.removed.iter().cloned());
+        self.create_backup(&touched)?;
+
+        // Removed directories are copied whole so rollback can bring them back
+        for relative in &plan.removed {
+            let source = self.root_dir.join(relative);
+            if source.is_dir() {
+                let dest = self.backup_dir.join(relative);
+                fs::create_dir_all(&dest)?;
+                copy_tree(&source, &dest)?;
+            }
+        }
+
+        // Step 3: Move staged files into place
+        self.set_progress("Installing files", 4, 5, 0, fetch.len() as u32);
+        for (i, entry) in fetch.iter().enumerate() {
+            let relative = relative_path(&entry.path)?;
+            let dest = self.root_dir.join(&relative);
+
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(self.staging_dir.join(&relative), &dest)?;
+
+            // Avoid if-let chains for MSRV 1.85 compatibility
+            #[allow(clippy::collapsible_if)]
+            if let Some(ref mode) = entry.mode {
+                if let Ok(mode) = u32::from_str_radix(mode.trim_start_matches("0o"), 8) {
+                    use std::os::unix::fs::PermissionsExt;
+                    fs::set_permissions(&dest, fs::Permissions::from_mode(mode))?;
+                }
+            }
+            self.set_files_processed(i as u32 + 1);
+        }
+
+        // Step 4: Remove files dropped by the update
+        self.set_progress("Removing old files", 5, 5, 0, plan.removed.len() as u32);
+        for relative in &plan.removed {
+            let path = self.root_dir.join(relative);
+            if path.is_dir() {
+                fs::remove_dir_all(&path)?;
+            } else {
+                fs::remove_file(&path)?;
+            }
+        }
+
+        fs::remove_dir_all(&self.staging_dir).ok();
+        self.mark_pending(&manifest.version)?;
+
+        Ok(InstallResult {
+            version: manifest.version.clone(),
+            files_updated: plan.changed.len() as u32,
+            files_added: plan.added.len() as u32,
+            files_removed: plan.removed.len() as u32,
+            needs_reboot: manifest.requires_reboot,
+        })
+    }
+
+    /// Check applied files read back the same as the staged ones
+    pub(crate) fn verify_installed(&self, files: &[PathBuf]) -> Result<(), UpdateError> {
+        for file in files {
+            let source = self.staging_dir.join(file);
+            if is_metadata(file) || !source.is_file() {
+                continue;
+            }
+
+            let dest = self.root_dir.join(file);
+            if !dest.is_file() || self.compute_sha256(&dest)? != self.compute_sha256(&source)? {
+                return Err(UpdateError::VerificationFailed(format!(
+                    "{} doesn't match the package after install",
+                    file.display()
+                )));
+            }
+        }
+        Ok(())
+    }
+
     /// Extract update package to staging directory
-    fn extract_package(&self, package_path: &PathBuf) -> Result<Vec<PathBuf>, UpdateError> {
-        let file = File::open(package_path)?;
-        let gz = GzDecoder::new(BufReader::new(file));
-        let mut archive = Archive::new(gz);
+    ///
+    /// Unpacks into a temporary sibling directory that replaces staging
+    /// only once every entry is written, so an interrupted extraction
+    /// never leaves partial files in staging. Leftovers from earlier
+    /// interrupted attempts are removed first.
+    fn extract_package(&self, package_path: &Path) -> Result<Vec<PathBuf>, UpdateError> {
+        self.remove_partial_extractions()?;
+
+        let partial = self.partial_dir();
+        fs::create_dir_all(&partial)?;
+
+        let files = match self.unpack_into(package_path, &partial) {
+            Ok(files) => files,
+            Err(e) => {
+                fs::remove_dir_all(&partial).ok();
+                return Err(e);
+            }
+        };
+
+        if self.staging_dir.exists() {
+            fs::remove_dir_all(&self.staging_dir)?;
+        }
+        fs::rename(&partial, &self.staging_dir)?;
+
+        tracing::info!("Extracted {} files to staging", files.len());
+        Ok(files)
+    }
+
+    /// Unpack every entry of a package under `dir`
+    fn unpack_into(&self, package_path: &Path, dir: &Path) -> Result<Vec<PathBuf>, UpdateError> {
+        let (compression, reader) = open_package(package_path)?;
+        tracing::debug!("Package compression: {}", compression.name());
+        let mut archive = Archive::new(reader);
 
         let mut files = Vec::new();
 
         for entry in archive.entries()? {
             let mut entry = entry?;
             let path = entry.path()?.to_path_buf();
-            let dest = self.staging_dir.join(&path);
+            let dest = dir.join(&path);
 
             // Create parent directories
             if let Some(parent) = dest.parent() {
@@ -144,10 +601,42 @@ impl UpdateInstaller {
             files.push(path);
         }
 
-        tracing::info!("Extracted {} files to staging", files.len());
         Ok(files)
     }
 
+    /// Name prefix of temporary extraction directories
+    fn partial_prefix(&self) -> String {
+        let name = self
+            .staging_dir
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "staging".to_string());
+        format!(".{}.partial-", name)
+    }
+
+    /// Temporary extraction directory next to staging
+    fn partial_dir(&self) -> PathBuf {
+        let parent = self.staging_dir.parent().unwrap_or(Path::new("/tmp"));
+        parent.join(format!("{}{}", self.partial_prefix(), std::process::id()))
+    }
+
+    /// Remove extraction directories left by interrupted attempts
+    fn remove_partial_extractions(&self) -> Result<(), UpdateError> {
+        let parent = self.staging_dir.parent().unwrap_or(Path::new("/tmp"));
+        let Ok(entries) = fs::read_dir(parent) else {
+            return Ok(());
+        };
+
+        let prefix = self.partial_prefix();
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with(&prefix) {
+                tracing::warn!("Removing partial extraction {}", entry.path().display());
+                fs::remove_dir_all(entry.path())?;
+            }
+        }
+        Ok(())
+    }
+
     /// Verify extracted files match manifest
     fn verify_extracted_files(&self, _files: &[PathBuf]) -> Result<(), UpdateError> {
         // Check for manifest
@@ -162,6 +651,7 @@ impl UpdateInstaller {
         let manifest_content = fs::read_to_string(&manifest_path)?;
         let manifest: serde_json::Value = serde_json::from_str(&manifest_content)
             .map_err(|e| UpdateError::InvalidManifest(e.to_string()))?;
+        UpdateManifest::check_schema(&manifest)?;
 
         if let Some(file_hashes) = manifest.get("files").and_then(|f| f.as_object()) {
             for (file, expected_hash) in file_hashes {
@@ -187,6 +677,119 @@ impl UpdateInstaller {
         Ok(())
     }
 
+    /// Rebuild patched files of a delta package inside staging
+    ///
+    /// Each patch is applied to the installed file and the result checked
+    /// against the manifest before anything outside staging changes, so a
+    /// wrong base or a bad patch aborts with the system untouched. Returns
+    /// the staged files with patches replaced by the files they produce.
+    fn apply_patches(&self, files: &[PathBuf]) -> Result<Vec<PathBuf>, UpdateError> {
+        let manifest_path = self.staging_dir.join("manifest.json");
+        if !manifest_path.exists() {
+            return Ok(files.to_vec());
+        }
+
+        let manifest_content = fs::read_to_string(&manifest_path)?;
+        let value: serde_json::Value = serde_json::from_str(&manifest_content)
+            .map_err(|e| UpdateError::InvalidManifest(e.to_string()))?;
+        if value.get("type").and_then(|t| t.as_str()) != Some("delta") {
+            return Ok(files.to_vec());
+        }
+        let manifest = UpdateManifest::from_json(&manifest_content)?;
+
+        let mut patches = Vec::new();
+        let mut patched = Vec::new();
+
+        for entry in &manifest.files {
+            let target = relative_path(&entry.path)?;
+            let staged = self.staging_dir.join(&target);
+
+            let Some(patch) = &entry.patch else {
+                // Whole files in a delta package are checked here too
+                if staged.is_file() && self.compute_sha256(&staged)? != entry.sha256.to_lowercase()
+                {
+                    return Err(UpdateError::VerificationFailed(format!(
+                        "Hash mismatch for {}",
+                        entry.path
+                    )));
+                }
+                continue;
+            };
+            let patch = relative_path(patch)?;
+
+            let installed = self.root_dir.join(&target);
+            let old = fs::read(&installed).map_err(|e| {
+                UpdateError::InstallFailed(format!(
+                    "Delta base {} unavailable: {}",
+                    installed.display(),
+                    e
+                ))
+            })?;
+
+            // Avoid if-let chains for MSRV 1.85 compatibility
+            #[allow(clippy::collapsible_if)]
+            if let Some(base) = &entry.base_sha256 {
+                if HashVerifier::sha256_data(&old) != base.to_lowercase() {
+                    return Err(UpdateError::VerificationFailed(format!(
+                        "{} doesn't match the delta base{}",
+                        entry.path,
+                        manifest
+                            .delta_base
+                            .as_ref()
+                            .map(|v| format!(" ({})", v))
+                            .unwrap_or_default()
+                    )));
+                }
+            }
+
+            let patch_data = fs::read(self.staging_dir.join(&patch))?;
+            let mut new = Vec::new();
+            bsdiff::patch(&old, &mut patch_data.as_slice(), &mut new).map_err(|e| {
+                UpdateError::InstallFailed(format!("Failed to patch {}: {}", entry.path, e))
+            })?;
+
+            if HashVerifier::sha256_data(&new) != entry.sha256.to_lowercase() {
+                return Err(UpdateError::VerificationFailed(format!(
+                    "Patched {} doesn't match the manifest",
+                    entry.path
+                )));
+            }
+
+            if let Some(parent) = staged.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&staged, &new)?;
+
+            // Keep the installed file's permissions unless the manifest sets them
+            let mode = entry
+                .mode
+                .as_ref()
+                .and_then(|mode| u32::from_str_radix(mode.trim_start_matches("0o"), 8).ok());
+            let permissions = match mode {
+                Some(mode) => {
+                    use std::os::unix::fs::PermissionsExt;
+                    fs::Permissions::from_mode(mode)
+                }
+                None => fs::metadata(&installed)?.permissions(),
+            };
+            fs::set_permissions(&staged, permissions)?;
+
+            fs::remove_file(self.staging_dir.join(&patch))?;
+            patches.push(patch);
+            patched.push(target);
+        }
+
+        tracing::info!("Applied {} delta patches", patched.len());
+
+        let mut staged: Vec<PathBuf> = files
+            .iter()
+            .filter(|file| !patches.contains(file) && !patched.contains(file))
+            .cloned()
+            .collect();
+        staged.extend(patched);
+        Ok(staged)
+    }
+
     /// Compute SHA256 hash of a file
     fn compute_sha256(&self, path: &PathBuf) -> Result<String, UpdateError> {
         use sha2::{Digest, Sha256};
@@ -214,12 +817,15 @@ impl UpdateInstaller {
         }
         fs::create_dir_all(&self.backup_dir)?;
 
-        let root = PathBuf::from("/");
+        let root = self.root_dir.clone();
+
+        // Files that don't exist yet are recorded so rollback can remove them
+        let mut added = Vec::new();
 
         for file in files {
             let source = root.join(file);
 
-            if source.exists() {
+            if source.is_file() {
                 let dest = self.backup_dir.join(file);
 
                 if let Some(parent) = dest.parent() {
@@ -227,13 +833,16 @@ impl UpdateInstaller {
                 }
 
                 fs::copy(&source, &dest)?;
+            } else if !source.exists() {
+                added.push(file.to_string_lossy());
             }
         }
 
         // Write backup manifest
         let manifest = serde_json::json!({
             "timestamp": chrono::Utc::now().to_rfc3339(),
-            "files": files.iter().map(|p| p.to_string_lossy()).collect::<Vec<_>>()
+            "files": files.iter().map(|p| p.to_string_lossy()).collect::<Vec<_>>(),
+            "added": added
         });
 
         fs::write(
@@ -246,16 +855,14 @@ impl UpdateInstaller {
     }
 
     /// Apply the update
-    fn apply_update(&self, files: &[PathBuf]) -> Result<(u32, u32, u32), UpdateError> {
-        let root = PathBuf::from("/");
+    pub(crate) fn apply_update(&self, files: &[PathBuf]) -> Result<(u32, u32, u32), UpdateError> {
+        let root = self.root_dir.clone();
         let mut updated = 0u32;
         let mut added = 0u32;
 
         for (i, file) in files.iter().enumerate() {
             // Skip manifest and metadata files
-            if file.to_string_lossy().ends_with("manifest.json")
-                || file.to_string_lossy().ends_with(".meta")
-            {
+            if is_metadata(file) {
                 continue;
             }
 
@@ -314,55 +921,104 @@ impl UpdateInstaller {
 
     /// Process file removals from update manifest
     fn process_removals(&self) -> Result<u32, UpdateError> {
+        let mut removed = 0u32;
+
+        for file in self.removal_paths()? {
+            let path = self.root_dir.join(file);
+
+            if path.exists() {
+                if path.is_dir() {
+                    fs::remove_dir_all(&path)?;
+                } else {
+                    fs::remove_file(&path)?;
+                }
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Paths listed for removal in the staged manifest
+    fn removal_paths(&self) -> Result<Vec<PathBuf>, UpdateError> {
         let manifest_path = self.staging_dir.join("manifest.json");
 
         if !manifest_path.exists() {
-            return Ok(0);
+            return Ok(Vec::new());
         }
 
         let manifest_content = fs::read_to_string(&manifest_path)?;
         let manifest: serde_json::Value = serde_json::from_str(&manifest_content)
             .map_err(|e| UpdateError::InvalidManifest(e.to_string()))?;
 
-        let mut removed = 0u32;
-        let root = PathBuf::from("/");
-
-        if let Some(removals) = manifest.get("remove").and_then(|r| r.as_array()) {
-            for file in removals {
-                if let Some(path_str) = file.as_str() {
-                    let path = root.join(path_str);
-
-                    if path.exists() {
-                        if path.is_dir() {
-                            fs::remove_dir_all(&path)?;
-                        } else {
-                            fs::remove_file(&path)?;
-                        }
-                        removed += 1;
-                    }
-                }
-            }
-        }
-
-        Ok(removed)
+        Ok(manifest
+            .get("remove")
+            .and_then(|r| r.as_array())
+            .map(|removals| {
+                removals
+                    .iter()
+                    .filter_map(|file| file.as_str())
+                    .map(PathBuf::from)
+                    .collect()
+            })
+            .unwrap_or_default())
     }
 
     /// Run post-install scripts
+    ///
+    /// The script runs from staging with only a minimal environment, not
+    /// init's, plus `REXOS_ROOT` and `REXOS_STAGING`. Its output goes to
+    /// [`POST_INSTALL_LOG`] in staging, and it's killed, along with
+    /// anything it started, once the script timeout passes.
     fn run_post_install(&self) -> Result<bool, UpdateError> {
+        use std::os::unix::process::CommandExt;
+
         let script_path = self.staging_dir.join("post-install.sh");
 
         if !script_path.exists() {
             return Ok(false);
         }
 
-        let output = Command::new("sh").arg(&script_path).output()?;
+        let log_path = self.staging_dir.join(POST_INSTALL_LOG);
+        let log = File::create(&log_path)?;
+        let mut child = Command::new("sh")
+            .arg(&script_path)
+            .current_dir(&self.staging_dir)
+            .env_clear()
+            .env("PATH", SCRIPT_PATH)
+            .env("HOME", "/")
+            .env("REXOS_ROOT", &self.root_dir)
+            .env("REXOS_STAGING", &self.staging_dir)
+            .stdin(Stdio::null())
+            .stdout(log.try_clone()?)
+            .stderr(log)
+            .process_group(0)
+            .spawn()?;
+
+        let deadline = Instant::now() + self.script_timeout;
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                // SAFETY: kill() has no memory safety requirements; the
+                // negative pid names the script's own process group
+                unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) };
+                let _ = child.wait();
+                return Err(UpdateError::InstallFailed(format!(
+                    "Post-install script timed out after {}s, see {}",
+                    self.script_timeout.as_secs(),
+                    log_path.display()
+                )));
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        };
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            tracing::error!("Post-install script failed: {}", stderr);
+        if !status.success() {
             return Err(UpdateError::InstallFailed(format!(
-                "Post-install script failed: {}",
-                stderr
+                "Post-install script failed ({}), see {}",
+                status,
+                log_path.display()
             )));
         }
 
@@ -373,6 +1029,11 @@ impl UpdateInstaller {
 
     /// Rollback to previous version
     pub async fn rollback(&self) -> Result<(), UpdateError> {
+        self.restore_backup()
+    }
+
+    /// Restore backed up files and remove added ones
+    fn restore_backup(&self) -> Result<(), UpdateError> {
         if !self.backup_dir.exists() {
             return Err(UpdateError::RollbackFailed("No backup available".into()));
         }
@@ -389,7 +1050,7 @@ impl UpdateInstaller {
         let manifest: serde_json::Value = serde_json::from_str(&manifest_content)
             .map_err(|e| UpdateError::RollbackFailed(e.to_string()))?;
 
-        let root = PathBuf::from("/");
+        let root = self.root_dir.clone();
 
         if let Some(files) = manifest.get("files").and_then(|f| f.as_array()) {
             for file in files {
@@ -398,7 +1059,13 @@ impl UpdateInstaller {
                     let backup = self.backup_dir.join(&path);
                     let dest = root.join(&path);
 
-                    if backup.exists() {
+                    if backup.is_dir() {
+                        // Directories the update removed
+                        if !dest.exists() {
+                            fs::create_dir_all(&dest)?;
+                            copy_tree(&backup, &dest)?;
+                        }
+                    } else if backup.exists() {
                         if let Some(parent) = dest.parent() {
                             fs::create_dir_all(parent)?;
                         }
@@ -408,13 +1075,76 @@ impl UpdateInstaller {
             }
         }
 
+        // Remove files the update added
+        if let Some(files) = manifest.get("added").and_then(|f| f.as_array()) {
+            for file in files.iter().filter_map(|f| f.as_str()) {
+                let dest = root.join(file);
+                if dest.is_file() {
+                    fs::remove_file(&dest)?;
+                }
+            }
+        }
+
+        self.clear_pending()?;
         tracing::info!("Rollback completed successfully");
         Ok(())
     }
 
-    /// Get current progress
+    /// Version of an install awaiting a good boot, if any
+    pub fn pending_verification(&self) -> Option<String> {
+        fs::read_to_string(self.backup_dir.join(PENDING_MARKER))
+            .ok()
+            .map(|version| version.trim().to_string())
+    }
+
+    /// Accept the installed update after a good boot
+    ///
+    /// The backup is kept for a manual rollback.
+    pub fn commit(&self) -> Result<(), UpdateError> {
+        if let Some(version) = self.pending_verification() {
+            self.clear_pending()?;
+            tracing::info!("Update to {} verified", version);
+        }
+        Ok(())
+    }
+
+    /// Record that the installed update needs verifying on next boot
+    fn mark_pending(&self, version: &str) -> Result<(), UpdateError> {
+        fs::create_dir_all(&self.backup_dir)?;
+        fs::write(self.backup_dir.join(PENDING_MARKER), version)?;
+        Ok(())
+    }
+
+    /// Forget a pending install, e.g. after a failed rollback
+    pub fn clear_pending(&self) -> Result<(), UpdateError> {
+        match fs::remove_file(self.backup_dir.join(PENDING_MARKER)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Get current progress, reporting a staged update left by an earlier
+    /// boot as awaiting reboot
     pub fn progress(&self) -> Option<InstallProgress> {
-        self.progress.lock().unwrap().clone()
+        let progress = self.progress.lock().unwrap().clone();
+        progress.or_else(|| {
+            let total = self.staged()?.files.len() as u32;
+            Some(InstallProgress {
+                step: STAGED_STEP.to_string(),
+                current_step: 3,
+                total_steps: 6,
+                files_processed: total,
+                total_files: total,
+            })
+        })
+    }
+
+    /// Update the processed file count
+    fn set_files_processed(&self, files: u32) {
+        let mut progress = self.progress.lock().unwrap();
+        if let Some(ref mut p) = *progress {
+            p.files_processed = files;
+        }
     }
 
     /// Set progress
@@ -430,6 +1160,50 @@ impl UpdateInstaller {
     }
 }
 
+/// Version from a package name like `rexos-1.2.0.tar.gz` or
+/// `rexos-1.2.0.delta.tar.gz`
+pub(crate) fn package_version(package_path: &Path) -> String {
+    package_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .and_then(|s| s.strip_prefix("rexos-"))
+        .and_then(|s| s.strip_suffix(".tar"))
+        .map(|s| s.strip_suffix(".delta").unwrap_or(s))
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+/// Manifest and metadata files in a package, never installed
+fn is_metadata(file: &Path) -> bool {
+    let name = file.to_string_lossy();
+    name.ends_with("manifest.json") || name.ends_with(".meta")
+}
+
+/// Make a manifest path relative, rejecting paths that escape the root
+fn relative_path(path: &str) -> Result<PathBuf, UpdateError> {
+    let mut relative = PathBuf::new();
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::RootDir | Component::CurDir => {}
+            Component::ParentDir | Component::Prefix(_) => {
+                return Err(UpdateError::InvalidManifest(format!(
+                    "Path escapes install root: {}",
+                    path
+                )));
+            }
+        }
+    }
+
+    if relative.as_os_str().is_empty() {
+        return Err(UpdateError::InvalidManifest(format!(
+            "Empty install path: {}",
+            path
+        )));
+    }
+    Ok(relative)
+}
+
 // Chrono for timestamps
 mod chrono {
     pub struct Utc;
@@ -469,4 +1243,556 @@ mod tests {
 
         assert_eq!(progress.percent(), 50);
     }
+
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(
+            relative_path("/usr/bin/rexos").unwrap(),
+            PathBuf::from("usr/bin/rexos")
+        );
+        assert!(relative_path("/usr/../../etc/shadow").is_err());
+        assert!(relative_path("/").is_err());
+    }
+
+    /// Build a small tar archive in memory
+    fn tar_bytes(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, path, *content).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    #[cfg(all(feature = "zstd", feature = "xz"))]
+    #[test]
+    fn test_extract_any_compression() {
+        use std::io::Write;
+
+        let files: &[(&str, &[u8])] = &[
+            ("usr/bin/rexos-launcher", b"launcher binary"),
+            ("etc/rexos/version", b"1.1.0\n"),
+        ];
+        let tar = tar_bytes(files);
+
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(&tar).unwrap();
+        let mut xz = xz2::write::XzEncoder::new(Vec::new(), 6);
+        xz.write_all(&tar).unwrap();
+
+        let packages = [
+            ("gzip", gzip.finish().unwrap()),
+            ("zstd", zstd::encode_all(tar.as_slice(), 0).unwrap()),
+            ("xz", xz.finish().unwrap()),
+        ];
+
+        let dir = tempfile::tempdir().unwrap();
+        for (name, data) in packages {
+            let package = dir.path().join(format!("update.tar.{}", name));
+            fs::write(&package, &data).unwrap();
+
+            let staging = dir.path().join(name).join("staging");
+            let installer = UpdateInstaller::new(staging.clone());
+            let mut extracted = installer.extract_package(&package).unwrap();
+            extracted.sort();
+            assert_eq!(
+                extracted,
+                vec![
+                    PathBuf::from("etc/rexos/version"),
+                    PathBuf::from("usr/bin/rexos-launcher")
+                ],
+                "{}",
+                name
+            );
+
+            for (path, content) in files {
+                assert_eq!(fs::read(staging.join(path)).unwrap(), *content, "{}", name);
+            }
+        }
+
+        let unknown = dir.path().join("update.tar");
+        fs::write(&unknown, &tar).unwrap();
+        assert!(matches!(
+            UpdateInstaller::new(dir.path().join("staging")).extract_package(&unknown),
+            Err(UpdateError::InstallFailed(_))
+        ));
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        use std::io::Write;
+
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(data).unwrap();
+        gzip.finish().unwrap()
+    }
+
+    #[test]
+    fn test_extract_replaces_partial_and_stale_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let staging = dir.path().join("staging");
+        let installer = UpdateInstaller::new(staging.clone());
+
+        // Left behind by an interrupted extraction and an older update
+        let partial = dir.path().join(".staging.partial-12345");
+        fs::create_dir_all(partial.join("usr/bin")).unwrap();
+        fs::write(partial.join("usr/bin/rexos-launcher"), b"half a bin").unwrap();
+        fs::create_dir_all(&staging).unwrap();
+        fs::write(staging.join("old-only"), b"stale").unwrap();
+
+        let package = dir.path().join("rexos-1.2.0.tar.gz");
+        fs::write(
+            &package,
+            gzip(&tar_bytes(&[("usr/bin/rexos-launcher", b"launcher")])),
+        )
+        .unwrap();
+
+        let files = installer.extract_package(&package).unwrap();
+        assert_eq!(files, vec![PathBuf::from("usr/bin/rexos-launcher")]);
+        assert!(!partial.exists());
+        assert!(!staging.join("old-only").exists());
+        assert_eq!(
+            fs::read(staging.join("usr/bin/rexos-launcher")).unwrap(),
+            b"launcher"
+        );
+    }
+
+    #[test]
+    fn test_failed_extract_leaves_no_partial_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let staging = dir.path().join("staging");
+        let installer = UpdateInstaller::new(staging.clone());
+
+        let data = gzip(&tar_bytes(&[
+            ("usr/bin/a", &[1u8; 4096]),
+            ("usr/bin/b", &[2u8; 4096]),
+        ]));
+        let package = dir.path().join("rexos-1.2.0.tar.gz");
+        fs::write(&package, &data[..data.len() / 2]).unwrap();
+
+        assert!(installer.extract_package(&package).is_err());
+        assert!(!staging.exists());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_stage_then_apply_after_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root");
+        fs::create_dir_all(root.join("usr/bin")).unwrap();
+        let launcher = root.join("usr/bin/rexos-launcher");
+        fs::write(&launcher, b"old launcher").unwrap();
+
+        let package = dir.path().join("rexos-1.2.0.tar.gz");
+        fs::write(
+            &package,
+            gzip(&tar_bytes(&[("usr/bin/rexos-launcher", b"new launcher")])),
+        )
+        .unwrap();
+
+        let installer = || {
+            UpdateInstaller::new(dir.path().join("staging"))
+                .with_root_dir(root.clone())
+                .with_backup_dir(dir.path().join("backup"))
+        };
+
+        let staged = installer().stage_package(&package).unwrap();
+        assert_eq!(staged.version, "1.2.0");
+        assert_eq!(fs::read(&launcher).unwrap(), b"old launcher");
+
+        // A fresh installer, as after a reboot, still sees it
+        let installer = installer();
+        assert_eq!(installer.staged(), Some(staged));
+        assert!(installer.progress().unwrap().is_awaiting_reboot());
+
+        let result = installer.apply_staged().await.unwrap().unwrap();
+        assert_eq!(result.version, "1.2.0");
+        assert_eq!(fs::read(&launcher).unwrap(), b"new launcher");
+        assert_eq!(installer.pending_verification().as_deref(), Some("1.2.0"));
+        assert!(!installer.progress().unwrap().is_awaiting_reboot());
+
+        assert!(installer.staged().is_none());
+        assert!(installer.apply_staged().await.unwrap().is_none());
+    }
+
+    /// Package replacing the launcher, with a post-install script
+    fn package_with_script(dir: &Path, script: &str) -> PathBuf {
+        let package = dir.join("rexos-1.2.0.tar.gz");
+        fs::write(
+            &package,
+            gzip(&tar_bytes(&[
+                ("usr/bin/rexos-launcher", b"new launcher"),
+                ("post-install.sh", script.as_bytes()),
+            ])),
+        )
+        .unwrap();
+        package
+    }
+
+    #[tokio::test]
+    async fn test_post_install_timeout_rolls_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root");
+        fs::create_dir_all(root.join("usr/bin")).unwrap();
+        let launcher = root.join("usr/bin/rexos-launcher");
+        fs::write(&launcher, b"old launcher").unwrap();
+
+        let package = package_with_script(dir.path(), "sleep 30\n");
+        let installer = UpdateInstaller::new(dir.path().join("staging"))
+            .with_root_dir(root.clone())
+            .with_backup_dir(dir.path().join("backup"))
+            .with_script_timeout(Duration::from_millis(200));
+
+        let started = Instant::now();
+        assert!(matches!(
+            installer.install(&package).await,
+            Err(UpdateError::InstallFailed(_))
+        ));
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert_eq!(fs::read(&launcher).unwrap(), b"old launcher");
+        assert!(!root.join("post-install.sh").exists());
+        assert_eq!(installer.pending_verification(), None);
+    }
+
+    #[tokio::test]
+    async fn test_post_install_env_and_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root");
+        fs::create_dir_all(&root).unwrap();
+
+        let package = package_with_script(dir.path(), "env\necho broken >&2\nexit 3\n");
+        let installer = UpdateInstaller::new(dir.path().join("staging"))
+            .with_root_dir(root.clone())
+            .with_backup_dir(dir.path().join("backup"));
+        assert!(installer.install(&package).await.is_err());
+
+        // The log is kept in staging, and the environment isn't inherited
+        let log = fs::read_to_string(dir.path().join("staging").join(POST_INSTALL_LOG)).unwrap();
+        assert!(log.contains("broken"));
+        assert!(log.contains(&format!("REXOS_ROOT={}", root.display())));
+        assert!(log.contains(&format!("PATH={}", SCRIPT_PATH)));
+        assert!(!log.contains("CARGO"));
+    }
+
+    #[test]
+    fn test_commit_clears_pending() {
+        let dir = tempfile::tempdir().unwrap();
+        let installer = UpdateInstaller::new(dir.path().join("staging"))
+            .with_backup_dir(dir.path().join("backup"));
+
+        assert_eq!(installer.pending_verification(), None);
+        installer.commit().unwrap();
+
+        installer.mark_pending("1.2.0").unwrap();
+        assert_eq!(installer.pending_verification().as_deref(), Some("1.2.0"));
+        installer.commit().unwrap();
+        assert_eq!(installer.pending_verification(), None);
+    }
+
+    #[test]
+    fn test_dry_run_leaves_root_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root");
+        fs::create_dir_all(root.join("usr/bin")).unwrap();
+        fs::create_dir_all(root.join("etc/rexos")).unwrap();
+        fs::write(root.join("usr/bin/rexos-launcher"), b"old launcher").unwrap();
+        fs::write(root.join("etc/rexos/version"), b"1.1.0\n").unwrap();
+        fs::write(root.join("usr/bin/obsolete"), b"gone soon").unwrap();
+
+        let manifest = serde_json::json!({ "remove": ["usr/bin/obsolete", "usr/bin/missing"] });
+        let tar = tar_bytes(&[
+            ("usr/bin/rexos-launcher", b"new launcher"),
+            ("etc/rexos/version", b"1.1.0\n"),
+            ("usr/lib/rexos/new.so", b"library"),
+            ("manifest.json", manifest.to_string().as_bytes()),
+            (".needs-reboot", b""),
+        ]);
+        let package = dir.path().join("rexos-1.2.0.tar.gz");
+        fs::write(&package, gzip(&tar)).unwrap();
+
+        // An update staged earlier
+        let staging = dir.path().join("staging");
+        fs::create_dir_all(&staging).unwrap();
+        fs::write(staging.join(STAGED_METADATA), b"{}").unwrap();
+
+        let installer = UpdateInstaller::new(staging.clone()).with_root_dir(root.clone());
+        let plan = installer.dry_run(&package).unwrap();
+
+        assert_eq!(plan.version, "1.2.0");
+        assert_eq!(plan.updated, vec![PathBuf::from("usr/bin/rexos-launcher")]);
+        assert_eq!(plan.unchanged, 1);
+        assert!(plan.added.contains(&PathBuf::from("usr/lib/rexos/new.so")));
+        assert_eq!(plan.removed, vec![PathBuf::from("usr/bin/obsolete")]);
+        assert!(plan.needs_reboot);
+        assert!(!plan.has_post_install);
+        assert_eq!(plan.summary().files_updated, 1);
+        assert_eq!(plan.bytes_written, 12 + 7);
+
+        // Nothing installed or removed, staging untouched, no scratch left
+        assert_eq!(
+            fs::read(root.join("usr/bin/rexos-launcher")).unwrap(),
+            b"old launcher"
+        );
+        assert!(root.join("usr/bin/obsolete").exists());
+        assert!(!root.join("usr/lib/rexos/new.so").exists());
+        assert_eq!(fs::read(staging.join(STAGED_METADATA)).unwrap(), b"{}");
+        assert_eq!(fs::read_dir(&staging).unwrap().count(), 1);
+        assert!(!dir.path().join("staging.dry-run").exists());
+    }
+
+    fn entry(path: &str, content: &[u8]) -> FileEntry {
+        FileEntry {
+            path: path.to_string(),
+            size: content.len() as u64,
+            sha256: HashVerifier::sha256_data(content),
+            mode: Some("0755".to_string()),
+            owner: None,
+            file_type: FileType::Regular,
+            action: FileAction::Update,
+            patch: None,
+            base_sha256: None,
+        }
+    }
+
+    /// Serve files over HTTP, recording requested paths
+    async fn serve(
+        files: std::collections::HashMap<String, Vec<u8>>,
+    ) -> (String, Arc<Mutex<Vec<String>>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = requests.clone();
+
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let n = stream.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                let path = request.split_whitespace().nth(1).unwrap_or("").to_string();
+                log.lock().unwrap().push(path.clone());
+
+                let body = files.get(&path).cloned().unwrap_or_default();
+                let header = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                    body.len()
+                );
+                let _ = stream.write_all(header.as_bytes()).await;
+                let _ = stream.write_all(&body).await;
+            }
+        });
+
+        (format!("http://{}/files", addr), requests)
+    }
+
+    #[tokio::test]
+    async fn test_sync_install_and_rollback() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root");
+        fs::create_dir_all(root.join("usr/bin")).unwrap();
+        fs::write(root.join("usr/bin/same"), b"same").unwrap();
+        fs::write(root.join("usr/bin/changed"), b"old").unwrap();
+        fs::write(root.join("usr/bin/obsolete"), b"gone").unwrap();
+
+        let mut served = std::collections::HashMap::new();
+        served.insert("/files/usr/bin/changed".to_string(), b"new".to_vec());
+        served.insert("/files/usr/bin/added".to_string(), b"added".to_vec());
+        let (files_url, requests) = serve(served).await;
+
+        let mut manifest = UpdateManifest::new("1.1.0");
+        manifest.files_url = Some(files_url);
+        manifest.add_file(entry("/usr/bin/same", b"same"));
+        manifest.add_file(entry("/usr/bin/changed", b"new"));
+        manifest.add_file(entry("/usr/bin/added", b"added"));
+        manifest.remove_file("/usr/bin/obsolete");
+
+        let installer = UpdateInstaller::new(dir.path().join("staging"))
+            .with_root_dir(root.clone())
+            .with_backup_dir(dir.path().join("backup"));
+
+        let plan = installer.plan_sync(&manifest).unwrap();
+        assert_eq!(plan.unchanged, 1);
+        assert_eq!(plan.changed.len(), 1);
+        assert_eq!(plan.added.len(), 1);
+        assert_eq!(plan.removed, vec![PathBuf::from("usr/bin/obsolete")]);
+
+        // No proxy, whatever the environment says
+        let client = crate::build_client(
+            std::time::Duration::from_secs(5),
+            &crate::ProxySettings::default(),
+        )
+        .unwrap();
+        let downloader = UpdateDownloader::new(dir.path().join("downloads"), 1).with_client(client);
+        let result = installer
+            .sync_install(&manifest, &downloader)
+            .await
+            .unwrap();
+        assert_eq!(result.version, "1.1.0");
+        assert_eq!(result.files_updated, 1);
+        assert_eq!(result.files_added, 1);
+        assert_eq!(result.files_removed, 1);
+
+        // Only the differing files were fetched
+        let mut fetched = requests.lock().unwrap().clone();
+        fetched.sort();
+        assert_eq!(fetched, ["/files/usr/bin/added", "/files/usr/bin/changed"]);
+
+        assert_eq!(fs::read(root.join("usr/bin/changed")).unwrap(), b"new");
+        assert_eq!(fs::read(root.join("usr/bin/added")).unwrap(), b"added");
+        assert!(!root.join("usr/bin/obsolete").exists());
+
+        // Nothing left to do afterwards
+        assert!(installer.plan_sync(&manifest).unwrap().is_empty());
+
+        // Awaiting a good boot until committed or rolled back
+        assert_eq!(installer.pending_verification().as_deref(), Some("1.1.0"));
+
+        installer.rollback().await.unwrap();
+        assert_eq!(installer.pending_verification(), None);
+        assert_eq!(fs::read(root.join("usr/bin/changed")).unwrap(), b"old");
+        assert_eq!(fs::read(root.join("usr/bin/obsolete")).unwrap(), b"gone");
+        assert!(!root.join("usr/bin/added").exists());
+        assert_eq!(fs::read(root.join("usr/bin/same")).unwrap(), b"same");
+    }
+
+    #[tokio::test]
+    async fn test_sync_rollback_restores_removed_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root");
+        fs::create_dir_all(root.join("usr/share/old-theme/icons")).unwrap();
+        fs::write(root.join("usr/share/old-theme/theme.cfg"), b"theme").unwrap();
+        fs::write(root.join("usr/share/old-theme/icons/nes.png"), b"png").unwrap();
+        fs::create_dir_all(root.join("usr/bin")).unwrap();
+        fs::write(root.join("usr/bin/obsolete"), b"gone").unwrap();
+
+        let mut manifest = UpdateManifest::new("1.1.0");
+        manifest.remove_file("/usr/share/old-theme");
+        manifest.remove_file("/usr/bin/obsolete");
+
+        let installer = UpdateInstaller::new(dir.path().join("staging"))
+            .with_root_dir(root.clone())
+            .with_backup_dir(dir.path().join("backup"));
+        let downloader = UpdateDownloader::new(dir.path().join("downloads"), 1);
+
+        let result = installer
+            .sync_install(&manifest, &downloader)
+            .await
+            .unwrap();
+        assert_eq!(result.files_removed, 2);
+        assert!(!root.join("usr/share/old-theme").exists());
+        assert!(!root.join("usr/bin/obsolete").exists());
+
+        installer.rollback().await.unwrap();
+        assert_eq!(
+            fs::read(root.join("usr/share/old-theme/theme.cfg")).unwrap(),
+            b"theme"
+        );
+        assert_eq!(
+            fs::read(root.join("usr/share/old-theme/icons/nes.png")).unwrap(),
+            b"png"
+        );
+        assert_eq!(fs::read(root.join("usr/bin/obsolete")).unwrap(), b"gone");
+    }
+
+    /// Delta package patching `usr/bin/rexos-launcher` from `old` to `new`
+    fn delta_package(dir: &Path, old: &[u8], new: &[u8], new_hash: &str) -> PathBuf {
+        let mut patch = Vec::new();
+        bsdiff::diff(old, new, &mut patch).unwrap();
+
+        let mut manifest = UpdateManifest::new("1.2.0");
+        manifest.package_type = crate::PackageType::Delta;
+        manifest.delta_base = Some("1.1.0".to_string());
+        manifest.files.push(FileEntry {
+            sha256: new_hash.to_string(),
+            patch: Some("patches/rexos-launcher.bsdiff".to_string()),
+            base_sha256: Some(HashVerifier::sha256_data(old)),
+            mode: None,
+            ..entry("/usr/bin/rexos-launcher", new)
+        });
+        manifest
+            .files
+            .push(entry("/usr/share/rexos/new.txt", b"whole file"));
+        let manifest = serde_json::to_vec(&manifest).unwrap();
+
+        let package = dir.join("rexos-1.2.0.delta.tar.gz");
+        fs::write(
+            &package,
+            gzip(&tar_bytes(&[
+                ("manifest.json", manifest.as_slice()),
+                ("patches/rexos-launcher.bsdiff", patch.as_slice()),
+                ("usr/share/rexos/new.txt", b"whole file"),
+            ])),
+        )
+        .unwrap();
+        package
+    }
+
+    #[tokio::test]
+    async fn test_install_delta_package() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root");
+        fs::create_dir_all(root.join("usr/bin")).unwrap();
+        let launcher = root.join("usr/bin/rexos-launcher");
+        fs::write(&launcher, b"launcher build 1.1.0 with some shared bytes").unwrap();
+        fs::set_permissions(&launcher, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let new = b"launcher build 1.2.0 with some shared bytes and more";
+        let package = delta_package(
+            dir.path(),
+            &fs::read(&launcher).unwrap(),
+            new,
+            &HashVerifier::sha256_data(new),
+        );
+
+        let installer = UpdateInstaller::new(dir.path().join("staging"))
+            .with_root_dir(root.clone())
+            .with_backup_dir(dir.path().join("backup"));
+        let result = installer.install(&package).await.unwrap();
+
+        assert_eq!(result.version, "1.2.0");
+        assert_eq!(result.files_updated, 1);
+        assert_eq!(result.files_added, 1);
+        assert_eq!(fs::read(&launcher).unwrap(), new);
+        assert_eq!(
+            fs::metadata(&launcher).unwrap().permissions().mode() & 0o777,
+            0o755
+        );
+        assert!(!root.join("patches").exists());
+    }
+
+    #[tokio::test]
+    async fn test_bad_delta_leaves_system_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root");
+        fs::create_dir_all(root.join("usr/bin")).unwrap();
+        let launcher = root.join("usr/bin/rexos-launcher");
+        let old = b"launcher build 1.1.0".to_vec();
+        fs::write(&launcher, &old).unwrap();
+
+        // The manifest expects a different result than the patch produces
+        let package = delta_package(
+            dir.path(),
+            &old,
+            b"launcher build 1.2.0",
+            &HashVerifier::sha256_data(b"something else"),
+        );
+
+        let backup = dir.path().join("backup");
+        let installer = UpdateInstaller::new(dir.path().join("staging"))
+            .with_root_dir(root.clone())
+            .with_backup_dir(backup.clone());
+        assert!(matches!(
+            installer.install(&package).await,
+            Err(UpdateError::VerificationFailed(_))
+        ));
+
+        assert_eq!(fs::read(&launcher).unwrap(), old);
+        assert!(!root.join("usr/share").exists());
+        assert!(!backup.exists());
+    }
 }
@@ -1,16 +1,38 @@
 //! Update manifest format
-
+//!
+//! Unknown fields are ignored so servers can add fields without breaking
+//! older clients. Incompatible changes bump `schema_version`, and manifests
+//! newer than [`SCHEMA_VERSION`] are rejected before being parsed.
+//!
+//! Manifests and update check responses carry a detached Ed25519
+//! signature over the rest of the document (see [`signing_payload`]), so
+//! the version, URLs and hashes they list can't be swapped in transit.
+
+use crate::{SignatureVerifier, UpdateError};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Newest manifest schema this client understands
+pub const SCHEMA_VERSION: u32 = 2;
 
 /// Update manifest containing all update metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpdateManifest {
-    /// Manifest format version
-    pub manifest_version: u32,
+    /// Manifest schema version (schema 1 called this `manifest_version`)
+    #[serde(alias = "manifest_version", default = "default_schema_version")]
+    pub schema_version: u32,
 
     /// RexOS version being installed
     pub version: String,
 
+    /// Whether the package holds whole files or patches
+    #[serde(rename = "type", default)]
+    pub package_type: PackageType,
+
+    /// Version a delta package's patches apply to
+    #[serde(default)]
+    pub delta_base: Option<String>,
+
     /// Build timestamp
     pub build_date: String,
 
@@ -40,6 +62,10 @@ pub struct UpdateManifest {
     #[serde(default)]
     pub files: Vec<FileEntry>,
 
+    /// Base URL for fetching individual files (sync install)
+    #[serde(default)]
+    pub files_url: Option<String>,
+
     /// Files to remove
     #[serde(default)]
     pub remove: Vec<String>,
@@ -77,8 +103,13 @@ pub struct UpdateManifest {
     /// SHA256 of the compressed package
     pub sha256: String,
 
-    /// Ed25519 signature of the manifest
+    /// Ed25519 signature of the manifest, detached: it covers the
+    /// [`signing_payload`] of every other field
     pub signature: String,
+
+    /// ID of the key that made `signature` (see [`crate::key_id`])
+    #[serde(default)]
+    pub key_id: Option<String>,
 }
 
 /// Release notes
@@ -138,6 +169,26 @@ pub struct FileEntry {
     /// Action (add, update, config)
     #[serde(default)]
     pub action: FileAction,
+
+    /// bsdiff patch in a delta package producing this file from the
+    /// installed one
+    #[serde(default)]
+    pub patch: Option<String>,
+
+    /// SHA256 of the installed file the patch applies to
+    #[serde(default)]
+    pub base_sha256: Option<String>,
+}
+
+/// Package types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageType {
+    /// Whole files
+    #[default]
+    Full,
+    /// bsdiff patches against the installed files, plus whole new files
+    Delta,
 }
 
 /// File types
@@ -192,6 +243,10 @@ fn default_true() -> bool {
     true
 }
 
+fn default_schema_version() -> u32 {
+    1
+}
+
 fn default_timeout() -> u32 {
     60
 }
@@ -214,8 +269,10 @@ impl UpdateManifest {
     /// Create a new empty manifest
     pub fn new(version: &str) -> Self {
         Self {
-            manifest_version: 1,
+            schema_version: SCHEMA_VERSION,
             version: version.to_string(),
+            package_type: PackageType::Full,
+            delta_base: None,
             build_date: String::new(),
             build_number: None,
             commit: None,
@@ -225,6 +282,7 @@ impl UpdateManifest {
             target_devices: Vec::new(),
             release_notes: ReleaseNotes::default(),
             files: Vec::new(),
+            files_url: None,
             remove: Vec::new(),
             pre_install: Vec::new(),
             post_install: Vec::new(),
@@ -236,9 +294,51 @@ impl UpdateManifest {
             uncompressed_size: 0,
             sha256: String::new(),
             signature: String::new(),
+            key_id: None,
         }
     }
 
+    /// Parse a manifest, checking the schema version first
+    pub fn from_json(json: &str) -> Result<Self, UpdateError> {
+        let value: serde_json::Value =
+            serde_json::from_str(json).map_err(|e| UpdateError::InvalidManifest(e.to_string()))?;
+
+        Self::check_schema(&value)?;
+
+        serde_json::from_value(value).map_err(|e| UpdateError::InvalidManifest(e.to_string()))
+    }
+
+    /// Parse a manifest, checking its signature before anything else is
+    /// trusted
+    pub fn from_signed_json(json: &str, verifier: &SignatureVerifier) -> Result<Self, UpdateError> {
+        let value: Value =
+            serde_json::from_str(json).map_err(|e| UpdateError::InvalidManifest(e.to_string()))?;
+
+        Self::check_schema(&value)?;
+        verify_detached(&value, "signature", verifier)?;
+
+        serde_json::from_value(value).map_err(|e| UpdateError::InvalidManifest(e.to_string()))
+    }
+
+    /// Reject manifests newer than this client supports
+    pub fn check_schema(value: &serde_json::Value) -> Result<(), UpdateError> {
+        let schema = value
+            .get("schema_version")
+            .or_else(|| value.get("manifest_version"))
+            .and_then(|v| v.as_u64())
+            .unwrap_or(1);
+
+        if schema > SCHEMA_VERSION as u64 {
+            return Err(UpdateError::InvalidManifest(format!(
+                "Manifest schema version {} is newer than supported version {}; \
+                 update the updater first",
+                schema, SCHEMA_VERSION
+            )));
+        }
+
+        Ok(())
+    }
+
     /// Add a file to the manifest
     pub fn add_file(&mut self, entry: FileEntry) {
         self.uncompressed_size += entry.size;
@@ -271,11 +371,27 @@ impl UpdateManifest {
         Ok(())
     }
 
+    /// Check if this is a delta package
+    pub fn is_delta(&self) -> bool {
+        self.package_type == PackageType::Delta
+    }
+
     /// Get total file count
     pub fn file_count(&self) -> usize {
         self.files.len()
     }
 
+    /// URL to fetch a single file from, if the manifest has `files_url`
+    pub fn file_url(&self, entry: &FileEntry) -> Option<String> {
+        self.files_url.as_ref().map(|base| {
+            format!(
+                "{}/{}",
+                base.trim_end_matches('/'),
+                entry.path.trim_start_matches('/')
+            )
+        })
+    }
+
     /// Check if device is supported
     pub fn supports_device(&self, device_id: &str) -> bool {
         self.target_devices.is_empty()
@@ -379,6 +495,74 @@ impl ReleaseNotes {
     }
 }
 
+/// Bytes covered by a detached signature stored in `field`
+///
+/// This is the document without `field`, as compact JSON with object keys
+/// sorted at every level, so servers may serialize fields in any order.
+/// Release tooling signs this with `sign_data`.
+pub fn signing_payload(value: &Value, field: &str) -> Vec<u8> {
+    let mut out = String::new();
+    match value {
+        Value::Object(map) => {
+            let mut unsigned = map.clone();
+            unsigned.remove(field);
+            write_canonical(&Value::Object(unsigned), &mut out);
+        }
+        other => write_canonical(other, &mut out),
+    }
+    out.into_bytes()
+}
+
+/// Check the detached signature in `field` of a JSON document, made by
+/// the key its `key_id` names
+pub(crate) fn verify_detached(
+    value: &Value,
+    field: &str,
+    verifier: &SignatureVerifier,
+) -> Result<(), UpdateError> {
+    let signature = value
+        .get(field)
+        .and_then(Value::as_str)
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| UpdateError::VerificationFailed(format!("Missing {}", field)))?;
+
+    let key_id = value.get("key_id").and_then(Value::as_str);
+    verifier
+        .verify_data_from(&signing_payload(value, field), signature, key_id)
+        .map_err(|e| UpdateError::VerificationFailed(format!("Bad {}: {}", field, e)))
+}
+
+/// Compact JSON with sorted keys, whatever map order serde_json was built with
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            out.push('{');
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(&map[key], out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        other => out.push_str(&other.to_string()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -387,7 +571,128 @@ mod tests {
     fn test_manifest_new() {
         let manifest = UpdateManifest::new("1.0.0");
         assert_eq!(manifest.version, "1.0.0");
-        assert_eq!(manifest.manifest_version, 1);
+        assert_eq!(manifest.schema_version, SCHEMA_VERSION);
+    }
+
+    const MANIFEST_BODY: &str = r#"
+        "version": "1.2.0",
+        "build_date": "2025-01-01",
+        "architecture": "aarch64",
+        "release_notes": {"title": "1.2", "summary": "", "description": ""},
+        "compressed_size": 10,
+        "uncompressed_size": 20,
+        "sha256": "abc",
+        "signature": "def"
+    "#;
+
+    #[test]
+    fn test_signing_payload_ignores_key_order() {
+        let a: Value = serde_json::from_str(
+            r#"{"b": 1, "a": {"y": [1, {"d": 2, "c": 3}], "x": "s"}, "signature": "ff"}"#,
+        )
+        .unwrap();
+        let b: Value =
+            serde_json::from_str(r#"{"a": {"x": "s", "y": [1, {"c": 3, "d": 2}]}, "b": 1}"#)
+                .unwrap();
+
+        assert_eq!(
+            signing_payload(&a, "signature"),
+            br#"{"a":{"x":"s","y":[1,{"c":3,"d":2}]},"b":1}"#
+        );
+        assert_eq!(
+            signing_payload(&a, "signature"),
+            signing_payload(&b, "signature")
+        );
+    }
+
+    #[test]
+    fn test_signed_manifest() {
+        use crate::verification::{generate_keypair, sign_data};
+
+        let (private, public) = generate_keypair();
+        let verifier = SignatureVerifier::from_hex(&public).unwrap();
+
+        let mut value: Value = serde_json::from_str(&format!("{{{}}}", MANIFEST_BODY)).unwrap();
+        let signature = sign_data(&signing_payload(&value, "signature"), &private).unwrap();
+        value["signature"] = Value::String(signature);
+        let json = value.to_string();
+
+        let manifest = UpdateManifest::from_signed_json(&json, &verifier).unwrap();
+        assert_eq!(manifest.version, "1.2.0");
+
+        // Pointing at an older release breaks the signature
+        let tampered = json.replace("1.2.0", "1.0.0");
+        assert!(matches!(
+            UpdateManifest::from_signed_json(&tampered, &verifier),
+            Err(UpdateError::VerificationFailed(_))
+        ));
+
+        value["signature"] = Value::String(String::new());
+        assert!(UpdateManifest::from_signed_json(&value.to_string(), &verifier).is_err());
+    }
+
+    #[test]
+    fn test_parse_older_schema() {
+        let json = format!(r#"{{"manifest_version": 1, {}}}"#, MANIFEST_BODY);
+        let manifest = UpdateManifest::from_json(&json).unwrap();
+        assert_eq!(manifest.schema_version, 1);
+        assert_eq!(manifest.version, "1.2.0");
+
+        // No version field at all is schema 1 too
+        let json = format!("{{{}}}", MANIFEST_BODY);
+        assert_eq!(UpdateManifest::from_json(&json).unwrap().schema_version, 1);
+    }
+
+    #[test]
+    fn test_parse_current_schema_with_unknown_fields() {
+        let json = format!(
+            r#"{{"schema_version": {}, "delta_base": "1.1.0", "mirrors": ["a"], {}}}"#,
+            SCHEMA_VERSION, MANIFEST_BODY
+        );
+        let manifest = UpdateManifest::from_json(&json).unwrap();
+        assert_eq!(manifest.schema_version, SCHEMA_VERSION);
+        assert_eq!(manifest.delta_base.as_deref(), Some("1.1.0"));
+        assert!(!manifest.is_delta());
+
+        // Serializes back with the new field name
+        let out = serde_json::to_string(&manifest).unwrap();
+        assert!(out.contains("\"schema_version\""));
+    }
+
+    #[test]
+    fn test_parse_delta_manifest() {
+        let json = format!(
+            r#"{{"type": "delta", "delta_base": "1.1.0", "files": [{{
+                "path": "/usr/bin/rexos-launcher",
+                "size": 4,
+                "sha256": "new",
+                "patch": "patches/rexos-launcher.bsdiff",
+                "base_sha256": "old"
+            }}], {}}}"#,
+            MANIFEST_BODY
+        );
+        let manifest = UpdateManifest::from_json(&json).unwrap();
+        assert!(manifest.is_delta());
+        assert_eq!(
+            manifest.files[0].patch.as_deref(),
+            Some("patches/rexos-launcher.bsdiff")
+        );
+        assert_eq!(manifest.files[0].base_sha256.as_deref(), Some("old"));
+    }
+
+    #[test]
+    fn test_reject_newer_schema() {
+        // Fields a newer schema might change shape are never reached
+        let json = format!(
+            r#"{{"schema_version": {}, "version": {{"major": 2}}}}"#,
+            SCHEMA_VERSION + 1
+        );
+        match UpdateManifest::from_json(&json) {
+            Err(UpdateError::InvalidManifest(msg)) => {
+                assert!(msg.contains("update the updater first"))
+            }
+            other => panic!("expected InvalidManifest, got {:?}", other),
+        }
     }
 
     #[test]
@@ -402,12 +707,37 @@ mod tests {
             owner: None,
             file_type: FileType::Regular,
             action: FileAction::Add,
+            patch: None,
+            base_sha256: None,
         });
 
         assert_eq!(manifest.file_count(), 1);
         assert_eq!(manifest.uncompressed_size, 1024);
     }
 
+    #[test]
+    fn test_file_url() {
+        let mut manifest = UpdateManifest::new("1.0.0");
+        let entry = FileEntry {
+            path: "/usr/bin/rexos-launcher".to_string(),
+            size: 0,
+            sha256: String::new(),
+            mode: None,
+            owner: None,
+            file_type: FileType::Regular,
+            action: FileAction::Update,
+            patch: None,
+            base_sha256: None,
+        };
+        assert_eq!(manifest.file_url(&entry), None);
+
+        manifest.files_url = Some("https://updates.rexos.io/files/1.0.0/".to_string());
+        assert_eq!(
+            manifest.file_url(&entry).as_deref(),
+            Some("https://updates.rexos.io/files/1.0.0/usr/bin/rexos-launcher")
+        );
+    }
+
     #[test]
     fn test_device_support() {
         let mut manifest = UpdateManifest::new("1.0.0");
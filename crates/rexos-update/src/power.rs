@@ -0,0 +1,82 @@
+//! Battery gating for installs
+//!
+//! Installing on a nearly flat battery can leave a half-written system, so
+//! the update manager asks a [`PowerSource`] for the battery state right
+//! before installing. The trait keeps the manager testable without sysfs.
+
+use crate::UpdateError;
+use rexos_hal::PowerManager;
+
+/// Battery charge and charger state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatteryState {
+    /// Charge percentage
+    pub percentage: u8,
+    /// Whether a charger is connected
+    pub charging: bool,
+}
+
+/// Source of battery state for the update manager
+pub trait PowerSource: Send + Sync {
+    /// Current battery state, `None` if there is no battery
+    fn battery(&self) -> Option<BatteryState>;
+}
+
+impl PowerSource for PowerManager {
+    fn battery(&self) -> Option<BatteryState> {
+        self.get_battery_info().ok().map(|info| BatteryState {
+            percentage: info.percentage,
+            charging: info.is_charging,
+        })
+    }
+}
+
+/// Check that the battery can carry an install
+///
+/// Passes when charging, at or above `min_percentage`, or when the battery
+/// state is unknown.
+pub fn check_battery(power: &dyn PowerSource, min_percentage: u8) -> Result<(), UpdateError> {
+    match power.battery() {
+        Some(state) if !state.charging && state.percentage < min_percentage => {
+            Err(UpdateError::BatteryTooLow {
+                percentage: state.percentage,
+                required: min_percentage,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedPower(Option<BatteryState>);
+
+    impl PowerSource for FixedPower {
+        fn battery(&self) -> Option<BatteryState> {
+            self.0
+        }
+    }
+
+    fn battery(percentage: u8, charging: bool) -> FixedPower {
+        FixedPower(Some(BatteryState {
+            percentage,
+            charging,
+        }))
+    }
+
+    #[test]
+    fn test_check_battery() {
+        assert!(matches!(
+            check_battery(&battery(10, false), 30),
+            Err(UpdateError::BatteryTooLow {
+                percentage: 10,
+                required: 30
+            })
+        ));
+        assert!(check_battery(&battery(10, true), 30).is_ok());
+        assert!(check_battery(&battery(30, false), 30).is_ok());
+        assert!(check_battery(&FixedPower(None), 30).is_ok());
+    }
+}
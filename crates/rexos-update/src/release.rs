@@ -0,0 +1,158 @@
+//! Installed release information
+//!
+//! Parses `/etc/rexos-release`, which follows the os-release format:
+//! `KEY=value` lines, optionally single or double quoted, with `#`
+//! comments. Used by the updater for the current version and channel, and
+//! meant for anything else that shows what's installed.
+
+use crate::{UpdateChannel, UpdateError};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Release file written at build time
+pub const RELEASE_FILE: &str = "/etc/rexos-release";
+
+/// Contents of a release file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReleaseInfo {
+    /// `VERSION`, always present
+    pub version: String,
+    /// `BUILD_ID`, e.g. a date or commit
+    pub build_id: Option<String>,
+    /// `CHANNEL` the image was built for, if recognised
+    pub channel: Option<UpdateChannel>,
+    /// Every field, including the ones above
+    pub fields: HashMap<String, String>,
+}
+
+impl ReleaseInfo {
+    /// Parse release file contents
+    pub fn parse(contents: &str) -> Result<Self, UpdateError> {
+        let fields = parse_fields(contents);
+
+        let version = fields
+            .get("VERSION")
+            .filter(|v| !v.is_empty())
+            .cloned()
+            .ok_or_else(|| UpdateError::InvalidRelease("missing VERSION".to_string()))?;
+        let channel = fields.get("CHANNEL").and_then(|c| {
+            let channel = UpdateChannel::parse(c);
+            if channel.is_none() {
+                tracing::warn!("Unknown release channel: {}", c);
+            }
+            channel
+        });
+
+        Ok(Self {
+            version,
+            build_id: fields.get("BUILD_ID").cloned(),
+            channel,
+            fields,
+        })
+    }
+
+    /// Read and parse a release file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, UpdateError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Look up any field
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields.get(key).map(String::as_str)
+    }
+}
+
+/// Parse `KEY=value` lines, skipping comments and malformed lines
+fn parse_fields(contents: &str) -> HashMap<String, String> {
+    contents
+        .lines()
+        .map(|line| line.trim_end_matches('\r').trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            let key = key.trim();
+            if key.is_empty() || key.contains(char::is_whitespace) {
+                return None;
+            }
+            Some((key.to_string(), unquote(value.trim())))
+        })
+        .collect()
+}
+
+/// Strip quotes, handling shell-style escapes inside double quotes
+fn unquote(value: &str) -> String {
+    if value.len() >= 2 && value.starts_with('\'') && value.ends_with('\'') {
+        return value[1..value.len() - 1].to_string();
+    }
+    if value.len() < 2 || !value.starts_with('"') || !value.ends_with('"') {
+        return value.to_string();
+    }
+
+    let mut out = String::new();
+    let mut chars = value[1..value.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(next @ ('"' | '\\' | '$' | '`')) => out.push(next),
+                Some(next) => {
+                    out.push('\\');
+                    out.push(next);
+                }
+                None => out.push('\\'),
+            },
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RELEASE: &str = "# RexOS release information\r\n\
+        NAME=\"RexOS\"\r\n\
+        PRETTY_NAME=\"RexOS 1.4.0 (\\\"Raptor\\\")\"\r\n\
+        VERSION=\"1.4.0-beta.2\"\r\n\
+        VERSION_ID=1.4.0\r\n\
+        BUILD_ID='20260301-abc1234'\r\n\
+        CHANNEL=beta\r\n\
+        \r\n\
+        not a field\r\n";
+
+    #[test]
+    fn test_parse_release_file() {
+        let info = ReleaseInfo::parse(RELEASE).unwrap();
+        assert_eq!(info.version, "1.4.0-beta.2");
+        assert_eq!(info.build_id.as_deref(), Some("20260301-abc1234"));
+        assert_eq!(info.channel, Some(UpdateChannel::Beta));
+        assert_eq!(info.get("NAME"), Some("RexOS"));
+        assert_eq!(info.get("PRETTY_NAME"), Some("RexOS 1.4.0 (\"Raptor\")"));
+        assert_eq!(info.get("VERSION_ID"), Some("1.4.0"));
+        assert_eq!(info.fields.len(), 6);
+    }
+
+    #[test]
+    fn test_missing_version() {
+        let err = ReleaseInfo::parse("NAME=RexOS\nVERSION=\"\"\n").unwrap_err();
+        assert!(matches!(err, UpdateError::InvalidRelease(_)));
+    }
+
+    #[test]
+    fn test_unknown_channel_and_comments() {
+        let info =
+            ReleaseInfo::parse("VERSION=1.0.0\n# CHANNEL=nightly\nCHANNEL=custom\n").unwrap();
+        assert_eq!(info.version, "1.0.0");
+        assert_eq!(info.channel, None);
+        assert_eq!(info.build_id, None);
+    }
+
+    #[test]
+    fn test_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rexos-release");
+        std::fs::write(&path, RELEASE).unwrap();
+        assert_eq!(ReleaseInfo::load(&path).unwrap().version, "1.4.0-beta.2");
+        assert!(ReleaseInfo::load(dir.path().join("missing")).is_err());
+    }
+}
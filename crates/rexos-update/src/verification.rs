@@ -24,86 +24,198 @@ pub enum VerificationError {
 }
 
 /// Verifies update signatures using Ed25519
+///
+/// Several keys can be trusted at once so the signing key can be rotated:
+/// devices ship the new key alongside the old one before releases switch
+/// to it. Keys are identified by [`key_id`].
 pub struct SignatureVerifier {
-    public_key: ed25519_dalek::VerifyingKey,
+    keys: Vec<(String, ed25519_dalek::VerifyingKey)>,
 }
 
 impl SignatureVerifier {
     /// Create verifier from hex-encoded public key
     pub fn from_hex(hex_key: &str) -> Result<Self, VerificationError> {
-        let bytes =
-            hex::decode(hex_key).map_err(|e| VerificationError::InvalidPublicKey(e.to_string()))?;
-
-        if bytes.len() != 32 {
-            return Err(VerificationError::InvalidPublicKey(format!(
-                "Key must be 32 bytes, got {}",
-                bytes.len()
-            )));
+        Self::from_hex_keys([hex_key])
+    }
+
+    /// Create verifier trusting any of several hex-encoded public keys
+    pub fn from_hex_keys<'a>(
+        hex_keys: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Self, VerificationError> {
+        let keys = hex_keys
+            .into_iter()
+            .map(|hex_key| {
+                let public_key = parse_public_key(hex_key)?;
+                Ok((fingerprint(&public_key), public_key))
+            })
+            .collect::<Result<Vec<_>, VerificationError>>()?;
+
+        if keys.is_empty() {
+            return Err(VerificationError::InvalidPublicKey(
+                "No public keys configured".to_string(),
+            ));
         }
 
-        let mut key_bytes = [0u8; 32];
-        key_bytes.copy_from_slice(&bytes);
+        Ok(Self { keys })
+    }
 
-        let public_key = ed25519_dalek::VerifyingKey::from_bytes(&key_bytes)
-            .map_err(|e| VerificationError::InvalidPublicKey(e.to_string()))?;
+    /// IDs of the trusted keys
+    pub fn key_ids(&self) -> Vec<&str> {
+        self.keys.iter().map(|(id, _)| id.as_str()).collect()
+    }
 
-        Ok(Self { public_key })
+    /// Trusted keys to try, the one `key_id` names first
+    ///
+    /// The declared ID is only a hint; it's covered by the signature or
+    /// sits next to it, so every key is still tried.
+    fn candidates(&self, key_id: Option<&str>) -> Vec<&(String, ed25519_dalek::VerifyingKey)> {
+        let mut candidates: Vec<_> = self.keys.iter().collect();
+        if let Some(key_id) = key_id {
+            candidates.sort_by_key(|(id, _)| id != key_id);
+        }
+        candidates
     }
 
     /// Verify a file's signature
     pub fn verify_file(&self, path: &Path, signature_hex: &str) -> Result<(), VerificationError> {
-        use ed25519_dalek::Verifier;
-
         // Read file content
         let mut file = File::open(path)?;
         let mut content = Vec::new();
         file.read_to_end(&mut content)?;
 
-        // Parse signature
-        let sig_bytes = hex::decode(signature_hex)
-            .map_err(|e| VerificationError::InvalidSignature(e.to_string()))?;
+        self.verify_data(&content, signature_hex)
+    }
 
-        if sig_bytes.len() != 64 {
-            return Err(VerificationError::InvalidSignature(format!(
-                "Signature must be 64 bytes, got {}",
-                sig_bytes.len()
-            )));
-        }
+    /// Verify a file's SHA256 hash and signature in a single read
+    ///
+    /// Each chunk feeds both the hasher and a verifier per trusted key, so
+    /// large packages are read from disk once and never held in memory. A
+    /// hash mismatch is reported in preference to a bad signature.
+    pub fn verify_file_and_hash(
+        &self,
+        path: &Path,
+        expected_hash: &str,
+        signature_hex: &str,
+        key_id: Option<&str>,
+    ) -> Result<(), VerificationError> {
+        use sha2::{Digest, Sha256};
 
-        let mut sig_array = [0u8; 64];
-        sig_array.copy_from_slice(&sig_bytes);
+        let signature = parse_signature(signature_hex)?;
+        let mut verifiers = self
+            .candidates(key_id)
+            .into_iter()
+            .filter_map(|(id, key)| Some((id, key.verify_stream(&signature).ok()?)))
+            .collect::<Vec<_>>();
+        let mut hasher = Sha256::new();
 
-        let signature = ed25519_dalek::Signature::from_bytes(&sig_array);
+        let mut file = File::open(path)?;
+        let mut buffer = [0u8; 65536];
+        loop {
+            let bytes_read = file.read(&mut buffer)?;
+            if bytes_read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..bytes_read]);
+            for (_, verifier) in &mut verifiers {
+                verifier.update(&buffer[..bytes_read]);
+            }
+        }
 
-        // Verify
-        self.public_key
-            .verify(&content, &signature)
-            .map_err(|_| VerificationError::SignatureMismatch)
+        check_hash(hex::encode(hasher.finalize()), expected_hash)?;
+        let id = verifiers
+            .into_iter()
+            .find_map(|(id, verifier)| verifier.finalize_and_verify().ok().map(|_| id))
+            .ok_or(VerificationError::SignatureMismatch)?;
+        tracing::debug!("Signature verified with key {}", id);
+        Ok(())
     }
 
     /// Verify data signature
     pub fn verify_data(&self, data: &[u8], signature_hex: &str) -> Result<(), VerificationError> {
+        self.verify_data_from(data, signature_hex, None)
+    }
+
+    /// Verify data signature, trying the key `key_id` names first
+    pub fn verify_data_from(
+        &self,
+        data: &[u8],
+        signature_hex: &str,
+        key_id: Option<&str>,
+    ) -> Result<(), VerificationError> {
         use ed25519_dalek::Verifier;
 
-        let sig_bytes = hex::decode(signature_hex)
-            .map_err(|e| VerificationError::InvalidSignature(e.to_string()))?;
+        let signature = parse_signature(signature_hex)?;
 
-        if sig_bytes.len() != 64 {
-            return Err(VerificationError::InvalidSignature(format!(
-                "Signature must be 64 bytes, got {}",
-                sig_bytes.len()
-            )));
-        }
+        let (id, _) = self
+            .candidates(key_id)
+            .into_iter()
+            .find(|(_, key)| key.verify(data, &signature).is_ok())
+            .ok_or(VerificationError::SignatureMismatch)?;
+        tracing::debug!("Signature verified with key {}", id);
+        Ok(())
+    }
+}
 
-        let mut sig_array = [0u8; 64];
-        sig_array.copy_from_slice(&sig_bytes);
+/// ID of a hex-encoded public key: the first 8 bytes of its SHA256
+///
+/// Release tooling puts this in the `key_id` of what it signs.
+pub fn key_id(public_key_hex: &str) -> Result<String, VerificationError> {
+    Ok(fingerprint(&parse_public_key(public_key_hex)?))
+}
 
-        let signature = ed25519_dalek::Signature::from_bytes(&sig_array);
+fn fingerprint(public_key: &ed25519_dalek::VerifyingKey) -> String {
+    use sha2::{Digest, Sha256};
 
-        self.public_key
-            .verify(data, &signature)
-            .map_err(|_| VerificationError::SignatureMismatch)
+    hex::encode(&Sha256::digest(public_key.as_bytes())[..8])
+}
+
+/// Parse a hex-encoded Ed25519 public key
+fn parse_public_key(hex_key: &str) -> Result<ed25519_dalek::VerifyingKey, VerificationError> {
+    let bytes =
+        hex::decode(hex_key).map_err(|e| VerificationError::InvalidPublicKey(e.to_string()))?;
+
+    if bytes.len() != 32 {
+        return Err(VerificationError::InvalidPublicKey(format!(
+            "Key must be 32 bytes, got {}",
+            bytes.len()
+        )));
     }
+
+    let mut key_bytes = [0u8; 32];
+    key_bytes.copy_from_slice(&bytes);
+
+    ed25519_dalek::VerifyingKey::from_bytes(&key_bytes)
+        .map_err(|e| VerificationError::InvalidPublicKey(e.to_string()))
+}
+
+/// Parse a hex-encoded Ed25519 signature
+fn parse_signature(signature_hex: &str) -> Result<ed25519_dalek::Signature, VerificationError> {
+    let sig_bytes = hex::decode(signature_hex)
+        .map_err(|e| VerificationError::InvalidSignature(e.to_string()))?;
+
+    if sig_bytes.len() != 64 {
+        return Err(VerificationError::InvalidSignature(format!(
+            "Signature must be 64 bytes, got {}",
+            sig_bytes.len()
+        )));
+    }
+
+    let mut sig_array = [0u8; 64];
+    sig_array.copy_from_slice(&sig_bytes);
+
+    Ok(ed25519_dalek::Signature::from_bytes(&sig_array))
+}
+
+/// Compare a computed hash with the expected one, ignoring case
+fn check_hash(actual: String, expected_hash: &str) -> Result<(), VerificationError> {
+    if actual != expected_hash.to_lowercase() {
+        return Err(VerificationError::HashMismatch {
+            expected: expected_hash.to_string(),
+            actual,
+        });
+    }
+
+    Ok(())
 }
 
 /// Verifies file hashes using SHA256
@@ -140,30 +252,12 @@ impl HashVerifier {
 
     /// Verify file matches expected hash
     pub fn verify_file(path: &Path, expected_hash: &str) -> Result<(), VerificationError> {
-        let actual = Self::sha256_file(path)?;
-
-        if actual != expected_hash.to_lowercase() {
-            return Err(VerificationError::HashMismatch {
-                expected: expected_hash.to_string(),
-                actual,
-            });
-        }
-
-        Ok(())
+        check_hash(Self::sha256_file(path)?, expected_hash)
     }
 
     /// Verify data matches expected hash
     pub fn verify_data(data: &[u8], expected_hash: &str) -> Result<(), VerificationError> {
-        let actual = Self::sha256_data(data);
-
-        if actual != expected_hash.to_lowercase() {
-            return Err(VerificationError::HashMismatch {
-                expected: expected_hash.to_string(),
-                actual,
-            });
-        }
-
-        Ok(())
+        check_hash(Self::sha256_data(data), expected_hash)
     }
 }
 
@@ -358,6 +452,104 @@ mod tests {
         assert!(verifier.verify_data(data, &signature).is_ok());
     }
 
+    #[test]
+    fn test_verify_file_and_hash() {
+        let (private, public) = generate_keypair();
+        let data = vec![0x5a; 200_000];
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rexos-1.2.0.tar.gz");
+        std::fs::write(&path, &data).unwrap();
+
+        let hash = HashVerifier::sha256_data(&data);
+        let signature = sign_data(&data, &private).unwrap();
+        let verifier = SignatureVerifier::from_hex(&public).unwrap();
+
+        assert!(
+            verifier
+                .verify_file_and_hash(&path, &hash.to_uppercase(), &signature, None)
+                .is_ok()
+        );
+        assert!(matches!(
+            verifier.verify_file_and_hash(&path, &"0".repeat(64), &signature, None),
+            Err(VerificationError::HashMismatch { .. })
+        ));
+
+        let other = sign_data(b"other package", &private).unwrap();
+        assert!(matches!(
+            verifier.verify_file_and_hash(&path, &hash, &other, None),
+            Err(VerificationError::SignatureMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_key_rotation() {
+        let (old_private, old_public) = generate_keypair();
+        let (new_private, new_public) = generate_keypair();
+        let (other_private, _) = generate_keypair();
+        let data = b"test message";
+
+        let verifier =
+            SignatureVerifier::from_hex_keys([old_public.as_str(), new_public.as_str()]).unwrap();
+        let new_id = key_id(&new_public).unwrap();
+        assert_eq!(
+            verifier.key_ids(),
+            [key_id(&old_public).unwrap(), new_id.clone()]
+        );
+
+        for private in [&old_private, &new_private] {
+            let signature = sign_data(data, private).unwrap();
+            assert!(verifier.verify_data(data, &signature).is_ok());
+            // A wrong hint still finds the right key
+            assert!(
+                verifier
+                    .verify_data_from(data, &signature, Some(&new_id))
+                    .is_ok()
+            );
+        }
+
+        let signature = sign_data(data, &other_private).unwrap();
+        assert!(matches!(
+            verifier.verify_data(data, &signature),
+            Err(VerificationError::SignatureMismatch)
+        ));
+        assert!(SignatureVerifier::from_hex_keys([]).is_err());
+        assert!(SignatureVerifier::from_hex_keys([old_public.as_str(), "00"]).is_err());
+    }
+
+    #[test]
+    fn test_verify_file_with_rotated_keys() {
+        let (old_private, old_public) = generate_keypair();
+        let (new_private, new_public) = generate_keypair();
+        let (other_private, _) = generate_keypair();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rexos-1.3.0.tar.gz");
+        let data = vec![0xa5; 100_000];
+        std::fs::write(&path, &data).unwrap();
+        let hash = HashVerifier::sha256_file(&path).unwrap();
+
+        let verifier =
+            SignatureVerifier::from_hex_keys([old_public.as_str(), new_public.as_str()]).unwrap();
+        let old_id = key_id(&old_public).unwrap();
+        let new_id = key_id(&new_public).unwrap();
+
+        for private in [&old_private, &new_private] {
+            let signature = sign_data(&data, private).unwrap();
+            for hint in [None, Some(old_id.as_str()), Some(new_id.as_str())] {
+                assert!(
+                    verifier
+                        .verify_file_and_hash(&path, &hash, &signature, hint)
+                        .is_ok()
+                );
+            }
+        }
+
+        let signature = sign_data(&data, &other_private).unwrap();
+        assert!(matches!(
+            verifier.verify_file_and_hash(&path, &hash, &signature, Some(&new_id)),
+            Err(VerificationError::SignatureMismatch)
+        ));
+    }
+
     #[test]
     fn test_invalid_signature() {
         let (_, public) = generate_keypair();
@@ -0,0 +1,85 @@
+//! Version comparison
+//!
+//! Semver precedence for release versions: numeric prerelease identifiers
+//! compare numerically (`beta.10` > `beta.2`), a release beats any of its
+//! prereleases, and build metadata is ignored.
+
+use crate::UpdateChannel;
+use std::cmp::Ordering;
+
+/// Parse a version, allowing a leading `v`
+pub fn parse(version: &str) -> Option<semver::Version> {
+    semver::Version::parse(version.trim().trim_start_matches('v')).ok()
+}
+
+/// Order two versions by semver precedence
+///
+/// Versions that don't parse fall back to string comparison.
+pub fn cmp(a: &str, b: &str) -> Ordering {
+    match (parse(a), parse(b)) {
+        (Some(a), Some(b)) => {
+            (a.major, a.minor, a.patch, &a.pre).cmp(&(b.major, b.minor, b.patch, &b.pre))
+        }
+        _ => a.cmp(b),
+    }
+}
+
+/// Compare a candidate update against the current version
+///
+/// Returns `Greater` when `candidate` should replace `current`. On the
+/// stable channel prereleases are never offered, so they compare `Less`.
+pub fn compare(current: &str, candidate: &str, channel: UpdateChannel) -> Ordering {
+    if channel == UpdateChannel::Stable && parse(candidate).is_some_and(|v| !v.pre.is_empty()) {
+        return Ordering::Less;
+    }
+    cmp(candidate, current)
+}
+
+/// Check if `candidate` is an update over `current` on a channel
+pub fn is_newer(current: &str, candidate: &str, channel: UpdateChannel) -> bool {
+    compare(current, candidate, channel) == Ordering::Greater
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prerelease_numeric_order() {
+        // String comparison gets this one backwards
+        assert!("1.2.0-beta.10" < "1.2.0-beta.2");
+        assert_eq!(cmp("1.2.0-beta.10", "1.2.0-beta.2"), Ordering::Greater);
+        assert_eq!(cmp("1.2.0-beta.2", "1.2.0-beta.10"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_release_beats_prerelease() {
+        assert_eq!(cmp("1.2.0", "1.2.0-rc.1"), Ordering::Greater);
+        assert_eq!(cmp("1.2.0", "1.2.0-nightly.20240101"), Ordering::Greater);
+        assert_eq!(cmp("1.2.0-rc.1", "1.1.9"), Ordering::Greater);
+
+        // Alphanumeric identifiers sort after numeric ones
+        assert_eq!(cmp("1.2.0-beta", "1.2.0-alpha"), Ordering::Greater);
+        assert_eq!(cmp("1.2.0-beta.x", "1.2.0-beta.9"), Ordering::Greater);
+        assert_eq!(cmp("1.2.0-beta.1", "1.2.0-beta"), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_build_metadata_ignored() {
+        assert_eq!(cmp("1.2.0+build.5", "1.2.0+build.7"), Ordering::Equal);
+        assert_eq!(cmp("v1.10.0", "1.9.0"), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_channel_policy() {
+        assert!(!is_newer("1.1.0", "1.2.0-beta.1", UpdateChannel::Stable));
+        assert!(is_newer("1.1.0", "1.2.0-beta.1", UpdateChannel::Beta));
+        assert!(is_newer(
+            "1.2.0-beta.2",
+            "1.2.0-beta.10",
+            UpdateChannel::Beta
+        ));
+        assert!(is_newer("1.2.0-beta.10", "1.2.0", UpdateChannel::Stable));
+        assert!(!is_newer("1.2.0", "1.2.0", UpdateChannel::Nightly));
+    }
+}